@@ -0,0 +1,44 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use sea_orm::entity::prelude::*;
+
+/// Change of a resource. Entries are kept when the user is deleted, so there is no
+/// foreign key to the user.
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "audit_log")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    pub created_at: DateTimeUtc,
+    /// User who made the change, None for jobs of the server
+    pub user_id: Option<u32>,
+    /// Kind of resource, e.g. `ride`
+    pub resource: String,
+    pub resource_id: u32,
+    /// `created`, `updated` or `deleted`
+    pub action: String,
+    /// JSON object of the changed fields with old and new value
+    pub diff: String,
+    pub request_id: Option<String>,
+    /// Hash of the previous entry of the chain, None for the first entry
+    pub prev_hash: Option<String>,
+    /// Hash of this entry including [prev_hash], None until the entry is chained
+    pub hash: Option<String>,
+    /// JWS over [id] and [hash] signed with a server key, set on the newest entry of
+    /// each chaining run
+    pub signature: Option<String>,
+    /// Position in the chain, None until the entry is chained. Entries are chained in the
+    /// order their transactions committed, which may differ from the order of [id].
+    pub chain_seq: Option<u32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,39 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use sea_orm::entity::prelude::*;
+
+/// Calendar event already pulled as draft ride
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "caldav_event")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    pub created_at: DateTimeUtc,
+    pub user_id: u32,
+    /// UID of the event
+    pub uid: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,57 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use sea_orm::entity::prelude::*;
+
+/// CalDAV calendar a user synchronizes rides with
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "caldav_settings")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+    #[sea_orm(unique)]
+    pub user_id: u32,
+    /// URL of the calendar collection
+    pub calendar_url: String,
+    pub username: String,
+    pub password: String,
+    /// Whether rides are pushed as events
+    pub push: bool,
+    /// Whether flagged events are pulled as draft rides
+    pub pull: bool,
+    /// Events whose summary contains the keyword are pulled
+    pub pull_keyword: String,
+    pub last_synced_at: Option<DateTimeUtc>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C: ConnectionTrait>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr> {
+        crate::timestamps::maintain(&mut self.created_at, &mut self.updated_at, insert);
+        Ok(self)
+    }
+}
@@ -0,0 +1,29 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use sea_orm::entity::prelude::*;
+
+/// Response of a geocoding provider, shared by all users
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "geocode_cache")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    pub created_at: DateTimeUtc,
+    /// `nominatim` or `photon`
+    pub provider: String,
+    /// Normalized query
+    pub query: String,
+    /// JSON array of the places found
+    pub results: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
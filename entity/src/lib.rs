@@ -11,3 +11,21 @@ pub mod ride;
 pub mod ride_tag;
 pub mod tag_descriptor;
 pub mod tag_enum_option;
+pub mod idempotency_key;
+pub mod webhook;
+pub mod webhook_delivery;
+pub mod audit_log;
+pub mod geocode_cache;
+pub mod inbox_address;
+pub mod inbox_item;
+pub mod caldav_settings;
+pub mod caldav_event;
+pub mod webdav_target;
+pub mod telegram_link;
+pub mod ride_status_change;
+pub mod ride_reference;
+pub mod ride_track;
+pub mod ride_summary;
+pub mod server_setting;
+
+pub mod timestamps;
@@ -7,8 +7,9 @@
  */
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "ride")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -23,6 +24,64 @@ pub struct Model {
     pub location_to: String,
     pub remarks: Option<String>,
     pub is_template: bool,
+    pub arrival_estimated: bool,
+    pub passengers: u32,
+    pub status: RideStatus,
+    pub vat_rate: Option<f64>,
+}
+
+/// Stage of a ride in its lifecycle
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize, schemars::JsonSchema)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RideStatus {
+    Planned,
+    #[default]
+    Completed,
+    Cancelled,
+    /// Claimed for reimbursement
+    Submitted,
+    Reimbursed,
+}
+
+impl RideStatus {
+    /// Statuses the ride can change to
+    pub fn transitions(&self) -> &'static [RideStatus] {
+        match self {
+            RideStatus::Planned => &[RideStatus::Completed, RideStatus::Cancelled],
+            RideStatus::Completed => &[RideStatus::Submitted, RideStatus::Cancelled],
+            RideStatus::Cancelled => &[RideStatus::Planned],
+            RideStatus::Submitted => &[RideStatus::Reimbursed, RideStatus::Completed],
+            RideStatus::Reimbursed => &[],
+        }
+    }
+}
+
+impl std::fmt::Display for RideStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RideStatus::Planned => write!(f, "planned"),
+            RideStatus::Completed => write!(f, "completed"),
+            RideStatus::Cancelled => write!(f, "cancelled"),
+            RideStatus::Submitted => write!(f, "submitted"),
+            RideStatus::Reimbursed => write!(f, "reimbursed"),
+        }
+    }
+}
+
+impl std::str::FromStr for RideStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "planned" => Ok(RideStatus::Planned),
+            "completed" => Ok(RideStatus::Completed),
+            "cancelled" => Ok(RideStatus::Cancelled),
+            "submitted" => Ok(RideStatus::Submitted),
+            "reimbursed" => Ok(RideStatus::Reimbursed),
+            _ => Err(format!("Invalid ride status '{}'", s)),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -35,6 +94,12 @@ pub enum Relation {
     User,
     #[sea_orm(has_many = "super::ride_tag::Entity")]
     RideTags,
+    #[sea_orm(has_many = "super::ride_status_change::Entity")]
+    RideStatusChanges,
+    #[sea_orm(has_many = "super::ride_reference::Entity")]
+    RideReferences,
+    #[sea_orm(has_one = "super::ride_track::Entity")]
+    RideTrack,
 }
 
 impl Related<super::user::Entity> for Entity {
@@ -49,4 +114,76 @@ impl Related<super::ride_tag::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+impl Related<super::ride_status_change::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RideStatusChanges.def()
+    }
+}
+
+impl Related<super::ride_reference::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RideReferences.def()
+    }
+}
+
+impl Related<super::ride_track::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RideTrack.def()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C: ConnectionTrait>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr> {
+        crate::timestamps::maintain(&mut self.created_at, &mut self.updated_at, insert);
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Iterable;
+    use super::RideStatus;
+
+    #[test]
+    fn test_transitions() {
+        let allowed = [
+            (RideStatus::Planned, RideStatus::Completed),
+            (RideStatus::Planned, RideStatus::Cancelled),
+            (RideStatus::Completed, RideStatus::Submitted),
+            (RideStatus::Completed, RideStatus::Cancelled),
+            (RideStatus::Cancelled, RideStatus::Planned),
+            (RideStatus::Submitted, RideStatus::Reimbursed),
+            (RideStatus::Submitted, RideStatus::Completed),
+        ];
+        for from in RideStatus::iter() {
+            for to in RideStatus::iter() {
+                assert_eq!(
+                    from.transitions().contains(&to),
+                    allowed.contains(&(from, to)),
+                    "{} to {}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_reimbursed_is_final() {
+        assert!(RideStatus::Reimbursed.transitions().is_empty());
+        for status in RideStatus::iter() {
+            assert!(!status.transitions().contains(&status), "{}", status);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        for status in RideStatus::iter() {
+            assert_eq!(status.to_string().parse::<RideStatus>(), Ok(status));
+        }
+        for text in ["", "Planned", "paid", " completed"] {
+            assert!(text.parse::<RideStatus>().is_err(), "{}", text);
+        }
+    }
+}
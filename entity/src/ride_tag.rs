@@ -7,8 +7,9 @@
  */
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "ride_tag")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -25,6 +26,7 @@ pub struct Model {
     pub value_date_time: Option<DateTimeUtc>,
     pub value_enum_option_id: Option<u32>,
     pub remarks: Option<String>,
+    pub per_ride: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -55,4 +57,10 @@ impl Related<super::tag_descriptor::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C: ConnectionTrait>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr> {
+        crate::timestamps::maintain(&mut self.created_at, &mut self.updated_at, insert);
+        Ok(self)
+    }
+}
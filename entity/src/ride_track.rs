@@ -0,0 +1,75 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// GPS track recorded during a ride, at most one per ride
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ride_track")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+    #[sea_orm(unique)]
+    pub ride_id: u32,
+    /// GPX document as uploaded
+    #[serde(skip)]
+    pub gpx: String,
+    pub point_count: u32,
+    /// Travelled distance in meters
+    pub distance: f64,
+    /// Time of the first point, if the track has timestamps
+    pub started_at: Option<DateTimeUtc>,
+    /// Time of the last point, if the track has timestamps
+    pub ended_at: Option<DateTimeUtc>,
+    /// Result of the malware scan of the upload
+    pub scan_status: ScanStatus,
+    /// What the scanner found, e.g. the name of the signature
+    pub scan_finding: Option<String>,
+}
+
+/// Result of the malware scan of an upload
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize, schemars::JsonSchema)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ScanStatus {
+    /// Uploaded while no scanner was configured
+    #[default]
+    Unscanned,
+    Clean,
+    /// Found to be malicious, held back until an admin releases or deletes it
+    Quarantined,
+    /// Released from quarantine by an admin
+    Released,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::ride::Entity",
+        from = "Column::RideId",
+        to = "super::ride::Column::Id"
+    )]
+    Ride,
+}
+
+impl Related<super::ride::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Ride.def()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C: ConnectionTrait>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr> {
+        crate::timestamps::maintain(&mut self.created_at, &mut self.updated_at, insert);
+        Ok(self)
+    }
+}
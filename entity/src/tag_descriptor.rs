@@ -7,8 +7,9 @@
  */
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "tag_descriptor")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -23,10 +24,14 @@ pub struct Model {
     pub uuid: Uuid,
     pub unit: Option<String>,
     pub remarks: Option<String>,
+    pub order: u32,
+    /// How values are rolled up, see [Aggregation]. `None` for the default of the tag.
+    pub aggregation: Option<Aggregation>,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, EnumIter, DeriveActiveEnum)]
+#[derive(Clone, Debug, Eq, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum TagType {
     Float,
     Integer,
@@ -35,6 +40,19 @@ pub enum TagType {
     DateTime,
 }
 
+/// How the values of a numeric tag are rolled up in statistics and reports
+#[derive(Clone, Copy, Debug, Eq, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize, schemars::JsonSchema)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    Sum,
+    Average,
+    Min,
+    Max,
+    /// Not rolled up
+    None,
+}
+
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(
@@ -67,7 +85,13 @@ impl Related<super::tag_enum_option::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C: ConnectionTrait>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr> {
+        crate::timestamps::maintain(&mut self.created_at, &mut self.updated_at, insert);
+        Ok(self)
+    }
+}
 
 impl TryFrom<String> for TagType {
     type Error = &'static str;
@@ -95,3 +119,30 @@ impl Into<String> for TagType {
         }.to_string()
     }
 }
+
+impl TryFrom<String> for Aggregation {
+    type Error = &'static str;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        match s.as_str() {
+            "sum" => Ok(Aggregation::Sum),
+            "average" => Ok(Aggregation::Average),
+            "min" => Ok(Aggregation::Min),
+            "max" => Ok(Aggregation::Max),
+            "none" => Ok(Aggregation::None),
+            _ => Err("Invalid aggregation"),
+        }
+    }
+}
+
+impl From<Aggregation> for String {
+    fn from(aggregation: Aggregation) -> Self {
+        match aggregation {
+            Aggregation::Sum => "sum",
+            Aggregation::Average => "average",
+            Aggregation::Min => "min",
+            Aggregation::Max => "max",
+            Aggregation::None => "none",
+        }.to_string()
+    }
+}
@@ -0,0 +1,51 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Timestamps of the entities. Active models set them in their [ActiveModelBehavior]
+//! with [maintain], or [created] if they are never updated. Bulk updates bypass it and
+//! use [touch] instead.
+//!
+//! [ActiveModelBehavior]: sea_orm::ActiveModelBehavior
+
+use sea_orm::{EntityTrait, UpdateMany};
+use sea_orm::ActiveValue::{self, Set};
+use sea_orm::prelude::{DateTimeUtc, Expr};
+
+/// Time stored for changes made now
+pub fn now() -> DateTimeUtc {
+    chrono::Utc::now()
+}
+
+/// Set [column] of all rows changed by [update] to now, e.g. `updated_at`
+pub fn touch<E: EntityTrait>(update: UpdateMany<E>, column: E::Column) -> UpdateMany<E> {
+    update.col_expr(column, Expr::value(now()))
+}
+
+/// Maintain the timestamps of an active model before it is saved. On insert,
+/// [created_at] and [updated_at] are set to now unless given. On update, [updated_at] is
+/// set to now unless given.
+pub(crate) fn maintain(
+    created_at: &mut ActiveValue<DateTimeUtc>,
+    updated_at: &mut ActiveValue<DateTimeUtc>,
+    insert: bool,
+) {
+    let now = now();
+    if insert && created_at.is_not_set() {
+        *created_at = Set(now);
+    }
+    if !updated_at.is_set() {
+        *updated_at = Set(now);
+    }
+}
+
+/// Set [created_at] of an active model without update time to now on insert unless given
+pub(crate) fn created(created_at: &mut ActiveValue<DateTimeUtc>, insert: bool) {
+    if insert && created_at.is_not_set() {
+        *created_at = Set(now());
+    }
+}
@@ -10,7 +10,7 @@ use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel, Serialize, Deserialize, schemars::JsonSchema)]
-#[serde(rename = "User")]
+#[serde(rename = "User", deny_unknown_fields)]
 #[sea_orm(table_name = "user")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -20,7 +20,64 @@ pub struct Model {
     pub jwt_issuer: String,
     #[serde(skip_deserializing)]
     pub jwt_subject: String,
+    #[schemars(length(max = 200))]
     pub name: Option<String>,
+    /// Rides which departed more than this number of days ago are deleted permanently,
+    /// kept forever if not set. A shorter retention of the instance takes precedence.
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+    /// Locale of CSV exports, English if not set
+    #[serde(default)]
+    pub locale: Option<Locale>,
+    /// Contact address, not verified
+    #[serde(default)]
+    #[schemars(length(max = 254))]
+    pub email: Option<String>,
+    /// URL of the profile picture, `http` or `https`
+    #[serde(default)]
+    #[schemars(length(max = 2000))]
+    pub avatar_url: Option<String>,
+    /// Color scheme of clients, following the system if not set
+    #[serde(default)]
+    pub theme: Option<Theme>,
+    /// ISO 4217 code of the currency clients show prices in, e.g. `EUR`
+    #[serde(default)]
+    #[schemars(length(max = 3))]
+    pub currency: Option<String>,
+    /// Unit clients show distances in, kilometres if not set
+    #[serde(default)]
+    pub distance_unit: Option<DistanceUnit>,
+}
+
+/// Language and number format of exports
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize, schemars::JsonSchema)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Locale {
+    /// Decimal point, comma separated, dates as RFC 3339
+    #[default]
+    En,
+    /// Decimal comma, semicolon separated, dates as `DD.MM.YYYY HH:MM`, German column
+    /// headers
+    De,
+}
+
+/// Color scheme of clients
+#[derive(Clone, Copy, Debug, Eq, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize, schemars::JsonSchema)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+/// Unit of distances shown by clients. Distances are stored in kilometres.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize, schemars::JsonSchema)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceUnit {
+    Km,
+    Mi,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -8,7 +8,7 @@
 
 use std::path::PathBuf;
 use chrono::{DateTime, Utc, TimeDelta};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use jwt_auth::keys::KeyCache;
 use jwt_auth::keys::KeyGenerator;
 use jwt_auth::jwt::TokenProducer;
@@ -27,6 +27,108 @@ struct Cli {
     action: Commands,
 }
 
+/// Options shared by token creating commands
+#[derive(Args)]
+struct TokenOptions {
+    /// Key ID
+    #[arg(short, long)]
+    key_id: Option<String>,
+    /// Issuer
+    #[arg(short, long)]
+    issuer: Option<String>,
+    /// Audience
+    #[arg(short, long)]
+    audience: Option<String>,
+    /// Not before date time string
+    #[arg(short, long)]
+    not_before: Option<DateTime<Utc>>,
+    /// Expiration date time string
+    #[arg(short, long)]
+    expiration: Option<DateTime<Utc>>,
+    /// Create a refresh token
+    #[arg(long)]
+    refresh: bool,
+}
+
+impl TokenOptions {
+    /// Create token producer configured with the options
+    fn producer<'cache, 'kid>(&'kid self, key_cache: &'cache mut KeyCache) -> TokenProducer<'cache, 'kid> {
+        let mut token_producer = TokenProducer::new(key_cache);
+        if let Some(key_id) = &self.key_id {
+            token_producer = token_producer.with_key_id(key_id.as_str());
+        }
+        if let Some(issuer) = &self.issuer {
+            token_producer = token_producer.with_issuer(issuer);
+        }
+        if let Some(audience) = &self.audience {
+            token_producer = token_producer.with_audience(audience);
+        }
+        if let Some(not_before) = self.not_before {
+            token_producer = token_producer.with_not_before(not_before);
+        }
+        if let Some(expiration) = self.expiration {
+            token_producer = token_producer.with_expiration(expiration);
+        }
+        if self.refresh {
+            token_producer = token_producer.as_refresh_token();
+        }
+        token_producer
+    }
+}
+
+/// Input format of batch token creation
+#[derive(Clone, ValueEnum)]
+enum InputFormat {
+    Csv,
+    Json,
+}
+
+/// Read subjects and their claims from stdin
+fn read_subjects(format: &InputFormat) -> Vec<(String, serde_json::Value)> {
+    let mut subjects = Vec::new();
+    match format {
+        InputFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(std::io::stdin());
+            let headers = reader.headers().unwrap().clone();
+            let subject_column = match headers.iter().position(|header| header == "subject") {
+                Some(column) => column,
+                None => panic!("CSV input has no subject column"),
+            };
+            for record in reader.records() {
+                let record = record.unwrap();
+                let mut claims = serde_json::Map::new();
+                for (column, (header, value)) in headers.iter().zip(record.iter()).enumerate() {
+                    if column != subject_column && !value.is_empty() {
+                        let value = serde_json::from_str(value)
+                            .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+                        claims.insert(header.to_string(), value);
+                    }
+                }
+                subjects.push((record[subject_column].to_string(), serde_json::Value::Object(claims)));
+            }
+        },
+        InputFormat::Json => {
+            let input: serde_json::Value = serde_json::from_reader(std::io::stdin()).unwrap();
+            let items = match input.as_array() {
+                Some(items) => items,
+                None => panic!("JSON input must be an array"),
+            };
+            for item in items {
+                let subject = match item["subject"].as_str() {
+                    Some(subject) => subject,
+                    None => panic!("JSON input item has no subject"),
+                };
+                let claims = match item.get("claims") {
+                    Some(claims) => claims.clone(),
+                    None => serde_json::Value::Object(serde_json::Map::new()),
+                };
+                subjects.push((subject.to_string(), claims));
+            }
+        },
+    }
+    subjects
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Create a new key
@@ -36,29 +138,60 @@ enum Commands {
         key_id: Option<String>,
     },
     /// List keys
-    ListKeys,
+    ListKeys {
+        /// Also list disabled keys
+        #[arg(short, long)]
+        all: bool,
+    },
     /// Show public key
     ShowPublic {
         /// Key ID
         key_id: String,
     },
-    /// Create a new token
-    CreateToken {
+    /// Make a key the default key for signing
+    MakeDefault {
         /// Key ID
+        key_id: String,
+    },
+    /// Create a new key and make it the default key for signing
+    RotateKey {
+        /// Key ID of the new key
         #[arg(short, long)]
         key_id: Option<String>,
-        /// Issuer
-        #[arg(short, long)]
-        issuer: Option<String>,
-        /// Audience
-        #[arg(short, long)]
-        audience: Option<String>,
-        /// Not before date time string
-        #[arg(short, long)]
-        not_before: Option<DateTime<Utc>>,
-        /// Expiration date time string
-        #[arg(short, long)]
-        expiration: Option<DateTime<Utc>>,
+        /// Disable the previous default key, so its tokens are not accepted anymore
+        #[arg(long)]
+        disable_previous: bool,
+    },
+    /// Disable a key, it can be enabled again
+    DisableKey {
+        /// Key ID
+        key_id: String,
+    },
+    /// Enable a disabled key
+    EnableKey {
+        /// Key ID
+        key_id: String,
+    },
+    /// Delete a key, its files are moved aside
+    DeleteKey {
+        /// Key ID
+        key_id: String,
+    },
+    /// Export all public keys as JWK Set
+    ExportJwks,
+    /// Enable or disable encryption (JWE) of tokens signed with a key
+    SetEncryption {
+        /// Key ID
+        key_id: String,
+        /// Disable encryption
+        #[arg(long)]
+        disable: bool,
+    },
+    /// Create a new token
+    CreateToken {
+        /// Token options
+        #[command(flatten)]
+        options: TokenOptions,
         /// Addition claims, in the form: key=value
         #[arg(short, long)]
         claim: Vec<String>,
@@ -68,6 +201,19 @@ enum Commands {
         /// Subject
         subject: String,
     },
+    /// Create one token per subject read from stdin, printing one token per line.
+    ///
+    /// CSV input needs a header with a `subject` column. All other columns are claims,
+    /// their values are parsed as JSON if possible and used as strings otherwise.
+    /// JSON input is an array of objects with `subject` and an optional `claims` object.
+    CreateTokens {
+        /// Token options, applied to all tokens
+        #[command(flatten)]
+        options: TokenOptions,
+        /// Input format
+        #[arg(short, long, value_enum, default_value = "csv")]
+        format: InputFormat,
+    },
     /// Verify token
     VerifyToken {
         /// Key ID
@@ -82,6 +228,12 @@ enum Commands {
         /// Maximum expiration from issuing time in seconds
         #[arg(short = 'e', long)]
         max_expiration: Option<i64>,
+        /// Required claims, in the form: key=JSON value
+        #[arg(short = 'r', long)]
+        require_claim: Vec<String>,
+        /// Print key ID, header and claims as JSON
+        #[arg(long)]
+        json: bool,
         /// Token
         token: String,
     }
@@ -105,41 +257,67 @@ fn main() {
             println!("Key ID: {}", key_id);
             println!("Public Key:\n{}", String::from_utf8(key.public_key_to_pem().unwrap()).unwrap());
         },
-        Commands::ListKeys => {
+        Commands::ListKeys { all } => {
+            let default_key_id = key_cache.default_key_id().map(String::from);
             for key_id in key_cache.key_id_list().unwrap() {
-                println!("{}", key_id);
+                if default_key_id.as_ref() == Some(&key_id) {
+                    println!("{} (default)", key_id);
+                } else {
+                    println!("{}", key_id);
+                }
             }
+            if all {
+                for key_id in key_cache.disabled_key_id_list().unwrap() {
+                    println!("{} (disabled)", key_id);
+                }
+            }
+        },
+        Commands::MakeDefault { key_id } => {
+            key_cache.make_default(key_id.as_str()).unwrap();
+        },
+        Commands::RotateKey { key_id, disable_previous } => {
+            let previous_key_id = key_cache.default_key_id().map(String::from);
+            let (_, key_id) = key_cache.create_private_key(
+                key_id.as_deref(),
+                Some(KeyGenerator::new_rsa(2048)),
+            ).unwrap();
+            key_cache.make_default(key_id.as_str()).unwrap();
+            println!("New default key ID: {}", key_id);
+
+            if let Some(previous_key_id) = previous_key_id {
+                if disable_previous && previous_key_id != key_id {
+                    key_cache.disable_key(previous_key_id.as_str()).unwrap();
+                    println!("Disabled key ID: {}", previous_key_id);
+                }
+            }
+        },
+        Commands::DisableKey { key_id } => {
+            key_cache.disable_key(key_id.as_str()).unwrap();
+        },
+        Commands::EnableKey { key_id } => {
+            key_cache.enable_key(key_id.as_str()).unwrap();
+        },
+        Commands::DeleteKey { key_id } => {
+            key_cache.delete_key(key_id.as_str()).unwrap();
+        },
+        Commands::ExportJwks => {
+            let jwks = key_cache.export_jwks().unwrap();
+            println!("{}", serde_json::to_string_pretty(&jwks).unwrap());
         },
         Commands::ShowPublic { key_id } => {
             let (key, _) = key_cache.get_public_key(Some(key_id.as_str())).unwrap();
             println!("{}", String::from_utf8(key.public_key_to_pem().unwrap()).unwrap());
         },
+        Commands::SetEncryption { key_id, disable } => {
+            key_cache.set_encryption(key_id.as_str(), !disable).unwrap();
+        },
         Commands::CreateToken {
-            key_id,
-            issuer,
-            audience,
-            not_before,
-            expiration,
+            options,
             claim,
             claims_json,
             subject,
         } => {
-            let mut token_producer = TokenProducer::new(&mut key_cache);
-            if let Some(key_id) = &key_id {
-                token_producer = token_producer.with_key_id(key_id.as_str());
-            }
-            if let Some(issuer) = issuer {
-                token_producer = token_producer.with_issuer(issuer);
-            }
-            if let Some(audience) = audience {
-                token_producer = token_producer.with_audience(audience);
-            }
-            if let Some(not_before) = not_before {
-                token_producer = token_producer.with_not_before(not_before);
-            }
-            if let Some(expiration) = expiration {
-                token_producer = token_producer.with_expiration(expiration);
-            }
+            let mut token_producer = options.producer(&mut key_cache);
             for item in &claim {
                 let mut iter = item.split('=');
                 let key = match iter.next() {
@@ -160,14 +338,26 @@ fn main() {
                 let value: serde_json::Value = serde_json::from_str(&claims).unwrap();
                 token_producer = token_producer.add_claims_from_json(value).unwrap();
             }
-            let token = token_producer.produce(&subject).unwrap();
-            println!("{}", String::from(token))
+            let token = token_producer.produce_compact(&subject).unwrap();
+            println!("{}", token)
+        },
+        Commands::CreateTokens { options, format } => {
+            for (subject, claims) in read_subjects(&format) {
+                let token = options
+                    .producer(&mut key_cache)
+                    .add_claims_from_json(claims)
+                    .and_then(|producer| producer.produce_compact(&subject))
+                    .unwrap();
+                println!("{}", token);
+            }
         },
         Commands::VerifyToken {
             expect_key_id,
             expect_issuer,
             expect_audience,
             max_expiration,
+            require_claim,
+            json,
             token,
         } => {
             let mut verifier = TokenVerifier::new(&mut key_cache);
@@ -183,13 +373,32 @@ fn main() {
             if let Some(max_expiration) = max_expiration {
                 verifier = verifier.with_max_expiration(TimeDelta::seconds(max_expiration));
             }
-            let (token, key_id) = verifier.verify(token).unwrap();
-            println!("Token was signed with key: {}", key_id);
-            if let Some(subject) = &token.claims().registered.subject {
-                println!("Token subject is: {}", subject);
+            for item in &require_claim {
+                let (key, value) = match item.split_once('=') {
+                    Some(pair) => pair,
+                    None => panic!("Cannot parse required claim, missing ="),
+                };
+                let value: serde_json::Value = serde_json::from_str(value).unwrap();
+                verifier = verifier.require_claim(key, value);
             }
-            if let Some(token_id) = &token.claims().registered.json_web_token_id {
-                println!("Token Web Token ID is: {}", token_id);
+            let (token, key_id) = verifier.verify(token).unwrap();
+            let claims = serde_json::to_value(token.claims()).unwrap();
+            if json {
+                let output = serde_json::json!({
+                    "key_id": key_id,
+                    "header": serde_json::to_value(token.header()).unwrap(),
+                    "claims": claims,
+                });
+                println!("{}", output);
+            } else {
+                println!("Token was signed with key: {}", key_id);
+                if let Some(subject) = &token.claims().registered.subject {
+                    println!("Token subject is: {}", subject);
+                }
+                if let Some(token_id) = &token.claims().registered.json_web_token_id {
+                    println!("Token Web Token ID is: {}", token_id);
+                }
+                println!("Token claims:\n{}", serde_json::to_string_pretty(&claims).unwrap());
             }
         }
     }
@@ -0,0 +1,230 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::error::Error;
+use serde_json::Value;
+
+/// Claim distinguishing access tokens from refresh tokens
+pub const TOKEN_USE_CLAIM: &str = "token_use";
+/// Value of [TOKEN_USE_CLAIM] for refresh tokens
+pub const TOKEN_USE_REFRESH: &str = "refresh";
+
+/// JSON type of a claim
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClaimType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl ClaimType {
+    /// Check if [value] is of this type
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Number => value.is_number(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Boolean => value.is_boolean(),
+            Self::Array => value.is_array(),
+            Self::Object => value.is_object(),
+        }
+    }
+}
+
+/// Get nested claim by its path segments, e.g. `["realm_access", "roles"]`
+pub fn get_claim_path<'a, S: AsRef<str>>(claims: &'a Value, path: &[S]) -> Option<&'a Value> {
+    let mut value = claims;
+    for segment in path {
+        value = value.as_object()?.get(segment.as_ref())?;
+    }
+    Some(value)
+}
+
+/// Get claim by [name]. If there is no top-level claim [name], it is interpreted as
+/// dot-separated path to a nested claim, e.g. `realm_access.roles`. Top-level lookup
+/// comes first, because namespaced claims are often URLs containing dots.
+pub fn get_claim<'a>(claims: &'a Value, name: &str) -> Option<&'a Value> {
+    match claims.get(name) {
+        Some(value) => Some(value),
+        None => {
+            let path: Vec<&str> = name.split('.').collect();
+            if path.len() > 1 {
+                get_claim_path(claims, &path)
+            } else {
+                None
+            }
+        },
+    }
+}
+
+/// Check if claim [name] contains [item]. Arrays must contain a string equal to [item];
+/// strings are treated as space-separated lists, as the OAuth `scope` claim.
+pub fn claim_contains(claims: &Value, name: &str, item: &str) -> bool {
+    match get_claim(claims, name) {
+        Some(Value::Array(values)) => values.iter().any(|value| value.as_str() == Some(item)),
+        Some(Value::String(value)) => value.split_whitespace().any(|value| value == item),
+        _ => false,
+    }
+}
+
+/// Policy of custom claims a token must carry
+#[derive(Clone, Debug, Default)]
+pub struct ClaimPolicy {
+    /// Claims which must be present with exactly this value
+    required_values: Vec<(String, Value)>,
+    /// Claims which must be present with this type
+    required_types: Vec<(String, ClaimType)>,
+    /// Claims which must contain this item
+    required_items: Vec<(String, String)>,
+}
+
+impl ClaimPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require [claim] to be present and equal to [value]
+    pub fn require_claim<S: ToString>(mut self, claim: S, value: Value) -> Self {
+        self.required_values.push((claim.to_string(), value));
+        self
+    }
+
+    /// Require all claims of [schema] to be present with the given type
+    pub fn require_claims_schema<S: ToString, I: IntoIterator<Item = (S, ClaimType)>>(mut self, schema: I) -> Self {
+        for (claim, claim_type) in schema {
+            self.required_types.push((claim.to_string(), claim_type));
+        }
+        self
+    }
+
+    /// Require [claim] to contain [item], e.g. a role in `realm_access.roles`
+    pub fn require_claim_contains<S: ToString, T: ToString>(mut self, claim: S, item: T) -> Self {
+        self.required_items.push((claim.to_string(), item.to_string()));
+        self
+    }
+
+    /// Check if the policy is empty
+    pub fn is_empty(&self) -> bool {
+        self.required_values.is_empty() && self.required_types.is_empty() && self.required_items.is_empty()
+    }
+
+    /// Check [claims] against policy
+    pub fn check(&self, claims: &Value) -> Result<(), Box<dyn Error>> {
+        for (claim, expected_value) in &self.required_values {
+            match get_claim(claims, claim) {
+                Some(value) => {
+                    if value != expected_value {
+                        Err(format!("Claim {} does not have the required value", claim))?;
+                    }
+                },
+                None => Err(format!("Claim {} not set in token", claim))?,
+            }
+        }
+        for (claim, claim_type) in &self.required_types {
+            match get_claim(claims, claim) {
+                Some(value) => {
+                    if !claim_type.matches(value) {
+                        Err(format!("Claim {} is not of type {:?}", claim, claim_type))?;
+                    }
+                },
+                None => Err(format!("Claim {} not set in token", claim))?,
+            }
+        }
+        for (claim, item) in &self.required_items {
+            if !claim_contains(claims, claim, item) {
+                Err(format!("Claim {} does not contain {}", claim, item))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::jwt::claims::{claim_contains, get_claim, ClaimPolicy, ClaimType};
+
+    #[test]
+    fn test_claim_policy() {
+        let claims = json!({
+            "sub": "subject@example.tld",
+            "ptet:write": true,
+            "groups": ["a", "b"],
+            "level": 3,
+        });
+
+        assert!(ClaimPolicy::new().check(&claims).is_ok());
+        assert!(
+            ClaimPolicy::new()
+                .require_claim("ptet:write", json!(true))
+                .check(&claims)
+                .is_ok()
+        );
+        assert!(
+            ClaimPolicy::new()
+                .require_claim("ptet:write", json!(false))
+                .check(&claims)
+                .is_err()
+        );
+        assert!(
+            ClaimPolicy::new()
+                .require_claim("ptet:admin", json!(true))
+                .check(&claims)
+                .is_err()
+        );
+        assert!(
+            ClaimPolicy::new()
+                .require_claims_schema([("groups", ClaimType::Array), ("level", ClaimType::Integer)])
+                .check(&claims)
+                .is_ok()
+        );
+        assert!(
+            ClaimPolicy::new()
+                .require_claims_schema([("level", ClaimType::String)])
+                .check(&claims)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_nested_claims() {
+        let claims = json!({
+            "https://example.tld/claims.v1": "namespaced",
+            "realm_access": {
+                "roles": ["ptet:write", "offline_access"],
+            },
+            "scope": "openid profile",
+        });
+
+        assert_eq!(get_claim(&claims, "https://example.tld/claims.v1"), Some(&json!("namespaced")));
+        assert_eq!(get_claim(&claims, "realm_access.roles").unwrap().as_array().unwrap().len(), 2);
+        assert_eq!(get_claim(&claims, "realm_access.groups"), None);
+        assert_eq!(get_claim(&claims, "scope.openid"), None);
+
+        assert!(claim_contains(&claims, "realm_access.roles", "ptet:write"));
+        assert!(!claim_contains(&claims, "realm_access.roles", "ptet:admin"));
+        assert!(claim_contains(&claims, "scope", "profile"));
+
+        assert!(
+            ClaimPolicy::new()
+                .require_claim_contains("realm_access.roles", "offline_access")
+                .require_claims_schema([("realm_access.roles", ClaimType::Array)])
+                .check(&claims)
+                .is_ok()
+        );
+        assert!(
+            ClaimPolicy::new()
+                .require_claim_contains("realm_access.roles", "ptet:admin")
+                .check(&claims)
+                .is_err()
+        );
+    }
+}
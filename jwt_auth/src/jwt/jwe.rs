@@ -0,0 +1,155 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! JSON Web Encryption (RFC 7516) in compact serialization. Only the key management
+//! algorithm RSA-OAEP-256 with content encryption A256GCM is supported. Signed tokens
+//! are nested into the encrypted token (`cty` is `JWT`).
+
+use std::error::Error;
+use openssl::encrypt::{Decrypter, Encrypter};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private, Public};
+use openssl::rand::rand_bytes;
+use openssl::rsa::Padding;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use serde_json::json;
+
+const ALGORITHM: &str = "RSA-OAEP-256";
+const ENCRYPTION: &str = "A256GCM";
+const CONTENT_KEY_LEN: usize = 32;
+const IV_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+fn encode_b64url(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode_b64url(value: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(base64::decode_config(value, base64::URL_SAFE_NO_PAD)?)
+}
+
+/// Check if [token] is in JWE compact serialization (five segments)
+pub fn is_encrypted(token: &str) -> bool {
+    token.split('.').count() == 5
+}
+
+/// Get key ID from the protected header of encrypted [token]
+pub fn key_id(token: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let header_b64 = match token.split('.').next() {
+        Some(header_b64) => header_b64,
+        None => Err("Token has no header")?,
+    };
+    let header: serde_json::Value = serde_json::from_slice(&decode_b64url(header_b64)?)?;
+    Ok(header["kid"].as_str().map(String::from))
+}
+
+/// Encrypt [payload] (usually a signed token) for [key] with ID [key_id]
+pub fn encrypt(payload: &str, key: &PKey<Public>, key_id: &str) -> Result<String, Box<dyn Error>> {
+    let header = json!({
+        "alg": ALGORITHM,
+        "enc": ENCRYPTION,
+        "kid": key_id,
+        "cty": "JWT",
+    });
+    let header_b64 = encode_b64url(serde_json::to_string(&header)?.as_bytes());
+
+    // Random content encryption key, wrapped with the recipient's public key
+    let mut content_key = [0u8; CONTENT_KEY_LEN];
+    rand_bytes(&mut content_key)?;
+    let mut encrypter = Encrypter::new(key)?;
+    encrypter.set_rsa_padding(Padding::PKCS1_OAEP)?;
+    encrypter.set_rsa_oaep_md(MessageDigest::sha256())?;
+    encrypter.set_rsa_mgf1_md(MessageDigest::sha256())?;
+    let mut encrypted_key = vec![0u8; encrypter.encrypt_len(&content_key)?];
+    let encrypted_key_len = encrypter.encrypt(&content_key, &mut encrypted_key)?;
+    encrypted_key.truncate(encrypted_key_len);
+
+    // Additional authenticated data is the encoded protected header
+    let mut iv = [0u8; IV_LEN];
+    rand_bytes(&mut iv)?;
+    let mut tag = [0u8; TAG_LEN];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &content_key,
+        Some(&iv),
+        header_b64.as_bytes(),
+        payload.as_bytes(),
+        &mut tag,
+    )?;
+
+    Ok(
+        [
+            header_b64,
+            encode_b64url(&encrypted_key),
+            encode_b64url(&iv),
+            encode_b64url(&ciphertext),
+            encode_b64url(&tag),
+        ].join(".")
+    )
+}
+
+/// Decrypt [token] with private [key] and return the payload
+pub fn decrypt(token: &str, key: &PKey<Private>) -> Result<String, Box<dyn Error>> {
+    let segments: Vec<&str> = token.split('.').collect();
+    if segments.len() != 5 {
+        Err("Token is not in JWE compact serialization")?;
+    }
+
+    let header: serde_json::Value = serde_json::from_slice(&decode_b64url(segments[0])?)?;
+    if header["alg"].as_str() != Some(ALGORITHM) {
+        Err("Unsupported JWE key management algorithm")?;
+    }
+    if header["enc"].as_str() != Some(ENCRYPTION) {
+        Err("Unsupported JWE content encryption algorithm")?;
+    }
+
+    let encrypted_key = decode_b64url(segments[1])?;
+    let mut decrypter = Decrypter::new(key)?;
+    decrypter.set_rsa_padding(Padding::PKCS1_OAEP)?;
+    decrypter.set_rsa_oaep_md(MessageDigest::sha256())?;
+    decrypter.set_rsa_mgf1_md(MessageDigest::sha256())?;
+    let mut content_key = vec![0u8; decrypter.decrypt_len(&encrypted_key)?];
+    let content_key_len = decrypter.decrypt(&encrypted_key, &mut content_key)?;
+    content_key.truncate(content_key_len);
+    if content_key.len() != CONTENT_KEY_LEN {
+        Err("Invalid JWE content encryption key")?;
+    }
+
+    let payload = decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &content_key,
+        Some(&decode_b64url(segments[2])?),
+        segments[0].as_bytes(),
+        &decode_b64url(segments[3])?,
+        &decode_b64url(segments[4])?,
+    )?;
+    Ok(String::from_utf8(payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use openssl::pkey::PKey;
+    use crate::jwt::jwe::{decrypt, encrypt, is_encrypted, key_id};
+    use crate::keys::key_generator::KeyGenerator;
+
+    #[test]
+    fn test_jwe_roundtrip() {
+        let private_key = KeyGenerator::new_rsa(2048).generate().unwrap();
+        let public_key = PKey::public_key_from_pem(&private_key.public_key_to_pem().unwrap()).unwrap();
+
+        let token = encrypt("header.claims.signature", &public_key, "test1").unwrap();
+        assert!(is_encrypted(&token));
+        assert_eq!(key_id(&token).unwrap(), Some("test1".to_string()));
+        assert_eq!(decrypt(&token, &private_key).unwrap(), "header.claims.signature");
+
+        // Tampering with the protected header must be detected
+        let mut segments: Vec<String> = token.split('.').map(String::from).collect();
+        segments[0] = segments[0].replacen('e', "f", 1);
+        assert!(decrypt(&segments.join("."), &private_key).is_err());
+    }
+}
@@ -6,18 +6,25 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+pub mod claims;
+pub mod jwe;
 pub mod token_producer;
 mod token_verifier;
 
+pub use claims::{ClaimPolicy, ClaimType, get_claim, claim_contains, TOKEN_USE_CLAIM, TOKEN_USE_REFRESH};
 pub use token_producer::TokenProducer;
 pub use token_verifier::TokenVerifier;
 
 
 #[cfg(test)]
 mod tests {
+    use openssl::hash::MessageDigest;
     use openssl::nid::Nid;
+    use chrono::{TimeDelta, Utc};
+    use serde_json::json;
     use tempfile::TempDir;
-    use crate::jwt::{TokenProducer, TokenVerifier};
+    use jwt::{Claims, Header, PKeyWithDigest, RegisteredClaims, SignWithKey, SigningAlgorithm, Token};
+    use crate::jwt::{ClaimType, TokenProducer, TokenVerifier, TOKEN_USE_CLAIM, TOKEN_USE_REFRESH};
     use crate::keys::key_generator::KeyGenerator;
     use crate::keys::KeyCache;
 
@@ -55,5 +62,255 @@ mod tests {
         assert_eq!(token_decoded.claims().registered.audience, Some("resource.example.tld".to_string()));
         assert_eq!(token_decoded.claims().registered.json_web_token_id, Some("qwertyuiop".to_string()));
     }
-}
 
+    #[test]
+    fn test_token_required_claims() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut key_cache = KeyCache::from_path(tmp_dir.path()).unwrap();
+        key_cache.create_private_key(
+            Some("test1"),
+            Some(KeyGenerator::new_rsa(2048)),
+        ).unwrap();
+
+        let token_str = String::from(
+            TokenProducer::new(&mut key_cache)
+                .add_claims_from_json(json!({"ptet:write": true, "email": "subject@example.tld"}))
+                .unwrap()
+                .produce("subject@example.tld")
+                .unwrap()
+        );
+
+        assert!(
+            TokenVerifier::new(&mut key_cache)
+                .disable_time_check()
+                .require_claim("ptet:write", json!(true))
+                .require_claims_schema([("email", ClaimType::String)])
+                .verify(&token_str)
+                .is_ok()
+        );
+        assert!(
+            TokenVerifier::new(&mut key_cache)
+                .disable_time_check()
+                .require_claim("ptet:write", json!(false))
+                .verify(&token_str)
+                .is_err()
+        );
+        assert!(
+            TokenVerifier::new(&mut key_cache)
+                .disable_time_check()
+                .require_claims_schema([("ptet:admin", ClaimType::Boolean)])
+                .verify(&token_str)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_refresh_token() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut key_cache = KeyCache::from_path(tmp_dir.path()).unwrap();
+        key_cache.create_private_key(
+            Some("test1"),
+            Some(KeyGenerator::new_rsa(2048)),
+        ).unwrap();
+
+        let token_str = String::from(
+            TokenProducer::new(&mut key_cache)
+                .as_refresh_token()
+                .produce("subject@example.tld")
+                .unwrap()
+        );
+        let (token_decoded, _) = TokenVerifier::new(&mut key_cache)
+            .require_claim(TOKEN_USE_CLAIM, json!(TOKEN_USE_REFRESH))
+            .verify(&token_str)
+            .unwrap();
+
+        // Refresh tokens receive a long default lifetime
+        let expiration = token_decoded.claims().registered.expiration.unwrap();
+        assert!(expiration > (Utc::now() + TimeDelta::days(29)).timestamp() as u64);
+    }
+
+    #[test]
+    fn test_encrypted_token() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut key_cache = KeyCache::from_path(tmp_dir.path()).unwrap();
+        key_cache.create_private_key(
+            Some("test1"),
+            Some(KeyGenerator::new_rsa(2048)),
+        ).unwrap();
+        key_cache.set_encryption("test1", true).unwrap();
+
+        let token_str = TokenProducer::new(&mut key_cache)
+            .add_claim_string("email", "subject@example.tld")
+            .produce_compact("subject@example.tld")
+            .unwrap();
+        assert!(crate::jwt::jwe::is_encrypted(&token_str));
+        assert!(!token_str.contains(".eyJ"));
+
+        let (token_decoded, key_id) = TokenVerifier::new(&mut key_cache)
+            .disable_time_check()
+            .require_claim("email", json!("subject@example.tld"))
+            .verify(&token_str)
+            .unwrap();
+        assert_eq!(key_id, "test1");
+        assert_eq!(token_decoded.claims().registered.subject, Some("subject@example.tld".to_string()));
+    }
+
+    #[test]
+    fn test_disabled_key() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut key_cache = KeyCache::from_path(tmp_dir.path()).unwrap();
+        key_cache.create_private_key(
+            Some("test1"),
+            Some(KeyGenerator::new_rsa(2048)),
+        ).unwrap();
+
+        let token_str = String::from(
+            TokenProducer::new(&mut key_cache)
+                .produce("subject@example.tld")
+                .unwrap()
+        );
+        assert!(TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).is_ok());
+
+        // Cached keys must not be used anymore once the key is disabled
+        key_cache.disable_key("test1").unwrap();
+        assert!(TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).is_err());
+        assert!(TokenProducer::new(&mut key_cache).produce("subject@example.tld").is_err());
+
+        key_cache.enable_key("test1").unwrap();
+        assert!(TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).is_ok());
+
+        key_cache.delete_key("test1").unwrap();
+        assert!(TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).is_err());
+    }
+
+    #[test]
+    fn test_key_disabled_elsewhere() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut key_cache = KeyCache::from_path(tmp_dir.path()).unwrap();
+        key_cache.create_private_key(
+            Some("test1"),
+            Some(KeyGenerator::new_rsa(2048)),
+        ).unwrap();
+        let token_str = String::from(
+            TokenProducer::new(&mut key_cache)
+                .produce("subject@example.tld")
+                .unwrap()
+        );
+        assert!(TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).is_ok());
+
+        // A key disabled through another cache, e.g. by the token tool, is not used anymore
+        let mut other_cache = KeyCache::from_path(tmp_dir.path()).unwrap();
+        other_cache.disable_key("test1").unwrap();
+        assert!(TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).is_err());
+        assert!(TokenProducer::new(&mut key_cache).with_key_id("test1").produce("subject@example.tld").is_err());
+
+        other_cache.enable_key("test1").unwrap();
+        assert!(TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).is_ok());
+
+        // The default key made by the other cache is used once the old one is gone
+        other_cache.create_private_key(Some("test2"), None).unwrap();
+        other_cache.make_default("test2").unwrap();
+        other_cache.disable_key("test1").unwrap();
+        let token_str = String::from(
+            TokenProducer::new(&mut key_cache)
+                .produce("subject@example.tld")
+                .unwrap()
+        );
+        let (_, key_id) = TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).unwrap();
+        assert_eq!(key_id, "test2");
+    }
+
+    #[test]
+    fn test_legacy_ec_token() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut key_cache = KeyCache::from_path(tmp_dir.path()).unwrap();
+        for (key_id, nid) in [("test1", Nid::X9_62_PRIME256V1), ("test2", Nid::SECP384R1)] {
+            let (private_key, _) = key_cache.create_private_key(
+                Some(key_id),
+                Some(KeyGenerator::new_ec_from_nid(nid).unwrap()),
+            ).unwrap();
+
+            // EC keys signed with SHA-512 regardless of their curve before
+            let alg = PKeyWithDigest {
+                key: private_key.clone(),
+                digest: MessageDigest::sha512(),
+            };
+            let header = Header {
+                algorithm: alg.algorithm_type(),
+                key_id: Some(key_id.to_string()),
+                ..Default::default()
+            };
+            let token_str = String::from(
+                Token::new(header, Claims::new(RegisteredClaims::default()))
+                    .sign_with_key(&alg)
+                    .unwrap()
+            );
+            let (_, verified_key_id) = TokenVerifier::new(&mut key_cache)
+                .disable_time_check()
+                .verify(&token_str)
+                .unwrap();
+            assert_eq!(verified_key_id, key_id);
+
+            // Other digests than the legacy one and the one of the curve are rejected
+            let alg = PKeyWithDigest {
+                key: key_cache.get_private_key(Some(key_id)).unwrap().0.clone(),
+                digest: if nid == Nid::SECP384R1 { MessageDigest::sha256() } else { MessageDigest::sha384() },
+            };
+            let header = Header {
+                algorithm: alg.algorithm_type(),
+                key_id: Some(key_id.to_string()),
+                ..Default::default()
+            };
+            let token_str = String::from(
+                Token::new(header, Claims::new(RegisteredClaims::default()))
+                    .sign_with_key(&alg)
+                    .unwrap()
+            );
+            assert!(TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).is_err());
+
+            // New tokens are signed with the digest of the curve
+            let token_str = String::from(
+                TokenProducer::new(&mut key_cache)
+                    .with_key_id(key_id)
+                    .produce("subject@example.tld")
+                    .unwrap()
+            );
+            assert!(TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_key_id_traversal() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut key_cache = KeyCache::from_path(tmp_dir.path()).unwrap();
+        key_cache.create_private_key(
+            Some("test1"),
+            Some(KeyGenerator::new_rsa(2048)),
+        ).unwrap();
+        let (compromised, _) = key_cache.create_private_key(
+            Some("test2"),
+            Some(KeyGenerator::new_rsa(2048)),
+        ).unwrap();
+        let compromised = compromised.clone();
+        key_cache.disable_key("test2").unwrap();
+
+        // The key ID must not lead from an active key to the disabled one
+        let alg = PKeyWithDigest {
+            key: compromised,
+            digest: MessageDigest::sha512(),
+        };
+        for key_id in ["test1/../disabled_test2", "../disabled_test2"] {
+            let header = Header {
+                algorithm: alg.algorithm_type(),
+                key_id: Some(key_id.to_string()),
+                ..Default::default()
+            };
+            let token_str = String::from(
+                Token::new(header, Claims::new(RegisteredClaims::default()))
+                    .sign_with_key(&alg)
+                    .unwrap()
+            );
+            assert!(TokenVerifier::new(&mut key_cache).disable_time_check().verify(&token_str).is_err());
+        }
+    }
+}
@@ -8,12 +8,17 @@
 
 use std::collections::BTreeMap;
 use std::error::Error;
-use openssl::hash::MessageDigest;
 use jwt::{Token, Header, algorithm::openssl::PKeyWithDigest, SigningAlgorithm, Claims, RegisteredClaims, SignWithKey, token::Signed};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use rand::distr::Alphanumeric;
 use rand::Rng;
 use crate::keys::KeyCache;
+use crate::keys::jwk::signature_algorithm;
+use super::claims::{TOKEN_USE_CLAIM, TOKEN_USE_REFRESH};
+use super::jwe;
+
+/// Signed token together with the key cache it was signed from
+type SignedToken<'cache> = (Token<Header, Claims, Signed>, &'cache mut KeyCache);
 
 /// Producer for JWT
 pub struct TokenProducer<'cache, 'kid> {
@@ -25,12 +30,14 @@ pub struct TokenProducer<'cache, 'kid> {
     audience: Option<String>,
     token_id: Option<String>,
     additional_claims: BTreeMap<String, serde_json::Value>,
+    refresh_token: bool,
     now: DateTime<Utc>,
 }
 
 impl<'cache, 'kid> TokenProducer<'cache, 'kid> {
     const DEFAULT_WEB_TOKEN_ID_LENGTH: usize = 20;
-    
+    const DEFAULT_REFRESH_TOKEN_LIFETIME_DAYS: i64 = 30;
+
     pub fn new(key_cache: &'cache mut KeyCache) -> Self {
         Self { 
             key_cache,
@@ -41,6 +48,7 @@ impl<'cache, 'kid> TokenProducer<'cache, 'kid> {
             audience: None,
             token_id: None,
             additional_claims: BTreeMap::new(),
+            refresh_token: false,
             now: Utc::now(),
         }
     }
@@ -113,12 +121,50 @@ impl<'cache, 'kid> TokenProducer<'cache, 'kid> {
         }
     }
 
+    /// Produce a refresh token instead of an access token. Refresh tokens carry the
+    /// `token_use=refresh` claim and expire after 30 days unless an expiration is set.
+    pub fn as_refresh_token(mut self) -> Self {
+        self.refresh_token = true;
+        self
+    }
+
     /// Produces a new token
     pub fn produce(self, subject: &str) -> Result<Token<Header, Claims, Signed>, Box<dyn Error>> {
+        let (token, _) = self.sign(subject)?;
+        Ok(token)
+    }
+
+    /// Produces a new token in compact serialization. If encryption is enabled for the
+    /// signing key, the signed token is wrapped into a JWE for the same key.
+    pub fn produce_compact(self, subject: &str) -> Result<String, Box<dyn Error>> {
+        let (token, key_cache) = self.sign(subject)?;
+        let key_id = token.header().key_id.clone().unwrap_or_default();
+        let token = String::from(token);
+
+        if key_cache.encryption_enabled(key_id.as_str()) {
+            let (key, _) = key_cache.get_public_key(Some(key_id.as_str()))?;
+            jwe::encrypt(token.as_str(), key, key_id.as_str())
+        } else {
+            Ok(token)
+        }
+    }
+
+    /// Sign a new token and hand back the key cache
+    fn sign(mut self, subject: &str) -> Result<SignedToken<'cache>, Box<dyn Error>> {
+        if self.refresh_token {
+            self.additional_claims.insert(
+                TOKEN_USE_CLAIM.to_string(),
+                serde_json::Value::String(TOKEN_USE_REFRESH.to_string()),
+            );
+            if self.expiration.is_none() {
+                self.expiration = Some(self.now + TimeDelta::days(Self::DEFAULT_REFRESH_TOKEN_LIFETIME_DAYS));
+            }
+        }
+
         let (key, key_id) = self.key_cache.get_private_key(self.key_id)?;
         let alg = PKeyWithDigest {
             key: key.clone(),
-            digest: MessageDigest::sha512(),
+            digest: signature_algorithm(key)?.1,
         };
 
         let header = Header {
@@ -140,6 +186,6 @@ impl<'cache, 'kid> TokenProducer<'cache, 'kid> {
         );
         claims.private = self.additional_claims;
         let token = Token::new(header, claims);
-        Ok(token.sign_with_key(&alg)?)
+        Ok((token.sign_with_key(&alg)?, self.key_cache))
     }
 }
@@ -7,9 +7,13 @@
  */
 use std::error::Error;
 use chrono::{DateTime, Utc, TimeDelta};
-use jwt::{Claims, Header, PKeyWithDigest, Token, Unverified, Verified, VerifyWithKey};
+use jwt::{AlgorithmType, Claims, Header, PKeyWithDigest, Token, Unverified, Verified, VerifyWithKey};
 use openssl::hash::MessageDigest;
+use openssl::pkey::{Id, PKey, Public};
 use crate::keys::KeyCache;
+use crate::keys::jwk::signature_algorithm;
+use super::claims::{ClaimPolicy, ClaimType};
+use super::jwe;
 
 /// Verifier for JWT
 pub struct TokenVerifier<'cache, 'kid> {
@@ -20,6 +24,7 @@ pub struct TokenVerifier<'cache, 'kid> {
     check_times: bool,
     max_expiration: Option<TimeDelta>,
     issued_after: Option<DateTime<Utc>>,
+    claim_policy: ClaimPolicy,
     now: DateTime<Utc>,
 }
 
@@ -33,6 +38,7 @@ impl<'cache, 'kid> TokenVerifier<'cache, 'kid> {
             check_times: true,
             max_expiration: None,
             issued_after: None,
+            claim_policy: ClaimPolicy::new(),
             now: Utc::now(),
         }
     }
@@ -72,9 +78,44 @@ impl<'cache, 'kid> TokenVerifier<'cache, 'kid> {
         self
     }
 
+    /// Require custom [claim] to be present and equal to [value]
+    pub fn require_claim<S: ToString>(mut self, claim: S, value: serde_json::Value) -> Self {
+        self.claim_policy = self.claim_policy.require_claim(claim, value);
+        self
+    }
+
+    /// Require all custom claims of [schema] to be present with the given type
+    pub fn require_claims_schema<S: ToString, I: IntoIterator<Item = (S, ClaimType)>>(mut self, schema: I) -> Self {
+        self.claim_policy = self.claim_policy.require_claims_schema(schema);
+        self
+    }
+
+    /// Require custom [claim] to contain [item], e.g. a role in `realm_access.roles`
+    pub fn require_claim_contains<S: ToString, T: ToString>(mut self, claim: S, item: T) -> Self {
+        self.claim_policy = self.claim_policy.require_claim_contains(claim, item);
+        self
+    }
+
+    /// Replace the claim policy
+    pub fn with_claim_policy(mut self, claim_policy: ClaimPolicy) -> Self {
+        self.claim_policy = claim_policy;
+        self
+    }
+
     /// Verify token and return key ID used to sign the token
     pub fn verify<S: AsRef<str>>(self, token: S) -> Result<(Token<Header, Claims, Verified>, String), Box<dyn Error>> {
-        let token: Token<Header, Claims, Unverified> = Token::parse_unverified(token.as_ref())?;
+        // Encrypted tokens are decrypted first with the private key of the recipient
+        let decrypted;
+        let token = if jwe::is_encrypted(token.as_ref()) {
+            let key_id = jwe::key_id(token.as_ref())?;
+            let (key, _) = self.key_cache.get_private_key(key_id.as_deref())?;
+            decrypted = jwe::decrypt(token.as_ref(), key)?;
+            decrypted.as_str()
+        } else {
+            token.as_ref()
+        };
+
+        let token: Token<Header, Claims, Unverified> = Token::parse_unverified(token)?;
         let key_id = match &token.header().key_id {
             Some(key_id) => Some(key_id.as_str()),
             None => None,
@@ -83,7 +124,7 @@ impl<'cache, 'kid> TokenVerifier<'cache, 'kid> {
         let (key, key_id) = self.key_cache.get_public_key(key_id)?;
         let alg = PKeyWithDigest {
             key: key.clone(),
-            digest: MessageDigest::sha512(),
+            digest: verification_digest(key, token.header().algorithm)?,
         };
 
         // Check key ID
@@ -161,6 +202,23 @@ impl<'cache, 'kid> TokenVerifier<'cache, 'kid> {
             }
         }
 
+        // Check custom claims
+        if !self.claim_policy.is_empty() {
+            let claims = serde_json::to_value(token.claims())?;
+            self.claim_policy.check(&claims)?;
+        }
+
         Ok((token, key_id))
     }
 }
+
+/// Digest to verify a signature of [key] made with [algorithm]. EC keys of all curves
+/// signed with SHA-512 as `ES512` before the digest matched the curve, so tokens
+/// issued that way with P-256 and P-384 keys are still accepted. Any other algorithm
+/// than the one of the key is rejected when verifying.
+fn verification_digest(key: &PKey<Public>, algorithm: AlgorithmType) -> Result<MessageDigest, Box<dyn Error>> {
+    match (key.id(), algorithm) {
+        (Id::EC, AlgorithmType::Es512) => Ok(MessageDigest::sha512()),
+        _ => Ok(signature_algorithm(key)?.1),
+    }
+}
@@ -0,0 +1,205 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::error::Error;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{HasPublic, Id, PKey, Public};
+use openssl::rsa::Rsa;
+use serde_json::{json, Value};
+
+/// Encode bytes as Base64url without padding (RFC 7515, Section 2)
+fn encode_b64url(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Decode Base64url without padding (RFC 7515, Section 2)
+fn decode_b64url(value: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(base64::decode_config(value, base64::URL_SAFE_NO_PAD)?)
+}
+
+/// Get string member [name] of [jwk]
+fn get_member<'a>(jwk: &'a Value, name: &str) -> Result<&'a str, Box<dyn Error>> {
+    match jwk[name].as_str() {
+        Some(value) => Ok(value),
+        None => Err(From::from(format!("JWK member \"{}\" is missing", name))),
+    }
+}
+
+/// Map OpenSSL curve to JWK curve name (RFC 7518, Section 6.2.1.1)
+fn curve_name(nid: Nid) -> Result<&'static str, Box<dyn Error>> {
+    match nid {
+        Nid::X9_62_PRIME256V1 => Ok("P-256"),
+        Nid::SECP384R1 => Ok("P-384"),
+        Nid::SECP521R1 => Ok("P-521"),
+        _ => Err(From::from("Elliptic curve is not supported by JWK")),
+    }
+}
+
+/// Map JWK curve name to OpenSSL curve (RFC 7518, Section 6.2.1.1)
+fn curve_nid(name: &str) -> Result<Nid, Box<dyn Error>> {
+    match name {
+        "P-256" => Ok(Nid::X9_62_PRIME256V1),
+        "P-384" => Ok(Nid::SECP384R1),
+        "P-521" => Ok(Nid::SECP521R1),
+        _ => Err(From::from(format!("JWK curve \"{}\" is not supported", name))),
+    }
+}
+
+/// JWS algorithm and digest of the signatures made with [key] (RFC 7518, Section 3.1).
+/// RSA keys sign with SHA-512, EC keys with the digest matching their curve.
+pub fn signature_algorithm<T: HasPublic>(key: &PKey<T>) -> Result<(&'static str, MessageDigest), Box<dyn Error>> {
+    match key.id() {
+        Id::RSA => Ok(("RS512", MessageDigest::sha512())),
+        Id::EC => match key.ec_key()?.group().curve_name() {
+            Some(Nid::X9_62_PRIME256V1) => Ok(("ES256", MessageDigest::sha256())),
+            Some(Nid::SECP384R1) => Ok(("ES384", MessageDigest::sha384())),
+            Some(Nid::SECP521R1) => Ok(("ES512", MessageDigest::sha512())),
+            _ => Err(From::from("Elliptic curve is not supported by JWS")),
+        },
+        _ => Err(From::from("Key type is not supported by JWS")),
+    }
+}
+
+/// Export public key [key] with ID [key_id] as JWK (RFC 7517)
+pub fn public_key_to_jwk(key: &PKey<Public>, key_id: &str) -> Result<Value, Box<dyn Error>> {
+    match key.id() {
+        Id::RSA => {
+            let rsa = key.rsa()?;
+            Ok(json!({
+                "kty": "RSA",
+                "kid": key_id,
+                "use": "sig",
+                "alg": signature_algorithm(key)?.0,
+                "n": encode_b64url(&rsa.n().to_vec()),
+                "e": encode_b64url(&rsa.e().to_vec()),
+            }))
+        },
+        Id::EC => {
+            let ec_key = key.ec_key()?;
+            let group = ec_key.group();
+            let nid = match group.curve_name() {
+                Some(nid) => nid,
+                None => Err("Elliptic curve has no name")?,
+            };
+
+            // Coordinates must be padded to the full field size
+            let coordinate_len = group.degree().div_ceil(8) as i32;
+            let mut x = BigNum::new()?;
+            let mut y = BigNum::new()?;
+            let mut ctx = BigNumContext::new()?;
+            ec_key.public_key().affine_coordinates(group, &mut x, &mut y, &mut ctx)?;
+
+            Ok(json!({
+                "kty": "EC",
+                "kid": key_id,
+                "use": "sig",
+                "alg": signature_algorithm(key)?.0,
+                "crv": curve_name(nid)?,
+                "x": encode_b64url(&x.to_vec_padded(coordinate_len)?),
+                "y": encode_b64url(&y.to_vec_padded(coordinate_len)?),
+            }))
+        },
+        _ => Err(From::from("Key type is not supported by JWK")),
+    }
+}
+
+/// Import public key from JWK (RFC 7517). Returns the key and its key ID, if present.
+pub fn jwk_to_public_key(jwk: &Value) -> Result<(PKey<Public>, Option<String>), Box<dyn Error>> {
+    let key_id = jwk["kid"].as_str().map(String::from);
+
+    let key = match get_member(jwk, "kty")? {
+        "RSA" => {
+            let n = BigNum::from_slice(&decode_b64url(get_member(jwk, "n")?)?)?;
+            let e = BigNum::from_slice(&decode_b64url(get_member(jwk, "e")?)?)?;
+            PKey::from_rsa(Rsa::from_public_components(n, e)?)?
+        },
+        "EC" => {
+            let group = EcGroup::from_curve_name(curve_nid(get_member(jwk, "crv")?)?)?;
+            let x = BigNum::from_slice(&decode_b64url(get_member(jwk, "x")?)?)?;
+            let y = BigNum::from_slice(&decode_b64url(get_member(jwk, "y")?)?)?;
+            PKey::from_ec_key(EcKey::from_public_key_affine_coordinates(&group, &x, &y)?)?
+        },
+        kty => Err(format!("JWK key type \"{}\" is not supported", kty))?,
+    };
+    Ok((key, key_id))
+}
+
+/// Wrap a list of JWKs into a JWK Set (RFC 7517, Section 5)
+pub fn make_jwks(keys: Vec<Value>) -> Value {
+    json!({
+        "keys": keys,
+    })
+}
+
+/// Extract the list of JWKs from a JWK Set (RFC 7517, Section 5)
+pub fn split_jwks(jwks: &Value) -> Result<&Vec<Value>, Box<dyn Error>> {
+    match jwks["keys"].as_array() {
+        Some(keys) => Ok(keys),
+        None => Err(From::from("JWK Set has no \"keys\" member")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use crate::keys::jwk::{jwk_to_public_key, public_key_to_jwk};
+    use crate::keys::key_generator::KeyGenerator;
+
+    #[test]
+    fn test_jwk_rsa_roundtrip() {
+        let private_key = KeyGenerator::new_rsa(2048).generate().unwrap();
+        let public_key = PKey::public_key_from_pem(&private_key.public_key_to_pem().unwrap()).unwrap();
+
+        let jwk = public_key_to_jwk(&public_key, "test1").unwrap();
+        assert_eq!(jwk["kty"], "RSA");
+        assert_eq!(jwk["kid"], "test1");
+        assert_eq!(jwk["e"], "AQAB");
+        assert_eq!(jwk["alg"], "RS512");
+
+        let (imported, key_id) = jwk_to_public_key(&jwk).unwrap();
+        assert_eq!(key_id, Some("test1".to_string()));
+        assert!(imported.public_eq(&public_key));
+    }
+
+    #[test]
+    fn test_jwk_ec_roundtrip() {
+        let private_key = KeyGenerator::new_ec_from_nid(Nid::SECP521R1).unwrap().generate().unwrap();
+        let public_key = PKey::public_key_from_pem(&private_key.public_key_to_pem().unwrap()).unwrap();
+
+        let jwk = public_key_to_jwk(&public_key, "test2").unwrap();
+        assert_eq!(jwk["kty"], "EC");
+        assert_eq!(jwk["crv"], "P-521");
+        assert_eq!(jwk["alg"], "ES512");
+        // 66 bytes of coordinate are 88 Base64 characters without padding
+        assert_eq!(jwk["x"].as_str().unwrap().len(), 88);
+
+        let (imported, key_id) = jwk_to_public_key(&jwk).unwrap();
+        assert_eq!(key_id, Some("test2".to_string()));
+        assert!(imported.public_eq(&public_key));
+    }
+
+    #[test]
+    fn test_jwk_ec_algorithm_of_curve() {
+        for (nid, crv, alg) in [
+            (Nid::X9_62_PRIME256V1, "P-256", "ES256"),
+            (Nid::SECP384R1, "P-384", "ES384"),
+            (Nid::SECP521R1, "P-521", "ES512"),
+        ] {
+            let private_key = KeyGenerator::new_ec_from_nid(nid).unwrap().generate().unwrap();
+            let public_key = PKey::public_key_from_pem(&private_key.public_key_to_pem().unwrap()).unwrap();
+
+            let jwk = public_key_to_jwk(&public_key, "test3").unwrap();
+            assert_eq!(jwk["crv"], crv);
+            assert_eq!(jwk["alg"], alg);
+        }
+    }
+}
@@ -107,28 +107,131 @@ impl<'a> KeyCache {
         }
     }
 
+    /// Evict key [key_id] if it was disabled or deleted in the key store meanwhile, e.g.
+    /// by the `token` tool or another process sharing the key directory
+    fn evict_if_inactive(&mut self, key_id: &str) -> Result<(), Box<dyn Error>> {
+        if !self.key_store.is_active(key_id) {
+            self.evict(key_id);
+            Err("Key not found")?;
+        }
+        Ok(())
+    }
+
+    /// Read the default key ID from the key store again if the default key was evicted,
+    /// so that a default made meanwhile is picked up
+    fn reload_default_if_none(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.default_key_id.is_none() {
+            self.default_key_id = self.key_store.default_key_id()?;
+        }
+        Ok(())
+    }
+
     /// Get private key with ID [key_id], or the default private key if [key_id] is None
     pub fn get_private_key(&'a mut self, key_id: Option<&str>) -> Result<(&'a PKey<Private>, String), Box<dyn Error>> {
-        let key_id = Self::default_key_if_none(key_id, &self.default_key_id)?;
+        self.reload_default_if_none()?;
+        let key_id = String::from(Self::default_key_if_none(key_id, &self.default_key_id)?);
 
-        if !self.private_keys.contains_key(key_id) {
-            self.private_keys.insert(String::from(key_id), self.key_store.load_private_key(key_id)?);
+        if self.private_keys.contains_key(key_id.as_str()) {
+            self.evict_if_inactive(key_id.as_str())?;
+        } else {
+            self.private_keys.insert(key_id.clone(), self.key_store.load_private_key(key_id.as_str())?);
         }
-        Ok((&self.private_keys[key_id], key_id.to_string()))
+        Ok((&self.private_keys[key_id.as_str()], key_id))
     }
 
     /// Get public key with ID [key_id]
     pub fn get_public_key(&'a mut self, key_id: Option<&str>) -> Result<(&'a PKey<Public>, String), Box<dyn Error>> {
-        let key_id = Self::default_key_if_none(key_id, &self.default_key_id)?;
+        self.reload_default_if_none()?;
+        let key_id = String::from(Self::default_key_if_none(key_id, &self.default_key_id)?);
 
-        if !self.public_keys.contains_key(key_id) {
-            self.public_keys.insert(String::from(key_id), self.key_store.load_public_key(key_id)?);
+        if self.public_keys.contains_key(key_id.as_str()) {
+            self.evict_if_inactive(key_id.as_str())?;
+        } else {
+            self.public_keys.insert(key_id.clone(), self.key_store.load_public_key(key_id.as_str())?);
         }
-        Ok((&self.public_keys[key_id], key_id.to_string()))
+        Ok((&self.public_keys[key_id.as_str()], key_id))
     }
 
     /// List all key IDs
     pub fn key_id_list(&self) -> Result<Vec<String>, Box<dyn Error>> {
         self.key_store.key_id_list()
     }
+
+    /// Get default key ID
+    pub fn default_key_id(&self) -> Option<&str> {
+        self.default_key_id.as_deref()
+    }
+
+    /// Set [key_id] as default. The key must exist and have a private key.
+    pub fn make_default(&mut self, key_id: &str) -> Result<(), Box<dyn Error>> {
+        self.get_private_key(Some(key_id))?;
+        self.key_store.make_default(key_id)?;
+        self.default_key_id = Some(String::from(key_id));
+        Ok(())
+    }
+
+    /// List all disabled key IDs
+    pub fn disabled_key_id_list(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        self.key_store.disabled_key_id_list()
+    }
+
+    /// Remove key [key_id] from the cache
+    fn evict(&mut self, key_id: &str) {
+        self.private_keys.remove(key_id);
+        self.public_keys.remove(key_id);
+        if self.default_key_id.as_deref() == Some(key_id) {
+            self.default_key_id = None;
+        }
+    }
+
+    /// Disable key [key_id] and evict it from the cache
+    pub fn disable_key(&mut self, key_id: &str) -> Result<(), Box<dyn Error>> {
+        self.key_store.disable_key(key_id)?;
+        self.evict(key_id);
+        Ok(())
+    }
+
+    /// Enable disabled key [key_id] again
+    pub fn enable_key(&mut self, key_id: &str) -> Result<(), Box<dyn Error>> {
+        self.key_store.enable_key(key_id)
+    }
+
+    /// Delete key [key_id] and evict it from the cache
+    pub fn delete_key(&mut self, key_id: &str) -> Result<(), Box<dyn Error>> {
+        self.key_store.delete_key(key_id)?;
+        self.evict(key_id);
+        Ok(())
+    }
+
+    /// Check if tokens signed with key [key_id] shall be encrypted
+    pub fn encryption_enabled(&self, key_id: &str) -> bool {
+        self.key_store.encryption_enabled(key_id)
+    }
+
+    /// Enable or disable encryption (JWE) of tokens signed with key [key_id]
+    pub fn set_encryption(&self, key_id: &str, enabled: bool) -> Result<(), Box<dyn Error>> {
+        self.key_store.set_encryption(key_id, enabled)
+    }
+
+    /// Export all public keys as JWK Set
+    pub fn export_jwks(&self) -> Result<serde_json::Value, Box<dyn Error>> {
+        self.key_store.export_jwks()
+    }
+
+    /// Import public key from JWK into the cache and the underlying key store.
+    /// Returns the key ID of the imported key.
+    pub fn import_jwk(&mut self, jwk: &serde_json::Value, key_id: Option<&str>) -> Result<String, Box<dyn Error>> {
+        let (key, key_id) = self.key_store.import_jwk(jwk, key_id)?;
+        self.public_keys.insert(key_id.clone(), key);
+        Ok(key_id)
+    }
+
+    /// Import all public keys of a JWK Set. Returns the key IDs of the imported keys.
+    pub fn import_jwks(&mut self, jwks: &serde_json::Value) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut key_ids = Vec::new();
+        for jwk in super::jwk::split_jwks(jwks)? {
+            key_ids.push(self.import_jwk(jwk, None)?);
+        }
+        Ok(key_ids)
+    }
 }
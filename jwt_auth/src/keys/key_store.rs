@@ -11,10 +11,14 @@ use std::path::{Path, PathBuf};
 use std::error::Error;
 use openssl::pkey::{PKey, Public, Private};
 use super::key_generator::KeyGenerator;
+use super::jwk;
 
 /// Facade to keys
 ///
 /// All keys are stored at [base_dir]/key_[key_id]/{public,private}.pem
+///
+/// Disabled keys are moved to [base_dir]/disabled_[key_id] and can be enabled again.
+/// Deleted keys are moved to [base_dir]/retired_[key_id] and are kept for auditing only.
 pub struct KeyStore {
     /// Base directory where the keys are stored
     base_dir: PathBuf,
@@ -22,9 +26,12 @@ pub struct KeyStore {
 
 impl KeyStore {
     const KEY_DIR_PREFIX: &'static str = "key_";
+    const DISABLED_DIR_PREFIX: &'static str = "disabled_";
+    const RETIRED_DIR_PREFIX: &'static str = "retired_";
     const DEFAULT_TXT: &'static str = "default.txt";
     const PUBLIC_PEM: &'static str = "public.pem";
     const PRIVATE_PEM: &'static str = "private.pem";
+    const ENCRYPT_MARKER: &'static str = "encrypt";
 
     /// Create a new key store with [base_dir] as base directory
     pub fn new<P: AsRef<Path>>(base_dir: P) -> Self {
@@ -33,17 +40,62 @@ impl KeyStore {
         }
     }
 
+    /// Check that [key_id] only consists of `A-Z`, `a-z`, `0-9`, `.`, `_` and `-`, as it
+    /// names the directory of the key
+    fn check_key_id(key_id: &str) -> Result<(), Box<dyn Error>> {
+        let valid = !key_id.is_empty()
+            && key_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+        if valid {
+            Ok(())
+        } else {
+            Err(From::from(format!("Invalid key ID \"{}\"", key_id)))
+        }
+    }
+
     /// Path to directory of key with ID [key_id]
-    fn key_dir(&self, key_id: &str) -> PathBuf {
+    fn key_dir(&self, key_id: &str) -> Result<PathBuf, Box<dyn Error>> {
+        self.prefixed_dir(Self::KEY_DIR_PREFIX, key_id)
+    }
+
+    /// Path to directory of key with ID [key_id] in the state given by [prefix]. The key
+    /// ID is checked, as it may come from an untrusted token header and must not lead
+    /// out of the directory, e.g. to a disabled key.
+    fn prefixed_dir(&self, prefix: &str, key_id: &str) -> Result<PathBuf, Box<dyn Error>> {
+        Self::check_key_id(key_id)?;
         let mut key_path = self.base_dir.clone();
-        let dir_name = String::from(Self::KEY_DIR_PREFIX) + key_id;
+        let dir_name = String::from(prefix) + key_id;
         key_path.push(dir_name);
-        key_path
+        Ok(key_path)
+    }
+
+    /// Move key [key_id] from state [from_prefix] to state [to_prefix]
+    fn move_key(&self, key_id: &str, from_prefix: &str, to_prefix: &str) -> Result<(), Box<dyn Error>> {
+        let from_path = self.prefixed_dir(from_prefix, key_id)?;
+        let to_path = self.prefixed_dir(to_prefix, key_id)?;
+
+        if !from_path.is_dir() {
+            Err("Key not found")?;
+        }
+        if to_path.exists() {
+            Err(format!("Key {} already exists in state {}", key_id, to_prefix.trim_end_matches('_')))?;
+        }
+        fs::rename(&from_path, &to_path)?;
+        Ok(())
+    }
+
+    /// Remove default key ID if it is [key_id]
+    fn clear_default_if(&self, key_id: &str) -> Result<(), Box<dyn Error>> {
+        if self.default_key_id()?.as_deref() == Some(key_id) {
+            let mut default_txt_path = self.base_dir.clone();
+            default_txt_path.push(Self::DEFAULT_TXT);
+            fs::remove_file(&default_txt_path)?;
+        }
+        Ok(())
     }
 
     /// Create key pair with ID [key_id]
     pub fn create_key_pair(&self, key_id: &str, generator: KeyGenerator) -> Result<PKey<Private>, Box<dyn Error>> {
-        let key_path = self.key_dir(key_id);
+        let key_path = self.key_dir(key_id)?;
 
         if key_path.exists() {
             Err(From::from("Key already exists"))
@@ -71,7 +123,7 @@ impl KeyStore {
 
     /// Load public key with ID [key_id]
     pub fn load_public_key(&self, key_id: &str) -> Result<PKey<Public>, Box<dyn Error>> {
-        let mut public_key_path = self.key_dir(key_id);
+        let mut public_key_path = self.key_dir(key_id)?;
         public_key_path.push(Self::PUBLIC_PEM);
 
         if public_key_path.is_file() {
@@ -85,7 +137,7 @@ impl KeyStore {
 
     /// Load private key with ID [key_id]
     pub fn load_private_key(&self, key_id: &str) -> Result<PKey<Private>, Box<dyn Error>> {
-        let mut private_key_path = self.key_dir(key_id);
+        let mut private_key_path = self.key_dir(key_id)?;
         private_key_path.push(Self::PRIVATE_PEM);
 
         if private_key_path.is_file() {
@@ -97,20 +149,129 @@ impl KeyStore {
         }
     }
 
-    /// Get list of keys
-    pub fn key_id_list(&self) -> Result<Vec<String>, Box<dyn Error>> {
+    /// Import public key from JWK. The key is stored without private key and can only
+    /// be used to verify tokens. If the JWK has no key ID, [key_id] must be given.
+    pub fn import_jwk(&self, jwk: &serde_json::Value, key_id: Option<&str>) -> Result<(PKey<Public>, String), Box<dyn Error>> {
+        let (key, jwk_key_id) = jwk::jwk_to_public_key(jwk)?;
+        let key_id = match key_id {
+            Some(key_id) => String::from(key_id),
+            None => match jwk_key_id {
+                Some(key_id) => key_id,
+                None => Err("JWK has no key ID and none was given")?,
+            },
+        };
+        let key_path = self.key_dir(key_id.as_str())?;
+        if key_path.exists() {
+            Err(From::from("Key already exists"))
+        } else {
+            fs::create_dir_all(&key_path)?;
+
+            let mut public_key_path = key_path.clone();
+            public_key_path.push(Self::PUBLIC_PEM);
+            fs::write(&public_key_path, key.public_key_to_pem()?)?;
+
+            Ok((key, key_id))
+        }
+    }
+
+    /// Export public key with ID [key_id] as JWK
+    pub fn export_jwk(&self, key_id: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+        let key = self.load_public_key(key_id)?;
+        jwk::public_key_to_jwk(&key, key_id)
+    }
+
+    /// Export all public keys as JWK Set
+    pub fn export_jwks(&self) -> Result<serde_json::Value, Box<dyn Error>> {
+        let mut keys = Vec::new();
+        for key_id in self.key_id_list()? {
+            keys.push(self.export_jwk(key_id.as_str())?);
+        }
+        Ok(jwk::make_jwks(keys))
+    }
+
+    /// Enable or disable encryption (JWE) of tokens signed with key [key_id]
+    pub fn set_encryption(&self, key_id: &str, enabled: bool) -> Result<(), Box<dyn Error>> {
+        let key_path = self.key_dir(key_id)?;
+        if !key_path.is_dir() {
+            Err("Key not found")?;
+        }
+
+        let mut marker_path = key_path;
+        marker_path.push(Self::ENCRYPT_MARKER);
+        if enabled {
+            if self.load_public_key(key_id)?.rsa().is_err() {
+                Err("Encryption is only supported for RSA keys")?;
+            }
+            fs::write(&marker_path, [])?;
+        } else if marker_path.exists() {
+            fs::remove_file(&marker_path)?;
+        }
+        Ok(())
+    }
+
+    /// Check if tokens signed with key [key_id] shall be encrypted
+    pub fn encryption_enabled(&self, key_id: &str) -> bool {
+        match self.key_dir(key_id) {
+            Ok(key_path) => key_path.join(Self::ENCRYPT_MARKER).is_file(),
+            Err(_) => false,
+        }
+    }
+
+    /// Check if key [key_id] is in service, i.e. neither disabled nor deleted
+    pub fn is_active(&self, key_id: &str) -> bool {
+        match self.key_dir(key_id) {
+            Ok(key_path) => key_path.is_dir(),
+            Err(_) => false,
+        }
+    }
+
+    /// Disable key [key_id]. The key is not used for signing and verification anymore,
+    /// but can be enabled again. If it is the default key, there is no default afterward.
+    pub fn disable_key(&self, key_id: &str) -> Result<(), Box<dyn Error>> {
+        self.move_key(key_id, Self::KEY_DIR_PREFIX, Self::DISABLED_DIR_PREFIX)?;
+        self.clear_default_if(key_id)
+    }
+
+    /// Enable disabled key [key_id] again
+    pub fn enable_key(&self, key_id: &str) -> Result<(), Box<dyn Error>> {
+        self.move_key(key_id, Self::DISABLED_DIR_PREFIX, Self::KEY_DIR_PREFIX)
+    }
+
+    /// Delete key [key_id], which may be active or disabled. The key files are moved
+    /// aside instead of being removed and the key cannot be enabled again.
+    pub fn delete_key(&self, key_id: &str) -> Result<(), Box<dyn Error>> {
+        if self.is_active(key_id) {
+            self.move_key(key_id, Self::KEY_DIR_PREFIX, Self::RETIRED_DIR_PREFIX)?;
+        } else {
+            self.move_key(key_id, Self::DISABLED_DIR_PREFIX, Self::RETIRED_DIR_PREFIX)?;
+        }
+        self.clear_default_if(key_id)
+    }
+
+    /// Get list of key IDs in the state given by [prefix]
+    fn prefixed_key_id_list(&self, prefix: &str) -> Result<Vec<String>, Box<dyn Error>> {
         let mut key_ids = Vec::new();
         for dir in fs::read_dir(&self.base_dir)? {
             let dir = dir?;
             let dir_name = dir.file_name().to_str().unwrap().to_owned();
-            if dir.file_type()?.is_dir() && dir_name.starts_with(Self::KEY_DIR_PREFIX) {
-                let key_id = &dir_name[Self::KEY_DIR_PREFIX.len()..];
+            if dir.file_type()?.is_dir() && dir_name.starts_with(prefix) {
+                let key_id = &dir_name[prefix.len()..];
                 key_ids.push(String::from(key_id));
             }
         }
         Ok(key_ids)
     }
 
+    /// Get list of keys
+    pub fn key_id_list(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        self.prefixed_key_id_list(Self::KEY_DIR_PREFIX)
+    }
+
+    /// Get list of disabled keys
+    pub fn disabled_key_id_list(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        self.prefixed_key_id_list(Self::DISABLED_DIR_PREFIX)
+    }
+
     /// Set [key_id] as default
     pub fn make_default(&self, key_id: &str) -> Result<(), Box<dyn Error>> {
         let mut default_txt_path = self.base_dir.clone();
@@ -168,4 +329,112 @@ mod tests {
         key_store.make_default("test1").unwrap();
         assert_eq!(key_store.default_key_id().unwrap(), Some(String::from("test1")));
     }
+
+    #[test]
+    fn test_key_store_jwks() {
+        let tmp_dir_export = TempDir::new().unwrap();
+        let export_store = KeyStore::new(tmp_dir_export.path());
+        let test1_private = export_store.create_key_pair(
+            "test1",
+            KeyGenerator::new_rsa(2048),
+        ).unwrap();
+        export_store.create_key_pair(
+            "test2",
+            KeyGenerator::new_ec_from_nid(Nid::X9_62_PRIME256V1).unwrap(),
+        ).unwrap();
+
+        let jwks = export_store.export_jwks().unwrap();
+        assert_eq!(jwks["keys"].as_array().unwrap().len(), 2);
+
+        let tmp_dir_import = TempDir::new().unwrap();
+        let import_store = KeyStore::new(tmp_dir_import.path());
+        for jwk in jwks["keys"].as_array().unwrap() {
+            import_store.import_jwk(jwk, None).unwrap();
+        }
+        let key_id_list = import_store.key_id_list().unwrap();
+        assert_eq!(key_id_list.len(), 2);
+
+        let test1_public = import_store.load_public_key("test1").unwrap();
+        assert!(test1_private.public_eq(&test1_public));
+        assert!(import_store.load_private_key("test1").is_err());
+
+        // Importing the same key twice must fail
+        assert!(import_store.import_jwk(&jwks["keys"][0], None).is_err());
+    }
+
+    #[test]
+    fn test_key_store_key_id() {
+        let tmp_dir = TempDir::new().unwrap();
+        let key_store = KeyStore::new(tmp_dir.path());
+        key_store.create_key_pair(
+            "test1",
+            KeyGenerator::new_ec_from_nid(Nid::X9_62_PRIME256V1).unwrap(),
+        ).unwrap();
+        let mut jwk = key_store.export_jwk("test1").unwrap();
+
+        for key_id in ["../test2", "test/2", "", "test 2", "tëst2", "test2\0"] {
+            jwk["kid"] = serde_json::Value::String(key_id.to_string());
+            assert!(key_store.import_jwk(&jwk, None).is_err(), "{}", key_id);
+            assert!(key_store.import_jwk(&jwk, Some(key_id)).is_err(), "{}", key_id);
+            assert!(key_store.create_key_pair(key_id, KeyGenerator::new_rsa(2048)).is_err(), "{}", key_id);
+        }
+        assert_eq!(key_store.key_id_list().unwrap(), vec![String::from("test1")]);
+
+        jwk["kid"] = serde_json::Value::String("Test-2_v1.0".to_string());
+        let (_, key_id) = key_store.import_jwk(&jwk, None).unwrap();
+        assert_eq!(key_id, "Test-2_v1.0");
+    }
+
+    #[test]
+    fn test_key_store_encryption() {
+        let tmp_dir = TempDir::new().unwrap();
+        let key_store = KeyStore::new(tmp_dir.path());
+        key_store.create_key_pair("test1", KeyGenerator::new_rsa(2048)).unwrap();
+        key_store.create_key_pair(
+            "test2",
+            KeyGenerator::new_ec_from_nid(Nid::X9_62_PRIME256V1).unwrap(),
+        ).unwrap();
+
+        assert!(!key_store.encryption_enabled("test1"));
+        key_store.set_encryption("test1", true).unwrap();
+        assert!(key_store.encryption_enabled("test1"));
+        key_store.set_encryption("test1", false).unwrap();
+        assert!(!key_store.encryption_enabled("test1"));
+
+        assert!(key_store.set_encryption("test2", true).is_err());
+        assert!(key_store.set_encryption("test3", true).is_err());
+    }
+
+    #[test]
+    fn test_key_store_disable_delete() {
+        let tmp_dir = TempDir::new().unwrap();
+        let key_store = KeyStore::new(tmp_dir.path());
+        key_store.create_key_pair("test1", KeyGenerator::new_rsa(2048)).unwrap();
+        key_store.create_key_pair("test2", KeyGenerator::new_rsa(2048)).unwrap();
+        key_store.make_default("test1").unwrap();
+
+        key_store.disable_key("test1").unwrap();
+        assert_eq!(key_store.key_id_list().unwrap(), vec![String::from("test2")]);
+        assert_eq!(key_store.disabled_key_id_list().unwrap(), vec![String::from("test1")]);
+        assert_eq!(key_store.default_key_id().unwrap(), None);
+        assert!(key_store.load_private_key("test1").is_err());
+        assert!(key_store.disable_key("test1").is_err());
+
+        key_store.enable_key("test1").unwrap();
+        assert!(key_store.load_private_key("test1").is_ok());
+        assert_eq!(key_store.disabled_key_id_list().unwrap().len(), 0);
+
+        key_store.make_default("test2").unwrap();
+        key_store.delete_key("test1").unwrap();
+        key_store.disable_key("test2").unwrap();
+        key_store.delete_key("test2").unwrap();
+        assert_eq!(key_store.key_id_list().unwrap().len(), 0);
+        assert_eq!(key_store.disabled_key_id_list().unwrap().len(), 0);
+        assert_eq!(key_store.default_key_id().unwrap(), None);
+        assert!(key_store.enable_key("test2").is_err());
+        assert!(key_store.delete_key("test3").is_err());
+
+        // Files of deleted keys are kept
+        assert!(tmp_dir.path().join("retired_test1").join("private.pem").is_file());
+    }
 }
@@ -9,6 +9,7 @@
 pub mod key_store;
 pub mod key_generator;
 pub mod key_cache;
+pub mod jwk;
 
 pub use key_store::KeyStore;
 pub use key_generator::KeyGenerator;
@@ -5,6 +5,32 @@ mod m20250323_195423_ride;
 mod m20250323_220823_tag_descriptor;
 mod m20250323_224215_ride_tag;
 mod m20250323_230053_tag_enum_option;
+mod m20261016_090000_idempotency_key;
+mod m20261016_100000_webhook;
+mod m20261016_110000_performance_indexes;
+mod m20261016_120000_unique_constraints;
+mod m20261016_130000_user_retention;
+mod m20261016_140000_audit_log;
+mod m20261016_150000_geocode_cache;
+mod m20261016_160000_inbox;
+mod m20261016_170000_caldav;
+mod m20261016_180000_webdav_target;
+mod m20261016_190000_telegram_link;
+mod m20261016_200000_ride_tag_per_ride;
+mod m20261016_210000_ride_arrival_estimated;
+mod m20261016_220000_ride_passengers;
+mod m20261016_230000_ride_status;
+mod m20261017_000000_ride_reference;
+mod m20261017_010000_ride_vat_rate;
+mod m20261017_020000_user_locale;
+mod m20261017_030000_tag_order;
+mod m20261017_040000_ride_track;
+mod m20261017_050000_ride_summary;
+mod m20261017_060000_audit_chain;
+mod m20261017_070000_user_profile;
+mod m20261017_080000_ride_track_scan;
+mod m20261017_090000_tag_aggregation;
+mod m20261017_100000_server_setting;
 
 pub struct Migrator;
 
@@ -17,6 +43,32 @@ impl MigratorTrait for Migrator {
             Box::new(m20250323_220823_tag_descriptor::Migration),
             Box::new(m20250323_224215_ride_tag::Migration),
             Box::new(m20250323_230053_tag_enum_option::Migration),
+            Box::new(m20261016_090000_idempotency_key::Migration),
+            Box::new(m20261016_100000_webhook::Migration),
+            Box::new(m20261016_110000_performance_indexes::Migration),
+            Box::new(m20261016_120000_unique_constraints::Migration),
+            Box::new(m20261016_130000_user_retention::Migration),
+            Box::new(m20261016_140000_audit_log::Migration),
+            Box::new(m20261016_150000_geocode_cache::Migration),
+            Box::new(m20261016_160000_inbox::Migration),
+            Box::new(m20261016_170000_caldav::Migration),
+            Box::new(m20261016_180000_webdav_target::Migration),
+            Box::new(m20261016_190000_telegram_link::Migration),
+            Box::new(m20261016_200000_ride_tag_per_ride::Migration),
+            Box::new(m20261016_210000_ride_arrival_estimated::Migration),
+            Box::new(m20261016_220000_ride_passengers::Migration),
+            Box::new(m20261016_230000_ride_status::Migration),
+            Box::new(m20261017_000000_ride_reference::Migration),
+            Box::new(m20261017_010000_ride_vat_rate::Migration),
+            Box::new(m20261017_020000_user_locale::Migration),
+            Box::new(m20261017_030000_tag_order::Migration),
+            Box::new(m20261017_040000_ride_track::Migration),
+            Box::new(m20261017_050000_ride_summary::Migration),
+            Box::new(m20261017_060000_audit_chain::Migration),
+            Box::new(m20261017_070000_user_profile::Migration),
+            Box::new(m20261017_080000_ride_track_scan::Migration),
+            Box::new(m20261017_090000_tag_aggregation::Migration),
+            Box::new(m20261017_100000_server_setting::Migration),
         ]
     }
 }
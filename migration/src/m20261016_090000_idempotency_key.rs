@@ -0,0 +1,62 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250316_204923_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IdempotencyKey::Table)
+                    .if_not_exists()
+                    .col(pk_auto(IdempotencyKey::Id))
+                    .col(date_time(IdempotencyKey::CreatedAt))
+                    .col(integer(IdempotencyKey::UserId))
+                    .foreign_key(ForeignKey::create()
+                        .name(IdempotencyKey::UserId.to_string())
+                        .from(IdempotencyKey::Table, IdempotencyKey::UserId)
+                        .to(User::Table, User::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string(IdempotencyKey::Key))
+                    .col(string(IdempotencyKey::Fingerprint))
+                    .col(integer_null(IdempotencyKey::Status))
+                    .col(text_null(IdempotencyKey::Body))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_idempotency_key_user_id_key")
+                    .table(IdempotencyKey::Table)
+                    .col(IdempotencyKey::UserId)
+                    .col(IdempotencyKey::Key)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IdempotencyKey::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum IdempotencyKey {
+    Table,
+    Id,
+    CreatedAt,
+    UserId,
+    Key,
+    Fingerprint,
+    Status,
+    Body,
+}
@@ -0,0 +1,109 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250316_204923_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Webhook::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Webhook::Id))
+                    .col(date_time(Webhook::CreatedAt))
+                    .col(date_time(Webhook::UpdatedAt))
+                    .col(date_time_null(Webhook::DeletedAt))
+                    .col(integer(Webhook::UserId))
+                    .foreign_key(ForeignKey::create()
+                        .name(Webhook::UserId.to_string())
+                        .from(Webhook::Table, Webhook::UserId)
+                        .to(User::Table, User::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string(Webhook::Url))
+                    .col(string(Webhook::Secret))
+                    .col(string(Webhook::Events))
+                    .col(boolean(Webhook::Active))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookDelivery::Table)
+                    .if_not_exists()
+                    .col(pk_auto(WebhookDelivery::Id))
+                    .col(date_time(WebhookDelivery::CreatedAt))
+                    .col(integer(WebhookDelivery::WebhookId))
+                    .foreign_key(ForeignKey::create()
+                        .name(WebhookDelivery::WebhookId.to_string())
+                        .from(WebhookDelivery::Table, WebhookDelivery::WebhookId)
+                        .to(Webhook::Table, Webhook::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string(WebhookDelivery::EventType))
+                    .col(text(WebhookDelivery::Payload))
+                    .col(string(WebhookDelivery::State))
+                    .col(integer(WebhookDelivery::Attempts))
+                    .col(date_time_null(WebhookDelivery::NextAttemptAt))
+                    .col(date_time_null(WebhookDelivery::DeliveredAt))
+                    .col(integer_null(WebhookDelivery::ResponseStatus))
+                    .col(string_null(WebhookDelivery::Error))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_delivery_state_next_attempt_at")
+                    .table(WebhookDelivery::Table)
+                    .col(WebhookDelivery::State)
+                    .col(WebhookDelivery::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebhookDelivery::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Webhook::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Webhook {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    DeletedAt,
+    UserId,
+    Url,
+    Secret,
+    Events,
+    Active,
+}
+
+#[derive(DeriveIden)]
+pub enum WebhookDelivery {
+    Table,
+    Id,
+    CreatedAt,
+    WebhookId,
+    EventType,
+    Payload,
+    State,
+    Attempts,
+    NextAttemptAt,
+    DeliveredAt,
+    ResponseStatus,
+    Error,
+}
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250323_195423_ride::Ride;
+use super::m20250323_220823_tag_descriptor::TagDescriptor;
+use super::m20250323_224215_ride_tag::RideTag;
+use super::m20250323_230053_tag_enum_option::TagEnumOption;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Indexes on the columns the lists filter by, as name, table and columns
+fn indexes() -> Vec<(&'static str, DynIden, Vec<DynIden>)> {
+    vec![
+        (
+            "idx_ride_user_id_deleted_at_journey_departure",
+            Ride::Table.into_iden(),
+            vec![Ride::UserId.into_iden(), Ride::DeletedAt.into_iden(), Ride::JourneyDeparture.into_iden()],
+        ),
+        (
+            "idx_ride_tag_ride_id_deleted_at",
+            RideTag::Table.into_iden(),
+            vec![RideTag::RideId.into_iden(), RideTag::DeletedAt.into_iden()],
+        ),
+        (
+            "idx_ride_tag_tag_descriptor_id",
+            RideTag::Table.into_iden(),
+            vec![RideTag::TagDescriptorId.into_iden()],
+        ),
+        (
+            "idx_tag_descriptor_user_id_deleted_at",
+            TagDescriptor::Table.into_iden(),
+            vec![TagDescriptor::UserId.into_iden(), TagDescriptor::DeletedAt.into_iden()],
+        ),
+        (
+            "idx_tag_enum_option_tag_descriptor_id_deleted_at",
+            TagEnumOption::Table.into_iden(),
+            vec![TagEnumOption::TagDescriptorId.into_iden(), TagEnumOption::DeletedAt.into_iden()],
+        ),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, table, columns) in indexes() {
+            let mut index = Index::create();
+            index
+                .name(name)
+                .table(table)
+                .if_not_exists();
+            for column in columns {
+                index.col(column);
+            }
+            manager.create_index(index.to_owned()).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, table, _) in indexes() {
+            manager
+                .drop_index(
+                    Index::drop()
+                        .name(name)
+                        .table(table)
+                        .if_exists()
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
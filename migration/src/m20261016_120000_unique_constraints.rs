@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250316_204923_user::User;
+use super::m20250323_220823_tag_descriptor::TagDescriptor;
+use super::m20250323_224215_ride_tag::RideTag;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Unique indexes, as name, table, columns and whether rows which are deleted softly are
+/// left out, so that a key can be used again after deletion
+fn indexes() -> Vec<(&'static str, DynIden, Vec<DynIden>, Option<DynIden>)> {
+    vec![
+        (
+            "idx_user_jwt_issuer_jwt_subject_unique",
+            User::Table.into_iden(),
+            vec![User::JwtIssuer.into_iden(), User::JwtSubject.into_iden()],
+            None,
+        ),
+        (
+            "idx_tag_descriptor_user_id_tag_key_unique",
+            TagDescriptor::Table.into_iden(),
+            vec![TagDescriptor::UserId.into_iden(), TagDescriptor::TagKey.into_iden()],
+            Some(TagDescriptor::DeletedAt.into_iden()),
+        ),
+        (
+            "idx_ride_tag_ride_id_tag_descriptor_id_unique",
+            RideTag::Table.into_iden(),
+            vec![RideTag::RideId.into_iden(), RideTag::TagDescriptorId.into_iden()],
+            Some(RideTag::DeletedAt.into_iden()),
+        ),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, table, columns, deleted_at) in indexes() {
+            let mut index = Index::create();
+            index
+                .name(name)
+                .table(table)
+                .unique()
+                .if_not_exists();
+            for column in columns {
+                index.col(column);
+            }
+            if let Some(deleted_at) = deleted_at {
+                index.and_where(Expr::col(deleted_at).is_null());
+            }
+            manager.create_index(index.to_owned()).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, table, _, _) in indexes() {
+            manager
+                .drop_index(
+                    Index::drop()
+                        .name(name)
+                        .table(table)
+                        .if_exists()
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
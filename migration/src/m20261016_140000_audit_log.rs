@@ -0,0 +1,64 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(pk_auto(AuditLog::Id))
+                    .col(date_time(AuditLog::CreatedAt))
+                    .col(integer_null(AuditLog::UserId))
+                    .col(string(AuditLog::Resource))
+                    .col(integer(AuditLog::ResourceId))
+                    .col(string(AuditLog::Action))
+                    .col(text(AuditLog::Diff))
+                    .col(string_null(AuditLog::RequestId))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_log_resource_resource_id")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::Resource)
+                    .col(AuditLog::ResourceId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_log_user_id")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum AuditLog {
+    Table,
+    Id,
+    CreatedAt,
+    UserId,
+    Resource,
+    ResourceId,
+    Action,
+    Diff,
+    RequestId,
+}
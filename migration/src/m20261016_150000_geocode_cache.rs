@@ -0,0 +1,50 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GeocodeCache::Table)
+                    .if_not_exists()
+                    .col(pk_auto(GeocodeCache::Id))
+                    .col(date_time(GeocodeCache::CreatedAt))
+                    .col(string(GeocodeCache::Provider))
+                    .col(string(GeocodeCache::Query))
+                    .col(text(GeocodeCache::Results))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_geocode_cache_provider_query_unique")
+                    .table(GeocodeCache::Table)
+                    .col(GeocodeCache::Provider)
+                    .col(GeocodeCache::Query)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GeocodeCache::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum GeocodeCache {
+    Table,
+    Id,
+    CreatedAt,
+    Provider,
+    Query,
+    Results,
+}
@@ -0,0 +1,98 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250316_204923_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(InboxAddress::Table)
+                    .if_not_exists()
+                    .col(pk_auto(InboxAddress::Id))
+                    .col(date_time(InboxAddress::CreatedAt))
+                    .col(integer_uniq(InboxAddress::UserId))
+                    .foreign_key(ForeignKey::create()
+                        .name(InboxAddress::UserId.to_string())
+                        .from(InboxAddress::Table, InboxAddress::UserId)
+                        .to(User::Table, User::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string_uniq(InboxAddress::Token))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(InboxItem::Table)
+                    .if_not_exists()
+                    .col(pk_auto(InboxItem::Id))
+                    .col(date_time(InboxItem::CreatedAt))
+                    .col(integer(InboxItem::UserId))
+                    .foreign_key(ForeignKey::create()
+                        .name(InboxItem::UserId.to_string())
+                        .from(InboxItem::Table, InboxItem::UserId)
+                        .to(User::Table, User::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string(InboxItem::MailFrom))
+                    .col(string(InboxItem::Subject))
+                    .col(text(InboxItem::Body))
+                    .col(date_time_null(InboxItem::JourneyDeparture))
+                    .col(string_null(InboxItem::LocationFrom))
+                    .col(string_null(InboxItem::LocationTo))
+                    .col(double_null(InboxItem::Amount))
+                    .col(string_null(InboxItem::Currency))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_inbox_item_user_id")
+                    .table(InboxItem::Table)
+                    .col(InboxItem::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(InboxItem::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(InboxAddress::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum InboxAddress {
+    Table,
+    Id,
+    CreatedAt,
+    UserId,
+    Token,
+}
+
+#[derive(DeriveIden)]
+pub enum InboxItem {
+    Table,
+    Id,
+    CreatedAt,
+    UserId,
+    MailFrom,
+    Subject,
+    Body,
+    JourneyDeparture,
+    LocationFrom,
+    LocationTo,
+    Amount,
+    Currency,
+}
@@ -0,0 +1,102 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250316_204923_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CaldavSettings::Table)
+                    .if_not_exists()
+                    .col(pk_auto(CaldavSettings::Id))
+                    .col(date_time(CaldavSettings::CreatedAt))
+                    .col(date_time(CaldavSettings::UpdatedAt))
+                    .col(integer_uniq(CaldavSettings::UserId))
+                    .foreign_key(ForeignKey::create()
+                        .name(CaldavSettings::UserId.to_string())
+                        .from(CaldavSettings::Table, CaldavSettings::UserId)
+                        .to(User::Table, User::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string(CaldavSettings::CalendarUrl))
+                    .col(string(CaldavSettings::Username))
+                    .col(string(CaldavSettings::Password))
+                    .col(boolean(CaldavSettings::Push))
+                    .col(boolean(CaldavSettings::Pull))
+                    .col(string(CaldavSettings::PullKeyword))
+                    .col(date_time_null(CaldavSettings::LastSyncedAt))
+                    .col(string_null(CaldavSettings::LastError))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(CaldavEvent::Table)
+                    .if_not_exists()
+                    .col(pk_auto(CaldavEvent::Id))
+                    .col(date_time(CaldavEvent::CreatedAt))
+                    .col(integer(CaldavEvent::UserId))
+                    .foreign_key(ForeignKey::create()
+                        .name(CaldavEvent::UserId.to_string())
+                        .from(CaldavEvent::Table, CaldavEvent::UserId)
+                        .to(User::Table, User::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string(CaldavEvent::Uid))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_caldav_event_user_id_uid_unique")
+                    .table(CaldavEvent::Table)
+                    .col(CaldavEvent::UserId)
+                    .col(CaldavEvent::Uid)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CaldavEvent::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(CaldavSettings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CaldavSettings {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    UserId,
+    CalendarUrl,
+    Username,
+    Password,
+    Push,
+    Pull,
+    PullKeyword,
+    LastSyncedAt,
+    LastError,
+}
+
+#[derive(DeriveIden)]
+pub enum CaldavEvent {
+    Table,
+    Id,
+    CreatedAt,
+    UserId,
+    Uid,
+}
@@ -0,0 +1,57 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250316_204923_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebdavTarget::Table)
+                    .if_not_exists()
+                    .col(pk_auto(WebdavTarget::Id))
+                    .col(date_time(WebdavTarget::CreatedAt))
+                    .col(date_time(WebdavTarget::UpdatedAt))
+                    .col(integer_uniq(WebdavTarget::UserId))
+                    .foreign_key(ForeignKey::create()
+                        .name(WebdavTarget::UserId.to_string())
+                        .from(WebdavTarget::Table, WebdavTarget::UserId)
+                        .to(User::Table, User::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string(WebdavTarget::FolderUrl))
+                    .col(string(WebdavTarget::Username))
+                    .col(string(WebdavTarget::PasswordEncrypted))
+                    .col(integer_null(WebdavTarget::IntervalDays))
+                    .col(date_time_null(WebdavTarget::LastUploadedAt))
+                    .col(string_null(WebdavTarget::LastError))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebdavTarget::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum WebdavTarget {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    UserId,
+    FolderUrl,
+    Username,
+    PasswordEncrypted,
+    IntervalDays,
+    LastUploadedAt,
+    LastError,
+}
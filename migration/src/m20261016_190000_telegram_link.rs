@@ -0,0 +1,47 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250316_204923_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TelegramLink::Table)
+                    .if_not_exists()
+                    .col(pk_auto(TelegramLink::Id))
+                    .col(date_time(TelegramLink::CreatedAt))
+                    .col(integer_uniq(TelegramLink::UserId))
+                    .foreign_key(ForeignKey::create()
+                        .name(TelegramLink::UserId.to_string())
+                        .from(TelegramLink::Table, TelegramLink::UserId)
+                        .to(User::Table, User::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string_uniq(TelegramLink::Code))
+                    .col(big_integer_null(TelegramLink::TelegramUserId).unique_key())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TelegramLink::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum TelegramLink {
+    Table,
+    Id,
+    CreatedAt,
+    UserId,
+    Code,
+    TelegramUserId,
+}
@@ -0,0 +1,79 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250323_195423_ride::Ride;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Ride::Table)
+                    .add_column(string(RideStatus::Status).default("completed"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(RideStatusChange::Table)
+                    .if_not_exists()
+                    .col(pk_auto(RideStatusChange::Id))
+                    .col(date_time(RideStatusChange::CreatedAt))
+                    .col(integer(RideStatusChange::RideId))
+                    .foreign_key(ForeignKey::create()
+                        .name(RideStatusChange::RideId.to_string())
+                        .from(RideStatusChange::Table, RideStatusChange::RideId)
+                        .to(Ride::Table, Ride::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string_null(RideStatusChange::FromStatus))
+                    .col(string(RideStatusChange::ToStatus))
+                    .col(string_null(RideStatusChange::Remarks))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ride_status_change_ride_id")
+                    .table(RideStatusChange::Table)
+                    .col(RideStatusChange::RideId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RideStatusChange::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Ride::Table)
+                    .drop_column(RideStatus::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RideStatus {
+    Status,
+}
+
+#[derive(DeriveIden)]
+pub enum RideStatusChange {
+    Table,
+    Id,
+    CreatedAt,
+    RideId,
+    FromStatus,
+    ToStatus,
+    Remarks,
+}
@@ -0,0 +1,62 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250323_195423_ride::Ride;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RideReference::Table)
+                    .if_not_exists()
+                    .col(pk_auto(RideReference::Id))
+                    .col(date_time(RideReference::CreatedAt))
+                    .col(date_time(RideReference::UpdatedAt))
+                    .col(date_time_null(RideReference::DeletedAt))
+                    .col(integer(RideReference::RideId))
+                    .foreign_key(ForeignKey::create()
+                        .name(RideReference::RideId.to_string())
+                        .from(RideReference::Table, RideReference::RideId)
+                        .to(Ride::Table, Ride::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string(RideReference::Kind))
+                    .col(string(RideReference::Value))
+                    .col(string_null(RideReference::Url))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ride_reference_ride_id")
+                    .table(RideReference::Table)
+                    .col(RideReference::RideId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RideReference::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum RideReference {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    DeletedAt,
+    RideId,
+    Kind,
+    Value,
+    Url,
+}
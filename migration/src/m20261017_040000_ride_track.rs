@@ -0,0 +1,65 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250323_195423_ride::Ride;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RideTrack::Table)
+                    .if_not_exists()
+                    .col(pk_auto(RideTrack::Id))
+                    .col(date_time(RideTrack::CreatedAt))
+                    .col(date_time(RideTrack::UpdatedAt))
+                    .col(integer(RideTrack::RideId))
+                    .foreign_key(ForeignKey::create()
+                        .name(RideTrack::RideId.to_string())
+                        .from(RideTrack::Table, RideTrack::RideId)
+                        .to(Ride::Table, Ride::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(text(RideTrack::Gpx))
+                    .col(integer(RideTrack::PointCount))
+                    .col(double(RideTrack::Distance))
+                    .col(date_time_null(RideTrack::StartedAt))
+                    .col(date_time_null(RideTrack::EndedAt))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ride_track_ride_id")
+                    .table(RideTrack::Table)
+                    .col(RideTrack::RideId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RideTrack::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum RideTrack {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    RideId,
+    Gpx,
+    PointCount,
+    Distance,
+    StartedAt,
+    EndedAt,
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250316_204923_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RideSummary::Table)
+                    .if_not_exists()
+                    .col(pk_auto(RideSummary::Id))
+                    .col(date_time(RideSummary::CreatedAt))
+                    .col(date_time(RideSummary::UpdatedAt))
+                    .col(integer(RideSummary::UserId))
+                    .foreign_key(ForeignKey::create()
+                        .name(RideSummary::UserId.to_string())
+                        .from(RideSummary::Table, RideSummary::UserId)
+                        .to(User::Table, User::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string(RideSummary::Month))
+                    .col(integer(RideSummary::RideCount))
+                    .col(double(RideSummary::TotalCost))
+                    .col(double(RideSummary::TotalDistance))
+                    .col(boolean(RideSummary::Stale))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ride_summary_user_id_month_unique")
+                    .table(RideSummary::Table)
+                    .col(RideSummary::UserId)
+                    .col(RideSummary::Month)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RideSummary::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum RideSummary {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    UserId,
+    Month,
+    RideCount,
+    TotalCost,
+    TotalDistance,
+    Stale,
+}
@@ -0,0 +1,85 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20261016_140000_audit_log::AuditLog;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite alters one column per statement
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuditLog::Table)
+                    .add_column(string_null(AuditChain::PrevHash))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuditLog::Table)
+                    .add_column(string_null(AuditChain::Hash))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuditLog::Table)
+                    .add_column(text_null(AuditChain::Signature))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuditLog::Table)
+                    .add_column(integer_null(AuditChain::ChainSeq))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_log_chain_seq_unique")
+                    .table(AuditLog::Table)
+                    .col(AuditChain::ChainSeq)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_audit_log_chain_seq_unique")
+                    .table(AuditLog::Table)
+                    .to_owned(),
+            )
+            .await?;
+        for column in [AuditChain::ChainSeq, AuditChain::Signature, AuditChain::Hash, AuditChain::PrevHash] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(AuditLog::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditChain {
+    PrevHash,
+    Hash,
+    Signature,
+    ChainSeq,
+}
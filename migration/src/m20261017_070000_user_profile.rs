@@ -0,0 +1,59 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20250316_204923_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite alters one column per statement
+        for column in [
+            UserProfile::Email,
+            UserProfile::AvatarUrl,
+            UserProfile::Theme,
+            UserProfile::Currency,
+            UserProfile::DistanceUnit,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(User::Table)
+                        .add_column(string_null(column))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            UserProfile::DistanceUnit,
+            UserProfile::Currency,
+            UserProfile::Theme,
+            UserProfile::AvatarUrl,
+            UserProfile::Email,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(User::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserProfile {
+    Email,
+    AvatarUrl,
+    Theme,
+    Currency,
+    DistanceUnit,
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20261017_040000_ride_track::RideTrack;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite alters one column per statement
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RideTrack::Table)
+                    .add_column(string(RideTrackScan::ScanStatus).default("unscanned"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RideTrack::Table)
+                    .add_column(string_null(RideTrackScan::ScanFinding))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ride_track_scan_status")
+                    .table(RideTrack::Table)
+                    .col(RideTrackScan::ScanStatus)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_ride_track_scan_status")
+                    .table(RideTrack::Table)
+                    .to_owned(),
+            )
+            .await?;
+        for column in [RideTrackScan::ScanFinding, RideTrackScan::ScanStatus] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(RideTrack::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum RideTrackScan {
+    ScanStatus,
+    ScanFinding,
+}
@@ -0,0 +1,187 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::error::Error;
+use clap::Subcommand;
+use sea_orm::{prelude::*, DatabaseConnection, TransactionTrait};
+use serde_json::json;
+use entity::{ride, ride_tag, tag_descriptor, tag_enum_option, user};
+use crate::fairings::cache_invalidation::{notify, Invalidation};
+use crate::model::ride::Ride;
+use crate::model::ride_summary::invalidate_all;
+use crate::model::tag::Tag;
+
+/// Origin of cache invalidations sent by admin commands, which no server ignores
+const NOTIFY_ORIGIN: &str = "admin";
+
+/// Maintenance commands working directly on the database
+#[derive(Subcommand)]
+pub enum AdminCommand {
+    /// List all users
+    ListUsers,
+    /// Export all data of a user as JSON
+    ExportUser {
+        /// User ID
+        user_id: u32,
+    },
+    /// Delete a user with all rides and tags, including soft-deleted ones
+    DeleteUser {
+        /// User ID
+        user_id: u32,
+        /// Confirm the deletion
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Count rides, tags and tag links of all users. The monthly ride summaries are
+    /// computed again on their next read, e.g. after changing the price or distance tag.
+    RecountStats,
+}
+
+fn map_err<E: ToString>(e: E) -> Box<dyn Error> {
+    From::from(e.to_string())
+}
+
+async fn find_user(user_id: u32, db: &impl ConnectionTrait) -> Result<user::Model, Box<dyn Error>> {
+    match user::Entity::find_by_id(user_id).one(db).await? {
+        Some(user) => Ok(user),
+        None => Err(From::from(format!("User {} not found", user_id))),
+    }
+}
+
+async fn list_users(db: &DatabaseConnection) -> Result<(), Box<dyn Error>> {
+    for user in user::Entity::find().all(db).await? {
+        println!(
+            "{}\t{}\t{}\t{}",
+            user.id,
+            user.jwt_issuer,
+            user.jwt_subject,
+            user.name.unwrap_or_default(),
+        );
+    }
+    Ok(())
+}
+
+async fn export_user(user_id: u32, db: &DatabaseConnection) -> Result<(), Box<dyn Error>> {
+    let user = find_user(user_id, db).await?;
+    let tags = Tag::find_all(user_id, true, false, db).await.map_err(map_err)?;
+    let rides = Ride::find_all(user_id, true, false, db).await.map_err(map_err)?;
+
+    let export = json!({
+        "user": {
+            "id": user.id,
+            "jwt_issuer": user.jwt_issuer,
+            "jwt_subject": user.jwt_subject,
+            "name": user.name,
+            "retention_days": user.retention_days,
+            "email": user.email,
+            "avatar_url": user.avatar_url,
+        },
+        "tags": tags,
+        "rides": rides,
+    });
+    println!("{}", serde_json::to_string_pretty(&export)?);
+    Ok(())
+}
+
+async fn delete_user(user_id: u32, yes: bool, db: &DatabaseConnection) -> Result<(), Box<dyn Error>> {
+    let user = find_user(user_id, db).await?;
+    if !yes {
+        Err(format!("Refusing to delete user {} ({}) without --yes", user.id, user.jwt_subject))?;
+    }
+
+    // Rides and tags restrict the deletion of their user, so they are deleted first. Their
+    // ride tags and options, and all other data of the user, are deleted by the database
+    // with ON DELETE CASCADE, which the connection also enables on SQLite. Ride tags and
+    // options are only counted for the summary.
+    let txn = db.begin().await?;
+    let links = ride_tag::Entity::find()
+        .inner_join(ride::Entity)
+        .filter(ride::Column::UserId.eq(user_id))
+        .count(&txn)
+        .await?;
+    let options = tag_enum_option::Entity::find()
+        .inner_join(tag_descriptor::Entity)
+        .filter(tag_descriptor::Column::UserId.eq(user_id))
+        .count(&txn)
+        .await?;
+    let rides = ride::Entity::delete_many()
+        .filter(ride::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    let tags = tag_descriptor::Entity::delete_many()
+        .filter(tag_descriptor::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    user::Entity::delete_by_id(user_id).exec(&txn).await?;
+    // Running servers drop the user from their caches on commit, if on Postgres
+    let user_token = Invalidation::User {
+        issuer: user.jwt_issuer.clone(),
+        subject: user.jwt_subject.clone(),
+    };
+    notify(user_token, NOTIFY_ORIGIN, &txn).await?;
+    notify(Invalidation::Tag { user_id }, NOTIFY_ORIGIN, &txn).await?;
+    txn.commit().await?;
+
+    println!(
+        "Deleted user {} with {} rides, {} tag links, {} tags and {} tag options",
+        user_id,
+        rides.rows_affected,
+        links,
+        tags.rows_affected,
+        options,
+    );
+    Ok(())
+}
+
+async fn recount_stats(db: &DatabaseConnection) -> Result<(), Box<dyn Error>> {
+    println!("user_id\trides\tdeleted_rides\ttemplates\ttags\ttag_links");
+    for user in user::Entity::find().all(db).await? {
+        let rides = ride::Entity::find()
+            .filter(ride::Column::UserId.eq(user.id))
+            .filter(ride::Column::DeletedAt.is_null())
+            .filter(ride::Column::IsTemplate.eq(false))
+            .count(db)
+            .await?;
+        let deleted_rides = ride::Entity::find()
+            .filter(ride::Column::UserId.eq(user.id))
+            .filter(ride::Column::DeletedAt.is_not_null())
+            .count(db)
+            .await?;
+        let templates = ride::Entity::find()
+            .filter(ride::Column::UserId.eq(user.id))
+            .filter(ride::Column::DeletedAt.is_null())
+            .filter(ride::Column::IsTemplate.eq(true))
+            .count(db)
+            .await?;
+        let tags = tag_descriptor::Entity::find()
+            .filter(tag_descriptor::Column::UserId.eq(user.id))
+            .filter(tag_descriptor::Column::DeletedAt.is_null())
+            .count(db)
+            .await?;
+        let tag_links = ride_tag::Entity::find()
+            .inner_join(ride::Entity)
+            .filter(ride::Column::UserId.eq(user.id))
+            .filter(ride::Column::DeletedAt.is_null())
+            .filter(ride_tag::Column::DeletedAt.is_null())
+            .count(db)
+            .await?;
+        println!("{}\t{}\t{}\t{}\t{}\t{}", user.id, rides, deleted_rides, templates, tags, tag_links);
+    }
+    invalidate_all(db).await.map_err(map_err)?;
+    Ok(())
+}
+
+/// Execute admin [command]
+pub async fn run(command: &AdminCommand, db: &DatabaseConnection) -> Result<(), Box<dyn Error>> {
+    match command {
+        AdminCommand::ListUsers => list_users(db).await,
+        AdminCommand::ExportUser { user_id } => export_user(*user_id, db).await,
+        AdminCommand::DeleteUser { user_id, yes } => delete_user(*user_id, *yes, db).await,
+        AdminCommand::RecountStats => recount_stats(db).await,
+    }
+}
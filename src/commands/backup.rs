@@ -0,0 +1,786 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Backup and restore of the full dataset as JSON Lines archive
+//!
+//! The first line is a header with format and schema version. Every following line
+//! holds one row as `{"table": ..., "row": ...}`. Tables are written parents first,
+//! so a restore can insert the rows in file order. IDs, UUIDs and soft-delete
+//! timestamps are kept, so the archive can be restored into another database engine.
+//!
+//! Caches and derived data are left out and rebuilt after a restore: `geocode_cache`
+//! fills again on lookups, `ride_summary` is computed again on the next read, and
+//! `idempotency_key` only protects retries of recent requests. `server_setting` holds
+//! the runtime state of the running server, e.g. the maintenance mode enabled while
+//! the backup is taken, and is not carried over.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use sea_orm::{
+    prelude::*,
+    ConnectionTrait,
+    DatabaseBackend,
+    DatabaseConnection,
+    DatabaseTransaction,
+    IntoActiveModel,
+    QueryOrder,
+    Statement,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use entity::{
+    audit_log,
+    caldav_event,
+    caldav_settings,
+    inbox_address,
+    inbox_item,
+    ride,
+    ride_reference,
+    ride_status_change,
+    ride_tag,
+    ride_track,
+    tag_descriptor,
+    tag_enum_option,
+    telegram_link,
+    user,
+    webdav_target,
+    webhook,
+    webhook_delivery,
+};
+use migration::{Migrator, MigratorTrait};
+
+/// Identifier of the archive format
+const FORMAT: &str = "ptet-backup";
+/// Version of the archive format
+const FORMAT_VERSION: u32 = 1;
+/// Rows fetched from the database at once
+const PAGE_SIZE: u64 = 500;
+/// Tables in the order they are written and restored
+const TABLES: &[&str] = &[
+    "user",
+    "tag_descriptor",
+    "tag_enum_option",
+    "ride",
+    "ride_tag",
+    "webhook",
+    "webhook_delivery",
+    "audit_log",
+    "inbox_address",
+    "inbox_item",
+    "caldav_settings",
+    "caldav_event",
+    "webdav_target",
+    "telegram_link",
+    "ride_status_change",
+    "ride_reference",
+    "ride_track",
+];
+
+/// Archive header
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+    /// Name of the latest migration of the database the archive was made from
+    schema: String,
+    created_at: DateTimeUtc,
+}
+
+/// Row of the user table. The entity model does not deserialize all fields, because
+/// it is exposed in the API.
+#[derive(Serialize, Deserialize)]
+struct UserRow {
+    id: u32,
+    jwt_issuer: String,
+    jwt_subject: String,
+    name: Option<String>,
+    #[serde(default)]
+    retention_days: Option<u32>,
+    #[serde(default)]
+    locale: Option<user::Locale>,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    avatar_url: Option<String>,
+    #[serde(default)]
+    theme: Option<user::Theme>,
+    #[serde(default)]
+    currency: Option<String>,
+    #[serde(default)]
+    distance_unit: Option<user::DistanceUnit>,
+}
+
+impl From<user::Model> for UserRow {
+    fn from(model: user::Model) -> Self {
+        Self {
+            id: model.id,
+            jwt_issuer: model.jwt_issuer,
+            jwt_subject: model.jwt_subject,
+            name: model.name,
+            retention_days: model.retention_days,
+            locale: model.locale,
+            email: model.email,
+            avatar_url: model.avatar_url,
+            theme: model.theme,
+            currency: model.currency,
+            distance_unit: model.distance_unit,
+        }
+    }
+}
+
+impl From<UserRow> for user::Model {
+    fn from(row: UserRow) -> Self {
+        Self {
+            id: row.id,
+            jwt_issuer: row.jwt_issuer,
+            jwt_subject: row.jwt_subject,
+            name: row.name,
+            retention_days: row.retention_days,
+            locale: row.locale,
+            email: row.email,
+            avatar_url: row.avatar_url,
+            theme: row.theme,
+            currency: row.currency,
+            distance_unit: row.distance_unit,
+        }
+    }
+}
+
+/// Row of the webhook_delivery table
+#[derive(Serialize, Deserialize)]
+struct WebhookDeliveryRow {
+    id: u32,
+    created_at: DateTimeUtc,
+    webhook_id: u32,
+    event_type: String,
+    payload: String,
+    state: webhook_delivery::DeliveryState,
+    attempts: u32,
+    next_attempt_at: Option<DateTimeUtc>,
+    delivered_at: Option<DateTimeUtc>,
+    response_status: Option<u32>,
+    error: Option<String>,
+}
+
+impl From<webhook_delivery::Model> for WebhookDeliveryRow {
+    fn from(model: webhook_delivery::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            webhook_id: model.webhook_id,
+            event_type: model.event_type,
+            payload: model.payload,
+            state: model.state,
+            attempts: model.attempts,
+            next_attempt_at: model.next_attempt_at,
+            delivered_at: model.delivered_at,
+            response_status: model.response_status,
+            error: model.error,
+        }
+    }
+}
+
+impl From<WebhookDeliveryRow> for webhook_delivery::Model {
+    fn from(row: WebhookDeliveryRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            webhook_id: row.webhook_id,
+            event_type: row.event_type,
+            payload: row.payload,
+            state: row.state,
+            attempts: row.attempts,
+            next_attempt_at: row.next_attempt_at,
+            delivered_at: row.delivered_at,
+            response_status: row.response_status,
+            error: row.error,
+        }
+    }
+}
+
+/// Row of the audit_log table. IDs, hashes, signatures and chain positions are kept, so
+/// that the chain can still be verified after a restore.
+#[derive(Serialize, Deserialize)]
+struct AuditLogRow {
+    id: u32,
+    created_at: DateTimeUtc,
+    user_id: Option<u32>,
+    resource: String,
+    resource_id: u32,
+    action: String,
+    diff: String,
+    request_id: Option<String>,
+    prev_hash: Option<String>,
+    hash: Option<String>,
+    signature: Option<String>,
+    chain_seq: Option<u32>,
+}
+
+impl From<audit_log::Model> for AuditLogRow {
+    fn from(model: audit_log::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            user_id: model.user_id,
+            resource: model.resource,
+            resource_id: model.resource_id,
+            action: model.action,
+            diff: model.diff,
+            request_id: model.request_id,
+            prev_hash: model.prev_hash,
+            hash: model.hash,
+            signature: model.signature,
+            chain_seq: model.chain_seq,
+        }
+    }
+}
+
+impl From<AuditLogRow> for audit_log::Model {
+    fn from(row: AuditLogRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            user_id: row.user_id,
+            resource: row.resource,
+            resource_id: row.resource_id,
+            action: row.action,
+            diff: row.diff,
+            request_id: row.request_id,
+            prev_hash: row.prev_hash,
+            hash: row.hash,
+            signature: row.signature,
+            chain_seq: row.chain_seq,
+        }
+    }
+}
+
+/// Row of the inbox_address table
+#[derive(Serialize, Deserialize)]
+struct InboxAddressRow {
+    id: u32,
+    created_at: DateTimeUtc,
+    user_id: u32,
+    token: String,
+}
+
+impl From<inbox_address::Model> for InboxAddressRow {
+    fn from(model: inbox_address::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            user_id: model.user_id,
+            token: model.token,
+        }
+    }
+}
+
+impl From<InboxAddressRow> for inbox_address::Model {
+    fn from(row: InboxAddressRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            user_id: row.user_id,
+            token: row.token,
+        }
+    }
+}
+
+/// Row of the inbox_item table
+#[derive(Serialize, Deserialize)]
+struct InboxItemRow {
+    id: u32,
+    created_at: DateTimeUtc,
+    user_id: u32,
+    mail_from: String,
+    subject: String,
+    body: String,
+    journey_departure: Option<DateTimeUtc>,
+    location_from: Option<String>,
+    location_to: Option<String>,
+    amount: Option<f64>,
+    currency: Option<String>,
+}
+
+impl From<inbox_item::Model> for InboxItemRow {
+    fn from(model: inbox_item::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            user_id: model.user_id,
+            mail_from: model.mail_from,
+            subject: model.subject,
+            body: model.body,
+            journey_departure: model.journey_departure,
+            location_from: model.location_from,
+            location_to: model.location_to,
+            amount: model.amount,
+            currency: model.currency,
+        }
+    }
+}
+
+impl From<InboxItemRow> for inbox_item::Model {
+    fn from(row: InboxItemRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            user_id: row.user_id,
+            mail_from: row.mail_from,
+            subject: row.subject,
+            body: row.body,
+            journey_departure: row.journey_departure,
+            location_from: row.location_from,
+            location_to: row.location_to,
+            amount: row.amount,
+            currency: row.currency,
+        }
+    }
+}
+
+/// Row of the caldav_settings table
+#[derive(Serialize, Deserialize)]
+struct CaldavSettingsRow {
+    id: u32,
+    created_at: DateTimeUtc,
+    updated_at: DateTimeUtc,
+    user_id: u32,
+    calendar_url: String,
+    username: String,
+    password: String,
+    push: bool,
+    pull: bool,
+    pull_keyword: String,
+    last_synced_at: Option<DateTimeUtc>,
+    last_error: Option<String>,
+}
+
+impl From<caldav_settings::Model> for CaldavSettingsRow {
+    fn from(model: caldav_settings::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            user_id: model.user_id,
+            calendar_url: model.calendar_url,
+            username: model.username,
+            password: model.password,
+            push: model.push,
+            pull: model.pull,
+            pull_keyword: model.pull_keyword,
+            last_synced_at: model.last_synced_at,
+            last_error: model.last_error,
+        }
+    }
+}
+
+impl From<CaldavSettingsRow> for caldav_settings::Model {
+    fn from(row: CaldavSettingsRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            user_id: row.user_id,
+            calendar_url: row.calendar_url,
+            username: row.username,
+            password: row.password,
+            push: row.push,
+            pull: row.pull,
+            pull_keyword: row.pull_keyword,
+            last_synced_at: row.last_synced_at,
+            last_error: row.last_error,
+        }
+    }
+}
+
+/// Row of the caldav_event table
+#[derive(Serialize, Deserialize)]
+struct CaldavEventRow {
+    id: u32,
+    created_at: DateTimeUtc,
+    user_id: u32,
+    uid: String,
+}
+
+impl From<caldav_event::Model> for CaldavEventRow {
+    fn from(model: caldav_event::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            user_id: model.user_id,
+            uid: model.uid,
+        }
+    }
+}
+
+impl From<CaldavEventRow> for caldav_event::Model {
+    fn from(row: CaldavEventRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            user_id: row.user_id,
+            uid: row.uid,
+        }
+    }
+}
+
+/// Row of the webdav_target table. The password stays encrypted, so the restored
+/// instance needs the same `credentials_key`.
+#[derive(Serialize, Deserialize)]
+struct WebdavTargetRow {
+    id: u32,
+    created_at: DateTimeUtc,
+    updated_at: DateTimeUtc,
+    user_id: u32,
+    folder_url: String,
+    username: String,
+    password_encrypted: String,
+    interval_days: Option<u32>,
+    last_uploaded_at: Option<DateTimeUtc>,
+    last_error: Option<String>,
+}
+
+impl From<webdav_target::Model> for WebdavTargetRow {
+    fn from(model: webdav_target::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            user_id: model.user_id,
+            folder_url: model.folder_url,
+            username: model.username,
+            password_encrypted: model.password_encrypted,
+            interval_days: model.interval_days,
+            last_uploaded_at: model.last_uploaded_at,
+            last_error: model.last_error,
+        }
+    }
+}
+
+impl From<WebdavTargetRow> for webdav_target::Model {
+    fn from(row: WebdavTargetRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            user_id: row.user_id,
+            folder_url: row.folder_url,
+            username: row.username,
+            password_encrypted: row.password_encrypted,
+            interval_days: row.interval_days,
+            last_uploaded_at: row.last_uploaded_at,
+            last_error: row.last_error,
+        }
+    }
+}
+
+/// Row of the telegram_link table
+#[derive(Serialize, Deserialize)]
+struct TelegramLinkRow {
+    id: u32,
+    created_at: DateTimeUtc,
+    user_id: u32,
+    code: String,
+    telegram_user_id: Option<i64>,
+}
+
+impl From<telegram_link::Model> for TelegramLinkRow {
+    fn from(model: telegram_link::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            user_id: model.user_id,
+            code: model.code,
+            telegram_user_id: model.telegram_user_id,
+        }
+    }
+}
+
+impl From<TelegramLinkRow> for telegram_link::Model {
+    fn from(row: TelegramLinkRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            user_id: row.user_id,
+            code: row.code,
+            telegram_user_id: row.telegram_user_id,
+        }
+    }
+}
+
+/// Row of the ride_status_change table
+#[derive(Serialize, Deserialize)]
+struct RideStatusChangeRow {
+    id: u32,
+    created_at: DateTimeUtc,
+    ride_id: u32,
+    from_status: Option<ride::RideStatus>,
+    to_status: ride::RideStatus,
+    remarks: Option<String>,
+}
+
+impl From<ride_status_change::Model> for RideStatusChangeRow {
+    fn from(model: ride_status_change::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            ride_id: model.ride_id,
+            from_status: model.from_status,
+            to_status: model.to_status,
+            remarks: model.remarks,
+        }
+    }
+}
+
+impl From<RideStatusChangeRow> for ride_status_change::Model {
+    fn from(row: RideStatusChangeRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            ride_id: row.ride_id,
+            from_status: row.from_status,
+            to_status: row.to_status,
+            remarks: row.remarks,
+        }
+    }
+}
+
+/// Row of the ride_track table. The entity model does not serialize the GPX document.
+#[derive(Serialize, Deserialize)]
+struct RideTrackRow {
+    id: u32,
+    created_at: DateTimeUtc,
+    updated_at: DateTimeUtc,
+    ride_id: u32,
+    gpx: String,
+    point_count: u32,
+    distance: f64,
+    started_at: Option<DateTimeUtc>,
+    ended_at: Option<DateTimeUtc>,
+    scan_status: ride_track::ScanStatus,
+    scan_finding: Option<String>,
+}
+
+impl From<ride_track::Model> for RideTrackRow {
+    fn from(model: ride_track::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            ride_id: model.ride_id,
+            gpx: model.gpx,
+            point_count: model.point_count,
+            distance: model.distance,
+            started_at: model.started_at,
+            ended_at: model.ended_at,
+            scan_status: model.scan_status,
+            scan_finding: model.scan_finding,
+        }
+    }
+}
+
+impl From<RideTrackRow> for ride_track::Model {
+    fn from(row: RideTrackRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            ride_id: row.ride_id,
+            gpx: row.gpx,
+            point_count: row.point_count,
+            distance: row.distance,
+            started_at: row.started_at,
+            ended_at: row.ended_at,
+            scan_status: row.scan_status,
+            scan_finding: row.scan_finding,
+        }
+    }
+}
+
+/// Name of the latest migration known to this binary
+fn schema_version() -> String {
+    match Migrator::migrations().last() {
+        Some(migration) => migration.name().to_string(),
+        None => String::new(),
+    }
+}
+
+/// Write all rows of entity [E] ordered by [id_column], converted to [R]
+async fn dump_table<E, C, R>(
+    table: &str,
+    id_column: C,
+    out: &mut impl Write,
+    db: &DatabaseConnection,
+) -> Result<u64, Box<dyn Error>>
+where
+    E: EntityTrait,
+    C: ColumnTrait,
+    E::Model: Sync,
+    R: From<E::Model> + Serialize,
+{
+    let mut pages = E::find()
+        .order_by_asc(id_column)
+        .paginate(db, PAGE_SIZE);
+    let mut count = 0;
+    while let Some(models) = pages.fetch_and_next().await? {
+        for model in models {
+            let line = json!({
+                "table": table,
+                "row": R::from(model),
+            });
+            writeln!(out, "{}", serde_json::to_string(&line)?)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Write the full dataset to [output], or stdout if [output] is None
+pub async fn backup(output: Option<&Path>, db: &DatabaseConnection) -> Result<(), Box<dyn Error>> {
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+
+    let header = Header {
+        format: FORMAT.to_string(),
+        version: FORMAT_VERSION,
+        schema: schema_version(),
+        created_at: chrono::Utc::now(),
+    };
+    writeln!(out, "{}", serde_json::to_string(&header)?)?;
+
+    let mut counts = Vec::with_capacity(TABLES.len());
+    counts.push(dump_table::<user::Entity, _, UserRow>("user", user::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<tag_descriptor::Entity, _, tag_descriptor::Model>("tag_descriptor", tag_descriptor::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<tag_enum_option::Entity, _, tag_enum_option::Model>("tag_enum_option", tag_enum_option::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<ride::Entity, _, ride::Model>("ride", ride::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<ride_tag::Entity, _, ride_tag::Model>("ride_tag", ride_tag::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<webhook::Entity, _, webhook::Model>("webhook", webhook::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<webhook_delivery::Entity, _, WebhookDeliveryRow>("webhook_delivery", webhook_delivery::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<audit_log::Entity, _, AuditLogRow>("audit_log", audit_log::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<inbox_address::Entity, _, InboxAddressRow>("inbox_address", inbox_address::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<inbox_item::Entity, _, InboxItemRow>("inbox_item", inbox_item::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<caldav_settings::Entity, _, CaldavSettingsRow>("caldav_settings", caldav_settings::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<caldav_event::Entity, _, CaldavEventRow>("caldav_event", caldav_event::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<webdav_target::Entity, _, WebdavTargetRow>("webdav_target", webdav_target::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<telegram_link::Entity, _, TelegramLinkRow>("telegram_link", telegram_link::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<ride_status_change::Entity, _, RideStatusChangeRow>("ride_status_change", ride_status_change::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<ride_reference::Entity, _, ride_reference::Model>("ride_reference", ride_reference::Column::Id, &mut out, db).await?);
+    counts.push(dump_table::<ride_track::Entity, _, RideTrackRow>("ride_track", ride_track::Column::Id, &mut out, db).await?);
+    out.flush()?;
+
+    for (table, count) in TABLES.iter().zip(counts) {
+        eprintln!("{}: {} rows", table, count);
+    }
+    Ok(())
+}
+
+/// Insert [row] deserialized as [R] into entity [E]
+async fn restore_row<E, R>(row: serde_json::Value, txn: &DatabaseTransaction) -> Result<(), Box<dyn Error>>
+where
+    E: EntityTrait,
+    R: Into<E::Model> + DeserializeOwned,
+    E::Model: IntoActiveModel<E::ActiveModel>,
+{
+    let model: E::Model = serde_json::from_value::<R>(row)?.into();
+    E::insert(model.into_active_model()).exec(txn).await?;
+    Ok(())
+}
+
+/// Advance ID sequences past the restored IDs. Only PostgreSQL uses sequences that are
+/// not updated by inserts with explicit IDs.
+async fn reset_sequences(txn: &DatabaseTransaction) -> Result<(), Box<dyn Error>> {
+    if txn.get_database_backend() == DatabaseBackend::Postgres {
+        for table in TABLES {
+            txn.execute(
+                Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    format!(
+                        "SELECT setval(pg_get_serial_sequence('\"{table}\"', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM \"{table}\"",
+                        table = table,
+                    ),
+                )
+            ).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Load the full dataset from [input], or stdin if [input] is None. The database must
+/// be empty.
+pub async fn restore(input: Option<&Path>, db: &DatabaseConnection) -> Result<(), Box<dyn Error>> {
+    let reader: Box<dyn BufRead> = match input {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+    let mut lines = reader.lines();
+
+    let header: Header = match lines.next() {
+        Some(line) => serde_json::from_str(&line?)?,
+        None => Err("Archive is empty")?,
+    };
+    if header.format != FORMAT || header.version != FORMAT_VERSION {
+        Err(format!("Unsupported archive format {} version {}", header.format, header.version))?;
+    }
+    if header.schema != schema_version() {
+        Err(format!("Archive has schema {}, but this version expects {}", header.schema, schema_version()))?;
+    }
+
+    if user::Entity::find().count(db).await? > 0 {
+        Err("Database is not empty, restore needs an empty database")?;
+    }
+
+    let txn = db.begin().await?;
+    let mut counts = vec![0u64; TABLES.len()];
+    for (line_no, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut item: serde_json::Value = serde_json::from_str(&line)?;
+        let row = item["row"].take();
+        let table = match item["table"].as_str() {
+            Some(table) => table,
+            None => Err(format!("Line {} has no table", line_no + 2))?,
+        };
+        match table {
+            "user" => restore_row::<user::Entity, UserRow>(row, &txn).await?,
+            "tag_descriptor" => restore_row::<tag_descriptor::Entity, tag_descriptor::Model>(row, &txn).await?,
+            "tag_enum_option" => restore_row::<tag_enum_option::Entity, tag_enum_option::Model>(row, &txn).await?,
+            "ride" => restore_row::<ride::Entity, ride::Model>(row, &txn).await?,
+            "ride_tag" => restore_row::<ride_tag::Entity, ride_tag::Model>(row, &txn).await?,
+            "webhook" => restore_row::<webhook::Entity, webhook::Model>(row, &txn).await?,
+            "webhook_delivery" => restore_row::<webhook_delivery::Entity, WebhookDeliveryRow>(row, &txn).await?,
+            "audit_log" => restore_row::<audit_log::Entity, AuditLogRow>(row, &txn).await?,
+            "inbox_address" => restore_row::<inbox_address::Entity, InboxAddressRow>(row, &txn).await?,
+            "inbox_item" => restore_row::<inbox_item::Entity, InboxItemRow>(row, &txn).await?,
+            "caldav_settings" => restore_row::<caldav_settings::Entity, CaldavSettingsRow>(row, &txn).await?,
+            "caldav_event" => restore_row::<caldav_event::Entity, CaldavEventRow>(row, &txn).await?,
+            "webdav_target" => restore_row::<webdav_target::Entity, WebdavTargetRow>(row, &txn).await?,
+            "telegram_link" => restore_row::<telegram_link::Entity, TelegramLinkRow>(row, &txn).await?,
+            "ride_status_change" => restore_row::<ride_status_change::Entity, RideStatusChangeRow>(row, &txn).await?,
+            "ride_reference" => restore_row::<ride_reference::Entity, ride_reference::Model>(row, &txn).await?,
+            "ride_track" => restore_row::<ride_track::Entity, RideTrackRow>(row, &txn).await?,
+            table => Err(format!("Line {} has unknown table {}", line_no + 2, table))?,
+        }
+        if let Some(index) = TABLES.iter().position(|t| *t == table) {
+            counts[index] += 1;
+        }
+    }
+    reset_sequences(&txn).await?;
+    txn.commit().await?;
+
+    for (table, count) in TABLES.iter().zip(counts) {
+        eprintln!("{}: {} rows", table, count);
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::error::Error;
+use clap::Subcommand;
+use sea_orm::DatabaseConnection;
+use migration::{Migrator, MigratorTrait};
+
+/// Schema migration commands
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+    /// Apply pending migrations
+    Up {
+        /// Number of migrations to apply, all if omitted
+        #[arg(short, long)]
+        steps: Option<u32>,
+    },
+    /// Roll back applied migrations
+    Down {
+        /// Number of migrations to roll back
+        #[arg(short, long, default_value = "1")]
+        steps: u32,
+    },
+    /// Show applied and pending migrations
+    Status,
+    /// Drop all tables and apply all migrations. All data is lost.
+    Fresh {
+        /// Confirm dropping all data
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+/// Apply pending migrations if [auto_migrate] is set. Otherwise, fail if there are
+/// pending migrations.
+pub async fn prepare_schema(db: &DatabaseConnection, auto_migrate: bool) -> Result<(), Box<dyn Error>> {
+    if auto_migrate {
+        Migrator::up(db, None).await?;
+    } else {
+        let pending = Migrator::get_pending_migrations(db).await?;
+        if !pending.is_empty() {
+            Err(format!("Database has {} pending migrations, run `migrate up` first", pending.len()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Execute migration [command]
+pub async fn run(command: &MigrateCommand, db: &DatabaseConnection) -> Result<(), Box<dyn Error>> {
+    match command {
+        MigrateCommand::Up { steps } => {
+            let pending = Migrator::get_pending_migrations(db).await?.len();
+            Migrator::up(db, *steps).await?;
+            println!("Applied {} migrations", steps.map_or(pending, |steps| pending.min(steps as usize)));
+        },
+        MigrateCommand::Down { steps } => {
+            Migrator::down(db, Some(*steps)).await?;
+        },
+        MigrateCommand::Status => {
+            for migration in Migrator::get_applied_migrations(db).await? {
+                println!("{}\tapplied", migration.name());
+            }
+            for migration in Migrator::get_pending_migrations(db).await? {
+                println!("{}\tpending", migration.name());
+            }
+        },
+        MigrateCommand::Fresh { yes } => {
+            if !yes {
+                Err("Refusing to drop all tables without --yes")?;
+            }
+            Migrator::fresh(db).await?;
+        },
+    }
+    Ok(())
+}
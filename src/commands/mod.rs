@@ -0,0 +1,28 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Subcommands of the server binary which work on the database without running the
+//! HTTP server.
+
+pub mod admin;
+pub mod backup;
+pub mod migrate;
+pub mod seed;
+
+use std::error::Error;
+use sea_orm::DatabaseConnection;
+use crate::config::DatabaseConfig;
+use crate::fairings::db;
+
+/// Connect to database and bring the schema up to date, or check that it is, depending
+/// on [config]. An unreachable database is retried according to its connect policy.
+pub async fn connect(config: &DatabaseConfig) -> Result<DatabaseConnection, Box<dyn Error>> {
+    let conn = db::connect(&config.database, "database", &config.connect_policy()).await?;
+    migrate::prepare_schema(&conn, config.auto_migrate).await?;
+    Ok(conn)
+}
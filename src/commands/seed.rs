@@ -0,0 +1,247 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::error::Error;
+use chrono::{DateTime, Datelike, TimeDelta, Utc, Weekday};
+use clap::Args;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use sea_orm::{prelude::*, DatabaseConnection, Set, TransactionTrait};
+use entity::tag_descriptor::TagType;
+use entity::user::{Entity as UserEntity, Column as UserColumn, ActiveModel as UserActiveModel};
+use crate::model::{ride, ride_tag_link, tag, tag_option};
+use crate::model::audit::Actor;
+use crate::model::ride_tag_link::Value;
+
+/// Stations used for the demo rides
+const STATIONS: [&str; 8] = [
+    "Hauptbahnhof",
+    "Alexanderplatz",
+    "Ostkreuz",
+    "Zoologischer Garten",
+    "Potsdamer Platz",
+    "Friedrichstraße",
+    "Südkreuz",
+    "Gesundbrunnen",
+];
+
+/// Lines used for the demo rides
+const LINES: [&str; 6] = ["S1", "S5", "S41", "U2", "U8", "RE1"];
+
+/// Arguments of the seed command
+#[derive(Args)]
+pub struct SeedArgs {
+    /// Seed of the random generator. The same seed produces the same rides and tags.
+    #[arg(long, default_value = "0")]
+    seed: u64,
+    /// JWT issuer of the demo user
+    #[arg(long, default_value = "demo")]
+    issuer: String,
+    /// JWT subject of the demo user
+    #[arg(long, default_value = "demo")]
+    subject: String,
+    /// Number of rides to create
+    #[arg(long, default_value = "100")]
+    rides: u32,
+    /// Day of the first ride
+    #[arg(long, default_value = "2025-01-06T00:00:00Z")]
+    start: DateTime<Utc>,
+}
+
+/// IDs of the demo tags and their options
+struct DemoTags {
+    price: u32,
+    distance: u32,
+    line: u32,
+    operator: u32,
+    operator_options: Vec<u32>,
+    ticket: u32,
+    ticket_options: Vec<u32>,
+}
+
+fn map_err<E: ToString>(e: E) -> Box<dyn Error> {
+    From::from(e.to_string())
+}
+
+/// Create an enum tag with [options] given as (value, name)
+async fn insert_enum_tag(
+    user_id: u32,
+    tag_key: &str,
+    tag_name: &str,
+    options: &[(&str, &str)],
+    db: &impl ConnectionTrait,
+) -> Result<(u32, Vec<u32>), Box<dyn Error>> {
+    let tag = tag::CreateUpdateBuilder::new(
+        TagType::Enum,
+        tag_key.to_string(),
+        Some(tag_name.to_string()),
+        None,
+        None,
+    ).insert(user_id, &Actor::default(), db).await.map_err(map_err)?;
+
+    let mut option_ids = Vec::with_capacity(options.len());
+    for (order, (value, name)) in options.iter().enumerate() {
+        let option = tag_option::CreateUpdateBuilder::new(
+            order as u32,
+            value.to_string(),
+            Some(name.to_string()),
+        ).insert(tag.id(), &Actor::default(), db).await.map_err(map_err)?;
+        option_ids.push(option.id());
+    }
+    Ok((tag.id(), option_ids))
+}
+
+/// Create a plain tag of [tag_type]
+async fn insert_tag(
+    user_id: u32,
+    tag_type: TagType,
+    tag_key: &str,
+    tag_name: &str,
+    unit: Option<&str>,
+    db: &impl ConnectionTrait,
+) -> Result<u32, Box<dyn Error>> {
+    let tag = tag::CreateUpdateBuilder::new(
+        tag_type,
+        tag_key.to_string(),
+        Some(tag_name.to_string()),
+        unit.map(String::from),
+        None,
+    ).insert(user_id, &Actor::default(), db).await.map_err(map_err)?;
+    Ok(tag.id())
+}
+
+async fn insert_tags(user_id: u32, db: &impl ConnectionTrait) -> Result<DemoTags, Box<dyn Error>> {
+    let price = insert_tag(user_id, TagType::Float, "price", "Price", Some("EUR"), db).await?;
+    let distance = insert_tag(user_id, TagType::Integer, "distance", "Distance", Some("km"), db).await?;
+    let line = insert_tag(user_id, TagType::String, "line", "Line", None, db).await?;
+    let (operator, operator_options) = insert_enum_tag(
+        user_id,
+        "operator",
+        "Operator",
+        &[("sbahn", "S-Bahn"), ("ubahn", "U-Bahn"), ("regional", "Regional train")],
+        db,
+    ).await?;
+    let (ticket, ticket_options) = insert_enum_tag(
+        user_id,
+        "ticket",
+        "Ticket",
+        &[("single", "Single ticket"), ("day", "Day ticket"), ("monthly", "Monthly pass")],
+        db,
+    ).await?;
+
+    Ok(
+        DemoTags {
+            price,
+            distance,
+            line,
+            operator,
+            operator_options,
+            ticket,
+            ticket_options,
+        }
+    )
+}
+
+/// Insert a ride with all demo tags linked
+async fn insert_ride(
+    user_id: u32,
+    tags: &DemoTags,
+    departure: DateTime<Utc>,
+    is_template: bool,
+    rng: &mut StdRng,
+    db: &impl ConnectionTrait,
+) -> Result<(), Box<dyn Error>> {
+    let from = *STATIONS.choose(rng).unwrap();
+    let to = *STATIONS.iter().filter(|station| **station != from).collect::<Vec<_>>().choose(rng).unwrap();
+    let arrival = departure + TimeDelta::minutes(rng.random_range(10..60));
+
+    let ride = ride::CreateUpdateBuilder::new(
+        departure,
+        Some(arrival),
+        from.to_string(),
+        to.to_string(),
+        if rng.random_bool(0.1) { Some("Delayed".to_string()) } else { None },
+        is_template,
+    ).insert(user_id, &Actor::default(), db).await.map_err(map_err)?;
+
+    let line = *LINES.choose(rng).unwrap();
+    let operator = if line.starts_with('S') {
+        tags.operator_options[0]
+    } else if line.starts_with('U') {
+        tags.operator_options[1]
+    } else {
+        tags.operator_options[2]
+    };
+    let ticket_index = rng.random_range(0..tags.ticket_options.len());
+    let price = match ticket_index {
+        0 => 3.8,
+        1 => 10.6,
+        _ => 0.0,
+    };
+
+    let links = [
+        (tags.line, Value::String(line.to_string())),
+        (tags.operator, Value::EnumOption(operator)),
+        (tags.ticket, Value::EnumOption(tags.ticket_options[ticket_index])),
+        (tags.price, Value::Float(price)),
+        (tags.distance, Value::Integer(rng.random_range(2..40))),
+    ];
+    for (order, (tag_id, value)) in links.into_iter().enumerate() {
+        ride_tag_link::CreateUpdateBuilder::new(order as u32, value, None)
+            .insert(ride.id(), tag_id, &Actor::default(), db)
+            .await
+            .map_err(map_err)?;
+    }
+    Ok(())
+}
+
+/// Populate the database with a demo user, tags and rides
+pub async fn run(args: &SeedArgs, db: &DatabaseConnection) -> Result<(), Box<dyn Error>> {
+    let txn = db.begin().await?;
+
+    let existing = UserEntity::find()
+        .filter(UserColumn::JwtIssuer.eq(args.issuer.as_str()))
+        .filter(UserColumn::JwtSubject.eq(args.subject.as_str()))
+        .count(&txn)
+        .await?;
+    if existing > 0 {
+        Err(format!("User {} of issuer {} already exists", args.subject, args.issuer))?;
+    }
+
+    let user = UserActiveModel {
+        jwt_issuer: Set(args.issuer.clone()),
+        jwt_subject: Set(args.subject.clone()),
+        name: Set(Some("Demo User".to_string())),
+        ..Default::default()
+    }.insert(&txn).await?;
+
+    let tags = insert_tags(user.id, &txn).await?;
+    let mut rng = StdRng::seed_from_u64(args.seed);
+
+    // A template for the daily commute, followed by commuting rides on working days
+    insert_ride(user.id, &tags, args.start + TimeDelta::hours(7), true, &mut rng, &txn).await?;
+    let mut day = args.start;
+    let mut created = 0;
+    while created < args.rides {
+        if day.weekday() != Weekday::Sat && day.weekday() != Weekday::Sun {
+            for hour in [7, 17] {
+                if created < args.rides {
+                    let departure = day + TimeDelta::hours(hour) + TimeDelta::minutes(rng.random_range(0..90));
+                    insert_ride(user.id, &tags, departure, false, &mut rng, &txn).await?;
+                    created += 1;
+                }
+            }
+        }
+        day += TimeDelta::days(1);
+    }
+
+    txn.commit().await?;
+    println!("Created user {} with {} rides", user.id, created);
+    Ok(())
+}
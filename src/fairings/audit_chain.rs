@@ -0,0 +1,63 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+use rocket::Shutdown;
+use rocket::fairing::AdHoc;
+use sea_orm::DatabaseConnection;
+use crate::fairings::{AuthCache, Database};
+use crate::model::audit_chain::AuditChain;
+use crate::model::error::CurdError;
+
+/// Chain new audit log entries every [interval] until shutdown
+async fn extend_chain(chain: AuditChain, interval: Duration, conn: Arc<DatabaseConnection>, shutdown: Shutdown) {
+    tokio::pin!(shutdown);
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = interval.tick() => (),
+        };
+        // Batches are chained until the backlog is done
+        loop {
+            match chain.extend(conn.as_ref()).await {
+                Ok(0) => break,
+                Ok(count) => info!("Chained {} audit log entries", count),
+                Err(CurdError::Conflict(e)) => {
+                    info!("Skipping chaining of the audit log: {}", e);
+                    break;
+                },
+                Err(e) => {
+                    error!("Cannot chain audit log: {}", e);
+                    break;
+                },
+            }
+        }
+    }
+}
+
+/// Fairing starting the chaining of the audit log every [interval_minutes], if given.
+/// Requires [AuthCache] and [Database] state.
+pub fn init(interval_minutes: Option<u64>) -> AdHoc {
+    AdHoc::on_liftoff(
+        "Audit log chain",
+        move |rocket| Box::pin(async move {
+            let Some(interval_minutes) = interval_minutes else {
+                return;
+            };
+            let (Some(auth_cache), Some(db)) = (rocket.state::<AuthCache>(), rocket.state::<Database>()) else {
+                error!("Audit log chain needs key cache and database");
+                return;
+            };
+            let chain = AuditChain::new(auth_cache.key_cache.clone(), auth_cache.expect_jwt_audience.clone());
+            let interval = Duration::from_secs(interval_minutes * 60);
+            tokio::spawn(extend_chain(chain, interval, db.conn.clone(), rocket.shutdown()));
+        })
+    )
+}
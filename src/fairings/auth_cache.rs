@@ -8,9 +8,20 @@
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::sync::RwLock;
 use rocket::fairing::AdHoc;
 use chrono::{DateTime, TimeDelta, Utc};
+use redis::AsyncCommands;
+use redis::aio::ConnectionManager;
+use crate::fairings::db::ConnectPolicy;
+
+/// Prefix of the keys of cached users in Redis
+const REDIS_USER_PREFIX: &str = "ptet:user:";
+/// Time a user stays cached in Redis, so that deleted users drop out eventually
+const REDIS_USER_TTL_SECONDS: u64 = 3600;
+/// Issuer of the user of the insecure development authentication
+pub const DEV_ISSUER: &str = "insecure-dev-auth";
 
 /// JWT information
 #[derive(Clone, Eq, PartialEq)]
@@ -19,10 +30,11 @@ pub struct TokenInfo {
     pub subject: String,
 }
 
-/// Rocket state for authentication cache
+/// Rocket state for authentication cache. Clones share the caches.
+#[derive(Clone)]
 pub struct AuthCache {
     /// Key cache
-    pub key_cache: RwLock<jwt_auth::keys::KeyCache>,
+    pub key_cache: Arc<RwLock<jwt_auth::keys::KeyCache>>,
     /// Expected audience in JWT
     pub expect_jwt_audience: String,
     /// Expected issuer in JWT
@@ -33,31 +45,160 @@ pub struct AuthCache {
     pub jwt_issued_after: Option<DateTime<Utc>>,
     /// Maximum expiration time
     pub jwt_max_expiration: TimeDelta,
+    /// Lifetime of access tokens issued in exchange for refresh tokens
+    pub access_token_lifetime: TimeDelta,
+    /// Maximum lifetime of refresh tokens, from issue to expiration
+    pub refresh_token_max_lifetime: TimeDelta,
     /// User cache. Maps JWT information to user ID in database
-    pub user_model_cache: RwLock<HashMap<TokenInfo, u32>>,
+    pub user_model_cache: UserModelCache,
+    /// Fixed user every request is authenticated as, without verifying a JWT. Only for
+    /// local development.
+    pub dev_user: Option<TokenInfo>,
+}
+
+/// Cache of user IDs by JWT information. It is kept in the memory of the process, or
+/// in Redis to share it between server instances. Clones share the cache.
+#[derive(Clone)]
+pub enum UserModelCache {
+    Memory(Arc<RwLock<HashMap<TokenInfo, u32>>>),
+    Redis(ConnectionManager),
 }
 
-/// Fairing for key cache
+impl UserModelCache {
+    /// Cache in Redis at [url], or in memory if not set
+    pub async fn connect(url: Option<&str>) -> redis::RedisResult<Self> {
+        match url {
+            Some(url) => {
+                let client = redis::Client::open(url)?;
+                Ok(Self::Redis(ConnectionManager::new(client).await?))
+            },
+            None => Ok(Self::Memory(Arc::new(RwLock::new(HashMap::new())))),
+        }
+    }
+
+    /// Redis key of [token]. Issuer and subject are JSON-encoded, so that they cannot
+    /// run into each other.
+    fn redis_key(token: &TokenInfo) -> String {
+        format!("{}{}", REDIS_USER_PREFIX, serde_json::json!([token.issuer, token.subject]))
+    }
+
+    /// User ID of [token] if cached. Failures of Redis are logged and count as miss.
+    pub async fn get(&self, token: &TokenInfo) -> Option<u32> {
+        match self {
+            Self::Memory(cache) => cache.read().await.get(token).copied(),
+            Self::Redis(conn) => {
+                let result: redis::RedisResult<Option<u32>> = conn.clone().get(Self::redis_key(token)).await;
+                match result {
+                    Ok(user_id) => user_id,
+                    Err(e) => {
+                        warn!("Cannot read user from Redis: {}", e);
+                        None
+                    },
+                }
+            },
+        }
+    }
+
+    /// Drop [token] from the cache
+    pub async fn remove(&self, token: &TokenInfo) {
+        match self {
+            Self::Memory(cache) => {
+                cache.write().await.remove(token);
+            },
+            Self::Redis(conn) => {
+                let result: redis::RedisResult<()> = conn.clone().del(Self::redis_key(token)).await;
+                if let Err(e) = result {
+                    warn!("Cannot remove user from Redis: {}", e);
+                }
+            },
+        }
+    }
+
+    /// Drop all entries kept in memory. Entries in Redis are shared and kept.
+    pub async fn clear(&self) {
+        if let Self::Memory(cache) = self {
+            cache.write().await.clear();
+        }
+    }
+
+    /// Cache [user_id] for [token]
+    pub async fn insert(&self, token: &TokenInfo, user_id: u32) {
+        match self {
+            Self::Memory(cache) => {
+                cache.write().await.insert(token.clone(), user_id);
+            },
+            Self::Redis(conn) => {
+                let result: redis::RedisResult<()> = conn
+                    .clone()
+                    .set_ex(Self::redis_key(token), user_id, REDIS_USER_TTL_SECONDS)
+                    .await;
+                if let Err(e) = result {
+                    warn!("Cannot write user to Redis: {}", e);
+                }
+            },
+        }
+    }
+}
+
+/// Fairing for key cache. The user cache is kept in Redis if [redis_url] is set. An
+/// unreachable Redis is retried according to [connect_policy], and the connection
+/// reconnects on its own if Redis restarts later.
+///
+/// If [dev_user] is set, all requests are authenticated as the user with this subject
+/// and [key_cache_path] is created if missing, so that no keys need to be set up.
 pub fn init(
     key_cache_path: PathBuf,
     expect_jwt_audience: String,
     expect_jwt_issuer: Option<String>,
     jwt_issued_after: Option<DateTime<Utc>>,
     jwt_max_expiration: TimeDelta,
+    access_token_lifetime: TimeDelta,
+    refresh_token_max_lifetime: TimeDelta,
+    redis_url: Option<String>,
+    connect_policy: ConnectPolicy,
+    dev_user: Option<String>,
 ) -> AdHoc {
-    AdHoc::on_ignite(
+    AdHoc::try_on_ignite(
         "Initializing key cache",
         move |rocket| async move {
-            let key_cache = jwt_auth::keys::KeyCache::from_path(key_cache_path).unwrap();
+            if let Some(dev_user) = &dev_user {
+                warn!("Insecure development authentication: every request is authenticated as {} without a token", dev_user);
+                if let Err(e) = std::fs::create_dir_all(&key_cache_path) {
+                    error!("Cannot create key cache at {}: {}", key_cache_path.display(), e);
+                    return Err(rocket);
+                }
+            }
+            let key_cache = match jwt_auth::keys::KeyCache::from_path(&key_cache_path) {
+                Ok(key_cache) => key_cache,
+                Err(e) => {
+                    error!("Cannot load key cache from {}: {}", key_cache_path.display(), e);
+                    return Err(rocket);
+                },
+            };
+            let user_model_cache = match connect_policy.connect("Redis", || UserModelCache::connect(redis_url.as_deref())).await {
+                Ok(user_model_cache) => user_model_cache,
+                Err(e) => {
+                    error!("Cannot connect to Redis: {}", e);
+                    return Err(rocket);
+                },
+            };
             let state = AuthCache {
-                key_cache: RwLock::new(key_cache),
+                key_cache: Arc::new(RwLock::new(key_cache)),
                 expect_jwt_audience,
                 expect_jwt_issuer,
                 jwt_issued_after,
                 jwt_max_expiration,
-                user_model_cache: RwLock::new(HashMap::new()),
+                access_token_lifetime,
+                refresh_token_max_lifetime,
+                user_model_cache,
+                dev_user: dev_user.map(
+                    |subject| TokenInfo {
+                        issuer: DEV_ISSUER.to_string(),
+                        subject,
+                    }
+                ),
             };
-            rocket.manage(state)
+            Ok(rocket.manage(state))
         }
     )
 }
@@ -0,0 +1,201 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::time::Duration;
+use lapin::{
+    options::{BasicPublishOptions, ExchangeDeclareOptions},
+    types::FieldTable,
+    BasicProperties,
+    Channel,
+    Connection,
+    ConnectionProperties,
+    ExchangeKind,
+};
+use rocket::Shutdown;
+use rocket::fairing::AdHoc;
+use rumqttc::{AsyncClient, MqttOptions, QoS};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use crate::model::event::{Event, EventBus};
+
+/// Messages buffered while the MQTT broker is unreachable
+const MQTT_CAPACITY: usize = 1024;
+/// Delay before reconnecting to the MQTT broker
+const MQTT_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Protocol of a broker URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Mqtt,
+    Amqp,
+}
+
+impl Protocol {
+    /// Protocol of [url] by its scheme, `mqtt`, `mqtts`, `amqp` or `amqps`
+    pub fn of_url(url: &str) -> Result<Self, String> {
+        let url = reqwest::Url::parse(url)
+            .map_err(|e| format!("Invalid event broker URL: {}", e))?;
+        match url.scheme() {
+            "mqtt" | "mqtts" => Ok(Protocol::Mqtt),
+            "amqp" | "amqps" => Ok(Protocol::Amqp),
+            scheme => Err(format!("Unsupported event broker scheme {}", scheme)),
+        }
+    }
+}
+
+/// Connection to the broker
+enum Publisher {
+    Mqtt {
+        client: AsyncClient,
+        /// Topics are `<prefix>/<user_id>/<resource>/<action>`
+        prefix: String,
+    },
+    Amqp {
+        url: String,
+        /// Topic exchange, routing keys are `<user_id>.<resource>.<action>`
+        exchange: String,
+        /// Opened on first use and after errors
+        channel: Option<Channel>,
+    },
+}
+
+impl Publisher {
+    /// Send [event] with the payload of webhook deliveries
+    async fn publish(&mut self, event: &Event) -> Result<(), String> {
+        let payload = serde_json::to_vec(event).map_err(|e| e.to_string())?;
+        match self {
+            Publisher::Mqtt { client, prefix } => {
+                let topic = format!("{}/{}/{}/{}", prefix, event.user_id, event.resource, event.action);
+                // Do not wait for the broker, events are dropped when the buffer is full
+                client
+                    .try_publish(topic, QoS::AtLeastOnce, false, payload)
+                    .map_err(|e| e.to_string())
+            },
+            Publisher::Amqp { url, exchange, channel } => {
+                let open = match channel.take() {
+                    Some(open) if open.status().connected() => open,
+                    _ => open_channel(url, exchange).await.map_err(|e| e.to_string())?,
+                };
+                let routing_key = format!("{}.{}.{}", event.user_id, event.resource, event.action);
+                open
+                    .basic_publish(
+                        exchange,
+                        &routing_key,
+                        BasicPublishOptions::default(),
+                        &payload,
+                        BasicProperties::default()
+                            .with_content_type("application/json".into())
+                            .with_message_id(event.id.as_str().into())
+                            .with_delivery_mode(2),
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
+                // Keep the channel only if it works
+                *channel = Some(open);
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Channel on a new connection to [url], with [exchange] declared
+async fn open_channel(url: &str, exchange: &str) -> Result<Channel, lapin::Error> {
+    let connection = Connection::connect(url, ConnectionProperties::default()).await?;
+    let channel = connection.create_channel().await?;
+    channel
+        .exchange_declare(
+            exchange,
+            ExchangeKind::Topic,
+            ExchangeDeclareOptions {
+                durable: true,
+                ..ExchangeDeclareOptions::default()
+            },
+            FieldTable::default(),
+        )
+        .await?;
+    Ok(channel)
+}
+
+/// MQTT client for [url], with a client ID generated if the URL has none. The
+/// connection is kept by a task until shutdown.
+fn connect_mqtt(url: &str, shutdown: Shutdown) -> Result<AsyncClient, String> {
+    let mut url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+    if !url.query_pairs().any(|(key, _)| key == "client_id") {
+        let client_id = format!("ptet-{}", uuid::Uuid::new_v4().simple());
+        url.query_pairs_mut().append_pair("client_id", &client_id);
+    }
+    let options = MqttOptions::parse_url(url.as_str()).map_err(|e| e.to_string())?;
+    let (client, mut event_loop) = AsyncClient::new(options, MQTT_CAPACITY);
+    tokio::spawn(async move {
+        tokio::pin!(shutdown);
+        loop {
+            let result = tokio::select! {
+                _ = &mut shutdown => break,
+                result = event_loop.poll() => result,
+            };
+            if let Err(e) = result {
+                warn!("MQTT connection failed, reconnecting: {}", e);
+                tokio::time::sleep(MQTT_RECONNECT_DELAY).await;
+            }
+        }
+    });
+    Ok(client)
+}
+
+/// Publish events until shutdown
+async fn publish_events(mut publisher: Publisher, mut events: Receiver<Event>, shutdown: Shutdown) {
+    tokio::pin!(shutdown);
+    loop {
+        let event = tokio::select! {
+            _ = &mut shutdown => break,
+            event = events.recv() => event,
+        };
+        match event {
+            Ok(event) => {
+                if let Err(e) = publisher.publish(&event).await {
+                    warn!("Cannot publish event {} to the broker: {}", event.id, e);
+                }
+            },
+            Err(RecvError::Lagged(count)) => {
+                warn!("{} events were not published to the broker", count);
+            },
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Fairing publishing events to the MQTT or AMQP broker at [url], if given. [topic] is
+/// the MQTT topic prefix or the AMQP exchange. Requires [EventBus] state.
+pub fn init(url: Option<String>, topic: String) -> AdHoc {
+    AdHoc::on_liftoff(
+        "Event broker",
+        move |rocket| Box::pin(async move {
+            let Some(url) = url else {
+                return;
+            };
+            let Some(events) = rocket.state::<EventBus>() else {
+                error!("Event broker needs event bus");
+                return;
+            };
+            let publisher = match Protocol::of_url(&url) {
+                Ok(Protocol::Mqtt) => match connect_mqtt(&url, rocket.shutdown()) {
+                    Ok(client) => Publisher::Mqtt { client, prefix: topic },
+                    Err(e) => {
+                        error!("Cannot create MQTT client: {}", e);
+                        return;
+                    },
+                },
+                Ok(Protocol::Amqp) => Publisher::Amqp { url, exchange: topic, channel: None },
+                Err(e) => {
+                    error!("{}", e);
+                    return;
+                },
+            };
+            tokio::spawn(publish_events(publisher, events.subscribe(), rocket.shutdown()));
+        })
+    )
+}
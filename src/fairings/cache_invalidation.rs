@@ -0,0 +1,248 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+use rocket::Shutdown;
+use rocket::fairing::AdHoc;
+use serde::{Deserialize, Serialize};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement};
+use sea_orm::sqlx::postgres::PgListener;
+use crate::fairings::{AuthCache, Database, TagCache};
+use crate::fairings::auth_cache::TokenInfo;
+use crate::model::feature::FeatureFlags;
+use crate::model::maintenance::Maintenance;
+
+/// Postgres channel of cache invalidations
+pub const CHANNEL: &str = "ptet_cache_invalidation";
+/// Delay before listening again after an error
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Cache entries to drop on all server instances
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "cache", rename_all = "snake_case")]
+pub enum Invalidation {
+    /// Tags of a user in the [TagCache]
+    Tag { user_id: u32 },
+    /// User ID of a token in the user cache of [AuthCache]
+    User { issuer: String, subject: String },
+    /// Stored [Maintenance] mode, which is read again
+    Maintenance,
+    /// Stored [FeatureFlags], which are read again
+    Features,
+}
+
+/// Payload of a notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Notification {
+    /// Sender, which ignores its own notifications
+    origin: String,
+    #[serde(flatten)]
+    invalidation: Invalidation,
+}
+
+/// Send [invalidation] from [origin] to all server instances if [db] is Postgres. Within
+/// a transaction, it is only delivered on commit.
+pub async fn notify(invalidation: Invalidation, origin: &str, db: &impl ConnectionTrait) -> Result<(), DbErr> {
+    if db.get_database_backend() != DbBackend::Postgres {
+        return Ok(());
+    }
+    let payload = serde_json::to_string(
+        &Notification {
+            origin: origin.to_string(),
+            invalidation,
+        }
+    )
+        .map_err(
+            |error| {
+                DbErr::Custom(error.to_string())
+            }
+        )?;
+    db.execute(
+        Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT pg_notify($1, $2)",
+            [CHANNEL.into(), payload.into()],
+        )
+    )
+        .await?;
+    Ok(())
+}
+
+/// Sends the invalidations of the caches of this server instance to the other
+/// instances. Only supported on Postgres.
+#[derive(Clone)]
+pub struct CacheNotifier {
+    conn: Arc<DatabaseConnection>,
+    /// Random ID of this server instance
+    origin: String,
+}
+
+impl CacheNotifier {
+    /// Notifier sending on [conn], `None` if it is not Postgres
+    pub fn new(conn: Arc<DatabaseConnection>) -> Option<Self> {
+        if conn.get_database_backend() == DbBackend::Postgres {
+            Some(
+                Self {
+                    conn,
+                    origin: uuid::Uuid::new_v4().to_string(),
+                }
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Send [invalidation] to the other instances. Failures are logged, the other
+    /// instances keep their entries then.
+    pub async fn publish(&self, invalidation: Invalidation) {
+        if let Err(e) = notify(invalidation, &self.origin, self.conn.as_ref()).await {
+            error!("Cannot send cache invalidation: {}", e);
+        }
+    }
+}
+
+/// Read the [maintenance] mode again from [conn]
+async fn reload_maintenance(maintenance: &Maintenance, conn: &DatabaseConnection) {
+    if let Err(e) = maintenance.reload(conn).await {
+        error!("Cannot reload maintenance mode: {}", e);
+    }
+}
+
+/// Read the feature [flags] again from [conn]
+async fn reload_features(flags: &FeatureFlags, conn: &DatabaseConnection) {
+    if let Err(e) = flags.reload(conn).await {
+        error!("Cannot reload feature flags: {}", e);
+    }
+}
+
+/// Drop the entries named by [invalidation]
+async fn apply(invalidation: Invalidation, conn: &DatabaseConnection, caches: &Caches) {
+    match invalidation {
+        Invalidation::Tag { user_id } => caches.tag_cache.drop_local(user_id).await,
+        Invalidation::User { issuer, subject } => {
+            caches.auth_cache.user_model_cache.remove(&TokenInfo { issuer, subject }).await;
+        },
+        Invalidation::Maintenance => reload_maintenance(&caches.maintenance, conn).await,
+        Invalidation::Features => reload_features(&caches.features, conn).await,
+    }
+}
+
+/// State of this server instance kept up to date by [listen]
+struct Caches {
+    tag_cache: TagCache,
+    auth_cache: AuthCache,
+    maintenance: Maintenance,
+    features: FeatureFlags,
+}
+
+/// Apply the invalidations of other instances until shutdown. Notifications sent while
+/// the connection is lost are missed, so the caches are cleared and the maintenance mode
+/// and feature flags are read again after reconnecting.
+async fn listen(
+    conn: Arc<DatabaseConnection>,
+    origin: String,
+    caches: Caches,
+    shutdown: Shutdown,
+) {
+    tokio::pin!(shutdown);
+    let mut listener: Option<PgListener> = None;
+    let mut reconnect = false;
+    loop {
+        if listener.is_none() {
+            let connected = async {
+                let mut listener = PgListener::connect_with(conn.get_postgres_connection_pool()).await?;
+                listener.listen(CHANNEL).await?;
+                Ok::<_, sea_orm::sqlx::Error>(listener)
+            };
+            match connected.await {
+                Ok(connected) => listener = Some(connected),
+                Err(e) => {
+                    error!("Cannot listen for cache invalidations: {}", e);
+                    tokio::select! {
+                        _ = &mut shutdown => break,
+                        _ = tokio::time::sleep(RETRY_DELAY) => continue,
+                    }
+                },
+            }
+            if reconnect {
+                info!("Listening for cache invalidations again, clearing caches");
+                caches.tag_cache.clear().await;
+                caches.auth_cache.user_model_cache.clear().await;
+                reload_maintenance(&caches.maintenance, &conn).await;
+                reload_features(&caches.features, &conn).await;
+            }
+            reconnect = true;
+        }
+        let Some(active) = listener.as_mut() else {
+            continue;
+        };
+        let notification = tokio::select! {
+            _ = &mut shutdown => break,
+            notification = active.try_recv() => notification,
+        };
+        match notification {
+            Ok(Some(notification)) => {
+                match serde_json::from_str::<Notification>(notification.payload()) {
+                    Ok(notification) if notification.origin == origin => {},
+                    Ok(notification) => apply(notification.invalidation, &conn, &caches).await,
+                    Err(e) => warn!("Invalid cache invalidation {}: {}", notification.payload(), e),
+                }
+            },
+            Ok(None) => {
+                warn!("Connection for cache invalidations lost");
+                listener = None;
+            },
+            Err(e) => {
+                error!("Cannot receive cache invalidations: {}", e);
+                listener = None;
+            },
+        }
+    }
+}
+
+/// Fairing for cache invalidation across server instances. On Postgres, it manages a
+/// [CacheNotifier] for the [TagCache], the [Maintenance] mode and the [FeatureFlags] and
+/// listens for the invalidations of other instances. It does nothing on other databases.
+/// Requires [Database] state and must be attached before the tag cache, the maintenance
+/// mode and the feature flags.
+pub fn init() -> AdHoc {
+    AdHoc::on_ignite(
+        "Cache invalidation",
+        |rocket| async move {
+            let Some(notifier) = rocket.state::<Database>().and_then(|db| CacheNotifier::new(db.conn.clone())) else {
+                return rocket;
+            };
+            rocket
+                .manage(notifier.clone())
+                .attach(
+                    AdHoc::on_liftoff(
+                        "Cache invalidation listener",
+                        move |rocket| Box::pin(async move {
+                            let (Some(tag_cache), Some(auth_cache), Some(maintenance), Some(features)) = (
+                                rocket.state::<TagCache>(),
+                                rocket.state::<AuthCache>(),
+                                rocket.state::<Maintenance>(),
+                                rocket.state::<FeatureFlags>(),
+                            ) else {
+                                error!("Cache invalidation needs tag cache, auth cache, maintenance mode and feature flags");
+                                return;
+                            };
+                            let caches = Caches {
+                                tag_cache: tag_cache.clone(),
+                                auth_cache: auth_cache.clone(),
+                                maintenance: maintenance.clone(),
+                                features: features.clone(),
+                            };
+                            tokio::spawn(listen(notifier.conn, notifier.origin, caches, rocket.shutdown()));
+                        })
+                    )
+                )
+        }
+    )
+}
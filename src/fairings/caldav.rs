@@ -0,0 +1,59 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+use rocket::Shutdown;
+use rocket::fairing::AdHoc;
+use sea_orm::DatabaseConnection;
+use crate::fairings::Database;
+use crate::model::caldav;
+
+/// Synchronize the calendars of all users every [interval] until shutdown
+async fn sync_all(interval: Duration, conn: Arc<DatabaseConnection>, shutdown: Shutdown) {
+    tokio::pin!(shutdown);
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = interval.tick() => (),
+        };
+        let user_ids = match caldav::find_users(conn.as_ref()).await {
+            Ok(user_ids) => user_ids,
+            Err(e) => {
+                error!("Cannot find CalDAV settings: {}", e);
+                continue;
+            },
+        };
+        for user_id in user_ids {
+            // Errors are recorded in the settings of the user
+            if let Err(e) = caldav::sync(user_id, conn.as_ref()).await {
+                warn!("CalDAV synchronization of user {} failed: {}", user_id, e);
+            }
+        }
+    }
+}
+
+/// Fairing starting the CalDAV synchronization every [interval_minutes], if given.
+/// Requires [Database] state.
+pub fn init(interval_minutes: Option<u64>) -> AdHoc {
+    AdHoc::on_liftoff(
+        "CalDAV synchronization",
+        move |rocket| Box::pin(async move {
+            let Some(interval_minutes) = interval_minutes else {
+                return;
+            };
+            let Some(db) = rocket.state::<Database>() else {
+                error!("CalDAV synchronization needs database");
+                return;
+            };
+            let interval = Duration::from_secs(interval_minutes * 60);
+            tokio::spawn(sync_all(interval, db.conn.clone(), rocket.shutdown()));
+        })
+    )
+}
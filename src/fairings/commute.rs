@@ -0,0 +1,75 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+use rocket::Shutdown;
+use rocket::fairing::AdHoc;
+use sea_orm::DatabaseConnection;
+use crate::fairings::Database;
+use crate::model::audit::Actor;
+use crate::model::commute;
+use crate::model::event::{Action, Event, EventBus, Resource};
+
+/// Interval between analyses of the rides
+const RUN_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+/// Tag the commutes of all users having the tag [tag_key] until shutdown
+async fn tag_commutes(tag_key: String, events: EventBus, conn: Arc<DatabaseConnection>, shutdown: Shutdown) {
+    tokio::pin!(shutdown);
+    let mut interval = tokio::time::interval(RUN_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = interval.tick() => (),
+        };
+        let user_ids = match commute::find_users(&tag_key, conn.as_ref()).await {
+            Ok(user_ids) => user_ids,
+            Err(e) => {
+                error!("Cannot find users tagging commutes: {}", e);
+                continue;
+            },
+        };
+        for user_id in user_ids {
+            let links = match commute::apply_tag(user_id, &tag_key, chrono::Utc::now(), &Actor::default(), conn.as_ref()).await {
+                Ok(links) => links,
+                Err(e) => {
+                    warn!("Cannot tag commutes of user {}: {}", user_id, e);
+                    continue;
+                },
+            };
+            if !links.is_empty() {
+                info!("Tagged {} commute rides of user {}", links.len(), user_id);
+            }
+            for link in links {
+                events.publish(Event::new(user_id, Resource::RideTag, Action::Created, link.id()).with_data(&link));
+            }
+        }
+    }
+}
+
+/// Fairing starting the commute tagging if [tag_key] is given. Requires [EventBus] and
+/// [Database] state.
+pub fn init(tag_key: Option<String>) -> AdHoc {
+    AdHoc::on_liftoff(
+        "Commute tagging",
+        move |rocket| Box::pin(async move {
+            let Some(tag_key) = tag_key else {
+                return;
+            };
+            let (Some(events), Some(db)) = (
+                rocket.state::<EventBus>(),
+                rocket.state::<Database>(),
+            ) else {
+                error!("Commute tagging needs event bus and database");
+                return;
+            };
+            tokio::spawn(tag_commutes(tag_key, events.clone(), db.conn.clone(), rocket.shutdown()));
+        })
+    )
+}
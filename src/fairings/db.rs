@@ -6,29 +6,160 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+use std::fmt::Display;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 use rocket::fairing::AdHoc;
+use sea_orm::ConnectOptions;
+use sea_orm::sqlx::sqlite::{SqliteJournalMode, SqliteSynchronous};
+use crate::commands::migrate::prepare_schema;
+use crate::config::DatabaseConfig;
+
+/// Time an SQLite connection waits for a lock held by another connection before
+/// failing with "database is locked"
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time to establish a connection, before a connection attempt counts as failed
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time a query waits for a connection of the pool, before it fails with 503
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Upper bound of the delay between connection attempts on startup
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(30);
+/// Postgres session settings of each connection
+const POSTGRES_SESSION_SETTINGS: [(&str, &str); 2] = [
+    // Timestamps are stored and returned in UTC
+    ("timezone", "UTC"),
+    // Release locks of transactions abandoned by a stuck request
+    ("idle_in_transaction_session_timeout", "60s"),
+];
 
 /// Database state in Rocket
+#[derive(Clone)]
 pub struct Database {
-    /// Database connection
+    /// Database connection for writes and reads which must see them
     pub conn: Arc<sea_orm::DatabaseConnection>,
+    /// Database connection for queries which only read. This is the read replica if
+    /// configured, otherwise the same connection as [conn].
+    pub read_conn: Arc<sea_orm::DatabaseConnection>,
+}
+
+/// How to handle services which are not reachable on startup, e.g. a database which
+/// starts later than the server
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectPolicy {
+    /// Number of retries after the first failed attempt. 0 fails fast.
+    pub retries: u32,
+    /// Delay before the first retry, doubled for every further retry up to
+    /// [MAX_CONNECT_BACKOFF]
+    pub backoff: Duration,
+    /// Keep retrying until the service is reachable, regardless of [retries]
+    pub wait: bool,
+}
+
+impl ConnectPolicy {
+    /// Run [connect] and run it again with exponential backoff as long as it fails,
+    /// according to the policy. [service] names the service in the log.
+    pub async fn connect<T, E, F, Fut>(&self, service: &str, mut connect: F) -> Result<T, E>
+    where
+        E: Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match connect().await {
+                Ok(value) => {
+                    if attempt > 1 {
+                        info!("Connected to {} after {} attempts", service, attempt);
+                    }
+                    return Ok(value);
+                },
+                Err(error) if self.wait || attempt <= self.retries => {
+                    warn!("Cannot connect to {} (attempt {}), retrying in {:?}: {}", service, attempt, backoff, error);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+                    attempt += 1;
+                },
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// Options for connecting to [url]. SeaORM selects the engine from the URL and applies
+/// only its settings to each connection of the pool:
+///
+/// * SQLite: WAL journal, so that readers do not block the writer, a busy timeout,
+///   so that concurrent writers wait for each other, and foreign keys.
+/// * Postgres: [POSTGRES_SESSION_SETTINGS] and the application name.
+///
+/// Connections are checked before they are handed out, so that connections broken by
+/// a restart of the database are replaced by new ones. While the database is down,
+/// queries fail after [ACQUIRE_TIMEOUT] instead of hanging.
+pub fn connect_options(url: &str) -> ConnectOptions {
+    let mut options = ConnectOptions::new(url);
+    options
+        .connect_timeout(CONNECT_TIMEOUT)
+        .acquire_timeout(ACQUIRE_TIMEOUT)
+        .test_before_acquire(true)
+        .map_sqlx_sqlite_opts(|opts| {
+            opts.journal_mode(SqliteJournalMode::Wal)
+                .synchronous(SqliteSynchronous::Normal)
+                .busy_timeout(SQLITE_BUSY_TIMEOUT)
+                .foreign_keys(true)
+        })
+        .map_sqlx_postgres_opts(|opts| {
+            opts.application_name(env!("CARGO_PKG_NAME"))
+                .options(POSTGRES_SESSION_SETTINGS)
+        });
+    options
+}
+
+/// Connect to database at [url], retried according to [policy]
+pub async fn connect(url: &str, service: &str, policy: &ConnectPolicy) -> Result<sea_orm::DatabaseConnection, sea_orm::DbErr> {
+    policy.connect(service, || sea_orm::Database::connect(connect_options(url))).await
 }
 
 /// Fairing for database setup
-pub fn init(url: String) -> AdHoc {
-    AdHoc::on_ignite(
+///
+/// Pending migrations are applied if enabled in [config]. Otherwise, ignition fails
+/// if there are pending migrations. Unreachable databases are retried according to the
+/// [ConnectPolicy] of [config].
+pub fn init(config: DatabaseConfig) -> AdHoc {
+    AdHoc::try_on_ignite(
         "Connecting to database",
-        move |rocket| async {
-            let conn = sea_orm::Database::connect(url).await.unwrap();
+        move |rocket| async move {
+            let policy = config.connect_policy();
+            let conn = match connect(&config.database, "database", &policy).await {
+                Ok(conn) => Arc::new(conn),
+                Err(e) => {
+                    error!("Cannot connect to database: {}", e);
+                    return Err(rocket);
+                },
+            };
+            let read_conn = match &config.read_database {
+                Some(read_database) => match connect(read_database, "read replica", &policy).await {
+                    Ok(read_conn) => Arc::new(read_conn),
+                    Err(e) => {
+                        error!("Cannot connect to read replica: {}", e);
+                        return Err(rocket);
+                    },
+                },
+                None => conn.clone(),
+            };
             let db = Database {
-                conn: Arc::new(conn),
+                conn,
+                read_conn,
             };
 
-            use migration::{Migrator, MigratorTrait};
-            Migrator::up(db.conn.as_ref(), None).await.unwrap();
-
-            rocket.manage(db)
+            match prepare_schema(db.conn.as_ref(), config.auto_migrate).await {
+                Ok(()) => Ok(rocket.manage(db)),
+                Err(e) => {
+                    error!("Database schema is not ready: {}", e);
+                    Err(rocket)
+                },
+            }
         }
     )
 }
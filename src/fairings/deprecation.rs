@@ -0,0 +1,52 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::{DateTime, Utc};
+use rocket::fairing::AdHoc;
+use crate::routes::v1;
+
+/// Header with the time the endpoint was deprecated (RFC 9745)
+pub const DEPRECATION_HEADER: &str = "Deprecation";
+/// Header with the time the endpoint is removed (RFC 8594)
+pub const SUNSET_HEADER: &str = "Sunset";
+
+/// Fairing adding `Deprecation`, `Sunset` and a `Link` to the successor version to
+/// responses of the v1 endpoints in [DEPRECATED](v1::DEPRECATED) mounted at [v1_base].
+/// Nothing is added until [deprecated_at] is set.
+pub fn init(
+    v1_base: String,
+    v2_base: String,
+    deprecated_at: Option<DateTime<Utc>>,
+    sunset: Option<DateTime<Utc>>,
+) -> AdHoc {
+    let v1_base = v1_base.trim_end_matches('/').to_string();
+    AdHoc::on_response(
+        "API deprecation",
+        move |request, response| {
+            let v1_base = v1_base.clone();
+            let v2_base = v2_base.clone();
+            Box::pin(async move {
+                let Some(deprecated_at) = deprecated_at else {
+                    return;
+                };
+                let Some(route) = request.route() else {
+                    return;
+                };
+                if route.uri.base().trim_end_matches('/') != v1_base
+                    || !v1::is_deprecated(route.method, route.uri.unmounted_origin.path().as_str()) {
+                    return;
+                }
+                response.set_raw_header(DEPRECATION_HEADER, format!("@{}", deprecated_at.timestamp()));
+                if let Some(sunset) = sunset {
+                    response.set_raw_header(SUNSET_HEADER, sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string());
+                }
+                response.adjoin_raw_header("Link", format!("<{}>; rel=\"successor-version\"", v2_base));
+            })
+        }
+    )
+}
@@ -0,0 +1,70 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use crate::fairings::request_id::RequestId;
+use crate::request_guards::AuthenticatedUser;
+use crate::routes::ApiError;
+
+/// Initialize the Sentry client for [dsn]. Events are sent until the returned guard
+/// is dropped. Panics are captured by the panic integration.
+pub fn init_client(dsn: &str) -> sentry::ClientInitGuard {
+    sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: sentry::release_name!(),
+            ..Default::default()
+        },
+    ))
+}
+
+/// Send an event for a server error response to [request]
+fn report(request: &Request<'_>, status: Status) {
+    let route = match request.route() {
+        Some(route) => route.uri.to_string(),
+        None => request.uri().path().to_string(),
+    };
+    let message = match request.local_cache(|| None::<ApiError>) {
+        Some(error) => error.to_string(),
+        None => ApiError::from_status(status).to_string(),
+    };
+
+    sentry::with_scope(
+        |scope| {
+            scope.set_tag("method", request.method());
+            scope.set_tag("route", &route);
+            scope.set_tag("request_id", RequestId::of(request));
+            scope.set_tag("status", status.code);
+            if let Some(user_id) = AuthenticatedUser::of(request) {
+                scope.set_user(Some(sentry::User {
+                    id: Some(user_id.to_string()),
+                    ..Default::default()
+                }));
+            }
+        },
+        || sentry::capture_message(
+            &format!("{} {}: {}", request.method(), route, message),
+            sentry::Level::Error,
+        ),
+    );
+}
+
+/// Fairing reporting server errors with route, user ID and request ID. Without an
+/// initialized Sentry client, nothing is sent.
+pub fn init() -> AdHoc {
+    AdHoc::on_response(
+        "Error reporting",
+        |request, response| Box::pin(async move {
+            if response.status().class().is_server_error() {
+                report(request, response.status());
+            }
+        })
+    )
+}
@@ -0,0 +1,33 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::fairing::AdHoc;
+use crate::fairings::Database;
+use crate::fairings::cache_invalidation::CacheNotifier;
+use crate::model::feature::FeatureFlags;
+
+/// Fairing managing the [FeatureFlags] of the configuration, with the flags switched at
+/// runtime restored from the database. It uses the [CacheNotifier] state if managed and
+/// requires [Database] state.
+pub fn load(flags: FeatureFlags) -> AdHoc {
+    AdHoc::try_on_ignite(
+        "Loading feature flags",
+        |rocket| async move {
+            let Some(db) = rocket.state::<Database>() else {
+                error!("Feature flags need the database");
+                return Err(rocket);
+            };
+            let flags = flags.with_notifier(rocket.state::<CacheNotifier>().cloned());
+            if let Err(e) = flags.reload(db.conn.as_ref()).await {
+                error!("Cannot load feature flags: {}", e);
+                return Err(rocket);
+            }
+            Ok(rocket.manage(flags))
+        }
+    )
+}
@@ -0,0 +1,67 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::net::SocketAddr;
+use rocket::fairing::AdHoc;
+use tonic::transport::Server;
+use crate::fairings::{AuthCache, Database, TagCache};
+use crate::grpc::{
+    GrpcState,
+    proto::{ride_service_server::RideServiceServer, tag_service_server::TagServiceServer},
+    ride::Rides,
+    tag::Tags,
+};
+use crate::model::event::EventBus;
+use crate::model::maintenance::Maintenance;
+use crate::model::tag::PriceTag;
+
+/// Fairing starting the gRPC server on [port] next to Rocket. It shares the auth
+/// cache, database and event bus with the HTTP API and stops with Rocket. Does
+/// nothing if [port] is not set.
+pub fn init(port: Option<u16>) -> AdHoc {
+    AdHoc::on_liftoff(
+        "gRPC server",
+        move |rocket| Box::pin(async move {
+            let Some(port) = port else {
+                return;
+            };
+            let (Some(auth_cache), Some(db), Some(events), Some(tag_cache), Some(maintenance), Some(price_tag)) = (
+                rocket.state::<AuthCache>(),
+                rocket.state::<Database>(),
+                rocket.state::<EventBus>(),
+                rocket.state::<TagCache>(),
+                rocket.state::<Maintenance>(),
+                rocket.state::<PriceTag>(),
+            ) else {
+                error!("gRPC server needs auth cache, database, event bus, tag cache, maintenance mode and price tag");
+                return;
+            };
+            let state = GrpcState {
+                auth_cache: auth_cache.clone(),
+                db: db.clone(),
+                events: events.clone(),
+                tag_cache: tag_cache.clone(),
+                maintenance: maintenance.clone(),
+                price_tag: price_tag.clone(),
+            };
+            let address = SocketAddr::new(rocket.config().address, port);
+            let shutdown = rocket.shutdown();
+            tokio::spawn(async move {
+                let result = Server::builder()
+                    .add_service(RideServiceServer::new(Rides::new(state.clone())))
+                    .add_service(TagServiceServer::new(Tags::new(state)))
+                    .serve_with_shutdown(address, shutdown)
+                    .await;
+                if let Err(e) = result {
+                    error!("gRPC server on {} failed: {}", address, e);
+                }
+            });
+            info!("gRPC server listening on {}", address);
+        })
+    )
+}
@@ -0,0 +1,102 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{Request, Response, State};
+use rocket::fairing::AdHoc;
+use rocket::http::{Method, Status, uri::Origin};
+use rocket::response::Responder;
+use crate::fairings::Database;
+use crate::fairings::cache_invalidation::CacheNotifier;
+use crate::model::maintenance::Maintenance;
+use crate::routes::ApiError;
+
+/// Path write requests are redirected to during maintenance, mounted at `/`
+pub const UNAVAILABLE_PATH: &str = "/__maintenance";
+
+/// Whether [method] changes data
+fn is_write(method: Method) -> bool {
+    matches!(method, Method::Post | Method::Put | Method::Patch | Method::Delete)
+}
+
+/// Fairing rejecting writes while the [Maintenance] mode is enabled. Write requests are
+/// redirected to [unavailable], which responds with 503 and `Retry-After`. Reads and
+/// the admin endpoints, which switch the mode off again, are passed through.
+pub fn init() -> AdHoc {
+    AdHoc::on_request(
+        "Maintenance mode",
+        |request, _| Box::pin(async move {
+            let Some(maintenance) = request.rocket().state::<Maintenance>() else {
+                return;
+            };
+            if !is_write(request.method()) || !maintenance.is_enabled() {
+                return;
+            }
+            if request.uri().path().segments().any(|segment| segment == "admin") {
+                return;
+            }
+            request.set_method(Method::Get);
+            request.set_uri(Origin::parse(UNAVAILABLE_PATH).expect("Path is valid"));
+        })
+    )
+}
+
+/// Fairing managing the [Maintenance] state, restored from the database so that a
+/// restart does not open writes again. It uses the [CacheNotifier] state if managed and
+/// requires [Database] state.
+pub fn load() -> AdHoc {
+    AdHoc::try_on_ignite(
+        "Loading maintenance mode",
+        |rocket| async move {
+            let Some(db) = rocket.state::<Database>() else {
+                error!("Maintenance mode needs the database");
+                return Err(rocket);
+            };
+            let maintenance = Maintenance::new(rocket.state::<CacheNotifier>().cloned());
+            match maintenance.reload(db.conn.as_ref()).await {
+                Ok(status) if status.enabled => warn!("Maintenance mode is enabled, writes are rejected"),
+                Ok(_) => {},
+                Err(e) => {
+                    error!("Cannot load maintenance mode: {}", e);
+                    return Err(rocket);
+                },
+            }
+            Ok(rocket.manage(maintenance))
+        }
+    )
+}
+
+/// Error response asking the client to retry after [retry_after] seconds
+pub struct Unavailable {
+    error: ApiError,
+    retry_after: u32,
+}
+
+impl<'r> Responder<'r, 'static> for Unavailable {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'static> {
+        Response::build_from(self.error.respond_to(request)?)
+            .raw_header("Retry-After", self.retry_after.to_string())
+            .ok()
+    }
+}
+
+/// Target of write requests during maintenance. Not found if requested directly
+/// outside of maintenance.
+#[get("/__maintenance")]
+pub fn unavailable(maintenance: &State<Maintenance>) -> Result<Unavailable, ApiError> {
+    let status = maintenance.status();
+    if !status.enabled {
+        Err(ApiError::new_not_found())?;
+    }
+    Ok(
+        Unavailable {
+            error: ApiError::from_status(Status::ServiceUnavailable)
+                .with_description(status.message.unwrap_or_else(|| "Down for maintenance, only reads are available".to_string())),
+            retry_after: status.retry_after_seconds,
+        }
+    )
+}
@@ -6,8 +6,25 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+pub mod audit_chain;
 pub mod auth_cache;
+pub mod broker;
+pub mod cache_invalidation;
+pub mod caldav;
+pub mod commute;
 pub mod db;
+pub mod deprecation;
+pub mod error_reporting;
+pub mod feature;
+pub mod grpc;
+pub mod maintenance;
+pub mod request_id;
+pub mod retention;
+pub mod tag_cache;
+pub mod transaction;
+pub mod webdav;
+pub mod webhooks;
 
 pub use auth_cache::AuthCache;
-pub use db::Database;
\ No newline at end of file
+pub use db::Database;pub use request_id::RequestId;
+pub use tag_cache::TagCache;
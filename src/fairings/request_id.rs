@@ -0,0 +1,47 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::fairing::AdHoc;
+
+/// Header carrying the request ID, in requests and responses
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+/// Maximum length of a request ID given by the client
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// ID to correlate a request with logs and error reports
+pub struct RequestId(String);
+
+impl RequestId {
+    /// ID of [request]. The ID sent by the client is used if it is short and printable,
+    /// otherwise a new UUID is generated.
+    pub fn of<'r>(request: &'r Request<'_>) -> &'r str {
+        &request.local_cache(|| {
+            match request.headers().get_one(REQUEST_ID_HEADER) {
+                Some(id) if Self::is_valid(id) => RequestId(id.to_string()),
+                _ => RequestId(uuid::Uuid::new_v4().to_string()),
+            }
+        }).0
+    }
+
+    fn is_valid(id: &str) -> bool {
+        !id.is_empty()
+            && id.len() <= MAX_REQUEST_ID_LEN
+            && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    }
+}
+
+/// Fairing adding the request ID to every response
+pub fn init() -> AdHoc {
+    AdHoc::on_response(
+        "Request ID",
+        |request, response| Box::pin(async move {
+            response.set_raw_header(REQUEST_ID_HEADER, RequestId::of(request).to_string());
+        })
+    )
+}
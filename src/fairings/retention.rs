@@ -0,0 +1,63 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+use rocket::Shutdown;
+use rocket::fairing::AdHoc;
+use sea_orm::DatabaseConnection;
+use crate::fairings::Database;
+use crate::model::event::{Action, Event, EventBus, Resource};
+use crate::model::retention::RetentionPolicy;
+
+/// Interval between deletions of expired rides
+const RUN_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Delete expired rides until shutdown
+async fn purge_expired(policy: RetentionPolicy, events: EventBus, conn: Arc<DatabaseConnection>, shutdown: Shutdown) {
+    tokio::pin!(shutdown);
+    let mut interval = tokio::time::interval(RUN_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = interval.tick() => (),
+        };
+        let purged = match policy.purge(chrono::Utc::now(), conn.as_ref()).await {
+            Ok(purged) => purged,
+            Err(e) => {
+                error!("Cannot delete expired rides: {}", e);
+                continue;
+            },
+        };
+        for (user_id, ride_ids) in purged {
+            info!("Deleted {} expired rides of user {}", ride_ids.len(), user_id);
+            for ride_id in ride_ids {
+                events.publish(Event::new(user_id, Resource::Ride, Action::Deleted, ride_id));
+            }
+        }
+    }
+}
+
+/// Fairing starting the retention job. Requires [RetentionPolicy], [EventBus] and
+/// [Database] state.
+pub fn init() -> AdHoc {
+    AdHoc::on_liftoff(
+        "Data retention",
+        |rocket| Box::pin(async move {
+            let (Some(policy), Some(events), Some(db)) = (
+                rocket.state::<RetentionPolicy>(),
+                rocket.state::<EventBus>(),
+                rocket.state::<Database>(),
+            ) else {
+                error!("Data retention needs retention policy, event bus and database");
+                return;
+            };
+            tokio::spawn(purge_expired(policy.clone(), events.clone(), db.conn.clone(), rocket.shutdown()));
+        })
+    )
+}
@@ -0,0 +1,107 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use rocket::fairing::AdHoc;
+use sea_orm::ConnectionTrait;
+use crate::model::error::CurdError;
+use crate::model::tag::Tag;
+use super::cache_invalidation::{CacheNotifier, Invalidation};
+
+/// Rocket state caching the tag descriptors of a user, including their options.
+/// Tags are loaded for all of a user's tags at once on the first access and dropped
+/// again on every write to a tag or tag option of the user, also on the other server
+/// instances if a [CacheNotifier] is set. Clones share the cache.
+#[derive(Clone, Default)]
+pub struct TagCache {
+    inner: Arc<RwLock<Inner>>,
+    notifier: Option<CacheNotifier>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Maps user ID to the user's tags by tag ID
+    tags: HashMap<u32, Arc<HashMap<u32, Tag>>>,
+    /// Incremented on each invalidation, so that a load racing with a write does not
+    /// store the tags it read before the write
+    generation: u64,
+}
+
+impl TagCache {
+    /// Tags of [user_id], loaded from [db] if not cached
+    async fn load(&self, user_id: u32, db: &impl ConnectionTrait) -> Result<Arc<HashMap<u32, Tag>>, CurdError> {
+        let generation = {
+            let inner = self.inner.read().await;
+            if let Some(tags) = inner.tags.get(&user_id) {
+                return Ok(tags.clone());
+            }
+            inner.generation
+        };
+
+        let tags: HashMap<u32, Tag> = Tag::find_all(user_id, true, false, db)
+            .await?
+            .into_iter()
+            .map(|tag| (tag.id(), tag))
+            .collect();
+        let tags = Arc::new(tags);
+        let mut inner = self.inner.write().await;
+        if inner.generation == generation {
+            inner.tags.insert(user_id, tags.clone());
+        }
+        Ok(tags)
+    }
+
+    /// Tag [tag_id] with options. Fails with [CurdError::NotFound] if the tag does not
+    /// exist or does not belong to [user_id]. [db] should be the primary connection, so
+    /// that a lagging replica does not put outdated tags into the cache.
+    pub async fn get(&self, user_id: u32, tag_id: u32, db: &impl ConnectionTrait) -> Result<Tag, CurdError> {
+        self.load(user_id, db)
+            .await?
+            .get(&tag_id)
+            .cloned()
+            .ok_or(CurdError::NotFound)
+    }
+
+    /// Drop the cached tags of [user_id] on all server instances. Must be called after
+    /// each write to a tag or tag option of the user.
+    pub async fn invalidate(&self, user_id: u32) {
+        self.drop_local(user_id).await;
+        if let Some(notifier) = &self.notifier {
+            notifier.publish(Invalidation::Tag { user_id }).await;
+        }
+    }
+
+    /// Drop the cached tags of [user_id] only in this server instance
+    pub async fn drop_local(&self, user_id: u32) {
+        let mut inner = self.inner.write().await;
+        inner.tags.remove(&user_id);
+        inner.generation += 1;
+    }
+
+    /// Drop the cached tags of all users in this server instance
+    pub async fn clear(&self) {
+        let mut inner = self.inner.write().await;
+        inner.tags.clear();
+        inner.generation += 1;
+    }
+}
+
+/// Fairing for tag cache. It uses the [CacheNotifier] state if managed.
+pub fn init() -> AdHoc {
+    AdHoc::on_ignite(
+        "Initializing tag cache",
+        |rocket| async move {
+            let tag_cache = TagCache {
+                notifier: rocket.state::<CacheNotifier>().cloned(),
+                ..Default::default()
+            };
+            rocket.manage(tag_cache)
+        }
+    )
+}
@@ -0,0 +1,33 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::io::Cursor;
+use rocket::fairing::AdHoc;
+use rocket::http::ContentType;
+use crate::request_guards::RequestTransaction;
+use crate::routes::ApiError;
+
+/// Fairing finishing the [RequestTransaction] of a request: committed if the response
+/// is successful, rolled back otherwise. If the commit fails, the response is replaced
+/// by the error.
+pub fn init() -> AdHoc {
+    AdHoc::on_response(
+        "Request transaction",
+        |request, response| Box::pin(async move {
+            if let Err(e) = RequestTransaction::finish(request, response.status()).await {
+                error!("Cannot commit transaction of {} {}: {}", request.method(), request.uri().path(), e);
+                let error = ApiError::new_internal_server_error()
+                    .with_description("Changes could not be saved");
+                let body = serde_json::to_string(&error).unwrap();
+                response.set_status(error.to_status());
+                response.set_header(ContentType::JSON);
+                response.set_sized_body(body.len(), Cursor::new(body));
+            }
+        })
+    )
+}
@@ -0,0 +1,66 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+use rocket::Shutdown;
+use rocket::fairing::AdHoc;
+use sea_orm::DatabaseConnection;
+use crate::fairings::Database;
+use crate::model::credentials::CredentialCipher;
+use crate::model::webdav;
+
+/// Interval between checks for due uploads
+const RUN_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Upload the archives which are due until shutdown
+async fn upload_due(cipher: CredentialCipher, conn: Arc<DatabaseConnection>, shutdown: Shutdown) {
+    tokio::pin!(shutdown);
+    let mut interval = tokio::time::interval(RUN_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = interval.tick() => (),
+        };
+        let user_ids = match webdav::find_due(chrono::Utc::now(), conn.as_ref()).await {
+            Ok(user_ids) => user_ids,
+            Err(e) => {
+                error!("Cannot find due WebDAV uploads: {}", e);
+                continue;
+            },
+        };
+        for user_id in user_ids {
+            // Errors are recorded in the folder settings of the user
+            match webdav::upload(user_id, &cipher, conn.as_ref()).await {
+                Ok(upload) => info!("Uploaded {} of user {}", upload.file_name, user_id),
+                Err(e) => warn!("WebDAV upload of user {} failed: {}", user_id, e),
+            }
+        }
+    }
+}
+
+/// Fairing starting the scheduled WebDAV uploads if credentials can be stored. Requires
+/// [CredentialCipher] and [Database] state.
+pub fn init() -> AdHoc {
+    AdHoc::on_liftoff(
+        "WebDAV uploads",
+        |rocket| Box::pin(async move {
+            let (Some(cipher), Some(db)) = (
+                rocket.state::<CredentialCipher>(),
+                rocket.state::<Database>(),
+            ) else {
+                error!("WebDAV uploads need credential cipher and database");
+                return;
+            };
+            if !cipher.is_enabled() {
+                return;
+            }
+            tokio::spawn(upload_due(cipher.clone(), db.conn.clone(), rocket.shutdown()));
+        })
+    )
+}
@@ -0,0 +1,164 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+use hmac::{Hmac, Mac};
+use rocket::Shutdown;
+use rocket::fairing::AdHoc;
+use sha2::Sha256;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use sea_orm::DatabaseConnection;
+use entity::{webhook, webhook_delivery};
+use crate::fairings::Database;
+use crate::model::event::{Event, EventBus};
+use crate::model::feature::{FeatureFlags, WEBHOOKS};
+use crate::model::webhook::{enqueue, find_due, record_attempt, WebhookTargets};
+
+/// Interval for checking due deliveries
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Deliveries attempted per poll
+const BATCH_SIZE: u64 = 50;
+/// Timeout of a single delivery attempt
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Header with the event type
+pub const EVENT_HEADER: &str = "X-Ptet-Event";
+/// Header with the delivery ID, which stays the same on retries
+pub const DELIVERY_HEADER: &str = "X-Ptet-Delivery";
+/// Header with the Unix timestamp of the attempt
+pub const TIMESTAMP_HEADER: &str = "X-Ptet-Timestamp";
+/// Header with the HMAC-SHA256 of `<timestamp>.<body>`, keyed with the webhook secret
+pub const SIGNATURE_HEADER: &str = "X-Ptet-Signature";
+
+/// Signature of [payload] sent at [timestamp], as `sha256=<hex>`
+fn sign(secret: &str, timestamp: i64, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, payload).as_bytes());
+    let signature: String = mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", signature)
+}
+
+/// Post [delivery] to [webhook]. Returns the response status and an error message if
+/// the request failed. The target is checked again, as its host may resolve to other
+/// addresses by now.
+async fn deliver(
+    client: &reqwest::Client,
+    targets: &WebhookTargets,
+    delivery: &webhook_delivery::Model,
+    webhook: &webhook::Model,
+) -> (Option<u16>, Option<String>) {
+    if let Err(e) = targets.check(&webhook.url).await {
+        return (None, Some(e.to_string()));
+    }
+    let timestamp = chrono::Utc::now().timestamp();
+    let result = client
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event_type)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(SIGNATURE_HEADER, sign(&webhook.secret, timestamp, &delivery.payload))
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => (
+            Some(response.status().as_u16()),
+            Some(format!("Unexpected status {}", response.status())),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+/// Turn events into pending deliveries until shutdown. Events are dropped while the
+/// feature is disabled.
+async fn enqueue_events(mut events: Receiver<Event>, flags: FeatureFlags, conn: Arc<DatabaseConnection>, shutdown: Shutdown) {
+    tokio::pin!(shutdown);
+    loop {
+        let event = tokio::select! {
+            _ = &mut shutdown => break,
+            event = events.recv() => event,
+        };
+        match event {
+            Ok(_) if !flags.is_enabled(WEBHOOKS) => (),
+            Ok(event) => {
+                if let Err(e) = enqueue(&event, conn.as_ref()).await {
+                    error!("Cannot enqueue webhook deliveries for event {}: {}", event.id, e);
+                }
+            },
+            Err(RecvError::Lagged(count)) => {
+                warn!("Webhook deliveries for {} events were dropped", count);
+            },
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Attempt due deliveries until shutdown. Deliveries are kept while the feature is
+/// disabled.
+async fn deliver_due(client: reqwest::Client, targets: WebhookTargets, flags: FeatureFlags, conn: Arc<DatabaseConnection>, shutdown: Shutdown) {
+    tokio::pin!(shutdown);
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = interval.tick() => (),
+        };
+        if !flags.is_enabled(WEBHOOKS) {
+            continue;
+        }
+        let due = match find_due(BATCH_SIZE, conn.as_ref()).await {
+            Ok(due) => due,
+            Err(e) => {
+                error!("Cannot fetch due webhook deliveries: {}", e);
+                continue;
+            },
+        };
+        for (delivery, webhook) in due {
+            let (status, error) = deliver(&client, &targets, &delivery, &webhook).await;
+            let id = delivery.id;
+            if let Err(e) = record_attempt(delivery, status, error, conn.as_ref()).await {
+                error!("Cannot record webhook delivery {}: {}", id, e);
+            }
+        }
+    }
+}
+
+/// Fairing starting the webhook delivery worker. Requires [EventBus], [FeatureFlags],
+/// [WebhookTargets] and [Database] state.
+pub fn init() -> AdHoc {
+    AdHoc::on_liftoff(
+        "Webhook delivery",
+        |rocket| Box::pin(async move {
+            let (Some(events), Some(flags), Some(targets), Some(db)) = (rocket.state::<EventBus>(), rocket.state::<FeatureFlags>(), rocket.state::<WebhookTargets>(), rocket.state::<Database>()) else {
+                error!("Webhook delivery needs event bus, feature flags, webhook targets and database");
+                return;
+            };
+            let client = match reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .redirect(reqwest::redirect::Policy::none())
+                .dns_resolver(Arc::new(targets.clone()))
+                .build() {
+                Ok(client) => client,
+                Err(e) => {
+                    error!("Cannot create webhook client: {}", e);
+                    return;
+                },
+            };
+            tokio::spawn(enqueue_events(events.subscribe(), flags.clone(), db.conn.clone(), rocket.shutdown()));
+            tokio::spawn(deliver_due(client, targets.clone(), flags.clone(), db.conn.clone(), rocket.shutdown()));
+        })
+    )
+}
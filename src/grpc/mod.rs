@@ -0,0 +1,108 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+pub mod ride;
+pub mod tag;
+
+use rocket::http::Status as HttpStatus;
+use sea_orm::prelude::DateTimeUtc;
+use tonic::{Code, Request, Status};
+use crate::fairings::{AuthCache, Database, TagCache};
+use crate::model::error::CurdError;
+use crate::model::event::EventBus;
+use crate::model::maintenance::Maintenance;
+use crate::model::tag::PriceTag;
+use crate::request_guards::auth::{authenticate, authenticate_dev, JwtValidator};
+use crate::routes::ApiError;
+
+/// Generated protobuf messages and service traits
+pub mod proto {
+    tonic::include_proto!("ptet.v1");
+}
+
+/// State shared by the gRPC services, the same as the Rocket state of the HTTP API
+#[derive(Clone)]
+pub struct GrpcState {
+    pub auth_cache: AuthCache,
+    pub db: Database,
+    pub events: EventBus,
+    pub tag_cache: TagCache,
+    pub maintenance: Maintenance,
+    pub price_tag: PriceTag,
+}
+
+impl GrpcState {
+    /// Authenticate the JWT in the `authorization` metadata of [request] and return the
+    /// ID of the user, or the user of the insecure development authentication. Writes
+    /// fail as unavailable during maintenance.
+    pub async fn authenticate<Val: JwtValidator, T>(&self, request: &Request<T>) -> Result<u32, Status> {
+        if Val::writes() && self.maintenance.is_enabled() {
+            let status = self.maintenance.status();
+            Err(Status::unavailable(status.message.unwrap_or_else(|| "Down for maintenance, only reads are available".to_string())))?;
+        }
+        if let Some(dev_user) = &self.auth_cache.dev_user {
+            let (_, user_id) = authenticate_dev::<Val>(&self.auth_cache, &self.db, dev_user).await?;
+            return Ok(user_id);
+        }
+        let bearer = request
+            .metadata()
+            .get("authorization")
+            .ok_or_else(|| Status::unauthenticated("Authorization metadata is missing"))?
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Authorization must be Bearer"))?;
+        let (_, user_id) = authenticate::<Val>(&self.auth_cache, &self.db, bearer).await?;
+        Ok(user_id)
+    }
+}
+
+impl From<ApiError> for Status {
+    fn from(e: ApiError) -> Status {
+        let code = match e.to_status() {
+            HttpStatus::BadRequest => Code::InvalidArgument,
+            HttpStatus::Unauthorized => Code::Unauthenticated,
+            HttpStatus::Forbidden => Code::PermissionDenied,
+            HttpStatus::NotFound => Code::NotFound,
+            HttpStatus::Conflict => Code::Aborted,
+            HttpStatus::PayloadTooLarge => Code::ResourceExhausted,
+            HttpStatus::UnprocessableEntity => Code::FailedPrecondition,
+            HttpStatus::BadGateway => Code::Unavailable,
+            HttpStatus::ServiceUnavailable => Code::Unavailable,
+            _ => Code::Internal,
+        };
+        Status::new(code, e.message())
+    }
+}
+
+impl From<CurdError> for Status {
+    fn from(e: CurdError) -> Status {
+        Status::from(ApiError::from(e))
+    }
+}
+
+/// Convert [value] to a protobuf timestamp
+pub fn to_timestamp(value: DateTimeUtc) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: value.timestamp(),
+        nanos: value.timestamp_subsec_nanos() as i32,
+    }
+}
+
+/// Convert a protobuf timestamp to UTC date/time
+pub fn from_timestamp(value: prost_types::Timestamp) -> Result<DateTimeUtc, Status> {
+    u32::try_from(value.nanos)
+        .ok()
+        .and_then(|nanos| DateTimeUtc::from_timestamp(value.seconds, nanos))
+        .ok_or_else(|| Status::invalid_argument("Timestamp is out of range"))
+}
+
+/// Unwrap the required message field [name]
+pub fn required<T>(value: Option<T>, name: &str) -> Result<T, Status> {
+    value.ok_or_else(|| Status::invalid_argument(format!("{} is required", name)))
+}
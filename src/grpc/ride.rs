@@ -0,0 +1,288 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use tonic::{Request, Response, Status};
+use crate::model::audit::Actor;
+use crate::model::event::{Action, Event, Resource};
+use crate::model::{
+    ride, ride::{Ride, RideFilter},
+    ride_tag_link, ride_tag_link::{RideTagLink, Value as LinkValue},
+};
+use crate::request_guards::{ReadOnly, ReadWrite};
+use super::proto::{self, ride_service_server::RideService, ride_tag_value};
+use super::{from_timestamp, required, to_timestamp, GrpcState};
+
+impl From<LinkValue> for proto::RideTagValue {
+    fn from(value: LinkValue) -> Self {
+        let value = match value {
+            LinkValue::Integer(value) => ride_tag_value::Value::Integer(value),
+            LinkValue::Float(value) => ride_tag_value::Value::Float(value),
+            LinkValue::String(value) => ride_tag_value::Value::String(value),
+            LinkValue::DateTime(value) => ride_tag_value::Value::DateTime(to_timestamp(value)),
+            LinkValue::EnumOption(value) => ride_tag_value::Value::EnumOption(value),
+        };
+        Self {
+            value: Some(value),
+        }
+    }
+}
+
+impl TryFrom<proto::RideTagValue> for LinkValue {
+    type Error = Status;
+
+    fn try_from(value: proto::RideTagValue) -> Result<Self, Self::Error> {
+        let value = match required(value.value, "value")? {
+            ride_tag_value::Value::Integer(value) => LinkValue::Integer(value),
+            ride_tag_value::Value::Float(value) => LinkValue::Float(value),
+            ride_tag_value::Value::String(value) => LinkValue::String(value),
+            ride_tag_value::Value::DateTime(value) => LinkValue::DateTime(from_timestamp(value)?),
+            ride_tag_value::Value::EnumOption(value) => LinkValue::EnumOption(value),
+        };
+        Ok(value)
+    }
+}
+
+impl From<RideTagLink> for proto::RideTag {
+    fn from(link: RideTagLink) -> Self {
+        Self {
+            id: link.id(),
+            ride_id: link.ride_id(),
+            tag_id: link.tag_id(),
+            order: link.order,
+            value: Some(link.value.into()),
+            remarks: link.remarks,
+            per_ride: link.per_ride,
+        }
+    }
+}
+
+impl From<Ride> for proto::Ride {
+    fn from(ride: Ride) -> Self {
+        Self {
+            id: ride.id(),
+            journey_departure: Some(to_timestamp(ride.journey_departure)),
+            journey_arrival: ride.journey_arrival.map(to_timestamp),
+            tags: ride.tags().iter().flatten().cloned().map(proto::RideTag::from).collect(),
+            location_from: ride.location_from,
+            location_to: ride.location_to,
+            remarks: ride.remarks,
+            is_template: ride.is_template,
+            arrival_estimated: ride.arrival_estimated,
+            passengers: ride.passengers,
+            status: ride.status.to_string(),
+            vat_rate: ride.vat_rate,
+        }
+    }
+}
+
+impl TryFrom<proto::RideInput> for ride::CreateUpdateBuilder {
+    type Error = Status;
+
+    fn try_from(ride: proto::RideInput) -> Result<Self, Self::Error> {
+        Ok(
+            Self::new(
+                from_timestamp(required(ride.journey_departure, "journey_departure")?)?,
+                ride.journey_arrival.map(from_timestamp).transpose()?,
+                ride.location_from,
+                ride.location_to,
+                ride.remarks,
+                ride.is_template,
+            )
+                .with_passengers(ride.passengers.unwrap_or(1))
+                .with_vat_rate(ride.vat_rate)
+        )
+    }
+}
+
+impl TryFrom<proto::RideTagInput> for ride_tag_link::CreateUpdateBuilder {
+    type Error = Status;
+
+    fn try_from(link: proto::RideTagInput) -> Result<Self, Self::Error> {
+        Ok(
+            Self::new(
+                link.order,
+                required(link.value, "value")?.try_into()?,
+                link.remarks,
+            )
+                .with_per_ride(link.per_ride)
+        )
+    }
+}
+
+/// gRPC service for rides and ride tags
+pub struct Rides {
+    state: GrpcState,
+}
+
+impl Rides {
+    pub fn new(state: GrpcState) -> Self {
+        Self {
+            state,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl RideService for Rides {
+    async fn list_rides(
+        &self,
+        request: Request<proto::ListRidesRequest>,
+    ) -> Result<Response<proto::ListRidesResponse>, Status> {
+        let user_id = self.state.authenticate::<ReadOnly, _>(&request).await?;
+        let request = request.into_inner();
+        let conn = self.state.db.read_conn.as_ref();
+
+        let (rides, total) = match request.size {
+            Some(0) => Err(Status::invalid_argument("Page size must be greater than zero."))?,
+            Some(size) => Ride::find_all_paginated(user_id, &RideFilter::default(), true, false, conn, request.page.unwrap_or(0), size).await?,
+            None => {
+                let rides = Ride::find_all(user_id, true, false, conn).await?;
+                let total = rides.len() as u64;
+                (rides, total)
+            },
+        };
+        Ok(
+            Response::new(
+                proto::ListRidesResponse {
+                    rides: rides.into_iter().map(proto::Ride::from).collect(),
+                    total,
+                }
+            )
+        )
+    }
+
+    async fn get_ride(
+        &self,
+        request: Request<proto::RideId>,
+    ) -> Result<Response<proto::Ride>, Status> {
+        let user_id = self.state.authenticate::<ReadOnly, _>(&request).await?;
+        let ride_id = request.into_inner().ride_id;
+        let conn = self.state.db.read_conn.as_ref();
+
+        // First, make sure that resource belongs to the user
+        ride::is_owner(ride_id, user_id, false, conn).await?;
+
+        let ride = Ride::find_by_id(ride_id, true, false, conn).await?;
+        Ok(Response::new(ride.into()))
+    }
+
+    async fn create_ride(
+        &self,
+        request: Request<proto::RideInput>,
+    ) -> Result<Response<proto::Ride>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let builder = ride::CreateUpdateBuilder::try_from(request.into_inner())?;
+
+        let ride = builder
+            .insert(user_id, &Actor::new(Some(user_id), None), self.state.db.conn.as_ref())
+            .await?;
+        self.state.events.publish(Event::new(user_id, Resource::Ride, Action::Created, ride.id()).with_data(&ride));
+        Ok(Response::new(ride.into()))
+    }
+
+    async fn update_ride(
+        &self,
+        request: Request<proto::UpdateRideRequest>,
+    ) -> Result<Response<()>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let request = request.into_inner();
+        let builder = ride::CreateUpdateBuilder::try_from(required(request.ride, "ride")?)?;
+        let conn = self.state.db.conn.as_ref();
+
+        // First, make sure that resource belongs to the user
+        ride::is_owner(request.ride_id, user_id, false, conn).await?;
+
+        builder
+            .update(request.ride_id, &Actor::new(Some(user_id), None), conn)
+            .await?;
+        let ride = Ride::find_by_id(request.ride_id, true, false, conn).await?;
+        self.state.events.publish(Event::new(user_id, Resource::Ride, Action::Updated, request.ride_id).with_data(&ride));
+        Ok(Response::new(()))
+    }
+
+    async fn delete_ride(
+        &self,
+        request: Request<proto::RideId>,
+    ) -> Result<Response<()>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let ride_id = request.into_inner().ride_id;
+        let conn = self.state.db.conn.as_ref();
+
+        // First, make sure that resource belongs to the user
+        ride::is_owner(ride_id, user_id, false, conn).await?;
+
+        ride::remove(ride_id, &Actor::new(Some(user_id), None), conn).await?;
+        self.state.events.publish(Event::new(user_id, Resource::Ride, Action::Deleted, ride_id));
+        Ok(Response::new(()))
+    }
+
+    async fn create_ride_tag(
+        &self,
+        request: Request<proto::CreateRideTagRequest>,
+    ) -> Result<Response<proto::RideTag>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let request = request.into_inner();
+        let builder = ride_tag_link::CreateUpdateBuilder::try_from(required(request.link, "link")?)?;
+        let conn = self.state.db.conn.as_ref();
+
+        // First, make sure that resource belongs to the user
+        ride::is_owner(request.ride_id, user_id, false, conn).await?;
+        let tag = self.state.tag_cache.get(user_id, request.tag_id, conn).await?;
+        builder.validate(&tag)?;
+
+        // Prevent double use of tag ID
+        if RideTagLink::find_by_tag_id(request.ride_id, request.tag_id, false, conn).await.is_ok() {
+            Err(Status::already_exists("Tag is already linked to ride"))?;
+        }
+
+        let link = builder
+            .insert(request.ride_id, request.tag_id, &Actor::new(Some(user_id), None), conn)
+            .await?;
+        self.state.events.publish(Event::new(user_id, Resource::RideTag, Action::Created, link.id()).with_data(&link));
+        Ok(Response::new(link.into()))
+    }
+
+    async fn update_ride_tag(
+        &self,
+        request: Request<proto::UpdateRideTagRequest>,
+    ) -> Result<Response<()>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let request = request.into_inner();
+        let builder = ride_tag_link::CreateUpdateBuilder::try_from(required(request.link, "link")?)?;
+        let conn = self.state.db.conn.as_ref();
+
+        // First, make sure that resource belongs to the user
+        ride_tag_link::is_owner(request.link_id, user_id, false, conn).await?;
+
+        let tag_id = RideTagLink::find_by_id(request.link_id, false, conn).await?.tag_id();
+        let tag = self.state.tag_cache.get(user_id, tag_id, conn).await?;
+        builder.validate(&tag)?;
+        builder
+            .update(request.link_id, &Actor::new(Some(user_id), None), conn)
+            .await?;
+        let link = RideTagLink::find_by_id(request.link_id, false, conn).await?;
+        self.state.events.publish(Event::new(user_id, Resource::RideTag, Action::Updated, request.link_id).with_data(&link));
+        Ok(Response::new(()))
+    }
+
+    async fn delete_ride_tag(
+        &self,
+        request: Request<proto::RideTagId>,
+    ) -> Result<Response<()>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let link_id = request.into_inner().link_id;
+        let conn = self.state.db.conn.as_ref();
+
+        // First, make sure that resource belongs to the user
+        ride_tag_link::is_owner(link_id, user_id, false, conn).await?;
+
+        ride_tag_link::remove(link_id, &Actor::new(Some(user_id), None), conn).await?;
+        self.state.events.publish(Event::new(user_id, Resource::RideTag, Action::Deleted, link_id));
+        Ok(Response::new(()))
+    }
+}
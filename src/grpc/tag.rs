@@ -0,0 +1,239 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use tonic::{Request, Response, Status};
+use crate::model::audit::Actor;
+use crate::model::event::{Action, Event, Resource};
+use crate::model::{
+    tag, tag::Tag,
+    tag_option, tag_option::TagOption,
+};
+use crate::request_guards::{ReadOnly, ReadWrite};
+use super::proto::{self, tag_service_server::TagService};
+use super::{required, GrpcState};
+
+impl From<TagOption> for proto::TagOption {
+    fn from(option: TagOption) -> Self {
+        Self {
+            id: option.id(),
+            tag_id: option.tag_id(),
+            uuid: option.uuid().clone(),
+            display_name: option.display_name().clone(),
+            order: option.order,
+            value: option.value,
+            name: option.name,
+        }
+    }
+}
+
+impl From<Tag> for proto::Tag {
+    fn from(tag: Tag) -> Self {
+        Self {
+            id: tag.id(),
+            tag_key: tag.tag_key().clone(),
+            tag_name: tag.tag_name().clone(),
+            tag_display_name: tag.tag_display_name().clone(),
+            uuid: tag.uuid().clone(),
+            options: tag
+                .options()
+                .iter()
+                .flatten()
+                .cloned()
+                .map(proto::TagOption::from)
+                .collect(),
+            tag_type: tag.tag_type,
+            unit: tag.unit,
+            remarks: tag.remarks,
+            order: tag.order,
+            aggregation: tag.aggregation.map(Into::into),
+        }
+    }
+}
+
+impl From<proto::TagInput> for tag::CreateUpdateBuilder<String> {
+    fn from(tag: proto::TagInput) -> Self {
+        Self::new(
+            tag.tag_type,
+            tag.tag_key,
+            tag.tag_name,
+            tag.unit,
+            tag.remarks,
+        )
+            .with_order(tag.order)
+            .with_aggregation(tag.aggregation)
+    }
+}
+
+impl From<proto::TagOptionInput> for tag_option::CreateUpdateBuilder {
+    fn from(option: proto::TagOptionInput) -> Self {
+        Self::new(
+            option.order,
+            option.value,
+            option.name,
+        )
+    }
+}
+
+/// gRPC service for tags and tag options
+pub struct Tags {
+    state: GrpcState,
+}
+
+impl Tags {
+    pub fn new(state: GrpcState) -> Self {
+        Self {
+            state,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl TagService for Tags {
+    async fn list_tags(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<proto::ListTagsResponse>, Status> {
+        let user_id = self.state.authenticate::<ReadOnly, _>(&request).await?;
+
+        let tags = Tag::find_all(user_id, true, false, self.state.db.read_conn.as_ref()).await?;
+        Ok(
+            Response::new(
+                proto::ListTagsResponse {
+                    tags: tags.into_iter().map(proto::Tag::from).collect(),
+                }
+            )
+        )
+    }
+
+    async fn get_tag(
+        &self,
+        request: Request<proto::TagId>,
+    ) -> Result<Response<proto::Tag>, Status> {
+        let user_id = self.state.authenticate::<ReadOnly, _>(&request).await?;
+        let tag_id = request.into_inner().tag_id;
+        let conn = self.state.db.read_conn.as_ref();
+
+        // First, make sure that tag belongs to the user
+        tag::is_owner(tag_id, user_id, false, conn).await?;
+
+        let tag = Tag::find_by_id(tag_id, true, false, conn).await?;
+        Ok(Response::new(tag.into()))
+    }
+
+    async fn create_tag(
+        &self,
+        request: Request<proto::TagInput>,
+    ) -> Result<Response<proto::Tag>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+
+        let tag = tag::CreateUpdateBuilder::from(request.into_inner())
+            .with_price_tag(&self.state.price_tag)
+            .insert(user_id, &Actor::new(Some(user_id), None), self.state.db.conn.as_ref())
+            .await?;
+        self.state.tag_cache.invalidate(user_id).await;
+        self.state.events.publish(Event::new(user_id, Resource::Tag, Action::Created, tag.id()).with_data(&tag));
+        Ok(Response::new(tag.into()))
+    }
+
+    async fn update_tag(
+        &self,
+        request: Request<proto::UpdateTagRequest>,
+    ) -> Result<Response<()>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let request = request.into_inner();
+        let input = required(request.tag, "tag")?;
+        let conn = self.state.db.conn.as_ref();
+
+        // First, make sure that tag belongs to the user
+        tag::is_owner(request.tag_id, user_id, false, conn).await?;
+
+        tag::CreateUpdateBuilder::from(input)
+            .with_price_tag(&self.state.price_tag)
+            .update(request.tag_id, &Actor::new(Some(user_id), None), conn)
+            .await?;
+        let tag = Tag::find_by_id(request.tag_id, true, false, conn).await?;
+        self.state.tag_cache.invalidate(user_id).await;
+        self.state.events.publish(Event::new(user_id, Resource::Tag, Action::Updated, request.tag_id).with_data(&tag));
+        Ok(Response::new(()))
+    }
+
+    async fn delete_tag(
+        &self,
+        request: Request<proto::TagId>,
+    ) -> Result<Response<()>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let tag_id = request.into_inner().tag_id;
+        let conn = self.state.db.conn.as_ref();
+
+        // First, make sure that tag belongs to the user
+        tag::is_owner(tag_id, user_id, false, conn).await?;
+
+        tag::remove(tag_id, &Actor::new(Some(user_id), None), conn).await?;
+        self.state.tag_cache.invalidate(user_id).await;
+        self.state.events.publish(Event::new(user_id, Resource::Tag, Action::Deleted, tag_id));
+        Ok(Response::new(()))
+    }
+
+    async fn create_tag_option(
+        &self,
+        request: Request<proto::CreateTagOptionRequest>,
+    ) -> Result<Response<proto::TagOption>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let request = request.into_inner();
+        let input = required(request.option, "option")?;
+        let conn = self.state.db.conn.as_ref();
+
+        // First, make sure that tag belongs to the user
+        tag::is_owner(request.tag_id, user_id, false, conn).await?;
+
+        let option = tag_option::CreateUpdateBuilder::from(input)
+            .insert(request.tag_id, &Actor::new(Some(user_id), None), conn)
+            .await?;
+        self.state.tag_cache.invalidate(user_id).await;
+        self.state.events.publish(Event::new(user_id, Resource::TagOption, Action::Created, option.id()).with_data(&option));
+        Ok(Response::new(option.into()))
+    }
+
+    async fn update_tag_option(
+        &self,
+        request: Request<proto::UpdateTagOptionRequest>,
+    ) -> Result<Response<()>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let request = request.into_inner();
+        let input = required(request.option, "option")?;
+        let conn = self.state.db.conn.as_ref();
+
+        // First, make sure that tag option belongs to the user
+        tag_option::is_owner(request.option_id, user_id, false, conn).await?;
+
+        tag_option::CreateUpdateBuilder::from(input)
+            .update(request.option_id, &Actor::new(Some(user_id), None), conn)
+            .await?;
+        let option = TagOption::find_by_id(request.option_id, false, conn).await?;
+        self.state.tag_cache.invalidate(user_id).await;
+        self.state.events.publish(Event::new(user_id, Resource::TagOption, Action::Updated, request.option_id).with_data(&option));
+        Ok(Response::new(()))
+    }
+
+    async fn delete_tag_option(
+        &self,
+        request: Request<proto::TagOptionId>,
+    ) -> Result<Response<()>, Status> {
+        let user_id = self.state.authenticate::<ReadWrite, _>(&request).await?;
+        let option_id = request.into_inner().option_id;
+        let conn = self.state.db.conn.as_ref();
+
+        // First, make sure that tag option belongs to the user
+        tag_option::is_owner(option_id, user_id, false, conn).await?;
+
+        tag_option::remove(option_id, &Actor::new(Some(user_id), None), conn).await?;
+        self.state.tag_cache.invalidate(user_id).await;
+        self.state.events.publish(Event::new(user_id, Resource::TagOption, Action::Deleted, option_id));
+        Ok(Response::new(()))
+    }
+}
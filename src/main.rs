@@ -6,99 +6,530 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+mod commands;
+mod config;
 mod fairings;
+mod grpc;
 mod request_guards;
 mod model;
 mod responders;
 mod routes;
+#[cfg(test)]
+mod tests;
 
 use std::error::Error;
+use std::net::IpAddr;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use chrono::{DateTime, TimeDelta, Utc};
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use rocket::{Build, Rocket};
+use rocket::data::ByteUnit;
+use tokio::signal::unix::{signal, SignalKind};
+use serde::Serialize;
+use config::Config;
 use rocket_okapi::{
-    openapi_get_routes,
-    swagger_ui::{make_swagger_ui, SwaggerUIConfig},
+    get_openapi_route,
+    rapidoc::{make_rapidoc, GeneralConfig, RapiDocConfig},
+    swagger_ui::{make_swagger_ui, SwaggerUIConfig, UrlObject},
 };
 
 #[macro_use] extern crate rocket;
 
 /// CLI interface
-#[derive(Parser)]
+///
+/// All settings can also be given in the config file or as environment variables
+/// prefixed with `PTET_`, e.g. `PTET_DATABASE`. CLI arguments take precedence.
+#[derive(Parser, Serialize)]
 #[command(version, about, long_about = None)]
 struct Cli {
+    /// Command to execute, runs the server if omitted
+    #[command(subcommand)]
+    #[serde(skip)]
+    command: Option<Command>,
+    /// Path to the TOML config file, defaults to ptet.toml if it exists
+    #[arg(short, long, env = "PTET_CONFIG")]
+    #[serde(skip)]
+    config: Option<PathBuf>,
     /// Database URI for SeaORM
     #[arg(short, long)]
-    database: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    database: Option<String>,
+    /// Database URI of a read replica for queries which only read
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    read_database: Option<String>,
+    /// Apply pending migrations on startup [default: true]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auto_migrate: Option<bool>,
+    /// Retries of a failed connection to the database or Redis on startup, 0 fails fast [default: 5]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connect_retries: Option<u32>,
+    /// Seconds before the first retry of a failed connection, doubled per retry [default: 1]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connect_backoff: Option<u64>,
+    /// Retry failed connections on startup until they succeed [default: false]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wait_for_services: Option<bool>,
     /// Path to the key cache
     #[arg(short, long)]
-    keys_dir: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keys_dir: Option<PathBuf>,
     /// Server base URI
     #[arg(short = 'u', long)]
-    server_base_uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    server_base_uri: Option<String>,
     /// Optionally, restrict accepted JWTs to issuer
     #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     expect_jwt_issuer: Option<String>,
     /// Optionally, only accept issued after a certain time
     #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     jwt_issued_after: Option<DateTime<Utc>>,
-    /// Set maximum expiration time
-    #[arg(long, default_value = "31536000")]
-    jwt_max_expiration: i64,
+    /// Set maximum expiration time in seconds [default: 31536000]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jwt_max_expiration: Option<i64>,
+    /// Lifetime in seconds of access tokens issued in exchange for refresh tokens [default: 900]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_token_lifetime: Option<i64>,
+    /// Maximum lifetime in seconds of refresh tokens exchanged on /auth/refresh [default: 2592000]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token_max_lifetime: Option<i64>,
+    /// Allow insecure settings for local development [default: false]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dev_mode: Option<bool>,
+    /// Authenticate every request as this user without a JWT, needs --dev-mode true
+    #[arg(long, value_name = "USER")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    insecure_dev_auth: Option<String>,
+    /// Address to bind to
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<IpAddr>,
+    /// Port to bind to
+    #[arg(short, long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+    /// Path the API is mounted at [default: /api/v1/]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_base: Option<String>,
+    /// Path v2 of the API is mounted at [default: /api/v2/]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_v2_base: Option<String>,
+    /// Time the v1 endpoints which change in v2 are deprecated, e.g. 2025-06-01T00:00:00Z
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    v1_deprecated_at: Option<DateTime<Utc>>,
+    /// Time the deprecated v1 endpoints are removed
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    v1_sunset: Option<DateTime<Utc>>,
+    /// Path the Swagger UI is mounted at [default: /api/v1/docs/]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    docs_path: Option<String>,
+    /// Path the RapiDoc UI is mounted at, not served if not set
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rapidoc_path: Option<String>,
+    /// Path to the TLS certificate chain in PEM format, enables TLS
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tls_certs: Option<PathBuf>,
+    /// Path to the TLS private key in PEM format
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tls_key: Option<PathBuf>,
+    /// Maximum size of JSON request bodies, e.g. 256KiB [default: 1MiB]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json_limit: Option<ByteUnit>,
+    /// Maximum size of account archives uploaded for import [default: 32MiB]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    import_limit: Option<ByteUnit>,
+    /// Seconds after which API requests are cancelled with 503, unlimited if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_timeout_seconds: Option<u64>,
+    /// Sentry DSN to report server errors and panics to
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sentry_dsn: Option<String>,
+    /// Port of the gRPC server, disabled if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grpc_port: Option<u16>,
+    /// Delete rides which departed more than this number of days ago
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retention_days: Option<u32>,
+    /// Redis URL for caches shared by server instances, e.g. redis://localhost/
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redis_url: Option<String>,
+    /// Geocoding provider, nominatim or photon, disabled if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    geocoder: Option<String>,
+    /// Base URL of the geocoding provider [default: its public instance]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    geocoder_url: Option<String>,
+    /// Routing service, motis or otp, disabled if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    router: Option<String>,
+    /// Base URL of the routing service
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    router_url: Option<String>,
+    /// Key of the tag holding ride prices for fare comparison [default: price]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fare_price_tag: Option<String>,
+    /// Key of the tag holding ride distances for list summaries and GPX tracks [default: distance]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distance_tag: Option<String>,
+    /// Key of the tag holding ride purposes for travel logs [default: purpose]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    purpose_tag: Option<String>,
+    /// OCR engine for receipts, tesseract or http, disabled if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ocr: Option<String>,
+    /// Tesseract command [default: tesseract]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ocr_command: Option<String>,
+    /// Tesseract languages [default: eng]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ocr_language: Option<String>,
+    /// URL of the OCR service for engine http
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ocr_url: Option<String>,
+    /// Maximum size of receipt images [default: 10MiB]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    receipt_limit: Option<ByteUnit>,
+    /// Malware scanner of uploaded GPX tracks, clamd or http, uploads not scanned if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    malware_scanner: Option<String>,
+    /// Address of clamd as tcp://host:port or unix:///path, or URL of the scanning service
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    malware_scanner_url: Option<String>,
+    /// Domain of the inbox addresses, disabled if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inbox_domain: Option<String>,
+    /// Secret of the inbound mail webhook
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inbox_secret: Option<String>,
+    /// Minutes between CalDAV synchronizations, only on request if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    caldav_sync_interval: Option<u64>,
+    /// Minutes between runs chaining and signing the audit log, disabled if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audit_chain_interval: Option<u64>,
+    /// Key encrypting stored credentials, 32 bytes as base64, WebDAV export disabled if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    credentials_key: Option<String>,
+    /// MQTT or AMQP broker URL events are published to, disabled if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event_broker_url: Option<String>,
+    /// MQTT topic prefix or AMQP exchange of the events [default: ptet]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event_broker_topic: Option<String>,
+    /// Offset of the time zone of quickly added rides in minutes [default: 0]
+    #[arg(long, allow_negative_numbers = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quick_add_utc_offset_minutes: Option<i32>,
+    /// Secret token of the Telegram bot webhook, bot disabled if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    telegram_secret: Option<String>,
+    /// Key of the string tag linked with rides of detected commutes, disabled if omitted
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commute_tag: Option<String>,
+    /// Default window between departures of duplicate rides in minutes [default: 15]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicate_window_minutes: Option<u32>,
+    /// Fill missing arrivals from the average duration of the route [default: false]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimate_arrival: Option<bool>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the server
+    Serve,
+    /// Populate the database with a demo user, tags and rides
+    Seed(commands::seed::SeedArgs),
+    /// Maintenance tasks on the database, without running the server
+    Admin {
+        #[command(subcommand)]
+        command: commands::admin::AdminCommand,
+    },
+    /// Write all data to a JSON Lines archive
+    Backup {
+        /// Archive file, stdout if omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Load all data from a JSON Lines archive into an empty database
+    Restore {
+        /// Archive file, stdin if omitted
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+    },
+    /// Control schema migrations
+    Migrate {
+        #[command(subcommand)]
+        command: commands::migrate::MigrateCommand,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
 
-    rocket::build()
-        .attach(fairings::db::init(cli.database.clone()))
+    match &cli.command {
+        Some(Command::Seed(args)) => {
+            let db = commands::connect(&Config::load_database(cli.config.as_deref(), &cli)?).await?;
+            commands::seed::run(args, &db).await
+        },
+        Some(Command::Admin { command }) => {
+            let db = commands::connect(&Config::load_database(cli.config.as_deref(), &cli)?).await?;
+            commands::admin::run(command, &db).await
+        },
+        Some(Command::Backup { output }) => {
+            let db = commands::connect(&Config::load_database(cli.config.as_deref(), &cli)?).await?;
+            commands::backup::backup(output.as_deref(), &db).await
+        },
+        Some(Command::Restore { input }) => {
+            let db = commands::connect(&Config::load_database(cli.config.as_deref(), &cli)?).await?;
+            commands::backup::restore(input.as_deref(), &db).await
+        },
+        Some(Command::Migrate { command }) => {
+            let config = Config::load_database(cli.config.as_deref(), &cli)?;
+            let db = fairings::db::connect(&config.database, "database", &config.connect_policy()).await?;
+            commands::migrate::run(command, &db).await
+        },
+        Some(Command::Serve) | None => {
+            let config = Config::load(cli.config.as_deref(), &cli)?;
+            config.validate()?;
+            serve(config).await
+        },
+    }
+}
+
+/// Run the server. If TLS is configured, the server is shut down gracefully on SIGHUP
+/// and launched again, which reloads the TLS certificate and key. Rocket cannot swap
+/// them on a running server, so this is a full restart: open connections and event
+/// streams are closed, in-memory caches are emptied and background workers start over.
+/// The configuration is not read again. Without TLS, SIGHUP is left to its default.
+async fn serve(config: Config) -> Result<(), Box<dyn Error>> {
+    let _sentry = config.sentry_dsn.as_deref().map(fairings::error_reporting::init_client);
+
+    if config.tls_certs.is_none() {
+        build(&config).launch().await?;
+        return Ok(());
+    }
+    loop {
+        let rocket = build(&config).ignite().await?;
+        let reload = Arc::new(AtomicBool::new(false));
+        let listener = {
+            let shutdown = rocket.shutdown();
+            let reload = reload.clone();
+            let mut hangup = signal(SignalKind::hangup())?;
+            tokio::spawn(async move {
+                if hangup.recv().await.is_some() {
+                    reload.store(true, Ordering::SeqCst);
+                    shutdown.notify();
+                }
+            })
+        };
+
+        rocket.launch().await?;
+        listener.abort();
+        if !reload.load(Ordering::SeqCst) {
+            break;
+        }
+        info!("Received SIGHUP, restarting");
+    }
+
+    Ok(())
+}
+
+/// Build the server
+fn build(config: &Config) -> Rocket<Build> {
+    let settings = routes::openapi::settings();
+    let (v1_routes, mut v1_spec) = routes::v1::routes();
+    if config.v1_deprecated_at.is_some() {
+        routes::v1::mark_deprecated(&mut v1_spec);
+    }
+    let (v2_routes, v2_spec) = routes::v2::routes();
+    let request_timeout = config.request_timeout_seconds.map(Duration::from_secs);
+
+    let rocket = rocket::custom(config.rocket_figment())
+        .attach(fairings::request_id::init())
+        .attach(fairings::maintenance::init())
+        .attach(fairings::transaction::init())
+        .attach(fairings::error_reporting::init())
+        .attach(
+            fairings::deprecation::init(
+                config.api_base.clone(),
+                config.api_v2_base.clone(),
+                config.v1_deprecated_at,
+                config.v1_sunset,
+            )
+        )
+        .attach(fairings::webhooks::init())
+        .attach(fairings::broker::init(config.event_broker_url.clone(), config.event_broker_topic.clone()))
+        .attach(fairings::retention::init())
+        .attach(fairings::caldav::init(config.caldav_sync_interval))
+        .attach(fairings::audit_chain::init(config.audit_chain_interval))
+        .attach(fairings::commute::init(config.commute_tag.clone()))
+        .attach(fairings::webdav::init())
+        .attach(fairings::grpc::init(config.grpc_port))
+        .manage(model::event::EventBus::new())
+        .manage(model::retention::RetentionPolicy::new(config.retention_days))
+        .manage(
+            model::geocode::Geocoder::new(config.geocoder, config.geocoder_url.clone())
+                .expect("Cannot create geocoding client")
+        )
+        .manage(
+            model::fare::Tariff::new(config.fare_regions.clone(), config.fare_price_tag.clone())
+                .expect("Fare regions are validated")
+        )
+        .manage(
+            model::mobility_budget::MobilityBudget::new(config.mobility_budget.clone(), config.fare_price_tag.clone())
+                .expect("Mobility budget is validated")
+        )
+        .manage(model::tag::PriceTag::new(config.fare_price_tag.clone()))
+        .manage(model::vat::VatReport::new(config.fare_price_tag.clone()))
+        .manage(model::travel_log::TravelLogs::new(config.fare_price_tag.clone(), config.purpose_tag.clone()))
+        .manage(model::ride_summary::RideSummaries::new(config.fare_price_tag.clone(), config.distance_tag.clone()))
+        .manage(
+            model::ride_track::RideTracks::new(
+                config.distance_tag.clone(),
+                model::malware_scan::MalwareScanner::new(config.malware_scanner, config.malware_scanner_url.clone())
+                    .expect("Malware scanner is validated"),
+            )
+        )
+        .manage(
+            model::inbox::Inbox::new(
+                config.inbox_domain.clone(),
+                config.inbox_secret.as_deref(),
+                config.fare_price_tag.clone(),
+            )
+        )
+        .manage(
+            model::credentials::CredentialCipher::new(config.credentials_key.as_deref())
+                .expect("Credentials key is validated")
+        )
+        .manage(model::quick_add::QuickAdd::new(config.fare_price_tag.clone(), config.quick_add_utc_offset_minutes))
+        .manage(model::telegram::Telegram::new(config.telegram_secret.as_deref()))
+        .manage(model::duplicate::Duplicates::new(config.duplicate_window_minutes, config.fare_price_tag.clone()))
+        .manage(model::arrival::ArrivalEstimator::new(config.estimate_arrival))
+        .manage(model::webhook::WebhookTargets::new(config.webhook_allowed_hosts.clone()))
+        .manage(
+            model::receipt::ReceiptReader::new(
+                config.ocr,
+                config.ocr_command.clone(),
+                config.ocr_language.clone(),
+                config.ocr_url.clone(),
+                config.fare_price_tag.clone(),
+            )
+                .expect("Cannot create OCR client")
+        )
+        .manage(
+            model::routing::RoutePlanner::new(config.router, config.router_url.clone())
+                .expect("Cannot create routing client")
+        )
+        .attach(fairings::db::init(config.database_config()))
         .attach(
             fairings::auth_cache::init(
-                cli.keys_dir.clone(),
-                cli.server_base_uri.clone(),
-                cli.expect_jwt_issuer.clone(),
-                cli.jwt_issued_after,
-                TimeDelta::seconds(cli.jwt_max_expiration),
+                config.keys_dir.clone(),
+                config.server_base_uri.clone(),
+                config.expect_jwt_issuer.clone(),
+                config.jwt_issued_after,
+                TimeDelta::seconds(config.jwt_max_expiration),
+                TimeDelta::seconds(config.access_token_lifetime),
+                TimeDelta::seconds(config.refresh_token_max_lifetime),
+                config.redis_url.clone(),
+                config.database_config().connect_policy(),
+                config.insecure_dev_auth.clone(),
             )
         )
-        .mount(
-            "/api/v1/",
-            openapi_get_routes![
-                routes::user::get,
-                routes::user::put,
-                routes::ride::list,
-                routes::ride::post,
-                routes::ride::get,
-                routes::ride::put,
-                routes::ride::delete,
-                routes::ride_tag::list,
-                routes::ride_tag::get_by_tag_id,
-                routes::ride_tag::post_by_tag_id,
-                routes::ride_tag::get_by_link_id,
-                routes::ride_tag::put,
-                routes::ride_tag::delete,
-                routes::tag::list,
-                routes::tag::post,
-                routes::tag::get,
-                routes::tag::put,
-                routes::tag::delete,
-                routes::tag_option::list,
-                routes::tag_option::post,
-                routes::tag_option::get,
-                routes::tag_option::put,
-                routes::tag_option::delete,
-            ]
+        .attach(fairings::cache_invalidation::init())
+        .attach(fairings::tag_cache::init())
+        .attach(fairings::maintenance::load())
+        .attach(
+            fairings::feature::load(
+                model::feature::FeatureFlags::new(&config.features).expect("Feature flags are validated")
+            )
         )
+        .mount(config.api_base.as_str(), routes::deadline::with_deadline(v1_routes, request_timeout))
+        .mount(config.api_base.as_str(), vec![get_openapi_route(v1_spec, &settings)])
+        .register(config.api_base.as_str(), catchers![routes::catchers::default])
+        .mount(config.api_v2_base.as_str(), routes::deadline::with_deadline(v2_routes, request_timeout))
+        .mount(config.api_v2_base.as_str(), vec![get_openapi_route(v2_spec, &settings)])
+        .register(config.api_v2_base.as_str(), catchers![routes::catchers::default])
+        .mount("/", routes![fairings::maintenance::unavailable])
         .mount(
-            "/api/v1/docs/",
+            config.docs_path.as_str(),
             make_swagger_ui(&SwaggerUIConfig {
-                url: "/api/v1/openapi.json".to_owned(),
+                url: config.openapi_url(),
+                urls: vec![
+                    UrlObject::new("v1", &config.openapi_url()),
+                    UrlObject::new("v2", &config.openapi_v2_url()),
+                ],
                 ..SwaggerUIConfig::default()
             })
-        )
-        .launch()
-        .await?;
-
-    Ok(())
+        );
+    match &config.rapidoc_path {
+        Some(rapidoc_path) => rocket.mount(
+            rapidoc_path.as_str(),
+            make_rapidoc(&RapiDocConfig {
+                general: GeneralConfig {
+                    spec_urls: vec![
+                        UrlObject::new("v1", &config.openapi_url()),
+                        UrlObject::new("v2", &config.openapi_v2_url()),
+                    ],
+                    ..GeneralConfig::default()
+                },
+                ..RapiDocConfig::default()
+            })
+        ),
+        None => rocket,
+    }
 }
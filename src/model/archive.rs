@@ -0,0 +1,614 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read, Write};
+use std::str::FromStr;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::prelude::*;
+use entity::ride::RideStatus;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use super::audit::Actor;
+use super::error::CurdError;
+use super::event::{Action, Event, Resource};
+use super::locale::{CsvFormat, Locale};
+use super::ride::{self, Ride};
+use super::ride_tag_link::{self, Value};
+use super::tag::{self, PriceTag, Tag};
+use super::tag_option::{self, TagOption};
+
+/// Format name in the manifest
+pub const ARCHIVE_FORMAT: &str = "ptet-account-archive";
+/// Version of the archive format
+pub const ARCHIVE_VERSION: u32 = 1;
+/// Manifest file of a ZIP archive
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// Tags with their options
+pub const TAGS_FILE: &str = "tags.json";
+/// Rides with their ride tags, for import
+pub const RIDES_FILE: &str = "rides.json";
+/// Rides as table, for humans
+pub const RIDES_CSV_FILE: &str = "rides.csv";
+/// Maximum uncompressed size of a file read from a ZIP archive
+const MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Description of an archive
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Manifest {
+    /// Always [ARCHIVE_FORMAT]
+    pub format: String,
+    /// [ARCHIVE_VERSION] of the producer
+    pub version: u32,
+    pub created_at: DateTimeUtc,
+    /// Files of the ZIP archive
+    pub files: Vec<String>,
+    pub tags: usize,
+    pub options: usize,
+    pub rides: usize,
+    pub ride_tags: usize,
+}
+
+/// Option of an archived tag
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ArchivedOption {
+    pub uuid: String,
+    pub order: u32,
+    pub value: String,
+    pub name: Option<String>,
+}
+
+/// Archived tag. Tags are identified by UUID, which is kept on import.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ArchivedTag {
+    pub uuid: String,
+    pub tag_type: String,
+    pub tag_key: String,
+    pub tag_name: Option<String>,
+    pub unit: Option<String>,
+    pub remarks: Option<String>,
+    #[serde(default)]
+    pub order: u32,
+    #[serde(default)]
+    pub aggregation: Option<String>,
+    pub options: Vec<ArchivedOption>,
+}
+
+/// Value of an archived ride tag. Options are referenced by UUID instead of ID.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(tag = "type", content = "value")]
+pub enum ArchivedValue {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    DateTime(DateTimeUtc),
+    EnumOption(String),
+}
+
+/// Archived ride tag, referencing the tag by UUID
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ArchivedRideTag {
+    pub tag: String,
+    pub order: u32,
+    pub value: ArchivedValue,
+    pub remarks: Option<String>,
+    #[serde(default)]
+    pub per_ride: bool,
+}
+
+/// Archived ride
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ArchivedRide {
+    pub journey_departure: DateTimeUtc,
+    pub journey_arrival: Option<DateTimeUtc>,
+    pub location_from: String,
+    pub location_to: String,
+    pub remarks: Option<String>,
+    pub is_template: bool,
+    #[serde(default = "ArchivedRide::default_passengers")]
+    pub passengers: u32,
+    #[serde(default)]
+    pub vat_rate: Option<f64>,
+    #[serde(default)]
+    pub status: RideStatus,
+    #[serde(default)]
+    pub arrival_estimated: bool,
+    pub tags: Vec<ArchivedRideTag>,
+}
+
+impl ArchivedRide {
+    fn default_passengers() -> u32 {
+        1
+    }
+}
+
+/// Handling of archived resources which already exist on import
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictMode {
+    /// Keep existing tags, options and rides unchanged
+    Skip,
+    /// Update existing tags, options and rides, and replace the ride tags of rides
+    Overwrite,
+    /// Create rides again. Tags and options are never duplicated: tag keys are unique,
+    /// so matching ones are kept as with [ConflictMode::Skip].
+    Duplicate,
+}
+
+impl FromStr for ConflictMode {
+    type Err = CurdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "duplicate" => Ok(Self::Duplicate),
+            _ => Err(CurdError::DeserializationError(format!("Invalid conflict mode {}, expected skip, overwrite or duplicate", s))),
+        }
+    }
+}
+
+/// Number of imported resources of one type
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct ImportCount {
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    /// Only ride tags, replaced by the ride tags of the archive
+    pub deleted: usize,
+}
+
+/// Result of an import
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct ImportReport {
+    pub tags: ImportCount,
+    pub options: ImportCount,
+    pub rides: ImportCount,
+    pub ride_tags: ImportCount,
+}
+
+/// All rides and tags of a user, without soft-deleted ones
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Archive {
+    pub manifest: Manifest,
+    pub tags: Vec<ArchivedTag>,
+    pub rides: Vec<ArchivedRide>,
+}
+
+impl Archive {
+    /// Load the rides and tags of [user_id]
+    pub async fn load(user_id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        let tags = Tag::find_all(user_id, true, false, db).await?;
+        let rides = Ride::find_all(user_id, true, false, db).await?;
+
+        let tag_uuids: HashMap<u32, &String> = tags.iter().map(|tag| (tag.id(), tag.uuid())).collect();
+        let option_uuids: HashMap<u32, &String> = tags
+            .iter()
+            .flat_map(|tag| tag.options().iter().flatten())
+            .map(|option| (option.id(), option.uuid()))
+            .collect();
+
+        let archived_tags: Vec<ArchivedTag> = tags
+            .iter()
+            .map(|tag| ArchivedTag {
+                uuid: tag.uuid().clone(),
+                tag_type: tag.tag_type.clone(),
+                tag_key: tag.tag_key().clone(),
+                tag_name: tag.tag_name().clone(),
+                unit: tag.unit.clone(),
+                remarks: tag.remarks.clone(),
+                order: tag.order,
+                aggregation: tag.aggregation.map(Into::into),
+                options: tag
+                    .options()
+                    .iter()
+                    .flatten()
+                    .map(|option| ArchivedOption {
+                        uuid: option.uuid().clone(),
+                        order: option.order,
+                        value: option.value.clone(),
+                        name: option.name.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let mut archived_rides = Vec::with_capacity(rides.len());
+        for ride in &rides {
+            let mut ride_tags = Vec::new();
+            for link in ride.tags().iter().flatten() {
+                // Links to soft-deleted tags or options are left out
+                let Some(tag) = tag_uuids.get(&link.tag_id()) else {
+                    continue;
+                };
+                let value = match &link.value {
+                    Value::Integer(value) => ArchivedValue::Integer(*value),
+                    Value::Float(value) => ArchivedValue::Float(*value),
+                    Value::String(value) => ArchivedValue::String(value.clone()),
+                    Value::DateTime(value) => ArchivedValue::DateTime(*value),
+                    Value::EnumOption(option_id) => match option_uuids.get(option_id) {
+                        Some(option) => ArchivedValue::EnumOption((*option).clone()),
+                        None => continue,
+                    },
+                };
+                ride_tags.push(ArchivedRideTag {
+                    tag: (*tag).clone(),
+                    order: link.order,
+                    value,
+                    remarks: link.remarks.clone(),
+                    per_ride: link.per_ride,
+                });
+            }
+            archived_rides.push(ArchivedRide {
+                journey_departure: ride.journey_departure,
+                journey_arrival: ride.journey_arrival,
+                location_from: ride.location_from.clone(),
+                location_to: ride.location_to.clone(),
+                remarks: ride.remarks.clone(),
+                is_template: ride.is_template,
+                passengers: ride.passengers,
+                vat_rate: ride.vat_rate,
+                status: ride.status,
+                arrival_estimated: ride.arrival_estimated,
+                tags: ride_tags,
+            });
+        }
+
+        Ok(
+            Self {
+                manifest: Manifest {
+                    format: ARCHIVE_FORMAT.to_string(),
+                    version: ARCHIVE_VERSION,
+                    created_at: chrono::Utc::now(),
+                    files: [MANIFEST_FILE, TAGS_FILE, RIDES_FILE, RIDES_CSV_FILE]
+                        .iter()
+                        .map(|file| file.to_string())
+                        .collect(),
+                    tags: archived_tags.len(),
+                    options: archived_tags.iter().map(|tag| tag.options.len()).sum(),
+                    rides: archived_rides.len(),
+                    ride_tags: archived_rides.iter().map(|ride| ride.tags.len()).sum(),
+                },
+                tags: archived_tags,
+                rides: archived_rides,
+            }
+        )
+    }
+
+    /// Text of [value] in the CSV table in [format]
+    fn csv_cell(&self, value: &ArchivedValue, format: &CsvFormat) -> String {
+        match value {
+            ArchivedValue::Integer(value) => value.to_string(),
+            ArchivedValue::Float(value) => format.number(&value.to_string()),
+            ArchivedValue::String(value) => value.clone(),
+            ArchivedValue::DateTime(value) => format.date_time(*value),
+            ArchivedValue::EnumOption(uuid) => self.tags
+                .iter()
+                .flat_map(|tag| tag.options.iter())
+                .find(|option| &option.uuid == uuid)
+                .map(|option| option.name.clone().unwrap_or_else(|| option.value.clone()))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Rides as CSV table with one column per tag, named by the tag key, in [format]
+    fn rides_csv(&self, format: &CsvFormat) -> Result<Vec<u8>, csv::Error> {
+        let mut writer = format.writer();
+        let mut header: Vec<&str> = [
+            "journey_departure",
+            "journey_arrival",
+            "location_from",
+            "location_to",
+            "remarks",
+            "is_template",
+            "passengers",
+            "vat_rate",
+            "status",
+        ]
+            .into_iter()
+            .map(|column| format.header(column))
+            .collect();
+        header.extend(self.tags.iter().map(|tag| tag.tag_key.as_str()));
+        writer.write_record(&header)?;
+        for ride in &self.rides {
+            let mut record = vec![
+                format.date_time(ride.journey_departure),
+                ride.journey_arrival.map(|arrival| format.date_time(arrival)).unwrap_or_default(),
+                ride.location_from.clone(),
+                ride.location_to.clone(),
+                ride.remarks.clone().unwrap_or_default(),
+                ride.is_template.to_string(),
+                ride.passengers.to_string(),
+                ride.vat_rate.map(|rate| format.number(&rate.to_string())).unwrap_or_default(),
+                ride.status.to_string(),
+            ];
+            for tag in &self.tags {
+                let values: Vec<String> = ride.tags
+                    .iter()
+                    .filter(|ride_tag| ride_tag.tag == tag.uuid)
+                    .map(|ride_tag| self.csv_cell(&ride_tag.value, format))
+                    .collect();
+                record.push(values.join(", "));
+            }
+            writer.write_record(&record)?;
+        }
+        writer.into_inner().map_err(|e| e.into_error().into())
+    }
+
+    /// Name of the ZIP archive, with the date of creation
+    pub fn file_name(&self) -> String {
+        format!("ptet-export-{}.zip", self.manifest.created_at.format("%Y-%m-%d"))
+    }
+
+    /// ZIP archive with the files listed in the manifest, `rides.csv` in [locale]
+    pub fn to_zip(&self, locale: Locale) -> Result<Vec<u8>, CurdError> {
+        let internal = |e: &dyn std::fmt::Display| CurdError::InternalError(format!("Cannot write archive: {}", e));
+        let files: Vec<(&str, Vec<u8>)> = vec![
+            (MANIFEST_FILE, serde_json::to_vec_pretty(&self.manifest).map_err(|e| internal(&e))?),
+            (TAGS_FILE, serde_json::to_vec_pretty(&self.tags).map_err(|e| internal(&e))?),
+            (RIDES_FILE, serde_json::to_vec_pretty(&self.rides).map_err(|e| internal(&e))?),
+            (RIDES_CSV_FILE, self.rides_csv(&CsvFormat::new(locale)).map_err(|e| internal(&e))?),
+        ];
+
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            writer.start_file(name, options).map_err(|e| internal(&e))?;
+            writer.write_all(&data).map_err(|e| internal(&e))?;
+        }
+        Ok(writer.finish().map_err(|e| internal(&e))?.into_inner())
+    }
+
+    /// Read an archive written by [Archive::to_zip]
+    pub fn from_zip(data: &[u8]) -> Result<Self, CurdError> {
+        let mut zip = ZipArchive::new(Cursor::new(data))
+            .map_err(
+                |error| {
+                    CurdError::Unprocessable(format!("Invalid ZIP archive: {}", error))
+                }
+            )?;
+        Ok(
+            Self {
+                manifest: read_json(&mut zip, MANIFEST_FILE)?,
+                tags: read_json(&mut zip, TAGS_FILE)?,
+                rides: read_json(&mut zip, RIDES_FILE)?,
+            }
+        )
+    }
+
+    /// Check that the archive can be read by this version
+    fn check_manifest(&self) -> Result<(), CurdError> {
+        if self.manifest.format != ARCHIVE_FORMAT {
+            Err(CurdError::Unprocessable(format!("Unknown archive format {}", self.manifest.format)))?;
+        }
+        if self.manifest.version > ARCHIVE_VERSION {
+            Err(
+                CurdError::Unprocessable(
+                    format!("Archive version {} is newer than the supported version {}", self.manifest.version, ARCHIVE_VERSION)
+                )
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Create the tags, options, rides and ride tags of the archive for [user_id] on
+    /// behalf of [actor]. Tags are matched by UUID or tag key, options by UUID or value
+    /// and rides by departure, locations and template flag. Matches are handled
+    /// according to [mode]. The [price_tag] is only aggregated by sum. Returns the counts
+    /// and the events to publish after [db] was committed.
+    pub async fn import(
+        &self,
+        user_id: u32,
+        mode: ConflictMode,
+        actor: &Actor,
+        price_tag: &PriceTag,
+        db: &impl ConnectionTrait,
+    ) -> Result<(ImportReport, Vec<Event>), CurdError> {
+        self.check_manifest()?;
+        let mut report = ImportReport::default();
+        let mut events = Vec::new();
+
+        // IDs of the imported or matching tags and options by their UUID in the archive
+        let mut tag_ids: HashMap<&str, u32> = HashMap::new();
+        let mut option_ids: HashMap<&str, u32> = HashMap::new();
+        let existing_tags = Tag::find_all(user_id, true, false, db).await?;
+        for archived in &self.tags {
+            let uuid = parse_uuid(&archived.uuid)?;
+            let builder = tag::CreateUpdateBuilder::new(
+                archived.tag_type.clone(),
+                archived.tag_key.clone(),
+                archived.tag_name.clone(),
+                archived.unit.clone(),
+                archived.remarks.clone(),
+            )
+                .with_order(archived.order)
+                .with_aggregation(archived.aggregation.clone())
+                .with_price_tag(price_tag);
+            let existing = existing_tags
+                .iter()
+                .find(|tag| tag.uuid() == &uuid.to_string())
+                .or_else(|| existing_tags.iter().find(|tag| tag.tag_key() == &archived.tag_key));
+            let (tag_id, existing_options) = match existing {
+                Some(tag) if mode == ConflictMode::Overwrite => {
+                    builder.update(tag.id(), actor, db).await?;
+                    let updated = Tag::find_by_id(tag.id(), true, false, db).await?;
+                    events.push(Event::new(user_id, Resource::Tag, Action::Updated, tag.id()).with_data(&updated));
+                    report.tags.updated += 1;
+                    (tag.id(), tag.options().clone().unwrap_or_default())
+                },
+                Some(tag) => {
+                    report.tags.skipped += 1;
+                    (tag.id(), tag.options().clone().unwrap_or_default())
+                },
+                None => {
+                    let tag = builder
+                        .with_uuid(uuid)
+                        .insert(user_id, actor, db)
+                        .await?;
+                    events.push(Event::new(user_id, Resource::Tag, Action::Created, tag.id()).with_data(&tag));
+                    report.tags.created += 1;
+                    (tag.id(), Vec::new())
+                },
+            };
+            tag_ids.insert(&archived.uuid, tag_id);
+
+            for archived_option in &archived.options {
+                let uuid = parse_uuid(&archived_option.uuid)?;
+                let builder = tag_option::CreateUpdateBuilder::new(
+                    archived_option.order,
+                    archived_option.value.clone(),
+                    archived_option.name.clone(),
+                );
+                let existing = existing_options
+                    .iter()
+                    .find(|option| option.uuid() == &uuid.to_string())
+                    .or_else(|| existing_options.iter().find(|option| option.value == archived_option.value));
+                let option_id = match existing {
+                    Some(option) if mode == ConflictMode::Overwrite => {
+                        builder.update(option.id(), actor, db).await?;
+                        let updated = TagOption::find_by_id(option.id(), false, db).await?;
+                        events.push(Event::new(user_id, Resource::TagOption, Action::Updated, option.id()).with_data(&updated));
+                        report.options.updated += 1;
+                        option.id()
+                    },
+                    Some(option) => {
+                        report.options.skipped += 1;
+                        option.id()
+                    },
+                    None => {
+                        let option = builder
+                            .with_uuid(uuid)
+                            .insert(tag_id, actor, db)
+                            .await?;
+                        events.push(Event::new(user_id, Resource::TagOption, Action::Created, option.id()).with_data(&option));
+                        report.options.created += 1;
+                        option.id()
+                    },
+                };
+                option_ids.insert(&archived_option.uuid, option_id);
+            }
+        }
+
+        // Reloaded to validate ride tags against the imported options
+        let tags: HashMap<u32, Tag> = Tag::find_all(user_id, true, false, db)
+            .await?
+            .into_iter()
+            .map(|tag| (tag.id(), tag))
+            .collect();
+        let existing_rides = match mode {
+            ConflictMode::Duplicate => Vec::new(),
+            _ => Ride::find_all(user_id, true, false, db).await?,
+        };
+        for archived in &self.rides {
+            let builder = ride::CreateUpdateBuilder::new(
+                archived.journey_departure,
+                archived.journey_arrival,
+                archived.location_from.clone(),
+                archived.location_to.clone(),
+                archived.remarks.clone(),
+                archived.is_template,
+            )
+                .with_passengers(archived.passengers)
+                .with_vat_rate(archived.vat_rate)
+                .with_status(archived.status)
+                .with_arrival_estimated(archived.arrival_estimated);
+            let existing = existing_rides
+                .iter()
+                .find(|ride| {
+                    ride.journey_departure == archived.journey_departure
+                        && ride.location_from == archived.location_from
+                        && ride.location_to == archived.location_to
+                        && ride.is_template == archived.is_template
+                });
+            let (ride_id, action) = match existing {
+                Some(_) if mode == ConflictMode::Skip => {
+                    report.rides.skipped += 1;
+                    report.ride_tags.skipped += archived.tags.len();
+                    continue;
+                },
+                Some(ride) => {
+                    builder.update(ride.id(), actor, db).await?;
+                    for link in ride.tags().iter().flatten() {
+                        ride_tag_link::remove(link.id(), actor, db).await?;
+                        events.push(Event::new(user_id, Resource::RideTag, Action::Deleted, link.id()));
+                        report.ride_tags.deleted += 1;
+                    }
+                    report.rides.updated += 1;
+                    (ride.id(), Action::Updated)
+                },
+                None => {
+                    let ride = builder.insert(user_id, actor, db).await?;
+                    report.rides.created += 1;
+                    (ride.id(), Action::Created)
+                },
+            };
+
+            let mut linked = HashSet::new();
+            for archived_link in &archived.tags {
+                let tag = tag_ids
+                    .get(archived_link.tag.as_str())
+                    .and_then(|tag_id| tags.get(tag_id))
+                    .ok_or_else(|| CurdError::Unprocessable(format!("Ride tag references unknown tag {}", archived_link.tag)))?;
+                if !linked.insert(tag.id()) {
+                    Err(CurdError::Unprocessable(format!("Tag {} is linked more than once to a ride", archived_link.tag)))?;
+                }
+                let value = match &archived_link.value {
+                    ArchivedValue::Integer(value) => Value::Integer(*value),
+                    ArchivedValue::Float(value) => Value::Float(*value),
+                    ArchivedValue::String(value) => Value::String(value.clone()),
+                    ArchivedValue::DateTime(value) => Value::DateTime(*value),
+                    ArchivedValue::EnumOption(uuid) => match option_ids.get(uuid.as_str()) {
+                        Some(option_id) => Value::EnumOption(*option_id),
+                        None => Err(CurdError::Unprocessable(format!("Ride tag references unknown option {}", uuid)))?,
+                    },
+                };
+                let builder = ride_tag_link::CreateUpdateBuilder::new(archived_link.order, value, archived_link.remarks.clone())
+                    .with_per_ride(archived_link.per_ride);
+                builder.validate(tag)?;
+                let link = builder.insert(ride_id, tag.id(), actor, db).await?;
+                events.push(Event::new(user_id, Resource::RideTag, Action::Created, link.id()).with_data(&link));
+                report.ride_tags.created += 1;
+            }
+            let ride = Ride::find_by_id(ride_id, true, false, db).await?;
+            events.push(Event::new(user_id, Resource::Ride, action, ride_id).with_data(&ride));
+        }
+
+        Ok((report, events))
+    }
+}
+
+/// Parse the UUID of an archived tag or option
+fn parse_uuid(uuid: &str) -> Result<Uuid, CurdError> {
+    Uuid::parse_str(uuid)
+        .map_err(
+            |error| {
+                CurdError::Unprocessable(format!("Invalid UUID {}: {}", uuid, error))
+            }
+        )
+}
+
+/// Deserialize the JSON file [name] of [zip]
+fn read_json<T: DeserializeOwned>(zip: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<T, CurdError> {
+    let file = zip
+        .by_name(name)
+        .map_err(
+            |error| {
+                CurdError::Unprocessable(format!("Cannot read {} from archive: {}", name, error))
+            }
+        )?;
+    if file.size() > MAX_FILE_SIZE {
+        Err(CurdError::Unprocessable(format!("{} in archive is too large", name)))?;
+    }
+    // The declared size is not trusted
+    serde_json::from_reader(file.take(MAX_FILE_SIZE))
+        .map_err(
+            |error| {
+                CurdError::Unprocessable(format!("Invalid {} in archive: {}", name, error))
+            }
+        )
+}
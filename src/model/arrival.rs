@@ -0,0 +1,99 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::TimeDelta;
+use sea_orm::{
+    prelude::*,
+    sea_query::Func,
+    QuerySelect,
+};
+use entity::ride;
+use super::error::CurdError;
+use super::ride::Ride;
+
+/// Rides with an arrival on the route needed for an estimate
+const MIN_RIDES: usize = 2;
+
+/// Rocket state for completing missing arrivals
+#[derive(Debug, Clone)]
+pub struct ArrivalEstimator {
+    enabled: bool,
+}
+
+impl ArrivalEstimator {
+    /// Estimator filling arrivals if [enabled]
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+        }
+    }
+
+    /// Whether missing arrivals are estimated
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// [ride] of [user_id] with the arrival estimated, if it has none and is no template.
+    /// Rides without enough history on the route, or with estimation disabled, are
+    /// returned unchanged. The ride [exclude_id] is left out of the history.
+    pub async fn complete(&self, user_id: u32, mut ride: Ride, exclude_id: Option<u32>, db: &impl ConnectionTrait) -> Result<Ride, CurdError> {
+        if !self.enabled || ride.is_template || ride.journey_arrival.is_some() {
+            return Ok(ride);
+        }
+        if let Some(duration) = average_duration(user_id, &ride.location_from, &ride.location_to, exclude_id, db).await? {
+            ride.journey_arrival = Some(ride.journey_departure + duration);
+            ride.arrival_estimated = true;
+        }
+        Ok(ride)
+    }
+}
+
+/// Average duration of the rides of [user_id] from [location_from] to [location_to],
+/// compared case-insensitively. Only rides with a recorded arrival after the departure
+/// count, so estimates do not feed back into later ones. `None` with fewer than
+/// [MIN_RIDES] rides.
+pub async fn average_duration(
+    user_id: u32,
+    location_from: &str,
+    location_to: &str,
+    exclude_id: Option<u32>,
+    db: &impl ConnectionTrait,
+) -> Result<Option<TimeDelta>, CurdError> {
+    let mut query = ride::Entity::find()
+        .select_only()
+        .column(ride::Column::JourneyDeparture)
+        .column(ride::Column::JourneyArrival)
+        .filter(ride::Column::UserId.eq(user_id))
+        .filter(ride::Column::IsTemplate.eq(false))
+        .filter(ride::Column::ArrivalEstimated.eq(false))
+        .filter(ride::Column::DeletedAt.is_null())
+        .filter(ride::Column::JourneyArrival.is_not_null())
+        .filter(Expr::expr(Func::lower(Expr::col(ride::Column::LocationFrom))).eq(location_from.trim().to_lowercase()))
+        .filter(Expr::expr(Func::lower(Expr::col(ride::Column::LocationTo))).eq(location_to.trim().to_lowercase()));
+    if let Some(exclude_id) = exclude_id {
+        query = query.filter(ride::Column::Id.ne(exclude_id));
+    }
+    let durations: Vec<TimeDelta> = query
+        .into_tuple::<(DateTimeUtc, DateTimeUtc)>()
+        .all(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .into_iter()
+        .map(|(departure, arrival)| arrival - departure)
+        .filter(|duration| *duration > TimeDelta::zero())
+        .collect();
+    if durations.len() < MIN_RIDES {
+        return Ok(None);
+    }
+    let seconds: i64 = durations.iter().map(|duration| duration.num_seconds()).sum();
+    Ok(Some(TimeDelta::seconds(seconds / durations.len() as i64)))
+}
@@ -0,0 +1,196 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::SubsecRound;
+use serde::Serialize;
+use serde_json::{json, Map};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::*, Condition, NotSet, QueryOrder, QuerySelect, Set};
+use entity::audit_log;
+use super::error::CurdError;
+use super::event::{Action, Resource};
+
+/// Originator of a change, recorded in the audit log
+#[derive(Debug, Clone, Default)]
+pub struct Actor {
+    /// Authenticated user, None for jobs of the server
+    user_id: Option<u32>,
+    /// ID of the HTTP request
+    request_id: Option<String>,
+}
+
+impl Actor {
+    pub fn new(user_id: Option<u32>, request_id: Option<String>) -> Self {
+        Self {
+            user_id,
+            request_id,
+        }
+    }
+}
+
+/// JSON structure of an audit log entry
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct AuditEntry {
+    id: u32,
+    created_at: DateTimeUtc,
+    /// User who made the change, not set for jobs of the server
+    user_id: Option<u32>,
+    /// Kind of resource, e.g. `ride`
+    resource: String,
+    resource_id: u32,
+    /// `created`, `updated` or `deleted`
+    action: String,
+    /// Changed fields with old and new value, e.g. `{"remarks": {"old": null, "new": "Late"}}`
+    diff: serde_json::Value,
+    request_id: Option<String>,
+    /// Hash of the entry in the chain of the audit log, not set until chained
+    hash: Option<String>,
+}
+
+impl From<audit_log::Model> for AuditEntry {
+    fn from(model: audit_log::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            user_id: model.user_id,
+            resource: model.resource,
+            resource_id: model.resource_id,
+            action: model.action,
+            diff: serde_json::from_str(&model.diff).unwrap_or(serde_json::Value::Null),
+            request_id: model.request_id,
+            hash: model.hash,
+        }
+    }
+}
+
+/// Filter of audit log queries. Unset fields match all entries.
+#[derive(Debug, Clone)]
+pub struct AuditFilter {
+    pub user_id: Option<u32>,
+    /// Kind of resource, e.g. `ride`
+    pub resource: Option<String>,
+    pub resource_id: Option<u32>,
+}
+
+impl AuditFilter {
+    fn condition(&self) -> Condition {
+        let mut condition = Condition::all();
+        if let Some(user_id) = self.user_id {
+            condition = condition.add(audit_log::Column::UserId.eq(user_id));
+        }
+        if let Some(resource) = &self.resource {
+            condition = condition.add(audit_log::Column::Resource.eq(resource.as_str()));
+        }
+        if let Some(resource_id) = self.resource_id {
+            condition = condition.add(audit_log::Column::ResourceId.eq(resource_id));
+        }
+        condition
+    }
+}
+
+impl AuditEntry {
+    /// Count all entries matching [filter]
+    pub async fn count_all(filter: &AuditFilter, db: &impl ConnectionTrait) -> Result<u64, CurdError> {
+        audit_log::Entity::find()
+            .filter(filter.condition())
+            .count(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )
+    }
+
+    /// Fetch entries matching [filter], newest first. Use pagination
+    pub async fn find_all_paginated(filter: &AuditFilter, db: &impl ConnectionTrait, page: u64, size: u64) -> Result<Vec<Self>, CurdError> {
+        let models = audit_log::Entity::find()
+            .filter(filter.condition())
+            .order_by_desc(audit_log::Column::Id)
+            .offset(page * size)
+            .limit(size)
+            .all(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(models.into_iter().map(Self::from).collect())
+    }
+}
+
+/// Fields which differ between [before] and [after], with old and new value. Missing
+/// models count as all fields null.
+fn diff(before: Option<serde_json::Value>, after: Option<serde_json::Value>) -> serde_json::Value {
+    let as_map = |value: Option<serde_json::Value>| match value {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    let before = as_map(before);
+    let after = as_map(after);
+    let mut changes = Map::new();
+    for key in before.keys().chain(after.keys()) {
+        let old = before.get(key).unwrap_or(&serde_json::Value::Null);
+        let new = after.get(key).unwrap_or(&serde_json::Value::Null);
+        if old != new && !changes.contains_key(key) {
+            changes.insert(key.clone(), json!({ "old": old, "new": new }));
+        }
+    }
+    serde_json::Value::Object(changes)
+}
+
+/// Record that [actor] changed [resource] [resource_id] from [before] to [after], which
+/// are the database models. [before] is None for created resources and [after] for
+/// resources deleted from the database. The monthly ride summaries affected by the
+/// change are marked as stale.
+pub async fn record<M: Serialize>(
+    actor: &Actor,
+    resource: Resource,
+    resource_id: u32,
+    action: Action,
+    before: Option<&M>,
+    after: Option<&M>,
+    db: &impl ConnectionTrait,
+) -> Result<(), CurdError> {
+    let to_value = |model: Option<&M>| {
+        model
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| CurdError::InternalError(e.to_string()))
+    };
+    let before = to_value(before)?;
+    let after = to_value(after)?;
+    super::ride_summary::invalidate(resource, before.as_ref(), after.as_ref(), db).await?;
+    let diff = diff(before, after);
+    let model = audit_log::ActiveModel {
+        id: NotSet,
+        // PostgreSQL keeps microseconds, so the hash over the entry stays valid when a
+        // backup is restored into it
+        created_at: Set(chrono::Utc::now().trunc_subsecs(6)),
+        user_id: Set(actor.user_id),
+        resource: Set(resource.to_string()),
+        resource_id: Set(resource_id),
+        action: Set(action.to_string()),
+        diff: Set(diff.to_string()),
+        request_id: Set(actor.request_id.clone()),
+        prev_hash: NotSet,
+        hash: NotSet,
+        signature: NotSet,
+        chain_seq: NotSet,
+    };
+    model
+        .insert(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(())
+}
@@ -0,0 +1,270 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::ops::DerefMut;
+use std::sync::Arc;
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, TransactionTrait};
+use jwt_auth::jwt::{TokenProducer, TokenVerifier};
+use jwt_auth::keys::KeyCache;
+use entity::audit_log;
+use super::error::CurdError;
+
+/// Entries chained in one transaction at most
+const BATCH_SIZE: u64 = 500;
+/// Subject of the signatures over the chain
+const SUBJECT: &str = "audit_log";
+/// Claim of a signature with the ID of the signed entry
+const ID_CLAIM: &str = "ptet:audit_id";
+/// Claim of a signature with the hash of the signed entry
+const HASH_CLAIM: &str = "ptet:audit_hash";
+
+/// Result of checking the hash chain of the audit log
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct ChainReport {
+    /// Whether all chained entries are unchanged and all signatures are valid
+    pub valid: bool,
+    /// Number of chained entries checked
+    pub chained: u64,
+    /// Number of entries not chained yet, which are chained by the next run
+    pub pending: u64,
+    /// ID of the newest chained entry
+    pub head: Option<u32>,
+    /// Hash of the newest chained entry
+    pub head_hash: Option<String>,
+    /// ID of the newest signed entry. Entries up to it are proven unchanged by its
+    /// signature.
+    pub signed_until: Option<u32>,
+    /// Signature of the newest signed entry, a JWS to keep outside of the database as
+    /// receipt of the state of the audit log
+    pub signature: Option<String>,
+    /// ID of the first entry failing the check
+    pub broken_at: Option<u32>,
+    /// Why the check failed
+    pub error: Option<String>,
+}
+
+/// Hash of [model] chained to [prev_hash], as hex. Covers all fields except the hash
+/// and the signature.
+fn entry_hash(model: &audit_log::Model, prev_hash: Option<&str>) -> String {
+    let content = json!([
+        model.id,
+        model.created_at,
+        model.user_id,
+        model.resource,
+        model.resource_id,
+        model.action,
+        model.diff,
+        model.request_id,
+        prev_hash,
+    ]);
+    Sha256::digest(content.to_string().as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Hash chain of the audit log. Each entry holds the hash of the previous one and a
+/// hash over both, and the newest entry of each run is signed with the default key of
+/// the key cache. Changing, inserting or deleting entries breaks the chain, and
+/// rewriting the chain needs the private key.
+///
+/// Entries are appended to the chain in the order they become visible, kept in
+/// `chain_seq`. An entry whose transaction commits after entries with higher IDs were
+/// chained, e.g. of a long import, is appended by the next run instead of being left
+/// out.
+#[derive(Clone)]
+pub struct AuditChain {
+    key_cache: Arc<RwLock<KeyCache>>,
+    /// Issuer of the signatures, the server base URI
+    issuer: String,
+}
+
+impl AuditChain {
+    pub fn new(key_cache: Arc<RwLock<KeyCache>>, issuer: String) -> Self {
+        Self {
+            key_cache,
+            issuer,
+        }
+    }
+
+    /// Chain the entries not chained yet, oldest first, and sign the newest of them.
+    /// Returns the number of chained entries, at most one batch. Fails with
+    /// [CurdError::Conflict] if another server instance chains concurrently.
+    pub async fn extend(&self, db: &DatabaseConnection) -> Result<u64, CurdError> {
+        let txn = db.begin()
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        let head = audit_log::Entity::find()
+            .filter(audit_log::Column::ChainSeq.is_not_null())
+            .order_by_desc(audit_log::Column::ChainSeq)
+            .one(&txn)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        let entries = audit_log::Entity::find()
+            .filter(audit_log::Column::ChainSeq.is_null())
+            .order_by_asc(audit_log::Column::Id)
+            .limit(BATCH_SIZE)
+            .all(&txn)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        let Some(last) = entries.last().map(|entry| entry.id) else {
+            return Ok(0);
+        };
+
+        let mut chain_seq = head.as_ref().and_then(|head| head.chain_seq).unwrap_or_default();
+        let mut prev_hash = head.and_then(|head| head.hash);
+        for entry in &entries {
+            chain_seq += 1;
+            let hash = entry_hash(entry, prev_hash.as_deref());
+            let mut update = audit_log::Entity::update_many()
+                .col_expr(audit_log::Column::PrevHash, Expr::value(prev_hash.clone()))
+                .col_expr(audit_log::Column::Hash, Expr::value(hash.clone()))
+                .col_expr(audit_log::Column::ChainSeq, Expr::value(chain_seq))
+                .filter(audit_log::Column::Id.eq(entry.id))
+                .filter(audit_log::Column::ChainSeq.is_null());
+            if entry.id == last {
+                update = update.col_expr(audit_log::Column::Signature, Expr::value(self.sign(entry.id, &hash).await?));
+            }
+            // A concurrent run taking the same position violates the unique index of
+            // `chain_seq` and rolls back as well
+            let result = update
+                .exec(&txn)
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::Conflict(format!("Audit log entry {} was chained concurrently: {}", entry.id, error))
+                    }
+                )?;
+            if result.rows_affected == 0 {
+                // Dropping the transaction rolls back
+                return Err(CurdError::Conflict(format!("Audit log entry {} was chained concurrently", entry.id)));
+            }
+            prev_hash = Some(hash);
+        }
+        txn.commit()
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(entries.len() as u64)
+    }
+
+    /// JWS over entry [id] with [hash]. It has no audience and does not expire, so that
+    /// it is not accepted as access token.
+    async fn sign(&self, id: u32, hash: &str) -> Result<String, CurdError> {
+        let mut key_cache = self.key_cache.write().await;
+        TokenProducer::new(key_cache.deref_mut())
+            .with_issuer(&self.issuer)
+            .add_claims_from_json(json!({ ID_CLAIM: id, HASH_CLAIM: hash }))
+            .and_then(|producer| producer.produce(SUBJECT))
+            .map(String::from)
+            .map_err(|e| CurdError::InternalError(format!("Cannot sign audit log: {}", e)))
+    }
+
+    /// Check that [signature] is valid and signs entry [id] with [hash]
+    async fn check_signature(&self, id: u32, hash: &str, signature: &str) -> Result<(), String> {
+        let mut key_cache = self.key_cache.write().await;
+        let (token, _) = TokenVerifier::new(key_cache.deref_mut())
+            .expect_issuer(&self.issuer)
+            .disable_time_check()
+            .verify(signature)
+            .map_err(|e| format!("Signature is invalid: {}", e))?;
+        let claims = &token.claims().private;
+        if token.claims().registered.subject.as_deref() != Some(SUBJECT)
+            || claims.get(ID_CLAIM) != Some(&json!(id))
+            || claims.get(HASH_CLAIM) != Some(&json!(hash)) {
+            Err("Signature is for another entry")?;
+        }
+        Ok(())
+    }
+
+    /// Check all entries of the chain in chain order, up to the first broken one
+    pub async fn verify(&self, db: &impl ConnectionTrait) -> Result<ChainReport, CurdError> {
+        let mut report = ChainReport {
+            pending: audit_log::Entity::find()
+                .filter(audit_log::Column::ChainSeq.is_null())
+                .count(db)
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?,
+            ..Default::default()
+        };
+        let mut prev_hash: Option<String> = None;
+        let mut after = 0;
+        loop {
+            let entries = audit_log::Entity::find()
+                .filter(audit_log::Column::ChainSeq.gt(after))
+                .order_by_asc(audit_log::Column::ChainSeq)
+                .limit(BATCH_SIZE)
+                .all(db)
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            let Some(last) = entries.last() else {
+                break;
+            };
+            after = last.chain_seq.unwrap_or_default();
+            for entry in entries {
+                let error = match &entry.hash {
+                    None => Some("Entry was changed, its hash is missing".to_string()),
+                    Some(_) if entry.prev_hash != prev_hash => {
+                        Some("Entry does not follow the previous one, entries were deleted or inserted".to_string())
+                    },
+                    Some(hash) if *hash != entry_hash(&entry, prev_hash.as_deref()) => {
+                        Some("Entry was changed".to_string())
+                    },
+                    Some(hash) => match &entry.signature {
+                        Some(signature) => self.check_signature(entry.id, hash, signature).await.err(),
+                        None => None,
+                    },
+                };
+                if let Some(error) = error {
+                    report.broken_at = Some(entry.id);
+                    report.error = Some(error);
+                    return Ok(report);
+                }
+                let hash = entry.hash.unwrap_or_default();
+                report.chained += 1;
+                report.head = Some(entry.id);
+                report.head_hash = Some(hash.clone());
+                if entry.signature.is_some() {
+                    report.signed_until = Some(entry.id);
+                    report.signature = entry.signature;
+                }
+                prev_hash = Some(hash);
+            }
+        }
+        report.valid = true;
+        Ok(report)
+    }
+}
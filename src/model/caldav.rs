@@ -0,0 +1,569 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::time::Duration;
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{
+    prelude::*,
+    sea_query::OnConflict,
+    Set,
+    NotSet,
+    Unchanged,
+};
+use entity::{caldav_event, caldav_settings, inbox_item, ride, timestamps};
+use super::error::CurdError;
+use super::geocode::USER_AGENT;
+
+/// Timeout of requests to the CalDAV server
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Events starting up to this long before the sync are pulled
+const PULL_WINDOW: TimeDelta = TimeDelta::days(30);
+/// Sender of inbox items pulled from the calendar
+pub const INBOX_SENDER: &str = "caldav";
+/// Prefix of the UIDs of pushed events
+const UID_PREFIX: &str = "ptet-ride-";
+
+/// JSON structure of the CalDAV settings of a user
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CaldavSettings {
+    /// URL of the calendar collection, e.g.
+    /// `https://cloud.example.tld/remote.php/dav/calendars/user/rides/`
+    pub calendar_url: String,
+    pub username: String,
+    /// Password, preferably an app password. Only written, kept on update if not given.
+    #[serde(skip_serializing, default)]
+    pub password: Option<String>,
+    /// Push rides as calendar events
+    pub push: bool,
+    /// Pull events whose summary contains [pull_keyword] into the inbox as draft rides
+    pub pull: bool,
+    #[serde(default = "CaldavSettings::default_pull_keyword")]
+    pub pull_keyword: String,
+    #[serde(skip_deserializing)]
+    last_synced_at: Option<DateTimeUtc>,
+    /// Error of the last synchronization, if it failed
+    #[serde(skip_deserializing)]
+    last_error: Option<String>,
+}
+
+impl From<caldav_settings::Model> for CaldavSettings {
+    fn from(model: caldav_settings::Model) -> Self {
+        Self {
+            calendar_url: model.calendar_url,
+            username: model.username,
+            password: None,
+            push: model.push,
+            pull: model.pull,
+            pull_keyword: model.pull_keyword,
+            last_synced_at: model.last_synced_at,
+            last_error: model.last_error,
+        }
+    }
+}
+
+impl CaldavSettings {
+    fn default_pull_keyword() -> String {
+        "#ride".to_string()
+    }
+
+    /// Fetch the settings of [user_id]
+    pub async fn find(user_id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        Ok(Self::from(find_model(user_id, db).await?))
+    }
+
+    /// Insert or update the settings of [user_id]
+    pub async fn save(self, user_id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        match reqwest::Url::parse(&self.calendar_url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => (),
+            _ => Err(CurdError::DeserializationError(format!("Invalid calendar URL {}", self.calendar_url)))?,
+        }
+        if self.pull && self.pull_keyword.trim().is_empty() {
+            Err(CurdError::DeserializationError("pull_keyword must not be empty".to_string()))?;
+        }
+        let existing = caldav_settings::Entity::find()
+            .filter(caldav_settings::Column::UserId.eq(user_id))
+            .one(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        let password = match (self.password, &existing) {
+            (Some(password), _) => Set(password),
+            (None, Some(_)) => NotSet,
+            (None, None) => Err(CurdError::DeserializationError("password is required".to_string()))?,
+        };
+        // All rides are pushed to a new calendar
+        let last_synced_at = match &existing {
+            Some(existing) if existing.calendar_url == self.calendar_url => NotSet,
+            _ => Set(None),
+        };
+        let model = caldav_settings::ActiveModel {
+            id: existing.as_ref().map_or(NotSet, |existing| Unchanged(existing.id)),
+            created_at: NotSet,
+            updated_at: NotSet,
+            user_id: Set(user_id),
+            calendar_url: Set(self.calendar_url),
+            username: Set(self.username),
+            password,
+            push: Set(self.push),
+            pull: Set(self.pull),
+            pull_keyword: Set(self.pull_keyword),
+            last_synced_at,
+            last_error: NotSet,
+        };
+        let result = if existing.is_some() {
+            model.update(db).await
+        } else {
+            model.insert(db).await
+        }
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(Self::from(result))
+    }
+}
+
+/// Remove the settings of [user_id]
+pub async fn remove(user_id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let result = caldav_settings::Entity::delete_many()
+        .filter(caldav_settings::Column::UserId.eq(user_id))
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if result.rows_affected == 0 {
+        Err(CurdError::NotFound)
+    } else {
+        Ok(())
+    }
+}
+
+async fn find_model(user_id: u32, db: &impl ConnectionTrait) -> Result<caldav_settings::Model, CurdError> {
+    caldav_settings::Entity::find()
+        .filter(caldav_settings::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)
+}
+
+/// Result of a synchronization
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct SyncReport {
+    /// Rides created or updated in the calendar
+    pub pushed: u32,
+    /// Events of deleted rides removed from the calendar
+    pub removed: u32,
+    /// Events pulled into the inbox
+    pub pulled: u32,
+}
+
+/// Synchronize the rides of [user_id] with the calendar of the settings. The outcome is
+/// recorded in the settings.
+pub async fn sync(user_id: u32, db: &impl ConnectionTrait) -> Result<SyncReport, CurdError> {
+    let settings = find_model(user_id, db).await?;
+    let started_at = chrono::Utc::now();
+    let result = Client::new(&settings)?.sync(&settings, started_at, db).await;
+    let model = caldav_settings::ActiveModel {
+        id: Unchanged(settings.id),
+        last_synced_at: match result {
+            Ok(_) => Set(Some(started_at)),
+            Err(_) => NotSet,
+        },
+        last_error: Set(result.as_ref().err().map(|error| error.to_string())),
+        ..Default::default()
+    };
+    model
+        .update(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    result
+}
+
+/// Users with CalDAV settings
+pub async fn find_users(db: &impl ConnectionTrait) -> Result<Vec<u32>, CurdError> {
+    let models = caldav_settings::Entity::find()
+        .all(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(models.into_iter().map(|model| model.user_id).collect())
+}
+
+/// CalDAV requests with the credentials of a user
+struct Client {
+    client: reqwest::Client,
+    /// Calendar URL ending with a slash
+    base: reqwest::Url,
+    username: String,
+    password: String,
+}
+
+impl Client {
+    fn new(settings: &caldav_settings::Model) -> Result<Self, CurdError> {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(
+                |error| {
+                    CurdError::InternalError(error.to_string())
+                }
+            )?;
+        let mut url = settings.calendar_url.clone();
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+        let base = reqwest::Url::parse(&url)
+            .map_err(
+                |error| {
+                    CurdError::Unprocessable(format!("Invalid calendar URL: {}", error))
+                }
+            )?;
+        Ok(
+            Self {
+                client,
+                base,
+                username: settings.username.clone(),
+                password: settings.password.clone(),
+            }
+        )
+    }
+
+    async fn sync(&self, settings: &caldav_settings::Model, now: DateTimeUtc, db: &impl ConnectionTrait) -> Result<SyncReport, CurdError> {
+        let mut report = SyncReport::default();
+        if settings.push {
+            self.push(settings, &mut report, db).await?;
+        }
+        if settings.pull {
+            self.pull(settings, now, &mut report, db).await?;
+        }
+        Ok(report)
+    }
+
+    /// Push the rides changed since the last synchronization
+    async fn push(&self, settings: &caldav_settings::Model, report: &mut SyncReport, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+        let mut query = ride::Entity::find()
+            .filter(ride::Column::UserId.eq(settings.user_id))
+            .filter(ride::Column::IsTemplate.eq(false));
+        if let Some(last_synced_at) = settings.last_synced_at {
+            query = query.filter(ride::Column::UpdatedAt.gte(last_synced_at));
+        }
+        let rides = query
+            .all(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        for ride in rides {
+            let url = self.event_url(ride.id)?;
+            if ride.deleted_at.is_some() {
+                let response = self.request(reqwest::Method::DELETE, url).send().await;
+                match response {
+                    Ok(response) if response.status() == reqwest::StatusCode::NOT_FOUND => (),
+                    response => {
+                        check(response)?;
+                        report.removed += 1;
+                    },
+                }
+            } else {
+                let response = self.request(reqwest::Method::PUT, url)
+                    .header(reqwest::header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+                    .body(to_ical(&ride))
+                    .send()
+                    .await;
+                check(response)?;
+                report.pushed += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Pull the flagged events around [now] which have not been pulled before
+    async fn pull(&self, settings: &caldav_settings::Model, now: DateTimeUtc, report: &mut SyncReport, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><C:calendar-data/></D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:time-range start="{}" end="{}"/>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#,
+            format_time(now - PULL_WINDOW),
+            format_time(now + PULL_WINDOW),
+        );
+        let method = reqwest::Method::from_bytes(b"REPORT").expect("Valid method");
+        let response = self.request(method, self.base.clone())
+            .header("Depth", "1")
+            .header(reqwest::header::CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(body)
+            .send()
+            .await;
+        let multistatus = check(response)?
+            .text()
+            .await
+            .map_err(
+                |error| {
+                    CurdError::Upstream(format!("CalDAV response cannot be read: {}", error))
+                }
+            )?;
+
+        let keyword = settings.pull_keyword.to_lowercase();
+        for event in parse_events(&unescape_xml(&multistatus)) {
+            if event.uid.starts_with(UID_PREFIX) || !event.summary.to_lowercase().contains(&keyword) {
+                continue;
+            }
+            let inserted = caldav_event::Entity::insert(
+                caldav_event::ActiveModel {
+                    id: NotSet,
+                    created_at: Set(timestamps::now()),
+                    user_id: Set(settings.user_id),
+                    uid: Set(event.uid.clone()),
+                }
+            )
+                .on_conflict(
+                    OnConflict::columns([caldav_event::Column::UserId, caldav_event::Column::Uid])
+                        .do_nothing()
+                        .to_owned()
+                )
+                .exec_without_returning(db)
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            if inserted == 0 {
+                continue;
+            }
+            let (location_from, location_to) = match event.location.as_deref().and_then(split_route) {
+                Some((from, to)) => (Some(from), Some(to)),
+                None => (event.location.clone(), None),
+            };
+            inbox_item::ActiveModel {
+                id: NotSet,
+                created_at: NotSet,
+                user_id: Set(settings.user_id),
+                mail_from: Set(INBOX_SENDER.to_string()),
+                subject: Set(event.summary),
+                body: Set(event.description.unwrap_or_default()),
+                journey_departure: Set(event.start),
+                location_from: Set(location_from),
+                location_to: Set(location_to),
+                amount: Set(None),
+                currency: Set(None),
+            }
+                .insert(db)
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            report.pulled += 1;
+        }
+        Ok(())
+    }
+
+    fn request(&self, method: reqwest::Method, url: reqwest::Url) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, url)
+            .basic_auth(&self.username, Some(&self.password))
+    }
+
+    /// URL of the event of ride [ride_id]
+    fn event_url(&self, ride_id: u32) -> Result<reqwest::Url, CurdError> {
+        self.base
+            .join(&format!("{}{}.ics", UID_PREFIX, ride_id))
+            .map_err(
+                |error| {
+                    CurdError::InternalError(error.to_string())
+                }
+            )
+    }
+}
+
+/// Response of a successful request
+fn check(response: Result<reqwest::Response, reqwest::Error>) -> Result<reqwest::Response, CurdError> {
+    response
+        .and_then(|response| response.error_for_status())
+        .map_err(
+            |error| {
+                CurdError::Upstream(format!("CalDAV request failed: {}", error))
+            }
+        )
+}
+
+/// iCalendar UTC date-time
+fn format_time(time: DateTimeUtc) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape [text] for an iCalendar property value
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold [line] after 75 octets as iCalendar requires
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Calendar with the event of [ride]. Rides without arrival last a minute.
+fn to_ical(ride: &ride::Model) -> String {
+    let end = ride.journey_arrival.unwrap_or(ride.journey_departure + TimeDelta::minutes(1));
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//{}//EN", USER_AGENT),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}{}", UID_PREFIX, ride.id),
+        format!("DTSTAMP:{}", format_time(ride.updated_at)),
+        format!("DTSTART:{}", format_time(ride.journey_departure)),
+        format!("DTEND:{}", format_time(end)),
+        format!("SUMMARY:{}", escape_text(&format!("{} → {}", ride.location_from, ride.location_to))),
+        format!("LOCATION:{}", escape_text(&ride.location_from)),
+    ];
+    if let Some(remarks) = &ride.remarks {
+        lines.push(format!("DESCRIPTION:{}", escape_text(remarks)));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Event found in a calendar
+#[derive(Debug, Default)]
+struct CalendarEvent {
+    uid: String,
+    summary: String,
+    description: Option<String>,
+    location: Option<String>,
+    start: Option<DateTimeUtc>,
+}
+
+/// Replace the entities of the XML text [text]
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&amp;", "&")
+}
+
+/// Unescape an iCalendar property value
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => (),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Start time of a `DTSTART` value. Times with a time zone parameter are taken as UTC,
+/// dates as midnight.
+fn parse_time(value: &str) -> Option<DateTimeUtc> {
+    let value = value.trim_end_matches('Z');
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(|date| date.and_time(Default::default())))
+        .map(|time| time.and_utc())
+}
+
+/// Events of the calendars in [text]
+fn parse_events(text: &str) -> Vec<CalendarEvent> {
+    // Unfold continuation lines first
+    let unfolded = text
+        .replace("\r\n ", "")
+        .replace("\r\n\t", "")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut events = Vec::new();
+    let mut event: Option<CalendarEvent> = None;
+    for line in unfolded.lines() {
+        let line = line.trim_end_matches('\r');
+        match line {
+            "BEGIN:VEVENT" => event = Some(CalendarEvent::default()),
+            "END:VEVENT" => events.extend(event.take().filter(|event| !event.uid.is_empty())),
+            _ => {
+                let (Some(event), Some((name, value))) = (event.as_mut(), line.split_once(':')) else {
+                    continue;
+                };
+                let name = name.split(';').next().unwrap_or_default().to_uppercase();
+                match name.as_str() {
+                    "UID" => event.uid = value.to_string(),
+                    "SUMMARY" => event.summary = unescape_text(value),
+                    "DESCRIPTION" => event.description = Some(unescape_text(value)),
+                    "LOCATION" => event.location = Some(unescape_text(value)),
+                    "DTSTART" => event.start = parse_time(value),
+                    _ => (),
+                }
+            },
+        }
+    }
+    events
+}
+
+/// Locations of a route `A → B` or `A - B`
+fn split_route(location: &str) -> Option<(String, String)> {
+    ["→", "->", " - "]
+        .iter()
+        .find_map(|separator| location.split_once(separator))
+        .map(|(from, to)| (from.trim().to_string(), to.trim().to_string()))
+}
@@ -0,0 +1,197 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::{BTreeMap, HashSet};
+use chrono::{Datelike, NaiveTime, TimeDelta, Timelike};
+use serde::Serialize;
+use rocket_okapi::okapi::schemars;
+use sea_orm::{
+    prelude::*,
+    QuerySelect,
+};
+use entity::{ride_tag, tag_descriptor, tag_descriptor::TagType};
+use super::audit::Actor;
+use super::error::CurdError;
+use super::ride::Ride;
+use super::ride_tag_link::{self, RideTagLink, Value};
+use super::tag::Tag;
+
+/// Days before now whose rides are analysed
+const WINDOW_DAYS: i64 = 56;
+/// Days with a ride on the route at a similar time making a commute
+const MIN_DAYS: usize = 4;
+/// Maximum minutes between the earliest and latest departure of a commute
+const MAX_SPREAD_MINUTES: u32 = 60;
+
+/// Ride to create as template, the body of `POST /ride`
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct TemplateProposal {
+    pub journey_departure: DateTimeUtc,
+    pub location_from: String,
+    pub location_to: String,
+    pub is_template: bool,
+}
+
+/// Recurring ride on the same route at a similar time
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Commute {
+    pub location_from: String,
+    pub location_to: String,
+    /// Median time of departure in UTC as `HH:MM`
+    pub departure: String,
+    /// Days of the week with rides, e.g. `Mon`
+    pub weekdays: Vec<String>,
+    /// Number of days with rides in the last 8 weeks
+    pub days: usize,
+    pub ride_ids: Vec<u32>,
+    /// Template to create, if there is no template for the route
+    pub template: Option<TemplateProposal>,
+}
+
+/// Commutes in [rides] departing within 8 weeks before [now]. Rides are grouped by route,
+/// compared case-insensitively, and by time of day.
+pub fn detect(rides: &[Ride], now: DateTimeUtc) -> Vec<Commute> {
+    let start = now - TimeDelta::days(WINDOW_DAYS);
+    let templates: HashSet<(String, String)> = rides
+        .iter()
+        .filter(|ride| ride.is_template)
+        .map(route_of)
+        .collect();
+    let mut routes: BTreeMap<(String, String), Vec<&Ride>> = BTreeMap::new();
+    for ride in rides.iter().filter(|ride| !ride.is_template && ride.journey_departure >= start && ride.journey_departure <= now) {
+        routes.entry(route_of(ride)).or_default().push(ride);
+    }
+
+    let mut commutes = Vec::new();
+    for (route, mut rides) in routes {
+        rides.sort_by_key(|ride| minute_of_day(ride));
+        let mut cluster: Vec<&Ride> = Vec::new();
+        for ride in rides {
+            if cluster.first().is_some_and(|first| minute_of_day(ride) - minute_of_day(first) > MAX_SPREAD_MINUTES) {
+                commutes.extend(commute_of(&cluster, templates.contains(&route)));
+                cluster.clear();
+            }
+            cluster.push(ride);
+        }
+        commutes.extend(commute_of(&cluster, templates.contains(&route)));
+    }
+    commutes.sort_by(|a, b| b.days.cmp(&a.days).then_with(|| a.departure.cmp(&b.departure)));
+    commutes
+}
+
+/// Commute of the rides of [cluster], if on enough days
+fn commute_of(cluster: &[&Ride], has_template: bool) -> Option<Commute> {
+    let days: HashSet<_> = cluster.iter().map(|ride| ride.journey_departure.date_naive()).collect();
+    if days.len() < MIN_DAYS {
+        return None;
+    }
+    let median = minute_of_day(cluster[cluster.len() / 2]);
+    let mut weekdays: Vec<_> = days.iter().map(|day| day.weekday()).collect();
+    weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+    weekdays.dedup();
+    let latest = cluster.iter().max_by_key(|ride| ride.journey_departure)?;
+    Some(
+        Commute {
+            location_from: latest.location_from.clone(),
+            location_to: latest.location_to.clone(),
+            departure: NaiveTime::from_hms_opt(median / 60, median % 60, 0)
+                .unwrap_or_default()
+                .format("%H:%M")
+                .to_string(),
+            weekdays: weekdays.iter().map(|weekday| weekday.to_string()).collect(),
+            days: days.len(),
+            ride_ids: cluster.iter().map(|ride| ride.id()).collect(),
+            template: (!has_template).then(|| TemplateProposal {
+                journey_departure: latest.journey_departure,
+                location_from: latest.location_from.clone(),
+                location_to: latest.location_to.clone(),
+                is_template: true,
+            }),
+        }
+    )
+}
+
+/// Route of [ride], compared case-insensitively
+pub(super) fn route_of(ride: &Ride) -> (String, String) {
+    (ride.location_from.trim().to_lowercase(), ride.location_to.trim().to_lowercase())
+}
+
+/// Minute of the day of the departure of [ride] in UTC
+pub(super) fn minute_of_day(ride: &Ride) -> u32 {
+    let time = ride.journey_departure.time();
+    time.hour() * 60 + time.minute()
+}
+
+/// Commutes of [user_id], see [detect]
+pub async fn suggestions(user_id: u32, now: DateTimeUtc, db: &impl ConnectionTrait) -> Result<Vec<Commute>, CurdError> {
+    let rides = Ride::find_all(user_id, false, false, db).await?;
+    Ok(detect(&rides, now))
+}
+
+/// Users with a string tag [tag_key]
+pub async fn find_users(tag_key: &str, db: &impl ConnectionTrait) -> Result<Vec<u32>, CurdError> {
+    tag_descriptor::Entity::find()
+        .select_only()
+        .column(tag_descriptor::Column::UserId)
+        .filter(tag_descriptor::Column::TagKey.eq(tag_key))
+        .filter(tag_descriptor::Column::TagType.eq(TagType::String))
+        .filter(tag_descriptor::Column::DeletedAt.is_null())
+        .into_tuple::<u32>()
+        .all(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )
+}
+
+/// Link the rides of the commutes of [user_id] with the string tag [tag_key], with the
+/// route as value. Rides which are or were linked with the tag are skipped, so removed
+/// links are not restored. Returns the new links.
+pub async fn apply_tag(user_id: u32, tag_key: &str, now: DateTimeUtc, actor: &Actor, db: &impl ConnectionTrait) -> Result<Vec<RideTagLink>, CurdError> {
+    let tags = Tag::find_all(user_id, false, false, db).await?;
+    let Some(tag) = tags
+        .iter()
+        .find(|tag| *tag.tag_key() == tag_key && matches!(TagType::try_from(tag.tag_type.clone()), Ok(TagType::String)))
+    else {
+        return Ok(Vec::new());
+    };
+    let commutes = suggestions(user_id, now, db).await?;
+    let ride_ids: Vec<u32> = commutes.iter().flat_map(|commute| commute.ride_ids.iter().copied()).collect();
+    if ride_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let linked: HashSet<u32> = ride_tag::Entity::find()
+        .select_only()
+        .column(ride_tag::Column::RideId)
+        .filter(ride_tag::Column::TagDescriptorId.eq(tag.id()))
+        .filter(ride_tag::Column::RideId.is_in(ride_ids))
+        .into_tuple::<u32>()
+        .all(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .into_iter()
+        .collect();
+
+    let mut links = Vec::new();
+    for commute in &commutes {
+        let value = format!("{} → {}", commute.location_from, commute.location_to);
+        for ride_id in commute.ride_ids.iter().filter(|ride_id| !linked.contains(ride_id)) {
+            let link = ride_tag_link::CreateUpdateBuilder::new(0, Value::String(value.clone()), None)
+                .insert(*ride_id, tag.id(), actor, db)
+                .await?;
+            links.push(link);
+        }
+    }
+    Ok(links)
+}
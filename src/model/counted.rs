@@ -0,0 +1,72 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use sea_orm::{prelude::*, FromQueryResult, QueryResult, QuerySelect};
+use super::error::CurdError;
+use super::retry::retry;
+
+/// Alias of the column with the total number of rows
+const TOTAL_COUNT: &str = "total_count";
+
+/// Row of a page together with the total number of rows of all pages
+struct Counted<M> {
+    model: M,
+    total_count: i64,
+}
+
+impl<M: FromQueryResult> FromQueryResult for Counted<M> {
+    fn from_query_result(res: &QueryResult, pre: &str) -> Result<Self, DbErr> {
+        Ok(
+            Self {
+                model: M::from_query_result(res, pre)?,
+                total_count: res.try_get(pre, TOTAL_COUNT)?,
+            }
+        )
+    }
+}
+
+/// Fetch page [page] of [size] rows selected by [query] and the total number of rows
+/// selected by [query]. Both are fetched in one query with the window function
+/// `COUNT(*) OVER ()`, which is evaluated before offset and limit. Only if the page is
+/// beyond the last one, the rows are counted in a separate query.
+pub async fn find_page_with_count<E>(
+    query: Select<E>,
+    page: u64,
+    size: u64,
+    db: &impl ConnectionTrait,
+) -> Result<(Vec<E::Model>, u64), CurdError>
+where
+    E: EntityTrait,
+    E::Model: Sync,
+{
+    let statement = query
+        .clone()
+        .column_as(Expr::cust("COUNT(*) OVER ()"), TOTAL_COUNT)
+        .offset(page * size)
+        .limit(size);
+    let rows = retry(|| statement.clone().into_model::<Counted<E::Model>>().all(db))
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    let total_count = match rows.first() {
+        Some(row) => row.total_count as u64,
+        None => {
+            retry(|| query.clone().count(db))
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?
+        },
+    };
+    Ok((rows.into_iter().map(|row| row.model).collect(), total_count))
+}
@@ -0,0 +1,78 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm,
+    Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use super::error::CurdError;
+
+/// Length of the nonce prepended to the ciphertext
+const NONCE_LENGTH: usize = 12;
+
+/// Rocket state encrypting credentials of external services before they are stored
+#[derive(Clone)]
+pub struct CredentialCipher {
+    cipher: Option<Arc<Aes256Gcm>>,
+}
+
+impl CredentialCipher {
+    /// Cipher with [key], 32 bytes encoded as base64. Storing credentials is disabled
+    /// without [key].
+    pub fn new(key: Option<&str>) -> Result<Self, String> {
+        let Some(key) = key else {
+            return Ok(Self { cipher: None });
+        };
+        let key = STANDARD.decode(key.trim())
+            .map_err(|e| format!("Credentials key is not base64: {}", e))?;
+        let cipher = Aes256Gcm::new_from_slice(&key)
+            .map_err(|_| "Credentials key must be 32 bytes".to_string())?;
+        Ok(
+            Self {
+                cipher: Some(Arc::new(cipher)),
+            }
+        )
+    }
+
+    /// Whether a key is configured
+    pub fn is_enabled(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// [plaintext] encrypted with AES-256-GCM under a random nonce, as base64
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, CurdError> {
+        let cipher = self.cipher.as_ref().ok_or(CurdError::NotFound)?;
+        let nonce = rand::random::<[u8; NONCE_LENGTH]>();
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(
+                |_| {
+                    CurdError::InternalError("Cannot encrypt credentials".to_string())
+                }
+            )?;
+        Ok(STANDARD.encode([nonce.as_slice(), &ciphertext].concat()))
+    }
+
+    /// Plaintext of [encrypted], as returned by [encrypt]. Fails if the key has changed.
+    pub fn decrypt(&self, encrypted: &str) -> Result<String, CurdError> {
+        let cipher = self.cipher.as_ref().ok_or(CurdError::NotFound)?;
+        let error = || CurdError::InternalError("Cannot decrypt credentials, store them again".to_string());
+        let data = STANDARD.decode(encrypted).map_err(|_| error())?;
+        if data.len() < NONCE_LENGTH {
+            Err(error())?;
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| error())?;
+        String::from_utf8(plaintext).map_err(|_| error())
+    }
+}
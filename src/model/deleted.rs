@@ -0,0 +1,20 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use sea_orm::{ColumnTrait, Condition};
+
+/// Condition excluding soft-deleted rows by their `deleted_at` [column]. Matches all
+/// rows if [include_deleted] is set.
+pub fn not_deleted<C: ColumnTrait>(column: C, include_deleted: bool) -> Condition {
+    let condition = Condition::all();
+    if include_deleted {
+        condition
+    } else {
+        condition.add(column.is_null())
+    }
+}
@@ -0,0 +1,241 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::{HashMap, HashSet};
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{
+    prelude::*,
+    sea_query::{Alias, Func, JoinType, Order, OverStatement, Query, SimpleExpr, WindowStatement},
+    Condition,
+};
+use entity::{ride, ride_tag, tag_descriptor};
+use super::audit::Actor;
+use super::error::CurdError;
+use super::ride::{self as ride_model, Ride};
+use super::ride_tag_link::{self, RideTagLink};
+
+/// Longest window between departures of duplicates in minutes
+pub const MAX_WINDOW_MINUTES: u32 = 24 * 60;
+
+/// Rocket state for finding duplicate rides
+#[derive(Debug, Clone)]
+pub struct Duplicates {
+    /// Default window between departures
+    window_minutes: u32,
+    /// Key of the tag holding the price of a ride
+    price_tag: String,
+}
+
+impl Duplicates {
+    /// Duplicates departing within [window_minutes] unless requested otherwise, with the
+    /// same value of the tag [price_tag]
+    pub fn new(window_minutes: u32, price_tag: String) -> Self {
+        Self {
+            window_minutes,
+            price_tag,
+        }
+    }
+
+    /// Groups of duplicate rides of [user_id] departing within [window_minutes], or the
+    /// default window, see [find]
+    pub async fn find(&self, user_id: u32, window_minutes: Option<u32>, db: &impl ConnectionTrait) -> Result<Vec<DuplicateGroup>, CurdError> {
+        let window_minutes = window_minutes.unwrap_or(self.window_minutes);
+        find(user_id, TimeDelta::minutes(window_minutes.into()), &self.price_tag, db).await
+    }
+}
+
+/// Rides which are likely the same journey
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct DuplicateGroup {
+    /// Rides in order of departure, with their tags
+    pub rides: Vec<Ride>,
+}
+
+/// Rides of [user_id] on the same route, compared case-insensitively, departing within
+/// [window] after the previous ride and with the same value of the tag [price_tag], or
+/// both without it. Templates are not compared.
+pub async fn find(user_id: u32, window: TimeDelta, price_tag: &str, db: &impl ConnectionTrait) -> Result<Vec<DuplicateGroup>, CurdError> {
+    let price = Alias::new("price");
+    let previous = Alias::new("w");
+    let price_value = Func::coalesce([
+        Expr::col((price.clone(), ride_tag::Column::ValueFloat)).into(),
+        Expr::col((price.clone(), ride_tag::Column::ValueInteger)).into(),
+    ]);
+    let lag = |expr: SimpleExpr| Func::cust(Alias::new("LAG")).arg(expr);
+
+    // Each ride with the previous one on its route
+    let mut window_statement = WindowStatement::new();
+    window_statement
+        .add_partition_by(Func::lower(Expr::col((ride::Entity, ride::Column::LocationFrom))).into())
+        .add_partition_by(Func::lower(Expr::col((ride::Entity, ride::Column::LocationTo))).into())
+        .order_by((ride::Entity, ride::Column::JourneyDeparture), Order::Asc)
+        .order_by((ride::Entity, ride::Column::Id), Order::Asc);
+    let pairs = Query::select()
+        .expr_as(Expr::col((ride::Entity, ride::Column::Id)), Alias::new("id"))
+        .expr_as(Expr::col((ride::Entity, ride::Column::JourneyDeparture)), Alias::new("departure"))
+        .expr_as(price_value.clone(), Alias::new("price"))
+        .expr_window_name_as(lag(Expr::col((ride::Entity, ride::Column::Id)).into()), previous.clone(), Alias::new("previous_id"))
+        .expr_window_name_as(lag(Expr::col((ride::Entity, ride::Column::JourneyDeparture)).into()), previous.clone(), Alias::new("previous_departure"))
+        .expr_window_name_as(lag(price_value.into()), previous.clone(), Alias::new("previous_price"))
+        .from(ride::Entity)
+        .join_as(
+            JoinType::LeftJoin,
+            ride_tag::Entity,
+            price.clone(),
+            Condition::all()
+                .add(Expr::col((price.clone(), ride_tag::Column::RideId)).equals((ride::Entity, ride::Column::Id)))
+                .add(Expr::col((price.clone(), ride_tag::Column::DeletedAt)).is_null())
+                .add(
+                    Expr::col((price.clone(), ride_tag::Column::TagDescriptorId)).in_subquery(
+                        Query::select()
+                            .column(tag_descriptor::Column::Id)
+                            .from(tag_descriptor::Entity)
+                            .and_where(tag_descriptor::Column::UserId.eq(user_id))
+                            .and_where(tag_descriptor::Column::TagKey.eq(price_tag))
+                            .and_where(tag_descriptor::Column::DeletedAt.is_null())
+                            .to_owned()
+                    )
+                ),
+        )
+        .and_where(ride::Column::UserId.eq(user_id))
+        .and_where(ride::Column::IsTemplate.eq(false))
+        .and_where(ride::Column::DeletedAt.is_null())
+        .window(previous, window_statement)
+        .to_owned();
+    let pairs_alias = Alias::new("pairs");
+    let query = Query::select()
+        .columns([Alias::new("id"), Alias::new("departure"), Alias::new("previous_id"), Alias::new("previous_departure")])
+        .from_subquery(pairs, pairs_alias)
+        .and_where(Expr::col(Alias::new("previous_id")).is_not_null())
+        .cond_where(
+            Condition::any()
+                .add(
+                    Condition::all()
+                        .add(Expr::col(Alias::new("price")).is_null())
+                        .add(Expr::col(Alias::new("previous_price")).is_null())
+                )
+                .add(Expr::col(Alias::new("price")).equals(Alias::new("previous_price")))
+        )
+        .order_by(Alias::new("departure"), Order::Asc)
+        .order_by(Alias::new("id"), Order::Asc)
+        .to_owned();
+    let rows = db
+        .query_all(db.get_database_backend().build(&query))
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+
+    // Chain the pairs within the window to groups
+    let mut group_of: HashMap<u32, usize> = HashMap::new();
+    let mut groups: Vec<Vec<u32>> = Vec::new();
+    for row in rows {
+        let get_error = |error| CurdError::DbErr(error);
+        let id: u32 = row.try_get("", "id").map_err(get_error)?;
+        let departure: DateTimeUtc = row.try_get("", "departure").map_err(get_error)?;
+        let previous_id: u32 = row.try_get("", "previous_id").map_err(get_error)?;
+        let previous_departure: DateTimeUtc = row.try_get("", "previous_departure").map_err(get_error)?;
+        if departure - previous_departure > window {
+            continue;
+        }
+        let group = match group_of.get(&previous_id) {
+            Some(group) => *group,
+            None => {
+                groups.push(vec![previous_id]);
+                group_of.insert(previous_id, groups.len() - 1);
+                groups.len() - 1
+            },
+        };
+        groups[group].push(id);
+        group_of.insert(id, group);
+    }
+
+    let ids: Vec<u32> = groups.iter().flatten().copied().collect();
+    let mut rides: HashMap<u32, Ride> = Ride::find_by_ids(user_id, &ids, true, false, db)
+        .await?
+        .into_iter()
+        .map(|ride| (ride.id(), ride))
+        .collect();
+    let groups = groups
+        .into_iter()
+        .map(|ids| DuplicateGroup {
+            rides: ids.iter().filter_map(|id| rides.remove(id)).collect(),
+        })
+        .collect();
+    Ok(groups)
+}
+
+/// Rides to merge
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+pub struct MergeRequest {
+    /// Ride to keep
+    pub keep: u32,
+    /// Rides to delete
+    pub remove: Vec<u32>,
+}
+
+/// Outcome of a merge
+#[derive(Debug, Clone)]
+pub struct Merged {
+    /// Kept ride with its tags
+    pub ride: Ride,
+    /// Links copied to the kept ride
+    pub links: Vec<RideTagLink>,
+    /// Soft-deleted rides
+    pub removed: Vec<u32>,
+}
+
+/// Merge the rides of [request] of [user_id] into the kept one. Tags of removed rides
+/// which the kept ride lacks are copied to it, then the removed rides are soft-deleted.
+pub async fn merge(user_id: u32, request: MergeRequest, actor: &Actor, db: &impl ConnectionTrait) -> Result<Merged, CurdError> {
+    let mut removed = request.remove.clone();
+    removed.sort();
+    removed.dedup();
+    if removed.is_empty() {
+        Err(CurdError::Unprocessable("No rides to remove".to_string()))?;
+    }
+    if removed.contains(&request.keep) {
+        Err(CurdError::Unprocessable("The kept ride cannot be removed".to_string()))?;
+    }
+    let mut ids = removed.clone();
+    ids.push(request.keep);
+    if Ride::find_by_ids(user_id, &ids, false, false, db).await?.len() != ids.len() {
+        Err(CurdError::NotFound)?;
+    }
+
+    let mut linked: HashSet<u32> = RideTagLink::find_all(request.keep, false, db)
+        .await?
+        .iter()
+        .map(|link| link.tag_id())
+        .collect();
+    let mut links = Vec::new();
+    for ride_id in &removed {
+        for link in RideTagLink::find_all(*ride_id, false, db).await? {
+            let tag_id = link.tag_id();
+            if !linked.insert(tag_id) {
+                continue;
+            }
+            let link = ride_tag_link::CreateUpdateBuilder::from_json(link)
+                .insert(request.keep, tag_id, actor, db)
+                .await?;
+            links.push(link);
+        }
+        ride_model::remove(*ride_id, actor, db).await?;
+    }
+    Ok(
+        Merged {
+            ride: Ride::find_by_id(request.keep, true, false, db).await?,
+            links,
+            removed,
+        }
+    )
+}
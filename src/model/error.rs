@@ -7,6 +7,7 @@
  */
 
 use std::fmt::Display;
+use rocket::http::Status;
 use sea_orm::error::DbErr;
 use crate::routes::ApiError;
 
@@ -16,16 +17,19 @@ pub enum CurdError {
     DeserializationError(String),
     DbErr(DbErr),
     InternalError(String),
+    /// Request conflicts with the current state, e.g. a request still in progress
+    Conflict(String),
+    /// Request is well-formed, but cannot be processed
+    Unprocessable(String),
+    /// External service failed, e.g. the geocoding provider
+    Upstream(String),
 }
 
 impl From<CurdError> for ApiError {
     fn from(e: CurdError) -> ApiError {
         match e {
             CurdError::NotFound => ApiError::new_not_found(),
-            CurdError::DbErr(e) => {
-                ApiError::new_internal_server_error()
-                    .with_description(e.to_string())
-            },
+            CurdError::DbErr(e) => ApiError::from(e),
             CurdError::DeserializationError(e) => {
                 ApiError::new_bad_request()
                     .with_description(e)
@@ -34,6 +38,18 @@ impl From<CurdError> for ApiError {
                 ApiError::new_internal_server_error()
                     .with_description(e)
             },
+            CurdError::Conflict(e) => {
+                ApiError::new_conflict()
+                    .with_description(e)
+            },
+            CurdError::Unprocessable(e) => {
+                ApiError::new_unprocessable_entity()
+                    .with_description(e)
+            },
+            CurdError::Upstream(e) => {
+                ApiError::from_status(Status::BadGateway)
+                    .with_description(e)
+            },
         }
     }
 }
@@ -45,6 +61,9 @@ impl Display for CurdError {
             CurdError::DeserializationError(e) => write!(f, "Deserialization error: {}", e),
             CurdError::DbErr(e) => write!(f, "Db error: {}", e),
             CurdError::InternalError(e) => write!(f, "Internal error: {}", e),
+            CurdError::Conflict(e) => write!(f, "Conflict: {}", e),
+            CurdError::Unprocessable(e) => write!(f, "Unprocessable: {}", e),
+            CurdError::Upstream(e) => write!(f, "Upstream error: {}", e),
         }
     }
 }
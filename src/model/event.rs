@@ -0,0 +1,150 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::fmt::Display;
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::prelude::DateTimeUtc;
+use tokio::sync::broadcast;
+
+/// Number of events buffered for slow subscribers
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Kind of resource an event is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Resource {
+    Ride,
+    Tag,
+    TagOption,
+    RideTag,
+    RideReference,
+    RideTrack,
+}
+
+/// Change of a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Resource::Ride => write!(f, "ride"),
+            Resource::Tag => write!(f, "tag"),
+            Resource::TagOption => write!(f, "tag_option"),
+            Resource::RideTag => write!(f, "ride_tag"),
+            Resource::RideReference => write!(f, "ride_reference"),
+            Resource::RideTrack => write!(f, "ride_track"),
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Created => write!(f, "created"),
+            Action::Updated => write!(f, "updated"),
+            Action::Deleted => write!(f, "deleted"),
+        }
+    }
+}
+
+/// Change of a resource of a user
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Event {
+    /// Unique ID of the event
+    pub id: String,
+    /// Event type, e.g. `ride.created`
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub created_at: DateTimeUtc,
+    #[serde(skip)]
+    pub user_id: u32,
+    pub resource: Resource,
+    pub action: Action,
+    /// ID of the changed resource
+    pub resource_id: u32,
+    /// The resource after the change, if available
+    pub data: Option<serde_json::Value>,
+}
+
+impl Event {
+    /// New event without data
+    pub fn new(user_id: u32, resource: Resource, action: Action, resource_id: u32) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            event_type: format!("{}.{}", resource, action),
+            created_at: chrono::Utc::now(),
+            user_id,
+            resource,
+            action,
+            resource_id,
+            data: None,
+        }
+    }
+
+    /// Attach the resource after the change
+    pub fn with_data<T: Serialize>(mut self, data: &T) -> Self {
+        self.data = serde_json::to_value(data).ok();
+        self
+    }
+
+    /// Check if the event type matches [filter], which is an event type, `<resource>.*`
+    /// or `*`
+    pub fn matches(&self, filter: &str) -> bool {
+        match filter.strip_suffix(".*") {
+            Some(resource) => resource == self.resource.to_string(),
+            None => filter == "*" || filter == self.event_type,
+        }
+    }
+}
+
+/// Rocket state distributing change events to subscribers, i.e. webhooks and event
+/// streams. Clones publish to the same subscribers.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+        }
+    }
+
+    /// Send [event] to all current subscribers
+    pub fn publish(&self, event: Event) {
+        // Sending only fails if there are no subscribers
+        let _ = self.sender.send(event);
+    }
+
+    /// Send all [events], e.g. after a transaction was committed
+    pub fn publish_all(&self, events: impl IntoIterator<Item = Event>) {
+        for event in events {
+            self.publish(event);
+        }
+    }
+
+    /// Receive all events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
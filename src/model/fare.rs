@@ -0,0 +1,294 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::DateTimeUtc, ConnectionTrait};
+use super::error::CurdError;
+use super::ride::Ride;
+use super::ride_tag_link::Value;
+use super::tag::Tag;
+
+/// Zone of a fare region with the locations inside
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FareZone {
+    pub name: String,
+    /// Location names as used in rides, compared case-insensitively
+    pub locations: Vec<String>,
+}
+
+/// Tariff of a region with ring zones, configured in `[[fare_regions]]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FareRegion {
+    pub name: String,
+    pub currency: String,
+    /// Zones ordered from the center outwards
+    pub zones: Vec<FareZone>,
+    /// Price of journeys spanning 1, 2, ... zones. The last price also applies to
+    /// journeys spanning more zones.
+    pub prices: Vec<f64>,
+}
+
+impl FareRegion {
+    /// Index of the zone containing [location]
+    fn zone_of(&self, location: &str) -> Option<usize> {
+        let location = normalize(location);
+        self.zones
+            .iter()
+            .position(|zone| zone.locations.iter().any(|candidate| normalize(candidate) == location))
+    }
+
+    /// Estimate from [from] to [to] if both are in this region
+    fn estimate(&self, from: &str, to: &str) -> Option<FareEstimate> {
+        let (from, to) = (self.zone_of(from)?, self.zone_of(to)?);
+        let zones = &self.zones[from.min(to)..=from.max(to)];
+        let price = self.prices.get(zones.len() - 1).or(self.prices.last())?;
+        Some(
+            FareEstimate {
+                region: self.name.clone(),
+                zones: zones.iter().map(|zone| zone.name.clone()).collect(),
+                price: *price,
+                currency: self.currency.clone(),
+            }
+        )
+    }
+}
+
+/// Expected price of a journey
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct FareEstimate {
+    /// Fare region covering both locations
+    pub region: String,
+    /// Zones travelled through
+    pub zones: Vec<String>,
+    pub price: f64,
+    pub currency: String,
+}
+
+/// Ride whose recorded price deviates from the estimate
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct FareDeviation {
+    pub ride_id: u32,
+    pub journey_departure: DateTimeUtc,
+    pub location_from: String,
+    pub location_to: String,
+    /// Recorded price
+    pub price: f64,
+    pub passengers: u32,
+    /// Estimate for all passengers
+    pub estimate: FareEstimate,
+    /// Deviation relative to the estimate, e.g. `0.5` for a price 50 % above it
+    pub deviation: f64,
+}
+
+/// Rocket state with the fare regions of the instance
+#[derive(Debug, Clone)]
+pub struct Tariff {
+    regions: Arc<Vec<FareRegion>>,
+    /// Key of the tag holding the recorded price
+    price_tag: String,
+}
+
+impl Tariff {
+    /// Tariff of [regions], comparing with the values of the tag [price_tag]. Fails on
+    /// regions without zones or prices and on locations in several zones of a region.
+    pub fn new(regions: Vec<FareRegion>, price_tag: String) -> Result<Self, String> {
+        for region in &regions {
+            if region.zones.is_empty() || region.prices.is_empty() {
+                Err(format!("Fare region {} needs zones and prices", region.name))?;
+            }
+            if region.prices.iter().any(|price| !price.is_finite() || *price < 0.0) {
+                Err(format!("Prices of fare region {} must not be negative", region.name))?;
+            }
+            let mut locations: Vec<String> = region.zones
+                .iter()
+                .flat_map(|zone| zone.locations.iter().map(|location| normalize(location)))
+                .collect();
+            locations.sort();
+            if let Some(location) = locations.windows(2).find(|pair| pair[0] == pair[1]) {
+                Err(format!("Location {} is in several zones of fare region {}", location[0], region.name))?;
+            }
+        }
+        Ok(
+            Self {
+                regions: Arc::new(regions),
+                price_tag,
+            }
+        )
+    }
+
+    /// Whether fare regions are configured
+    pub fn is_enabled(&self) -> bool {
+        !self.regions.is_empty()
+    }
+
+    /// Expected price from [from] to [to], from the first region covering both
+    pub fn estimate(&self, from: &str, to: &str) -> Option<FareEstimate> {
+        self.regions
+            .iter()
+            .find_map(|region| region.estimate(from, to))
+    }
+
+    /// Rides of [user_id] whose price deviates from the estimate by more than
+    /// [threshold], relative to the estimate. The estimate is scaled by the passengers of
+    /// the ride. Templates and rides without price or estimate are skipped.
+    pub async fn deviations(&self, user_id: u32, threshold: f64, db: &impl ConnectionTrait) -> Result<Vec<FareDeviation>, CurdError> {
+        let tags = Tag::find_all(user_id, false, false, db).await?;
+        let Some(price_tag) = tags.iter().find(|tag| *tag.tag_key() == self.price_tag) else {
+            return Ok(Vec::new());
+        };
+
+        let rides = Ride::find_all(user_id, true, false, db).await?;
+        let deviations = rides
+            .into_iter()
+            .filter(|ride| !ride.is_template)
+            .filter_map(|ride| {
+                let price = ride.tags()
+                    .iter()
+                    .flatten()
+                    .find(|link| link.tag_id() == price_tag.id())
+                    .and_then(|link| match link.value {
+                        Value::Float(price) => Some(price),
+                        Value::Integer(price) => Some(price as f64),
+                        _ => None,
+                    })?;
+                let mut estimate = self.estimate(&ride.location_from, &ride.location_to)?;
+                estimate.price *= ride.passengers as f64;
+                if estimate.price <= 0.0 {
+                    return None;
+                }
+                let deviation = (price - estimate.price) / estimate.price;
+                (deviation.abs() > threshold).then(|| FareDeviation {
+                    ride_id: ride.id(),
+                    journey_departure: ride.journey_departure,
+                    location_from: ride.location_from,
+                    location_to: ride.location_to,
+                    price,
+                    passengers: ride.passengers,
+                    estimate,
+                    deviation,
+                })
+            })
+            .collect();
+        Ok(deviations)
+    }
+}
+
+/// Location as compared, trimmed and lowercase
+fn normalize(location: &str) -> String {
+    location.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FareRegion, FareZone, Tariff};
+
+    fn zone(name: &str, locations: &[&str]) -> FareZone {
+        FareZone {
+            name: name.to_string(),
+            locations: locations.iter().map(|location| location.to_string()).collect(),
+        }
+    }
+
+    fn berlin() -> FareRegion {
+        FareRegion {
+            name: "Berlin".to_string(),
+            currency: "EUR".to_string(),
+            zones: vec![
+                zone("A", &["Alexanderplatz", "Berlin Hbf"]),
+                zone("B", &["Spandau"]),
+                zone("C", &["Potsdam Hbf", "BER Airport"]),
+            ],
+            prices: vec![3.8, 4.7],
+        }
+    }
+
+    fn potsdam() -> FareRegion {
+        FareRegion {
+            name: "Potsdam".to_string(),
+            currency: "EUR".to_string(),
+            zones: vec![zone("A", &["Potsdam Hbf", "Sanssouci"])],
+            prices: vec![2.6],
+        }
+    }
+
+    #[test]
+    fn test_zone_of() {
+        let region = berlin();
+        assert_eq!(region.zone_of("Berlin Hbf"), Some(0));
+        assert_eq!(region.zone_of("  berlin HBF "), Some(0));
+        assert_eq!(region.zone_of("Spandau"), Some(1));
+        assert_eq!(region.zone_of("BER Airport"), Some(2));
+        assert_eq!(region.zone_of("Berlin"), None);
+        assert_eq!(region.zone_of(""), None);
+    }
+
+    #[test]
+    fn test_estimate() {
+        let tariff = Tariff::new(vec![berlin(), potsdam()], "price".to_string()).expect("Tariff is valid");
+        let Some(estimate) = tariff.estimate("Alexanderplatz", "Berlin Hbf") else {
+            panic!("Journey in one zone is estimated");
+        };
+        assert_eq!(estimate.region, "Berlin");
+        assert_eq!(estimate.zones, ["A"]);
+        assert_eq!(estimate.price, 3.8);
+
+        // Outwards and inwards are the same
+        for (from, to) in [("Berlin Hbf", "Spandau"), ("spandau", "berlin hbf")] {
+            let Some(estimate) = tariff.estimate(from, to) else {
+                panic!("{} to {} is estimated", from, to);
+            };
+            assert_eq!(estimate.zones, ["A", "B"]);
+            assert_eq!(estimate.price, 4.7);
+        }
+
+        // The last price applies to journeys spanning more zones
+        let Some(estimate) = tariff.estimate("BER Airport", "Alexanderplatz") else {
+            panic!("Journey through all zones is estimated");
+        };
+        assert_eq!(estimate.zones, ["A", "B", "C"]);
+        assert_eq!(estimate.price, 4.7);
+
+        // The first region covering both locations is taken
+        let Some(estimate) = tariff.estimate("Potsdam Hbf", "Sanssouci") else {
+            panic!("Journey in Potsdam is estimated");
+        };
+        assert_eq!((estimate.region.as_str(), estimate.price), ("Potsdam", 2.6));
+        let Some(estimate) = tariff.estimate("Potsdam Hbf", "Spandau") else {
+            panic!("Journey to Potsdam is estimated");
+        };
+        assert_eq!((estimate.region.as_str(), estimate.price), ("Berlin", 4.7));
+
+        // Both locations must be in the same region
+        assert!(tariff.estimate("Sanssouci", "Spandau").is_none());
+        assert!(tariff.estimate("Berlin Hbf", "Hamburg Hbf").is_none());
+    }
+
+    #[test]
+    fn test_invalid_regions() {
+        let mut without_zones = berlin();
+        without_zones.zones.clear();
+        let mut without_prices = berlin();
+        without_prices.prices.clear();
+        let mut negative = berlin();
+        negative.prices.push(-1.0);
+        let mut not_a_number = berlin();
+        not_a_number.prices[0] = f64::NAN;
+        let mut duplicate = berlin();
+        duplicate.zones[1].locations.push(" berlin hbf".to_string());
+        for region in [without_zones, without_prices, negative, not_a_number, duplicate] {
+            assert!(Tariff::new(vec![region], "price".to_string()).is_err());
+        }
+
+        // Without regions nothing is estimated
+        let tariff = Tariff::new(Vec::new(), "price".to_string()).expect("Tariff is valid");
+        assert!(!tariff.is_enabled());
+        assert!(tariff.estimate("Berlin Hbf", "Spandau").is_none());
+    }
+}
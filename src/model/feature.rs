@@ -0,0 +1,124 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::ConnectionTrait;
+use crate::fairings::cache_invalidation::{CacheNotifier, Invalidation};
+use super::error::CurdError;
+use super::server_setting;
+
+/// Webhook routes and deliveries
+pub const WEBHOOKS: &str = "webhooks";
+/// Server-sent event stream
+pub const EVENTS: &str = "events";
+
+/// Known feature flags with their default
+pub const FEATURES: [(&str, bool); 2] = [
+    (WEBHOOKS, true),
+    (EVENTS, true),
+];
+
+/// State of a feature flag
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct FeatureFlag {
+    #[serde(skip_deserializing)]
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// Rocket state with the feature flags of the server. Flags can be switched at runtime.
+/// Switched flags are kept in the database and take precedence over the configuration,
+/// and other instances are told to reload them if a [CacheNotifier] is set. Clones share
+/// the flags.
+#[derive(Clone)]
+pub struct FeatureFlags {
+    flags: Arc<BTreeMap<&'static str, AtomicBool>>,
+    /// Flags of the configuration, used if not switched at runtime
+    defaults: Arc<BTreeMap<&'static str, bool>>,
+    notifier: Option<CacheNotifier>,
+}
+
+impl FeatureFlags {
+    /// Flags with the defaults of [FEATURES], overridden by [config]. Fails on unknown
+    /// flags.
+    pub fn new(config: &HashMap<String, bool>) -> Result<Self, String> {
+        if let Some(name) = config.keys().find(|name| !FEATURES.iter().any(|(feature, _)| *feature == name.as_str())) {
+            Err(format!("Unknown feature {}", name))?;
+        }
+        let defaults: BTreeMap<&'static str, bool> = FEATURES
+            .iter()
+            .map(|(name, default)| (*name, *config.get(*name).unwrap_or(default)))
+            .collect();
+        let flags = defaults
+            .iter()
+            .map(|(name, enabled)| (*name, AtomicBool::new(*enabled)))
+            .collect();
+        Ok(
+            Self {
+                flags: Arc::new(flags),
+                defaults: Arc::new(defaults),
+                notifier: None,
+            }
+        )
+    }
+
+    /// Flags telling the other server instances about switched flags with [notifier]
+    pub fn with_notifier(mut self, notifier: Option<CacheNotifier>) -> Self {
+        self.notifier = notifier;
+        self
+    }
+
+    /// Read the flags switched at runtime from [db]
+    pub async fn reload(&self, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+        for (name, flag) in self.flags.iter() {
+            let enabled = server_setting::load(&server_setting::feature(name), db)
+                .await?
+                .unwrap_or(self.defaults[name]);
+            flag.store(enabled, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Whether feature [name] is enabled. Unknown features are disabled.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.flags
+            .get(name)
+            .is_some_and(|enabled| enabled.load(Ordering::Relaxed))
+    }
+
+    /// Enable or disable feature [name] on all server instances
+    pub async fn set(&self, name: &str, enabled: bool, db: &impl ConnectionTrait) -> Result<FeatureFlag, CurdError> {
+        let flag = self.flags.get(name).ok_or(CurdError::NotFound)?;
+        server_setting::store(&server_setting::feature(name), &enabled, db).await?;
+        flag.store(enabled, Ordering::Relaxed);
+        if let Some(notifier) = &self.notifier {
+            notifier.publish(Invalidation::Features).await;
+        }
+        Ok(
+            FeatureFlag {
+                name: name.to_string(),
+                enabled,
+            }
+        )
+    }
+
+    /// All flags, ordered by name
+    pub fn all(&self) -> Vec<FeatureFlag> {
+        self.flags
+            .iter()
+            .map(|(name, enabled)| FeatureFlag {
+                name: name.to_string(),
+                enabled: enabled.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,169 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeMap;
+use chrono::{NaiveTime, TimeDelta};
+use entity::ride::RideStatus;
+use serde::Serialize;
+use rocket_okapi::okapi::schemars;
+use sea_orm::prelude::*;
+use super::commute::{minute_of_day, route_of};
+use super::error::CurdError;
+use super::ride::Ride;
+use super::ride_tag_link::Value;
+
+/// Days before now whose rides are analysed
+const WINDOW_DAYS: i64 = 180;
+/// Rides on a route making it a frequent connection
+const MIN_RIDES: usize = 2;
+/// Maximum minutes between the earliest and latest departure of a typical departure time
+const MAX_SPREAD_MINUTES: u32 = 60;
+/// Typical departure times per connection
+const MAX_DEPARTURES: usize = 3;
+/// Number of connections if not requested otherwise
+pub const DEFAULT_LIMIT: usize = 5;
+/// Maximum number of connections
+pub const MAX_LIMIT: usize = 50;
+
+/// Time of day rides on a connection usually depart
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct TypicalDeparture {
+    /// Median time of departure in UTC as `HH:MM`
+    pub departure: String,
+    /// Number of rides departing within an hour around it
+    pub count: usize,
+}
+
+/// Value a tag usually has on rides of a connection
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct TypicalValue {
+    pub tag_id: u32,
+    pub value: Value,
+    /// Number of rides with the value
+    pub count: usize,
+}
+
+/// Route the user rides often
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct FrequentConnection {
+    /// Spelling of the latest ride
+    pub location_from: String,
+    /// Spelling of the latest ride
+    pub location_to: String,
+    /// Number of rides in the last 180 days
+    pub count: usize,
+    /// Most common times of departure, most frequent first
+    pub departures: Vec<TypicalDeparture>,
+    /// Most common value of each tag linked with at least half of the rides
+    pub tags: Vec<TypicalValue>,
+    pub last_departure: DateTimeUtc,
+}
+
+/// The [limit] most frequent connections in [rides] departing within 180 days before
+/// [now], on routes compared case-insensitively with at least 2 rides. Templates and
+/// cancelled rides are left out. Requires the rides to be fetched with their tags.
+pub fn detect(rides: &[Ride], now: DateTimeUtc, limit: usize) -> Vec<FrequentConnection> {
+    let start = now - TimeDelta::days(WINDOW_DAYS);
+    let mut routes: BTreeMap<(String, String), Vec<&Ride>> = BTreeMap::new();
+    for ride in rides {
+        if ride.is_template || ride.status == RideStatus::Cancelled || ride.journey_departure < start || ride.journey_departure > now {
+            continue;
+        }
+        routes.entry(route_of(ride)).or_default().push(ride);
+    }
+
+    let mut connections: Vec<FrequentConnection> = routes
+        .into_values()
+        .filter(|rides| rides.len() >= MIN_RIDES)
+        .filter_map(connection_of)
+        .collect();
+    connections.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| b.last_departure.cmp(&a.last_departure)));
+    connections.truncate(limit);
+    connections
+}
+
+/// Connection of [rides] on the same route
+fn connection_of(mut rides: Vec<&Ride>) -> Option<FrequentConnection> {
+    let latest = *rides.iter().max_by_key(|ride| ride.journey_departure)?;
+
+    rides.sort_by_key(|ride| minute_of_day(ride));
+    let mut departures = Vec::new();
+    let mut cluster: Vec<u32> = Vec::new();
+    for minute in rides.iter().map(|ride| minute_of_day(ride)) {
+        if cluster.first().is_some_and(|first| minute - first > MAX_SPREAD_MINUTES) {
+            departures.push(typical_departure(&cluster));
+            cluster.clear();
+        }
+        cluster.push(minute);
+    }
+    departures.push(typical_departure(&cluster));
+    departures.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.departure.cmp(&b.departure)));
+    departures.truncate(MAX_DEPARTURES);
+
+    Some(
+        FrequentConnection {
+            location_from: latest.location_from.clone(),
+            location_to: latest.location_to.clone(),
+            count: rides.len(),
+            departures,
+            tags: typical_values(&rides),
+            last_departure: latest.journey_departure,
+        }
+    )
+}
+
+/// Median of the sorted minutes of day of [cluster]
+fn typical_departure(cluster: &[u32]) -> TypicalDeparture {
+    let median = cluster[cluster.len() / 2];
+    TypicalDeparture {
+        departure: NaiveTime::from_hms_opt(median / 60, median % 60, 0)
+            .unwrap_or_default()
+            .format("%H:%M")
+            .to_string(),
+        count: cluster.len(),
+    }
+}
+
+/// Most common value of each tag linked with at least half of [rides]. Dates and values
+/// copied from templates but not confirmed are left out.
+fn typical_values(rides: &[&Ride]) -> Vec<TypicalValue> {
+    // Values are compared by their JSON form, as floats cannot be hashed
+    let mut counts: BTreeMap<u32, BTreeMap<String, (Value, usize)>> = BTreeMap::new();
+    for link in rides.iter().flat_map(|ride| ride.tags().iter().flatten()) {
+        if link.per_ride || matches!(link.value, Value::DateTime(_)) {
+            continue;
+        }
+        let key = serde_json::to_string(&link.value).unwrap_or_default();
+        counts
+            .entry(link.tag_id())
+            .or_default()
+            .entry(key)
+            .or_insert_with(|| (link.value.clone(), 0))
+            .1 += 1;
+    }
+
+    counts
+        .into_iter()
+        .filter_map(|(tag_id, values)| {
+            let total: usize = values.values().map(|(_, count)| count).sum();
+            if total * 2 < rides.len() {
+                return None;
+            }
+            let (value, count) = values
+                .into_values()
+                .max_by_key(|(_, count)| *count)?;
+            Some(TypicalValue { tag_id, value, count })
+        })
+        .collect()
+}
+
+/// Frequent connections of [user_id], see [detect]
+pub async fn suggestions(user_id: u32, now: DateTimeUtc, limit: usize, db: &impl ConnectionTrait) -> Result<Vec<FrequentConnection>, CurdError> {
+    let rides = Ride::find_all(user_id, true, false, db).await?;
+    Ok(detect(&rides, now, limit))
+}
@@ -0,0 +1,312 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::sea_query::OnConflict;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use entity::geocode_cache;
+use super::error::CurdError;
+
+/// Maximum number of places returned for a query
+const RESULT_LIMIT: usize = 5;
+/// Minimum time between two requests to the provider. The public Nominatim instance
+/// allows one request per second.
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+/// Timeout of requests to the provider
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Age after which cached responses are requested again
+const CACHE_TTL: chrono::TimeDelta = chrono::TimeDelta::days(30);
+/// User agent sent to the provider, which the Nominatim usage policy requires
+pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Place found for a query
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Place {
+    /// Display name, e.g. to be used as ride location
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Geocoding service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    /// Nominatim of OpenStreetMap
+    Nominatim,
+    /// Photon of komoot
+    Photon,
+}
+
+impl Provider {
+    /// URL of the public instance
+    pub fn default_url(&self) -> &'static str {
+        match self {
+            Provider::Nominatim => "https://nominatim.openstreetmap.org",
+            Provider::Photon => "https://photon.komoot.io",
+        }
+    }
+
+    /// Request URL searching for [query]
+    fn search_url(&self, base: &str, query: &str) -> Result<reqwest::Url, CurdError> {
+        let base = base.trim_end_matches('/');
+        let limit = RESULT_LIMIT.to_string();
+        let url = match self {
+            Provider::Nominatim => reqwest::Url::parse_with_params(
+                &format!("{}/search", base),
+                [("q", query), ("format", "jsonv2"), ("limit", limit.as_str())],
+            ),
+            Provider::Photon => reqwest::Url::parse_with_params(
+                &format!("{}/api", base),
+                [("q", query), ("limit", limit.as_str())],
+            ),
+        };
+        url.map_err(
+            |error| {
+                CurdError::InternalError(format!("Invalid geocoder URL: {}", error))
+            }
+        )
+    }
+
+    /// Places in the response [body]
+    fn parse(&self, body: &str) -> Result<Vec<Place>, serde_json::Error> {
+        let places = match self {
+            Provider::Nominatim => {
+                serde_json::from_str::<Vec<NominatimPlace>>(body)?
+                    .into_iter()
+                    .filter_map(|place| Some(
+                        Place {
+                            name: place.display_name,
+                            latitude: place.lat.parse().ok()?,
+                            longitude: place.lon.parse().ok()?,
+                        }
+                    ))
+                    .collect()
+            },
+            Provider::Photon => {
+                serde_json::from_str::<PhotonResponse>(body)?
+                    .features
+                    .into_iter()
+                    .map(|feature| Place {
+                        name: feature.properties.name(),
+                        latitude: feature.geometry.coordinates[1],
+                        longitude: feature.geometry.coordinates[0],
+                    })
+                    .collect()
+            },
+        };
+        Ok(places)
+    }
+}
+
+impl Display for Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Provider::Nominatim => write!(f, "nominatim"),
+            Provider::Photon => write!(f, "photon"),
+        }
+    }
+}
+
+/// Search result of Nominatim
+#[derive(Deserialize)]
+struct NominatimPlace {
+    display_name: String,
+    lat: String,
+    lon: String,
+}
+
+/// GeoJSON response of Photon
+#[derive(Deserialize)]
+struct PhotonResponse {
+    features: Vec<PhotonFeature>,
+}
+
+#[derive(Deserialize)]
+struct PhotonFeature {
+    geometry: PhotonGeometry,
+    properties: PhotonProperties,
+}
+
+#[derive(Deserialize)]
+struct PhotonGeometry {
+    /// Longitude and latitude
+    coordinates: [f64; 2],
+}
+
+#[derive(Deserialize)]
+struct PhotonProperties {
+    name: Option<String>,
+    street: Option<String>,
+    housenumber: Option<String>,
+    city: Option<String>,
+    country: Option<String>,
+}
+
+impl PhotonProperties {
+    /// Name with city and country, like the display name of Nominatim
+    fn name(self) -> String {
+        let street = self.street.map(|street| match self.housenumber {
+            Some(housenumber) => format!("{} {}", street, housenumber),
+            None => street,
+        });
+        [self.name.or(street), self.city, self.country]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Configured provider
+struct Backend {
+    provider: Provider,
+    url: String,
+    client: reqwest::Client,
+    /// Earliest time of the next request to the provider
+    next_request: Mutex<Instant>,
+}
+
+/// Rocket state for geocoding place names. Responses of the provider are cached in the
+/// database and requests to it are rate limited.
+#[derive(Clone)]
+pub struct Geocoder {
+    backend: Option<Arc<Backend>>,
+}
+
+impl Geocoder {
+    /// Geocoder using [provider] at [url], or at its public instance if [url] is not
+    /// set. Geocoding is disabled without [provider].
+    pub fn new(provider: Option<Provider>, url: Option<String>) -> Result<Self, reqwest::Error> {
+        let Some(provider) = provider else {
+            return Ok(Self { backend: None });
+        };
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(
+            Self {
+                backend: Some(Arc::new(
+                    Backend {
+                        provider,
+                        url: url.unwrap_or_else(|| provider.default_url().to_string()),
+                        client,
+                        next_request: Mutex::new(Instant::now()),
+                    }
+                )),
+            }
+        )
+    }
+
+    /// Whether a provider is configured
+    pub fn is_enabled(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    /// Places matching [query]. Cached responses are used if they are not older than
+    /// [CACHE_TTL].
+    pub async fn search(&self, query: &str, db: &impl ConnectionTrait) -> Result<Vec<Place>, CurdError> {
+        let backend = self.backend.as_ref().ok_or(CurdError::NotFound)?;
+        let query = normalize(query);
+        let provider = backend.provider.to_string();
+
+        let cached = geocode_cache::Entity::find()
+            .filter(geocode_cache::Column::Provider.eq(provider.as_str()))
+            .filter(geocode_cache::Column::Query.eq(query.as_str()))
+            .filter(geocode_cache::Column::CreatedAt.gt(chrono::Utc::now() - CACHE_TTL))
+            .one(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        if let Some(cached) = cached {
+            if let Ok(places) = serde_json::from_str(&cached.results) {
+                return Ok(places);
+            }
+        }
+
+        let places = backend.request(&query).await?;
+        let results = serde_json::to_string(&places)
+            .map_err(
+                |error| {
+                    CurdError::InternalError(error.to_string())
+                }
+            )?;
+        geocode_cache::Entity::insert(
+            geocode_cache::ActiveModel {
+                created_at: Set(chrono::Utc::now()),
+                provider: Set(provider),
+                query: Set(query),
+                results: Set(results),
+                ..Default::default()
+            }
+        )
+            .on_conflict(
+                OnConflict::columns([geocode_cache::Column::Provider, geocode_cache::Column::Query])
+                    .update_columns([geocode_cache::Column::CreatedAt, geocode_cache::Column::Results])
+                    .to_owned()
+            )
+            .exec(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(places)
+    }
+}
+
+impl Backend {
+    /// Request [query] from the provider, waiting for [MIN_REQUEST_INTERVAL] since the
+    /// previous request
+    async fn request(&self, query: &str) -> Result<Vec<Place>, CurdError> {
+        let url = self.provider.search_url(&self.url, query)?;
+        {
+            let mut next_request = self.next_request.lock().await;
+            tokio::time::sleep_until(*next_request).await;
+            *next_request = Instant::now() + MIN_REQUEST_INTERVAL;
+        }
+
+        let upstream_error = |error: &dyn Display| {
+            error!("Geocoding request to {} failed: {}", self.provider, error);
+            CurdError::Upstream(format!("Geocoding provider {} failed", self.provider))
+        };
+        let response = self.client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| upstream_error(&error))?;
+        let body = response.text()
+            .await
+            .map_err(|error| upstream_error(&error))?;
+        let mut places = self.provider.parse(&body)
+            .map_err(|error| upstream_error(&error))?;
+        places.truncate(RESULT_LIMIT);
+        Ok(places)
+    }
+}
+
+/// Query as cached, trimmed, lowercase and with single spaces
+fn normalize(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
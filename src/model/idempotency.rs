@@ -0,0 +1,107 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::TimeDelta;
+use sea_orm::{
+    prelude::*,
+    Set,
+    NotSet,
+};
+use entity::idempotency_key;
+use super::error::CurdError;
+
+/// Time after which a key can be reused
+pub const KEY_LIFETIME: TimeDelta = TimeDelta::hours(24);
+
+/// State of an idempotency key
+pub enum Lookup {
+    /// First request with this key. The response must be recorded with [complete] or
+    /// the key released with [release].
+    New(u32),
+    /// The request was already processed, respond with the recorded response
+    Replay {
+        status: u16,
+        body: String,
+    },
+}
+
+/// Look up [key] of [user_id] and reserve it if it is new. [fingerprint] identifies the
+/// request, a key must not be reused for a different request.
+pub async fn begin(
+    user_id: u32,
+    key: &str,
+    fingerprint: &str,
+    db: &impl ConnectionTrait,
+) -> Result<Lookup, CurdError> {
+    let now = chrono::Utc::now();
+    idempotency_key::Entity::delete_many()
+        .filter(idempotency_key::Column::CreatedAt.lt(now - KEY_LIFETIME))
+        .exec(db)
+        .await
+        .map_err(CurdError::DbErr)?;
+
+    let existing = idempotency_key::Entity::find()
+        .filter(idempotency_key::Column::UserId.eq(user_id))
+        .filter(idempotency_key::Column::Key.eq(key))
+        .one(db)
+        .await
+        .map_err(CurdError::DbErr)?;
+    if let Some(existing) = existing {
+        if existing.fingerprint != fingerprint {
+            Err(CurdError::Unprocessable("Idempotency key was used for a different request".to_string()))?;
+        }
+        return match (existing.status, existing.body) {
+            (Some(status), Some(body)) => Ok(Lookup::Replay {
+                status: status as u16,
+                body,
+            }),
+            _ => Err(CurdError::Conflict("Request with this idempotency key is in progress".to_string())),
+        };
+    }
+
+    let model = idempotency_key::ActiveModel {
+        id: NotSet,
+        created_at: Set(now),
+        user_id: Set(user_id),
+        key: Set(key.to_string()),
+        fingerprint: Set(fingerprint.to_string()),
+        status: Set(None),
+        body: Set(None),
+    };
+    // A concurrent request with the same key may have reserved it in the meantime
+    match idempotency_key::Entity::insert(model).exec(db).await {
+        Ok(result) => Ok(Lookup::New(result.last_insert_id)),
+        Err(e) => match e.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => {
+                Err(CurdError::Conflict("Request with this idempotency key is in progress".to_string()))
+            },
+            _ => Err(CurdError::DbErr(e)),
+        },
+    }
+}
+
+/// Record the response of the request which reserved key [id]
+pub async fn complete(id: u32, status: u16, body: &str, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let model = idempotency_key::ActiveModel {
+        id: Set(id),
+        status: Set(Some(status as u32)),
+        body: Set(Some(body.to_string())),
+        ..Default::default()
+    };
+    model.update(db).await.map_err(CurdError::DbErr)?;
+    Ok(())
+}
+
+/// Release key [id] after the request failed, so it can be retried
+pub async fn release(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    idempotency_key::Entity::delete_by_id(id)
+        .exec(db)
+        .await
+        .map_err(CurdError::DbErr)?;
+    Ok(())
+}
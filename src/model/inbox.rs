@@ -0,0 +1,315 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{
+    prelude::*,
+    QueryOrder,
+    Set,
+    NotSet,
+};
+use entity::{inbox_address, inbox_item};
+use entity::tag_descriptor::TagType;
+use super::audit::Actor;
+use super::error::CurdError;
+use super::receipt;
+use super::ride::{self, Ride};
+use super::ride_tag_link::{self, Value};
+use super::tag::Tag;
+
+/// Mail bodies are stored up to this number of characters
+const MAX_BODY_CHARS: usize = 65536;
+
+/// Rocket state with the settings of the inbox
+#[derive(Debug, Clone)]
+pub struct Inbox {
+    /// Domain of the inbox addresses, the inbox is disabled if not set
+    domain: Option<String>,
+    /// SHA-256 of the secret of the inbound mail webhook
+    secret_hash: Option<[u8; 32]>,
+    /// Key of the tag the amount is linked with on confirmation
+    price_tag: String,
+}
+
+impl Inbox {
+    /// Inbox with addresses at [domain], receiving mails with [secret]. Amounts are
+    /// linked with the tag [price_tag].
+    pub fn new(domain: Option<String>, secret: Option<&str>, price_tag: String) -> Self {
+        Self {
+            domain,
+            secret_hash: secret.map(|secret| Sha256::digest(secret.as_bytes()).into()),
+            price_tag,
+        }
+    }
+
+    /// Whether the inbox is configured
+    pub fn is_enabled(&self) -> bool {
+        self.domain.is_some() && self.secret_hash.is_some()
+    }
+
+    /// Whether [secret] is the secret of the webhook. Hashes are compared, so the time
+    /// taken does not tell how much of the secret matches.
+    pub fn check_secret(&self, secret: &str) -> bool {
+        let hash: [u8; 32] = Sha256::digest(secret.as_bytes()).into();
+        self.secret_hash == Some(hash)
+    }
+
+    /// Address of [user_id], created on first use
+    pub async fn address(&self, user_id: u32, db: &impl ConnectionTrait) -> Result<InboxAddress, CurdError> {
+        let domain = self.domain.as_ref().ok_or(CurdError::NotFound)?;
+        let existing = inbox_address::Entity::find()
+            .filter(inbox_address::Column::UserId.eq(user_id))
+            .one(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        let token = match existing {
+            Some(existing) => existing.token,
+            None => {
+                let model = inbox_address::ActiveModel {
+                    id: NotSet,
+                    created_at: NotSet,
+                    user_id: Set(user_id),
+                    token: Set(new_token()),
+                };
+                model
+                    .insert(db)
+                    .await
+                    .map_err(
+                        |error| {
+                            CurdError::DbErr(error)
+                        }
+                    )?
+                    .token
+            },
+        };
+        Ok(
+            InboxAddress {
+                address: format!("{}@{}", token, domain),
+            }
+        )
+    }
+
+    /// Create [ride] as confirmed by the user from [item_id] and remove the item. The
+    /// amount of the item is linked with the price tag if the user has one of type
+    /// `float`.
+    pub async fn confirm(&self, item_id: u32, user_id: u32, ride: Ride, actor: &Actor, db: &impl ConnectionTrait) -> Result<Ride, CurdError> {
+        let item = InboxItem::find_by_id(item_id, db).await?;
+        let ride = ride::CreateUpdateBuilder::from_json(ride)
+            .insert(user_id, actor, db)
+            .await?;
+        if let Some(amount) = item.amount {
+            let price_tag = Tag::find_all(user_id, false, false, db)
+                .await?
+                .into_iter()
+                .find(|tag| *tag.tag_key() == self.price_tag && matches!(TagType::try_from(tag.tag_type.clone()), Ok(TagType::Float)));
+            if let Some(price_tag) = price_tag {
+                ride_tag_link::CreateUpdateBuilder::new(0, Value::Float(amount), None)
+                    .insert(ride.id(), price_tag.id(), actor, db)
+                    .await?;
+            }
+        }
+        remove(item_id, db).await?;
+        Ride::find_by_id(ride.id(), true, false, db).await
+    }
+}
+
+/// Address to forward booking confirmations to
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct InboxAddress {
+    pub address: String,
+}
+
+/// Mail posted by the inbound mail service
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+pub struct InboundMail {
+    pub from: String,
+    /// Recipients, e.g. `Inbox <token@inbox.example.tld>, other@example.tld`
+    pub to: String,
+    #[serde(default)]
+    pub subject: String,
+    /// Plain text body
+    pub text: String,
+}
+
+/// JSON structure of a draft ride parsed from a mail
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct InboxItem {
+    id: u32,
+    created_at: DateTimeUtc,
+    pub mail_from: String,
+    pub subject: String,
+    pub body: String,
+    /// Departure found in the mail. The mail does not tell the time zone, so the time is
+    /// taken as UTC.
+    pub journey_departure: Option<DateTimeUtc>,
+    pub location_from: Option<String>,
+    pub location_to: Option<String>,
+    /// Total amount found in the mail
+    pub amount: Option<f64>,
+    pub currency: Option<String>,
+}
+
+impl From<inbox_item::Model> for InboxItem {
+    fn from(model: inbox_item::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            mail_from: model.mail_from,
+            subject: model.subject,
+            body: model.body,
+            journey_departure: model.journey_departure,
+            location_from: model.location_from,
+            location_to: model.location_to,
+            amount: model.amount,
+            currency: model.currency,
+        }
+    }
+}
+
+impl InboxItem {
+    /// Getter for [id]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Fetch all instances belonging to [user_id], newest first
+    pub async fn find_all(user_id: u32, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        let models = inbox_item::Entity::find()
+            .filter(inbox_item::Column::UserId.eq(user_id))
+            .order_by_desc(inbox_item::Column::Id)
+            .all(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(models.into_iter().map(Self::from).collect())
+    }
+
+    /// Find instance by [id].
+    pub async fn find_by_id(id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        let model = inbox_item::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        match model {
+            Some(model) => Ok(Self::from(model)),
+            None => Err(CurdError::NotFound)?,
+        }
+    }
+}
+
+/// Check if [item_id] belongs to [user_id].
+pub async fn is_owner(
+    item_id: u32,
+    user_id: u32,
+    db: &impl ConnectionTrait
+) -> Result<(), CurdError> {
+    let rows = inbox_item::Entity::find()
+        .filter(inbox_item::Column::Id.eq(item_id))
+        .filter(inbox_item::Column::UserId.eq(user_id))
+        .count(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if rows == 0 {
+        Err(CurdError::NotFound)
+    } else {
+        Ok(())
+    }
+}
+
+/// Store [mail] as draft ride of the user whose inbox address is among its recipients.
+/// Fails with [CurdError::NotFound] if there is none.
+pub async fn receive(mail: InboundMail, db: &impl ConnectionTrait) -> Result<InboxItem, CurdError> {
+    let tokens: Vec<String> = mail.to
+        .split(',')
+        .filter_map(|recipient| {
+            let address = match recipient.rsplit_once('<') {
+                Some((_, address)) => address.trim_end().trim_end_matches('>'),
+                None => recipient,
+            };
+            address.trim().split_once('@').map(|(token, _)| token.to_lowercase())
+        })
+        .collect();
+    let address = inbox_address::Entity::find()
+        .filter(inbox_address::Column::Token.is_in(tokens))
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+
+    let values = receipt::extract(&format!("{}\n{}", mail.subject, mail.text));
+    let journey_departure = values.date
+        .map(|date| date.and_time(values.time.unwrap_or_default()).and_utc());
+    let model = inbox_item::ActiveModel {
+        id: NotSet,
+        created_at: NotSet,
+        user_id: Set(address.user_id),
+        mail_from: Set(mail.from),
+        subject: Set(mail.subject),
+        body: Set(mail.text.chars().take(MAX_BODY_CHARS).collect()),
+        journey_departure: Set(journey_departure),
+        location_from: Set(values.location_from),
+        location_to: Set(values.location_to),
+        amount: Set(values.amount),
+        currency: Set(values.currency),
+    };
+    let result = model
+        .insert(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(InboxItem::from(result))
+}
+
+/// Remove instance by [id].
+pub async fn remove(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let result = inbox_item::Entity::delete_by_id(id)
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if result.rows_affected == 0 {
+        Err(CurdError::NotFound)
+    } else {
+        Ok(())
+    }
+}
+
+/// Random local part of an inbox address
+fn new_token() -> String {
+    rand::random::<[u8; 12]>()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,35 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use sea_orm::{prelude::*, QuerySelect};
+use super::error::CurdError;
+use super::retry::retry;
+
+/// Time of the last update or deletion of the rows selected by [query]. Deleted rows
+/// count, so that removing a row changes the result. `None` if no row is selected.
+pub async fn latest_change<E: EntityTrait>(
+    query: Select<E>,
+    updated_at: E::Column,
+    deleted_at: E::Column,
+    db: &impl ConnectionTrait,
+) -> Result<Option<DateTimeUtc>, CurdError> {
+    let statement = query
+        .select_only()
+        .column_as(updated_at.max(), "updated_at")
+        .column_as(deleted_at.max(), "deleted_at");
+    let row = retry(|| statement.clone().into_tuple::<(Option<DateTimeUtc>, Option<DateTimeUtc>)>().one(db))
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(
+        row.and_then(|(updated_at, deleted_at)| updated_at.max(deleted_at))
+    )
+}
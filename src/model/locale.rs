@@ -0,0 +1,138 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::{DateTime, NaiveDate};
+use sea_orm::prelude::*;
+use entity::user;
+pub use entity::user::Locale;
+use super::error::CurdError;
+
+/// Column headers of the German locale, by field name
+const GERMAN_HEADERS: &[(&str, &str)] = &[
+    ("id", "ID"),
+    ("journey_departure", "Abfahrt"),
+    ("journey_arrival", "Ankunft"),
+    ("location_from", "Von"),
+    ("location_to", "Nach"),
+    ("remarks", "Bemerkungen"),
+    ("is_template", "Vorlage"),
+    ("passengers", "Personen"),
+    ("vat_rate", "MwSt.-Satz"),
+    ("status", "Status"),
+    ("arrival_estimated", "Ankunft geschätzt"),
+    ("created_at", "Erstellt"),
+    ("updated_at", "Geändert"),
+    ("deleted_at", "Gelöscht"),
+    ("tags", "Tags"),
+    ("tag_type", "Typ"),
+    ("tag_key", "Schlüssel"),
+    ("tag_name", "Name"),
+    ("tag_display_name", "Anzeigename"),
+    ("uuid", "UUID"),
+    ("unit", "Einheit"),
+    ("options", "Optionen"),
+];
+
+/// [Locale] of a language tag like `de`, `de-DE` or `de_AT`, by its language. `None` if
+/// the language is not supported.
+pub fn parse(tag: &str) -> Option<Locale> {
+    let language = tag.trim().split(['-', '_']).next().unwrap_or_default().to_lowercase();
+    match language.as_str() {
+        "en" => Some(Locale::En),
+        "de" => Some(Locale::De),
+        _ => None,
+    }
+}
+
+/// The [requested] locale, else the preference of [user_id], else English
+pub async fn resolve(requested: Option<Locale>, user_id: u32, db: &impl ConnectionTrait) -> Result<Locale, CurdError> {
+    if let Some(locale) = requested {
+        return Ok(locale);
+    }
+    let user = user::Entity::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(user.and_then(|user| user.locale).unwrap_or_default())
+}
+
+/// Layout of CSV files in a locale
+#[derive(Debug, Clone, Copy)]
+pub struct CsvFormat {
+    locale: Locale,
+}
+
+impl CsvFormat {
+    pub fn new(locale: Locale) -> Self {
+        Self {
+            locale,
+        }
+    }
+
+    /// Separator of the cells, a semicolon where the comma is the decimal separator
+    pub fn delimiter(&self) -> u8 {
+        match self.locale {
+            Locale::En => b',',
+            Locale::De => b';',
+        }
+    }
+
+    /// CSV writer with the [delimiter]
+    pub fn writer(&self) -> csv::Writer<Vec<u8>> {
+        csv::WriterBuilder::new()
+            .delimiter(self.delimiter())
+            .from_writer(Vec::new())
+    }
+
+    /// Header of the column of field [name], the name itself if there is no translation
+    pub fn header<'a>(&self, name: &'a str) -> &'a str {
+        match self.locale {
+            Locale::En => name,
+            Locale::De => GERMAN_HEADERS
+                .iter()
+                .find(|(field, _)| *field == name)
+                .map(|(_, header)| *header)
+                .unwrap_or(name),
+        }
+    }
+
+    /// [number] written with a decimal point, with the decimal separator of the locale
+    pub fn number(&self, number: &str) -> String {
+        match self.locale {
+            Locale::En => number.to_string(),
+            Locale::De => number.replace('.', ","),
+        }
+    }
+
+    /// [value] in the date format of the locale
+    pub fn date_time(&self, value: DateTimeUtc) -> String {
+        match self.locale {
+            Locale::En => value.to_rfc3339(),
+            Locale::De => value.format("%d.%m.%Y %H:%M").to_string(),
+        }
+    }
+
+    /// [text] with RFC 3339 times and `YYYY-MM-DD` dates in the date format of the
+    /// locale, other text unchanged
+    pub fn text(&self, text: &str) -> String {
+        if self.locale == Locale::En {
+            return text.to_string();
+        }
+        if let Ok(value) = DateTime::parse_from_rfc3339(text) {
+            return value.format("%d.%m.%Y %H:%M").to_string();
+        }
+        if let Ok(value) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            return value.format("%d.%m.%Y").to_string();
+        }
+        text.to_string()
+    }
+}
@@ -0,0 +1,111 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::{Arc, RwLock};
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::ConnectionTrait;
+use sea_orm::prelude::DateTimeUtc;
+use crate::fairings::cache_invalidation::{CacheNotifier, Invalidation};
+use super::error::CurdError;
+use super::server_setting;
+
+/// Seconds clients are asked to wait if not requested otherwise
+pub const DEFAULT_RETRY_AFTER_SECONDS: u32 = 300;
+
+/// Requested maintenance mode
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+pub struct MaintenanceRequest {
+    pub enabled: bool,
+    /// Reason shown to clients, e.g. the running migration
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Seconds clients should wait before retrying, 300 if not set
+    #[serde(default)]
+    pub retry_after_seconds: Option<u32>,
+}
+
+/// State of the maintenance mode
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    pub message: Option<String>,
+    pub retry_after_seconds: u32,
+    /// Time the maintenance mode was enabled
+    pub since: Option<DateTimeUtc>,
+}
+
+impl Default for MaintenanceStatus {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            message: None,
+            retry_after_seconds: DEFAULT_RETRY_AFTER_SECONDS,
+            since: None,
+        }
+    }
+}
+
+/// Rocket state with the maintenance mode of all server instances. While it is enabled,
+/// writes are rejected and reads stay available. The mode is kept in the database, so
+/// that it survives restarts, and other instances are told to reload it if a
+/// [CacheNotifier] is set. Clones share the state.
+#[derive(Clone, Default)]
+pub struct Maintenance {
+    status: Arc<RwLock<MaintenanceStatus>>,
+    notifier: Option<CacheNotifier>,
+}
+
+impl Maintenance {
+    pub fn new(notifier: Option<CacheNotifier>) -> Self {
+        Self {
+            notifier,
+            ..Default::default()
+        }
+    }
+
+    /// Whether writes are rejected
+    pub fn is_enabled(&self) -> bool {
+        self.status().enabled
+    }
+
+    /// Current state
+    pub fn status(&self) -> MaintenanceStatus {
+        self.status.read().expect("Maintenance lock is not poisoned").clone()
+    }
+
+    /// Read the stored state from [db], disabled if it was never set
+    pub async fn reload(&self, db: &impl ConnectionTrait) -> Result<MaintenanceStatus, CurdError> {
+        let status: MaintenanceStatus = server_setting::load(server_setting::MAINTENANCE, db)
+            .await?
+            .unwrap_or_default();
+        *self.status.write().expect("Maintenance lock is not poisoned") = status.clone();
+        Ok(status)
+    }
+
+    /// Enable or disable the maintenance mode on all server instances
+    pub async fn set(&self, request: MaintenanceRequest, db: &impl ConnectionTrait) -> Result<MaintenanceStatus, CurdError> {
+        let status = if request.enabled {
+            MaintenanceStatus {
+                enabled: true,
+                message: request.message,
+                retry_after_seconds: request.retry_after_seconds.unwrap_or(DEFAULT_RETRY_AFTER_SECONDS),
+                // Keep the start of a running maintenance if only the message changes
+                since: self.status().since.or_else(|| Some(chrono::Utc::now())),
+            }
+        } else {
+            MaintenanceStatus::default()
+        };
+        server_setting::store(server_setting::MAINTENANCE, &status, db).await?;
+        *self.status.write().expect("Maintenance lock is not poisoned") = status.clone();
+        if let Some(notifier) = &self.notifier {
+            notifier.publish(Invalidation::Maintenance).await;
+        }
+        Ok(status)
+    }
+}
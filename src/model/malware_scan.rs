@@ -0,0 +1,204 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+use super::error::CurdError;
+use super::geocode::USER_AGENT;
+
+/// Time after which a scan is aborted
+const SCAN_TIMEOUT: Duration = Duration::from_secs(60);
+/// Default port of clamd
+const CLAMD_PORT: u16 = 3310;
+/// Size of the chunks streamed to clamd
+const CLAMD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Malware scanner of uploads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scanner {
+    /// ClamAV daemon, reached by TCP or a Unix socket
+    Clamd,
+    /// External service receiving the upload by POST and responding with the verdict
+    Http,
+}
+
+impl Scanner {
+    /// Fails if [url] is not valid for the scanner
+    pub fn validate_url(&self, url: &str) -> Result<(), String> {
+        let url = Url::parse(url).map_err(|e| format!("Invalid malware_scanner_url: {}", e))?;
+        let schemes: &[&str] = match self {
+            Scanner::Clamd => &["tcp", "unix"],
+            Scanner::Http => &["http", "https"],
+        };
+        if !schemes.contains(&url.scheme()) {
+            Err(format!("malware_scanner_url must start with {}://", schemes.join(":// or ")))?;
+        }
+        if url.scheme() == "tcp" && url.host_str().is_none() {
+            Err("malware_scanner_url needs a host")?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of a scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Clean,
+    /// Malicious, with what the scanner found, e.g. the name of the signature
+    Infected(String),
+}
+
+/// Configured scanner
+enum Backend {
+    Clamd {
+        url: Url,
+    },
+    Http {
+        url: String,
+        client: reqwest::Client,
+    },
+}
+
+/// Rocket state scanning uploads for malware
+#[derive(Clone, Default)]
+pub struct MalwareScanner {
+    backend: Option<Arc<Backend>>,
+}
+
+impl MalwareScanner {
+    /// Scanner of kind [scanner] at [url]. Uploads are not scanned without [scanner].
+    pub fn new(scanner: Option<Scanner>, url: Option<String>) -> Result<Self, String> {
+        let backend = match (scanner, url) {
+            (Some(scanner), Some(url)) => {
+                scanner.validate_url(&url)?;
+                match scanner {
+                    Scanner::Clamd => Backend::Clamd {
+                        url: Url::parse(&url).map_err(|e| e.to_string())?,
+                    },
+                    Scanner::Http => Backend::Http {
+                        url,
+                        client: reqwest::Client::builder()
+                            .user_agent(USER_AGENT)
+                            .timeout(SCAN_TIMEOUT)
+                            .build()
+                            .map_err(|e| e.to_string())?,
+                    },
+                }
+            },
+            (Some(_), None) => Err("The malware scanner needs malware_scanner_url")?,
+            (None, _) => return Ok(Self::default()),
+        };
+        Ok(
+            Self {
+                backend: Some(Arc::new(backend)),
+            }
+        )
+    }
+
+    /// Verdict on [data], None if no scanner is configured. Fails with
+    /// [CurdError::Upstream] if the scanner cannot be reached or gives no verdict, so
+    /// that uploads are not accepted unscanned.
+    pub async fn scan(&self, data: &[u8]) -> Result<Option<Verdict>, CurdError> {
+        let Some(backend) = &self.backend else {
+            return Ok(None);
+        };
+        tokio::time::timeout(SCAN_TIMEOUT, backend.scan(data))
+            .await
+            .unwrap_or_else(|_| Err("Timed out".to_string()))
+            .map(Some)
+            .map_err(
+                |error| {
+                    error!("Malware scan failed: {}", error);
+                    CurdError::Upstream("Malware scanner failed".to_string())
+                }
+            )
+    }
+}
+
+impl Backend {
+    async fn scan(&self, data: &[u8]) -> Result<Verdict, String> {
+        match self {
+            Backend::Clamd { url } => {
+                let reply = if url.scheme() == "unix" {
+                    let stream = UnixStream::connect(url.path())
+                        .await
+                        .map_err(|e| format!("Cannot connect to clamd at {}: {}", url, e))?;
+                    clamd_instream(stream, data).await
+                } else {
+                    let host = url.host_str().unwrap_or_default();
+                    let stream = TcpStream::connect((host, url.port().unwrap_or(CLAMD_PORT)))
+                        .await
+                        .map_err(|e| format!("Cannot connect to clamd at {}: {}", url, e))?;
+                    clamd_instream(stream, data).await
+                }
+                    .map_err(|e| format!("clamd at {} failed: {}", url, e))?;
+                parse_clamd_reply(&reply)
+            },
+            Backend::Http { url, client } => {
+                #[derive(Deserialize)]
+                struct ScanResponse {
+                    infected: bool,
+                    #[serde(default)]
+                    finding: Option<String>,
+                }
+                let body = client
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+                    .body(data.to_vec())
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| format!("Request to {} failed: {}", url, e))?
+                    .text()
+                    .await
+                    .map_err(|e| format!("Request to {} failed: {}", url, e))?;
+                let response: ScanResponse = serde_json::from_str(&body)
+                    .map_err(|e| format!("Invalid response of {}: {}", url, e))?;
+                Ok(
+                    if response.infected {
+                        Verdict::Infected(response.finding.unwrap_or_else(|| "Malware".to_string()))
+                    } else {
+                        Verdict::Clean
+                    }
+                )
+            },
+        }
+    }
+}
+
+/// Stream [data] to clamd with the command INSTREAM and return its reply. clamd closes
+/// the connection after replying.
+async fn clamd_instream<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, data: &[u8]) -> std::io::Result<String> {
+    stream.write_all(b"zINSTREAM\0").await?;
+    for chunk in data.chunks(CLAMD_CHUNK_SIZE) {
+        stream.write_all(&(chunk.len() as u32).to_be_bytes()).await?;
+        stream.write_all(chunk).await?;
+    }
+    stream.write_all(&0u32.to_be_bytes()).await?;
+    stream.flush().await?;
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await?;
+    Ok(String::from_utf8_lossy(&reply).trim_end_matches('\0').trim().to_string())
+}
+
+/// Verdict of a clamd reply like `stream: OK` or `stream: Eicar-Signature FOUND`
+fn parse_clamd_reply(reply: &str) -> Result<Verdict, String> {
+    let result = reply.strip_prefix("stream:").map(str::trim);
+    match result {
+        Some("OK") => Ok(Verdict::Clean),
+        Some(result) if result.ends_with(" FOUND") => Ok(
+            Verdict::Infected(result.trim_end_matches(" FOUND").trim().to_string())
+        ),
+        _ => Err(format!("Unexpected reply of clamd: {}", reply)),
+    }
+}
@@ -0,0 +1,356 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::fmt::Write;
+use std::sync::Arc;
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sea_orm::prelude::*;
+use entity::tag_descriptor::TagType;
+use entity::user;
+use super::error::CurdError;
+use super::ride::Ride;
+use super::ride_tag_link::Value;
+use super::tag::Tag;
+use super::vat::VatBreakdown;
+
+/// Value of a column of a reimbursement line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    /// Name of the user, or the subject of the token if the user has no name
+    Employee,
+    RideId,
+    /// Month of the departure as `YYYY-MM`
+    Month,
+    /// Date of the departure in `date_format`
+    Date,
+    /// Time of the departure as `HH:MM`
+    Time,
+    From,
+    To,
+    /// Value of the mode option
+    Mode,
+    /// Number of travellers the price covers
+    Passengers,
+    Price,
+    /// VAT rate of the price in percent, empty if unknown
+    VatRate,
+    /// Price without VAT, empty if the rate is unknown
+    Net,
+    /// VAT included in the price, empty if the rate is unknown
+    Vat,
+    /// Part of the price paid by the employee, including amounts above the cap and the
+    /// part of other passengers
+    EmployeeShare,
+    /// Part of the price paid by the employer
+    Reimbursed,
+    Currency,
+}
+
+/// Column of the CSV layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Column {
+    pub header: String,
+    pub field: Field,
+}
+
+/// Mobility-budget scheme of the employer, configured in `[mobility_budget]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MobilityBudgetScheme {
+    /// Key of the enum tag holding the mode of transport
+    pub mode_tag: String,
+    /// Values of the options of the mode tag which are reimbursed, compared
+    /// case-insensitively
+    pub eligible_modes: Vec<String>,
+    /// Maximum reimbursement per calendar month, unlimited if not set
+    #[serde(default)]
+    pub monthly_cap: Option<f64>,
+    /// Part of each price paid by the employee, e.g. `0.2` for 20 %
+    #[serde(default)]
+    pub employee_share: f64,
+    pub currency: String,
+    /// Offset of the time zone of dates and months in minutes
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Columns of the CSV file in order
+    #[serde(default = "MobilityBudgetScheme::default_columns")]
+    pub columns: Vec<Column>,
+    #[serde(default = "MobilityBudgetScheme::default_delimiter")]
+    pub delimiter: char,
+    /// Write amounts with a decimal comma
+    #[serde(default)]
+    pub decimal_comma: bool,
+    /// Format of dates, see
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    #[serde(default = "MobilityBudgetScheme::default_date_format")]
+    pub date_format: String,
+}
+
+impl MobilityBudgetScheme {
+    fn default_columns() -> Vec<Column> {
+        [
+            Field::Employee,
+            Field::Date,
+            Field::From,
+            Field::To,
+            Field::Mode,
+            Field::Price,
+            Field::EmployeeShare,
+            Field::Reimbursed,
+            Field::Currency,
+        ]
+            .into_iter()
+            .map(|field| Column {
+                header: serde_json::to_value(field)
+                    .ok()
+                    .and_then(|value| value.as_str().map(|header| header.to_string()))
+                    .unwrap_or_default(),
+                field,
+            })
+            .collect()
+    }
+
+    fn default_delimiter() -> char {
+        ','
+    }
+
+    fn default_date_format() -> String {
+        "%Y-%m-%d".to_string()
+    }
+
+    /// Fails on shares outside of 0 to 1, negative caps and invalid layouts
+    fn validate(&self) -> Result<(), String> {
+        if !self.employee_share.is_finite() || !(0.0..=1.0).contains(&self.employee_share) {
+            Err("employee_share of mobility_budget must be between 0 and 1")?;
+        }
+        if self.monthly_cap.is_some_and(|cap| !cap.is_finite() || cap < 0.0) {
+            Err("monthly_cap of mobility_budget must not be negative")?;
+        }
+        if self.utc_offset_minutes.abs() >= 24 * 60 {
+            Err("utc_offset_minutes of mobility_budget must be less than a day")?;
+        }
+        if self.columns.is_empty() {
+            Err("mobility_budget needs columns")?;
+        }
+        if !self.delimiter.is_ascii() || self.delimiter == '"' || self.delimiter == '\n' {
+            Err("delimiter of mobility_budget must be an ASCII character other than a quote or newline")?;
+        }
+        let mut date = String::new();
+        if write!(date, "{}", NaiveDate::default().format(&self.date_format)).is_err() {
+            Err(format!("Invalid date_format of mobility_budget {}", self.date_format))?;
+        }
+        Ok(())
+    }
+
+    fn offset(&self) -> FixedOffset {
+        FixedOffset::east_opt(self.utc_offset_minutes * 60).expect("Offset is validated")
+    }
+
+    /// Amount in the layout, with two decimals
+    fn amount(&self, amount: f64) -> String {
+        let amount = format!("{:.2}", amount);
+        if self.decimal_comma {
+            amount.replace('.', ",")
+        } else {
+            amount
+        }
+    }
+}
+
+/// Ride as reimbursed by the scheme
+#[derive(Debug, Clone)]
+pub struct ReimbursementLine {
+    pub ride_id: u32,
+    pub departure: DateTime<FixedOffset>,
+    pub location_from: String,
+    pub location_to: String,
+    pub mode: String,
+    pub passengers: u32,
+    /// Price of the ticket for all passengers
+    pub price: f64,
+    /// Breakdown of the price, if the VAT rate is known
+    pub vat: Option<VatBreakdown>,
+    pub vat_rate: Option<f64>,
+    pub employee_share: f64,
+    pub reimbursed: f64,
+}
+
+/// Rocket state with the mobility-budget scheme of the instance
+#[derive(Debug, Clone)]
+pub struct MobilityBudget {
+    scheme: Option<Arc<MobilityBudgetScheme>>,
+    /// Key of the tag holding the price of a ride
+    price_tag: String,
+}
+
+impl MobilityBudget {
+    /// Reports of [scheme], reimbursing the values of the tag [price_tag]. Fails on an
+    /// invalid scheme.
+    pub fn new(scheme: Option<MobilityBudgetScheme>, price_tag: String) -> Result<Self, String> {
+        if let Some(scheme) = &scheme {
+            scheme.validate()?;
+        }
+        Ok(
+            Self {
+                scheme: scheme.map(Arc::new),
+                price_tag,
+            }
+        )
+    }
+
+    /// Whether a scheme is configured
+    pub fn is_enabled(&self) -> bool {
+        self.scheme.is_some()
+    }
+
+    /// Reimbursement lines of the rides of [user_id] departing in the months [from] to
+    /// [to], given as their first days. Rides without price or eligible mode and
+    /// templates are skipped. Only the employee's part of tickets for several passengers
+    /// is reimbursed, the price divided by the passengers. The cap applies to the rides
+    /// of a month in order of departure.
+    pub async fn lines(&self, user_id: u32, from: NaiveDate, to: NaiveDate, db: &impl ConnectionTrait) -> Result<Vec<ReimbursementLine>, CurdError> {
+        let Some(scheme) = &self.scheme else {
+            return Ok(Vec::new());
+        };
+        let offset = scheme.offset();
+        let tags = Tag::find_all(user_id, true, false, db).await?;
+        let Some(price_tag) = tags.iter().find(|tag| *tag.tag_key() == self.price_tag) else {
+            return Ok(Vec::new());
+        };
+        let Some(mode_tag) = tags
+            .iter()
+            .find(|tag| *tag.tag_key() == scheme.mode_tag && matches!(TagType::try_from(tag.tag_type.clone()), Ok(TagType::Enum)))
+        else {
+            return Ok(Vec::new());
+        };
+        let eligible: Vec<(u32, String)> = mode_tag.options()
+            .iter()
+            .flatten()
+            .filter(|option| scheme.eligible_modes.iter().any(|mode| mode.to_lowercase() == option.value.to_lowercase()))
+            .map(|option| (option.id(), option.value.clone()))
+            .collect();
+
+        let mut rides = Ride::find_all(user_id, true, false, db).await?;
+        rides.sort_by_key(|ride| (ride.journey_departure, ride.id()));
+        let mut lines = Vec::new();
+        let mut month = None;
+        let mut used = 0.0;
+        for ride in rides.into_iter().filter(|ride| !ride.is_template) {
+            let departure = ride.journey_departure.with_timezone(&offset);
+            let first_day = departure.date_naive().with_day(1).expect("Every month has a first day");
+            if first_day < from || first_day > to {
+                continue;
+            }
+            let links = ride.tags().iter().flatten();
+            let price = links
+                .clone()
+                .find(|link| link.tag_id() == price_tag.id())
+                .and_then(|link| match link.value {
+                    Value::Float(price) => Some(price),
+                    Value::Integer(price) => Some(price as f64),
+                    _ => None,
+                });
+            let mode = links
+                .filter(|link| link.tag_id() == mode_tag.id())
+                .find_map(|link| match link.value {
+                    Value::EnumOption(option_id) => eligible.iter().find(|(id, _)| *id == option_id),
+                    _ => None,
+                });
+            let (Some(price), Some((_, mode))) = (price, mode) else {
+                continue;
+            };
+            if month != Some(first_day) {
+                month = Some(first_day);
+                used = 0.0;
+            }
+            let own_price = price / ride.passengers.max(1) as f64;
+            let mut reimbursed = cents(own_price * (1.0 - scheme.employee_share)).max(0.0);
+            if let Some(cap) = scheme.monthly_cap {
+                reimbursed = reimbursed.min(cents(cap - used).max(0.0));
+            }
+            used += reimbursed;
+            lines.push(
+                ReimbursementLine {
+                    ride_id: ride.id(),
+                    departure,
+                    location_from: ride.location_from,
+                    location_to: ride.location_to,
+                    mode: mode.clone(),
+                    passengers: ride.passengers,
+                    price,
+                    vat: ride.vat_rate.map(|rate| VatBreakdown::from_gross(price, rate)),
+                    vat_rate: ride.vat_rate,
+                    employee_share: cents(price - reimbursed),
+                    reimbursed,
+                }
+            );
+        }
+        Ok(lines)
+    }
+
+    /// CSV file of the reimbursement lines of [user_id] in the layout of the scheme, see
+    /// [lines]
+    pub async fn to_csv(&self, user_id: u32, from: NaiveDate, to: NaiveDate, db: &impl ConnectionTrait) -> Result<Vec<u8>, CurdError> {
+        let Some(scheme) = &self.scheme else {
+            return Err(CurdError::NotFound);
+        };
+        let user = user::Entity::find_by_id(user_id)
+            .one(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?
+            .ok_or(CurdError::NotFound)?;
+        let employee = user.name.unwrap_or(user.jwt_subject);
+        let lines = self.lines(user_id, from, to, db).await?;
+
+        let internal = |error: csv::Error| CurdError::InternalError(error.to_string());
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(scheme.delimiter as u8)
+            .from_writer(Vec::new());
+        writer
+            .write_record(scheme.columns.iter().map(|column| &column.header))
+            .map_err(internal)?;
+        for line in &lines {
+            let record = scheme.columns.iter().map(|column| match column.field {
+                Field::Employee => employee.clone(),
+                Field::RideId => line.ride_id.to_string(),
+                Field::Month => line.departure.format("%Y-%m").to_string(),
+                Field::Date => line.departure.format(&scheme.date_format).to_string(),
+                Field::Time => line.departure.format("%H:%M").to_string(),
+                Field::From => line.location_from.clone(),
+                Field::To => line.location_to.clone(),
+                Field::Mode => line.mode.clone(),
+                Field::Passengers => line.passengers.to_string(),
+                Field::Price => scheme.amount(line.price),
+                Field::VatRate => line.vat_rate.map(|rate| rate.to_string()).unwrap_or_default(),
+                Field::Net => line.vat.map(|vat| scheme.amount(vat.net)).unwrap_or_default(),
+                Field::Vat => line.vat.map(|vat| scheme.amount(vat.vat)).unwrap_or_default(),
+                Field::EmployeeShare => scheme.amount(line.employee_share),
+                Field::Reimbursed => scheme.amount(line.reimbursed),
+                Field::Currency => scheme.currency.clone(),
+            });
+            writer.write_record(record).map_err(internal)?;
+        }
+        writer
+            .into_inner()
+            .map_err(
+                |error| {
+                    CurdError::InternalError(error.to_string())
+                }
+            )
+    }
+}
+
+/// [amount] rounded to cents
+pub(super) fn cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
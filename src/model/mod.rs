@@ -6,9 +6,52 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-mod error;
+pub mod archive;
+pub mod arrival;
+pub mod audit;
+pub mod audit_chain;
+pub mod caldav;
+pub mod commute;
+pub mod counted;
+pub mod credentials;
+pub mod deleted;
+pub mod duplicate;
+pub mod error;
+pub mod event;
+pub mod fare;
+pub mod feature;
+pub mod frequent_connection;
+pub mod geocode;
+pub mod idempotency;
+pub mod inbox;
+pub mod last_modified;
+pub mod locale;
+pub mod maintenance;
+pub mod malware_scan;
+pub mod overlap;
+pub mod pdf;
+pub mod mobility_budget;
+pub mod receipt;
+pub mod retention;
+pub mod retry;
+pub mod quick_add;
 pub mod ride;
+pub mod ride_group;
+pub mod ride_reference;
+pub mod ride_status;
+pub mod ride_summary;
+pub mod routing;
+pub mod server_setting;
 pub mod ride_tag_link;
+pub mod ride_track;
 pub mod tag;
 pub mod tag_option;
+pub mod tag_suggestion;
+pub mod template;
+pub mod telegram;
+pub mod travel_log;
+pub mod user;
+pub mod vat;
+pub mod webdav;
+pub mod webhook;
 
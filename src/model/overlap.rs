@@ -0,0 +1,98 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{
+    prelude::*,
+    Condition,
+};
+use entity::ride;
+use super::error::CurdError;
+use super::ride::Ride;
+
+/// Warning about a ride which was saved anyway
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Warning {
+    /// Kind of the warning, `overlap`
+    pub kind: String,
+    pub message: String,
+    /// Other rides concerned
+    pub ride_ids: Vec<u32>,
+}
+
+/// Whether the journeys from [a_start] to [a_end] and from [b_start] to [b_end] overlap.
+/// Arriving when the other departs is no overlap, departing at the same time is.
+fn overlaps(a_start: DateTimeUtc, a_end: DateTimeUtc, b_start: DateTimeUtc, b_end: DateTimeUtc) -> bool {
+    a_start == b_start || (a_start < b_end && b_start < a_end)
+}
+
+/// Rides of [user_id] overlapping the journey of [ride], without templates and the ride
+/// [exclude_id] itself
+pub async fn find_overlapping(user_id: u32, ride: &Ride, exclude_id: Option<u32>, db: &impl ConnectionTrait) -> Result<Vec<u32>, CurdError> {
+    let start = ride.journey_departure;
+    let end = ride.journey_arrival.unwrap_or(start).max(start);
+    let mut query = ride::Entity::find()
+        .filter(ride::Column::UserId.eq(user_id))
+        .filter(ride::Column::IsTemplate.eq(false))
+        .filter(ride::Column::DeletedAt.is_null())
+        .filter(ride::Column::JourneyDeparture.lte(end))
+        .filter(
+            Condition::any()
+                .add(ride::Column::JourneyArrival.gte(start))
+                .add(ride::Column::JourneyDeparture.gte(start))
+        );
+    if let Some(exclude_id) = exclude_id {
+        query = query.filter(ride::Column::Id.ne(exclude_id));
+    }
+    let models = query
+        .all(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    let ride_ids = models
+        .into_iter()
+        .filter(|model| {
+            let model_end = model.journey_arrival.unwrap_or(model.journey_departure).max(model.journey_departure);
+            overlaps(start, end, model.journey_departure, model_end)
+        })
+        .map(|model| model.id)
+        .collect();
+    Ok(ride_ids)
+}
+
+/// Warnings about [ride] of [user_id] overlapping other rides. With [strict], overlaps
+/// fail with a conflict instead. Templates are not checked.
+pub async fn check(user_id: u32, ride: &Ride, exclude_id: Option<u32>, strict: bool, db: &impl ConnectionTrait) -> Result<Vec<Warning>, CurdError> {
+    if ride.is_template {
+        return Ok(Vec::new());
+    }
+    let ride_ids = find_overlapping(user_id, ride, exclude_id, db).await?;
+    if ride_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let message = format!(
+        "Ride overlaps with rides {}, it may be a duplicate or have a wrong date",
+        ride_ids.iter().map(|ride_id| ride_id.to_string()).collect::<Vec<_>>().join(", "),
+    );
+    if strict {
+        Err(CurdError::Conflict(message))?;
+    }
+    Ok(
+        vec![
+            Warning {
+                kind: "overlap".to_string(),
+                message,
+                ride_ids,
+            }
+        ]
+    )
+}
@@ -0,0 +1,204 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Minimal writer of printable PDF documents with text in the standard fonts Helvetica
+//! and Helvetica-Bold and with lines. Fonts are not embedded, every PDF viewer has them.
+
+use std::io::Write;
+
+/// Width and height of an A4 page in landscape orientation in points
+pub const A4_LANDSCAPE: (f32, f32) = (842.0, 595.0);
+
+/// Widths of the characters ` ` to `~` of Helvetica in 1/1000 of the font size
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+/// Width of other characters, most are letters of about the width of digits
+const DEFAULT_WIDTH: u16 = 556;
+/// Helvetica-Bold is about this much wider than Helvetica
+const BOLD_FACTOR: f32 = 1.08;
+
+/// Standard font of a text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    /// Name of the font resource of the pages
+    fn resource(&self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+}
+
+/// [c] in WinAnsiEncoding, the encoding of the standard fonts. Characters which the
+/// encoding lacks are replaced by a question mark.
+fn win_ansi(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        '„' => 0x84,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        c if c.is_whitespace() => b' ',
+        _ => b'?',
+    }
+}
+
+/// Width of [text] in [font] of [size] in points
+pub fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    let width: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => HELVETICA_WIDTHS[c as usize - ' ' as usize],
+            _ => DEFAULT_WIDTH,
+        } as u32)
+        .sum();
+    let width = width as f32 * size / 1000.0;
+    match font {
+        Font::Regular => width,
+        Font::Bold => width * BOLD_FACTOR,
+    }
+}
+
+/// [text] shortened with an ellipsis to fit into [width]
+pub fn fit(text: &str, font: Font, size: f32, width: f32) -> String {
+    if text_width(text, font, size) <= width {
+        return text.to_string();
+    }
+    let mut fitted = text.to_string();
+    while !fitted.is_empty() && text_width(&format!("{}…", fitted), font, size) > width {
+        fitted.pop();
+    }
+    format!("{}…", fitted.trim_end())
+}
+
+/// Content of a page
+#[derive(Debug, Clone, Default)]
+pub struct Page {
+    content: Vec<u8>,
+}
+
+impl Page {
+    /// Write [text] with its baseline starting at [x], [y] from the bottom left corner
+    pub fn text(&mut self, x: f32, y: f32, font: Font, size: f32, text: &str) {
+        let _ = write!(self.content, "BT /{} {} Tf 1 0 0 1 {:.2} {:.2} Tm (", font.resource(), size, x, y);
+        for byte in text.chars().map(win_ansi) {
+            if matches!(byte, b'(' | b')' | b'\\') {
+                self.content.push(b'\\');
+            }
+            self.content.push(byte);
+        }
+        self.content.extend_from_slice(b") Tj ET\n");
+    }
+
+    /// Write [text] ending at [x]
+    pub fn text_right(&mut self, x: f32, y: f32, font: Font, size: f32, text: &str) {
+        self.text(x - text_width(text, font, size), y, font, size, text);
+    }
+
+    /// Draw a line of [width] from [from] to [to]
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32) {
+        let _ = writeln!(self.content, "{} w {:.2} {:.2} m {:.2} {:.2} l S", width, from.0, from.1, to.0, to.1);
+    }
+}
+
+/// PDF document of pages of the same size
+#[derive(Debug, Clone)]
+pub struct Document {
+    size: (f32, f32),
+    pages: Vec<Page>,
+}
+
+impl Document {
+    /// Empty document with pages of [size] in points, see [A4_LANDSCAPE]
+    pub fn new(size: (f32, f32)) -> Self {
+        Self {
+            size,
+            pages: Vec::new(),
+        }
+    }
+
+    /// Append an empty page
+    pub fn add_page(&mut self) -> &mut Page {
+        self.pages.push(Page::default());
+        self.pages.last_mut().expect("Page was added")
+    }
+
+    /// Pages in order
+    pub fn pages_mut(&mut self) -> &mut [Page] {
+        &mut self.pages
+    }
+
+    /// The document as PDF file. A document without pages gets an empty one.
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        if self.pages.is_empty() {
+            self.add_page();
+        }
+        // Objects 1 to 4 are the catalog, the page tree and the fonts, followed by each
+        // page and its content
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|index| 5 + 2 * index).collect();
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "),
+                page_ids.len(),
+            ).into_bytes(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+        ];
+        for (page, id) in self.pages.iter().zip(&page_ids) {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    self.size.0,
+                    self.size.1,
+                    id + 1,
+                ).into_bytes()
+            );
+            let mut stream = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+            stream.extend_from_slice(&page.content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            let _ = writeln!(pdf, "{} 0 obj", index + 1);
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        let _ = writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(pdf, "{:010} 00000 n ", offset);
+        }
+        let _ = writeln!(pdf, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF", objects.len() + 1, xref);
+        pdf
+    }
+}
@@ -0,0 +1,376 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::LazyLock;
+use chrono::{FixedOffset, NaiveTime, TimeDelta};
+use regex::Regex;
+use serde::Deserialize;
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::DateTimeUtc, ConnectionTrait};
+use entity::tag_descriptor::TagType;
+use super::audit::Actor;
+use super::error::CurdError;
+use super::ride::{self, Ride};
+use super::ride_tag_link::{self, Value};
+use super::tag::Tag;
+
+/// Time of departure as `8:05`
+static TIME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(\d{1,2}):(\d{2})\b").expect("Valid regex")
+});
+/// Price as `3.20€`, `EUR 3,20`, `3€` or `3.20`
+static AMOUNT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:(EUR|€|CHF|GBP|£|USD|\$)\s*(\d{1,5}(?:[.,]\d{1,2})?)\b|\b(\d{1,5}(?:[.,]\d{1,2})?)\s*(EUR\b|€|CHF\b|GBP\b|£|USD\b|\$)|\b(\d{1,5}[.,]\d{2})\b)")
+        .expect("Valid regex")
+});
+/// Separator of origin and destination
+static ROUTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\s*(?:→|->|\s\bto\b\s|\s\bnach\b\s)\s*").expect("Valid regex")
+});
+
+/// Line of free text describing a ride
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+pub struct QuickAddRequest {
+    /// E.g. `8:05 Hbf → Airport 3.20€ bus`. The time defaults to now, words after the
+    /// destination naming options of enum tags and `#option` link the option.
+    pub text: String,
+    /// Offset of the time zone of the time in minutes, e.g. `120` for CEST. Defaults to
+    /// the offset configured for the instance.
+    #[serde(default)]
+    pub utc_offset_minutes: Option<i32>,
+}
+
+/// Values found in a line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuickEntry {
+    pub time: Option<NaiveTime>,
+    pub location_from: String,
+    /// Destination, possibly followed by words naming tag options
+    pub location_to: String,
+    pub amount: Option<f64>,
+    /// Options given as `#option`
+    pub keywords: Vec<String>,
+}
+
+/// Rocket state for adding rides from a line of text
+#[derive(Debug, Clone)]
+pub struct QuickAdd {
+    /// Key of the tag the price is linked with
+    price_tag: String,
+    /// Offset of times without one
+    utc_offset_minutes: i32,
+}
+
+impl QuickAdd {
+    /// Quick entry linking prices with the tag [price_tag], taking times at
+    /// [utc_offset_minutes] unless requested otherwise
+    pub fn new(price_tag: String, utc_offset_minutes: i32) -> Self {
+        Self {
+            price_tag,
+            utc_offset_minutes,
+        }
+    }
+
+    /// Create the ride described by [request] for [user_id], with the price and the named
+    /// options linked
+    pub async fn add(&self, user_id: u32, request: &QuickAddRequest, now: DateTimeUtc, actor: &Actor, db: &impl ConnectionTrait) -> Result<Ride, CurdError> {
+        let mut entry = parse(&request.text)?;
+        let journey_departure = departure(entry.time, request.utc_offset_minutes.unwrap_or(self.utc_offset_minutes), now)?;
+
+        let tags = Tag::find_all(user_id, true, false, db).await?;
+        let mut links: Vec<(u32, Value)> = Vec::new();
+        if let Some(amount) = entry.amount {
+            let price_tag = tags
+                .iter()
+                .find(|tag| *tag.tag_key() == self.price_tag && matches!(TagType::try_from(tag.tag_type.clone()), Ok(TagType::Float)))
+                .ok_or_else(|| CurdError::Unprocessable(format!("There is no float tag {} for the price", self.price_tag)))?;
+            links.push((price_tag.id(), Value::Float(amount)));
+        }
+        for keyword in &entry.keywords {
+            let (tag_id, option_id) = find_option(&tags, keyword)
+                .ok_or_else(|| CurdError::Unprocessable(format!("There is no tag option {}", keyword)))?;
+            if !links.iter().any(|(linked, _)| *linked == tag_id) {
+                links.push((tag_id, Value::EnumOption(option_id)));
+            }
+        }
+        entry.location_to = take_options(&entry.location_to, &tags, &mut links);
+
+        let ride = ride::CreateUpdateBuilder::new(
+            journey_departure,
+            None,
+            entry.location_from,
+            entry.location_to,
+            None,
+            false,
+        )
+            .insert(user_id, actor, db)
+            .await?;
+        for (tag_id, value) in links {
+            ride_tag_link::CreateUpdateBuilder::new(0, value, None)
+                .insert(ride.id(), tag_id, actor, db)
+                .await?;
+        }
+        Ride::find_by_id(ride.id(), true, false, db).await
+    }
+}
+
+/// Values of [text]. Fails without origin and destination.
+pub fn parse(text: &str) -> Result<QuickEntry, CurdError> {
+    let mut entry = QuickEntry::default();
+    let mut rest = text.trim().to_string();
+    if let Some(captures) = TIME.captures(&rest) {
+        entry.time = NaiveTime::from_hms_opt(captures[1].parse().unwrap_or(99), captures[2].parse().unwrap_or(99), 0);
+        if entry.time.is_none() {
+            Err(CurdError::Unprocessable(format!("Invalid time {}", &captures[0])))?;
+        }
+        rest = rest.replacen(&captures[0], " ", 1);
+    }
+    if let Some(captures) = AMOUNT.captures(&rest) {
+        let amount = captures.get(2).or(captures.get(3)).or(captures.get(5)).map(|amount| amount.as_str()).unwrap_or_default();
+        entry.amount = amount.replace(',', ".").parse().ok();
+        rest = rest.replacen(&captures[0], " ", 1);
+    }
+    let words: Vec<&str> = rest
+        .split_whitespace()
+        .filter(|word| {
+            match word.strip_prefix('#') {
+                Some(keyword) if !keyword.is_empty() => {
+                    entry.keywords.push(keyword.to_string());
+                    false
+                },
+                _ => true,
+            }
+        })
+        .collect();
+    let rest = words.join(" ");
+    let parts: Vec<&str> = ROUTE.splitn(&rest, 2).collect();
+    let [from, to] = parts.as_slice() else {
+        return Err(CurdError::Unprocessable("Expected origin and destination as A → B".to_string()));
+    };
+    entry.location_from = from.trim().to_string();
+    entry.location_to = to.trim().to_string();
+    if entry.location_from.is_empty() || entry.location_to.is_empty() {
+        Err(CurdError::Unprocessable("Expected origin and destination as A → B".to_string()))?;
+    }
+    Ok(entry)
+}
+
+/// Departure at [time] today in the time zone with [utc_offset_minutes], or [now]
+fn departure(time: Option<NaiveTime>, utc_offset_minutes: i32, now: DateTimeUtc) -> Result<DateTimeUtc, CurdError> {
+    let Some(time) = time else {
+        return Ok(now);
+    };
+    let offset = utc_offset_minutes
+        .checked_mul(60)
+        .and_then(FixedOffset::east_opt)
+        .ok_or_else(|| CurdError::DeserializationError("Invalid utc_offset_minutes".to_string()))?;
+    let local = (now + TimeDelta::minutes(utc_offset_minutes.into())).date_naive().and_time(time);
+    local
+        .and_local_timezone(offset)
+        .single()
+        .map(|departure| departure.to_utc())
+        .ok_or_else(|| CurdError::Unprocessable("Invalid departure".to_string()))
+}
+
+/// [destination] without its trailing words naming options, which are added to [links]
+/// unless their tag is linked already. The first word is always kept as destination.
+fn take_options(destination: &str, tags: &[Tag], links: &mut Vec<(u32, Value)>) -> String {
+    let mut destination = destination.to_string();
+    loop {
+        let Some((rest, word)) = destination.rsplit_once(' ') else {
+            break;
+        };
+        let Some((tag_id, option_id)) = find_option(tags, word) else {
+            break;
+        };
+        if !links.iter().any(|(linked, _)| *linked == tag_id) {
+            links.push((tag_id, Value::EnumOption(option_id)));
+        }
+        destination = rest.trim_end().to_string();
+    }
+    destination
+}
+
+/// Enum tag and option named [word], compared case-insensitively with the value and name
+/// of the option
+fn find_option(tags: &[Tag], word: &str) -> Option<(u32, u32)> {
+    let word = word.to_lowercase();
+    tags.iter()
+        .filter(|tag| matches!(TagType::try_from(tag.tag_type.clone()), Ok(TagType::Enum)))
+        .find_map(|tag| {
+            tag.options()
+                .iter()
+                .flatten()
+                .find(|option| {
+                    option.value.to_lowercase() == word
+                        || option.name.as_ref().is_some_and(|name| name.to_lowercase() == word)
+                })
+                .map(|option| (tag.id(), option.id()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveTime};
+    use sea_orm::prelude::Uuid;
+    use entity::{tag_descriptor, tag_enum_option};
+    use crate::model::error::CurdError;
+    use crate::model::ride_tag_link::Value;
+    use crate::model::tag::Tag;
+    use super::{departure, find_option, parse, take_options, QuickEntry};
+
+    /// Tag [id] of [tag_type] with options of the given IDs, values and names
+    fn tag(id: u32, tag_type: tag_descriptor::TagType, options: &[(u32, &str, Option<&str>)]) -> Tag {
+        let model = tag_descriptor::Model {
+            id,
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            deleted_at: None,
+            user_id: 1,
+            tag_type,
+            tag_key: format!("tag{}", id),
+            tag_name: None,
+            uuid: Uuid::nil(),
+            unit: None,
+            remarks: None,
+            order: 0,
+            aggregation: None,
+        };
+        let options = options
+            .iter()
+            .map(|(option_id, value, name)| tag_enum_option::Model {
+                id: *option_id,
+                created_at: DateTime::UNIX_EPOCH,
+                updated_at: DateTime::UNIX_EPOCH,
+                deleted_at: None,
+                tag_descriptor_id: id,
+                order: 0,
+                value: value.to_string(),
+                uuid: Uuid::nil(),
+                name: name.map(str::to_string),
+            })
+            .collect();
+        Tag::from_models(model, options)
+    }
+
+    fn tags() -> Vec<Tag> {
+        vec![
+            tag(1, tag_descriptor::TagType::String, &[]),
+            tag(2, tag_descriptor::TagType::Enum, &[(21, "bus", Some("Bus")), (22, "train", Some("Zug"))]),
+            tag(3, tag_descriptor::TagType::Enum, &[(31, "work", None)]),
+        ]
+    }
+
+    #[test]
+    fn test_parse() {
+        let Ok(entry) = parse("8:05 Hbf → Airport 3.20€ bus #work") else {
+            panic!("Line is parsed");
+        };
+        assert_eq!(
+            entry,
+            QuickEntry {
+                time: NaiveTime::from_hms_opt(8, 5, 0),
+                location_from: "Hbf".to_string(),
+                location_to: "Airport bus".to_string(),
+                amount: Some(3.2),
+                keywords: vec!["work".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_separators() {
+        for text in ["Hbf → Airport", "Hbf -> Airport", "Hbf to Airport", "Hbf nach Airport", "Hbf->Airport"] {
+            let Ok(entry) = parse(text) else {
+                panic!("{} is parsed", text);
+            };
+            assert_eq!((entry.location_from.as_str(), entry.location_to.as_str()), ("Hbf", "Airport"), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_amounts() {
+        for (text, amount) in [
+            ("Hbf → Airport 3,20€", 3.2),
+            ("Hbf → Airport EUR 3,20", 3.2),
+            ("Hbf → Airport 2,50", 2.5),
+            ("Hbf → Airport $ 4", 4.0),
+            ("Hbf → Airport 7 CHF", 7.0),
+        ] {
+            let Ok(entry) = parse(text) else {
+                panic!("{} is parsed", text);
+            };
+            assert_eq!(entry.amount, Some(amount), "{}", text);
+            assert_eq!(entry.location_to, "Airport", "{}", text);
+        }
+        // Numbers without currency or cents are part of the locations
+        let Ok(entry) = parse("Terminal 1 → Terminal 2") else {
+            panic!("Line is parsed");
+        };
+        assert_eq!(entry.amount, None);
+        assert_eq!(entry.location_to, "Terminal 2");
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        for text in ["Hbf Airport 3.20€", "→ Airport", "Hbf →", "", "8:05 #bus"] {
+            assert!(matches!(parse(text), Err(CurdError::Unprocessable(_))), "{}", text);
+        }
+        for text in ["25:99 Hbf → Airport", "8:60 Hbf → Airport"] {
+            match parse(text) {
+                Err(CurdError::Unprocessable(message)) => assert!(message.starts_with("Invalid time"), "{}", text),
+                _ => panic!("{} is rejected", text),
+            }
+        }
+    }
+
+    #[test]
+    fn test_departure() {
+        let now = "2026-10-16T20:00:00Z".parse().expect("Valid time");
+        let time = NaiveTime::from_hms_opt(8, 5, 0);
+        assert!(matches!(departure(None, 120, now), Ok(at) if at == now));
+        assert!(matches!(departure(time, 0, now), Ok(at) if at.to_rfc3339() == "2026-10-16T08:05:00+00:00"));
+        assert!(matches!(departure(time, 120, now), Ok(at) if at.to_rfc3339() == "2026-10-16T06:05:00+00:00"));
+        // The day is the one of the time zone, which is already the next one
+        assert!(matches!(departure(time, 300, now), Ok(at) if at.to_rfc3339() == "2026-10-17T03:05:00+00:00"));
+        assert!(matches!(departure(time, -600, now), Ok(at) if at.to_rfc3339() == "2026-10-16T18:05:00+00:00"));
+        assert!(matches!(departure(time, i32::MAX, now), Err(CurdError::DeserializationError(_))));
+        assert!(matches!(departure(time, 24 * 60, now), Err(CurdError::DeserializationError(_))));
+    }
+
+    #[test]
+    fn test_find_option() {
+        let tags = tags();
+        assert_eq!(find_option(&tags, "bus"), Some((2, 21)));
+        assert_eq!(find_option(&tags, "BUS"), Some((2, 21)));
+        assert_eq!(find_option(&tags, "zug"), Some((2, 22)));
+        assert_eq!(find_option(&tags, "work"), Some((3, 31)));
+        assert_eq!(find_option(&tags, "tag1"), None);
+        assert_eq!(find_option(&tags, "tram"), None);
+        assert_eq!(find_option(&[], "bus"), None);
+    }
+
+    #[test]
+    fn test_take_options() {
+        let tags = tags();
+        let mut links = Vec::new();
+        assert_eq!(take_options("Airport Terminal 1 Zug work", &tags, &mut links), "Airport Terminal 1");
+        assert!(matches!(links.as_slice(), [(3, Value::EnumOption(31)), (2, Value::EnumOption(22))]));
+
+        // Only the first option of a tag is linked
+        let mut links = vec![(2, Value::EnumOption(21))];
+        assert_eq!(take_options("Airport train", &tags, &mut links), "Airport");
+        assert!(matches!(links.as_slice(), [(2, Value::EnumOption(21))]));
+
+        // An option word as the whole destination stays the destination
+        let mut links = Vec::new();
+        assert_eq!(take_options("Bus", &tags, &mut links), "Bus");
+        assert_eq!(take_options("bus train", &tags, &mut links), "bus");
+        assert!(matches!(links.as_slice(), [(2, Value::EnumOption(22))]));
+    }
+}
@@ -0,0 +1,325 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::process::Stdio;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use chrono::{NaiveDate, NaiveTime};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::ConnectionTrait;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use entity::tag_descriptor::TagType;
+use super::error::CurdError;
+use super::geocode::USER_AGENT;
+use super::ride_tag_link::Value;
+use super::tag::Tag;
+
+/// Time after which OCR is aborted
+const OCR_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Amount with currency before or after it, e.g. `EUR 3,80` or `3.80 €`
+static AMOUNT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:(EUR|€|CHF|GBP|£|USD|\$)\s*(\d{1,5}[.,]\d{2})\b|\b(\d{1,5}[.,]\d{2})\s*(EUR|€|CHF|GBP|£|USD|\$))")
+        .expect("Valid regex")
+});
+/// Line naming the total of a receipt
+static TOTAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(total|summe|gesamt|betrag|preis|price|amount)").expect("Valid regex")
+});
+/// Date as `2025-03-01` or `01.03.2025`
+static DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(?:(\d{4})-(\d{2})-(\d{2})|(\d{1,2})\.(\d{1,2})\.(\d{4}|\d{2}))\b").expect("Valid regex")
+});
+/// Time as `08:15`
+static TIME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(\d{1,2}):(\d{2})\b").expect("Valid regex")
+});
+/// Route as `from A to B`, `von A nach B` or `A -> B`
+static ROUTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:\b(?:from|von|ab)\s+(.+?)\s+(?:to|nach|bis)\s+(.+?)|^\s*(.+?)\s*(?:->|→)\s*(.+?))\s*$")
+        .expect("Valid regex")
+});
+
+/// OCR engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    /// `tesseract` command on the server
+    Tesseract,
+    /// External service receiving the image by POST and responding with the text
+    Http,
+}
+
+/// Values read from a receipt
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct ReceiptValues {
+    /// Total amount
+    pub amount: Option<f64>,
+    pub currency: Option<String>,
+    pub date: Option<NaiveDate>,
+    pub time: Option<NaiveTime>,
+    pub location_from: Option<String>,
+    pub location_to: Option<String>,
+}
+
+/// Value suggested for a tag of the user
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SuggestedTagValue {
+    pub tag_id: u32,
+    pub tag_key: String,
+    pub value: Value,
+}
+
+/// Result of scanning a receipt, for the user to confirm
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ReceiptScan {
+    /// Recognized text
+    pub text: String,
+    pub values: ReceiptValues,
+    /// Values for the tags of the user, currently the price tag
+    pub tags: Vec<SuggestedTagValue>,
+}
+
+/// Configured engine
+enum Backend {
+    Tesseract {
+        command: String,
+        language: String,
+    },
+    Http {
+        url: String,
+        client: reqwest::Client,
+    },
+}
+
+/// Rocket state for reading receipt images
+#[derive(Clone)]
+pub struct ReceiptReader {
+    backend: Option<Arc<Backend>>,
+    /// Key of the tag the amount is suggested for
+    price_tag: String,
+}
+
+impl ReceiptReader {
+    /// Reader using [engine], which runs [command] with [language] or posts to [url].
+    /// OCR is disabled without [engine].
+    pub fn new(
+        engine: Option<Engine>,
+        command: String,
+        language: String,
+        url: Option<String>,
+        price_tag: String,
+    ) -> Result<Self, reqwest::Error> {
+        let backend = match (engine, url) {
+            (Some(Engine::Tesseract), _) => Some(Backend::Tesseract { command, language }),
+            (Some(Engine::Http), Some(url)) => {
+                let client = reqwest::Client::builder()
+                    .user_agent(USER_AGENT)
+                    .timeout(OCR_TIMEOUT)
+                    .build()?;
+                Some(Backend::Http { url, client })
+            },
+            _ => None,
+        };
+        Ok(
+            Self {
+                backend: backend.map(Arc::new),
+                price_tag,
+            }
+        )
+    }
+
+    /// Whether an OCR engine is configured
+    pub fn is_enabled(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    /// Recognize the text of [image] of [content_type] and suggest values for the tags
+    /// of [user_id]
+    pub async fn scan(&self, user_id: u32, image: Vec<u8>, content_type: &str, db: &impl ConnectionTrait) -> Result<ReceiptScan, CurdError> {
+        let backend = self.backend.as_ref().ok_or(CurdError::NotFound)?;
+        let text = backend.recognize(image, content_type).await?;
+        let values = extract(&text);
+
+        let mut tags = Vec::new();
+        if let Some(amount) = values.amount {
+            let price_tag = Tag::find_all(user_id, false, false, db)
+                .await?
+                .into_iter()
+                .find(|tag| *tag.tag_key() == self.price_tag && matches!(TagType::try_from(tag.tag_type.clone()), Ok(TagType::Float)));
+            if let Some(price_tag) = price_tag {
+                tags.push(
+                    SuggestedTagValue {
+                        tag_id: price_tag.id(),
+                        tag_key: price_tag.tag_key().clone(),
+                        value: Value::Float(amount),
+                    }
+                );
+            }
+        }
+        Ok(
+            ReceiptScan {
+                text,
+                values,
+                tags,
+            }
+        )
+    }
+}
+
+impl Backend {
+    /// Text of [image]
+    async fn recognize(&self, image: Vec<u8>, content_type: &str) -> Result<String, CurdError> {
+        match self {
+            Backend::Tesseract { command, language } => {
+                tokio::time::timeout(OCR_TIMEOUT, run_tesseract(command, language, image))
+                    .await
+                    .unwrap_or_else(|_| Err("Timed out".to_string()))
+                    .map_err(
+                        |error| {
+                            error!("OCR with {} failed: {}", command, error);
+                            CurdError::InternalError("OCR failed".to_string())
+                        }
+                    )
+            },
+            Backend::Http { url, client } => {
+                let upstream_error = |error: &dyn std::fmt::Display| {
+                    error!("OCR request to {} failed: {}", url, error);
+                    CurdError::Upstream("OCR service failed".to_string())
+                };
+                let response = client
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, content_type)
+                    .body(image)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|error| upstream_error(&error))?;
+                let is_json = response.headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .is_some_and(|value| value.starts_with("application/json"));
+                let body = response.text()
+                    .await
+                    .map_err(|error| upstream_error(&error))?;
+                if is_json {
+                    #[derive(Deserialize)]
+                    struct OcrResponse {
+                        text: String,
+                    }
+                    let response: OcrResponse = serde_json::from_str(&body)
+                        .map_err(|error| upstream_error(&error))?;
+                    Ok(response.text)
+                } else {
+                    Ok(body)
+                }
+            },
+        }
+    }
+}
+
+/// Run [command] on [image], which is passed on stdin
+async fn run_tesseract(command: &str, language: &str, image: Vec<u8>) -> Result<String, String> {
+    let mut child = Command::new(command)
+        .args(["stdin", "stdout", "-l", language])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|error| error.to_string())?;
+    let mut stdin = child.stdin.take().ok_or("No stdin")?;
+    let write = async move {
+        let result = stdin.write_all(&image).await;
+        drop(stdin);
+        result
+    };
+    let (written, output) = tokio::join!(write, child.wait_with_output());
+    let output = output.map_err(|error| error.to_string())?;
+    if !output.status.success() {
+        Err(format!("{}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()))?;
+    }
+    written.map_err(|error| error.to_string())?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Values found in the recognized [text]. The amount on a line naming the total is
+/// preferred over the largest amount.
+pub fn extract(text: &str) -> ReceiptValues {
+    let mut values = ReceiptValues::default();
+    let mut total_found = false;
+    for line in text.lines() {
+        let is_total = TOTAL.is_match(line);
+        for captures in AMOUNT.captures_iter(line) {
+            let (Some(currency), Some(amount)) = (captures.get(1).or(captures.get(4)), captures.get(2).or(captures.get(3))) else {
+                continue;
+            };
+            let Ok(amount) = amount.as_str().replace(',', ".").parse::<f64>() else {
+                continue;
+            };
+            let better = match values.amount {
+                None => true,
+                Some(_) if is_total && !total_found => true,
+                Some(previous) => is_total == total_found && amount > previous,
+            };
+            if better {
+                values.amount = Some(amount);
+                values.currency = Some(currency_code(currency.as_str()));
+                total_found |= is_total;
+            }
+        }
+        if values.date.is_none() {
+            values.date = DATE.captures(line).and_then(|captures| parse_date(&captures));
+        }
+        if values.time.is_none() {
+            values.time = TIME.captures(line).and_then(|captures| {
+                NaiveTime::from_hms_opt(captures[1].parse().ok()?, captures[2].parse().ok()?, 0)
+            });
+        }
+        if values.location_from.is_none() {
+            if let Some(captures) = ROUTE.captures(line) {
+                let from = captures.get(1).or(captures.get(3));
+                let to = captures.get(2).or(captures.get(4));
+                if let (Some(from), Some(to)) = (from, to) {
+                    values.location_from = Some(from.as_str().trim().to_string());
+                    values.location_to = Some(to.as_str().trim().to_string());
+                }
+            }
+        }
+    }
+    values
+}
+
+/// Date of ISO or German format captured by [DATE]
+fn parse_date(captures: &regex::Captures) -> Option<NaiveDate> {
+    if let (Some(year), Some(month), Some(day)) = (captures.get(1), captures.get(2), captures.get(3)) {
+        return NaiveDate::from_ymd_opt(year.as_str().parse().ok()?, month.as_str().parse().ok()?, day.as_str().parse().ok()?);
+    }
+    let day = captures.get(4)?.as_str().parse().ok()?;
+    let month = captures.get(5)?.as_str().parse().ok()?;
+    let year = captures.get(6)?.as_str();
+    let year: i32 = if year.len() == 2 {
+        2000 + year.parse::<i32>().ok()?
+    } else {
+        year.parse().ok()?
+    };
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// ISO 4217 code of [symbol]
+fn currency_code(symbol: &str) -> String {
+    match symbol {
+        "€" => "EUR".to_string(),
+        "£" => "GBP".to_string(),
+        "$" => "USD".to_string(),
+        code => code.to_uppercase(),
+    }
+}
@@ -0,0 +1,164 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::TimeDelta;
+use serde::Serialize;
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::*, Condition};
+use entity::{ride, user};
+use super::audit::{record, Actor};
+use super::error::CurdError;
+use super::event::{Action, Resource};
+
+/// Rides deleted by a single statement at most
+const DELETE_BATCH_SIZE: usize = 500;
+
+/// Retention of rides, configured for the instance and optionally shortened per user
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    /// Retention of the instance in days
+    days: Option<u32>,
+}
+
+/// Rides of a user which are due for deletion
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct UserRetention {
+    pub user_id: u32,
+    /// Retention applying to the user in days
+    pub retention_days: u32,
+    /// Rides which departed before are deleted
+    pub cutoff: DateTimeUtc,
+    /// Number of rides due for deletion, including soft-deleted ones
+    pub rides: u64,
+}
+
+/// Result of a dry run of the retention policy
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct RetentionReport {
+    /// Retention of the instance in days
+    pub retention_days: Option<u32>,
+    /// Users a retention applies to
+    pub users: Vec<UserRetention>,
+}
+
+impl RetentionPolicy {
+    pub fn new(days: Option<u32>) -> Self {
+        Self {
+            days,
+        }
+    }
+
+    /// Retention applying to [user], the shorter one of instance and user
+    fn days_for(&self, user: &user::Model) -> Option<u32> {
+        match (self.days, user.retention_days) {
+            (Some(days), Some(user_days)) => Some(days.min(user_days)),
+            (days, user_days) => days.or(user_days),
+        }
+    }
+
+    /// Users a retention applies to, as user ID, retention in days and cutoff relative
+    /// to [now]
+    async fn cutoffs(&self, now: DateTimeUtc, db: &impl ConnectionTrait) -> Result<Vec<(u32, u32, DateTimeUtc)>, CurdError> {
+        let mut query = user::Entity::find();
+        if self.days.is_none() {
+            query = query.filter(user::Column::RetentionDays.is_not_null());
+        }
+        let users = query
+            .all(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(
+            users
+                .iter()
+                .filter_map(|user| {
+                    let days = self.days_for(user)?;
+                    Some((user.id, days, now - TimeDelta::days(days.into())))
+                })
+                .collect()
+        )
+    }
+
+    /// Rides of [user_id] which departed before [cutoff]. Templates are kept.
+    fn expired(user_id: u32, cutoff: DateTimeUtc) -> Condition {
+        Condition::all()
+            .add(ride::Column::UserId.eq(user_id))
+            .add(ride::Column::JourneyDeparture.lt(cutoff))
+            .add(ride::Column::IsTemplate.eq(false))
+    }
+
+    /// Count the rides which [purge](Self::purge) would delete at [now]
+    pub async fn report(&self, now: DateTimeUtc, db: &impl ConnectionTrait) -> Result<RetentionReport, CurdError> {
+        let mut users = Vec::new();
+        for (user_id, retention_days, cutoff) in self.cutoffs(now, db).await? {
+            let rides = ride::Entity::find()
+                .filter(Self::expired(user_id, cutoff))
+                .count(db)
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            users.push(UserRetention {
+                user_id,
+                retention_days,
+                cutoff,
+                rides,
+            });
+        }
+        Ok(
+            RetentionReport {
+                retention_days: self.days,
+                users,
+            }
+        )
+    }
+
+    /// Delete the rides which are expired at [now] from the database, including
+    /// soft-deleted ones. Their ride tags are deleted by the foreign key. Returns the
+    /// IDs of the deleted rides per user. The deletions are recorded in the audit log
+    /// without user.
+    pub async fn purge(&self, now: DateTimeUtc, db: &impl ConnectionTrait) -> Result<Vec<(u32, Vec<u32>)>, CurdError> {
+        let mut purged = Vec::new();
+        for (user_id, _, cutoff) in self.cutoffs(now, db).await? {
+            let rides = ride::Entity::find()
+                .filter(Self::expired(user_id, cutoff))
+                .all(db)
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            if rides.is_empty() {
+                continue;
+            }
+            let ids: Vec<u32> = rides.iter().map(|ride| ride.id).collect();
+            for chunk in ids.chunks(DELETE_BATCH_SIZE) {
+                ride::Entity::delete_many()
+                    .filter(ride::Column::Id.is_in(chunk.iter().copied()))
+                    .exec(db)
+                    .await
+                    .map_err(
+                        |error| {
+                            CurdError::DbErr(error)
+                        }
+                    )?;
+            }
+            for ride in &rides {
+                record(&Actor::default(), Resource::Ride, ride.id, Action::Deleted, Some(ride), None, db).await?;
+            }
+            purged.push((user_id, ids));
+        }
+        Ok(purged)
+    }
+}
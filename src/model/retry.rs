@@ -0,0 +1,83 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use sea_orm::{DbErr, RuntimeErr, sqlx};
+
+/// Maximum number of attempts of a statement, including the first one
+pub const MAX_ATTEMPTS: u32 = 4;
+/// Delay before the first retry, doubled for every further retry
+const INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Number of retried statements since startup
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+/// Number of statements which still failed after [MAX_ATTEMPTS]
+static EXHAUSTED: AtomicU64 = AtomicU64::new(0);
+
+/// Postgres error codes of transient errors: serialization failure, deadlock, server
+/// shutdown (e.g. on failover) and connection failures
+const POSTGRES_TRANSIENT_CODES: [&str; 6] = ["40001", "40P01", "57P01", "08000", "08003", "08006"];
+/// SQLite primary result codes of transient errors: SQLITE_BUSY and SQLITE_LOCKED
+const SQLITE_TRANSIENT_CODES: [i32; 2] = [5, 6];
+
+/// Whether [error] is transient, so that running the statement again may succeed
+pub fn is_transient(error: &DbErr) -> bool {
+    match error {
+        DbErr::ConnectionAcquire(_) => true,
+        DbErr::Conn(_) => true,
+        DbErr::Exec(RuntimeErr::SqlxError(error)) | DbErr::Query(RuntimeErr::SqlxError(error)) => {
+            match error {
+                sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed => true,
+                sqlx::Error::Database(error) => match error.code() {
+                    // SQLite reports numeric extended result codes, whose lowest byte is
+                    // the primary result code
+                    Some(code) => match code.parse::<i32>() {
+                        Ok(code) => SQLITE_TRANSIENT_CODES.contains(&(code & 0xff)),
+                        Err(_) => POSTGRES_TRANSIENT_CODES.contains(&code.as_ref()),
+                    },
+                    None => false,
+                },
+                _ => false,
+            }
+        },
+        _ => false,
+    }
+}
+
+/// Run [statement] and run it again with exponential backoff as long as it fails with a
+/// transient error, see [is_transient], up to [MAX_ATTEMPTS] times.
+///
+/// Only use this for statements which can be repeated safely, i.e. reads. If a write
+/// fails on a reset connection, it is unknown whether it was applied.
+pub async fn retry<T, F, Fut>(mut statement: F) -> Result<T, DbErr>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, DbErr>>,
+{
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match statement().await {
+            Err(error) if is_transient(&error) => {
+                if attempt >= MAX_ATTEMPTS {
+                    let exhausted = EXHAUSTED.fetch_add(1, Ordering::Relaxed) + 1;
+                    error!("Database statement failed after {} attempts ({} in total): {}", attempt, exhausted, error);
+                    return Err(error);
+                }
+                let retries = RETRIES.fetch_add(1, Ordering::Relaxed) + 1;
+                warn!("Retrying database statement in {:?} ({} retries in total): {}", backoff, retries, error);
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            },
+            result => return result,
+        }
+    }
+}
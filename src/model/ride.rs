@@ -8,40 +8,142 @@
 
 use serde::{Deserialize, Serialize};
 use rocket_okapi::okapi::schemars;
-use sea_orm::{prelude::*, Set, NotSet, QuerySelect};
+use sea_orm::{prelude::*, Condition, LoaderTrait, Set, NotSet, QueryOrder, QuerySelect, Unchanged};
 use entity::ride;
+use entity::ride::RideStatus;
 use entity::ride_tag;
+use entity::timestamps;
+use super::counted::find_page_with_count;
+use super::audit::{record, Actor};
+use super::deleted::not_deleted;
 use super::error::CurdError;
+use super::event::{Action, Resource};
+use super::retry::retry;
+use super::last_modified::latest_change;
+use super::overlap::Warning;
+use super::ride_status;
 use super::ride_tag_link::RideTagLink;
 
 /// JSON structure
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[schemars(example = "Ride::example")]
+#[serde(deny_unknown_fields)]
 pub struct Ride {
     #[serde(skip_deserializing)]
     id: u32,
     pub journey_departure: DateTimeUtc,
     pub journey_arrival: Option<DateTimeUtc>,
+    #[schemars(length(max = 200))]
     pub location_from: String,
+    #[schemars(length(max = 200))]
     pub location_to: String,
+    #[schemars(length(max = 2000))]
     pub remarks: Option<String>,
     pub is_template: bool,
+    /// Number of travellers the price covers, e.g. with a family or group ticket
+    #[serde(default = "Ride::default_passengers")]
+    pub passengers: u32,
+    /// Rate of the VAT included in the price in percent, e.g. `7`, unknown if not set
+    #[serde(default)]
+    pub vat_rate: Option<f64>,
+    /// Stage in the lifecycle, changed with `POST /ride/<id>/status`
     #[serde(skip_deserializing)]
-    tags: Vec<RideTagLink>,
+    pub status: RideStatus,
+    /// Whether `journey_arrival` was estimated from the average duration of the route
+    #[serde(skip_deserializing)]
+    pub arrival_estimated: bool,
+    #[serde(skip_deserializing)]
+    created_at: DateTimeUtc,
+    #[serde(skip_deserializing)]
+    updated_at: DateTimeUtc,
+    /// Time of deletion, only set for deleted resources
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<DateTimeUtc>,
+    /// Linked tags, only if embedded
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<RideTagLink>>,
+    /// Warnings about the saved ride, only in responses to creation
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<Warning>,
+}
+
+/// Filter of ride lists. Unset fields match all rides.
+#[derive(Debug, Clone, Default)]
+pub struct RideFilter {
+    /// Statuses to match, all if empty
+    pub statuses: Vec<RideStatus>,
+}
+
+impl RideFilter {
+    /// Condition matching the rides of the filter
+    pub(super) fn condition(&self) -> Condition {
+        let mut condition = Condition::all();
+        if !self.statuses.is_empty() {
+            condition = condition.add(ride::Column::Status.is_in(self.statuses.iter().copied()));
+        }
+        condition
+    }
 }
 
 impl Ride {
+    /// Example for the API documentation
+    fn example() -> Self {
+        Self {
+            id: 1,
+            journey_departure: DateTimeUtc::from_timestamp(1740816000, 0).unwrap_or_default(),
+            journey_arrival: DateTimeUtc::from_timestamp(1740818520, 0),
+            location_from: "Central Station".to_string(),
+            location_to: "Airport".to_string(),
+            remarks: None,
+            is_template: false,
+            passengers: 1,
+            vat_rate: Some(7.0),
+            status: RideStatus::Completed,
+            arrival_estimated: false,
+            created_at: DateTimeUtc::from_timestamp(1740819000, 0).unwrap_or_default(),
+            updated_at: DateTimeUtc::from_timestamp(1740819000, 0).unwrap_or_default(),
+            deleted_at: None,
+            tags: Some(vec![RideTagLink::example()]),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn default_passengers() -> u32 {
+        1
+    }
+
     /// Getter for [id]
     pub fn id(&self) -> u32 {
         self.id
     }
 
-    fn from_models(ride: ride::Model, tags: Vec<ride_tag::Model>) -> Result<Self, CurdError> {
-        let tags = {
-            let mut option_arr = Vec::with_capacity(tags.len());
-            for tag in tags {
-                option_arr.push(RideTagLink::try_from(tag)?);
-            }
-            option_arr
+    /// Getter for [tags]
+    pub fn tags(&self) -> &Option<Vec<RideTagLink>> {
+        &self.tags
+    }
+
+    /// The ride without embedded tags
+    pub fn without_tags(mut self) -> Self {
+        self.tags = None;
+        self
+    }
+
+    /// Add [warnings] to the response
+    pub fn with_warnings(mut self, warnings: Vec<Warning>) -> Self {
+        self.warnings.extend(warnings);
+        self
+    }
+
+    fn from_models(ride: ride::Model, tags: Option<Vec<ride_tag::Model>>) -> Result<Self, CurdError> {
+        let tags = match tags {
+            Some(tags) => {
+                let mut option_arr = Vec::with_capacity(tags.len());
+                for tag in tags {
+                    option_arr.push(RideTagLink::try_from(tag)?);
+                }
+                Some(option_arr)
+            },
+            None => None,
         };
 
         let ride = Self {
@@ -52,38 +154,42 @@ impl Ride {
             location_to: ride.location_to,
             remarks: ride.remarks,
             is_template: ride.is_template,
+            passengers: ride.passengers,
+            vat_rate: ride.vat_rate,
+            status: ride.status,
+            arrival_estimated: ride.arrival_estimated,
+            created_at: ride.created_at,
+            updated_at: ride.updated_at,
+            deleted_at: ride.deleted_at,
             tags,
+            warnings: Vec::new(),
         };
         Ok(ride)
     }
 
-    /// Fetch all instances belonging to [user_id]
-    pub async fn find_all(user_id: u32, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
-        let models = ride::Entity::find()
-            .find_with_related(ride_tag::Entity)
+    /// Fetch all instances belonging to [user_id]. The linked tags are only fetched and
+    /// embedded if [with_tags] is set.
+    pub async fn find_all(user_id: u32, with_tags: bool, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        Self::find_filtered(user_id, &RideFilter::default(), with_tags, include_deleted, db).await
+    }
+
+    /// Fetch all instances belonging to [user_id] matching [filter]
+    pub async fn find_filtered(user_id: u32, filter: &RideFilter, with_tags: bool, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        let query = ride::Entity::find()
             .filter(ride::Column::UserId.eq(user_id))
-            .filter(ride::Column::DeletedAt.is_null())
-            .all(db)
-            .await
-            .map_err(
-                |error| {
-                    CurdError::DbErr(error)
-                }
-            )?;
-        let mut result = Vec::with_capacity(models.len());
-        for (tag, options) in models {
-            result.push(Self::from_models(tag, options)?);
-        }
-        Ok(result)
+            .filter(not_deleted(ride::Column::DeletedAt, include_deleted))
+            .filter(filter.condition());
+        Self::fetch(query, with_tags, db).await
     }
     
-    /// Count all instances belonging to [user_id].
-    pub async fn count_all(user_id: u32, db: &impl ConnectionTrait) -> Result<u64, CurdError> {
+    /// Count all instances belonging to [user_id] matching [filter].
+    pub async fn count_all(user_id: u32, filter: &RideFilter, include_deleted: bool, db: &impl ConnectionTrait) -> Result<u64, CurdError> {
+        let statement = ride::Entity::find()
+            .filter(ride::Column::UserId.eq(user_id))
+            .filter(not_deleted(ride::Column::DeletedAt, include_deleted))
+            .filter(filter.condition());
         Ok(
-            ride::Entity::find()
-                .filter(ride::Column::UserId.eq(user_id))
-                .filter(ride::Column::DeletedAt.is_null())
-                .count(db)
+            retry(|| statement.clone().count(db))
                 .await
                 .map_err(
                     |error| {
@@ -93,46 +199,104 @@ impl Ride {
         )
     }
 
-    /// Fetch all instances belonging to [user_id]. Use pagination
-    pub async fn find_all_paginated(user_id: u32, db: &impl ConnectionTrait, page: u64, size: u64) -> Result<Vec<Self>, CurdError> {
-        let models = ride::Entity::find()
-            .find_with_related(ride_tag::Entity)
+    /// Fetch page [page] of [size] instances belonging to [user_id] matching [filter],
+    /// together with the number of all matching instances. The linked tags are only
+    /// fetched and embedded if [with_tags] is set.
+    pub async fn find_all_paginated(user_id: u32, filter: &RideFilter, with_tags: bool, include_deleted: bool, db: &impl ConnectionTrait, page: u64, size: u64) -> Result<(Vec<Self>, u64), CurdError> {
+        let query = ride::Entity::find()
             .filter(ride::Column::UserId.eq(user_id))
-            .filter(ride::Column::DeletedAt.is_null())
-            .offset(page * size)
-            .limit(size)
-            .all(db)
-            .await
-            .map_err(
-                |error| {
-                    CurdError::DbErr(error)
-                }
-            )?;
-        let mut result = Vec::with_capacity(models.len());
-        for (tag, options) in models {
-            result.push(Self::from_models(tag, options)?);
+            .filter(not_deleted(ride::Column::DeletedAt, include_deleted))
+            .filter(filter.condition())
+            .order_by_asc(ride::Column::Id);
+        let (rides, count) = find_page_with_count(query, page, size, db).await?;
+        // Tags are loaded separately, as joining them would break counting and limiting
+        // the rides
+        let tags = if with_tags {
+            retry(|| rides.load_many(ride_tag::Entity, db))
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?
+                .into_iter()
+                .map(Some)
+                .collect()
+        } else {
+            vec![None; rides.len()]
+        };
+        let mut result = Vec::with_capacity(rides.len());
+        for (ride, tags) in rides.into_iter().zip(tags) {
+            result.push(Self::from_models(ride, tags)?);
         }
-        Ok(result)
+        Ok((result, count))
+    }
+
+    /// Fetch up to [size] instances belonging to [user_id] matching [filter] with an ID
+    /// greater than [after], ordered by ID. Used for keyset pagination, which unlike pages
+    /// is stable when rides are inserted or deleted in between.
+    pub async fn find_all_after(user_id: u32, filter: &RideFilter, with_tags: bool, include_deleted: bool, db: &impl ConnectionTrait, after: u32, size: u64) -> Result<Vec<Self>, CurdError> {
+        let query = ride::Entity::find()
+            .filter(ride::Column::UserId.eq(user_id))
+            .filter(not_deleted(ride::Column::DeletedAt, include_deleted))
+            .filter(filter.condition())
+            .filter(ride::Column::Id.gt(after))
+            .order_by_asc(ride::Column::Id)
+            .limit(size);
+        Self::fetch(query, with_tags, db).await
+    }
+
+    /// Fetch the instances with [ids] belonging to [user_id]. IDs which do not exist or
+    /// belong to another user are left out.
+    pub async fn find_by_ids(user_id: u32, ids: &[u32], with_tags: bool, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        let query = ride::Entity::find()
+            .filter(ride::Column::UserId.eq(user_id))
+            .filter(ride::Column::Id.is_in(ids.iter().copied()))
+            .filter(not_deleted(ride::Column::DeletedAt, include_deleted));
+        Self::fetch(query, with_tags, db).await
     }
 
-    /// Find instance by [id].
-    pub async fn find_by_id(id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
-        let mut model = ride::Entity::find()
-            .find_with_related(ride_tag::Entity)
+    /// Find instance by [id]. The linked tags are only fetched and embedded if
+    /// [with_tags] is set.
+    pub async fn find_by_id(id: u32, with_tags: bool, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        let query = ride::Entity::find()
             .filter(ride::Column::Id.eq(id))
-            .filter(ride::Column::DeletedAt.is_null())
-            .all(db)
-            .await
-            .map_err(
-                |error| {
-                    CurdError::DbErr(error)
-                }
-            )?;
-        match model.pop() {
-            Some((tag, options)) => Ok(Self::from_models(tag, options)?),
+            .filter(not_deleted(ride::Column::DeletedAt, include_deleted));
+        match Self::fetch(query, with_tags, db).await?.pop() {
+            Some(ride) => Ok(ride),
             None => Err(CurdError::NotFound)?,
         }
     }
+
+    /// Fetch the rides selected by [query], with their linked tags if [with_tags] is set
+    async fn fetch(query: Select<ride::Entity>, with_tags: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        let mut result = Vec::new();
+        if with_tags {
+            let statement = query.find_with_related(ride_tag::Entity);
+            let models = retry(|| statement.clone().all(db))
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            for (ride, tags) in models {
+                result.push(Self::from_models(ride, Some(tags))?);
+            }
+        } else {
+            let models = retry(|| query.clone().all(db))
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            for ride in models {
+                result.push(Self::from_models(ride, None)?);
+            }
+        }
+        Ok(result)
+    }
 }
 
 /// Check if [tag_id] belongs to [user_id]. Use this to restrict
@@ -140,13 +304,14 @@ impl Ride {
 pub async fn is_owner(
     ride_id: u32,
     user_id: u32,
+    include_deleted: bool,
     db: &impl ConnectionTrait
 ) -> Result<(), CurdError> {
-    let rows = ride::Entity::find()
+    let statement = ride::Entity::find()
         .filter(ride::Column::Id.eq(ride_id))
         .filter(ride::Column::UserId.eq(user_id))
-        .filter(ride::Column::DeletedAt.is_null())
-        .count(db)
+        .filter(not_deleted(ride::Column::DeletedAt, include_deleted));
+    let rows = retry(|| statement.clone().count(db))
         .await
         .map_err(
             |error| {
@@ -160,6 +325,27 @@ pub async fn is_owner(
     }
 }
 
+/// Time of the last change of the rides of [user_id], or only of ride [ride_id], including
+/// their tag links. `None` if there are no rides.
+pub async fn last_modified(
+    user_id: u32,
+    ride_id: Option<u32>,
+    db: &impl ConnectionTrait
+) -> Result<Option<DateTimeUtc>, CurdError> {
+    let mut rides = ride::Entity::find()
+        .filter(ride::Column::UserId.eq(user_id));
+    let mut links = ride_tag::Entity::find()
+        .inner_join(ride::Entity)
+        .filter(ride::Column::UserId.eq(user_id));
+    if let Some(ride_id) = ride_id {
+        rides = rides.filter(ride::Column::Id.eq(ride_id));
+        links = links.filter(ride_tag::Column::RideId.eq(ride_id));
+    }
+    let rides = latest_change(rides, ride::Column::UpdatedAt, ride::Column::DeletedAt, db).await?;
+    let links = latest_change(links, ride_tag::Column::UpdatedAt, ride_tag::Column::DeletedAt, db).await?;
+    Ok(rides.max(links))
+}
+
 /// Builder for creating or updating a model (in the database)
 pub struct CreateUpdateBuilder {
     pub journey_departure: DateTimeUtc,
@@ -168,6 +354,11 @@ pub struct CreateUpdateBuilder {
     pub location_to: String,
     pub remarks: Option<String>,
     pub is_template: bool,
+    pub passengers: u32,
+    pub vat_rate: Option<f64>,
+    /// Status to set, for new rides by default from the departure
+    pub status: Option<RideStatus>,
+    pub arrival_estimated: bool,
 }
 
 impl CreateUpdateBuilder {
@@ -187,6 +378,10 @@ impl CreateUpdateBuilder {
             location_to,
             remarks,
             is_template,
+            passengers: 1,
+            vat_rate: None,
+            status: None,
+            arrival_estimated: false,
         }
     }
 
@@ -199,19 +394,62 @@ impl CreateUpdateBuilder {
             location_to: model.location_to,
             remarks: model.remarks,
             is_template: model.is_template,
+            passengers: model.passengers,
+            vat_rate: model.vat_rate,
+            status: None,
+            arrival_estimated: model.arrival_estimated,
+        }
+    }
+
+    /// Set the number of travellers the price covers
+    pub fn with_passengers(mut self, passengers: u32) -> Self {
+        self.passengers = passengers;
+        self
+    }
+
+    /// Set the rate of the VAT included in the price in percent
+    pub fn with_vat_rate(mut self, vat_rate: Option<f64>) -> Self {
+        self.vat_rate = vat_rate;
+        self
+    }
+
+    /// Fails on rides without travellers and VAT rates outside of 0 to 100 %
+    fn validate(&self) -> Result<(), CurdError> {
+        if self.passengers == 0 {
+            Err(CurdError::Unprocessable("A ride needs at least one passenger".to_string()))?;
+        }
+        if self.vat_rate.is_some_and(|rate| !rate.is_finite() || !(0.0..=100.0).contains(&rate)) {
+            Err(CurdError::Unprocessable("The VAT rate must be between 0 and 100 percent".to_string()))?;
         }
+        Ok(())
+    }
+
+    /// Set the status without checking the transition, e.g. when importing
+    pub fn with_status(mut self, status: RideStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Mark the arrival as estimated
+    pub fn with_arrival_estimated(mut self, arrival_estimated: bool) -> Self {
+        self.arrival_estimated = arrival_estimated;
+        self
     }
 
     /// Insert into database and return the new instance. It will belong to [user_id].
+    /// The initial status is recorded in the status history.
     pub async fn insert(
         self,
         user_id: u32,
+        actor: &Actor,
         db: &impl ConnectionTrait,
     ) -> Result<Ride, CurdError> {
+        self.validate()?;
+        let status = self.status.unwrap_or_else(|| ride_status::initial(self.journey_departure, chrono::Utc::now()));
         let model = ride::ActiveModel {
             id: NotSet,
-            created_at: Set(chrono::Utc::now()),
-            updated_at: Set(chrono::Utc::now()),
+            created_at: NotSet,
+            updated_at: NotSet,
             deleted_at: NotSet,
             user_id: Set(user_id),
             journey_departure: Set(self.journey_departure.clone()),
@@ -220,65 +458,109 @@ impl CreateUpdateBuilder {
             location_to: Set(self.location_to.clone()),
             remarks: Set(self.remarks.clone()),
             is_template: Set(self.is_template),
+            passengers: Set(self.passengers),
+            vat_rate: Set(self.vat_rate),
+            status: Set(status),
+            arrival_estimated: Set(self.arrival_estimated),
         };
-        let result = ride::Entity::insert(model)
-            .exec(db)
+        let result = model
+            .insert(db)
             .await
             .map_err(
                 |error| {
                     CurdError::DbErr(error)
                 }
             )?;
+        record(actor, Resource::Ride, result.id, Action::Created, None, Some(&result), db).await?;
+        ride_status::record_change(result.id, None, status, None, db).await?;
 
         Ok(
             Ride {
-                id: result.last_insert_id,
+                id: result.id,
                 journey_departure: self.journey_departure,
                 journey_arrival: self.journey_arrival,
                 location_from: self.location_from,
                 location_to: self.location_to,
                 remarks: self.remarks,
                 is_template: self.is_template,
-                tags: Vec::new(),
+                passengers: self.passengers,
+                vat_rate: self.vat_rate,
+                status,
+                arrival_estimated: self.arrival_estimated,
+                created_at: result.created_at,
+                updated_at: result.updated_at,
+                deleted_at: result.deleted_at,
+                tags: Some(Vec::new()),
+                warnings: Vec::new(),
             }
         )
     }
 
-    /// Update instance identified by [id] in database.
+    /// Update instance identified by [id] in database. The status is only changed if
+    /// set, and recorded in the status history if it differs.
     pub async fn update(
         self,
         id: u32,
+        actor: &Actor,
         db: &impl ConnectionTrait,
     ) -> Result<(), CurdError> {
-        let result = ride::Entity::update_many()
-            .col_expr(ride::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
-            .col_expr(ride::Column::JourneyDeparture, Expr::value(self.journey_departure.clone()))
-            .col_expr(ride::Column::JourneyArrival, Expr::value(self.journey_arrival.clone()))
-            .col_expr(ride::Column::LocationFrom, Expr::value(self.location_from.clone()))
-            .col_expr(ride::Column::LocationTo, Expr::value(self.location_to.clone()))
-            .col_expr(ride::Column::Remarks, Expr::value(self.remarks.clone()))
-            .col_expr(ride::Column::IsTemplate, Expr::value(self.is_template))
-            .filter(ride::Column::Id.eq(id))
-            .filter(ride::Column::DeletedAt.is_null())
-            .exec(db)
+        self.validate()?;
+        let before = ride::Entity::find_by_id(id)
+            .one(db)
             .await
             .map_err(
                 |error| {
                     CurdError::DbErr(error)
                 }
+            )?
+            .ok_or(CurdError::NotFound)?;
+        let model = ride::ActiveModel {
+            id: Unchanged(id),
+            journey_departure: Set(self.journey_departure),
+            journey_arrival: Set(self.journey_arrival),
+            location_from: Set(self.location_from),
+            location_to: Set(self.location_to),
+            remarks: Set(self.remarks),
+            is_template: Set(self.is_template),
+            passengers: Set(self.passengers),
+            vat_rate: Set(self.vat_rate),
+            status: self.status.map(Set).unwrap_or(NotSet),
+            arrival_estimated: Set(self.arrival_estimated),
+            ..Default::default()
+        };
+        let after = model
+            .update(db)
+            .await
+            .map_err(
+                |error| {
+                    match error {
+                        DbErr::RecordNotUpdated => CurdError::NotFound,
+                        error => CurdError::DbErr(error),
+                    }
+                }
             )?;
-        if result.rows_affected >= 1 {
-            Ok(())
-        } else {
-            Err(CurdError::NotFound)
+        record(actor, Resource::Ride, id, Action::Updated, Some(&before), Some(&after), db).await?;
+        if after.status != before.status {
+            ride_status::record_change(id, Some(before.status), after.status, None, db).await?;
         }
+        Ok(())
     }
 }
 
 /// Remove instance by [id].
-pub async fn remove(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+pub async fn remove(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let before = ride::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    let deleted_at = chrono::Utc::now();
     let result = ride::Entity::update_many()
-        .col_expr(ride::Column::DeletedAt, Expr::value(chrono::Utc::now()))
+        .col_expr(ride::Column::DeletedAt, Expr::value(deleted_at))
         .filter(ride::Column::Id.eq(id))
         .filter(ride::Column::DeletedAt.is_null())
         .exec(db)
@@ -289,8 +571,55 @@ pub async fn remove(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError>
             }
         )?;
     if result.rows_affected >= 1 {
-        Ok(())
+        let after = ride::Model {
+            deleted_at: Some(deleted_at),
+            ..before.clone()
+        };
+        record(actor, Resource::Ride, id, Action::Deleted, Some(&before), Some(&after), db).await
     } else {
         Err(CurdError::NotFound)
     }
 }
+
+/// Delete ride [id] from the database, also if it is soft-deleted. Its tag links are
+/// deleted by the foreign key cascade.
+pub async fn purge(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let before = ride::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    let result = ride::Entity::delete_many()
+        .filter(ride::Column::Id.eq(id))
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if result.rows_affected >= 1 {
+        record(actor, Resource::Ride, id, Action::Deleted, Some(&before), None, db).await
+    } else {
+        Err(CurdError::NotFound)
+    }
+}
+
+/// Mark the rides matching [condition] as updated, after tag links were deleted from
+/// the database. Otherwise, their time of last modification could move back.
+pub async fn touch(condition: Condition, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    timestamps::touch(ride::Entity::update_many(), ride::Column::UpdatedAt)
+        .filter(condition)
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(())
+}
@@ -0,0 +1,171 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::{BTreeMap, HashMap};
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::Serialize;
+use rocket_okapi::okapi::schemars;
+use entity::tag_descriptor::Aggregation;
+use super::mobility_budget::cents;
+use super::ride::Ride;
+use super::ride_tag_link::Value;
+use super::tag::Tag;
+
+/// Period rides are grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Day,
+    /// ISO week, starting on Monday
+    Week,
+    Month,
+}
+
+impl GroupBy {
+    /// Period of the query parameter [group_by], `None` if unknown
+    pub fn parse(group_by: &str) -> Option<Self> {
+        match group_by.trim() {
+            "day" => Some(Self::Day),
+            "week" => Some(Self::Week),
+            "month" => Some(Self::Month),
+            _ => None,
+        }
+    }
+
+    /// First day of the period containing [date]
+    fn start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date - Days::new(date.weekday().num_days_from_monday() as u64),
+            Self::Month => date.with_day(1).expect("Every month has a first day"),
+        }
+    }
+
+    /// Last day of the period starting at [start]
+    fn end(&self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => start,
+            Self::Week => start + Days::new(6),
+            Self::Month => start + Months::new(1) - Days::new(1),
+        }
+    }
+
+    /// Name of the period starting at [start], e.g. `2026-10-16`, `2026-W42` or `2026-10`
+    fn label(&self, start: NaiveDate) -> String {
+        match self {
+            Self::Day => start.format("%Y-%m-%d").to_string(),
+            Self::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            },
+            Self::Month => start.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// Rides departing in one period, with subtotals
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct RideGroup<R> {
+    /// Name of the period, e.g. `2026-10-16`, `2026-W42` or `2026-10`
+    pub period: String,
+    /// First day of the period
+    pub start: NaiveDate,
+    /// Last day of the period
+    pub end: NaiveDate,
+    /// Number of rides
+    pub count: usize,
+    /// Values of each numeric tag over the rides rolled up by its aggregation, e.g. the
+    /// sum of the prices, by tag key
+    pub totals: BTreeMap<String, f64>,
+    /// Rides, earliest departure first
+    pub rides: Vec<R>,
+}
+
+impl<R> RideGroup<R> {
+    /// Group with the rides converted by [f], e.g. to add links
+    pub fn map_rides<T>(self, f: impl FnOnce(Vec<R>) -> Vec<T>) -> RideGroup<T> {
+        RideGroup {
+            period: self.period,
+            start: self.start,
+            end: self.end,
+            count: self.count,
+            totals: self.totals,
+            rides: f(self.rides),
+        }
+    }
+}
+
+/// [values] rolled up by [aggregation], `None` if there are none or they are not rolled up
+fn aggregate(aggregation: Aggregation, values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    match aggregation {
+        Aggregation::Sum => Some(values.iter().sum()),
+        Aggregation::Average => Some(values.iter().sum::<f64>() / values.len() as f64),
+        Aggregation::Min => values.iter().copied().reduce(f64::min),
+        Aggregation::Max => values.iter().copied().reduce(f64::max),
+        Aggregation::None => None,
+    }
+}
+
+/// Group [rides] by the UTC date of their departure into periods of [group_by], oldest
+/// period first. The values of the numeric tags among [tags] are rolled up per period
+/// by their [Tag::aggregation], which requires the rides to be fetched with their tags.
+pub fn group(rides: Vec<Ride>, tags: &[Tag], group_by: GroupBy) -> Vec<RideGroup<Ride>> {
+    let aggregated_tags: HashMap<u32, &Tag> = tags
+        .iter()
+        .filter(|tag| tag.aggregation() != Aggregation::None)
+        .map(|tag| (tag.id(), tag))
+        .collect();
+
+    // Values of the aggregated tags per period and tag
+    let mut values: HashMap<(NaiveDate, u32), Vec<f64>> = HashMap::new();
+    let mut groups: BTreeMap<NaiveDate, RideGroup<Ride>> = BTreeMap::new();
+    for ride in rides {
+        let start = group_by.start(ride.journey_departure.date_naive());
+        let group = groups.entry(start).or_insert_with(|| RideGroup {
+            period: group_by.label(start),
+            start,
+            end: group_by.end(start),
+            count: 0,
+            totals: BTreeMap::new(),
+            rides: Vec::new(),
+        });
+        for link in ride.tags().iter().flatten() {
+            if !aggregated_tags.contains_key(&link.tag_id()) {
+                continue;
+            }
+            let value = match link.value {
+                Value::Float(value) => value,
+                Value::Integer(value) => value as f64,
+                _ => continue,
+            };
+            values.entry((start, link.tag_id())).or_default().push(value);
+        }
+        group.count += 1;
+        group.rides.push(ride);
+    }
+
+    for ((start, tag_id), values) in values {
+        let (Some(group), Some(tag)) = (groups.get_mut(&start), aggregated_tags.get(&tag_id)) else {
+            continue;
+        };
+        if let Some(total) = aggregate(tag.aggregation(), &values) {
+            let total = if tag.is_money() { cents(total) } else { total };
+            group.totals.insert(tag.tag_key().to_string(), total);
+        }
+    }
+
+    groups
+        .into_values()
+        .map(|mut group| {
+            group.rides.sort_by_key(|ride| (ride.journey_departure, ride.id()));
+            group
+        })
+        .collect()
+}
@@ -0,0 +1,251 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::*, NotSet, QueryOrder, Set, Unchanged};
+use entity::ride_reference;
+use entity::ride_reference::ReferenceKind;
+use super::audit::{record, Actor};
+use super::deleted::not_deleted;
+use super::error::CurdError;
+use super::event::{Action, Resource};
+use super::retry::retry;
+
+/// JSON structure
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[schemars(example = "RideReference::example")]
+pub struct RideReference {
+    #[serde(skip_deserializing)]
+    id: u32,
+    #[serde(skip_deserializing)]
+    ride_id: u32,
+    pub kind: ReferenceKind,
+    /// E.g. the booking number
+    pub value: String,
+    /// Page of the booking in the portal of the provider, `http` or `https`
+    pub url: Option<String>,
+    #[serde(skip_deserializing)]
+    created_at: DateTimeUtc,
+    #[serde(skip_deserializing)]
+    updated_at: DateTimeUtc,
+    /// Time of deletion, only set for deleted resources
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<DateTimeUtc>,
+}
+
+impl From<ride_reference::Model> for RideReference {
+    fn from(model: ride_reference::Model) -> Self {
+        Self {
+            id: model.id,
+            ride_id: model.ride_id,
+            kind: model.kind,
+            value: model.value,
+            url: model.url,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            deleted_at: model.deleted_at,
+        }
+    }
+}
+
+impl RideReference {
+    /// Example for the API documentation
+    fn example() -> Self {
+        Self {
+            id: 1,
+            ride_id: 1,
+            kind: ReferenceKind::BookingNumber,
+            value: "X7K2QP".to_string(),
+            url: Some("https://tickets.example.com/bookings/X7K2QP".to_string()),
+            created_at: DateTimeUtc::from_timestamp(1740819000, 0).unwrap_or_default(),
+            updated_at: DateTimeUtc::from_timestamp(1740819000, 0).unwrap_or_default(),
+            deleted_at: None,
+        }
+    }
+
+    /// Getter for [id]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Getter for [ride_id]
+    pub fn ride_id(&self) -> u32 {
+        self.ride_id
+    }
+
+    /// Fetch all instances of parent [ride_id], in order of creation.
+    pub async fn find_all(ride_id: u32, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        let statement = ride_reference::Entity::find()
+            .filter(ride_reference::Column::RideId.eq(ride_id))
+            .filter(not_deleted(ride_reference::Column::DeletedAt, include_deleted))
+            .order_by_asc(ride_reference::Column::Id);
+        let models = retry(|| statement.clone().all(db))
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(models.into_iter().map(Self::from).collect())
+    }
+
+    /// Find instance [id] of parent [ride_id].
+    pub async fn find_by_id(ride_id: u32, id: u32, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        let statement = ride_reference::Entity::find()
+            .filter(ride_reference::Column::Id.eq(id))
+            .filter(ride_reference::Column::RideId.eq(ride_id))
+            .filter(not_deleted(ride_reference::Column::DeletedAt, include_deleted));
+        let model = retry(|| statement.clone().one(db))
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        match model {
+            Some(model) => Ok(Self::from(model)),
+            None => Err(CurdError::NotFound)?,
+        }
+    }
+}
+
+/// Builder for creating or updating a model (in the database)
+pub struct CreateUpdateBuilder {
+    pub kind: ReferenceKind,
+    pub value: String,
+    pub url: Option<String>,
+}
+
+impl CreateUpdateBuilder {
+    /// New builder from deserialized JSON structure
+    pub fn from_json(model: RideReference) -> Self {
+        Self {
+            kind: model.kind,
+            value: model.value,
+            url: model.url,
+        }
+    }
+
+    /// Fails on empty values and URLs which cannot be opened in a browser
+    fn validate(&self) -> Result<(), CurdError> {
+        if self.value.trim().is_empty() {
+            Err(CurdError::Unprocessable("The value of a reference must not be empty".to_string()))?;
+        }
+        if let Some(url) = &self.url {
+            match reqwest::Url::parse(url) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {},
+                _ => Err(CurdError::Unprocessable(format!("Invalid URL {}, it must be http or https", url)))?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert into database and return the new instance. It will be child of [ride_id].
+    pub async fn insert(
+        self,
+        ride_id: u32,
+        actor: &Actor,
+        db: &impl ConnectionTrait,
+    ) -> Result<RideReference, CurdError> {
+        self.validate()?;
+        let model = ride_reference::ActiveModel {
+            id: NotSet,
+            created_at: NotSet,
+            updated_at: NotSet,
+            deleted_at: NotSet,
+            ride_id: Set(ride_id),
+            kind: Set(self.kind),
+            value: Set(self.value),
+            url: Set(self.url),
+        };
+        let result = model
+            .insert(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        record(actor, Resource::RideReference, result.id, Action::Created, None, Some(&result), db).await?;
+        Ok(RideReference::from(result))
+    }
+
+    /// Update instance identified by [id] in database.
+    pub async fn update(
+        self,
+        id: u32,
+        actor: &Actor,
+        db: &impl ConnectionTrait,
+    ) -> Result<RideReference, CurdError> {
+        self.validate()?;
+        let before = ride_reference::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?
+            .ok_or(CurdError::NotFound)?;
+        let model = ride_reference::ActiveModel {
+            id: Unchanged(id),
+            kind: Set(self.kind),
+            value: Set(self.value),
+            url: Set(self.url),
+            ..Default::default()
+        };
+        let after = model
+            .update(db)
+            .await
+            .map_err(
+                |error| {
+                    match error {
+                        DbErr::RecordNotUpdated => CurdError::NotFound,
+                        error => CurdError::DbErr(error),
+                    }
+                }
+            )?;
+        record(actor, Resource::RideReference, id, Action::Updated, Some(&before), Some(&after), db).await?;
+        Ok(RideReference::from(after))
+    }
+}
+
+/// Remove instance by [id].
+pub async fn remove(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let before = ride_reference::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    let deleted_at = chrono::Utc::now();
+    let result = ride_reference::Entity::update_many()
+        .col_expr(ride_reference::Column::DeletedAt, Expr::value(deleted_at))
+        .filter(ride_reference::Column::Id.eq(id))
+        .filter(ride_reference::Column::DeletedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if result.rows_affected >= 1 {
+        let after = ride_reference::Model {
+            deleted_at: Some(deleted_at),
+            ..before.clone()
+        };
+        record(actor, Resource::RideReference, id, Action::Deleted, Some(&before), Some(&after), db).await
+    } else {
+        Err(CurdError::NotFound)
+    }
+}
@@ -0,0 +1,154 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::*, NotSet, QueryOrder, Set, Unchanged};
+use entity::{ride, ride_status_change};
+use entity::ride::RideStatus;
+use super::audit::{record, Actor};
+use super::error::CurdError;
+use super::event::{Action, Resource};
+
+/// Requested change of the status of a ride
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+pub struct StatusRequest {
+    pub status: RideStatus,
+    /// Note on the change, e.g. the number of the expense claim
+    #[serde(default)]
+    pub remarks: Option<String>,
+}
+
+/// JSON structure of a change of the status of a ride
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct StatusChange {
+    id: u32,
+    created_at: DateTimeUtc,
+    /// Not set for the status the ride was created with
+    from_status: Option<RideStatus>,
+    to_status: RideStatus,
+    remarks: Option<String>,
+}
+
+impl From<ride_status_change::Model> for StatusChange {
+    fn from(model: ride_status_change::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            from_status: model.from_status,
+            to_status: model.to_status,
+            remarks: model.remarks,
+        }
+    }
+}
+
+/// Status of a new ride departing at [journey_departure]: planned if it departs after
+/// [now], completed otherwise
+pub fn initial(journey_departure: DateTimeUtc, now: DateTimeUtc) -> RideStatus {
+    if journey_departure > now {
+        RideStatus::Planned
+    } else {
+        RideStatus::Completed
+    }
+}
+
+/// Record that ride [ride_id] changed from [from_status] to [to_status]
+pub async fn record_change(
+    ride_id: u32,
+    from_status: Option<RideStatus>,
+    to_status: RideStatus,
+    remarks: Option<String>,
+    db: &impl ConnectionTrait,
+) -> Result<StatusChange, CurdError> {
+    let model = ride_status_change::ActiveModel {
+        id: NotSet,
+        created_at: NotSet,
+        ride_id: Set(ride_id),
+        from_status: Set(from_status),
+        to_status: Set(to_status),
+        remarks: Set(remarks),
+    };
+    let result = model
+        .insert(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(StatusChange::from(result))
+}
+
+/// Change the status of ride [ride_id] as requested. Fails with a conflict if the
+/// current status cannot change to the requested one.
+pub async fn change(ride_id: u32, request: StatusRequest, actor: &Actor, db: &impl ConnectionTrait) -> Result<StatusChange, CurdError> {
+    let before = ride::Entity::find_by_id(ride_id)
+        .filter(ride::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    if !before.status.transitions().contains(&request.status) {
+        Err(CurdError::Conflict(format!("A {} ride cannot become {}", before.status, request.status)))?;
+    }
+    let model = ride::ActiveModel {
+        id: Unchanged(ride_id),
+        status: Set(request.status),
+        ..Default::default()
+    };
+    let after = model
+        .update(db)
+        .await
+        .map_err(
+            |error| {
+                match error {
+                    DbErr::RecordNotUpdated => CurdError::NotFound,
+                    error => CurdError::DbErr(error),
+                }
+            }
+        )?;
+    record(actor, Resource::Ride, ride_id, Action::Updated, Some(&before), Some(&after), db).await?;
+    record_change(ride_id, Some(before.status), request.status, request.remarks, db).await
+}
+
+/// Changes of the status of ride [ride_id], oldest first
+pub async fn history(ride_id: u32, db: &impl ConnectionTrait) -> Result<Vec<StatusChange>, CurdError> {
+    let models = ride_status_change::Entity::find()
+        .filter(ride_status_change::Column::RideId.eq(ride_id))
+        .order_by_asc(ride_status_change::Column::Id)
+        .all(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(models.into_iter().map(StatusChange::from).collect())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use sea_orm::prelude::DateTimeUtc;
+    use entity::ride::RideStatus;
+    use super::initial;
+
+    #[test]
+    fn test_initial() {
+        let now: DateTimeUtc = "2026-10-16T12:00:00Z".parse().expect("Valid time");
+        assert_eq!(initial(now + TimeDelta::minutes(1), now), RideStatus::Planned);
+        assert_eq!(initial(now - TimeDelta::minutes(1), now), RideStatus::Completed);
+        // A ride departing right now has already started
+        assert_eq!(initial(now, now), RideStatus::Completed);
+    }
+}
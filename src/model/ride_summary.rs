@@ -0,0 +1,414 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeSet;
+use chrono::{Months, NaiveDate, NaiveTime};
+use serde::Serialize;
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::*, sea_query::OnConflict, Condition, FromQueryResult, NotSet, QueryOrder, QuerySelect, Set};
+use entity::{ride, ride_summary, ride_tag, tag_descriptor, timestamps};
+use entity::tag_descriptor::Aggregation;
+use super::deleted::not_deleted;
+use super::error::CurdError;
+use super::event::Resource;
+use super::mobility_budget::cents;
+use super::retry::retry;
+use super::ride::RideFilter;
+
+/// Totals over all rides of a list, not only the current page. The values of the price
+/// and distance tags are rolled up by the aggregation of the tag, the sum if it has
+/// none, and are 0 for the aggregation `none`. The price tag can only be aggregated by
+/// sum, see [super::tag::PriceTag].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RideSummary {
+    /// Sum of the prices, unless the price tag is aggregated differently
+    pub total_cost: f64,
+    /// Sum of the distances, unless the distance tag is aggregated differently
+    pub total_distance: f64,
+}
+
+/// Number, cost and distance of the rides departing in a month, rolled up like
+/// [RideSummary]
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct MonthlySummary {
+    /// Month of departure in UTC as `YYYY-MM`
+    pub month: String,
+    pub ride_count: u32,
+    /// Sum of the prices, unless the price tag is aggregated differently
+    pub total_cost: f64,
+    /// Sum of the distances, unless the distance tag is aggregated differently
+    pub total_distance: f64,
+}
+
+impl From<ride_summary::Model> for MonthlySummary {
+    fn from(model: ride_summary::Model) -> Self {
+        Self {
+            month: model.month,
+            ride_count: model.ride_count,
+            total_cost: model.total_cost,
+            total_distance: model.total_distance,
+        }
+    }
+}
+
+/// Aggregates of the values of a tag
+#[derive(Debug, FromQueryResult)]
+struct TagTotal {
+    tag_key: String,
+    aggregation: Option<Aggregation>,
+    total: Option<f64>,
+    average: Option<f64>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+}
+
+impl TagTotal {
+    /// Value rolled up by the aggregation of the tag, the sum if it has none
+    fn value(&self) -> f64 {
+        match self.aggregation.unwrap_or(Aggregation::Sum) {
+            Aggregation::Sum => self.total,
+            Aggregation::Average => self.average,
+            Aggregation::Min => self.minimum,
+            Aggregation::Max => self.maximum,
+            Aggregation::None => None,
+        }.unwrap_or_default()
+    }
+}
+
+/// Month of [departure] in UTC as `YYYY-MM`
+fn month_of(departure: DateTimeUtc) -> String {
+    departure.format("%Y-%m").to_string()
+}
+
+/// Start of [month], given as `YYYY-MM`, and of the month after
+fn month_range(month: &str) -> Option<(DateTimeUtc, DateTimeUtc)> {
+    let start = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").ok()?;
+    let end = start.checked_add_months(Months::new(1))?;
+    Some((start.and_time(NaiveTime::MIN).and_utc(), end.and_time(NaiveTime::MIN).and_utc()))
+}
+
+/// Mark the summaries of [months] of [user_id] as stale, creating missing ones
+async fn mark_stale(user_id: u32, months: impl IntoIterator<Item = String>, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    // Inserted in bulk with a conflict clause, which bypasses ActiveModelBehavior
+    let now = timestamps::now();
+    for month in months {
+        let model = ride_summary::ActiveModel {
+            id: NotSet,
+            created_at: Set(now),
+            updated_at: Set(now),
+            user_id: Set(user_id),
+            month: Set(month),
+            ride_count: Set(0),
+            total_cost: Set(0.0),
+            total_distance: Set(0.0),
+            stale: Set(true),
+        };
+        ride_summary::Entity::insert(model)
+            .on_conflict(
+                OnConflict::columns([ride_summary::Column::UserId, ride_summary::Column::Month])
+                    .update_columns([ride_summary::Column::UpdatedAt, ride_summary::Column::Stale])
+                    .to_owned()
+            )
+            .exec_without_returning(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+    }
+    Ok(())
+}
+
+/// Mark all summaries of [user_id], or of all users, as stale
+async fn mark_all_stale(user_id: Option<u32>, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let mut statement = timestamps::touch(ride_summary::Entity::update_many(), ride_summary::Column::UpdatedAt)
+        .col_expr(ride_summary::Column::Stale, Expr::value(true));
+    if let Some(user_id) = user_id {
+        statement = statement.filter(ride_summary::Column::UserId.eq(user_id));
+    }
+    statement
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(())
+}
+
+/// Mark the summaries affected by a change of [resource] from [before] to [after] as
+/// stale. These are the database models as recorded in the audit log. Changes of a ride
+/// affect the months of its old and new departure, changes of a ride tag the month of
+/// its ride, and changes of a tag all months of its user, as its key may have changed.
+pub(super) async fn invalidate(
+    resource: Resource,
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+    db: &impl ConnectionTrait,
+) -> Result<(), CurdError> {
+    let models = [before, after].into_iter().flatten().cloned();
+    match resource {
+        Resource::Ride => {
+            for model in models.filter_map(|model| serde_json::from_value::<ride::Model>(model).ok()) {
+                mark_stale(model.user_id, [month_of(model.journey_departure)], db).await?;
+            }
+        },
+        Resource::RideTag => {
+            let ride_ids: BTreeSet<u32> = models
+                .filter_map(|model| serde_json::from_value::<ride_tag::Model>(model).ok())
+                .map(|model| model.ride_id)
+                .collect();
+            for ride_id in ride_ids {
+                let ride = ride::Entity::find_by_id(ride_id)
+                    .one(db)
+                    .await
+                    .map_err(
+                        |error| {
+                            CurdError::DbErr(error)
+                        }
+                    )?;
+                if let Some(ride) = ride {
+                    mark_stale(ride.user_id, [month_of(ride.journey_departure)], db).await?;
+                }
+            }
+        },
+        Resource::Tag => {
+            let user_ids: BTreeSet<u32> = models
+                .filter_map(|model| serde_json::from_value::<tag_descriptor::Model>(model).ok())
+                .map(|model| model.user_id)
+                .collect();
+            for user_id in user_ids {
+                mark_all_stale(Some(user_id), db).await?;
+            }
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+/// Mark the summaries of all users as stale, e.g. after the keys of the price or
+/// distance tag were configured differently. They are computed again on the next read.
+pub async fn invalidate_all(db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    mark_all_stale(None, db).await
+}
+
+/// Rocket state rolling up the prices and distances of ride lists
+#[derive(Debug, Clone)]
+pub struct RideSummaries {
+    /// Key of the tag holding the price of a ride
+    price_tag: String,
+    /// Key of the tag holding the distance of a ride
+    distance_tag: String,
+}
+
+impl RideSummaries {
+    /// Sums of the values of the tags [price_tag] and [distance_tag]
+    pub fn new(price_tag: String, distance_tag: String) -> Self {
+        Self {
+            price_tag,
+            distance_tag,
+        }
+    }
+
+    /// Totals over the rides of [user_id] matching [filter]. Without filter, sums are
+    /// taken from the monthly summaries, otherwise and for other aggregations they are
+    /// aggregated from the ride tags in one query. Rides without price or distance count
+    /// as 0 in sums. Writes if summaries are stale, so [db] must not be a read replica.
+    pub async fn summary(&self, user_id: u32, filter: &RideFilter, include_deleted: bool, db: &impl ConnectionTrait) -> Result<RideSummary, CurdError> {
+        if filter.statuses.is_empty() && !include_deleted && self.summed(user_id, db).await? {
+            let months = self.monthly(user_id, None, db).await?;
+            return Ok(
+                RideSummary {
+                    total_cost: cents(months.iter().map(|month| month.total_cost).sum()),
+                    total_distance: months.iter().map(|month| month.total_distance).sum(),
+                }
+            );
+        }
+        let condition = Condition::all()
+            .add(ride::Column::UserId.eq(user_id))
+            .add(not_deleted(ride::Column::DeletedAt, include_deleted))
+            .add(filter.condition());
+        self.totals(condition, db).await
+    }
+
+    /// Whether the price and distance tags of [user_id] are summed, so that the totals
+    /// of months add up to the total of all rides
+    async fn summed(&self, user_id: u32, db: &impl ConnectionTrait) -> Result<bool, CurdError> {
+        let statement = tag_descriptor::Entity::find()
+            .select_only()
+            .column(tag_descriptor::Column::Aggregation)
+            .filter(tag_descriptor::Column::UserId.eq(user_id))
+            .filter(not_deleted(tag_descriptor::Column::DeletedAt, false))
+            .filter(tag_descriptor::Column::TagKey.is_in([self.price_tag.clone(), self.distance_tag.clone()]));
+        let aggregations = retry(|| statement.clone().into_tuple::<Option<Aggregation>>().all(db))
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(aggregations.into_iter().all(|aggregation| aggregation.is_none_or(|aggregation| aggregation == Aggregation::Sum)))
+    }
+
+    /// Totals of the price and distance tags of the rides matching [condition]
+    async fn totals(&self, condition: Condition, db: &impl ConnectionTrait) -> Result<RideSummary, CurdError> {
+        // Integer and float tags are aggregated alike, as floating point
+        let value = "COALESCE(ride_tag.value_float, ride_tag.value_integer * 1.0)";
+        let statement = ride_tag::Entity::find()
+            .select_only()
+            .column(tag_descriptor::Column::TagKey)
+            .column(tag_descriptor::Column::Aggregation)
+            .column_as(Expr::cust(format!("SUM({})", value)), "total")
+            .column_as(Expr::cust(format!("AVG({})", value)), "average")
+            .column_as(Expr::cust(format!("MIN({})", value)), "minimum")
+            .column_as(Expr::cust(format!("MAX({})", value)), "maximum")
+            .inner_join(ride::Entity)
+            .inner_join(tag_descriptor::Entity)
+            .filter(condition)
+            .filter(not_deleted(ride_tag::Column::DeletedAt, false))
+            .filter(not_deleted(tag_descriptor::Column::DeletedAt, false))
+            .filter(tag_descriptor::Column::TagKey.is_in([self.price_tag.clone(), self.distance_tag.clone()]))
+            .group_by(tag_descriptor::Column::TagKey)
+            .group_by(tag_descriptor::Column::Aggregation);
+        let totals = retry(|| statement.clone().into_model::<TagTotal>().all(db))
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+
+        let mut summary = RideSummary::default();
+        for total in totals {
+            let value = total.value();
+            if total.tag_key == self.price_tag {
+                summary.total_cost = cents(value);
+            } else if total.tag_key == self.distance_tag {
+                summary.total_distance = value;
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Summaries of the months with rides of [user_id], oldest first, optionally only
+    /// from the months of [range], given as `YYYY-MM`. Stale summaries are computed
+    /// again first. If the summaries do not count all rides, e.g. after a restore of a
+    /// backup, all months are computed again.
+    pub async fn monthly(&self, user_id: u32, range: Option<(String, String)>, db: &impl ConnectionTrait) -> Result<Vec<MonthlySummary>, CurdError> {
+        self.refresh(user_id, db).await?;
+        let rides = ride::Entity::find()
+            .filter(ride::Column::UserId.eq(user_id))
+            .filter(not_deleted(ride::Column::DeletedAt, false));
+        let ride_count = retry(|| rides.clone().count(db))
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        let mut summaries = self.find_all(user_id, db).await?;
+        if summaries.iter().map(|summary| summary.ride_count as u64).sum::<u64>() != ride_count {
+            let departures = retry(|| rides.clone().select_only().column(ride::Column::JourneyDeparture).into_tuple::<DateTimeUtc>().all(db))
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            let months: BTreeSet<String> = departures
+                .into_iter()
+                .map(month_of)
+                .chain(summaries.iter().map(|summary| summary.month.clone()))
+                .collect();
+            mark_stale(user_id, months, db).await?;
+            self.refresh(user_id, db).await?;
+            summaries = self.find_all(user_id, db).await?;
+        }
+        Ok(
+            summaries
+                .into_iter()
+                .filter(|summary| range.as_ref().is_none_or(|(from, to)| summary.month >= *from && summary.month <= *to))
+                .map(MonthlySummary::from)
+                .collect()
+        )
+    }
+
+    /// Summaries of [user_id], oldest month first
+    async fn find_all(&self, user_id: u32, db: &impl ConnectionTrait) -> Result<Vec<ride_summary::Model>, CurdError> {
+        let statement = ride_summary::Entity::find()
+            .filter(ride_summary::Column::UserId.eq(user_id))
+            .order_by_asc(ride_summary::Column::Month);
+        retry(|| statement.clone().all(db))
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )
+    }
+
+    /// Compute the stale summaries of [user_id] again. Months without rides are removed.
+    /// A summary marked as stale again meanwhile stays stale.
+    async fn refresh(&self, user_id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+        let stale: Vec<ride_summary::Model> = self.find_all(user_id, db)
+            .await?
+            .into_iter()
+            .filter(|summary| summary.stale)
+            .collect();
+        for summary in stale {
+            let Some((start, end)) = month_range(&summary.month) else {
+                continue;
+            };
+            let condition = Condition::all()
+                .add(ride::Column::UserId.eq(user_id))
+                .add(not_deleted(ride::Column::DeletedAt, false))
+                .add(ride::Column::JourneyDeparture.gte(start))
+                .add(ride::Column::JourneyDeparture.lt(end));
+            let statement = ride::Entity::find().filter(condition.clone());
+            let ride_count = retry(|| statement.clone().count(db))
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            let unchanged = Condition::all()
+                .add(ride_summary::Column::Id.eq(summary.id))
+                .add(ride_summary::Column::UpdatedAt.eq(summary.updated_at));
+            if ride_count == 0 {
+                ride_summary::Entity::delete_many()
+                    .filter(unchanged)
+                    .exec(db)
+                    .await
+                    .map_err(
+                        |error| {
+                            CurdError::DbErr(error)
+                        }
+                    )?;
+                continue;
+            }
+            let totals = self.totals(condition, db).await?;
+            ride_summary::Entity::update_many()
+                .col_expr(ride_summary::Column::RideCount, Expr::value(ride_count as u32))
+                .col_expr(ride_summary::Column::TotalCost, Expr::value(totals.total_cost))
+                .col_expr(ride_summary::Column::TotalDistance, Expr::value(totals.total_distance))
+                .col_expr(ride_summary::Column::Stale, Expr::value(false))
+                .filter(unchanged)
+                .exec(db)
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+        }
+        Ok(())
+    }
+}
@@ -10,17 +10,25 @@ use serde::{Deserialize, Serialize};
 use rocket_okapi::okapi::schemars;
 use sea_orm::{
     prelude::*,
+    Condition,
     Set,
     NotSet,
+    Unchanged,
 };
 use entity::ride;
 use entity::ride_tag;
 use entity::tag_descriptor::TagType;
+use super::audit::{record, Actor};
+use super::deleted::not_deleted;
 use super::error::CurdError;
+use super::event::{Action, Resource};
+use super::retry::retry;
 use super::tag::Tag;
 
 /// JSON structure
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[schemars(example = "RideTagLink::example")]
+#[serde(deny_unknown_fields)]
 pub struct RideTagLink {
     #[serde(skip_deserializing)]
     id: u32,
@@ -30,16 +38,33 @@ pub struct RideTagLink {
     tag_id: u32,
     pub order: u32,
     pub value: Value,
+    #[schemars(length(max = 2000))]
     pub remarks: Option<String>,
+    /// On templates, the value is a default to confirm for each ride instead of a fixed
+    /// value. On rides, the value was copied from a template and is not confirmed yet.
+    #[serde(default)]
+    pub per_ride: bool,
+    #[serde(skip_deserializing)]
+    created_at: DateTimeUtc,
+    #[serde(skip_deserializing)]
+    updated_at: DateTimeUtc,
+    /// Time of deletion, only set for deleted resources
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<DateTimeUtc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 #[serde(tag = "type", content = "value")]
 pub enum Value {
+    /// Value of an `integer` tag
     Integer(i64),
+    /// Value of a `float` tag
     Float(f64),
+    /// Value of a `string` tag
     String(String),
+    /// Value of a `date_time` tag
     DateTime(DateTimeUtc),
+    /// ID of an option of an `enum` tag
     EnumOption(u32),
 }
 
@@ -94,6 +119,22 @@ impl TryFrom<ride_tag::Model> for RideTagLink {
 }
 
 impl RideTagLink {
+    /// Example for the API documentation
+    pub fn example() -> Self {
+        Self {
+            id: 1,
+            ride_id: 1,
+            tag_id: 1,
+            order: 0,
+            value: Value::Float(2.9),
+            remarks: None,
+            per_ride: false,
+            created_at: DateTimeUtc::from_timestamp(1740819000, 0).unwrap_or_default(),
+            updated_at: DateTimeUtc::from_timestamp(1740819000, 0).unwrap_or_default(),
+            deleted_at: None,
+        }
+    }
+
     /// Getter for [id]
     pub fn id(&self) -> u32 {
         self.id
@@ -130,16 +171,20 @@ impl RideTagLink {
             order: model.order,
             value,
             remarks: model.remarks,
+            per_ride: model.per_ride,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            deleted_at: model.deleted_at,
         };
         Ok(link)
     }
 
     /// Fetch all instances belonging to [ride_id]
-    pub async fn find_all(ride_id: u32, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
-        let models = ride_tag::Entity::find()
+    pub async fn find_all(ride_id: u32, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        let statement = ride_tag::Entity::find()
             .filter(ride_tag::Column::RideId.eq(ride_id))
-            .filter(ride_tag::Column::DeletedAt.is_null())
-            .all(db)
+            .filter(not_deleted(ride_tag::Column::DeletedAt, include_deleted));
+        let models = retry(|| statement.clone().all(db))
             .await
             .map_err(
                 |error| {
@@ -154,12 +199,12 @@ impl RideTagLink {
     }
 
     /// Find instance by [tag_id] of [ride_id].
-    pub async fn find_by_tag_id(ride_id: u32, tag_id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
-        let mut model = ride_tag::Entity::find()
+    pub async fn find_by_tag_id(ride_id: u32, tag_id: u32, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        let statement = ride_tag::Entity::find()
             .filter(ride_tag::Column::RideId.eq(ride_id))
             .filter(ride_tag::Column::TagDescriptorId.eq(tag_id))
-            .filter(ride_tag::Column::DeletedAt.is_null())
-            .one(db)
+            .filter(not_deleted(ride_tag::Column::DeletedAt, include_deleted));
+        let mut model = retry(|| statement.clone().one(db))
             .await
             .map_err(
                 |error| {
@@ -173,11 +218,11 @@ impl RideTagLink {
     }
 
     /// Find instance by [id].
-    pub async fn find_by_id(id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
-        let mut model = ride_tag::Entity::find()
+    pub async fn find_by_id(id: u32, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        let statement = ride_tag::Entity::find()
             .filter(ride_tag::Column::Id.eq(id))
-            .filter(ride_tag::Column::DeletedAt.is_null())
-            .one(db)
+            .filter(not_deleted(ride_tag::Column::DeletedAt, include_deleted));
+        let mut model = retry(|| statement.clone().one(db))
             .await
             .map_err(
                 |error| {
@@ -196,15 +241,16 @@ impl RideTagLink {
 pub async fn is_owner(
     link_id: u32,
     user_id: u32,
+    include_deleted: bool,
     db: &impl ConnectionTrait
 ) -> Result<(), CurdError> {
-    let rows = ride_tag::Entity::find()
+    let statement = ride_tag::Entity::find()
         .find_also_related(ride::Entity)
         .filter(ride_tag::Column::Id.eq(link_id))
-        .filter(ride_tag::Column::DeletedAt.is_null())
+        .filter(not_deleted(ride_tag::Column::DeletedAt, include_deleted))
         .filter(ride::Column::UserId.eq(user_id))
-        .filter(ride::Column::DeletedAt.is_null())
-        .count(db)
+        .filter(not_deleted(ride::Column::DeletedAt, include_deleted));
+    let rows = retry(|| statement.clone().count(db))
         .await
         .map_err(
             |error| {
@@ -223,6 +269,7 @@ pub struct CreateUpdateBuilder {
     pub order: u32,
     pub value: Value,
     pub remarks: Option<String>,
+    pub per_ride: bool,
 }
 
 impl CreateUpdateBuilder {
@@ -236,18 +283,37 @@ impl CreateUpdateBuilder {
             order,
             value,
             remarks,
+            per_ride: false,
         }
     }
 
+    /// Mark the value as default to confirm for each ride
+    pub fn with_per_ride(mut self, per_ride: bool) -> Self {
+        self.per_ride = per_ride;
+        self
+    }
+
     /// New builder from deserialized JSON structure
     pub fn from_json(model: RideTagLink) -> Self {
         Self {
             order: model.order,
             value: model.value,
             remarks: model.remarks,
+            per_ride: model.per_ride,
         }
     }
 
+    /// Check that the value matches the type and options of [tag]
+    pub fn validate(&self, tag: &Tag) -> Result<(), CurdError> {
+        self.value
+            .validate(tag)
+            .map_err(
+                |error| {
+                    CurdError::Unprocessable(error.to_string())
+                }
+            )
+    }
+
     fn get_value_integer(&self) -> Option<i64> {
         if let Value::Integer(value) = self.value {
             Some(value)
@@ -293,12 +359,13 @@ impl CreateUpdateBuilder {
         self,
         ride_id: u32,
         tag_id: u32,
+        actor: &Actor,
         db: &impl ConnectionTrait,
     ) -> Result<RideTagLink, CurdError> {
         let model = ride_tag::ActiveModel {
             id: NotSet,
-            created_at: Set(chrono::Utc::now()),
-            updated_at: Set(chrono::Utc::now()),
+            created_at: NotSet,
+            updated_at: NotSet,
             deleted_at: NotSet,
             ride_id: Set(ride_id),
             tag_descriptor_id: Set(tag_id),
@@ -309,24 +376,30 @@ impl CreateUpdateBuilder {
             value_date_time: Set(self.get_value_date_time()),
             value_enum_option_id: Set(self.get_value_enum_option_id()),
             remarks: Set(self.remarks.clone()),
+            per_ride: Set(self.per_ride),
         };
-        let result = ride_tag::Entity::insert(model)
-            .exec(db)
+        let result = model
+            .insert(db)
             .await
             .map_err(
                 |error| {
                     CurdError::DbErr(error)
                 }
             )?;
+        record(actor, Resource::RideTag, result.id, Action::Created, None, Some(&result), db).await?;
 
         Ok(
             RideTagLink {
-                id: result.last_insert_id,
+                id: result.id,
                 ride_id,
                 tag_id,
                 order: self.order,
                 value: self.value,
                 remarks: self.remarks,
+                per_ride: self.per_ride,
+                created_at: result.created_at,
+                updated_at: result.updated_at,
+                deleted_at: result.deleted_at,
             }
         )
     }
@@ -335,38 +408,59 @@ impl CreateUpdateBuilder {
     pub async fn update(
         self,
         id: u32,
+        actor: &Actor,
         db: &impl ConnectionTrait,
     ) -> Result<(), CurdError> {
-        let result = ride_tag::Entity::update_many()
-            .col_expr(ride_tag::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
-            .col_expr(ride_tag::Column::Order, Expr::value(self.order))
-            .col_expr(ride_tag::Column::ValueInteger, Expr::value(self.get_value_integer()))
-            .col_expr(ride_tag::Column::ValueFloat, Expr::value(self.get_value_float()))
-            .col_expr(ride_tag::Column::ValueString, Expr::value(self.get_value_string()))
-            .col_expr(ride_tag::Column::ValueDateTime, Expr::value(self.get_value_date_time()))
-            .col_expr(ride_tag::Column::ValueEnumOptionId, Expr::value(self.get_value_enum_option_id()))
-            .col_expr(ride_tag::Column::Remarks, Expr::value(self.remarks.clone()))
-            .filter(ride_tag::Column::Id.eq(id))
-            .filter(ride_tag::Column::DeletedAt.is_null())
-            .exec(db)
+        let before = ride_tag::Entity::find_by_id(id)
+            .one(db)
             .await
             .map_err(
                 |error| {
                     CurdError::DbErr(error)
                 }
+            )?
+            .ok_or(CurdError::NotFound)?;
+        let model = ride_tag::ActiveModel {
+            id: Unchanged(id),
+            order: Set(self.order),
+            value_integer: Set(self.get_value_integer()),
+            value_float: Set(self.get_value_float()),
+            value_string: Set(self.get_value_string()),
+            value_date_time: Set(self.get_value_date_time()),
+            value_enum_option_id: Set(self.get_value_enum_option_id()),
+            remarks: Set(self.remarks),
+            per_ride: Set(self.per_ride),
+            ..Default::default()
+        };
+        let after = model
+            .update(db)
+            .await
+            .map_err(
+                |error| {
+                    match error {
+                        DbErr::RecordNotUpdated => CurdError::NotFound,
+                        error => CurdError::DbErr(error),
+                    }
+                }
             )?;
-        if result.rows_affected >= 1 {
-            Ok(())
-        } else {
-            Err(CurdError::NotFound)
-        }
+        record(actor, Resource::RideTag, id, Action::Updated, Some(&before), Some(&after), db).await
     }
 }
 
 /// Remove instance by [id].
-pub async fn remove(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+pub async fn remove(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let before = ride_tag::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    let deleted_at = chrono::Utc::now();
     let result = ride_tag::Entity::update_many()
-        .col_expr(ride_tag::Column::DeletedAt, Expr::value(chrono::Utc::now()))
+        .col_expr(ride_tag::Column::DeletedAt, Expr::value(deleted_at))
         .filter(ride_tag::Column::Id.eq(id))
         .filter(ride_tag::Column::DeletedAt.is_null())
         .exec(db)
@@ -377,8 +471,36 @@ pub async fn remove(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError>
             }
         )?;
     if result.rows_affected >= 1 {
-        Ok(())
+        let after = ride_tag::Model {
+            deleted_at: Some(deleted_at),
+            ..before.clone()
+        };
+        record(actor, Resource::RideTag, id, Action::Deleted, Some(&before), Some(&after), db).await
     } else {
         Err(CurdError::NotFound)
     }
 }
+
+/// Delete link [id] from the database, also if it is soft-deleted. The ride is marked as
+/// updated.
+pub async fn purge(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let link = ride_tag::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    ride_tag::Entity::delete_by_id(id)
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    super::ride::touch(Condition::all().add(ride::Column::Id.eq(link.ride_id)), db).await?;
+    record(actor, Resource::RideTag, id, Action::Deleted, Some(&link), None, db).await
+}
@@ -0,0 +1,472 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::LazyLock;
+use regex::Regex;
+use serde::Serialize;
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::*, IntoActiveModel, NotSet, QueryOrder, QuerySelect, Set, Unchanged};
+use entity::{ride, ride_track};
+use entity::ride_track::ScanStatus;
+use super::audit::{record, Actor};
+use super::error::CurdError;
+use super::event::{Action, Resource};
+use super::malware_scan::{MalwareScanner, Verdict};
+use super::retry::retry;
+use super::ride_tag_link::{self, RideTagLink, Value};
+use super::tag::Tag;
+
+static SEGMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<trkseg\b[^>]*>(.*?)</trkseg>").expect("Valid regex")
+});
+static POINT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<trkpt\b([^>]*?)(?:/>|>(.*?)</trkpt>)").expect("Valid regex")
+});
+static LATITUDE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\blat\s*=\s*["']([^"']*)["']"#).expect("Valid regex")
+});
+static LONGITUDE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\blon\s*=\s*["']([^"']*)["']"#).expect("Valid regex")
+});
+static TIME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<time>\s*([^<]*?)\s*</time>").expect("Valid regex")
+});
+
+/// Mean radius of the earth in meters
+const EARTH_RADIUS: f64 = 6_371_008.8;
+/// Meters per mile
+const MILE: f64 = 1609.344;
+
+/// Point of a track
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub time: Option<DateTimeUtc>,
+}
+
+impl TrackPoint {
+    /// Great-circle distance to [other] in meters
+    fn distance_to(&self, other: &TrackPoint) -> f64 {
+        let (lat_a, lat_b) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let d_lat = lat_b - lat_a;
+        let d_lon = (other.longitude - self.longitude).to_radians();
+        let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin()
+    }
+}
+
+/// Distance and times computed from a GPX document
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackStats {
+    pub point_count: u32,
+    /// Travelled distance in meters
+    pub distance: f64,
+    pub started_at: Option<DateTimeUtc>,
+    pub ended_at: Option<DateTimeUtc>,
+}
+
+/// Parse the track points of [gpx], grouped by track segment. Documents without
+/// `<trkseg>` are read as one segment.
+pub fn parse_gpx(gpx: &str) -> Result<Vec<Vec<TrackPoint>>, CurdError> {
+    let segments: Vec<&str> = if SEGMENT.is_match(gpx) {
+        SEGMENT.captures_iter(gpx)
+            .filter_map(|captures| captures.get(1))
+            .map(|segment| segment.as_str())
+            .collect()
+    } else {
+        vec![gpx]
+    };
+
+    let mut number = 0;
+    let mut result = Vec::new();
+    for segment in segments {
+        let mut points = Vec::new();
+        for captures in POINT.captures_iter(segment) {
+            number += 1;
+            let attributes = captures.get(1).map(|m| m.as_str()).unwrap_or_default();
+            let coordinate = |regex: &Regex, limit: f64| {
+                regex.captures(attributes)
+                    .and_then(|value| value[1].trim().parse::<f64>().ok())
+                    .filter(|value| value.is_finite() && value.abs() <= limit)
+            };
+            let latitude = coordinate(&LATITUDE, 90.0)
+                .ok_or_else(|| CurdError::Unprocessable(format!("Point {} has no valid latitude", number)))?;
+            let longitude = coordinate(&LONGITUDE, 180.0)
+                .ok_or_else(|| CurdError::Unprocessable(format!("Point {} has no valid longitude", number)))?;
+            let time = match captures.get(2).and_then(|content| TIME.captures(content.as_str())) {
+                Some(time) => Some(
+                    chrono::DateTime::parse_from_rfc3339(&time[1])
+                        .map_err(|_| CurdError::Unprocessable(format!("Point {} has an invalid time", number)))?
+                        .to_utc()
+                ),
+                None => None,
+            };
+            points.push(TrackPoint { latitude, longitude, time });
+        }
+        if !points.is_empty() {
+            result.push(points);
+        }
+    }
+    if result.is_empty() {
+        Err(CurdError::Unprocessable("The GPX document has no track points".to_string()))?;
+    }
+    Ok(result)
+}
+
+/// Distance along the points of each of [segments], without the gaps between segments,
+/// and the earliest and latest time of the points
+pub fn stats(segments: &[Vec<TrackPoint>]) -> TrackStats {
+    let points = segments.iter().flatten();
+    TrackStats {
+        point_count: points.clone().count() as u32,
+        distance: segments
+            .iter()
+            .flat_map(|segment| segment.windows(2))
+            .map(|pair| pair[0].distance_to(&pair[1]))
+            .sum(),
+        started_at: points.clone().filter_map(|point| point.time).min(),
+        ended_at: points.filter_map(|point| point.time).max(),
+    }
+}
+
+/// JSON structure
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+#[schemars(example = "RideTrack::example")]
+pub struct RideTrack {
+    id: u32,
+    ride_id: u32,
+    /// Number of track points
+    pub point_count: u32,
+    /// Travelled distance in meters
+    pub distance: f64,
+    /// Time between first and last point in seconds, if the track has timestamps
+    pub duration: Option<i64>,
+    pub started_at: Option<DateTimeUtc>,
+    pub ended_at: Option<DateTimeUtc>,
+    /// Result of the malware scan. The GPX document of a quarantined track cannot be
+    /// downloaded.
+    pub scan_status: ScanStatus,
+    created_at: DateTimeUtc,
+    updated_at: DateTimeUtc,
+}
+
+impl From<ride_track::Model> for RideTrack {
+    fn from(model: ride_track::Model) -> Self {
+        let duration = match (model.started_at, model.ended_at) {
+            (Some(started_at), Some(ended_at)) => Some((ended_at - started_at).num_seconds()),
+            _ => None,
+        };
+        Self {
+            id: model.id,
+            ride_id: model.ride_id,
+            point_count: model.point_count,
+            distance: model.distance,
+            duration,
+            started_at: model.started_at,
+            ended_at: model.ended_at,
+            scan_status: model.scan_status,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+impl RideTrack {
+    /// Example for the API documentation
+    fn example() -> Self {
+        Self {
+            id: 1,
+            ride_id: 1,
+            point_count: 412,
+            distance: 8734.2,
+            duration: Some(1920),
+            started_at: DateTimeUtc::from_timestamp(1740819000, 0),
+            ended_at: DateTimeUtc::from_timestamp(1740820920, 0),
+            scan_status: ScanStatus::Clean,
+            created_at: DateTimeUtc::from_timestamp(1740821000, 0).unwrap_or_default(),
+            updated_at: DateTimeUtc::from_timestamp(1740821000, 0).unwrap_or_default(),
+        }
+    }
+
+    /// Getter for [id]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Getter for [ride_id]
+    pub fn ride_id(&self) -> u32 {
+        self.ride_id
+    }
+
+    /// Find the track of [ride_id].
+    pub async fn find_by_ride_id(ride_id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        Ok(Self::from(find_model(ride_id, db).await?.ok_or(CurdError::NotFound)?))
+    }
+}
+
+/// Track of [ride_id] including the GPX document, if any
+async fn find_model(ride_id: u32, db: &impl ConnectionTrait) -> Result<Option<ride_track::Model>, CurdError> {
+    let statement = ride_track::Entity::find()
+        .filter(ride_track::Column::RideId.eq(ride_id));
+    retry(|| statement.clone().one(db))
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )
+}
+
+/// GPX document of the track of [ride_id], as uploaded. Fails with
+/// [CurdError::Conflict] while the track is quarantined.
+pub async fn gpx(ride_id: u32, db: &impl ConnectionTrait) -> Result<String, CurdError> {
+    let model = find_model(ride_id, db).await?.ok_or(CurdError::NotFound)?;
+    if model.scan_status == ScanStatus::Quarantined {
+        Err(CurdError::Conflict("The track is quarantined by the malware scan".to_string()))?;
+    }
+    Ok(model.gpx)
+}
+
+/// Rocket state storing GPX tracks of rides and filling in their distance
+#[derive(Clone)]
+pub struct RideTracks {
+    /// Key of the tag holding the distance of a ride
+    distance_tag: String,
+    scanner: MalwareScanner,
+}
+
+impl RideTracks {
+    /// Tracks fill the distance into the tag [distance_tag] and are checked by
+    /// [scanner] before they are stored
+    pub fn new(distance_tag: String, scanner: MalwareScanner) -> Self {
+        Self {
+            distance_tag,
+            scanner,
+        }
+    }
+
+    /// Store [gpx] as the track of [ride_id] of [user_id], replacing an earlier track,
+    /// and link the travelled distance with the distance tag if the user has one. A
+    /// distance the ride already has is kept unless [overwrite_distance] is set.
+    /// Tracks found to be malicious are stored quarantined, without filling in the
+    /// distance.
+    pub async fn attach(
+        &self,
+        ride_id: u32,
+        user_id: u32,
+        gpx: String,
+        overwrite_distance: bool,
+        actor: &Actor,
+        db: &impl ConnectionTrait,
+    ) -> Result<RideTrack, CurdError> {
+        let stats = stats(&parse_gpx(&gpx)?);
+        let (scan_status, scan_finding) = match self.scanner.scan(gpx.as_bytes()).await? {
+            None => (ScanStatus::Unscanned, None),
+            Some(Verdict::Clean) => (ScanStatus::Clean, None),
+            Some(Verdict::Infected(finding)) => {
+                warn!("GPX track of ride {} is quarantined: {}", ride_id, finding);
+                (ScanStatus::Quarantined, Some(finding))
+            },
+        };
+        let before = find_model(ride_id, db).await?;
+        let model = ride_track::ActiveModel {
+            id: match &before {
+                Some(before) => Unchanged(before.id),
+                None => NotSet,
+            },
+            created_at: NotSet,
+            updated_at: NotSet,
+            ride_id: Set(ride_id),
+            gpx: Set(gpx),
+            point_count: Set(stats.point_count),
+            distance: Set(stats.distance),
+            started_at: Set(stats.started_at),
+            ended_at: Set(stats.ended_at),
+            scan_status: Set(scan_status),
+            scan_finding: Set(scan_finding),
+        };
+        let after = match before {
+            Some(_) => model.update(db).await,
+            None => model.insert(db).await,
+        }
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        let action = if before.is_some() { Action::Updated } else { Action::Created };
+        record(actor, Resource::RideTrack, after.id, action, before.as_ref(), Some(&after), db).await?;
+
+        if scan_status != ScanStatus::Quarantined {
+            self.fill_distance(ride_id, user_id, stats.distance, overwrite_distance, actor, db).await?;
+        }
+        Ok(RideTrack::from(after))
+    }
+
+    /// Link [meters] with the distance tag of [user_id] on [ride_id], converted to the
+    /// unit of the tag: `m`, `mi` or kilometers otherwise. Does nothing if the user has
+    /// no numeric distance tag, or if the ride has a distance already and [overwrite] is
+    /// not set. Returns whether the distance was filled in.
+    async fn fill_distance(
+        &self,
+        ride_id: u32,
+        user_id: u32,
+        meters: f64,
+        overwrite: bool,
+        actor: &Actor,
+        db: &impl ConnectionTrait,
+    ) -> Result<bool, CurdError> {
+        let distance_tag = Tag::find_all(user_id, false, false, db)
+            .await?
+            .into_iter()
+            .find(|tag| *tag.tag_key() == self.distance_tag);
+        let Some(distance_tag) = distance_tag else {
+            return Ok(false);
+        };
+        let distance = match distance_tag.unit.as_deref().map(str::trim) {
+            Some("m") => meters,
+            Some("mi") => meters / MILE,
+            _ => meters / 1000.0,
+        };
+        let value = match distance_tag.tag_type.as_str() {
+            "float" => Value::Float((distance * 100.0).round() / 100.0),
+            "integer" => Value::Integer(distance.round() as i64),
+            _ => return Ok(false),
+        };
+
+        match RideTagLink::find_by_tag_id(ride_id, distance_tag.id(), false, db).await {
+            Ok(_) if !overwrite => Ok(false),
+            Ok(link) => {
+                ride_tag_link::CreateUpdateBuilder::new(link.order, value, link.remarks.clone())
+                    .update(link.id(), actor, db)
+                    .await?;
+                Ok(true)
+            },
+            Err(CurdError::NotFound) => {
+                ride_tag_link::CreateUpdateBuilder::new(0, value, None)
+                    .insert(ride_id, distance_tag.id(), actor, db)
+                    .await?;
+                Ok(true)
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Remove the track of [ride_id]. The distance filled in from it is kept.
+pub async fn remove(ride_id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let before = find_model(ride_id, db).await?.ok_or(CurdError::NotFound)?;
+    ride_track::Entity::delete_by_id(before.id)
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    record(actor, Resource::RideTrack, before.id, Action::Deleted, Some(&before), None, db).await
+}
+
+/// Track held back by the malware scan, for review by an admin
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct QuarantinedTrack {
+    pub id: u32,
+    pub ride_id: u32,
+    /// Owner of the ride
+    pub user_id: u32,
+    /// What the scanner found, e.g. the name of the signature
+    pub finding: Option<String>,
+    /// Size of the GPX document in bytes
+    pub size: usize,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+/// Count the quarantined tracks of all users
+pub async fn count_quarantined(db: &impl ConnectionTrait) -> Result<u64, CurdError> {
+    ride_track::Entity::find()
+        .filter(ride_track::Column::ScanStatus.eq(ScanStatus::Quarantined))
+        .count(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )
+}
+
+/// Quarantined tracks of all users, oldest upload first. Use pagination
+pub async fn find_quarantined_paginated(db: &impl ConnectionTrait, page: u64, size: u64) -> Result<Vec<QuarantinedTrack>, CurdError> {
+    let tracks = ride_track::Entity::find()
+        .filter(ride_track::Column::ScanStatus.eq(ScanStatus::Quarantined))
+        .find_also_related(ride::Entity)
+        .order_by_asc(ride_track::Column::UpdatedAt)
+        .order_by_asc(ride_track::Column::Id)
+        .offset(page * size)
+        .limit(size)
+        .all(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(
+        tracks
+            .into_iter()
+            .filter_map(|(track, ride)| ride.map(|ride| QuarantinedTrack {
+                id: track.id,
+                ride_id: track.ride_id,
+                user_id: ride.user_id,
+                finding: track.scan_finding,
+                size: track.gpx.len(),
+                created_at: track.created_at,
+                updated_at: track.updated_at,
+            }))
+            .collect()
+    )
+}
+
+/// Quarantined track [id]
+async fn find_quarantined_model(id: u32, db: &impl ConnectionTrait) -> Result<ride_track::Model, CurdError> {
+    ride_track::Entity::find_by_id(id)
+        .filter(ride_track::Column::ScanStatus.eq(ScanStatus::Quarantined))
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)
+}
+
+/// Release the quarantined track [id] after review, so that its owner can download it
+/// again. The distance is not filled in afterwards.
+pub async fn release(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<RideTrack, CurdError> {
+    let before = find_quarantined_model(id, db).await?;
+    let mut model = before.clone().into_active_model();
+    model.scan_status = Set(ScanStatus::Released);
+    let after = model
+        .update(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    record(actor, Resource::RideTrack, after.id, Action::Updated, Some(&before), Some(&after), db).await?;
+    Ok(RideTrack::from(after))
+}
+
+/// Delete the quarantined track [id] after review
+pub async fn remove_quarantined(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let before = find_quarantined_model(id, db).await?;
+    remove(before.ride_id, actor, db).await
+}
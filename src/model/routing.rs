@@ -0,0 +1,298 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::DateTimeUtc, ConnectionTrait};
+use super::error::CurdError;
+use super::geocode::{Geocoder, USER_AGENT};
+
+/// Maximum number of suggestions
+const ITINERARY_LIMIT: usize = 3;
+/// Timeout of requests to the router
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Trip planning query of the GTFS GraphQL API of OpenTripPlanner
+const OTP_QUERY: &str = "query Plan($from: PlanCoordinateInput!, $to: PlanCoordinateInput!, $departure: OffsetDateTime!, $first: Int!) {
+  planConnection(
+    origin: {location: {coordinate: $from}}
+    destination: {location: {coordinate: $to}}
+    dateTime: {earliestDeparture: $departure}
+    first: $first
+  ) {
+    edges { node { start end numberOfTransfers } }
+  }
+}";
+
+/// Journey to plan
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SuggestRequest {
+    /// Place name, or coordinates as `<latitude>,<longitude>`
+    pub location_from: String,
+    /// Place name, or coordinates as `<latitude>,<longitude>`
+    pub location_to: String,
+    /// Earliest departure
+    pub journey_departure: DateTimeUtc,
+}
+
+/// Connection found by the router
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Suggestion {
+    pub journey_departure: DateTimeUtc,
+    pub journey_arrival: DateTimeUtc,
+    /// Travel time in minutes
+    pub duration_minutes: i64,
+    /// Number of changes between vehicles
+    pub transfers: u32,
+}
+
+impl Suggestion {
+    fn new(departure: DateTimeUtc, arrival: DateTimeUtc, transfers: u32) -> Self {
+        Self {
+            journey_departure: departure,
+            journey_arrival: arrival,
+            duration_minutes: (arrival - departure).num_minutes(),
+            transfers,
+        }
+    }
+}
+
+/// Routing service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    /// MOTIS with its `/api/v1/plan` endpoint
+    Motis,
+    /// OpenTripPlanner 2 with its GTFS GraphQL API
+    Otp,
+}
+
+impl Display for Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Provider::Motis => write!(f, "motis"),
+            Provider::Otp => write!(f, "otp"),
+        }
+    }
+}
+
+/// Itinerary of MOTIS
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MotisItinerary {
+    start_time: DateTimeUtc,
+    end_time: DateTimeUtc,
+    transfers: u32,
+}
+
+#[derive(Deserialize)]
+struct MotisResponse {
+    itineraries: Vec<MotisItinerary>,
+}
+
+/// Itinerary of OpenTripPlanner
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OtpItinerary {
+    start: Option<DateTime<FixedOffset>>,
+    end: Option<DateTime<FixedOffset>>,
+    number_of_transfers: u32,
+}
+
+#[derive(Deserialize)]
+struct OtpEdge {
+    node: OtpItinerary,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OtpPlan {
+    plan_connection: Option<OtpConnection>,
+}
+
+#[derive(Deserialize)]
+struct OtpConnection {
+    edges: Vec<OtpEdge>,
+}
+
+#[derive(Deserialize)]
+struct OtpResponse {
+    data: Option<OtpPlan>,
+}
+
+/// Configured router
+struct Backend {
+    provider: Provider,
+    url: String,
+    client: reqwest::Client,
+}
+
+/// Rocket state for suggesting connections of new rides with a routing service
+#[derive(Clone)]
+pub struct RoutePlanner {
+    backend: Option<Arc<Backend>>,
+}
+
+impl RoutePlanner {
+    /// Planner using [provider] at [url]. Routing is disabled without [provider].
+    pub fn new(provider: Option<Provider>, url: Option<String>) -> Result<Self, reqwest::Error> {
+        let (Some(provider), Some(url)) = (provider, url) else {
+            return Ok(Self { backend: None });
+        };
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(
+            Self {
+                backend: Some(Arc::new(
+                    Backend {
+                        provider,
+                        url: url.trim_end_matches('/').to_string(),
+                        client,
+                    }
+                )),
+            }
+        )
+    }
+
+    /// Whether a router is configured
+    pub fn is_enabled(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    /// Connections for [request], ordered by arrival. Place names are resolved with
+    /// [geocoder], which uses [db] as cache.
+    pub async fn suggest(
+        &self,
+        request: &SuggestRequest,
+        geocoder: &Geocoder,
+        db: &impl ConnectionTrait,
+    ) -> Result<Vec<Suggestion>, CurdError> {
+        let backend = self.backend.as_ref().ok_or(CurdError::NotFound)?;
+        let from = resolve(&request.location_from, geocoder, db).await?;
+        let to = resolve(&request.location_to, geocoder, db).await?;
+        let mut suggestions = backend.plan(from, to, request.journey_departure).await?;
+        suggestions.sort_by_key(|suggestion| suggestion.journey_arrival);
+        suggestions.truncate(ITINERARY_LIMIT);
+        Ok(suggestions)
+    }
+}
+
+impl Backend {
+    /// Itineraries from [from] to [to] departing at [departure] or later
+    async fn plan(&self, from: (f64, f64), to: (f64, f64), departure: DateTimeUtc) -> Result<Vec<Suggestion>, CurdError> {
+        let upstream_error = |error: &dyn Display| {
+            error!("Routing request to {} failed: {}", self.provider, error);
+            CurdError::Upstream(format!("Router {} failed", self.provider))
+        };
+        let request = match self.provider {
+            Provider::Motis => {
+                let url = reqwest::Url::parse_with_params(
+                    &format!("{}/api/v1/plan", self.url),
+                    [
+                        ("fromPlace", format!("{},{}", from.0, from.1)),
+                        ("toPlace", format!("{},{}", to.0, to.1)),
+                        ("time", departure.to_rfc3339()),
+                        ("numItineraries", ITINERARY_LIMIT.to_string()),
+                    ],
+                )
+                    .map_err(
+                        |error| {
+                            CurdError::InternalError(format!("Invalid router URL: {}", error))
+                        }
+                    )?;
+                self.client.get(url)
+            },
+            Provider::Otp => {
+                let body = json!({
+                    "query": OTP_QUERY,
+                    "variables": {
+                        "from": {"latitude": from.0, "longitude": from.1},
+                        "to": {"latitude": to.0, "longitude": to.1},
+                        "departure": departure.to_rfc3339(),
+                        "first": ITINERARY_LIMIT,
+                    },
+                });
+                self.client
+                    .post(format!("{}/otp/gtfs/v1", self.url))
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(body.to_string())
+            },
+        };
+        let body = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| upstream_error(&error))?
+            .text()
+            .await
+            .map_err(|error| upstream_error(&error))?;
+
+        let suggestions = match self.provider {
+            Provider::Motis => {
+                serde_json::from_str::<MotisResponse>(&body)
+                    .map_err(|error| upstream_error(&error))?
+                    .itineraries
+                    .into_iter()
+                    .map(|itinerary| Suggestion::new(itinerary.start_time, itinerary.end_time, itinerary.transfers))
+                    .collect()
+            },
+            Provider::Otp => {
+                serde_json::from_str::<OtpResponse>(&body)
+                    .map_err(|error| upstream_error(&error))?
+                    .data
+                    .and_then(|data| data.plan_connection)
+                    .ok_or_else(|| upstream_error(&body))?
+                    .edges
+                    .into_iter()
+                    .filter_map(|edge| Some(
+                        Suggestion::new(
+                            edge.node.start?.to_utc(),
+                            edge.node.end?.to_utc(),
+                            edge.node.number_of_transfers,
+                        )
+                    ))
+                    .collect()
+            },
+        };
+        Ok(suggestions)
+    }
+}
+
+/// Latitude and longitude of [location], given as coordinates or found with [geocoder]
+async fn resolve(location: &str, geocoder: &Geocoder, db: &impl ConnectionTrait) -> Result<(f64, f64), CurdError> {
+    if let Some(coordinates) = parse_coordinates(location) {
+        return Ok(coordinates);
+    }
+    if !geocoder.is_enabled() {
+        Err(CurdError::Unprocessable(format!("{} must be coordinates as <latitude>,<longitude> without geocoding", location)))?;
+    }
+    geocoder.search(location, db)
+        .await?
+        .into_iter()
+        .next()
+        .map(|place| (place.latitude, place.longitude))
+        .ok_or_else(|| CurdError::Unprocessable(format!("Cannot find {}", location)))
+}
+
+/// Coordinates in [location] formatted as `<latitude>,<longitude>`
+fn parse_coordinates(location: &str) -> Option<(f64, f64)> {
+    let (latitude, longitude) = location.split_once(',')?;
+    let latitude: f64 = latitude.trim().parse().ok()?;
+    let longitude: f64 = longitude.trim().parse().ok()?;
+    if (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude) {
+        Some((latitude, longitude))
+    } else {
+        None
+    }
+}
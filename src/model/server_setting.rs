@@ -0,0 +1,83 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use serde::{de::DeserializeOwned, Serialize};
+use sea_orm::{
+    prelude::*,
+    ActiveModelTrait,
+    IntoActiveModel,
+    NotSet,
+    Set,
+};
+use entity::server_setting;
+use super::error::CurdError;
+
+/// Key of the maintenance mode, see [super::maintenance::Maintenance]
+pub const MAINTENANCE: &str = "maintenance";
+
+/// Key of feature flag [name] switched at runtime, see [super::feature::FeatureFlags]
+pub fn feature(name: &str) -> String {
+    format!("feature.{}", name)
+}
+
+/// Setting stored under [key], `None` if it was never set
+pub async fn load<T: DeserializeOwned>(key: &str, db: &impl ConnectionTrait) -> Result<Option<T>, CurdError> {
+    let Some(setting) = find(key, db).await? else {
+        return Ok(None);
+    };
+    serde_json::from_str(&setting.value)
+        .map(Some)
+        .map_err(
+            |error| {
+                CurdError::InternalError(format!("Invalid server setting {}: {}", key, error))
+            }
+        )
+}
+
+/// Store [value] under [key], replacing the previous value
+pub async fn store<T: Serialize>(key: &str, value: &T, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let value = serde_json::to_string(value)
+        .map_err(
+            |error| {
+                CurdError::InternalError(error.to_string())
+            }
+        )?;
+    let model = match find(key, db).await? {
+        Some(setting) => {
+            let mut model = setting.into_active_model();
+            model.value = Set(value);
+            model
+        },
+        None => server_setting::ActiveModel {
+            id: NotSet,
+            key: Set(key.to_string()),
+            value: Set(value),
+            ..Default::default()
+        },
+    };
+    model.save(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(())
+}
+
+async fn find(key: &str, db: &impl ConnectionTrait) -> Result<Option<server_setting::Model>, CurdError> {
+    server_setting::Entity::find()
+        .filter(server_setting::Column::Key.eq(key))
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )
+}
@@ -10,29 +10,66 @@ use serde::{Deserialize, Serialize};
 use rocket_okapi::okapi::schemars;
 use sea_orm::{
     prelude::*,
+    Condition,
+    Iterable,
+    Order,
+    QueryOrder,
+    QuerySelect,
+    QueryTrait,
     Set,
+    Unchanged,
 };
+use sea_orm::sea_query::{Func, SimpleExpr};
 use rand;
 use uuid;
-use entity::tag_descriptor;
+use entity::ride;
+use entity::ride_tag;
+use entity::tag_descriptor::{self, Aggregation};
 use entity::tag_enum_option;
+use entity::timestamps;
+use super::audit::{record, Actor};
+use super::deleted::not_deleted;
 use super::error::CurdError;
+use super::event::{Action, Resource};
+use super::retry::retry;
+use super::last_modified::latest_change;
 use super::tag_option::TagOption;
 
 /// JSON structure
 #[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[schemars(example = "Tag::example")]
+#[serde(deny_unknown_fields)]
 pub struct Tag {
     #[serde(skip_deserializing)]
     id: u32,
+    #[schemars(schema_with = "tag_type_schema")]
     pub tag_type: String,
+    #[schemars(length(max = 64))]
     tag_key: String,
+    #[schemars(length(max = 200))]
     tag_name: Option<String>,
     #[serde(skip_deserializing)]
     tag_display_name: String,
     #[serde(skip_deserializing)]
     uuid: String,
+    #[schemars(length(max = 32))]
     pub unit: Option<String>,
+    #[schemars(length(max = 2000))]
     pub remarks: Option<String>,
+    /// Position of the tag in lists sorted by `order`
+    #[serde(default)]
+    pub order: u32,
+    /// How the values of a numeric tag are rolled up in statistics and reports. If not
+    /// set, amounts of money are summed and other tags are not rolled up.
+    #[serde(default)]
+    pub aggregation: Option<Aggregation>,
+    #[serde(skip_deserializing)]
+    created_at: DateTimeUtc,
+    #[serde(skip_deserializing)]
+    updated_at: DateTimeUtc,
+    /// Time of deletion, only set for deleted resources
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<DateTimeUtc>,
     #[serde(skip_deserializing)]
     options: Option<Vec<TagOption>>,
 }
@@ -51,17 +88,137 @@ impl From<tag_descriptor::Model> for Tag {
             uuid: model.uuid.to_string(),
             unit: model.unit,
             remarks: model.remarks,
+            order: model.order,
+            aggregation: model.aggregation,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            deleted_at: model.deleted_at,
             options: None,
         }
     }
 }
 
+/// Schema of [Tag::tag_type], listing the values of [tag_descriptor::TagType]
+fn tag_type_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+    schemars::schema::SchemaObject {
+        instance_type: Some(schemars::schema::InstanceType::String.into()),
+        enum_values: Some(
+            tag_descriptor::TagType::iter()
+                .map(|tag_type| serde_json::Value::String(tag_type.into()))
+                .collect()
+        ),
+        ..Default::default()
+    }.into()
+}
+
+/// Field tags are sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TagSortField {
+    /// Order of creation
+    #[default]
+    Id,
+    Key,
+    /// Display name, i.e. the name or else the key
+    Name,
+    CreatedAt,
+    Order,
+}
+
+/// Sort order of tag listings, see [TagSort::parse]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TagSort {
+    field: TagSortField,
+    descending: bool,
+}
+
+impl TagSort {
+    /// Sort order of the query parameter [sort], i.e. `key`, `name`, `created_at` or
+    /// `order`, prefixed with `-` for descending order. `None` if the field is unknown.
+    pub fn parse(sort: &str) -> Option<Self> {
+        let (field, descending) = match sort.trim().strip_prefix('-') {
+            Some(field) => (field, true),
+            None => (sort.trim(), false),
+        };
+        let field = match field {
+            "key" => TagSortField::Key,
+            "name" => TagSortField::Name,
+            "created_at" => TagSortField::CreatedAt,
+            "order" => TagSortField::Order,
+            _ => return None,
+        };
+        Some(Self {
+            field,
+            descending,
+        })
+    }
+
+    /// Sort [query] accordingly, ties by ID
+    fn apply(&self, query: Select<tag_descriptor::Entity>) -> Select<tag_descriptor::Entity> {
+        let order = if self.descending { Order::Desc } else { Order::Asc };
+        let query = match self.field {
+            TagSortField::Id => query,
+            TagSortField::Key => query.order_by(tag_descriptor::Column::TagKey, order.clone()),
+            TagSortField::Name => query.order_by(
+                SimpleExpr::from(Func::coalesce([
+                    Expr::col((tag_descriptor::Entity, tag_descriptor::Column::TagName)).into(),
+                    Expr::col((tag_descriptor::Entity, tag_descriptor::Column::TagKey)).into(),
+                ])),
+                order.clone(),
+            ),
+            TagSortField::CreatedAt => query.order_by(tag_descriptor::Column::CreatedAt, order.clone()),
+            TagSortField::Order => query.order_by(tag_descriptor::Column::Order, order.clone()),
+        };
+        query.order_by(tag_descriptor::Column::Id, order)
+    }
+}
+
 impl Tag {
+    /// Example for the API documentation
+    fn example() -> Self {
+        Self {
+            id: 1,
+            tag_type: tag_descriptor::TagType::Float.into(),
+            tag_key: "price".to_string(),
+            tag_name: Some("Price".to_string()),
+            tag_display_name: "Price".to_string(),
+            uuid: "0b6f4c1e-3c1a-4f55-9d0e-5a4c2f1b7e21".to_string(),
+            unit: Some("EUR".to_string()),
+            remarks: None,
+            order: 0,
+            aggregation: Some(Aggregation::Sum),
+            created_at: DateTimeUtc::from_timestamp(1740819000, 0).unwrap_or_default(),
+            updated_at: DateTimeUtc::from_timestamp(1740819000, 0).unwrap_or_default(),
+            deleted_at: None,
+            options: None,
+        }
+    }
+
     /// Getter for [id]
     pub fn id(&self) -> u32 {
         self.id
     }
 
+    /// Whether the tag holds amounts of money, i.e. is numeric with a currency as unit,
+    /// like `EUR` or `€`
+    pub fn is_money(&self) -> bool {
+        let numeric = self.tag_type == "float" || self.tag_type == "integer";
+        let currency = self.unit.as_deref().is_some_and(|unit| {
+            (unit.len() == 3 && unit.chars().all(|c| c.is_ascii_uppercase()))
+                || ["€", "$", "£", "¥"].contains(&unit)
+        });
+        numeric && currency
+    }
+
+    /// How the values of the tag are rolled up, i.e. [aggregation] or else the sum for
+    /// amounts of money
+    pub fn aggregation(&self) -> Aggregation {
+        match self.aggregation {
+            Some(aggregation) => aggregation,
+            None if self.is_money() => Aggregation::Sum,
+            None => Aggregation::None,
+        }
+    }
+
     /// Getter for [tag_key]
     pub fn tag_key(&self) -> &String {
         &self.tag_key
@@ -99,6 +256,17 @@ impl Tag {
         &self.uuid
     }
 
+    /// Getter for [options]
+    pub fn options(&self) -> &Option<Vec<TagOption>> {
+        &self.options
+    }
+
+    /// Same tag without the options array
+    pub fn without_options(mut self) -> Self {
+        self.options = None;
+        self
+    }
+
     /// Checks if [option_id] is in options array
     pub fn has_option_id(&self, option_id: u32) -> bool {
         match &self.options {
@@ -109,7 +277,8 @@ impl Tag {
         }
     }
 
-    fn from_models(tag: tag_descriptor::Model, options: Vec<tag_enum_option::Model>) -> Self {
+    /// Tag of [tag] with [options] embedded if it is an enum tag
+    pub(super) fn from_models(tag: tag_descriptor::Model, options: Vec<tag_enum_option::Model>) -> Self {
         let mut tag = Self::from(tag);
         if tag.tag_type == "enum" {
             tag.options = Some(
@@ -125,44 +294,91 @@ impl Tag {
         tag
     }
 
-    /// Fetch all instances belonging to [user_id]
-    pub async fn find_all(user_id: u32, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
-        let models = tag_descriptor::Entity::find()
-            .find_with_related(tag_enum_option::Entity)
+    /// Fetch all instances belonging to [user_id]. The options are only fetched and
+    /// embedded if [with_options] is set.
+    pub async fn find_all(user_id: u32, with_options: bool, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        Self::find_all_sorted(user_id, TagSort::default(), with_options, include_deleted, db).await
+    }
+
+    /// Fetch all instances belonging to [user_id] in the order of [sort]. The options are
+    /// only fetched and embedded if [with_options] is set.
+    pub async fn find_all_sorted(user_id: u32, sort: TagSort, with_options: bool, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        let query = tag_descriptor::Entity::find()
             .filter(tag_descriptor::Column::UserId.eq(user_id))
-            .filter(tag_descriptor::Column::DeletedAt.is_null())
-            .all(db)
-            .await
-            .map_err(
-                |error| {
-                    CurdError::DbErr(error)
-                }
-            )?;
-        let mut result = Vec::with_capacity(models.len());
-        for (tag, options) in models {
-            result.push(Self::from_models(tag, options));
-        }
-        Ok(result)
+            .filter(not_deleted(tag_descriptor::Column::DeletedAt, include_deleted));
+        Self::fetch(sort.apply(query), with_options, db).await
     }
 
-    /// Find instance by [id].
-    pub async fn find_by_id(id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
-        let mut model = tag_descriptor::Entity::find()
-            .find_with_related(tag_enum_option::Entity)
+    /// Count all instances belonging to [user_id].
+    pub async fn count_all(user_id: u32, include_deleted: bool, db: &impl ConnectionTrait) -> Result<u64, CurdError> {
+        let statement = tag_descriptor::Entity::find()
+            .filter(tag_descriptor::Column::UserId.eq(user_id))
+            .filter(not_deleted(tag_descriptor::Column::DeletedAt, include_deleted));
+        Ok(
+            retry(|| statement.clone().count(db))
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?
+        )
+    }
+
+    /// Fetch the instances with [ids] belonging to [user_id] in the order of [sort]. IDs
+    /// which do not exist or belong to another user are left out.
+    pub async fn find_by_ids(user_id: u32, ids: &[u32], sort: TagSort, with_options: bool, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        let query = tag_descriptor::Entity::find()
+            .filter(tag_descriptor::Column::UserId.eq(user_id))
+            .filter(tag_descriptor::Column::Id.is_in(ids.iter().copied()))
+            .filter(not_deleted(tag_descriptor::Column::DeletedAt, include_deleted));
+        Self::fetch(sort.apply(query), with_options, db).await
+    }
+
+    /// Find instance by [id]. The options are only fetched and embedded if
+    /// [with_options] is set.
+    pub async fn find_by_id(id: u32, with_options: bool, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        let query = tag_descriptor::Entity::find()
             .filter(tag_descriptor::Column::Id.eq(id))
-            .filter(tag_descriptor::Column::DeletedAt.is_null())
-            .all(db)
-            .await
-            .map_err(
-                |error| {
-                    CurdError::DbErr(error)
-                }
-            )?;
-        match model.pop() {
-            Some((tag, options)) => Ok(Self::from_models(tag, options)),
+            .filter(not_deleted(tag_descriptor::Column::DeletedAt, include_deleted));
+        match Self::fetch(query, with_options, db).await?.pop() {
+            Some(tag) => Ok(tag),
             None => Err(CurdError::NotFound)?,
         }
     }
+
+    /// Fetch the tags selected by [query], with their options sorted by their order if
+    /// [with_options] is set
+    async fn fetch(query: Select<tag_descriptor::Entity>, with_options: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        if with_options {
+            let statement = query
+                .find_with_related(tag_enum_option::Entity)
+                .order_by_asc(tag_enum_option::Column::Order)
+                .order_by_asc(tag_enum_option::Column::Id);
+            let models = retry(|| statement.clone().all(db))
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            Ok(
+                models
+                    .into_iter()
+                    .map(|(tag, options)| Self::from_models(tag, options))
+                    .collect()
+            )
+        } else {
+            let models = retry(|| query.clone().all(db))
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?;
+            Ok(models.into_iter().map(Self::from).collect())
+        }
+    }
 }
 
 /// Check if [tag_id] belongs to [user_id]. Use this to restrict
@@ -170,13 +386,14 @@ impl Tag {
 pub async fn is_owner(
     tag_id: u32,
     user_id: u32,
+    include_deleted: bool,
     db: &impl ConnectionTrait
 ) -> Result<(), CurdError> {
-    let rows = tag_descriptor::Entity::find()
+    let statement = tag_descriptor::Entity::find()
         .filter(tag_descriptor::Column::Id.eq(tag_id))
         .filter(tag_descriptor::Column::UserId.eq(user_id))
-        .filter(tag_descriptor::Column::DeletedAt.is_null())
-        .count(db)
+        .filter(not_deleted(tag_descriptor::Column::DeletedAt, include_deleted));
+    let rows = retry(|| statement.clone().count(db))
         .await
         .map_err(
             |error| {
@@ -190,6 +407,43 @@ pub async fn is_owner(
     }
 }
 
+/// Time of the last change of the tags of [user_id], or only of tag [tag_id], including
+/// their options. `None` if there are no tags.
+pub async fn last_modified(
+    user_id: u32,
+    tag_id: Option<u32>,
+    db: &impl ConnectionTrait
+) -> Result<Option<DateTimeUtc>, CurdError> {
+    let mut tags = tag_descriptor::Entity::find()
+        .filter(tag_descriptor::Column::UserId.eq(user_id));
+    let mut options = tag_enum_option::Entity::find()
+        .inner_join(tag_descriptor::Entity)
+        .filter(tag_descriptor::Column::UserId.eq(user_id));
+    if let Some(tag_id) = tag_id {
+        tags = tags.filter(tag_descriptor::Column::Id.eq(tag_id));
+        options = options.filter(tag_enum_option::Column::TagDescriptorId.eq(tag_id));
+    }
+    let tags = latest_change(tags, tag_descriptor::Column::UpdatedAt, tag_descriptor::Column::DeletedAt, db).await?;
+    let options = latest_change(options, tag_enum_option::Column::UpdatedAt, tag_enum_option::Column::DeletedAt, db).await?;
+    Ok(tags.max(options))
+}
+
+/// Rocket state with the key of the tag holding the price of a ride. The VAT,
+/// mobility-budget and travel-log reports add up its values as amounts of money, so it
+/// cannot be aggregated other than by sum.
+#[derive(Debug, Clone)]
+pub struct PriceTag {
+    key: String,
+}
+
+impl PriceTag {
+    pub fn new(key: String) -> Self {
+        Self {
+            key,
+        }
+    }
+}
+
 /// Builder for creating or updating a model (in the database)
 pub struct CreateUpdateBuilder<T: TryInto<tag_descriptor::TagType>> where T::Error: ToString {
     pub tag_type: T,
@@ -197,6 +451,14 @@ pub struct CreateUpdateBuilder<T: TryInto<tag_descriptor::TagType>> where T::Err
     pub tag_name: Option<String>,
     pub unit: Option<String>,
     pub remarks: Option<String>,
+    /// Position in lists sorted by `order`
+    pub order: u32,
+    /// How values are rolled up, see [Tag::aggregation]
+    pub aggregation: Option<String>,
+    /// UUID of the inserted instance, random if not set
+    uuid: Option<Uuid>,
+    /// Key of the price tag, see [PriceTag]
+    price_tag: Option<String>,
 }
 
 impl CreateUpdateBuilder<String> {
@@ -208,6 +470,10 @@ impl CreateUpdateBuilder<String> {
             tag_name: model.tag_name,
             unit: model.unit,
             remarks: model.remarks,
+            order: model.order,
+            aggregation: model.aggregation.map(Into::into),
+            uuid: None,
+            price_tag: None,
         }
     }
 }
@@ -227,24 +493,53 @@ impl<T: TryInto<tag_descriptor::TagType>> CreateUpdateBuilder<T> where T::Error:
             tag_name,
             unit,
             remarks,
+            order: 0,
+            aggregation: None,
+            uuid: None,
+            price_tag: None,
         }
     }
 
+    /// Position in lists sorted by `order`
+    pub fn with_order(mut self, order: u32) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// How values are rolled up, one of [Aggregation]
+    pub fn with_aggregation(mut self, aggregation: Option<String>) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Reject aggregations other than the sum if the tag is [price_tag]
+    pub fn with_price_tag(mut self, price_tag: &PriceTag) -> Self {
+        self.price_tag = Some(price_tag.key.clone());
+        self
+    }
+
+    /// Keep [uuid] on insert instead of generating a random one, e.g. when importing a
+    /// tag. Ignored on update.
+    pub fn with_uuid(mut self, uuid: Uuid) -> Self {
+        self.uuid = Some(uuid);
+        self
+    }
+
     /// Insert into database and return the new instance. It will belong to [user_id].
     pub async fn insert(
         self,
         user_id: u32,
+        actor: &Actor,
         db: &impl ConnectionTrait,
     ) -> Result<Tag, CurdError> {
-        let uuid_val = uuid::Builder::from_random_bytes(rand::random()).into_uuid();
+        let uuid_val = self.uuid.unwrap_or_else(|| uuid::Builder::from_random_bytes(rand::random()).into_uuid());
         let tag_type: tag_descriptor::TagType = match self.tag_type.try_into() {
             Ok(value) => value,
             Err(e) => Err(CurdError::DeserializationError(e.to_string()))?,
         };
+        let aggregation = parse_aggregation(self.aggregation, &tag_type, self.price_tag.as_ref() == Some(&self.tag_key))?;
 
         let model = tag_descriptor::ActiveModel {
-            created_at: Set(chrono::Utc::now()),
-            updated_at: Set(chrono::Utc::now()),
             user_id: Set(user_id),
             tag_type: Set(tag_type.clone()),
             tag_key: Set(self.tag_key.clone()),
@@ -252,20 +547,23 @@ impl<T: TryInto<tag_descriptor::TagType>> CreateUpdateBuilder<T> where T::Error:
             uuid: Set(uuid_val.clone()),
             unit: Set(self.unit.clone()),
             remarks: Set(self.remarks.clone()),
+            order: Set(self.order),
+            aggregation: Set(aggregation),
             ..Default::default()
         };
-        let result = tag_descriptor::Entity::insert(model)
-            .exec(db)
+        let result = model
+            .insert(db)
             .await
             .map_err(
                 |error| {
                     CurdError::DbErr(error)
                 }
             )?;
+        record(actor, Resource::Tag, result.id, Action::Created, None, Some(&result), db).await?;
 
         Ok(
             Tag {
-                id: result.last_insert_id,
+                id: result.id,
                 tag_type: tag_type.into(),
                 tag_display_name: match &self.tag_name {
                     Some(value) => value.clone(),
@@ -276,6 +574,11 @@ impl<T: TryInto<tag_descriptor::TagType>> CreateUpdateBuilder<T> where T::Error:
                 uuid: uuid_val.to_string(),
                 unit: self.unit,
                 remarks: self.remarks,
+                order: self.order,
+                aggregation,
+                created_at: result.created_at,
+                updated_at: result.updated_at,
+                deleted_at: result.deleted_at,
                 options: None,
             }
         )
@@ -285,44 +588,81 @@ impl<T: TryInto<tag_descriptor::TagType>> CreateUpdateBuilder<T> where T::Error:
     pub async fn update(
         self,
         id: u32,
+        actor: &Actor,
         db: &impl ConnectionTrait,
     ) -> Result<(), CurdError> {
-        let result = tag_descriptor::Entity::update_many()
-            .col_expr(tag_descriptor::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
-            .col_expr(
-                tag_descriptor::Column::TagType,
-                Expr::value(
-                    match self.tag_type.try_into() {
-                        Ok(value) => value,
-                        Err(e) => Err(CurdError::DeserializationError(e.to_string()))?,
-                    }
-                )
-            )
-            .col_expr(tag_descriptor::Column::TagKey, Expr::value(self.tag_key.clone()))
-            .col_expr(tag_descriptor::Column::TagName, Expr::value(self.tag_name.clone()))
-            .col_expr(tag_descriptor::Column::Unit, Expr::value(self.unit.clone()))
-            .col_expr(tag_descriptor::Column::Remarks, Expr::value(self.remarks.clone()))
-            .filter(tag_descriptor::Column::Id.eq(id))
-            .filter(tag_descriptor::Column::DeletedAt.is_null())
-            .exec(db)
+        let tag_type: tag_descriptor::TagType = match self.tag_type.try_into() {
+            Ok(value) => value,
+            Err(e) => Err(CurdError::DeserializationError(e.to_string()))?,
+        };
+        let aggregation = parse_aggregation(self.aggregation, &tag_type, self.price_tag.as_ref() == Some(&self.tag_key))?;
+        let before = tag_descriptor::Entity::find_by_id(id)
+            .one(db)
             .await
             .map_err(
                 |error| {
                     CurdError::DbErr(error)
                 }
+            )?
+            .ok_or(CurdError::NotFound)?;
+        let model = tag_descriptor::ActiveModel {
+            id: Unchanged(id),
+            tag_type: Set(tag_type),
+            tag_key: Set(self.tag_key),
+            tag_name: Set(self.tag_name),
+            unit: Set(self.unit),
+            remarks: Set(self.remarks),
+            order: Set(self.order),
+            aggregation: Set(aggregation),
+            ..Default::default()
+        };
+        let after = model
+            .update(db)
+            .await
+            .map_err(
+                |error| {
+                    match error {
+                        DbErr::RecordNotUpdated => CurdError::NotFound,
+                        error => CurdError::DbErr(error),
+                    }
+                }
             )?;
-        if result.rows_affected >= 1 {
-            Ok(())
-        } else {
-            Err(CurdError::NotFound)
-        }
+        record(actor, Resource::Tag, id, Action::Updated, Some(&before), Some(&after), db).await
+    }
+}
+
+/// [aggregation] of a tag of [tag_type]. Only numeric tags can be rolled up, and the
+/// price tag only by sum, see [PriceTag].
+fn parse_aggregation(aggregation: Option<String>, tag_type: &tag_descriptor::TagType, is_price: bool) -> Result<Option<Aggregation>, CurdError> {
+    let Some(aggregation) = aggregation else {
+        return Ok(None);
+    };
+    let aggregation = Aggregation::try_from(aggregation)
+        .map_err(|e| CurdError::DeserializationError(e.to_string()))?;
+    let numeric = matches!(tag_type, tag_descriptor::TagType::Float | tag_descriptor::TagType::Integer);
+    if !numeric && aggregation != Aggregation::None {
+        Err(CurdError::Unprocessable("Only integer and float tags can be aggregated".to_string()))?;
+    }
+    if is_price && aggregation != Aggregation::Sum {
+        Err(CurdError::Unprocessable("The price tag is added up in reports and can only be aggregated by sum".to_string()))?;
     }
+    Ok(Some(aggregation))
 }
 
 /// Remove instance by [id].
-pub async fn remove(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+pub async fn remove(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let before = tag_descriptor::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    let deleted_at = chrono::Utc::now();
     let result = tag_descriptor::Entity::update_many()
-        .col_expr(tag_descriptor::Column::DeletedAt, Expr::value(chrono::Utc::now()))
+        .col_expr(tag_descriptor::Column::DeletedAt, Expr::value(deleted_at))
         .filter(tag_descriptor::Column::Id.eq(id))
         .filter(tag_descriptor::Column::DeletedAt.is_null())
         .exec(db)
@@ -333,7 +673,101 @@ pub async fn remove(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError>
             }
         )?;
     if result.rows_affected >= 1 {
-        Ok(())
+        let after = tag_descriptor::Model {
+            deleted_at: Some(deleted_at),
+            ..before.clone()
+        };
+        record(actor, Resource::Tag, id, Action::Deleted, Some(&before), Some(&after), db).await
+    } else {
+        Err(CurdError::NotFound)
+    }
+}
+
+/// Undo the soft deletion of tag [id]. Its options and ride tags, which are kept on
+/// deletion, are available again. Fails with a conflict if the tag is not deleted or its
+/// key is used by another tag of the user meanwhile.
+pub async fn restore(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let before = tag_descriptor::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    if before.deleted_at.is_none() {
+        Err(CurdError::Conflict("Tag is not deleted".to_string()))?;
+    }
+    let taken = tag_descriptor::Entity::find()
+        .filter(tag_descriptor::Column::UserId.eq(before.user_id))
+        .filter(tag_descriptor::Column::TagKey.eq(before.tag_key.clone()))
+        .filter(tag_descriptor::Column::DeletedAt.is_null())
+        .count(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if taken > 0 {
+        Err(CurdError::Conflict(format!("Tag key {} is used by another tag", before.tag_key)))?;
+    }
+    let updated_at = timestamps::now();
+    let result = tag_descriptor::Entity::update_many()
+        .col_expr(tag_descriptor::Column::DeletedAt, Expr::value(Option::<DateTimeUtc>::None))
+        .col_expr(tag_descriptor::Column::UpdatedAt, Expr::value(updated_at))
+        .filter(tag_descriptor::Column::Id.eq(id))
+        .filter(tag_descriptor::Column::DeletedAt.is_not_null())
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if result.rows_affected >= 1 {
+        let after = tag_descriptor::Model {
+            deleted_at: None,
+            updated_at,
+            ..before.clone()
+        };
+        record(actor, Resource::Tag, id, Action::Updated, Some(&before), Some(&after), db).await
+    } else {
+        Err(CurdError::NotFound)
+    }
+}
+
+/// Delete tag [id] from the database, also if it is soft-deleted. Its options and the
+/// links to rides are deleted by the foreign key cascade, and these rides are marked as
+/// updated.
+pub async fn purge(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let before = tag_descriptor::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    let linked_rides = ride_tag::Entity::find()
+        .select_only()
+        .column(ride_tag::Column::RideId)
+        .filter(ride_tag::Column::TagDescriptorId.eq(id))
+        .into_query();
+    super::ride::touch(Condition::all().add(ride::Column::Id.in_subquery(linked_rides)), db).await?;
+    let result = tag_descriptor::Entity::delete_many()
+        .filter(tag_descriptor::Column::Id.eq(id))
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if result.rows_affected >= 1 {
+        record(actor, Resource::Tag, id, Action::Deleted, Some(&before), None, db).await
     } else {
         Err(CurdError::NotFound)
     }
@@ -6,33 +6,59 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+use std::collections::HashSet;
 use serde::{Deserialize, Serialize};
 use rocket_okapi::okapi::schemars;
 use sea_orm::{
     prelude::*,
+    Condition,
+    QueryOrder,
+    QuerySelect,
+    QueryTrait,
     Set,
     NotSet,
+    Unchanged,
 };
 use rand;
 use uuid;
+use entity::ride;
+use entity::ride_tag;
 use entity::tag_descriptor;
 use entity::tag_enum_option;
+use entity::timestamps;
+use super::audit::{record, Actor};
+use super::counted::find_page_with_count;
+use super::deleted::not_deleted;
 use super::error::CurdError;
+use super::event::{Action, Resource};
+use super::retry::retry;
+use super::last_modified::latest_change;
 
 /// JSON structure
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[schemars(example = "TagOption::example")]
+#[serde(deny_unknown_fields)]
 pub struct TagOption {
     #[serde(skip_deserializing)]
     id: u32,
     #[serde(skip_deserializing)]
     tag_id: u32,
     pub order: u32,
+    #[schemars(length(max = 200))]
     pub value: String,
     #[serde(skip_deserializing)]
     uuid: String,
+    #[schemars(length(max = 200))]
     pub name: Option<String>,
     #[serde(skip_deserializing)]
     display_name: String,
+    #[serde(skip_deserializing)]
+    created_at: DateTimeUtc,
+    #[serde(skip_deserializing)]
+    updated_at: DateTimeUtc,
+    /// Time of deletion, only set for deleted resources
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<DateTimeUtc>,
 }
 
 impl From<tag_enum_option::Model> for TagOption {
@@ -48,11 +74,30 @@ impl From<tag_enum_option::Model> for TagOption {
             value: model.value,
             uuid: model.uuid.to_string(),
             name: model.name,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            deleted_at: model.deleted_at,
         }
     }
 }
 
 impl TagOption {
+    /// Example for the API documentation
+    fn example() -> Self {
+        Self {
+            id: 1,
+            tag_id: 2,
+            order: 0,
+            value: "second".to_string(),
+            uuid: "5d0e3b8a-6f0e-4a8e-a3c4-2b1f9e7d6c50".to_string(),
+            name: Some("2nd class".to_string()),
+            display_name: "2nd class".to_string(),
+            created_at: DateTimeUtc::from_timestamp(1740819000, 0).unwrap_or_default(),
+            updated_at: DateTimeUtc::from_timestamp(1740819000, 0).unwrap_or_default(),
+            deleted_at: None,
+        }
+    }
+
     /// Getter for [id]
     pub fn id(&self) -> u32 {
         self.id
@@ -68,12 +113,19 @@ impl TagOption {
         &self.uuid
     }
 
-    /// Fetch all instances of parent [tag_id].
-    pub async fn find_all(tag_id: u32, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
-        let models = tag_enum_option::Entity::find()
+    /// Getter for [display_name]
+    pub fn display_name(&self) -> &String {
+        &self.display_name
+    }
+
+    /// Fetch all instances of parent [tag_id], sorted by their order.
+    pub async fn find_all(tag_id: u32, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        let statement = tag_enum_option::Entity::find()
             .filter(tag_enum_option::Column::TagDescriptorId.eq(tag_id))
-            .filter(tag_enum_option::Column::DeletedAt.is_null())
-            .all(db)
+            .filter(not_deleted(tag_enum_option::Column::DeletedAt, include_deleted))
+            .order_by_asc(tag_enum_option::Column::Order)
+            .order_by_asc(tag_enum_option::Column::Id);
+        let models = retry(|| statement.clone().all(db))
             .await
             .map_err(
                 |error| {
@@ -87,12 +139,24 @@ impl TagOption {
         Ok(v)
     }
 
+    /// Fetch page [page] of [size] options of [tag_id], sorted by their order, and the
+    /// total number of options
+    pub async fn find_all_paginated(tag_id: u32, include_deleted: bool, db: &impl ConnectionTrait, page: u64, size: u64) -> Result<(Vec<Self>, u64), CurdError> {
+        let query = tag_enum_option::Entity::find()
+            .filter(tag_enum_option::Column::TagDescriptorId.eq(tag_id))
+            .filter(not_deleted(tag_enum_option::Column::DeletedAt, include_deleted))
+            .order_by_asc(tag_enum_option::Column::Order)
+            .order_by_asc(tag_enum_option::Column::Id);
+        let (models, count) = find_page_with_count(query, page, size, db).await?;
+        Ok((models.into_iter().map(Self::from).collect(), count))
+    }
+
     /// Find instance by [id].
-    pub async fn find_by_id(id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
-        let model = tag_enum_option::Entity::find()
+    pub async fn find_by_id(id: u32, include_deleted: bool, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        let statement = tag_enum_option::Entity::find()
             .filter(tag_enum_option::Column::Id.eq(id))
-            .filter(tag_enum_option::Column::DeletedAt.is_null())
-            .one(db)
+            .filter(not_deleted(tag_enum_option::Column::DeletedAt, include_deleted));
+        let model = retry(|| statement.clone().one(db))
             .await
             .map_err(
                 |error| {
@@ -111,15 +175,16 @@ impl TagOption {
 pub async fn is_owner(
     tag_option_id: u32,
     user_id: u32,
+    include_deleted: bool,
     db: &impl ConnectionTrait
 ) -> Result<(), CurdError> {
-    let rows = tag_enum_option::Entity::find()
+    let statement = tag_enum_option::Entity::find()
         .find_also_related(tag_descriptor::Entity)
         .filter(tag_enum_option::Column::Id.eq(tag_option_id))
-        .filter(tag_enum_option::Column::DeletedAt.is_null())
+        .filter(not_deleted(tag_enum_option::Column::DeletedAt, include_deleted))
         .filter(tag_descriptor::Column::UserId.eq(user_id))
-        .filter(tag_descriptor::Column::DeletedAt.is_null())
-        .count(db)
+        .filter(not_deleted(tag_descriptor::Column::DeletedAt, include_deleted));
+    let rows = retry(|| statement.clone().count(db))
         .await
         .map_err(
             |error| {
@@ -133,11 +198,105 @@ pub async fn is_owner(
     }
 }
 
+/// Time of the last change of the options of tag [tag_id], or only of option
+/// [tag_option_id]. `None` if there are no options.
+pub async fn last_modified(
+    tag_id: u32,
+    tag_option_id: Option<u32>,
+    db: &impl ConnectionTrait
+) -> Result<Option<DateTimeUtc>, CurdError> {
+    let mut options = tag_enum_option::Entity::find()
+        .filter(tag_enum_option::Column::TagDescriptorId.eq(tag_id));
+    if let Some(tag_option_id) = tag_option_id {
+        options = options.filter(tag_enum_option::Column::Id.eq(tag_option_id));
+    }
+    latest_change(options, tag_enum_option::Column::UpdatedAt, tag_enum_option::Column::DeletedAt, db).await
+}
+
+/// Option of an import list, see [parse_list]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedOption {
+    pub value: String,
+    pub name: Option<String>,
+}
+
+/// Parse a list of options with one option per line, either `value` or `value;name`.
+/// Values and names may be quoted as in CSV, empty lines are skipped.
+pub fn parse_list(list: &str) -> Result<Vec<ImportedOption>, CurdError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(list.as_bytes());
+    let mut options = Vec::new();
+    // Empty lines are not counted, so entries are numbered instead of lines
+    for (entry, record) in reader.records().enumerate() {
+        let record = record
+            .map_err(
+                |error| {
+                    CurdError::DeserializationError(error.to_string())
+                }
+            )?;
+        let entry = entry + 1;
+        let option = match (record.get(0), record.get(1), record.len()) {
+            (Some(""), _, 1) => continue,
+            (Some(""), _, _) => Err(CurdError::Unprocessable(format!("Entry {} has no value", entry)))?,
+            (Some(value), None, 1) => ImportedOption {
+                value: value.to_string(),
+                name: None,
+            },
+            (Some(value), Some(name), 2) => ImportedOption {
+                value: value.to_string(),
+                name: Some(name.to_string()).filter(|name| !name.is_empty()),
+            },
+            _ => Err(CurdError::Unprocessable(format!("Entry {} has more than value and name", entry)))?,
+        };
+        options.push(option);
+    }
+    Ok(options)
+}
+
+/// Create [options] for tag [tag_id], appended after the existing options in the order
+/// of the list. Fails without inserting if the list is empty or a value is repeated or
+/// is already an option of the tag. Run it in a transaction, so that the options are
+/// created completely or not at all.
+pub async fn import(
+    tag_id: u32,
+    options: Vec<ImportedOption>,
+    actor: &Actor,
+    db: &impl ConnectionTrait,
+) -> Result<Vec<TagOption>, CurdError> {
+    if options.is_empty() {
+        Err(CurdError::Unprocessable("List has no options".to_string()))?;
+    }
+    let existing = TagOption::find_all(tag_id, false, db).await?;
+    let mut values: HashSet<&str> = existing.iter().map(|option| option.value.as_str()).collect();
+    for option in &options {
+        if !values.insert(&option.value) {
+            Err(CurdError::Unprocessable(format!("Option {} exists already", option.value)))?;
+        }
+    }
+
+    let first_order = existing.iter().map(|option| option.order + 1).max().unwrap_or(0);
+    let mut created = Vec::with_capacity(options.len());
+    for (order, option) in (first_order..).zip(options) {
+        created.push(
+            CreateUpdateBuilder::new(order, option.value, option.name)
+                .insert(tag_id, actor, db)
+                .await?
+        );
+    }
+    Ok(created)
+}
+
 /// Builder for creating or updating a model (in the database)
 pub struct CreateUpdateBuilder {
     pub order: u32,
     pub value: String,
     pub name: Option<String>,
+    /// UUID of the inserted instance, random if not set
+    uuid: Option<Uuid>,
 }
 
 impl CreateUpdateBuilder {
@@ -151,6 +310,7 @@ impl CreateUpdateBuilder {
             order,
             value,
             name,
+            uuid: None,
         }
     }
 
@@ -160,21 +320,30 @@ impl CreateUpdateBuilder {
             order: model.order,
             value: model.value,
             name: model.name,
+            uuid: None,
         }
     }
 
+    /// Keep [uuid] on insert instead of generating a random one, e.g. when importing an
+    /// option. Ignored on update.
+    pub fn with_uuid(mut self, uuid: Uuid) -> Self {
+        self.uuid = Some(uuid);
+        self
+    }
+
     /// Insert into database and return the new instance. It will be child of [tag_id].
     pub async fn insert(
         self,
         tag_id: u32,
+        actor: &Actor,
         db: &impl ConnectionTrait,
     ) -> Result<TagOption, CurdError> {
-        let uuid_val = uuid::Builder::from_random_bytes(rand::random()).into_uuid();
+        let uuid_val = self.uuid.unwrap_or_else(|| uuid::Builder::from_random_bytes(rand::random()).into_uuid());
 
         let model = tag_enum_option::ActiveModel {
             id: NotSet,
-            created_at: Set(chrono::Utc::now()),
-            updated_at: Set(chrono::Utc::now()),
+            created_at: NotSet,
+            updated_at: NotSet,
             deleted_at: NotSet,
             tag_descriptor_id: Set(tag_id),
             order: Set(self.order),
@@ -182,18 +351,19 @@ impl CreateUpdateBuilder {
             uuid: Set(uuid_val.clone()),
             name: Set(self.name.clone()),
         };
-        let result = tag_enum_option::Entity::insert(model)
-            .exec(db)
+        let result = model
+            .insert(db)
             .await
             .map_err(
                 |error| {
                     CurdError::DbErr(error)
                 }
             )?;
+        record(actor, Resource::TagOption, result.id, Action::Created, None, Some(&result), db).await?;
 
         Ok(
             TagOption {
-                id: result.last_insert_id,
+                id: result.id,
                 tag_id,
                 order: self.order,
                 display_name: match &self.name {
@@ -203,6 +373,9 @@ impl CreateUpdateBuilder {
                 value: self.value,
                 uuid: uuid_val.to_string(),
                 name: self.name,
+                created_at: result.created_at,
+                updated_at: result.updated_at,
+                deleted_at: result.deleted_at,
             }
         )
     }
@@ -211,34 +384,54 @@ impl CreateUpdateBuilder {
     pub async fn update(
         self,
         id: u32,
+        actor: &Actor,
         db: &impl ConnectionTrait,
     ) -> Result<(), CurdError> {
-        let result = tag_enum_option::Entity::update_many()
-            .col_expr(tag_enum_option::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
-            .col_expr(tag_enum_option::Column::Order, Expr::value(self.order))
-            .col_expr(tag_enum_option::Column::Value, Expr::value(self.value))
-            .col_expr(tag_enum_option::Column::Name, Expr::value(self.name))
-            .filter(tag_enum_option::Column::Id.eq(id))
-            .filter(tag_enum_option::Column::DeletedAt.is_null())
-            .exec(db)
+        let before = tag_enum_option::Entity::find_by_id(id)
+            .one(db)
             .await
             .map_err(
                 |error| {
                     CurdError::DbErr(error)
                 }
+            )?
+            .ok_or(CurdError::NotFound)?;
+        let model = tag_enum_option::ActiveModel {
+            id: Unchanged(id),
+            order: Set(self.order),
+            value: Set(self.value),
+            name: Set(self.name),
+            ..Default::default()
+        };
+        let after = model
+            .update(db)
+            .await
+            .map_err(
+                |error| {
+                    match error {
+                        DbErr::RecordNotUpdated => CurdError::NotFound,
+                        error => CurdError::DbErr(error),
+                    }
+                }
             )?;
-        if result.rows_affected >= 1 {
-            Ok(())
-        } else {
-            Err(CurdError::NotFound)
-        }
+        record(actor, Resource::TagOption, id, Action::Updated, Some(&before), Some(&after), db).await
     }
 }
 
 /// Remove instance by [id].
-pub async fn remove(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+pub async fn remove(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let before = tag_enum_option::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    let deleted_at = chrono::Utc::now();
     let result = tag_enum_option::Entity::update_many()
-        .col_expr(tag_enum_option::Column::DeletedAt, Expr::value(chrono::Utc::now()))
+        .col_expr(tag_enum_option::Column::DeletedAt, Expr::value(deleted_at))
         .filter(tag_enum_option::Column::Id.eq(id))
         .filter(tag_enum_option::Column::DeletedAt.is_null())
         .exec(db)
@@ -249,8 +442,118 @@ pub async fn remove(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError>
             }
         )?;
     if result.rows_affected >= 1 {
-        Ok(())
+        let after = tag_enum_option::Model {
+            deleted_at: Some(deleted_at),
+            ..before.clone()
+        };
+        record(actor, Resource::TagOption, id, Action::Deleted, Some(&before), Some(&after), db).await
     } else {
         Err(CurdError::NotFound)
     }
 }
+
+/// Undo the soft deletion of option [id]. Fails with a conflict if the option is not
+/// deleted, its tag is deleted or another option of the tag has the same value meanwhile.
+pub async fn restore(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let (before, tag) = tag_enum_option::Entity::find_by_id(id)
+        .find_also_related(tag_descriptor::Entity)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    if before.deleted_at.is_none() {
+        Err(CurdError::Conflict("Option is not deleted".to_string()))?;
+    }
+    if tag.is_none_or(|tag| tag.deleted_at.is_some()) {
+        Err(CurdError::Conflict("The tag of the option is deleted, restore it first".to_string()))?;
+    }
+    let taken = tag_enum_option::Entity::find()
+        .filter(tag_enum_option::Column::TagDescriptorId.eq(before.tag_descriptor_id))
+        .filter(tag_enum_option::Column::Value.eq(before.value.clone()))
+        .filter(tag_enum_option::Column::DeletedAt.is_null())
+        .count(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if taken > 0 {
+        Err(CurdError::Conflict(format!("Option {} exists already", before.value)))?;
+    }
+    let updated_at = timestamps::now();
+    let result = tag_enum_option::Entity::update_many()
+        .col_expr(tag_enum_option::Column::DeletedAt, Expr::value(Option::<DateTimeUtc>::None))
+        .col_expr(tag_enum_option::Column::UpdatedAt, Expr::value(updated_at))
+        .filter(tag_enum_option::Column::Id.eq(id))
+        .filter(tag_enum_option::Column::DeletedAt.is_not_null())
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if result.rows_affected >= 1 {
+        let after = tag_enum_option::Model {
+            deleted_at: None,
+            updated_at,
+            ..before.clone()
+        };
+        record(actor, Resource::TagOption, id, Action::Updated, Some(&before), Some(&after), db).await
+    } else {
+        Err(CurdError::NotFound)
+    }
+}
+
+/// Delete option [id] from the database, also if it is soft-deleted, together with the
+/// ride tag links which select it. The tag and the rides of these links are marked as
+/// updated.
+pub async fn purge(id: u32, actor: &Actor, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let option = tag_enum_option::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)?;
+    let linked_rides = ride_tag::Entity::find()
+        .select_only()
+        .column(ride_tag::Column::RideId)
+        .filter(ride_tag::Column::ValueEnumOptionId.eq(id))
+        .into_query();
+    super::ride::touch(Condition::all().add(ride::Column::Id.in_subquery(linked_rides)), db).await?;
+    ride_tag::Entity::delete_many()
+        .filter(ride_tag::Column::ValueEnumOptionId.eq(id))
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    tag_enum_option::Entity::delete_by_id(id)
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    timestamps::touch(tag_descriptor::Entity::update_many(), tag_descriptor::Column::UpdatedAt)
+        .filter(tag_descriptor::Column::Id.eq(option.tag_descriptor_id))
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    record(actor, Resource::TagOption, id, Action::Deleted, Some(&option), None, db).await
+}
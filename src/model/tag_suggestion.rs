@@ -0,0 +1,110 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::Days;
+use serde::Serialize;
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::*, Order, QueryOrder, QuerySelect, Select, TryGetable};
+use entity::{ride, ride_tag};
+use super::deleted::not_deleted;
+use super::error::CurdError;
+use super::retry::retry;
+use super::ride_tag_link::Value;
+use super::tag::Tag;
+
+/// Number of suggestions if not requested otherwise
+pub const DEFAULT_LIMIT: u64 = 10;
+/// Maximum number of suggestions
+pub const MAX_LIMIT: u64 = 100;
+/// Rides of the last days considered if not requested otherwise
+pub const DEFAULT_DAYS: u32 = 365;
+
+/// Value a tag had on earlier rides
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ValueSuggestion {
+    pub value: Value,
+    /// Number of rides with the value
+    pub count: u64,
+    /// Departure of the latest ride with the value
+    pub last_used_at: DateTimeUtc,
+}
+
+/// Distinct values of [column] among the links of [statement] with their count and
+/// latest departure, most frequent first
+async fn usage<V: TryGetable>(
+    statement: Select<ride_tag::Entity>,
+    column: ride_tag::Column,
+    limit: u64,
+    db: &impl ConnectionTrait,
+) -> Result<Vec<(V, i64, DateTimeUtc)>, CurdError> {
+    let statement = statement
+        .select_only()
+        .column(column)
+        .column_as(Expr::cust("COUNT(*)"), "uses")
+        .column_as(ride::Column::JourneyDeparture.max(), "last_used_at")
+        .filter(column.is_not_null())
+        .group_by(column)
+        .order_by(Expr::cust("uses"), Order::Desc)
+        .order_by(Expr::cust("last_used_at"), Order::Desc)
+        .limit(limit);
+    retry(|| statement.clone().into_tuple::<(V, i64, DateTimeUtc)>().all(db))
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )
+}
+
+/// The [limit] most frequent values of [tag] on the rides of the last [days] days up to
+/// [now], for autocompletion. Templates, deleted rides and values copied from templates
+/// but not confirmed are left out. Only string, integer and money tags have
+/// suggestions.
+pub async fn suggestions(
+    tag: &Tag,
+    limit: u64,
+    days: u32,
+    now: DateTimeUtc,
+    db: &impl ConnectionTrait,
+) -> Result<Vec<ValueSuggestion>, CurdError> {
+    let since = now.checked_sub_days(Days::new(days as u64)).unwrap_or(DateTimeUtc::MIN_UTC);
+    let statement = ride_tag::Entity::find()
+        .inner_join(ride::Entity)
+        .filter(ride_tag::Column::TagDescriptorId.eq(tag.id()))
+        .filter(not_deleted(ride_tag::Column::DeletedAt, false))
+        .filter(ride_tag::Column::PerRide.eq(false))
+        .filter(not_deleted(ride::Column::DeletedAt, false))
+        .filter(ride::Column::IsTemplate.eq(false))
+        .filter(ride::Column::JourneyDeparture.gte(since))
+        .filter(ride::Column::JourneyDeparture.lte(now));
+
+    let suggestion = |value, count: i64, last_used_at| ValueSuggestion {
+        value,
+        count: count as u64,
+        last_used_at,
+    };
+    let suggestions = match tag.tag_type.as_str() {
+        "string" => usage::<String>(statement, ride_tag::Column::ValueString, limit, db)
+            .await?
+            .into_iter()
+            .map(|(value, count, last_used_at)| suggestion(Value::String(value), count, last_used_at))
+            .collect(),
+        "integer" => usage::<i64>(statement, ride_tag::Column::ValueInteger, limit, db)
+            .await?
+            .into_iter()
+            .map(|(value, count, last_used_at)| suggestion(Value::Integer(value), count, last_used_at))
+            .collect(),
+        "float" if tag.is_money() => usage::<f64>(statement, ride_tag::Column::ValueFloat, limit, db)
+            .await?
+            .into_iter()
+            .map(|(value, count, last_used_at)| suggestion(Value::Float(value), count, last_used_at))
+            .collect(),
+        _ => Err(CurdError::Unprocessable("Suggestions are only available for string, integer and money tags".to_string()))?,
+    };
+    Ok(suggestions)
+}
@@ -0,0 +1,285 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{
+    prelude::*,
+    sea_query::Expr,
+    Set,
+    NotSet,
+    Unchanged,
+};
+use entity::telegram_link;
+use super::audit::Actor;
+use super::error::CurdError;
+use super::quick_add::{QuickAdd, QuickAddRequest};
+use super::ride::Ride;
+
+/// Reply to messages which are not understood
+const HELP: &str = "Send a ride like \"8:05 Hbf → Airport 3.20€ bus\".";
+
+/// Rocket state with the settings of the Telegram bot
+#[derive(Debug, Clone)]
+pub struct Telegram {
+    /// SHA-256 of the secret token of the webhook, the bot is disabled if not set
+    secret_hash: Option<[u8; 32]>,
+}
+
+impl Telegram {
+    /// Bot receiving updates with [secret]
+    pub fn new(secret: Option<&str>) -> Self {
+        Self {
+            secret_hash: secret.map(|secret| Sha256::digest(secret.as_bytes()).into()),
+        }
+    }
+
+    /// Whether the bot is configured
+    pub fn is_enabled(&self) -> bool {
+        self.secret_hash.is_some()
+    }
+
+    /// Whether [secret] is the secret token of the webhook. Hashes are compared, so the
+    /// time taken does not tell how much of the secret matches.
+    pub fn check_secret(&self, secret: &str) -> bool {
+        let hash: [u8; 32] = Sha256::digest(secret.as_bytes()).into();
+        self.secret_hash == Some(hash)
+    }
+}
+
+/// Code linking a Telegram account with the user
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct TelegramLink {
+    pub code: String,
+    /// Message to send to the bot, `/start <code>`
+    pub command: String,
+    /// Whether a Telegram account is linked
+    pub linked: bool,
+}
+
+impl From<telegram_link::Model> for TelegramLink {
+    fn from(model: telegram_link::Model) -> Self {
+        Self {
+            command: format!("/start {}", model.code),
+            code: model.code,
+            linked: model.telegram_user_id.is_some(),
+        }
+    }
+}
+
+/// Code of [user_id], created on first use
+pub async fn link(user_id: u32, db: &impl ConnectionTrait) -> Result<TelegramLink, CurdError> {
+    let existing = telegram_link::Entity::find()
+        .filter(telegram_link::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    let model = match existing {
+        Some(existing) => existing,
+        None => {
+            let model = telegram_link::ActiveModel {
+                id: NotSet,
+                created_at: Set(chrono::Utc::now()),
+                user_id: Set(user_id),
+                code: Set(new_code()),
+                telegram_user_id: Set(None),
+            };
+            model
+                .insert(db)
+                .await
+                .map_err(
+                    |error| {
+                        CurdError::DbErr(error)
+                    }
+                )?
+        },
+    };
+    Ok(TelegramLink::from(model))
+}
+
+/// Unlink the Telegram account of [user_id] and discard the code
+pub async fn unlink(user_id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let result = telegram_link::Entity::delete_many()
+        .filter(telegram_link::Column::UserId.eq(user_id))
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if result.rows_affected == 0 {
+        Err(CurdError::NotFound)
+    } else {
+        Ok(())
+    }
+}
+
+/// Update sent by Telegram, only the fields used
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+pub struct Update {
+    pub update_id: i64,
+    pub message: Option<Message>,
+}
+
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+pub struct Message {
+    pub chat: Chat,
+    pub from: Option<TelegramUser>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+pub struct Chat {
+    pub id: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+pub struct TelegramUser {
+    pub id: i64,
+}
+
+/// Answer of the webhook, which Telegram sends as message to the chat
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Reply {
+    /// Always `sendMessage`
+    pub method: String,
+    pub chat_id: i64,
+    pub text: String,
+}
+
+impl Reply {
+    fn new<S: ToString>(chat_id: i64, text: S) -> Self {
+        Self {
+            method: "sendMessage".to_string(),
+            chat_id,
+            text: text.to_string(),
+        }
+    }
+}
+
+/// Result of handling an update
+pub struct Handled {
+    pub reply: Reply,
+    /// User and ride added from the message
+    pub ride: Option<(u32, Ride)>,
+}
+
+/// Handle [update]: `/start <code>` links the sender, other messages of linked senders
+/// are added as rides with [quick_add]. Updates without text are ignored.
+pub async fn handle(
+    update: Update,
+    quick_add: &QuickAdd,
+    request_id: Option<String>,
+    db: &impl ConnectionTrait,
+) -> Result<Option<Handled>, CurdError> {
+    let Some(message) = update.message else {
+        return Ok(None);
+    };
+    let (Some(from), Some(text)) = (message.from, message.text) else {
+        return Ok(None);
+    };
+    let chat_id = message.chat.id;
+
+    if let Some(code) = text.strip_prefix("/start") {
+        let reply = match start(code.trim(), from.id, db).await? {
+            true => format!("Linked. {}", HELP),
+            false => "Unknown code. Send /start <code> with the code of GET /telegram/link.".to_string(),
+        };
+        return Ok(Some(Handled { reply: Reply::new(chat_id, reply), ride: None }));
+    }
+
+    let link = telegram_link::Entity::find()
+        .filter(telegram_link::Column::TelegramUserId.eq(from.id))
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    let Some(link) = link else {
+        let reply = "This account is not linked. Send /start <code> with the code of GET /telegram/link.";
+        return Ok(Some(Handled { reply: Reply::new(chat_id, reply), ride: None }));
+    };
+    let request = QuickAddRequest {
+        text,
+        utc_offset_minutes: None,
+    };
+    let actor = Actor::new(Some(link.user_id), request_id);
+    match quick_add.add(link.user_id, &request, chrono::Utc::now(), &actor, db).await {
+        Ok(ride) => {
+            let reply = format!(
+                "Added {} → {} at {}.",
+                ride.location_from,
+                ride.location_to,
+                ride.journey_departure.format("%Y-%m-%d %H:%M UTC"),
+            );
+            Ok(Some(Handled { reply: Reply::new(chat_id, reply), ride: Some((link.user_id, ride)) }))
+        },
+        Err(CurdError::Unprocessable(error)) => {
+            Ok(Some(Handled { reply: Reply::new(chat_id, format!("{} {}", error, HELP)), ride: None }))
+        },
+        Err(error) => Err(error),
+    }
+}
+
+/// Link the Telegram user [telegram_user_id] with the user of [code]. Returns false if
+/// the code is unknown.
+async fn start(code: &str, telegram_user_id: i64, db: &impl ConnectionTrait) -> Result<bool, CurdError> {
+    let link = telegram_link::Entity::find()
+        .filter(telegram_link::Column::Code.eq(code.to_lowercase()))
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    let Some(link) = link else {
+        return Ok(false);
+    };
+    // A Telegram account is linked with one user only
+    telegram_link::Entity::update_many()
+        .col_expr(telegram_link::Column::TelegramUserId, Expr::value(Option::<i64>::None))
+        .filter(telegram_link::Column::TelegramUserId.eq(telegram_user_id))
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    let model = telegram_link::ActiveModel {
+        id: Unchanged(link.id),
+        telegram_user_id: Set(Some(telegram_user_id)),
+        ..Default::default()
+    };
+    model
+        .update(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(true)
+}
+
+/// Random code for linking
+fn new_code() -> String {
+    rand::random::<[u8; 8]>()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
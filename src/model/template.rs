@@ -0,0 +1,67 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use serde::Deserialize;
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::DateTimeUtc, ConnectionTrait};
+use super::audit::Actor;
+use super::error::CurdError;
+use super::ride::{self, Ride};
+use super::ride_tag_link::{self, RideTagLink};
+
+/// Departure of the ride created from a template
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct InstantiateRequest {
+    /// Defaults to now
+    #[serde(default)]
+    pub journey_departure: Option<DateTimeUtc>,
+    /// Defaults to the departure plus the duration of the template, if it has an arrival
+    #[serde(default)]
+    pub journey_arrival: Option<DateTimeUtc>,
+}
+
+/// Create a ride of [user_id] from the template [template_id], departing as requested.
+/// The tag links of the template are copied. Links with `per_ride` set keep the flag, so
+/// the copied values are marked as defaults still to be confirmed for the ride.
+pub async fn instantiate(
+    template_id: u32,
+    user_id: u32,
+    request: InstantiateRequest,
+    now: DateTimeUtc,
+    actor: &Actor,
+    db: &impl ConnectionTrait,
+) -> Result<Ride, CurdError> {
+    let template = Ride::find_by_id(template_id, false, false, db).await?;
+    if !template.is_template {
+        Err(CurdError::Unprocessable("Ride is not a template".to_string()))?;
+    }
+    let journey_departure = request.journey_departure.unwrap_or(now);
+    let journey_arrival = request.journey_arrival.or_else(|| {
+        template.journey_arrival
+            .map(|arrival| journey_departure + (arrival - template.journey_departure))
+    });
+    let instance = ride::CreateUpdateBuilder::new(
+        journey_departure,
+        journey_arrival,
+        template.location_from,
+        template.location_to,
+        template.remarks,
+        false,
+    )
+        .with_passengers(template.passengers)
+        .with_vat_rate(template.vat_rate)
+        .insert(user_id, actor, db)
+        .await?;
+    for link in RideTagLink::find_all(template_id, false, db).await? {
+        let tag_id = link.tag_id();
+        ride_tag_link::CreateUpdateBuilder::from_json(link)
+            .insert(instance.id(), tag_id, actor, db)
+            .await?;
+    }
+    Ride::find_by_id(instance.id(), true, false, db).await
+}
@@ -0,0 +1,454 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::fmt::Write;
+use chrono::{Datelike, Months, NaiveDate};
+use sea_orm::prelude::*;
+use entity::ride::RideStatus;
+use entity::user;
+use super::error::CurdError;
+use super::locale::Locale;
+use super::mobility_budget::cents;
+use super::pdf::{self, Document, Font, Page};
+use super::ride::Ride;
+use super::ride_tag_link::Value;
+use super::tag::Tag;
+
+/// Texts of a travel log in a locale
+struct Labels {
+    title: &'static str,
+    name: &'static str,
+    period: &'static str,
+    times: &'static str,
+    date: &'static str,
+    departure: &'static str,
+    arrival: &'static str,
+    from: &'static str,
+    to: &'static str,
+    purpose: &'static str,
+    price: &'static str,
+    total: &'static str,
+    empty: &'static str,
+    signature: &'static str,
+    page: &'static str,
+    of: &'static str,
+    months: [&'static str; 12],
+}
+
+const ENGLISH: Labels = Labels {
+    title: "Travel log",
+    name: "Name",
+    period: "Period",
+    times: "Times in UTC",
+    date: "Date",
+    departure: "Departure",
+    arrival: "Arrival",
+    from: "From",
+    to: "To",
+    purpose: "Purpose",
+    price: "Price",
+    total: "Total",
+    empty: "No journeys",
+    signature: "Date, signature",
+    page: "Page",
+    of: "of",
+    months: [
+        "January", "February", "March", "April", "May", "June",
+        "July", "August", "September", "October", "November", "December",
+    ],
+};
+
+const GERMAN: Labels = Labels {
+    title: "Fahrtenbuch",
+    name: "Name",
+    period: "Zeitraum",
+    times: "Zeiten in UTC",
+    date: "Datum",
+    departure: "Abfahrt",
+    arrival: "Ankunft",
+    from: "Von",
+    to: "Nach",
+    purpose: "Zweck",
+    price: "Preis",
+    total: "Summe",
+    empty: "Keine Fahrten",
+    signature: "Datum, Unterschrift",
+    page: "Seite",
+    of: "von",
+    months: [
+        "Januar", "Februar", "März", "April", "Mai", "Juni",
+        "Juli", "August", "September", "Oktober", "November", "Dezember",
+    ],
+};
+
+/// Font size of the table in the PDF
+const FONT_SIZE: f32 = 9.0;
+/// Height of a table row in the PDF
+const ROW_HEIGHT: f32 = 14.0;
+/// Margin of the PDF pages
+const MARGIN: f32 = 40.0;
+/// Left edges of the columns date, departure, arrival, from, to, purpose and price
+/// and the right edge of the table in the PDF
+const COLUMNS: [f32; 8] = [40.0, 102.0, 152.0, 222.0, 392.0, 562.0, 722.0, 802.0];
+
+/// Ride of a travel log
+#[derive(Debug, Clone)]
+pub struct Journey {
+    pub departure: DateTimeUtc,
+    pub arrival: Option<DateTimeUtc>,
+    pub location_from: String,
+    pub location_to: String,
+    /// Values of the purpose tag, separated by commas
+    pub purpose: Option<String>,
+    pub price: Option<f64>,
+}
+
+/// Rides of a user in a calendar month, printable as HTML or PDF in the style of a
+/// "Fahrtenbuch"
+#[derive(Debug, Clone)]
+pub struct TravelLog {
+    /// First day of the month
+    pub month: NaiveDate,
+    /// Name of the user, or the subject of the token if the user has no name
+    pub traveller: String,
+    /// Currency of the prices, the preference of the user
+    pub currency: Option<String>,
+    /// Journeys in order of departure
+    pub journeys: Vec<Journey>,
+    locale: Locale,
+}
+
+impl TravelLog {
+    fn labels(&self) -> &'static Labels {
+        match self.locale {
+            Locale::En => &ENGLISH,
+            Locale::De => &GERMAN,
+        }
+    }
+
+    /// Title with the name of the month and year, e.g. `Travel log October 2026`
+    fn title(&self) -> String {
+        let labels = self.labels();
+        format!("{} {} {}", labels.title, labels.months[self.month.month0() as usize], self.month.year())
+    }
+
+    fn date(&self, date: NaiveDate) -> String {
+        match self.locale {
+            Locale::En => date.format("%Y-%m-%d").to_string(),
+            Locale::De => date.format("%d.%m.%Y").to_string(),
+        }
+    }
+
+    /// First and last day of the month
+    fn period(&self) -> String {
+        let last = self.month
+            .checked_add_months(Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(self.month);
+        format!("{} – {}", self.date(self.month), self.date(last))
+    }
+
+    /// Time of the arrival, with the date if it is not the day of the departure
+    fn arrival(&self, journey: &Journey) -> String {
+        let Some(arrival) = journey.arrival else {
+            return String::new();
+        };
+        if arrival.date_naive() == journey.departure.date_naive() {
+            return arrival.format("%H:%M").to_string();
+        }
+        match self.locale {
+            Locale::En => arrival.format("%m-%d %H:%M").to_string(),
+            Locale::De => arrival.format("%d.%m. %H:%M").to_string(),
+        }
+    }
+
+    /// [amount] with two decimals and the decimal separator of the locale
+    fn amount(&self, amount: f64) -> String {
+        let amount = format!("{:.2}", amount);
+        match self.locale {
+            Locale::En => amount,
+            Locale::De => amount.replace('.', ","),
+        }
+    }
+
+    fn price_header(&self) -> String {
+        match &self.currency {
+            Some(currency) => format!("{} ({})", self.labels().price, currency),
+            None => self.labels().price.to_string(),
+        }
+    }
+
+    /// Sum of the prices of the journeys
+    pub fn total(&self) -> f64 {
+        cents(self.journeys.iter().filter_map(|journey| journey.price).sum())
+    }
+
+    /// Cells of the row of [journey] in order of the columns
+    fn cells(&self, journey: &Journey) -> [String; 7] {
+        [
+            self.date(journey.departure.date_naive()),
+            journey.departure.format("%H:%M").to_string(),
+            self.arrival(journey),
+            journey.location_from.clone(),
+            journey.location_to.clone(),
+            journey.purpose.clone().unwrap_or_default(),
+            journey.price.map(|price| self.amount(price)).unwrap_or_default(),
+        ]
+    }
+
+    fn headers(&self) -> [String; 7] {
+        let labels = self.labels();
+        [
+            labels.date.to_string(),
+            labels.departure.to_string(),
+            labels.arrival.to_string(),
+            labels.from.to_string(),
+            labels.to.to_string(),
+            labels.purpose.to_string(),
+            self.price_header(),
+        ]
+    }
+
+    /// Standalone HTML document, laid out for printing on A4 in landscape orientation
+    pub fn to_html(&self) -> String {
+        let labels = self.labels();
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            concat!(
+                "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n",
+                "<style>\n",
+                "@page {{ size: A4 landscape; margin: 15mm; }}\n",
+                "body {{ font-family: Helvetica, Arial, sans-serif; font-size: 10pt; color: #000; }}\n",
+                "h1 {{ font-size: 16pt; margin: 0 0 4mm; }}\n",
+                "table {{ width: 100%; border-collapse: collapse; }}\n",
+                "thead {{ display: table-header-group; }}\n",
+                "tr {{ page-break-inside: avoid; }}\n",
+                "th, td {{ border-bottom: 0.5pt solid #888; padding: 1mm 2mm; text-align: left; vertical-align: top; }}\n",
+                "tfoot td {{ font-weight: bold; border-top: 1pt solid #000; border-bottom: none; }}\n",
+                ".amount {{ text-align: right; white-space: nowrap; }}\n",
+                ".signature {{ margin-top: 20mm; width: 80mm; border-top: 0.5pt solid #000; padding-top: 1mm; }}\n",
+                "</style>\n</head>\n<body>\n<h1>{}</h1>\n<p>{}: {}<br>{}: {}<br>{}</p>\n",
+            ),
+            match self.locale {
+                Locale::En => "en",
+                Locale::De => "de",
+            },
+            escape(&self.title()),
+            escape(&self.title()),
+            labels.name,
+            escape(&self.traveller),
+            labels.period,
+            self.period(),
+            labels.times,
+        );
+
+        html.push_str("<table>\n<thead>\n<tr>");
+        for (index, header) in self.headers().iter().enumerate() {
+            let class = if index == 6 { " class=\"amount\"" } else { "" };
+            let _ = write!(html, "<th{}>{}</th>", class, escape(header));
+        }
+        html.push_str("</tr>\n</thead>\n<tbody>\n");
+        if self.journeys.is_empty() {
+            let _ = writeln!(html, "<tr><td colspan=\"7\">{}</td></tr>", labels.empty);
+        }
+        for journey in &self.journeys {
+            html.push_str("<tr>");
+            for (index, cell) in self.cells(journey).iter().enumerate() {
+                let class = if index == 6 { " class=\"amount\"" } else { "" };
+                let _ = write!(html, "<td{}>{}</td>", class, escape(cell));
+            }
+            html.push_str("</tr>\n");
+        }
+        let _ = write!(
+            html,
+            "</tbody>\n<tfoot>\n<tr><td colspan=\"6\">{}</td><td class=\"amount\">{}</td></tr>\n</tfoot>\n</table>\n",
+            labels.total,
+            self.amount(self.total()),
+        );
+        let _ = write!(html, "<div class=\"signature\">{}</div>\n</body>\n</html>\n", labels.signature);
+        html
+    }
+
+    /// PDF document on A4 pages in landscape orientation, with the table header on each
+    /// page. Texts too wide for their column are shortened.
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let labels = self.labels();
+        let (_, height) = pdf::A4_LANDSCAPE;
+        let mut document = Document::new(pdf::A4_LANDSCAPE);
+
+        let page = document.add_page();
+        let mut y = height - MARGIN - 10.0;
+        page.text(COLUMNS[0], y, Font::Bold, 14.0, &self.title());
+        y -= 20.0;
+        page.text(COLUMNS[0], y, Font::Regular, FONT_SIZE, &format!("{}: {}", labels.name, self.traveller));
+        y -= 12.0;
+        page.text(COLUMNS[0], y, Font::Regular, FONT_SIZE, &format!("{}: {}", labels.period, self.period()));
+        y -= 12.0;
+        page.text(COLUMNS[0], y, Font::Regular, FONT_SIZE, labels.times);
+        y -= 24.0;
+        self.pdf_row(page, y, Font::Bold, &self.headers());
+        page.line((COLUMNS[0], y - 4.0), (COLUMNS[7], y - 4.0), 0.75);
+
+        let mut page_index = 0;
+        if self.journeys.is_empty() {
+            y -= ROW_HEIGHT;
+            document.pages_mut()[page_index].text(COLUMNS[0], y, Font::Regular, FONT_SIZE, labels.empty);
+        }
+        for journey in &self.journeys {
+            y -= ROW_HEIGHT;
+            if y < MARGIN + 20.0 {
+                let page = document.add_page();
+                page_index += 1;
+                y = height - MARGIN - 10.0;
+                self.pdf_row(page, y, Font::Bold, &self.headers());
+                page.line((COLUMNS[0], y - 4.0), (COLUMNS[7], y - 4.0), 0.75);
+                y -= ROW_HEIGHT;
+            }
+            let page = &mut document.pages_mut()[page_index];
+            self.pdf_row(page, y, Font::Regular, &self.cells(journey));
+        }
+
+        // Total and signature are kept together
+        y -= ROW_HEIGHT;
+        if y - 50.0 < MARGIN + 20.0 {
+            document.add_page();
+            page_index += 1;
+            y = height - MARGIN - 10.0;
+        }
+        let page = &mut document.pages_mut()[page_index];
+        page.line((COLUMNS[0], y + ROW_HEIGHT - 4.0), (COLUMNS[7], y + ROW_HEIGHT - 4.0), 0.75);
+        page.text(COLUMNS[0], y, Font::Bold, FONT_SIZE, labels.total);
+        page.text_right(COLUMNS[7] - 3.0, y, Font::Bold, FONT_SIZE, &self.amount(self.total()));
+        y -= 50.0;
+        page.line((COLUMNS[0], y + 10.0), (COLUMNS[0] + 230.0, y + 10.0), 0.5);
+        page.text(COLUMNS[0], y, Font::Regular, FONT_SIZE, labels.signature);
+
+        let pages = document.pages_mut().len();
+        for (index, page) in document.pages_mut().iter_mut().enumerate() {
+            let footer = format!("{} {} {} {}", labels.page, index + 1, labels.of, pages);
+            page.text_right(COLUMNS[7], MARGIN / 2.0, Font::Regular, 8.0, &footer);
+        }
+        document.into_bytes()
+    }
+
+    /// Write [cells] into the columns of the table at [y]. The price is right-aligned.
+    fn pdf_row(&self, page: &mut Page, y: f32, font: Font, cells: &[String; 7]) {
+        for (index, cell) in cells.iter().enumerate() {
+            let width = COLUMNS[index + 1] - COLUMNS[index] - 6.0;
+            let text = pdf::fit(cell, font, FONT_SIZE, width);
+            if index == 6 {
+                page.text_right(COLUMNS[7] - 3.0, y, font, FONT_SIZE, &text);
+            } else {
+                page.text(COLUMNS[index], y, font, FONT_SIZE, &text);
+            }
+        }
+    }
+}
+
+/// [text] with the characters which are special in HTML escaped
+fn escape(text: &str) -> String {
+    text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Rocket state composing the travel logs of users
+#[derive(Debug, Clone)]
+pub struct TravelLogs {
+    /// Key of the tag holding the price of a ride
+    price_tag: String,
+    /// Key of the tag holding the purpose of a ride
+    purpose_tag: String,
+}
+
+impl TravelLogs {
+    /// Logs with the values of the tags [price_tag] and [purpose_tag]
+    pub fn new(price_tag: String, purpose_tag: String) -> Self {
+        Self {
+            price_tag,
+            purpose_tag,
+        }
+    }
+
+    /// Travel log of the rides of [user_id] departing in [month], given as its first
+    /// day in UTC, with the texts of [locale]. Templates and cancelled rides are
+    /// skipped.
+    pub async fn month(&self, user_id: u32, month: NaiveDate, locale: Locale, db: &impl ConnectionTrait) -> Result<TravelLog, CurdError> {
+        let user = user::Entity::find_by_id(user_id)
+            .one(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?
+            .ok_or(CurdError::NotFound)?;
+        let tags = Tag::find_all(user_id, true, false, db).await?;
+        let price_tag = tags.iter().find(|tag| *tag.tag_key() == self.price_tag);
+        let purpose_tag = tags.iter().find(|tag| *tag.tag_key() == self.purpose_tag);
+
+        let mut rides = Ride::find_all(user_id, true, false, db).await?;
+        rides.sort_by_key(|ride| (ride.journey_departure, ride.id()));
+        let journeys = rides
+            .into_iter()
+            .filter(|ride| !ride.is_template && ride.status != RideStatus::Cancelled)
+            .filter(|ride| ride.journey_departure.date_naive().with_day(1) == Some(month))
+            .map(|ride| {
+                let links = ride.tags().iter().flatten();
+                let price = price_tag.and_then(|price_tag| {
+                    links
+                        .clone()
+                        .find(|link| link.tag_id() == price_tag.id())
+                        .and_then(|link| match link.value {
+                            Value::Float(price) => Some(price),
+                            Value::Integer(price) => Some(price as f64),
+                            _ => None,
+                        })
+                });
+                let purpose = purpose_tag.map(|purpose_tag| {
+                    links
+                        .filter(|link| link.tag_id() == purpose_tag.id())
+                        .filter_map(|link| match &link.value {
+                            Value::String(value) => Some(value.clone()),
+                            Value::Integer(value) => Some(value.to_string()),
+                            Value::Float(value) => Some(value.to_string()),
+                            Value::DateTime(value) => Some(value.to_rfc3339()),
+                            Value::EnumOption(option_id) => purpose_tag.options()
+                                .iter()
+                                .flatten()
+                                .find(|option| option.id() == *option_id)
+                                .map(|option| option.value.clone()),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }).filter(|purpose| !purpose.is_empty());
+                Journey {
+                    departure: ride.journey_departure,
+                    arrival: ride.journey_arrival,
+                    location_from: ride.location_from,
+                    location_to: ride.location_to,
+                    purpose,
+                    price,
+                }
+            })
+            .collect();
+        Ok(
+            TravelLog {
+                month,
+                traveller: user.name.unwrap_or(user.jwt_subject),
+                currency: user.currency,
+                journeys,
+                locale,
+            }
+        )
+    }
+}
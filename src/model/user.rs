@@ -0,0 +1,159 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::LazyLock;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{prelude::*, IntoActiveModel};
+use entity::user::{self, DistanceUnit, Locale, Theme};
+use super::error::CurdError;
+
+/// Plausible email address. Addresses are not verified.
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").expect("Valid regex")
+});
+/// ISO 4217 currency code
+static CURRENCY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Z]{3}$").expect("Valid regex")
+});
+
+/// Deserialize a field which is present, so that null can be told apart from a missing
+/// field, which is None by `#[serde(default)]`
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Check the profile fields of [model]
+fn validate(model: &user::Model) -> Result<(), CurdError> {
+    if model.retention_days == Some(0) {
+        Err(CurdError::Unprocessable("retention_days must be at least 1".to_string()))?;
+    }
+    if let Some(email) = &model.email {
+        if !EMAIL.is_match(email) {
+            Err(CurdError::Unprocessable(format!("Invalid email address {}", email)))?;
+        }
+    }
+    if let Some(avatar_url) = &model.avatar_url {
+        match reqwest::Url::parse(avatar_url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => (),
+            _ => Err(CurdError::Unprocessable(format!("Invalid avatar URL {}", avatar_url)))?,
+        }
+    }
+    if let Some(currency) = &model.currency {
+        if !CURRENCY.is_match(currency) {
+            Err(CurdError::Unprocessable(format!("Invalid currency {}, expected an ISO 4217 code like EUR", currency)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Partial update of the profile of a user as JSON merge patch (RFC 7396). Fields which
+/// are not given are kept, fields set to null are cleared.
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UserPatch {
+    #[serde(default, deserialize_with = "present")]
+    #[schemars(length(max = 200))]
+    pub name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub retention_days: Option<Option<u32>>,
+    #[serde(default, deserialize_with = "present")]
+    pub locale: Option<Option<Locale>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schemars(length(max = 254))]
+    pub email: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schemars(length(max = 2000))]
+    pub avatar_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub theme: Option<Option<Theme>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schemars(length(max = 3))]
+    pub currency: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub distance_unit: Option<Option<DistanceUnit>>,
+}
+
+impl UserPatch {
+    /// Apply the patch to the profile of [user_id] and return the updated profile.
+    /// Fails without changes if the patched profile is invalid.
+    pub async fn apply(self, user_id: u32, db: &impl ConnectionTrait) -> Result<user::Model, CurdError> {
+        let mut model = find(user_id, db).await?;
+        if let Some(name) = self.name {
+            model.name = name;
+        }
+        if let Some(retention_days) = self.retention_days {
+            model.retention_days = retention_days;
+        }
+        if let Some(locale) = self.locale {
+            model.locale = locale;
+        }
+        if let Some(email) = self.email {
+            model.email = email;
+        }
+        if let Some(avatar_url) = self.avatar_url {
+            model.avatar_url = avatar_url;
+        }
+        if let Some(theme) = self.theme {
+            model.theme = theme;
+        }
+        if let Some(currency) = self.currency {
+            model.currency = currency;
+        }
+        if let Some(distance_unit) = self.distance_unit {
+            model.distance_unit = distance_unit;
+        }
+        save(model, db).await
+    }
+}
+
+/// Fetch user [user_id]
+async fn find(user_id: u32, db: &impl ConnectionTrait) -> Result<user::Model, CurdError> {
+    user::Entity::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)
+}
+
+/// Validate and save all fields of [model]
+async fn save(model: user::Model, db: &impl ConnectionTrait) -> Result<user::Model, CurdError> {
+    validate(&model)?;
+    model
+        .into_active_model()
+        .reset_all()
+        .update(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )
+}
+
+/// Replace the profile of [user_id] with the fields of [profile], clearing the ones
+/// not set
+pub async fn replace_profile(user_id: u32, profile: user::Model, db: &impl ConnectionTrait) -> Result<user::Model, CurdError> {
+    let model = find(user_id, db).await?;
+    let model = user::Model {
+        id: model.id,
+        jwt_issuer: model.jwt_issuer,
+        jwt_subject: model.jwt_subject,
+        ..profile
+    };
+    save(model, db).await
+}
@@ -0,0 +1,143 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use rocket_okapi::okapi::schemars;
+use sea_orm::ConnectionTrait;
+use entity::ride::RideStatus;
+use super::error::CurdError;
+use super::mobility_budget::cents;
+use super::ride::Ride;
+use super::ride_tag_link::Value;
+use super::tag::Tag;
+
+/// Gross amount split into the net amount and the included VAT
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct VatBreakdown {
+    pub gross: f64,
+    pub net: f64,
+    pub vat: f64,
+}
+
+impl VatBreakdown {
+    /// Breakdown of [gross] including VAT at [rate] percent, rounded to cents. The VAT is
+    /// the difference, so net and VAT always add up to the gross amount.
+    pub fn from_gross(gross: f64, rate: f64) -> Self {
+        let net = cents(gross / (1.0 + rate / 100.0));
+        Self {
+            gross,
+            net,
+            vat: cents(gross - net),
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.gross = cents(self.gross + other.gross);
+        self.net = cents(self.net + other.net);
+        self.vat = cents(self.vat + other.vat);
+    }
+}
+
+/// Prices of the rides with the same VAT rate
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct VatRateTotal {
+    /// Rate in percent
+    pub rate: f64,
+    pub rides: usize,
+    #[serde(flatten)]
+    pub amounts: VatBreakdown,
+}
+
+/// Prices of rides without VAT rate
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct UnknownVat {
+    pub rides: usize,
+    pub gross: f64,
+}
+
+/// VAT included in the prices of the rides of a period
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct VatSummary {
+    /// Totals per rate, lowest rate first
+    pub rates: Vec<VatRateTotal>,
+    /// Sum of all rates
+    pub total: VatBreakdown,
+    /// Rides with a price but without VAT rate, not part of the total
+    pub without_rate: UnknownVat,
+}
+
+/// Rocket state summing the VAT included in ride prices
+#[derive(Debug, Clone)]
+pub struct VatReport {
+    /// Key of the tag holding the gross price of a ride
+    price_tag: String,
+}
+
+impl VatReport {
+    /// Reports on the values of the tag [price_tag]
+    pub fn new(price_tag: String) -> Self {
+        Self {
+            price_tag,
+        }
+    }
+
+    /// VAT included in the prices of the rides of [user_id] departing in the months
+    /// [from] to [to], given as their first days in UTC. Templates, cancelled rides and
+    /// rides without price are skipped. Each price is split and rounded on its own, like
+    /// on the receipt.
+    pub async fn summary(&self, user_id: u32, from: NaiveDate, to: NaiveDate, db: &impl ConnectionTrait) -> Result<VatSummary, CurdError> {
+        let mut summary = VatSummary::default();
+        let tags = Tag::find_all(user_id, false, false, db).await?;
+        let Some(price_tag) = tags.iter().find(|tag| *tag.tag_key() == self.price_tag) else {
+            return Ok(summary);
+        };
+
+        let rides = Ride::find_all(user_id, true, false, db).await?;
+        for ride in rides.iter().filter(|ride| !ride.is_template && ride.status != RideStatus::Cancelled) {
+            let first_day = ride.journey_departure.date_naive().with_day(1).expect("Every month has a first day");
+            if first_day < from || first_day > to {
+                continue;
+            }
+            let price = ride.tags()
+                .iter()
+                .flatten()
+                .find(|link| link.tag_id() == price_tag.id())
+                .and_then(|link| match link.value {
+                    Value::Float(price) => Some(price),
+                    Value::Integer(price) => Some(price as f64),
+                    _ => None,
+                });
+            let Some(price) = price else {
+                continue;
+            };
+            let Some(rate) = ride.vat_rate else {
+                summary.without_rate.rides += 1;
+                summary.without_rate.gross = cents(summary.without_rate.gross + price);
+                continue;
+            };
+            let breakdown = VatBreakdown::from_gross(price, rate);
+            match summary.rates.iter_mut().find(|total| total.rate == rate) {
+                Some(total) => {
+                    total.rides += 1;
+                    total.amounts.add(&breakdown);
+                },
+                None => summary.rates.push(
+                    VatRateTotal {
+                        rate,
+                        rides: 1,
+                        amounts: breakdown,
+                    }
+                ),
+            }
+            summary.total.add(&breakdown);
+        }
+        summary.rates.sort_by(|a, b| a.rate.total_cmp(&b.rate));
+        Ok(summary)
+    }
+}
@@ -0,0 +1,283 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::time::Duration;
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{
+    prelude::*,
+    Set,
+    NotSet,
+    Unchanged,
+};
+use entity::webdav_target;
+use super::archive::Archive;
+use super::credentials::CredentialCipher;
+use super::error::CurdError;
+use super::geocode::USER_AGENT;
+use super::locale;
+
+/// Timeout of requests to the WebDAV server
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// JSON structure of the WebDAV folder of a user
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WebdavTarget {
+    /// URL of the folder, e.g.
+    /// `https://cloud.example.tld/remote.php/dav/files/user/Expenses/`
+    pub folder_url: String,
+    pub username: String,
+    /// Password, preferably an app password. Only written, kept on update if not given.
+    #[serde(skip_serializing, default)]
+    pub password: Option<String>,
+    /// Days between scheduled uploads of the archive. Only uploaded on request if not set.
+    pub interval_days: Option<u32>,
+    #[serde(skip_deserializing)]
+    last_uploaded_at: Option<DateTimeUtc>,
+    /// Error of the last upload, if it failed
+    #[serde(skip_deserializing)]
+    last_error: Option<String>,
+}
+
+impl From<webdav_target::Model> for WebdavTarget {
+    fn from(model: webdav_target::Model) -> Self {
+        Self {
+            folder_url: model.folder_url,
+            username: model.username,
+            password: None,
+            interval_days: model.interval_days,
+            last_uploaded_at: model.last_uploaded_at,
+            last_error: model.last_error,
+        }
+    }
+}
+
+impl WebdavTarget {
+    /// Fetch the folder of [user_id]
+    pub async fn find(user_id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        Ok(Self::from(find_model(user_id, db).await?))
+    }
+
+    /// Insert or update the folder of [user_id]. The password is encrypted with [cipher].
+    pub async fn save(self, user_id: u32, cipher: &CredentialCipher, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        match reqwest::Url::parse(&self.folder_url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => (),
+            _ => Err(CurdError::DeserializationError(format!("Invalid folder URL {}", self.folder_url)))?,
+        }
+        if self.interval_days == Some(0) {
+            Err(CurdError::DeserializationError("interval_days must be at least 1".to_string()))?;
+        }
+        let existing = webdav_target::Entity::find()
+            .filter(webdav_target::Column::UserId.eq(user_id))
+            .one(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        let password_encrypted = match (self.password, &existing) {
+            (Some(password), _) => Set(cipher.encrypt(&password)?),
+            (None, Some(_)) => NotSet,
+            (None, None) => Err(CurdError::DeserializationError("password is required".to_string()))?,
+        };
+        let model = webdav_target::ActiveModel {
+            id: existing.as_ref().map_or(NotSet, |existing| Unchanged(existing.id)),
+            created_at: NotSet,
+            updated_at: NotSet,
+            user_id: Set(user_id),
+            folder_url: Set(self.folder_url),
+            username: Set(self.username),
+            password_encrypted,
+            interval_days: Set(self.interval_days),
+            last_uploaded_at: NotSet,
+            last_error: NotSet,
+        };
+        let result = if existing.is_some() {
+            model.update(db).await
+        } else {
+            model.insert(db).await
+        }
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(Self::from(result))
+    }
+}
+
+/// Remove the folder of [user_id]
+pub async fn remove(user_id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let result = webdav_target::Entity::delete_many()
+        .filter(webdav_target::Column::UserId.eq(user_id))
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if result.rows_affected == 0 {
+        Err(CurdError::NotFound)
+    } else {
+        Ok(())
+    }
+}
+
+async fn find_model(user_id: u32, db: &impl ConnectionTrait) -> Result<webdav_target::Model, CurdError> {
+    webdav_target::Entity::find()
+        .filter(webdav_target::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?
+        .ok_or(CurdError::NotFound)
+}
+
+/// File uploaded to the folder
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Upload {
+    pub file_name: String,
+    /// Size in bytes
+    pub size: usize,
+}
+
+/// Check that the folder of [user_id] exists and the credentials are accepted
+pub async fn test(user_id: u32, cipher: &CredentialCipher, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let target = find_model(user_id, db).await?;
+    let method = reqwest::Method::from_bytes(b"PROPFIND").expect("Valid method");
+    let response = request(&target, cipher, method, folder_url(&target)?)?
+        .header("Depth", "0")
+        .send()
+        .await;
+    check(response)?;
+    Ok(())
+}
+
+/// Upload the archive of [user_id] to the folder. The outcome is recorded.
+pub async fn upload(user_id: u32, cipher: &CredentialCipher, db: &impl ConnectionTrait) -> Result<Upload, CurdError> {
+    let target = find_model(user_id, db).await?;
+    let started_at = chrono::Utc::now();
+    let result = put_archive(&target, cipher, db).await;
+    let model = webdav_target::ActiveModel {
+        id: Unchanged(target.id),
+        last_uploaded_at: match result {
+            Ok(_) => Set(Some(started_at)),
+            Err(_) => NotSet,
+        },
+        last_error: Set(result.as_ref().err().map(|error| error.to_string())),
+        ..Default::default()
+    };
+    model
+        .update(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    result
+}
+
+/// Users whose scheduled upload is due at [now]
+pub async fn find_due(now: DateTimeUtc, db: &impl ConnectionTrait) -> Result<Vec<u32>, CurdError> {
+    let models = webdav_target::Entity::find()
+        .filter(webdav_target::Column::IntervalDays.is_not_null())
+        .all(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    let user_ids = models
+        .into_iter()
+        .filter(|model| {
+            let Some(interval_days) = model.interval_days else {
+                return false;
+            };
+            model.last_uploaded_at
+                .is_none_or(|last_uploaded_at| last_uploaded_at + TimeDelta::days(interval_days.into()) <= now)
+        })
+        .map(|model| model.user_id)
+        .collect();
+    Ok(user_ids)
+}
+
+async fn put_archive(target: &webdav_target::Model, cipher: &CredentialCipher, db: &impl ConnectionTrait) -> Result<Upload, CurdError> {
+    let locale = locale::resolve(None, target.user_id, db).await?;
+    let archive = Archive::load(target.user_id, db).await?;
+    let data = archive.to_zip(locale)?;
+    let upload = Upload {
+        file_name: archive.file_name(),
+        size: data.len(),
+    };
+    let url = folder_url(target)?
+        .join(&upload.file_name)
+        .map_err(
+            |error| {
+                CurdError::InternalError(error.to_string())
+            }
+        )?;
+    let response = request(target, cipher, reqwest::Method::PUT, url)?
+        .header(reqwest::header::CONTENT_TYPE, "application/zip")
+        .body(data)
+        .send()
+        .await;
+    check(response)?;
+    Ok(upload)
+}
+
+/// Folder URL ending with a slash, so that file names are joined
+fn folder_url(target: &webdav_target::Model) -> Result<reqwest::Url, CurdError> {
+    let mut url = target.folder_url.clone();
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+    reqwest::Url::parse(&url)
+        .map_err(
+            |error| {
+                CurdError::Unprocessable(format!("Invalid folder URL: {}", error))
+            }
+        )
+}
+
+/// Request with the credentials of [target]
+fn request(target: &webdav_target::Model, cipher: &CredentialCipher, method: reqwest::Method, url: reqwest::Url) -> Result<reqwest::RequestBuilder, CurdError> {
+    let password = cipher.decrypt(&target.password_encrypted)?;
+    let client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(
+            |error| {
+                CurdError::InternalError(error.to_string())
+            }
+        )?;
+    Ok(
+        client
+            .request(method, url)
+            .basic_auth(&target.username, Some(password))
+    )
+}
+
+/// Response of a successful request
+fn check(response: Result<reqwest::Response, reqwest::Error>) -> Result<reqwest::Response, CurdError> {
+    response
+        .and_then(|response| response.error_for_status())
+        .map_err(
+            |error| {
+                CurdError::Upstream(format!("WebDAV request failed: {}", error))
+            }
+        )
+}
@@ -0,0 +1,564 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use chrono::TimeDelta;
+use reqwest::Url;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use sea_orm::{
+    prelude::*,
+    QueryOrder,
+    Set,
+    NotSet,
+    Unchanged,
+};
+use entity::webhook;
+use entity::webhook_delivery::{self, DeliveryState};
+use super::error::CurdError;
+use super::event::{Action, Event, Resource};
+
+/// Attempts before a delivery is given up
+pub const MAX_ATTEMPTS: u32 = 8;
+/// Delay before the first retry, doubled for every further retry
+const RETRY_DELAY: TimeDelta = TimeDelta::seconds(30);
+/// Maximum delay between retries
+const MAX_RETRY_DELAY: TimeDelta = TimeDelta::hours(6);
+
+/// Targets webhooks may be delivered to. Hosts resolving to loopback, link-local or
+/// private addresses are rejected, so that users cannot reach services in the network of
+/// the server, unless the operator allowed the host. Also the DNS resolver of the
+/// delivery client, so that a host cannot resolve to other addresses once checked.
+#[derive(Debug, Clone, Default)]
+pub struct WebhookTargets {
+    allowed_hosts: Arc<Vec<String>>,
+}
+
+impl WebhookTargets {
+    pub fn new(allowed_hosts: Vec<String>) -> Self {
+        Self {
+            allowed_hosts: Arc::new(allowed_hosts),
+        }
+    }
+
+    fn is_allowed(&self, host: &str) -> bool {
+        self.allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+
+    /// Check that [url] is a http or https URL whose host only resolves to public
+    /// addresses
+    pub async fn check(&self, url: &str) -> Result<(), CurdError> {
+        let parsed = match Url::parse(url) {
+            Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => parsed,
+            _ => Err(CurdError::DeserializationError(format!("Invalid webhook URL {}", url)))?,
+        };
+        // IPv6 addresses are enclosed in brackets
+        let Some(host) = parsed.host_str().map(|host| host.trim_start_matches('[').trim_end_matches(']')) else {
+            Err(CurdError::DeserializationError(format!("Invalid webhook URL {}", url)))?
+        };
+        self.resolve_host(host, parsed.port_or_known_default().unwrap_or(80)).await?;
+        Ok(())
+    }
+
+    /// Addresses of [host], which must all be public unless the host is allowed
+    async fn resolve_host(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, CurdError> {
+        let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(
+                |error| {
+                    CurdError::Unprocessable(format!("Webhook host {} cannot be resolved: {}", host, error))
+                }
+            )?
+            .collect();
+        if self.is_allowed(host) {
+            return Ok(addresses);
+        }
+        if let Some(address) = addresses.iter().find(|address| !is_public(address.ip())) {
+            Err(CurdError::Unprocessable(format!("Webhook host {} resolves to non-public address {}", host, address.ip())))?;
+        }
+        Ok(addresses)
+    }
+}
+
+impl Resolve for WebhookTargets {
+    fn resolve(&self, name: Name) -> Resolving {
+        let targets = self.clone();
+        Box::pin(async move {
+            let addresses = targets.resolve_host(name.as_str(), 0)
+                .await
+                .map_err(|error| std::io::Error::other(error.to_string()))?;
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Check that [address] is reachable from the internet, i.e. not loopback, link-local,
+/// private, shared (carrier-grade NAT), unspecified, broadcast or multicast
+fn is_public(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => {
+            let [first, second, ..] = address.octets();
+            let shared = first == 100 && (second & 0xc0) == 64;
+            !(address.is_loopback()
+                || address.is_private()
+                || address.is_link_local()
+                || address.is_unspecified()
+                || address.is_broadcast()
+                || address.is_multicast()
+                || shared)
+        },
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(address) => is_public(IpAddr::V4(address)),
+            None => !(address.is_loopback()
+                || address.is_unique_local()
+                || address.is_unicast_link_local()
+                || address.is_unspecified()
+                || address.is_multicast()),
+        },
+    }
+}
+
+/// JSON structure
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Webhook {
+    #[serde(skip_deserializing)]
+    id: u32,
+    /// Callback URL, must be http or https
+    pub url: String,
+    /// Event filters, e.g. `ride.created`, `tag.*` or `*`
+    pub events: Vec<String>,
+    /// Deliveries are only made for active webhooks
+    #[serde(default = "Webhook::default_active")]
+    pub active: bool,
+    /// Secret for the payload signature. Generated on creation if not given, kept on
+    /// update if not given.
+    pub secret: Option<String>,
+}
+
+impl From<webhook::Model> for Webhook {
+    fn from(model: webhook::Model) -> Self {
+        Self {
+            id: model.id,
+            url: model.url,
+            events: split_events(&model.events),
+            active: model.active,
+            secret: Some(model.secret),
+        }
+    }
+}
+
+fn split_events(events: &str) -> Vec<String> {
+    events
+        .split(',')
+        .filter(|event| !event.is_empty())
+        .map(|event| event.to_string())
+        .collect()
+}
+
+impl Webhook {
+    fn default_active() -> bool {
+        true
+    }
+
+    /// Getter for [id]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Fetch all instances belonging to [user_id]
+    pub async fn find_all(user_id: u32, db: &impl ConnectionTrait) -> Result<Vec<Self>, CurdError> {
+        let models = webhook::Entity::find()
+            .filter(webhook::Column::UserId.eq(user_id))
+            .filter(webhook::Column::DeletedAt.is_null())
+            .all(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(models.into_iter().map(Self::from).collect())
+    }
+
+    /// Find instance by [id].
+    pub async fn find_by_id(id: u32, db: &impl ConnectionTrait) -> Result<Self, CurdError> {
+        let model = webhook::Entity::find()
+            .filter(webhook::Column::Id.eq(id))
+            .filter(webhook::Column::DeletedAt.is_null())
+            .one(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        match model {
+            Some(model) => Ok(Self::from(model)),
+            None => Err(CurdError::NotFound)?,
+        }
+    }
+}
+
+/// Check if [webhook_id] belongs to [user_id].
+pub async fn is_owner(
+    webhook_id: u32,
+    user_id: u32,
+    db: &impl ConnectionTrait
+) -> Result<(), CurdError> {
+    let rows = webhook::Entity::find()
+        .filter(webhook::Column::Id.eq(webhook_id))
+        .filter(webhook::Column::UserId.eq(user_id))
+        .filter(webhook::Column::DeletedAt.is_null())
+        .count(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if rows == 0 {
+        Err(CurdError::NotFound)
+    } else {
+        Ok(())
+    }
+}
+
+/// Builder for creating or updating a model (in the database)
+pub struct CreateUpdateBuilder {
+    pub url: String,
+    pub events: Vec<String>,
+    pub active: bool,
+    pub secret: Option<String>,
+}
+
+impl CreateUpdateBuilder {
+    /// New builder from deserialized JSON structure
+    pub fn from_json(model: Webhook) -> Self {
+        Self {
+            url: model.url,
+            events: model.events,
+            active: model.active,
+            secret: model.secret,
+        }
+    }
+
+    async fn validate(&self, targets: &WebhookTargets) -> Result<(), CurdError> {
+        if self.events.is_empty() {
+            Err(CurdError::DeserializationError("Webhook needs at least one event filter".to_string()))?;
+        }
+        for filter in &self.events {
+            if !is_valid_filter(filter) {
+                Err(CurdError::DeserializationError(format!("Invalid event filter {}", filter)))?;
+            }
+        }
+        targets.check(&self.url).await
+    }
+
+    /// Insert into database and return the new instance. It will belong to [user_id].
+    pub async fn insert(
+        self,
+        user_id: u32,
+        targets: &WebhookTargets,
+        db: &impl ConnectionTrait,
+    ) -> Result<Webhook, CurdError> {
+        self.validate(targets).await?;
+        let secret = match self.secret {
+            Some(secret) => secret,
+            None => new_secret(),
+        };
+        let model = webhook::ActiveModel {
+            id: NotSet,
+            created_at: NotSet,
+            updated_at: NotSet,
+            deleted_at: NotSet,
+            user_id: Set(user_id),
+            url: Set(self.url.clone()),
+            secret: Set(secret.clone()),
+            events: Set(self.events.join(",")),
+            active: Set(self.active),
+        };
+        let result = model
+            .insert(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+
+        Ok(
+            Webhook {
+                id: result.id,
+                url: self.url,
+                events: self.events,
+                active: self.active,
+                secret: Some(secret),
+            }
+        )
+    }
+
+    /// Update instance identified by [id] in database.
+    pub async fn update(
+        self,
+        id: u32,
+        targets: &WebhookTargets,
+        db: &impl ConnectionTrait,
+    ) -> Result<(), CurdError> {
+        self.validate(targets).await?;
+        let model = webhook::ActiveModel {
+            id: Unchanged(id),
+            url: Set(self.url),
+            events: Set(self.events.join(",")),
+            active: Set(self.active),
+            // The secret is kept if none is given
+            secret: match self.secret {
+                Some(secret) => Set(secret),
+                None => NotSet,
+            },
+            ..Default::default()
+        };
+        model
+            .update(db)
+            .await
+            .map_err(
+                |error| {
+                    match error {
+                        DbErr::RecordNotUpdated => CurdError::NotFound,
+                        error => CurdError::DbErr(error),
+                    }
+                }
+            )?;
+        Ok(())
+    }
+}
+
+/// Check that [filter] is `*`, `<resource>.*` or `<resource>.<action>`
+fn is_valid_filter(filter: &str) -> bool {
+    if filter == "*" {
+        return true;
+    }
+    let (resource, action) = match filter.split_once('.') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let resources = [Resource::Ride, Resource::Tag, Resource::TagOption, Resource::RideTag, Resource::RideReference, Resource::RideTrack];
+    let actions = [Action::Created, Action::Updated, Action::Deleted];
+    resources.iter().any(|r| r.to_string() == resource)
+        && (action == "*" || actions.iter().any(|a| a.to_string() == action))
+}
+
+/// Random secret for signing payloads
+fn new_secret() -> String {
+    rand::random::<[u8; 32]>()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Remove instance by [id].
+pub async fn remove(id: u32, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let result = webhook::Entity::update_many()
+        .col_expr(webhook::Column::DeletedAt, Expr::value(chrono::Utc::now()))
+        .col_expr(webhook::Column::Active, Expr::value(false))
+        .filter(webhook::Column::Id.eq(id))
+        .filter(webhook::Column::DeletedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    if result.rows_affected >= 1 {
+        Ok(())
+    } else {
+        Err(CurdError::NotFound)
+    }
+}
+
+/// JSON structure of a delivery log entry
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Delivery {
+    id: u32,
+    created_at: DateTimeUtc,
+    event_type: String,
+    /// `pending`, `delivered` or `failed`
+    state: String,
+    attempts: u32,
+    next_attempt_at: Option<DateTimeUtc>,
+    delivered_at: Option<DateTimeUtc>,
+    /// HTTP status of the last attempt
+    response_status: Option<u32>,
+    /// Error of the last attempt
+    error: Option<String>,
+    /// Payload as sent
+    payload: serde_json::Value,
+}
+
+impl From<webhook_delivery::Model> for Delivery {
+    fn from(model: webhook_delivery::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            event_type: model.event_type,
+            state: match model.state {
+                DeliveryState::Pending => "pending",
+                DeliveryState::Delivered => "delivered",
+                DeliveryState::Failed => "failed",
+            }.to_string(),
+            attempts: model.attempts,
+            next_attempt_at: model.next_attempt_at,
+            delivered_at: model.delivered_at,
+            response_status: model.response_status,
+            error: model.error,
+            payload: serde_json::from_str(&model.payload).unwrap_or(serde_json::Value::Null),
+        }
+    }
+}
+
+impl Delivery {
+    /// Count all deliveries of [webhook_id]
+    pub async fn count_all(webhook_id: u32, db: &impl ConnectionTrait) -> Result<u64, CurdError> {
+        webhook_delivery::Entity::find()
+            .filter(webhook_delivery::Column::WebhookId.eq(webhook_id))
+            .count(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )
+    }
+
+    /// Fetch deliveries of [webhook_id], newest first. Use pagination
+    pub async fn find_all_paginated(webhook_id: u32, db: &impl ConnectionTrait, page: u64, size: u64) -> Result<Vec<Self>, CurdError> {
+        let models = webhook_delivery::Entity::find()
+            .filter(webhook_delivery::Column::WebhookId.eq(webhook_id))
+            .order_by_desc(webhook_delivery::Column::Id)
+            .offset(page * size)
+            .limit(size)
+            .all(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+        Ok(models.into_iter().map(Self::from).collect())
+    }
+}
+
+/// Create pending deliveries of [event] for all active webhooks of the user whose
+/// filters match
+pub async fn enqueue(event: &Event, db: &impl ConnectionTrait) -> Result<(), CurdError> {
+    let webhooks = webhook::Entity::find()
+        .filter(webhook::Column::UserId.eq(event.user_id))
+        .filter(webhook::Column::DeletedAt.is_null())
+        .filter(webhook::Column::Active.eq(true))
+        .all(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    let payload = serde_json::to_string(event)
+        .map_err(|e| CurdError::InternalError(e.to_string()))?;
+    let now = chrono::Utc::now();
+    for webhook in webhooks {
+        if !split_events(&webhook.events).iter().any(|filter| event.matches(filter)) {
+            continue;
+        }
+        let model = webhook_delivery::ActiveModel {
+            id: NotSet,
+            created_at: Set(now),
+            webhook_id: Set(webhook.id),
+            event_type: Set(event.event_type.clone()),
+            payload: Set(payload.clone()),
+            state: Set(DeliveryState::Pending),
+            attempts: Set(0),
+            next_attempt_at: Set(Some(now)),
+            delivered_at: Set(None),
+            response_status: Set(None),
+            error: Set(None),
+        };
+        webhook_delivery::Entity::insert(model)
+            .exec(db)
+            .await
+            .map_err(
+                |error| {
+                    CurdError::DbErr(error)
+                }
+            )?;
+    }
+    Ok(())
+}
+
+/// Pending deliveries whose next attempt is due, with their webhook
+pub async fn find_due(
+    limit: u64,
+    db: &impl ConnectionTrait,
+) -> Result<Vec<(webhook_delivery::Model, webhook::Model)>, CurdError> {
+    let rows = webhook_delivery::Entity::find()
+        .find_also_related(webhook::Entity)
+        .filter(webhook_delivery::Column::State.eq(DeliveryState::Pending))
+        .filter(webhook_delivery::Column::NextAttemptAt.lte(chrono::Utc::now()))
+        .order_by_asc(webhook_delivery::Column::NextAttemptAt)
+        .limit(limit)
+        .all(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(
+        rows.into_iter()
+            .filter_map(|(delivery, webhook)| webhook.map(|webhook| (delivery, webhook)))
+            .collect()
+    )
+}
+
+/// Record an attempt of [delivery]. On failure, the next attempt is scheduled with
+/// exponential backoff, until [MAX_ATTEMPTS] is reached.
+pub async fn record_attempt(
+    delivery: webhook_delivery::Model,
+    response_status: Option<u16>,
+    error: Option<String>,
+    db: &impl ConnectionTrait,
+) -> Result<(), CurdError> {
+    let now = chrono::Utc::now();
+    let attempts = delivery.attempts + 1;
+    let success = error.is_none() && matches!(response_status, Some(status) if (200..300).contains(&status));
+    let (state, next_attempt_at, delivered_at) = if success {
+        (DeliveryState::Delivered, None, Some(now))
+    } else if attempts >= MAX_ATTEMPTS {
+        (DeliveryState::Failed, None, None)
+    } else {
+        let delay = RETRY_DELAY * 2i32.pow(attempts - 1);
+        (DeliveryState::Pending, Some(now + delay.min(MAX_RETRY_DELAY)), None)
+    };
+
+    let mut model: webhook_delivery::ActiveModel = delivery.into();
+    model.state = Set(state);
+    model.attempts = Set(attempts);
+    model.next_attempt_at = Set(next_attempt_at);
+    model.delivered_at = Set(delivered_at);
+    model.response_status = Set(response_status.map(u32::from));
+    model.error = Set(error);
+    model.update(db)
+        .await
+        .map_err(
+            |error| {
+                CurdError::DbErr(error)
+            }
+        )?;
+    Ok(())
+}
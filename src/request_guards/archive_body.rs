@@ -0,0 +1,125 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::ops::Deref;
+use rocket::Request;
+use rocket::data::{ByteUnit, Data, FromData, Outcome};
+use rocket::http::{ContentType, Status};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::{map, schemars::schema::{InstanceType, SchemaObject}};
+use rocket_okapi::okapi::openapi3::{MediaType, Object, RequestBody};
+use rocket_okapi::request::OpenApiFromData;
+use crate::model::archive::Archive;
+use crate::routes::ApiError;
+
+/// Name of the Rocket limit for archive uploads
+pub const IMPORT_LIMIT: &str = "import";
+/// Limit for archive uploads if not configured
+pub const DEFAULT_IMPORT_LIMIT: ByteUnit = ByteUnit::Mebibyte(32);
+
+/// Account archive request body, either the ZIP file written by the export with
+/// `Content-Type: application/zip` or the [Archive] as JSON with
+/// `Content-Type: application/json`
+#[derive(Debug)]
+pub struct ArchiveBody(pub Archive);
+
+impl ArchiveBody {
+    /// Unwrap the archive
+    pub fn into_inner(self) -> Archive {
+        self.0
+    }
+}
+
+impl Deref for ArchiveBody {
+    type Target = Archive;
+
+    fn deref(&self) -> &Archive {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for ArchiveBody {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        let is_zip = match request.content_type() {
+            Some(content_type) if *content_type == ContentType::ZIP => true,
+            Some(content_type) if content_type.is_json() => false,
+            _ => {
+                return Outcome::Error(
+                    ApiError::from_status(Status::UnsupportedMediaType)
+                        .with_description("Expected application/zip or application/json")
+                        .cache_for_catcher(request)
+                );
+            },
+        };
+
+        let limit = request.limits().get(IMPORT_LIMIT).unwrap_or(DEFAULT_IMPORT_LIMIT);
+        let body = match data.open(limit).into_bytes().await {
+            Ok(body) if body.is_complete() => body.into_inner(),
+            Ok(_) => {
+                return Outcome::Error(
+                    ApiError::new_payload_too_large()
+                        .with_description(format!("Request body exceeds the limit of {}", limit))
+                        .cache_for_catcher(request)
+                );
+            },
+            Err(e) => {
+                return Outcome::Error(
+                    ApiError::new_bad_request()
+                        .with_description(e.to_string())
+                        .cache_for_catcher(request)
+                );
+            },
+        };
+
+        let archive = if is_zip {
+            Archive::from_zip(&body).map_err(ApiError::from)
+        } else {
+            serde_json::from_slice(&body)
+                .map_err(
+                    |e| {
+                        ApiError::new_unprocessable_entity()
+                            .with_description(e.to_string())
+                    }
+                )
+        };
+        match archive {
+            Ok(archive) => Outcome::Success(ArchiveBody(archive)),
+            Err(e) => Outcome::Error(e.cache_for_catcher(request)),
+        }
+    }
+}
+
+impl<'r> OpenApiFromData<'r> for ArchiveBody {
+    fn request_body(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<RequestBody> {
+        let zip_schema = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            format: Some("binary".to_string()),
+            ..Default::default()
+        };
+        Ok(
+            RequestBody {
+                description: Some("ZIP file of `GET /user/export.zip` or the archive as JSON".to_string()),
+                content: map! {
+                    ContentType::ZIP.to_string() => MediaType {
+                        schema: Some(zip_schema),
+                        ..Default::default()
+                    },
+                    ContentType::JSON.to_string() => MediaType {
+                        schema: Some(gen.json_schema::<Archive>()),
+                        ..Default::default()
+                    }
+                },
+                required: true,
+                extensions: Object::new(),
+            }
+        )
+    }
+}
@@ -14,10 +14,11 @@ use rocket::{
 use rocket_okapi::gen::OpenApiGenerator;
 use rocket_okapi::okapi::openapi3::{Object, SecurityRequirement, SecurityScheme, SecuritySchemeData};
 use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
-use sea_orm::{prelude::*, ActiveValue::Set};
-use jwt_auth::jwt::TokenVerifier;
+use sea_orm::{prelude::*, ActiveValue::Set, SqlErr};
+use jwt_auth::jwt::{claim_contains, ClaimPolicy, TokenVerifier, TOKEN_USE_CLAIM, TOKEN_USE_REFRESH};
 use crate::routes::ApiError;
-use crate::fairings::auth_cache::TokenInfo;
+use crate::fairings::{AuthCache, Database, RequestId, auth_cache::TokenInfo};
+use crate::model::audit::Actor;
 
 /// Request Guard for authentication. It investigates the Authorization HTTP header
 /// for a valid JWT. It looks up the user according to the Issuer and Subject fields
@@ -26,16 +27,47 @@ pub struct Auth<Val: JwtValidator> {
     jwt_validator: Val,
     /// ID of the user in the database
     pub user_id: u32,
+    /// ID of the request, for the audit log
+    request_id: String,
+}
+
+impl<Val: JwtValidator> Auth<Val> {
+    /// Authenticated user as originator of changes
+    pub fn actor(&self) -> Actor {
+        Actor::new(Some(self.user_id), Some(self.request_id.clone()))
+    }
+}
+
+/// ID of the authenticated user of a request, for fairings which run after the
+/// request guards
+#[derive(Clone, Copy)]
+pub struct AuthenticatedUser(Option<u32>);
+
+impl AuthenticatedUser {
+    /// User ID if [request] was authenticated by an [Auth] guard
+    pub fn of(request: &Request<'_>) -> Option<u32> {
+        request.local_cache(|| AuthenticatedUser(None)).0
+    }
 }
 
 /// Validate the JSON Web Token
 pub trait JwtValidator: Sized + Send {
     /// Validate the claims of a JSON Web Token
     fn validate(claims: &serde_json::Value) -> Result<Self, String>;
+
+    /// Scopes the token must grant, listed in the security requirement of the route
+    fn scopes() -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Whether the token is used for writes, which are rejected during maintenance
+    fn writes() -> bool {
+        false
+    }
 }
 
 /// Retrieve auth cache from Rocket state
-fn get_auth_cache<'r>(request: &'r Request<'_>) -> Result<&'r crate::fairings::AuthCache, ApiError> {
+fn get_auth_cache<'r>(request: &'r Request<'_>) -> Result<&'r AuthCache, ApiError> {
     Ok(
         request
             .rocket()
@@ -48,7 +80,7 @@ fn get_auth_cache<'r>(request: &'r Request<'_>) -> Result<&'r crate::fairings::A
 }
 
 /// Retrieve DB from Rocket state
-fn get_db<'r>(request: &'r Request<'_>) -> Result<&'r crate::fairings::Database, ApiError> {
+fn get_db<'r>(request: &'r Request<'_>) -> Result<&'r Database, ApiError> {
     Ok(
         request
             .rocket()
@@ -60,20 +92,14 @@ fn get_db<'r>(request: &'r Request<'_>) -> Result<&'r crate::fairings::Database,
     )
 }
 
-async fn lookup_or_make_user<'r>(request: &'r Request<'_>, token: &TokenInfo) -> Result<u32, ApiError> {
+async fn lookup_or_make_user(auth_cache: &AuthCache, db: &Database, token: &TokenInfo) -> Result<u32, ApiError> {
     use entity::user::{Entity as UserEntity, Column as UserColumn, ActiveModel as UserActiveModel};
 
-    let auth_cache = get_auth_cache(request)?;
-    let mut model_cache = auth_cache
-        .user_model_cache
-        .write()
-        .await;
+    let model_cache = &auth_cache.user_model_cache;
 
-    let user_id = match model_cache.get(token) {
-        Some(id) => *id,
+    let user_id = match model_cache.get(token).await {
+        Some(id) => id,
         None => {
-            let db = get_db(request)?;
-
             let user = UserEntity::find()
                 .filter(UserColumn::JwtIssuer.eq(token.issuer.as_str()))
                 .filter(UserColumn::JwtSubject.eq(token.subject.as_str()))
@@ -84,7 +110,7 @@ async fn lookup_or_make_user<'r>(request: &'r Request<'_>, token: &TokenInfo) ->
                 })?;
             match user {
                 Some(user) => {
-                    model_cache.insert(token.clone(), user.id);
+                    model_cache.insert(token, user.id).await;
                     user.id
                 },
                 None => {
@@ -94,13 +120,23 @@ async fn lookup_or_make_user<'r>(request: &'r Request<'_>, token: &TokenInfo) ->
                         name: Set(None),
                         ..Default::default()
                     };
-                    let model = model
-                        .insert(db.conn.as_ref())
-                        .await
-                        .map_err(|db_err| {
-                            ApiError::from(db_err)
-                        })?;
-                    model.id
+                    match model.insert(db.conn.as_ref()).await {
+                        Ok(model) => model.id,
+                        // Another instance created the user concurrently
+                        Err(db_err) if matches!(db_err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                            UserEntity::find()
+                                .filter(UserColumn::JwtIssuer.eq(token.issuer.as_str()))
+                                .filter(UserColumn::JwtSubject.eq(token.subject.as_str()))
+                                .one(db.conn.as_ref())
+                                .await
+                                .map_err(|db_err| {
+                                    ApiError::from(db_err)
+                                })?
+                                .ok_or_else(ApiError::new_internal_server_error)?
+                                .id
+                        },
+                        Err(db_err) => Err(ApiError::from(db_err))?,
+                    }
                 },
             }
         }
@@ -111,10 +147,9 @@ async fn lookup_or_make_user<'r>(request: &'r Request<'_>, token: &TokenInfo) ->
 
 /// Validate bearer and extract JWT information
 async fn validate_bearer(
-    request: &Request<'_>,
+    auth_cache: &AuthCache,
     bearer: &str,
 ) -> Result<(TokenInfo, serde_json::Value), ApiError> {
-    let auth_cache = get_auth_cache(request)?;
     let mut key_cache = auth_cache
         .key_cache
         .write()
@@ -131,6 +166,13 @@ async fn validate_bearer(
     match verifier.verify(bearer)
     {
         Ok((token, _)) => {
+            // Refresh tokens may only be exchanged, but never grant access
+            if token.claims().private.get(TOKEN_USE_CLAIM).and_then(|value| value.as_str()) == Some(TOKEN_USE_REFRESH) {
+                Err(
+                    ApiError::new_unauthorized()
+                        .with_description("Refresh token cannot be used for access")
+                )?;
+            }
             let issuer = match &token.claims().registered.issuer {
                 Some(issuer) => issuer.clone(),
                 None => Err(
@@ -170,43 +212,78 @@ async fn validate_bearer(
     }
 }
 
+/// Authenticate the bearer token [bearer] and validate its claims with [Val]. Returns
+/// the validator and the ID of the user, who is created on first sight.
+pub async fn authenticate<Val: JwtValidator>(
+    auth_cache: &AuthCache,
+    db: &Database,
+    bearer: &str,
+) -> Result<(Val, u32), ApiError> {
+    let (token, claims) = validate_bearer(auth_cache, bearer).await?;
+    let val = Val::validate(&claims)
+        .map_err(
+            |e| {
+                ApiError::new_unauthorized()
+                    .with_description(e)
+            }
+        )?;
+    let user_id = lookup_or_make_user(auth_cache, db, &token).await?;
+    Ok((val, user_id))
+}
+
+/// Authenticate as [dev_user] of the insecure development authentication, who is
+/// granted all scopes. No token is verified.
+pub async fn authenticate_dev<Val: JwtValidator>(
+    auth_cache: &AuthCache,
+    db: &Database,
+    dev_user: &TokenInfo,
+) -> Result<(Val, u32), ApiError> {
+    let claims = serde_json::json!({
+        "iss": dev_user.issuer,
+        "sub": dev_user.subject,
+        "ptet:write": true,
+        "ptet:admin": true,
+    });
+    let val = Val::validate(&claims)
+        .map_err(
+            |e| {
+                ApiError::new_unauthorized()
+                    .with_description(e)
+            }
+        )?;
+    let user_id = lookup_or_make_user(auth_cache, db, dev_user).await?;
+    Ok((val, user_id))
+}
+
 #[rocket::async_trait]
 impl<'r, Val: JwtValidator> FromRequest<'r> for Auth<Val> {
     type Error = ApiError;
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        if let Some(auth) = request.headers().get_one("Authorization") {
-            if auth.starts_with("Bearer ") {
-                let token = &auth[7..];
-                match validate_bearer(request, token).await {
-                    Ok((token, claims)) => {
-                        match Val::validate(&claims) {
-                            Ok(val) => match lookup_or_make_user(request, &token).await {
-                                Ok(user_id) => Outcome::Success(Auth { jwt_validator: val, user_id }),
-                                Err(err) => Outcome::Error(err.into()),
-                            },
-                            Err(e) => Outcome::Error(
-                                ApiError::new_unauthorized()
-                                    .with_description(e.to_string())
-                                    .into()
-                            )
-                        }
-                    },
-                    Err(err) => Outcome::Error(err.into()),
-                }
-            } else {
-                Outcome::Error(
+        let result = match (get_auth_cache(request), get_db(request)) {
+            (Ok(auth_cache), Ok(db)) => match (&auth_cache.dev_user, request.headers().get_one("Authorization")) {
+                // The Authorization header is ignored in the development mode
+                (Some(dev_user), _) => authenticate_dev::<Val>(auth_cache, db, dev_user).await,
+                (None, Some(auth)) => match auth.strip_prefix("Bearer ") {
+                    Some(token) => authenticate::<Val>(auth_cache, db, token).await,
+                    None => Err(
+                        ApiError::new_bad_request()
+                            .with_description("Authorization must be Bearer")
+                    ),
+                },
+                (None, None) => Err(
                     ApiError::new_bad_request()
-                        .with_description("Authorization must be Bearer")
-                        .into()
-                )
-            }
-        } else {
-            Outcome::Error(
-                ApiError::new_bad_request()
-                    .with_description("Authorization header is missing")
-                    .into()
-            )
+                        .with_description("Authorization header is missing")
+                ),
+            },
+            (Err(err), _) | (_, Err(err)) => Err(err),
+        };
+        match result {
+            Ok((val, user_id)) => {
+                request.local_cache(|| AuthenticatedUser(Some(user_id)));
+                Outcome::Success(Auth { jwt_validator: val, user_id, request_id: RequestId::of(request).to_string() })
+            },
+            Err(err) => Outcome::Error(err.cache_for_catcher(request)),
         }
     }
 }
@@ -222,7 +299,7 @@ impl<Val: JwtValidator> OpenApiFromRequest<'_> for Auth<Val> {
                 "BearerAuth".to_string(),
                 SecurityScheme{
                     description: Some(
-                        "JWT is required for authentication".to_string()
+                        "JWT is required for authentication. Scopes `ptet:write` and `ptet:admin` are granted by the claims `ptet:write: true` and `ptet:admin: true` or realm roles of the same names.".to_string()
                     ),
                     data: SecuritySchemeData::Http {
                         scheme: "bearer".to_string(),
@@ -236,7 +313,7 @@ impl<Val: JwtValidator> OpenApiFromRequest<'_> for Auth<Val> {
                     [
                         (
                             "BearerAuth".to_string(),
-                            Vec::new(),
+                            Val::scopes(),
                         ),
                     ]
                 ),
@@ -245,6 +322,11 @@ impl<Val: JwtValidator> OpenApiFromRequest<'_> for Auth<Val> {
     }
 }
 
+/// Check if the token carries realm role [role] (`realm_access.roles` claim)
+pub fn has_role(claims: &serde_json::Value, role: &str) -> bool {
+    claim_contains(claims, "realm_access.roles", role)
+}
+
 /// Validates that a token grants read-only access
 pub struct ReadOnly {}
 
@@ -259,14 +341,43 @@ pub struct ReadWrite {}
 
 impl JwtValidator for ReadWrite {
     fn validate(claims: &serde_json::Value) -> Result<Self, String> {
-        if let Some(flag) = claims["ptet:write"].as_bool() {
-            if flag {
-                Ok(ReadWrite {})
-            } else {
-                Err("ptet:write claim is false".to_string())
-            }
+        // Write access is granted by the ptet:write claim or, for Keycloak-style
+        // identity providers, by a realm role of the same name
+        let flag = ClaimPolicy::new()
+            .require_claim("ptet:write", serde_json::Value::Bool(true))
+            .check(claims);
+        if flag.is_ok() || has_role(claims, "ptet:write") {
+            Ok(ReadWrite {})
+        } else {
+            Err(flag.unwrap_err().to_string())
+        }
+    }
+
+    fn scopes() -> Vec<String> {
+        vec!["ptet:write".to_string()]
+    }
+
+    fn writes() -> bool {
+        true
+    }
+}
+
+/// Validates that a token grants administrative access, e.g. to soft-deleted resources
+pub struct Admin {}
+
+impl JwtValidator for Admin {
+    fn validate(claims: &serde_json::Value) -> Result<Self, String> {
+        let flag = ClaimPolicy::new()
+            .require_claim("ptet:admin", serde_json::Value::Bool(true))
+            .check(claims);
+        if flag.is_ok() || has_role(claims, "ptet:admin") {
+            Ok(Admin {})
         } else {
-            Err("No ptet:write claim in JWT".to_string())
+            Err(flag.unwrap_err().to_string())
         }
     }
+
+    fn scopes() -> Vec<String> {
+        vec!["ptet:admin".to_string()]
+    }
 }
@@ -0,0 +1,90 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, Parameter, ParameterValue};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use crate::routes::ApiError;
+use super::auth::{Admin, Auth};
+
+/// Query parameter for including soft-deleted resources
+pub const INCLUDE_DELETED_QUERY: &str = "include_deleted";
+
+/// Request Guard for the query parameter `include_deleted`. Soft-deleted resources are
+/// only included for tokens with administrative access, the request fails otherwise.
+pub struct IncludeDeleted {
+    include_deleted: bool,
+}
+
+impl IncludeDeleted {
+    /// Whether soft-deleted resources are to be included
+    pub fn is_set(&self) -> bool {
+        self.include_deleted
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IncludeDeleted {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let include_deleted = match request.query_value::<bool>(INCLUDE_DELETED_QUERY) {
+            Some(Ok(include_deleted)) => include_deleted,
+            Some(Err(_)) => {
+                return Outcome::Error(
+                    ApiError::new_bad_request()
+                        .with_description(format!("{} must be true or false", INCLUDE_DELETED_QUERY))
+                        .cache_for_catcher(request)
+                );
+            },
+            None => false,
+        };
+        if include_deleted {
+            match request.guard::<Auth<Admin>>().await {
+                Outcome::Success(_) => {},
+                Outcome::Error(error) => return Outcome::Error(error),
+                Outcome::Forward(status) => return Outcome::Forward(status),
+            }
+        }
+        Outcome::Success(IncludeDeleted { include_deleted })
+    }
+}
+
+impl OpenApiFromRequest<'_> for IncludeDeleted {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(
+            RequestHeaderInput::Parameter(
+                Parameter {
+                    name: INCLUDE_DELETED_QUERY.to_string(),
+                    location: "query".to_string(),
+                    description: Some(
+                        "Also return soft-deleted resources, which carry `deleted_at`. Requires scope `ptet:admin`.".to_string()
+                    ),
+                    required: false,
+                    deprecated: false,
+                    allow_empty_value: false,
+                    value: ParameterValue::Schema {
+                        style: None,
+                        explode: None,
+                        allow_reserved: false,
+                        schema: gen.json_schema_no_ref::<bool>(),
+                        example: None,
+                        examples: None,
+                    },
+                    extensions: Object::new(),
+                }
+            )
+        )
+    }
+}
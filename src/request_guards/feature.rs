@@ -0,0 +1,72 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::marker::PhantomData;
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use crate::model::feature::{self, FeatureFlags};
+use crate::routes::ApiError;
+
+/// Feature flag checked by [Enabled]
+pub trait Feature: Send {
+    /// Name of the flag
+    const NAME: &'static str;
+}
+
+/// Flag [feature::WEBHOOKS]
+pub struct Webhooks;
+
+impl Feature for Webhooks {
+    const NAME: &'static str = feature::WEBHOOKS;
+}
+
+/// Flag [feature::EVENTS]
+pub struct Events;
+
+impl Feature for Events {
+    const NAME: &'static str = feature::EVENTS;
+}
+
+/// Request Guard for routes of an optional feature. The request fails with 404 if the
+/// feature [F] is disabled.
+pub struct Enabled<F: Feature> {
+    feature: PhantomData<F>,
+}
+
+#[rocket::async_trait]
+impl<'r, F: Feature> FromRequest<'r> for Enabled<F> {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let enabled = request
+            .rocket()
+            .state::<FeatureFlags>()
+            .is_some_and(|flags| flags.is_enabled(F::NAME));
+        if enabled {
+            Outcome::Success(Enabled { feature: PhantomData })
+        } else {
+            Outcome::Error(
+                ApiError::new_not_found()
+                    .with_description(format!("Feature {} is disabled", F::NAME))
+                    .cache_for_catcher(request)
+            )
+        }
+    }
+}
+
+impl<F: Feature> OpenApiFromRequest<'_> for Enabled<F> {
+    fn from_request_input(
+        _gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(RequestHeaderInput::None)
+    }
+}
@@ -0,0 +1,86 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, Parameter, ParameterValue};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use crate::responders::Sparse;
+
+/// Query parameter listing the fields to serialize
+pub const FIELDS_QUERY: &str = "fields";
+
+/// Request Guard for sparse fieldsets. The query parameter `fields` is a comma-separated
+/// list of the fields to serialize, e.g. `fields=id,location_from`. All fields are
+/// serialized if it is missing or empty.
+pub struct FieldSet {
+    fields: Option<BTreeSet<String>>,
+}
+
+impl FieldSet {
+    /// Respond with the requested fields of [value]
+    pub fn apply<T>(&self, value: T) -> Sparse<T> {
+        Sparse::new(value, self.fields.clone())
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for FieldSet {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let fields = match request.query_value::<&str>(FIELDS_QUERY) {
+            Some(Ok(fields)) => {
+                let fields: BTreeSet<String> = fields
+                    .split(',')
+                    .map(|field| field.trim())
+                    .filter(|field| !field.is_empty())
+                    .map(|field| field.to_string())
+                    .collect();
+                Some(fields).filter(|fields| !fields.is_empty())
+            },
+            _ => None,
+        };
+        Outcome::Success(FieldSet { fields })
+    }
+}
+
+impl OpenApiFromRequest<'_> for FieldSet {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(
+            RequestHeaderInput::Parameter(
+                Parameter {
+                    name: FIELDS_QUERY.to_string(),
+                    location: "query".to_string(),
+                    description: Some(
+                        "Comma-separated fields to serialize. `id` and `_links` are always included.".to_string()
+                    ),
+                    required: false,
+                    deprecated: false,
+                    allow_empty_value: false,
+                    value: ParameterValue::Schema {
+                        style: None,
+                        explode: None,
+                        allow_reserved: false,
+                        schema: gen.json_schema_no_ref::<String>(),
+                        example: None,
+                        examples: None,
+                    },
+                    extensions: Object::new(),
+                }
+            )
+        )
+    }
+}
@@ -0,0 +1,84 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::data::{Data, FromData, Outcome};
+use rocket::http::Status;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::{map, schemars::schema::{InstanceType, SchemaObject}};
+use rocket_okapi::okapi::openapi3::{MediaType, Object, RequestBody};
+use rocket_okapi::request::OpenApiFromData;
+use crate::routes::ApiError;
+use super::archive_body::{DEFAULT_IMPORT_LIMIT, IMPORT_LIMIT};
+
+/// GPX document as request body with `Content-Type: application/gpx+xml`,
+/// `application/xml` or `text/xml`. Limited like archive imports.
+#[derive(Debug)]
+pub struct GpxBody(pub String);
+
+impl GpxBody {
+    /// Unwrap the document
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for GpxBody {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        match request.content_type() {
+            Some(content_type) if matches!((content_type.top().as_str(), content_type.sub().as_str()), ("application", "gpx+xml") | ("application", "xml") | ("text", "xml")) => {},
+            _ => {
+                return Outcome::Error(
+                    ApiError::from_status(Status::UnsupportedMediaType)
+                        .with_description("Expected application/gpx+xml")
+                        .cache_for_catcher(request)
+                );
+            },
+        }
+
+        let limit = request.limits().get(IMPORT_LIMIT).unwrap_or(DEFAULT_IMPORT_LIMIT);
+        match data.open(limit).into_string().await {
+            Ok(body) if body.is_complete() => Outcome::Success(GpxBody(body.into_inner())),
+            Ok(_) => Outcome::Error(
+                ApiError::new_payload_too_large()
+                    .with_description(format!("Request body exceeds the limit of {}", limit))
+                    .cache_for_catcher(request)
+            ),
+            Err(e) => Outcome::Error(
+                ApiError::new_bad_request()
+                    .with_description(e.to_string())
+                    .cache_for_catcher(request)
+            ),
+        }
+    }
+}
+
+impl<'r> OpenApiFromData<'r> for GpxBody {
+    fn request_body(_gen: &mut OpenApiGenerator) -> rocket_okapi::Result<RequestBody> {
+        let schema = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            ..Default::default()
+        };
+        Ok(
+            RequestBody {
+                description: Some("GPX document with at least one track point".to_string()),
+                content: map! {
+                    "application/gpx+xml".to_string() => MediaType {
+                        schema: Some(schema),
+                        ..Default::default()
+                    }
+                },
+                required: true,
+                extensions: Object::new(),
+            }
+        )
+    }
+}
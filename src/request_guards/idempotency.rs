@@ -0,0 +1,149 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::future::Future;
+use rocket::Request;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, Parameter, ParameterValue};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use sea_orm::ConnectionTrait;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use crate::model::idempotency::{self, Lookup};
+use crate::responders::Idempotent;
+use crate::routes::ApiError;
+
+/// Header carrying the idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// Maximum length of an idempotency key
+const MAX_KEY_LEN: usize = 255;
+
+/// Request Guard for the optional `Idempotency-Key` header. If the header is given, the
+/// response is recorded and replayed for retries with the same key, instead of
+/// executing the request again.
+pub struct IdempotencyKey {
+    key: Option<String>,
+    /// Method and path of the request, part of the fingerprint
+    target: String,
+}
+
+impl IdempotencyKey {
+    /// Execute [operation] unless the key was already used by [user_id] for the same
+    /// request with [body]. Without a key, [operation] is always executed.
+    pub async fn run<T, F, Fut>(
+        &self,
+        user_id: u32,
+        body: &impl Serialize,
+        db: &impl ConnectionTrait,
+        operation: F,
+    ) -> Result<Idempotent<T>, ApiError>
+    where
+        T: Serialize,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        let key = match &self.key {
+            Some(key) => key,
+            None => {
+                let result = operation().await?;
+                return Ok(Idempotent::new(Status::Ok, Self::serialize(&result)?, false));
+            },
+        };
+
+        let id = match idempotency::begin(user_id, key, &self.fingerprint(body)?, db).await? {
+            Lookup::New(id) => id,
+            Lookup::Replay { status, body } => {
+                let status = Status::from_code(status).unwrap_or(Status::Ok);
+                return Ok(Idempotent::new(status, body, true));
+            },
+        };
+        match operation().await {
+            Ok(result) => {
+                let body = Self::serialize(&result)?;
+                idempotency::complete(id, Status::Ok.code, &body, db).await?;
+                Ok(Idempotent::new(Status::Ok, body, false))
+            },
+            Err(e) => {
+                idempotency::release(id, db).await?;
+                Err(e)
+            },
+        }
+    }
+
+    fn serialize(value: &impl Serialize) -> Result<String, ApiError> {
+        serde_json::to_string(value).map_err(|e| {
+            ApiError::new_internal_server_error()
+                .with_description(e.to_string())
+        })
+    }
+
+    /// Hash of method, path and [body]
+    fn fingerprint(&self, body: &impl Serialize) -> Result<String, ApiError> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.target.as_bytes());
+        hasher.update(b"\n");
+        hasher.update(Self::serialize(body)?.as_bytes());
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let key = request.headers().get_one(IDEMPOTENCY_KEY_HEADER);
+        if let Some(key) = key {
+            if key.is_empty() || key.len() > MAX_KEY_LEN {
+                return Outcome::Error(
+                    ApiError::new_bad_request()
+                        .with_description(format!("{} must have 1 to {} characters", IDEMPOTENCY_KEY_HEADER, MAX_KEY_LEN))
+                        .cache_for_catcher(request)
+                );
+            }
+        }
+        Outcome::Success(IdempotencyKey {
+            key: key.map(|key| key.to_string()),
+            target: format!("{} {}", request.method(), request.uri().path()),
+        })
+    }
+}
+
+impl OpenApiFromRequest<'_> for IdempotencyKey {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(
+            RequestHeaderInput::Parameter(
+                Parameter {
+                    name: IDEMPOTENCY_KEY_HEADER.to_string(),
+                    location: "header".to_string(),
+                    description: Some(
+                        "Retries with the same key return the recorded response instead of creating the resource again".to_string()
+                    ),
+                    required: false,
+                    deprecated: false,
+                    allow_empty_value: false,
+                    value: ParameterValue::Schema {
+                        style: None,
+                        explode: None,
+                        allow_reserved: false,
+                        schema: gen.json_schema_no_ref::<String>(),
+                        example: None,
+                        examples: None,
+                    },
+                    extensions: Object::new(),
+                }
+            )
+        )
+    }
+}
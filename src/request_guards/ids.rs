@@ -0,0 +1,103 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, Parameter, ParameterValue};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use crate::routes::ApiError;
+
+/// Query parameter listing the IDs to fetch
+pub const IDS_QUERY: &str = "ids";
+/// Maximum number of IDs in one request
+pub const MAX_IDS: usize = 100;
+
+/// Request Guard for fetching a list by IDs. The query parameter `ids` is a
+/// comma-separated list of IDs, e.g. `ids=1,2,3`. The whole list is requested if it is
+/// missing.
+pub struct IdFilter {
+    ids: Option<Vec<u32>>,
+}
+
+impl IdFilter {
+    /// Requested IDs, `None` if not filtered by IDs
+    pub fn ids(&self) -> Option<&[u32]> {
+        self.ids.as_deref()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdFilter {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let ids = match request.query_value::<&str>(IDS_QUERY) {
+            Some(Ok(ids)) => {
+                let ids: Result<Vec<u32>, _> = ids
+                    .split(',')
+                    .map(|id| id.trim())
+                    .filter(|id| !id.is_empty())
+                    .map(|id| id.parse::<u32>().map_err(|_| id))
+                    .collect();
+                let ids = match ids {
+                    Ok(ids) => ids,
+                    Err(invalid) => {
+                        return Outcome::Error(
+                            ApiError::new_bad_request()
+                                .with_description(format!("Invalid ID '{}'", invalid))
+                                .cache_for_catcher(request)
+                        );
+                    },
+                };
+                if ids.len() > MAX_IDS {
+                    return Outcome::Error(
+                        ApiError::new_bad_request()
+                            .with_description(format!("At most {} IDs can be requested at once", MAX_IDS))
+                            .cache_for_catcher(request)
+                    );
+                }
+                Some(ids)
+            },
+            _ => None,
+        };
+        Outcome::Success(IdFilter { ids })
+    }
+}
+
+impl OpenApiFromRequest<'_> for IdFilter {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(
+            RequestHeaderInput::Parameter(
+                Parameter {
+                    name: IDS_QUERY.to_string(),
+                    location: "query".to_string(),
+                    description: Some(
+                        format!("Comma-separated IDs to fetch, at most {}. IDs which do not exist are left out. Cannot be combined with pagination.", MAX_IDS)
+                    ),
+                    required: false,
+                    deprecated: false,
+                    allow_empty_value: true,
+                    value: ParameterValue::Schema {
+                        style: None,
+                        explode: None,
+                        allow_reserved: false,
+                        schema: gen.json_schema_no_ref::<String>(),
+                        example: None,
+                        examples: None,
+                    },
+                    extensions: Object::new(),
+                }
+            )
+        )
+    }
+}
@@ -0,0 +1,81 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::convert::Infallible;
+use chrono::{DateTime, Utc};
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, Parameter, ParameterValue};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+
+/// Header with the time of the client's copy
+pub const IF_MODIFIED_SINCE_HEADER: &str = "If-Modified-Since";
+
+/// Request Guard for the optional `If-Modified-Since` header. Invalid dates are ignored,
+/// as required by RFC 9110.
+pub struct IfModifiedSince {
+    since: Option<DateTime<Utc>>,
+}
+
+impl IfModifiedSince {
+    /// Whether the client's copy is still up to date with [last_modified]. HTTP dates have
+    /// a resolution of seconds, so sub-second changes are not distinguished.
+    pub fn is_fresh(&self, last_modified: Option<DateTime<Utc>>) -> bool {
+        match (self.since, last_modified) {
+            (Some(since), Some(last_modified)) => last_modified.timestamp() <= since.timestamp(),
+            _ => false,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfModifiedSince {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let since = request
+            .headers()
+            .get_one(IF_MODIFIED_SINCE_HEADER)
+            .and_then(|since| DateTime::parse_from_rfc2822(since).ok())
+            .map(|since| since.with_timezone(&Utc));
+        Outcome::Success(IfModifiedSince { since })
+    }
+}
+
+impl OpenApiFromRequest<'_> for IfModifiedSince {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(
+            RequestHeaderInput::Parameter(
+                Parameter {
+                    name: IF_MODIFIED_SINCE_HEADER.to_string(),
+                    location: "header".to_string(),
+                    description: Some(
+                        "Respond with 304 if the resource was not modified since this HTTP date".to_string()
+                    ),
+                    required: false,
+                    deprecated: false,
+                    allow_empty_value: false,
+                    value: ParameterValue::Schema {
+                        style: None,
+                        explode: None,
+                        allow_reserved: false,
+                        schema: gen.json_schema_no_ref::<String>(),
+                        example: None,
+                        examples: None,
+                    },
+                    extensions: Object::new(),
+                }
+            )
+        )
+    }
+}
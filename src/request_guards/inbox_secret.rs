@@ -0,0 +1,76 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, Parameter, ParameterValue};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use crate::model::inbox::Inbox;
+use crate::routes::ApiError;
+
+/// Header carrying the secret of the inbound mail webhook
+pub const INBOX_SECRET_HEADER: &str = "X-Ptet-Inbox-Secret";
+
+/// Request Guard for the inbound mail webhook, which is called by the mail service
+/// instead of a user. The request fails with 404 if the inbox is not configured and
+/// with 401 if the header [INBOX_SECRET_HEADER] does not match the configured secret.
+pub struct InboxSecret;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for InboxSecret {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(inbox) = request.rocket().state::<Inbox>().filter(|inbox| inbox.is_enabled()) else {
+            return Outcome::Error(
+                ApiError::new_not_found()
+                    .with_description("Inbox is not configured")
+                    .cache_for_catcher(request)
+            );
+        };
+        match request.headers().get_one(INBOX_SECRET_HEADER) {
+            Some(secret) if inbox.check_secret(secret) => Outcome::Success(InboxSecret),
+            _ => Outcome::Error(
+                ApiError::new_unauthorized()
+                    .with_description(format!("{} is missing or invalid", INBOX_SECRET_HEADER))
+                    .cache_for_catcher(request)
+            ),
+        }
+    }
+}
+
+impl OpenApiFromRequest<'_> for InboxSecret {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(
+            RequestHeaderInput::Parameter(
+                Parameter {
+                    name: INBOX_SECRET_HEADER.to_string(),
+                    location: "header".to_string(),
+                    description: Some("Secret of the inbound mail webhook, `inbox_secret`".to_string()),
+                    required: true,
+                    deprecated: false,
+                    allow_empty_value: false,
+                    value: ParameterValue::Schema {
+                        style: None,
+                        explode: None,
+                        allow_reserved: false,
+                        schema: gen.json_schema_no_ref::<String>(),
+                        example: None,
+                        examples: None,
+                    },
+                    extensions: Object::new(),
+                }
+            )
+        )
+    }
+}
@@ -0,0 +1,103 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeSet;
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, Parameter, ParameterValue};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use crate::routes::ApiError;
+
+/// Query parameter listing the embedded relations
+pub const INCLUDE_QUERY: &str = "include";
+/// Tag links of a ride
+pub const INCLUDE_TAGS: &str = "tags";
+/// Tag descriptor of a ride tag link
+pub const INCLUDE_TAG: &str = "tag";
+/// Options of a tag
+pub const INCLUDE_OPTIONS: &str = "options";
+/// All relations which can be embedded
+const RELATIONS: [&str; 3] = [INCLUDE_TAGS, INCLUDE_TAG, INCLUDE_OPTIONS];
+
+/// Request Guard for the embedded relations. The query parameter `include` is a
+/// comma-separated list of the relations to embed, e.g. `include=tags`. All relations are
+/// embedded if it is missing, none if it is empty.
+pub struct Include {
+    relations: Option<BTreeSet<String>>,
+}
+
+impl Include {
+    /// Whether [relation] is to be embedded
+    pub fn has(&self, relation: &str) -> bool {
+        match &self.relations {
+            Some(relations) => relations.contains(relation),
+            None => true,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Include {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let relations = match request.query_value::<&str>(INCLUDE_QUERY) {
+            Some(Ok(relations)) => {
+                let relations: BTreeSet<String> = relations
+                    .split(',')
+                    .map(|relation| relation.trim())
+                    .filter(|relation| !relation.is_empty())
+                    .map(|relation| relation.to_string())
+                    .collect();
+                if let Some(unknown) = relations.iter().find(|relation| !RELATIONS.contains(&relation.as_str())) {
+                    return Outcome::Error(
+                        ApiError::new_bad_request()
+                            .with_description(format!("Unknown relation '{}', expected one of {}", unknown, RELATIONS.join(", ")))
+                            .cache_for_catcher(request)
+                    );
+                }
+                Some(relations)
+            },
+            _ => None,
+        };
+        Outcome::Success(Include { relations })
+    }
+}
+
+impl OpenApiFromRequest<'_> for Include {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(
+            RequestHeaderInput::Parameter(
+                Parameter {
+                    name: INCLUDE_QUERY.to_string(),
+                    location: "query".to_string(),
+                    description: Some(
+                        "Comma-separated relations to embed: `tags` of rides, `tag` of ride tags and `options` of tags. All are embedded if missing.".to_string()
+                    ),
+                    required: false,
+                    deprecated: false,
+                    allow_empty_value: true,
+                    value: ParameterValue::Schema {
+                        style: None,
+                        explode: None,
+                        allow_reserved: false,
+                        schema: gen.json_schema_no_ref::<String>(),
+                        example: None,
+                        examples: None,
+                    },
+                    extensions: Object::new(),
+                }
+            )
+        )
+    }
+}
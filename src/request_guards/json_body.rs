@@ -0,0 +1,158 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::ops::{Deref, DerefMut};
+use rocket::Request;
+use rocket::data::{Data, FromData, Limits, Outcome};
+use rocket::serde::json::Json;
+use serde::de::DeserializeOwned;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::JsonSchema;
+use rocket_okapi::okapi::openapi3::RequestBody;
+use rocket_okapi::okapi::schemars::gen::SchemaGenerator;
+use rocket_okapi::okapi::schemars::schema::Schema;
+use serde_json::Value;
+use rocket_okapi::request::OpenApiFromData;
+use crate::routes::ApiError;
+
+/// Field of responses which is ignored in request bodies, so that a resource fetched with
+/// links can be sent back
+const LINKS_FIELD: &str = "_links";
+
+/// JSON request body
+///
+/// Works like Rocket's [Json], but reports failures as [ApiError]: 413 if the body
+/// exceeds the `json` limit, 400 if it is not valid JSON and 422 if it does not match
+/// the expected structure.
+///
+/// Bodies of types with `#[serde(deny_unknown_fields)]` are checked strictly, see
+/// [check_fields].
+#[derive(Debug)]
+pub struct JsonBody<T>(pub T);
+
+impl<T> JsonBody<T> {
+    /// Unwrap the deserialized body
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for JsonBody<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for JsonBody<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Check the fields of the JSON object [body] against the schema of [T], if [T] denies
+/// unknown fields. Returns a description of every unknown field and of every string
+/// longer than the `maxLength` of its field, e.g. set with
+/// `#[schemars(length(max = 200))]`. Read-only fields, like the `id` of a fetched
+/// resource sent back, are removed instead, so that they are ignored.
+fn check_fields<T: JsonSchema>(body: &mut Value) -> Vec<String> {
+    let Value::Object(fields) = body else {
+        return Vec::new();
+    };
+    let schema = SchemaGenerator::default().into_root_schema_for::<T>().schema;
+    let Some(object) = schema.object else {
+        return Vec::new();
+    };
+    if !matches!(object.additional_properties.as_deref(), Some(Schema::Bool(false))) {
+        return Vec::new();
+    }
+
+    let mut errors = Vec::new();
+    fields.retain(|name, value| {
+        let property = match object.properties.get(name) {
+            Some(Schema::Object(property)) => property,
+            Some(Schema::Bool(_)) => return true,
+            None if name == LINKS_FIELD => return false,
+            None => {
+                errors.push(format!("{} is unknown", name));
+                return true;
+            },
+        };
+        if property.metadata.as_ref().is_some_and(|metadata| metadata.read_only) {
+            return false;
+        }
+        let max_length = property.string.as_ref().and_then(|string| string.max_length);
+        if let (Some(max_length), Some(value)) = (max_length, value.as_str()) {
+            if value.chars().count() > max_length as usize {
+                errors.push(format!("{} is longer than {} characters", name, max_length));
+            }
+        }
+        true
+    });
+    errors
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned + JsonSchema> FromData<'r> for JsonBody<T> {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        let limit = request.limits().get("json").unwrap_or(Limits::JSON);
+        let body = match data.open(limit).into_string().await {
+            Ok(body) if body.is_complete() => body.into_inner(),
+            Ok(_) => {
+                return Outcome::Error(
+                    ApiError::new_payload_too_large()
+                        .with_description(format!("Request body exceeds the limit of {}", limit))
+                        .cache_for_catcher(request)
+                );
+            },
+            Err(e) => {
+                return Outcome::Error(
+                    ApiError::new_bad_request()
+                        .with_description(e.to_string())
+                        .cache_for_catcher(request)
+                );
+            },
+        };
+
+        let mut body: Value = match serde_json::from_str(&body) {
+            Ok(body) => body,
+            Err(e) => {
+                return Outcome::Error(
+                    ApiError::new_bad_request()
+                        .with_description(e.to_string())
+                        .cache_for_catcher(request)
+                );
+            },
+        };
+        let errors = check_fields::<T>(&mut body);
+        if !errors.is_empty() {
+            return Outcome::Error(
+                ApiError::new_unprocessable_entity()
+                    .with_description(format!("Invalid fields: {}", errors.join("; ")))
+                    .cache_for_catcher(request)
+            );
+        }
+        match serde_json::from_value(body) {
+            Ok(value) => Outcome::Success(JsonBody(value)),
+            Err(e) => Outcome::Error(
+                ApiError::new_unprocessable_entity()
+                    .with_description(e.to_string())
+                    .cache_for_catcher(request)
+            ),
+        }
+    }
+}
+
+impl<'r, T: JsonSchema + DeserializeOwned> OpenApiFromData<'r> for JsonBody<T> {
+    fn request_body(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<RequestBody> {
+        Json::<T>::request_body(gen)
+    }
+}
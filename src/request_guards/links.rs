@@ -0,0 +1,97 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::convert::Infallible;
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, Parameter, ParameterValue};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use crate::responders::linked::{Linkable, Linked};
+
+/// Query parameter requesting links
+pub const LINKS_QUERY: &str = "links";
+
+/// Request Guard for the opt-in `_links` of resources. Links are embedded if the query
+/// parameter `links=true` is given or `application/hal+json` is accepted.
+pub struct LinkProfile {
+    /// Path the API is mounted at, if links are requested
+    base: Option<String>,
+}
+
+impl LinkProfile {
+    /// Wrap [resource], with links if requested
+    pub fn wrap<T: Linkable>(&self, resource: T) -> Linked<T> {
+        let links = self.base.as_ref().map(|base| resource.links(base));
+        Linked::new(resource, links)
+    }
+
+    /// Wrap all [resources], with links if requested
+    pub fn wrap_all<T: Linkable>(&self, resources: Vec<T>) -> Vec<Linked<T>> {
+        resources
+            .into_iter()
+            .map(|resource| self.wrap(resource))
+            .collect()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LinkProfile {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let by_query = matches!(request.query_value::<bool>(LINKS_QUERY), Some(Ok(true)));
+        let by_accept = request
+            .accept()
+            .is_some_and(|accept| {
+                accept
+                    .media_types()
+                    .any(|media_type| media_type.top() == "application" && media_type.sub() == "hal+json")
+            });
+        let base = if by_query || by_accept {
+            request
+                .route()
+                .map(|route| route.uri.base().trim_end_matches('/').to_string())
+        } else {
+            None
+        };
+        Outcome::Success(LinkProfile { base })
+    }
+}
+
+impl OpenApiFromRequest<'_> for LinkProfile {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(
+            RequestHeaderInput::Parameter(
+                Parameter {
+                    name: LINKS_QUERY.to_string(),
+                    location: "query".to_string(),
+                    description: Some(
+                        "Embed `_links` to related resources, also enabled by accepting application/hal+json".to_string()
+                    ),
+                    required: false,
+                    deprecated: false,
+                    allow_empty_value: false,
+                    value: ParameterValue::Schema {
+                        style: None,
+                        explode: None,
+                        allow_reserved: false,
+                        schema: gen.json_schema_no_ref::<bool>(),
+                        example: None,
+                        examples: None,
+                    },
+                    extensions: Object::new(),
+                }
+            )
+        )
+    }
+}
@@ -0,0 +1,88 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, Parameter, ParameterValue};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use sea_orm::ConnectionTrait;
+use crate::model::error::CurdError;
+use crate::model::locale::{self, Locale};
+use crate::routes::ApiError;
+
+/// Query parameter selecting the locale of exports
+pub const LOCALE_QUERY: &str = "locale";
+
+/// Request Guard for the locale of CSV exports. The query parameter `locale` is a
+/// language tag, e.g. `locale=de-DE`. The preference of the user applies if it is
+/// missing.
+pub struct RequestedLocale {
+    locale: Option<Locale>,
+}
+
+impl RequestedLocale {
+    /// Requested locale, else the preference of [user_id], else English
+    pub async fn resolve(&self, user_id: u32, db: &impl ConnectionTrait) -> Result<Locale, CurdError> {
+        locale::resolve(self.locale, user_id, db).await
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestedLocale {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let locale = match request.query_value::<&str>(LOCALE_QUERY) {
+            Some(Ok(tag)) if !tag.trim().is_empty() => match locale::parse(tag) {
+                Some(locale) => Some(locale),
+                None => {
+                    return Outcome::Error(
+                        ApiError::new_bad_request()
+                            .with_description(format!("Unsupported locale '{}', use en or de", tag))
+                            .cache_for_catcher(request)
+                    );
+                },
+            },
+            _ => None,
+        };
+        Outcome::Success(RequestedLocale { locale })
+    }
+}
+
+impl OpenApiFromRequest<'_> for RequestedLocale {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(
+            RequestHeaderInput::Parameter(
+                Parameter {
+                    name: LOCALE_QUERY.to_string(),
+                    location: "query".to_string(),
+                    description: Some(
+                        "Locale of CSV representations, `en` or `de`, also as tag like `de-DE`. Defaults to the locale of the user.".to_string()
+                    ),
+                    required: false,
+                    deprecated: false,
+                    allow_empty_value: true,
+                    value: ParameterValue::Schema {
+                        style: None,
+                        explode: None,
+                        allow_reserved: false,
+                        schema: gen.json_schema_no_ref::<String>(),
+                        example: None,
+                        examples: None,
+                    },
+                    extensions: Object::new(),
+                }
+            )
+        )
+    }
+}
@@ -6,8 +6,42 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+pub mod archive_body;
 pub mod auth;
+pub mod deleted;
+pub mod feature;
+pub mod fields;
+pub mod gpx_body;
+pub mod idempotency;
+pub mod ids;
+pub mod if_modified_since;
+pub mod include;
+pub mod inbox_secret;
+pub mod json_body;
+pub mod links;
+pub mod locale;
+pub mod option_list;
+pub mod receipt_image;
+pub mod telegram_secret;
+pub mod transaction;
 
+pub use archive_body::ArchiveBody;
+pub use auth::Admin;
 pub use auth::Auth;
+pub use auth::AuthenticatedUser;
 pub use auth::ReadOnly;
 pub use auth::ReadWrite;
+pub use deleted::IncludeDeleted;
+pub use feature::Enabled;
+pub use fields::FieldSet;
+pub use gpx_body::GpxBody;
+pub use idempotency::IdempotencyKey;
+pub use ids::IdFilter;
+pub use if_modified_since::IfModifiedSince;
+pub use include::Include;
+pub use json_body::JsonBody;
+pub use links::LinkProfile;
+pub use locale::RequestedLocale;
+pub use option_list::OptionList;
+pub use receipt_image::ReceiptImage;
+pub use transaction::RequestTransaction;
@@ -0,0 +1,98 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::data::{Data, FromData, Outcome};
+use rocket::http::Status;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::{map, schemars::schema::{InstanceType, SchemaObject}};
+use rocket_okapi::okapi::openapi3::{MediaType, Object, RequestBody};
+use rocket_okapi::request::OpenApiFromData;
+use crate::model::tag_option::{parse_list, ImportedOption};
+use crate::routes::ApiError;
+use super::archive_body::{DEFAULT_IMPORT_LIMIT, IMPORT_LIMIT};
+
+/// List of tag options as request body with `Content-Type: text/plain` or `text/csv`,
+/// one option per line as parsed by [parse_list]. Limited like archive imports.
+#[derive(Debug)]
+pub struct OptionList(pub Vec<ImportedOption>);
+
+impl OptionList {
+    /// Unwrap the options
+    pub fn into_inner(self) -> Vec<ImportedOption> {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for OptionList {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        match request.content_type() {
+            Some(content_type) if content_type.top() == "text" && (content_type.sub() == "plain" || content_type.sub() == "csv") => {},
+            _ => {
+                return Outcome::Error(
+                    ApiError::from_status(Status::UnsupportedMediaType)
+                        .with_description("Expected text/plain or text/csv")
+                        .cache_for_catcher(request)
+                );
+            },
+        }
+
+        let limit = request.limits().get(IMPORT_LIMIT).unwrap_or(DEFAULT_IMPORT_LIMIT);
+        let body = match data.open(limit).into_string().await {
+            Ok(body) if body.is_complete() => body.into_inner(),
+            Ok(_) => {
+                return Outcome::Error(
+                    ApiError::new_payload_too_large()
+                        .with_description(format!("Request body exceeds the limit of {}", limit))
+                        .cache_for_catcher(request)
+                );
+            },
+            Err(e) => {
+                return Outcome::Error(
+                    ApiError::new_bad_request()
+                        .with_description(e.to_string())
+                        .cache_for_catcher(request)
+                );
+            },
+        };
+
+        match parse_list(&body) {
+            Ok(options) => Outcome::Success(OptionList(options)),
+            Err(e) => Outcome::Error(ApiError::from(e).cache_for_catcher(request)),
+        }
+    }
+}
+
+impl<'r> OpenApiFromData<'r> for OptionList {
+    fn request_body(_gen: &mut OpenApiGenerator) -> rocket_okapi::Result<RequestBody> {
+        let schema = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            ..Default::default()
+        };
+        Ok(
+            RequestBody {
+                description: Some("One option per line, either `value` or `value;name`".to_string()),
+                content: map! {
+                    "text/plain".to_string() => MediaType {
+                        schema: Some(schema.clone()),
+                        ..Default::default()
+                    },
+                    "text/csv".to_string() => MediaType {
+                        schema: Some(schema),
+                        ..Default::default()
+                    }
+                },
+                required: true,
+                extensions: Object::new(),
+            }
+        )
+    }
+}
@@ -0,0 +1,89 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::data::{ByteUnit, Data, FromData, Outcome};
+use rocket::http::Status;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::{map, schemars::schema::{InstanceType, SchemaObject}};
+use rocket_okapi::okapi::openapi3::{MediaType, Object, RequestBody};
+use rocket_okapi::request::OpenApiFromData;
+use crate::routes::ApiError;
+
+/// Name of the Rocket limit for receipt images
+pub const RECEIPT_LIMIT: &str = "receipt";
+/// Limit for receipt images if not configured
+pub const DEFAULT_RECEIPT_LIMIT: ByteUnit = ByteUnit::Mebibyte(10);
+
+/// Image of a receipt as request body with an `image/*` content type
+#[derive(Debug)]
+pub struct ReceiptImage {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for ReceiptImage {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        let content_type = match request.content_type() {
+            Some(content_type) if content_type.top() == "image" => content_type.to_string(),
+            _ => {
+                return Outcome::Error(
+                    ApiError::from_status(Status::UnsupportedMediaType)
+                        .with_description("Expected an image")
+                        .cache_for_catcher(request)
+                );
+            },
+        };
+
+        let limit = request.limits().get(RECEIPT_LIMIT).unwrap_or(DEFAULT_RECEIPT_LIMIT);
+        match data.open(limit).into_bytes().await {
+            Ok(body) if body.is_complete() => Outcome::Success(
+                ReceiptImage {
+                    content_type,
+                    data: body.into_inner(),
+                }
+            ),
+            Ok(_) => Outcome::Error(
+                ApiError::new_payload_too_large()
+                    .with_description(format!("Request body exceeds the limit of {}", limit))
+                    .cache_for_catcher(request)
+            ),
+            Err(e) => Outcome::Error(
+                ApiError::new_bad_request()
+                    .with_description(e.to_string())
+                    .cache_for_catcher(request)
+            ),
+        }
+    }
+}
+
+impl<'r> OpenApiFromData<'r> for ReceiptImage {
+    fn request_body(_gen: &mut OpenApiGenerator) -> rocket_okapi::Result<RequestBody> {
+        let schema = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            format: Some("binary".to_string()),
+            ..Default::default()
+        };
+        Ok(
+            RequestBody {
+                description: Some("Photo or scan of the receipt".to_string()),
+                content: map! {
+                    "image/*".to_string() => MediaType {
+                        schema: Some(schema),
+                        ..Default::default()
+                    }
+                },
+                required: true,
+                extensions: Object::new(),
+            }
+        )
+    }
+}
@@ -0,0 +1,156 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use rocket::Request;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use sea_orm::{DatabaseTransaction, DbErr, TransactionTrait};
+use crate::fairings::{Database, TagCache};
+use crate::model::event::{Event, EventBus};
+use crate::routes::ApiError;
+
+/// Work to do once the changes are committed
+type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Open transaction of a request with the tasks to run after its commit
+struct Open {
+    txn: Arc<DatabaseTransaction>,
+    after_commit: Vec<Task>,
+}
+
+/// Transaction of a request, kept in the request-local cache until the response
+#[derive(Default)]
+struct TransactionSlot(Mutex<Option<Open>>);
+
+impl TransactionSlot {
+    fn take(&self) -> Option<Open> {
+        self.0.lock().expect("Transaction lock is not poisoned").take()
+    }
+}
+
+/// Request Guard opening a transaction on the database for writes. The transaction is
+/// committed by the [transaction fairing] if the response is successful, i.e. has a
+/// status below 400, and rolled back otherwise. Handlers pass [as_ref] to the model
+/// instead of `db.conn`, also for reads, because the transaction may hold the only
+/// connection of the pool.
+///
+/// Work which must only happen for committed changes, e.g. publishing events or
+/// dropping cached tags, is handed to [after_commit] and run by the fairing after the
+/// commit.
+///
+/// [transaction fairing]: crate::fairings::transaction::init
+/// [as_ref]: RequestTransaction::as_ref
+/// [after_commit]: RequestTransaction::after_commit
+pub struct RequestTransaction<'r> {
+    txn: Arc<DatabaseTransaction>,
+    slot: &'r TransactionSlot,
+}
+
+impl RequestTransaction<'_> {
+    /// Run [task] once the transaction is committed. It is dropped on rollback.
+    pub fn after_commit(&self, task: impl Future<Output = ()> + Send + 'static) {
+        if let Some(open) = self.slot.0.lock().expect("Transaction lock is not poisoned").as_mut() {
+            open.after_commit.push(Box::pin(task));
+        }
+    }
+
+    /// Publish [event] on [events] once the transaction is committed
+    pub fn publish(&self, events: &EventBus, event: Event) {
+        let events = events.clone();
+        self.after_commit(async move { events.publish(event) });
+    }
+
+    /// Drop the cached tags of [user_id] from [tag_cache] once the transaction is
+    /// committed, so that no other request caches the tags before the change
+    pub fn invalidate_tags(&self, tag_cache: &TagCache, user_id: u32) {
+        let tag_cache = tag_cache.clone();
+        self.after_commit(async move { tag_cache.invalidate(user_id).await });
+    }
+
+    /// Commit the transaction of [request] if [status] is successful and run the tasks
+    /// handed to [after_commit], otherwise roll it back. Does nothing if the request did
+    /// not open a transaction.
+    ///
+    /// [after_commit]: RequestTransaction::after_commit
+    pub async fn finish(request: &Request<'_>, status: Status) -> Result<(), DbErr> {
+        let Some(Open { txn, after_commit }) = request.local_cache(TransactionSlot::default).take() else {
+            return Ok(());
+        };
+        let txn = match Arc::try_unwrap(txn) {
+            Ok(txn) => txn,
+            Err(_) => {
+                // The remaining reference rolls it back when dropped
+                error!("Transaction is still in use after the response, rolling back");
+                return Ok(());
+            },
+        };
+        if status.code >= 400 {
+            return txn.rollback().await;
+        }
+        txn.commit().await?;
+        for task in after_commit {
+            task.await;
+        }
+        Ok(())
+    }
+}
+
+impl AsRef<DatabaseTransaction> for RequestTransaction<'_> {
+    fn as_ref(&self) -> &DatabaseTransaction {
+        &self.txn
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestTransaction<'r> {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(db) = request.rocket().state::<Database>() else {
+            return Outcome::Error(
+                ApiError::new_internal_server_error()
+                    .with_description("Database is not available")
+                    .cache_for_catcher(request)
+            );
+        };
+        let slot = request.local_cache(TransactionSlot::default);
+        if slot.0.lock().expect("Transaction lock is not poisoned").is_some() {
+            return Outcome::Error(
+                ApiError::new_internal_server_error()
+                    .with_description("Transaction of the request is already open")
+                    .cache_for_catcher(request)
+            );
+        }
+        let txn = match db.conn.begin().await {
+            Ok(txn) => Arc::new(txn),
+            Err(e) => return Outcome::Error(ApiError::from(e).cache_for_catcher(request)),
+        };
+        *slot.0.lock().expect("Transaction lock is not poisoned") = Some(
+            Open {
+                txn: txn.clone(),
+                after_commit: Vec::new(),
+            }
+        );
+        Outcome::Success(RequestTransaction { txn, slot })
+    }
+}
+
+impl OpenApiFromRequest<'_> for RequestTransaction<'_> {
+    fn from_request_input(
+        _gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(RequestHeaderInput::None)
+    }
+}
@@ -0,0 +1,60 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{Request, Response};
+use rocket::http::{ContentType, Header};
+use rocket::response::Responder;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::{map, openapi3::{MediaType, RefOr, Responses}};
+use rocket_okapi::response::OpenApiResponderInner;
+
+/// File to download, sent with a `Content-Disposition: attachment` header
+pub struct Attachment {
+    content_type: ContentType,
+    filename: String,
+    data: Vec<u8>,
+}
+
+impl Attachment {
+    /// Download of [data] as [filename]
+    pub fn new(content_type: ContentType, filename: String, data: Vec<u8>) -> Self {
+        Self {
+            content_type,
+            filename,
+            data,
+        }
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for Attachment {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
+        Response::build_from(self.data.respond_to(request)?)
+            .header(self.content_type)
+            .header(Header::new("Content-Disposition", format!("attachment; filename=\"{}\"", self.filename)))
+            .ok()
+    }
+}
+
+impl OpenApiResponderInner for Attachment {
+    fn responses(_gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        Ok(Responses {
+            responses: map! {
+                "200".to_owned() => RefOr::Object(
+                    rocket_okapi::okapi::openapi3::Response {
+                        description: "File download".to_string(),
+                        content: map! {
+                            "application/octet-stream".to_owned() => MediaType::default()
+                        },
+                        ..Default::default()
+                    }
+                ),
+            },
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,85 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::{DateTime, Utc};
+use rocket::{Request, Response};
+use rocket::http::{Header, Status};
+use rocket::response::Responder;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{RefOr, Responses};
+use rocket_okapi::response::OpenApiResponderInner;
+
+/// Header with the time of the last change
+pub const LAST_MODIFIED_HEADER: &str = "Last-Modified";
+
+/// Response [R] with a `Last-Modified` header, or 304 if the client's copy is up to
+/// date, see [IfModifiedSince](crate::request_guards::IfModifiedSince)
+pub enum Conditional<R> {
+    Modified {
+        last_modified: Option<DateTime<Utc>>,
+        response: R,
+    },
+    NotModified {
+        last_modified: Option<DateTime<Utc>>,
+    },
+}
+
+impl<R> Conditional<R> {
+    pub fn modified(last_modified: Option<DateTime<Utc>>, response: R) -> Self {
+        Self::Modified {
+            last_modified,
+            response,
+        }
+    }
+
+    pub fn not_modified(last_modified: Option<DateTime<Utc>>) -> Self {
+        Self::NotModified {
+            last_modified,
+        }
+    }
+}
+
+/// [time] as HTTP date
+fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Conditional<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
+        let (mut res, last_modified) = match self {
+            Conditional::Modified { last_modified, response } => {
+                (Response::build_from(response.respond_to(request)?), last_modified)
+            },
+            Conditional::NotModified { last_modified } => {
+                let mut res = Response::build();
+                res.status(Status::NotModified);
+                (res, last_modified)
+            },
+        };
+        if let Some(last_modified) = last_modified {
+            res.header(Header::new(LAST_MODIFIED_HEADER, http_date(last_modified)));
+        }
+        res.ok()
+    }
+}
+
+impl<R: OpenApiResponderInner> OpenApiResponderInner for Conditional<R> {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = R::responses(gen)?;
+        responses.responses.insert(
+            "304".to_string(),
+            RefOr::Object(
+                rocket_okapi::okapi::openapi3::Response {
+                    description: "Not modified since If-Modified-Since".to_string(),
+                    ..Default::default()
+                }
+            ),
+        );
+        Ok(responses)
+    }
+}
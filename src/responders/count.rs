@@ -0,0 +1,66 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{Request, Response};
+use rocket::http::Header;
+use rocket::response::Responder;
+use rocket::serde::json::Json;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::{map, openapi3::{ParameterValue, RefOr, Responses}};
+use rocket_okapi::okapi::schemars;
+use rocket_okapi::response::OpenApiResponderInner;
+use rocket_okapi::JsonSchema;
+use serde::Serialize;
+use super::pagination::TOTAL_ITEMS_HEADER;
+
+/// Number of items of a list, without the items. The number is also sent in the
+/// `X-Total-Items` header, which is all that is left of the response to a HEAD request.
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy)]
+pub struct Count {
+    count: u64,
+}
+
+impl Count {
+    pub fn new(count: u64) -> Self {
+        Self {
+            count,
+        }
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for Count {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
+        Response::build_from(Json(self).respond_to(request)?)
+            .header(Header::new(TOTAL_ITEMS_HEADER, format!("{}", self.count)))
+            .ok()
+    }
+}
+
+impl OpenApiResponderInner for Count {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = Json::<Self>::responses(gen)?;
+        if let Some(RefOr::Object(response)) = responses.responses.get_mut("200") {
+            response.headers.insert(
+                TOTAL_ITEMS_HEADER.to_string(),
+                RefOr::Object(
+                    rocket_okapi::okapi::openapi3::Header {
+                        description: Some("Total number of items".to_string()),
+                        required: true,
+                        deprecated: false,
+                        allow_empty_value: false,
+                        value: ParameterValue::Content {
+                            content: map! {},
+                        },
+                        extensions: Default::default(),
+                    }
+                ),
+            );
+        }
+        Ok(responses)
+    }
+}
@@ -0,0 +1,85 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{Request, Response};
+use rocket::http::{Header, Status};
+use rocket::response::Responder;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::{map, openapi3::{ParameterValue, RefOr, Responses}};
+use rocket_okapi::response::OpenApiResponderInner;
+
+/// Header pointing at the created resource
+pub const LOCATION_HEADER: &str = "Location";
+
+/// Response [R] of a created resource, with status 201 and a `Location` header
+pub struct Created<R> {
+    /// Path of the resource below the path the API is mounted at
+    path: String,
+    inner: R,
+}
+
+impl<R> Created<R> {
+    /// Response [inner] of the resource created at [path], e.g. `/ride/1`
+    pub fn new(path: String, inner: R) -> Self {
+        Self {
+            path,
+            inner,
+        }
+    }
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Created<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
+        let base = request
+            .route()
+            .map(|route| route.uri.base().trim_end_matches('/').to_string())
+            .unwrap_or_default();
+        Response::build_from(self.inner.respond_to(request)?)
+            .status(Status::Created)
+            .header(Header::new(LOCATION_HEADER, format!("{}{}", base, self.path)))
+            .ok()
+    }
+}
+
+impl<R: OpenApiResponderInner> OpenApiResponderInner for Created<R> {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = R::responses(gen)?;
+        responses.responses = std::mem::take(&mut responses.responses)
+            .into_iter()
+            .map(|(status, response)| {
+                if status != "200" {
+                    return (status, response);
+                }
+                let response = match response {
+                    RefOr::Object(mut response) => {
+                        response.description = "Resource created".to_string();
+                        response.headers.insert(
+                            LOCATION_HEADER.to_string(),
+                            RefOr::Object(
+                                rocket_okapi::okapi::openapi3::Header {
+                                    description: Some("Path of the created resource".to_string()),
+                                    required: true,
+                                    deprecated: false,
+                                    allow_empty_value: false,
+                                    value: ParameterValue::Content {
+                                        content: map! {},
+                                    },
+                                    extensions: Default::default(),
+                                }
+                            ),
+                        );
+                        RefOr::Object(response)
+                    },
+                    other => other,
+                };
+                ("201".to_string(), response)
+            })
+            .collect();
+        Ok(responses)
+    }
+}
@@ -0,0 +1,74 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::marker::PhantomData;
+use rocket::{Request, Response};
+use rocket::http::{ContentType, Header, Status};
+use rocket::response::Responder;
+use rocket::serde::json::Json;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::JsonSchema;
+use rocket_okapi::okapi::openapi3::Responses;
+use rocket_okapi::response::OpenApiResponderInner;
+use serde::Serialize;
+use crate::routes::ApiError;
+
+/// Header set on responses which are replayed for a repeated idempotency key
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// JSON response of type [T] which may be a replay of a recorded response, see
+/// [IdempotencyKey](crate::request_guards::IdempotencyKey)
+pub struct Idempotent<T> {
+    status: Status,
+    body: String,
+    replayed: bool,
+    result_type: PhantomData<fn() -> T>,
+}
+
+impl<T> Idempotent<T> {
+    /// Response with serialized [body], fresh or replayed
+    pub fn new(status: Status, body: String, replayed: bool) -> Self {
+        Self {
+            status,
+            body,
+            replayed,
+            result_type: PhantomData,
+        }
+    }
+
+    /// ID of the resource in the body, which is the same for fresh and replayed responses
+    pub fn resource_id(&self) -> Result<u32, ApiError> {
+        serde_json::from_str::<serde_json::Value>(&self.body)
+            .ok()
+            .and_then(|body| body.get("id")?.as_u64())
+            .and_then(|id| u32::try_from(id).ok())
+            .ok_or_else(|| {
+                ApiError::new_internal_server_error()
+                    .with_description("Response has no resource ID")
+            })
+    }
+}
+
+impl<'r, T> Responder<'r, 'static> for Idempotent<T> {
+    fn respond_to(self, _: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let mut res = Response::build();
+        res.status(self.status);
+        res.header(ContentType::JSON);
+        if self.replayed {
+            res.header(Header::new(REPLAYED_HEADER, "true"));
+        }
+        res.sized_body(self.body.len(), std::io::Cursor::new(self.body));
+        res.ok()
+    }
+}
+
+impl<T: Serialize + JsonSchema + Send> OpenApiResponderInner for Idempotent<T> {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        Json::<T>::responses(gen)
+    }
+}
@@ -0,0 +1,125 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeMap;
+use serde::Serialize;
+use rocket_okapi::okapi::schemars;
+use crate::model::{
+    ride::Ride,
+    ride_reference::RideReference,
+    ride_tag_link::RideTagLink,
+    ride_track::RideTrack,
+    tag::Tag,
+    tag_option::TagOption,
+};
+
+/// Link to a related resource
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Link {
+    /// Absolute path of the resource
+    pub href: String,
+}
+
+/// Links of a resource, by relation
+pub type Links = BTreeMap<String, Link>;
+
+/// Resource which links to related resources
+pub trait Linkable {
+    /// Links of the resource. [base] is the path the API is mounted at.
+    fn links(&self, base: &str) -> Links;
+}
+
+/// Resource of type [T] with optional `_links`, see
+/// [LinkProfile](crate::request_guards::LinkProfile)
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Linked<T> {
+    #[serde(flatten)]
+    resource: T,
+    /// Related resources, only if requested
+    #[serde(rename = "_links", skip_serializing_if = "Option::is_none")]
+    links: Option<Links>,
+}
+
+impl<T> Linked<T> {
+    pub fn new(resource: T, links: Option<Links>) -> Self {
+        Self {
+            resource,
+            links,
+        }
+    }
+}
+
+/// Links from relation to path below [base]
+fn links<const N: usize>(base: &str, relations: [(&str, String); N]) -> Links {
+    relations
+        .into_iter()
+        .map(|(relation, path)| (relation.to_string(), Link { href: format!("{}{}", base, path) }))
+        .collect()
+}
+
+impl Linkable for Ride {
+    fn links(&self, base: &str) -> Links {
+        links(base, [
+            ("self", format!("/ride/{}", self.id())),
+            ("ride_tags", format!("/ride/{}/ride_tags", self.id())),
+            ("references", format!("/ride/{}/reference", self.id())),
+        ])
+    }
+}
+
+impl Linkable for RideReference {
+    fn links(&self, base: &str) -> Links {
+        links(base, [
+            ("self", format!("/ride/{}/reference/{}", self.ride_id(), self.id())),
+            ("ride", format!("/ride/{}", self.ride_id())),
+        ])
+    }
+}
+
+impl Linkable for RideTrack {
+    fn links(&self, base: &str) -> Links {
+        links(base, [
+            ("self", format!("/ride/{}/track", self.ride_id())),
+            ("gpx", format!("/ride/{}/track/gpx", self.ride_id())),
+            ("ride", format!("/ride/{}", self.ride_id())),
+        ])
+    }
+}
+
+impl Linkable for RideTagLink {
+    fn links(&self, base: &str) -> Links {
+        links(base, [
+            ("self", format!("/ride_tag/{}", self.id())),
+            ("ride", format!("/ride/{}", self.ride_id())),
+            ("tag", format!("/tag/{}", self.tag_id())),
+        ])
+    }
+}
+
+impl Linkable for Tag {
+    fn links(&self, base: &str) -> Links {
+        let mut result = links(base, [
+            ("self", format!("/tag/{}", self.id())),
+        ]);
+        if self.options().is_some() {
+            result.extend(links(base, [
+                ("options", format!("/tag/{}/tag_option", self.id())),
+            ]));
+        }
+        result
+    }
+}
+
+impl Linkable for TagOption {
+    fn links(&self, base: &str) -> Links {
+        links(base, [
+            ("self", format!("/tag_option/{}", self.id())),
+            ("tag", format!("/tag/{}", self.tag_id())),
+        ])
+    }
+}
@@ -6,6 +6,26 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+pub mod attachment;
+pub mod conditional;
+pub mod count;
+pub mod created;
+pub mod idempotent;
+pub mod linked;
+pub mod negotiated;
+pub mod one_of;
 pub mod pagination;
+pub mod sparse;
+pub mod warnings;
 
+pub use attachment::Attachment;
+pub use conditional::Conditional;
+pub use count::Count;
+pub use created::Created;
+pub use idempotent::Idempotent;
+pub use linked::Linked;
+pub use negotiated::Negotiated;
+pub use one_of::OneOf;
 pub use pagination::PaginatedResult;
+pub use sparse::Sparse;
+pub use warnings::Warnings;
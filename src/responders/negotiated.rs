@@ -0,0 +1,232 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{Request, Response};
+use rocket::http::{ContentType, Header, Status};
+use rocket::response::Responder;
+use rocket::serde::json::Json;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{MediaType, RefOr, Responses};
+use rocket_okapi::response::OpenApiResponderInner;
+use serde::Serialize;
+use serde_json::Value;
+use crate::model::locale::{CsvFormat, Locale};
+
+/// Representation of a list, chosen by the `Accept` header of the request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+    Xml,
+}
+
+impl Format {
+    /// Format of the most preferred media type the request accepts, JSON by default
+    pub fn negotiate(request: &Request<'_>) -> Self {
+        match request.accept() {
+            Some(accept) => {
+                let media_type = accept.preferred().media_type();
+                match (media_type.top().as_str(), media_type.sub().as_str()) {
+                    ("text", "csv") => Format::Csv,
+                    ("application", "xml") | ("text", "xml") => Format::Xml,
+                    _ => Format::Json,
+                }
+            },
+            None => Format::Json,
+        }
+    }
+}
+
+/// List response of type [T] in the format negotiated with [Format::negotiate]. The items
+/// are rows of a CSV table with one column per field, or `<item>` elements of a
+/// `<items>` XML document. Nested values are JSON in CSV cells and nested elements in XML.
+pub struct Negotiated<T> {
+    value: T,
+    /// Element name of an item in XML
+    item: &'static str,
+    /// Locale of the CSV table
+    locale: Locale,
+}
+
+impl<T> Negotiated<T> {
+    /// Response of the list [value], whose items are named [item] in XML
+    pub fn new(value: T, item: &'static str) -> Self {
+        Self {
+            value,
+            item,
+            locale: Locale::default(),
+        }
+    }
+
+    /// Write the CSV table in [locale]
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+}
+
+/// Objects of [value] as rows, a single object is a single row
+fn rows(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    }
+}
+
+/// Text of a CSV cell
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Bool(flag)) => flag.to_string(),
+        Some(Value::Number(number)) => number.to_string(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Text of a CSV cell with numbers and dates in [format]
+fn localized_cell(value: Option<&Value>, format: &CsvFormat) -> String {
+    match value {
+        Some(Value::String(text)) => format.text(text),
+        Some(Value::Number(number)) => format.number(&number.to_string()),
+        other => cell(other),
+    }
+}
+
+/// CSV table of [value] with a header row of all fields, in [format]
+fn to_csv(value: &Value, format: &CsvFormat) -> Result<String, csv::Error> {
+    let rows = rows(value);
+    let mut columns: Vec<&String> = Vec::new();
+    for row in &rows {
+        if let Value::Object(object) = row {
+            for key in object.keys() {
+                if !columns.contains(&key) {
+                    columns.push(key);
+                }
+            }
+        }
+    }
+
+    let mut writer = format.writer();
+    writer.write_record(columns.iter().map(|column| format.header(column)))?;
+    for row in &rows {
+        writer.write_record(columns.iter().map(|column| localized_cell(row.get(column.as_str()), format)))?;
+    }
+    let data = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Escape [text] for XML content and attributes
+fn escape_xml(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Append [value] as element [name] to [xml]
+fn write_element(xml: &mut String, name: &str, value: &Value) {
+    // Field names are identifiers, but link relations and map keys may not be valid names
+    let valid = name
+        .chars()
+        .enumerate()
+        .all(|(i, c)| c.is_ascii_alphabetic() || c == '_' || (i > 0 && (c.is_ascii_digit() || c == '-' || c == '.')));
+    let (open, close) = if valid && !name.is_empty() {
+        (name.to_string(), name.to_string())
+    } else {
+        (format!("field name=\"{}\"", escape_xml(name)), "field".to_string())
+    };
+    match value {
+        Value::Null => xml.push_str(&format!("<{}/>", open)),
+        Value::Array(items) => {
+            xml.push_str(&format!("<{}>", open));
+            for item in items {
+                write_element(xml, "item", item);
+            }
+            xml.push_str(&format!("</{}>", close));
+        },
+        Value::Object(object) => {
+            xml.push_str(&format!("<{}>", open));
+            for (key, field) in object {
+                write_element(xml, key, field);
+            }
+            xml.push_str(&format!("</{}>", close));
+        },
+        other => {
+            xml.push_str(&format!("<{}>{}</{}>", open, escape_xml(&cell(Some(other))), close));
+        },
+    }
+}
+
+/// XML document of [value] with one element [item] per object
+fn to_xml(value: &Value, item: &str) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    xml.push_str(&format!("<{}s>", item));
+    for row in rows(value) {
+        write_element(&mut xml, item, row);
+    }
+    xml.push_str(&format!("</{}s>", item));
+    xml
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Negotiated<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let format = Format::negotiate(request);
+        let response = match format {
+            Format::Json => Json(self.value).respond_to(request)?,
+            Format::Csv | Format::Xml => {
+                let value = serde_json::to_value(&self.value)
+                    .map_err(|e| {
+                        error!("Cannot serialize response: {}", e);
+                        Status::InternalServerError
+                    })?;
+                let (content_type, body) = if format == Format::Csv {
+                    let body = to_csv(&value, &CsvFormat::new(self.locale))
+                        .map_err(|e| {
+                            error!("Cannot write CSV: {}", e);
+                            Status::InternalServerError
+                        })?;
+                    (ContentType::CSV, body)
+                } else {
+                    (ContentType::new("application", "xml"), to_xml(&value, self.item))
+                };
+                Response::build_from(body.respond_to(request)?)
+                    .header(content_type)
+                    .finalize()
+            },
+        };
+        Response::build_from(response)
+            .header(Header::new("Vary", "Accept"))
+            .ok()
+    }
+}
+
+/// Add the CSV and XML representations to the successful response of [responses]
+pub fn add_formats(responses: &mut Responses) {
+    if let Some(RefOr::Object(response)) = responses.responses.get_mut("200") {
+        for content_type in ["text/csv", "application/xml"] {
+            response.content.insert(content_type.to_string(), MediaType::default());
+        }
+    }
+}
+
+impl<T: OpenApiResponderInner> OpenApiResponderInner for Negotiated<T> {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = T::responses(gen)?;
+        add_formats(&mut responses);
+        Ok(responses)
+    }
+}
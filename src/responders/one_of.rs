@@ -0,0 +1,77 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::response::Responder;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{RefOr, Responses};
+use rocket_okapi::okapi::schemars::schema::{Schema, SchemaObject, SubschemaValidation};
+use rocket_okapi::response::OpenApiResponderInner;
+
+/// Response [A] or [B] of the same endpoint, depending on the request, e.g. a list or
+/// its grouped form
+pub enum OneOf<A, B> {
+    First(A),
+    Second(B),
+}
+
+impl<'r, 'o: 'r, A: Responder<'r, 'o>, B: Responder<'r, 'o>> Responder<'r, 'o> for OneOf<A, B> {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
+        match self {
+            OneOf::First(response) => response.respond_to(request),
+            OneOf::Second(response) => response.respond_to(request),
+        }
+    }
+}
+
+/// Schema matching either [a] or [b]
+fn one_of(a: SchemaObject, b: SchemaObject) -> SchemaObject {
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            one_of: Some(vec![Schema::Object(a), Schema::Object(b)]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+}
+
+impl<A: OpenApiResponderInner, B: OpenApiResponderInner> OpenApiResponderInner for OneOf<A, B> {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = A::responses(gen)?;
+        for (status, second) in B::responses(gen)?.responses {
+            match (responses.responses.get_mut(&status), second) {
+                (Some(RefOr::Object(first)), RefOr::Object(second)) => {
+                    // Same status: the bodies of both are documented as alternatives
+                    for (media_type, content) in second.content {
+                        match first.content.get_mut(&media_type) {
+                            Some(existing) => {
+                                existing.schema = match (existing.schema.take(), content.schema) {
+                                    (Some(a), Some(b)) => Some(one_of(a, b)),
+                                    (a, b) => a.or(b),
+                                };
+                            },
+                            None => {
+                                first.content.insert(media_type, content);
+                            },
+                        }
+                    }
+                    for (name, header) in second.headers {
+                        if !first.headers.contains_key(&name) {
+                            first.headers.insert(name, header);
+                        }
+                    }
+                },
+                (Some(_), _) => {},
+                (None, second) => {
+                    responses.responses.insert(status, second);
+                },
+            }
+        }
+        Ok(responses)
+    }
+}
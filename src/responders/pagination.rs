@@ -7,12 +7,31 @@
  */
 
 use rocket::{Request, Response};
-use rocket::http::{ContentType, Header, Status};
+use rocket::http::{ContentType, Header, RawStr, Status};
 use rocket::response::Responder;
 use rocket_okapi::gen::OpenApiGenerator;
 use rocket_okapi::JsonSchema;
 use rocket_okapi::okapi::openapi3::Responses;
 use rocket_okapi::response::OpenApiResponderInner;
+use crate::model::ride_summary::RideSummary;
+
+/// Header with the total number of items
+pub const TOTAL_ITEMS_HEADER: &str = "X-Total-Items";
+/// Header with the current page number
+pub const PAGE_HEADER: &str = "X-Page";
+/// Header with the number of items per page
+pub const PAGE_SIZE_HEADER: &str = "X-Page-Size";
+/// Header with the total number of pages
+pub const TOTAL_PAGES_HEADER: &str = "X-Total-Pages";
+/// Header with the cursor of the next page
+pub const NEXT_CURSOR_HEADER: &str = "X-Next-Cursor";
+/// Header with the sum of the prices of all items, if requested
+pub const TOTAL_COST_HEADER: &str = "X-Total-Cost";
+/// Header with the sum of the distances of all items, if requested
+pub const TOTAL_DISTANCE_HEADER: &str = "X-Total-Distance";
+
+/// Query parameters controlling pagination, which are replaced in links
+const PAGINATION_PARAMETERS: [&str; 3] = ["page", "size", "cursor"];
 
 pub enum PaginatedResult<R> {
     Paginated {
@@ -21,10 +40,20 @@ pub enum PaginatedResult<R> {
         page: u64,
         page_size: u64,
         pages_count: u64,
+        summary: Option<RideSummary>,
+    },
+    /// Page of a keyset pagination, continued after [next_cursor]
+    Cursor {
+        result: R,
+        item_count: u64,
+        page_size: u64,
+        next_cursor: Option<String>,
+        summary: Option<RideSummary>,
     },
     Complete {
         result: R,
         item_count: Option<u64>,
+        summary: Option<RideSummary>,
     },
 }
 
@@ -37,6 +66,19 @@ impl<'r, 'o: 'r, R: Responder<'r, 'o>> PaginatedResult<R> {
             page,
             page_size,
             pages_count,
+            summary: None,
+        }
+    }
+
+    /// Page of a keyset pagination. [next_cursor] is `None` on the last page. The first
+    /// page is requested with cursor `0`.
+    pub fn new_cursor(result: R, item_count: u64, page_size: u64, next_cursor: Option<String>) -> Self {
+        Self::Cursor {
+            result,
+            item_count,
+            page_size,
+            next_cursor,
+            summary: None,
         }
     }
 
@@ -44,12 +86,84 @@ impl<'r, 'o: 'r, R: Responder<'r, 'o>> PaginatedResult<R> {
         Self::Complete {
             result,
             item_count,
+            summary: None,
+        }
+    }
+
+    /// Add the sums over all items, not only the page, as headers
+    pub fn with_summary(mut self, summary: RideSummary) -> Self {
+        match &mut self {
+            Self::Paginated { summary: field, .. }
+            | Self::Cursor { summary: field, .. }
+            | Self::Complete { summary: field, .. } => *field = Some(summary),
         }
+        self
+    }
+
+    fn summary(&self) -> Option<RideSummary> {
+        match self {
+            Self::Paginated { summary, .. } | Self::Cursor { summary, .. } | Self::Complete { summary, .. } => *summary,
+        }
+    }
+}
+
+/// Builds the links of the `Link` header (RFC 8288). The query parameters of the
+/// request, e.g. filters, sorting and field selection, are carried over and only the
+/// pagination parameters are replaced.
+struct LinkBuilder {
+    path: String,
+    query: Vec<(String, String)>,
+    links: Vec<String>,
+}
+
+impl LinkBuilder {
+    fn new(request: &Request<'_>) -> Self {
+        let query = request.uri().query()
+            .map(|query| {
+                query.segments()
+                    .filter(|(name, _)| !PAGINATION_PARAMETERS.contains(name))
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            path: request.uri().path().to_string(),
+            query,
+            links: Vec::new(),
+        }
+    }
+
+    /// Add link with [rel] and the pagination parameters [pagination]
+    fn add(&mut self, rel: &str, pagination: &[(&str, String)]) {
+        let query = self.query.iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .chain(pagination.iter().map(|(name, value)| (*name, value.as_str())))
+            .map(|(name, value)| format!("{}={}", RawStr::new(name).percent_encode(), RawStr::new(value).percent_encode()))
+            .collect::<Vec<_>>()
+            .join("&");
+        self.links.push(format!("<{}?{}>; rel=\"{}\"", self.path, query, rel));
+    }
+
+    fn build(self) -> String {
+        self.links.join(", ")
     }
 }
 
 impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for PaginatedResult<R> {
     fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
+        let summary = self.summary();
+        let mut response = self.respond_page(request)?;
+        if let Some(summary) = summary {
+            response.set_header(Header::new(TOTAL_COST_HEADER, format!("{:.2}", summary.total_cost)));
+            response.set_header(Header::new(TOTAL_DISTANCE_HEADER, format!("{}", summary.total_distance)));
+        }
+        Ok(response)
+    }
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> PaginatedResult<R> {
+    /// Response with the pagination headers
+    fn respond_page(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
         match self {
             PaginatedResult::Paginated {
                 result,
@@ -57,43 +171,79 @@ impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for PaginatedResult<R>
                 page,
                 page_size,
                 pages_count,
+                ..
             } => {
-                let uri = request.uri().path();
-                let mut links = format!("<{uri}?page={page}&size={page_size}>; rel=\"self\"");
-                links += format!(", <{uri}?page=0&size={page_size}>; rel=\"first\"").as_str();
-                let last_page = pages_count - 1;
-                links += format!(", <{uri}?page={last_page}&size={page_size}>; rel=\"last\"").as_str();
+                let mut links = LinkBuilder::new(request);
+                let at_page = |page: u64| [("page", page.to_string()), ("size", page_size.to_string())];
+                links.add("self", &at_page(page));
+                links.add("first", &at_page(0));
+                let last_page = pages_count.saturating_sub(1);
+                links.add("last", &at_page(last_page));
                 if page > 0 {
                     let prev_page = if page < last_page {
                         page - 1
                     } else {
                         last_page
                     };
-                    links += format!(", <{uri}?page={prev_page}&size={page_size}>; rel=\"prev\"").as_str();
+                    links.add("prev", &at_page(prev_page));
                 }
                 if page < last_page {
-                    let next_page = page + 1;
-                    links += format!(", <{uri}?page={next_page}&size={page_size}>; rel=\"next\"").as_str();
+                    links.add("next", &at_page(page + 1));
                 }
-                Response::build_from(result.respond_to(request)?)
+                let response = result.respond_to(request)?;
+                let content_type = response.content_type().unwrap_or(ContentType::JSON);
+                Response::build_from(response)
                     .status(Status::Ok)
-                    .header(ContentType::JSON)
-                    .header(Header::new("X-Total-Items", format!("{item_count}")))
-                    .header(Header::new("X-Page", format!("{page}")))
-                    .header(Header::new("X-Page-Size", format!("{page_size}")))
-                    .header(Header::new("X-Total-pages", format!("{pages_count}")))
-                    .header(Header::new("Link", links))
+                    .header(content_type)
+                    .header(Header::new(TOTAL_ITEMS_HEADER, format!("{item_count}")))
+                    .header(Header::new(PAGE_HEADER, format!("{page}")))
+                    .header(Header::new(PAGE_SIZE_HEADER, format!("{page_size}")))
+                    .header(Header::new(TOTAL_PAGES_HEADER, format!("{pages_count}")))
+                    .header(Header::new("Link", links.build()))
                     .ok()
             },
+            PaginatedResult::Cursor {
+                result,
+                item_count,
+                page_size,
+                next_cursor,
+                ..
+            } => {
+                let mut links = LinkBuilder::new(request);
+                let mut current = vec![("size", page_size.to_string())];
+                if let Some(cursor) = request.query_value::<&str>("cursor").and_then(Result::ok) {
+                    current.push(("cursor", cursor.to_string()));
+                }
+                links.add("self", &current);
+                links.add("first", &[("size", page_size.to_string()), ("cursor", "0".to_string())]);
+                if let Some(next_cursor) = &next_cursor {
+                    links.add("next", &[("size", page_size.to_string()), ("cursor", next_cursor.clone())]);
+                }
+                let response = result.respond_to(request)?;
+                let content_type = response.content_type().unwrap_or(ContentType::JSON);
+                let mut res = Response::build_from(response);
+                res.status(Status::Ok)
+                    .header(content_type)
+                    .header(Header::new(TOTAL_ITEMS_HEADER, format!("{item_count}")))
+                    .header(Header::new(PAGE_SIZE_HEADER, format!("{page_size}")))
+                    .header(Header::new("Link", links.build()));
+                if let Some(next_cursor) = next_cursor {
+                    res.header(Header::new(NEXT_CURSOR_HEADER, next_cursor));
+                }
+                res.ok()
+            },
             PaginatedResult::Complete {
                 result,
                 item_count,
+                ..
             } => {
-                let mut res = Response::build_from(result.respond_to(request)?);
+                let response = result.respond_to(request)?;
+                let content_type = response.content_type().unwrap_or(ContentType::JSON);
+                let mut res = Response::build_from(response);
                 res.status(Status::Ok);
-                res.header(ContentType::JSON);
+                res.header(content_type);
                 if let Some(item_count) = item_count {
-                    res.header(Header::new("X-Total-Items", format!("{item_count}")));
+                    res.header(Header::new(TOTAL_ITEMS_HEADER, format!("{item_count}")));
                 }
                 res.ok()
             },
@@ -103,52 +253,80 @@ impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for PaginatedResult<R>
 
 impl<I: JsonSchema> OpenApiResponderInner for PaginatedResult<rocket::serde::json::Json<I>> {
     fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
-        use rocket_okapi::okapi::{map, openapi3::{RefOr, MediaType, Header, ParameterValue}};
-        let make_header = |description: &str| {
-            Header {
-                description: Some(description.to_string()),
-                required: false,
-                deprecated: false,
-                allow_empty_value: true,
-                value: ParameterValue::Content {
-                    content: map ! {},
-                },
-                extensions: Default::default(),
-            }
-        };
-        Ok(Responses {
-            responses: map! {
-                "200".to_owned() => RefOr::Object(
-                    rocket_okapi::okapi::openapi3::Response {
-                        description: "".to_string(),
-                        content: map! {
-                            "application/json".to_owned() => MediaType {
-                                schema: Some(gen.json_schema::<I>()),
-                                ..Default::default()
-                            }
-                        },
-                        headers: map! {
-                            "X-Total-Items".to_owned() => RefOr::Object(
-                                make_header("Total number of items")
-                            ),
-                            "X-Page".to_owned() => RefOr::Object(
-                                make_header("Current page number")
-                            ),
-                            "X-Page-Size".to_owned() => RefOr::Object(
-                                make_header("Number of items on page")
-                            ),
-                            "X-Total-pages".to_owned() => RefOr::Object(
-                                make_header("Total number of pages")
-                            ),
-                            "Links".to_owned() => RefOr::Object(
-                                make_header("URL for preloading")
-                            ),
-                        },
-                        ..Default::default()
-                    }
-                ),
-            },
-            ..Default::default()
-        })
+        paginated_responses::<I>(gen)
+    }
+}
+
+impl<I: JsonSchema> OpenApiResponderInner for PaginatedResult<super::Sparse<I>> {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        paginated_responses::<I>(gen)
+    }
+}
+
+impl<I: JsonSchema> OpenApiResponderInner for PaginatedResult<super::Negotiated<super::Sparse<I>>> {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = paginated_responses::<I>(gen)?;
+        super::negotiated::add_formats(&mut responses);
+        Ok(responses)
     }
 }
+
+/// Responses of a paginated JSON list of [I]
+fn paginated_responses<I: JsonSchema>(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+    use rocket_okapi::okapi::{map, openapi3::{RefOr, MediaType, Header, ParameterValue}};
+    let make_header = |description: &str| {
+        Header {
+            description: Some(description.to_string()),
+            required: false,
+            deprecated: false,
+            allow_empty_value: true,
+            value: ParameterValue::Content {
+                content: map ! {},
+            },
+            extensions: Default::default(),
+        }
+    };
+    Ok(Responses {
+        responses: map! {
+            "200".to_owned() => RefOr::Object(
+                rocket_okapi::okapi::openapi3::Response {
+                    description: "".to_string(),
+                    content: map! {
+                        "application/json".to_owned() => MediaType {
+                            schema: Some(gen.json_schema::<I>()),
+                            ..Default::default()
+                        }
+                    },
+                    headers: map! {
+                        TOTAL_ITEMS_HEADER.to_owned() => RefOr::Object(
+                            make_header("Total number of items")
+                        ),
+                        PAGE_HEADER.to_owned() => RefOr::Object(
+                            make_header("Current page number")
+                        ),
+                        PAGE_SIZE_HEADER.to_owned() => RefOr::Object(
+                            make_header("Number of items on page")
+                        ),
+                        TOTAL_PAGES_HEADER.to_owned() => RefOr::Object(
+                            make_header("Total number of pages")
+                        ),
+                        NEXT_CURSOR_HEADER.to_owned() => RefOr::Object(
+                            make_header("Cursor of the next page, if paginated by cursor")
+                        ),
+                        "Link".to_owned() => RefOr::Object(
+                            make_header("Links to the other pages (RFC 8288), keeping the query parameters")
+                        ),
+                        TOTAL_COST_HEADER.to_owned() => RefOr::Object(
+                            make_header("Sum of the prices of all items, if requested with summary=true")
+                        ),
+                        TOTAL_DISTANCE_HEADER.to_owned() => RefOr::Object(
+                            make_header("Sum of the distances of all items, if requested with summary=true")
+                        ),
+                    },
+                    ..Default::default()
+                }
+            ),
+        },
+        ..Default::default()
+    })
+}
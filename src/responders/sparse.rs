@@ -0,0 +1,79 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeSet;
+use rocket::Request;
+use rocket::response::Responder;
+use rocket::serde::json::Json;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::JsonSchema;
+use rocket_okapi::okapi::openapi3::Responses;
+use rocket_okapi::response::OpenApiResponderInner;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+/// Fields which are serialized regardless of the fieldset
+const ALWAYS_INCLUDED: [&str; 2] = ["id", "_links"];
+
+/// JSON response of type [T], restricted to a sparse fieldset, see
+/// [FieldSet](crate::request_guards::FieldSet)
+pub struct Sparse<T> {
+    value: T,
+    fields: Option<BTreeSet<String>>,
+}
+
+impl<T> Sparse<T> {
+    /// Response with [fields] of [value], or all fields if [fields] is `None`
+    pub fn new(value: T, fields: Option<BTreeSet<String>>) -> Self {
+        Self {
+            value,
+            fields,
+        }
+    }
+}
+
+/// Remove all fields from the objects in [value] which are not in [fields]
+fn retain_fields(value: &mut Value, fields: &BTreeSet<String>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                retain_fields(item, fields);
+            }
+        },
+        Value::Object(object) => {
+            object.retain(|key, _| fields.contains(key) || ALWAYS_INCLUDED.contains(&key.as_str()));
+        },
+        _ => (),
+    }
+}
+
+impl<T: Serialize> Serialize for Sparse<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.fields {
+            Some(fields) => {
+                let mut value = serde_json::to_value(&self.value)
+                    .map_err(serde::ser::Error::custom)?;
+                retain_fields(&mut value, fields);
+                value.serialize(serializer)
+            },
+            None => self.value.serialize(serializer),
+        }
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Sparse<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'static> {
+        Json(self).respond_to(request)
+    }
+}
+
+impl<T: Serialize + JsonSchema + Send> OpenApiResponderInner for Sparse<T> {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        Json::<T>::responses(gen)
+    }
+}
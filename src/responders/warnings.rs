@@ -0,0 +1,63 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Request;
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::serde::json::Json;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{RefOr, Responses};
+use rocket_okapi::okapi::schemars;
+use rocket_okapi::response::OpenApiResponderInner;
+use rocket_okapi::JsonSchema;
+use serde::Serialize;
+use crate::model::overlap::Warning;
+
+/// Response to an update without content, or with status 200 and the warnings about the
+/// saved resource if there are any
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct Warnings {
+    warnings: Vec<Warning>,
+}
+
+impl Warnings {
+    pub fn new(warnings: Vec<Warning>) -> Self {
+        Self {
+            warnings,
+        }
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for Warnings {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
+        if self.warnings.is_empty() {
+            Status::NoContent.respond_to(request)
+        } else {
+            Json(self).respond_to(request)
+        }
+    }
+}
+
+impl OpenApiResponderInner for Warnings {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = Json::<Self>::responses(gen)?;
+        if let Some(RefOr::Object(response)) = responses.responses.get_mut("200") {
+            response.description = "Saved with warnings".to_string();
+        }
+        responses.responses.insert(
+            "204".to_string(),
+            RefOr::Object(
+                rocket_okapi::okapi::openapi3::Response {
+                    description: "Saved".to_string(),
+                    ..Default::default()
+                }
+            ),
+        );
+        Ok(responses)
+    }
+}
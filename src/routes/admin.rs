@@ -0,0 +1,186 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{State, response::status::NoContent, serde::json::Json};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::{AuthCache, Database};
+use crate::model::audit::{AuditEntry, AuditFilter};
+use crate::model::audit_chain::{AuditChain, ChainReport};
+use crate::model::feature::{FeatureFlag, FeatureFlags};
+use crate::model::maintenance::{Maintenance, MaintenanceRequest, MaintenanceStatus};
+use crate::model::retention::{RetentionPolicy, RetentionReport};
+use crate::model::ride_track::{self, QuarantinedTrack, RideTrack};
+use crate::request_guards::{Admin, Auth, JsonBody, RequestTransaction};
+use crate::responders::PaginatedResult;
+
+/// Page size of the audit log if not requested
+const DEFAULT_AUDIT_PAGE_SIZE: u64 = 50;
+/// Page size of the quarantined tracks if not requested
+const DEFAULT_QUARANTINE_PAGE_SIZE: u64 = 50;
+
+/// Dry run of the data retention: rides of each user which would be deleted now
+#[openapi(tag = "Admin")]
+#[get("/admin/retention")]
+pub async fn retention(
+    _auth: Auth<Admin>,
+    db: &State<Database>,
+    policy: &State<RetentionPolicy>,
+) -> Result<Json<RetentionReport>, ApiError> {
+    Ok(Json(policy.report(chrono::Utc::now(), db.read_conn.as_ref()).await?))
+}
+
+/// Audit log of changes to rides, tags, tag options and ride tags of all users, newest
+/// first. Filters by user, kind of resource (e.g. `ride`) and resource ID if given.
+#[openapi(tag = "Admin")]
+#[get("/admin/audit_log?<user_id>&<resource>&<resource_id>&<page>&<size>")]
+pub async fn audit_log(
+    _auth: Auth<Admin>,
+    db: &State<Database>,
+    user_id: Option<u32>,
+    resource: Option<String>,
+    resource_id: Option<u32>,
+    page: Option<u64>,
+    size: Option<u64>,
+) -> Result<PaginatedResult<Json<Vec<AuditEntry>>>, ApiError> {
+    let page = page.unwrap_or(0);
+    let size = size.unwrap_or(DEFAULT_AUDIT_PAGE_SIZE);
+    if size == 0 {
+        Err(
+            ApiError::new_bad_request()
+                .with_description("Page size must be greater than zero.")
+        )?;
+    }
+    let filter = AuditFilter {
+        user_id,
+        resource,
+        resource_id,
+    };
+    let count = AuditEntry::count_all(&filter, db.read_conn.as_ref()).await?;
+    let entries = AuditEntry::find_all_paginated(&filter, db.read_conn.as_ref(), page, size).await?;
+    Ok(PaginatedResult::new_paginated(Json(entries), count, page, size))
+}
+
+/// Check the hash chain of the audit log and the signatures over it, oldest entry
+/// first up to the first broken one. The newest signature can be kept outside of the
+/// database as receipt of the state of the audit log.
+#[openapi(tag = "Admin")]
+#[get("/admin/audit_log/verify")]
+pub async fn verify_audit_log(
+    _auth: Auth<Admin>,
+    db: &State<Database>,
+    auth_cache: &State<AuthCache>,
+) -> Result<Json<ChainReport>, ApiError> {
+    let chain = AuditChain::new(auth_cache.key_cache.clone(), auth_cache.expect_jwt_audience.clone());
+    Ok(Json(chain.verify(db.read_conn.as_ref()).await?))
+}
+
+/// GPX tracks held back by the malware scan, of all users, oldest upload first
+#[openapi(tag = "Admin")]
+#[get("/admin/quarantine?<page>&<size>")]
+pub async fn quarantine(
+    _auth: Auth<Admin>,
+    db: &State<Database>,
+    page: Option<u64>,
+    size: Option<u64>,
+) -> Result<PaginatedResult<Json<Vec<QuarantinedTrack>>>, ApiError> {
+    let page = page.unwrap_or(0);
+    let size = size.unwrap_or(DEFAULT_QUARANTINE_PAGE_SIZE);
+    if size == 0 {
+        Err(
+            ApiError::new_bad_request()
+                .with_description("Page size must be greater than zero.")
+        )?;
+    }
+    let count = ride_track::count_quarantined(db.read_conn.as_ref()).await?;
+    let tracks = ride_track::find_quarantined_paginated(db.read_conn.as_ref(), page, size).await?;
+    Ok(PaginatedResult::new_paginated(Json(tracks), count, page, size))
+}
+
+/// Release the quarantined track [id] after review, e.g. on a false positive, so that
+/// its owner can download it again
+#[openapi(tag = "Admin")]
+#[post("/admin/quarantine/<id>/release")]
+pub async fn release_quarantined(
+    auth: Auth<Admin>,
+    txn: RequestTransaction<'_>,
+    id: u32,
+) -> Result<Json<RideTrack>, ApiError> {
+    let track = ride_track::release(id, &auth.actor(), txn.as_ref()).await?;
+    info!("Quarantined track {} released", id);
+    Ok(Json(track))
+}
+
+/// Delete the quarantined track [id] after review
+#[openapi(tag = "Admin")]
+#[delete("/admin/quarantine/<id>")]
+pub async fn delete_quarantined(
+    auth: Auth<Admin>,
+    txn: RequestTransaction<'_>,
+    id: u32,
+) -> Result<NoContent, ApiError> {
+    ride_track::remove_quarantined(id, &auth.actor(), txn.as_ref()).await?;
+    info!("Quarantined track {} deleted", id);
+    Ok(NoContent)
+}
+
+/// Feature flags of the server
+#[openapi(tag = "Admin")]
+#[get("/admin/features")]
+pub async fn features(
+    _auth: Auth<Admin>,
+    flags: &State<FeatureFlags>,
+) -> Json<Vec<FeatureFlag>> {
+    Json(flags.all())
+}
+
+/// Enable or disable feature [name] on all server instances. The flag is kept across
+/// restarts and takes precedence over the configuration.
+#[openapi(tag = "Admin")]
+#[put("/admin/features/<name>", data = "<flag>")]
+pub async fn put_feature(
+    _auth: Auth<Admin>,
+    db: &State<Database>,
+    flags: &State<FeatureFlags>,
+    name: &str,
+    flag: JsonBody<FeatureFlag>,
+) -> Result<Json<FeatureFlag>, ApiError> {
+    // Not in a request transaction, the other instances read the setting once notified
+    Ok(Json(flags.set(name, flag.enabled, db.conn.as_ref()).await?))
+}
+
+/// Maintenance mode of the server
+#[openapi(tag = "Admin")]
+#[get("/admin/maintenance")]
+pub async fn maintenance(
+    _auth: Auth<Admin>,
+    maintenance: &State<Maintenance>,
+) -> Json<MaintenanceStatus> {
+    Json(maintenance.status())
+}
+
+/// Enable or disable the maintenance mode on all server instances. The mode is kept across
+/// restarts. While it is enabled, writes except to admin endpoints are answered with 503
+/// and `Retry-After`, and reads stay available, e.g. during migrations or backups.
+#[openapi(tag = "Admin")]
+#[put("/admin/maintenance", data = "<request>")]
+pub async fn put_maintenance(
+    _auth: Auth<Admin>,
+    db: &State<Database>,
+    maintenance: &State<Maintenance>,
+    request: JsonBody<MaintenanceRequest>,
+) -> Result<Json<MaintenanceStatus>, ApiError> {
+    // Not in a request transaction, the other instances read the setting once notified
+    let status = maintenance.set(request.into_inner(), db.conn.as_ref()).await?;
+    if status.enabled {
+        warn!("Maintenance mode enabled, writes are rejected");
+    } else {
+        info!("Maintenance mode disabled");
+    }
+    Ok(Json(status))
+}
@@ -0,0 +1,110 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::ops::DerefMut;
+use rocket::{State, serde::json::Json};
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use rocket_okapi::openapi;
+use jwt_auth::jwt::{TokenProducer, TokenVerifier, TOKEN_USE_CLAIM, TOKEN_USE_REFRESH};
+use super::ApiError;
+use crate::fairings::AuthCache;
+use crate::request_guards::JsonBody;
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct RefreshRequest {
+    /// Refresh token
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AccessTokenResponse {
+    /// Newly issued access token
+    access_token: String,
+    /// Always `Bearer`
+    token_type: String,
+    /// Lifetime of the access token in seconds
+    expires_in: i64,
+}
+
+/// Exchange a valid refresh token for a new short-lived access token. Refresh tokens
+/// living longer than `refresh_token_max_lifetime` from issue to expiration are rejected.
+#[openapi(tag = "Auth")]
+#[post("/auth/refresh", data = "<request>")]
+pub async fn refresh(
+    auth_cache: &State<AuthCache>,
+    request: JsonBody<RefreshRequest>,
+) -> Result<Json<AccessTokenResponse>, ApiError> {
+    let mut key_cache = auth_cache
+        .key_cache
+        .write()
+        .await;
+
+    let mut verifier = TokenVerifier::new(key_cache.deref_mut())
+        .expect_audience(&auth_cache.expect_jwt_audience)
+        .with_max_expiration(auth_cache.refresh_token_max_lifetime)
+        .require_claim(TOKEN_USE_CLAIM, serde_json::Value::String(TOKEN_USE_REFRESH.to_string()));
+    if let Some(expect_jwt_issuer) = &auth_cache.expect_jwt_issuer {
+        verifier = verifier.expect_issuer(expect_jwt_issuer);
+    }
+    if let Some(issued_after) = auth_cache.jwt_issued_after {
+        verifier = verifier.must_be_issued_after(issued_after);
+    }
+    let (token, _) = verifier
+        .verify(&request.refresh_token)
+        .map_err(
+            |e| {
+                ApiError::new_unauthorized()
+                    .with_description(e.to_string())
+            }
+        )?;
+
+    let issuer = match &token.claims().registered.issuer {
+        Some(issuer) => issuer.clone(),
+        None => Err(
+            ApiError::new_bad_request()
+                .with_description("Issuer is not set in token")
+        )?,
+    };
+    let subject = match &token.claims().registered.subject {
+        Some(subject) => subject.clone(),
+        None => Err(
+            ApiError::new_bad_request()
+                .with_description("Subject is not set in token")
+        )?,
+    };
+
+    // The access token inherits all custom claims (e.g. ptet:write) except its use
+    let mut claims = token.claims().private.clone();
+    claims.remove(TOKEN_USE_CLAIM);
+
+    let expires_in = auth_cache.access_token_lifetime.num_seconds();
+    let access_token = TokenProducer::new(key_cache.deref_mut())
+        .with_issuer(issuer)
+        .with_audience(&auth_cache.expect_jwt_audience)
+        .with_expiration(chrono::Utc::now() + auth_cache.access_token_lifetime)
+        .with_random_token_id(None)
+        .add_claims_from_json(serde_json::Value::Object(claims.into_iter().collect()))
+        .and_then(|producer| producer.produce_compact(&subject))
+        .map_err(
+            |e| {
+                ApiError::new_internal_server_error()
+                    .with_description(e.to_string())
+            }
+        )?;
+
+    Ok(
+        Json(
+            AccessTokenResponse {
+                access_token,
+                token_type: "Bearer".to_string(),
+                expires_in,
+            }
+        )
+    )
+}
@@ -0,0 +1,301 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{State, serde::json::Json};
+use serde::{Deserialize, Serialize};
+use rocket_okapi::okapi::schemars;
+use rocket_okapi::openapi;
+use sea_orm::DatabaseTransaction;
+use super::ApiError;
+use crate::fairings::TagCache;
+use crate::request_guards::{Auth, JsonBody, ReadWrite, RequestTransaction};
+use crate::model::audit::Actor;
+use crate::model::event::{Action, Event, EventBus, Resource};
+use crate::model::{
+    ride, ride::Ride,
+    ride_tag_link, ride_tag_link::RideTagLink,
+    tag, tag::{PriceTag, Tag},
+    tag_option, tag_option::TagOption,
+};
+
+/// Maximum number of operations in a batch
+const MAX_OPERATIONS: usize = 100;
+
+/// ID of an existing resource, or `"$<index>"` for the resource created by an earlier
+/// operation of the same batch
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum IdRef {
+    Id(u32),
+    Ref(String),
+}
+
+/// Operation of a batch
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    CreateRide { ride: Ride },
+    UpdateRide { ride_id: IdRef, ride: Ride },
+    DeleteRide { ride_id: IdRef },
+    CreateTag { tag: Tag },
+    UpdateTag { tag_id: IdRef, tag: Tag },
+    DeleteTag { tag_id: IdRef },
+    CreateTagOption { tag_id: IdRef, option: TagOption },
+    UpdateTagOption { option_id: IdRef, option: TagOption },
+    DeleteTagOption { option_id: IdRef },
+    CreateRideTag { ride_id: IdRef, tag_id: IdRef, link: RideTagLink },
+    UpdateRideTag { link_id: IdRef, link: RideTagLink },
+    DeleteRideTag { link_id: IdRef },
+}
+
+/// Batch of operations, executed in order
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+pub struct BatchRequest {
+    operations: Vec<Operation>,
+}
+
+/// Resource created by an operation
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum Created {
+    Ride(Ride),
+    Tag(Tag),
+    TagOption(TagOption),
+    RideTag(RideTagLink),
+}
+
+/// Result of an operation
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct OperationResult {
+    /// ID of the created, updated or deleted resource
+    id: u32,
+    /// Created resource, only set for create operations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<Created>,
+}
+
+/// Results of all operations, in the order of the request
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct BatchResponse {
+    results: Vec<OperationResult>,
+}
+
+impl OperationResult {
+    fn created(id: u32, created: Created) -> Self {
+        Self {
+            id,
+            created: Some(created),
+        }
+    }
+
+    fn changed(id: u32) -> Self {
+        Self {
+            id,
+            created: None,
+        }
+    }
+}
+
+/// Resolve [id] against [results] of earlier operations
+fn resolve(id: &IdRef, results: &[OperationResult]) -> Result<u32, ApiError> {
+    match id {
+        IdRef::Id(id) => Ok(*id),
+        IdRef::Ref(reference) => {
+            let index = reference
+                .strip_prefix('$')
+                .and_then(|index| index.parse::<usize>().ok())
+                .ok_or_else(|| {
+                    ApiError::new_bad_request()
+                        .with_description(format!("Invalid reference {}, expected $<index>", reference))
+                })?;
+            match results.get(index) {
+                Some(result) if result.created.is_some() => Ok(result.id),
+                _ => Err(
+                    ApiError::new_bad_request()
+                        .with_description(format!("Reference {} is not an earlier create operation", reference))
+                ),
+            }
+        },
+    }
+}
+
+/// Execute [operation] for [user_id] on behalf of [actor] within [txn]. Returns the result and the event
+/// to publish after the transaction was committed.
+async fn execute(
+    operation: Operation,
+    user_id: u32,
+    actor: &Actor,
+    results: &[OperationResult],
+    price_tag: &PriceTag,
+    txn: &DatabaseTransaction,
+) -> Result<(OperationResult, Event), ApiError> {
+    let result = match operation {
+        Operation::CreateRide { ride } => {
+            let ride = ride::CreateUpdateBuilder::from_json(ride)
+                .insert(user_id, actor, txn)
+                .await?;
+            let event = Event::new(user_id, Resource::Ride, Action::Created, ride.id()).with_data(&ride);
+            (OperationResult::created(ride.id(), Created::Ride(ride)), event)
+        },
+        Operation::UpdateRide { ride_id, ride } => {
+            let ride_id = resolve(&ride_id, results)?;
+            ride::is_owner(ride_id, user_id, false, txn).await?;
+            ride::CreateUpdateBuilder::from_json(ride)
+                .update(ride_id, actor, txn)
+                .await?;
+            let ride = Ride::find_by_id(ride_id, true, false, txn).await?;
+            let event = Event::new(user_id, Resource::Ride, Action::Updated, ride_id).with_data(&ride);
+            (OperationResult::changed(ride_id), event)
+        },
+        Operation::DeleteRide { ride_id } => {
+            let ride_id = resolve(&ride_id, results)?;
+            ride::is_owner(ride_id, user_id, false, txn).await?;
+            ride::remove(ride_id, actor, txn).await?;
+            let event = Event::new(user_id, Resource::Ride, Action::Deleted, ride_id);
+            (OperationResult::changed(ride_id), event)
+        },
+        Operation::CreateTag { tag } => {
+            let tag = tag::CreateUpdateBuilder::from_json(tag)
+                .with_price_tag(price_tag)
+                .insert(user_id, actor, txn)
+                .await?;
+            let event = Event::new(user_id, Resource::Tag, Action::Created, tag.id()).with_data(&tag);
+            (OperationResult::created(tag.id(), Created::Tag(tag)), event)
+        },
+        Operation::UpdateTag { tag_id, tag } => {
+            let tag_id = resolve(&tag_id, results)?;
+            tag::is_owner(tag_id, user_id, false, txn).await?;
+            tag::CreateUpdateBuilder::from_json(tag)
+                .with_price_tag(price_tag)
+                .update(tag_id, actor, txn)
+                .await?;
+            let tag = Tag::find_by_id(tag_id, true, false, txn).await?;
+            let event = Event::new(user_id, Resource::Tag, Action::Updated, tag_id).with_data(&tag);
+            (OperationResult::changed(tag_id), event)
+        },
+        Operation::DeleteTag { tag_id } => {
+            let tag_id = resolve(&tag_id, results)?;
+            tag::is_owner(tag_id, user_id, false, txn).await?;
+            tag::remove(tag_id, actor, txn).await?;
+            let event = Event::new(user_id, Resource::Tag, Action::Deleted, tag_id);
+            (OperationResult::changed(tag_id), event)
+        },
+        Operation::CreateTagOption { tag_id, option } => {
+            let tag_id = resolve(&tag_id, results)?;
+            tag::is_owner(tag_id, user_id, false, txn).await?;
+            let option = tag_option::CreateUpdateBuilder::from_json(option)
+                .insert(tag_id, actor, txn)
+                .await?;
+            let event = Event::new(user_id, Resource::TagOption, Action::Created, option.id()).with_data(&option);
+            (OperationResult::created(option.id(), Created::TagOption(option)), event)
+        },
+        Operation::UpdateTagOption { option_id, option } => {
+            let option_id = resolve(&option_id, results)?;
+            tag_option::is_owner(option_id, user_id, false, txn).await?;
+            tag_option::CreateUpdateBuilder::from_json(option)
+                .update(option_id, actor, txn)
+                .await?;
+            let option = TagOption::find_by_id(option_id, false, txn).await?;
+            let event = Event::new(user_id, Resource::TagOption, Action::Updated, option_id).with_data(&option);
+            (OperationResult::changed(option_id), event)
+        },
+        Operation::DeleteTagOption { option_id } => {
+            let option_id = resolve(&option_id, results)?;
+            tag_option::is_owner(option_id, user_id, false, txn).await?;
+            tag_option::remove(option_id, actor, txn).await?;
+            let event = Event::new(user_id, Resource::TagOption, Action::Deleted, option_id);
+            (OperationResult::changed(option_id), event)
+        },
+        Operation::CreateRideTag { ride_id, tag_id, link } => {
+            let ride_id = resolve(&ride_id, results)?;
+            let tag_id = resolve(&tag_id, results)?;
+            ride::is_owner(ride_id, user_id, false, txn).await?;
+            tag::is_owner(tag_id, user_id, false, txn).await?;
+            // Not from the tag cache, which does not see tags of this batch
+            let tag = Tag::find_by_id(tag_id, true, false, txn).await?;
+
+            // Prevent double use of tag ID
+            if RideTagLink::find_by_tag_id(ride_id, tag_id, false, txn).await.is_ok() {
+                Err(
+                    ApiError::new_bad_request()
+                        .with_description("Tag is already linked to ride")
+                )?;
+            }
+
+            let builder = ride_tag_link::CreateUpdateBuilder::from_json(link);
+            builder.validate(&tag)?;
+            let link = builder
+                .insert(ride_id, tag_id, actor, txn)
+                .await?;
+            let event = Event::new(user_id, Resource::RideTag, Action::Created, link.id()).with_data(&link);
+            (OperationResult::created(link.id(), Created::RideTag(link)), event)
+        },
+        Operation::UpdateRideTag { link_id, link } => {
+            let link_id = resolve(&link_id, results)?;
+            ride_tag_link::is_owner(link_id, user_id, false, txn).await?;
+            let tag_id = RideTagLink::find_by_id(link_id, false, txn).await?.tag_id();
+            let tag = Tag::find_by_id(tag_id, true, false, txn).await?;
+            let builder = ride_tag_link::CreateUpdateBuilder::from_json(link);
+            builder.validate(&tag)?;
+            builder
+                .update(link_id, actor, txn)
+                .await?;
+            let link = RideTagLink::find_by_id(link_id, false, txn).await?;
+            let event = Event::new(user_id, Resource::RideTag, Action::Updated, link_id).with_data(&link);
+            (OperationResult::changed(link_id), event)
+        },
+        Operation::DeleteRideTag { link_id } => {
+            let link_id = resolve(&link_id, results)?;
+            ride_tag_link::is_owner(link_id, user_id, false, txn).await?;
+            ride_tag_link::remove(link_id, actor, txn).await?;
+            let event = Event::new(user_id, Resource::RideTag, Action::Deleted, link_id);
+            (OperationResult::changed(link_id), event)
+        },
+    };
+    Ok(result)
+}
+
+/// Execute operations in a single transaction. Either all operations succeed, or none
+/// is applied and the error of the first failing operation is returned.
+#[openapi(tag = "Batch")]
+#[post("/batch", data = "<batch>")]
+pub async fn post(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
+    price_tag: &State<PriceTag>,
+    batch: JsonBody<BatchRequest>,
+) -> Result<Json<BatchResponse>, ApiError> {
+    let operations = batch.into_inner().operations;
+    if operations.len() > MAX_OPERATIONS {
+        Err(
+            ApiError::new_bad_request()
+                .with_description(format!("Batch must not have more than {} operations", MAX_OPERATIONS))
+        )?;
+    }
+
+    let actor = auth.actor();
+    let mut results = Vec::with_capacity(operations.len());
+    let mut changes = Vec::with_capacity(operations.len());
+    for (index, operation) in operations.into_iter().enumerate() {
+        let (result, event) = execute(operation, auth.user_id, &actor, &results, price_tag, txn.as_ref())
+            .await
+            .map_err(|e| e.with_context(format!("Operation {}", index)))?;
+        results.push(result);
+        changes.push(event);
+    }
+    if changes.iter().any(|event| matches!(event.resource, Resource::Tag | Resource::TagOption)) {
+        txn.invalidate_tags(tag_cache, auth.user_id);
+    }
+    for change in changes {
+        txn.publish(events, change);
+    }
+
+    Ok(Json(BatchResponse { results }))
+}
@@ -0,0 +1,63 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    response::status::NoContent,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, JsonBody, ReadOnly, ReadWrite, RequestTransaction};
+use crate::model::{caldav, caldav::{CaldavSettings, SyncReport}};
+
+/// CalDAV settings of the user, without the password
+#[openapi(tag = "CalDAV")]
+#[get("/caldav")]
+pub async fn get(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+) -> Result<Json<CaldavSettings>, ApiError> {
+    Ok(Json(CaldavSettings::find(auth.user_id, db.read_conn.as_ref()).await?))
+}
+
+/// Set the calendar to synchronize with. The password is stored as given, so an app
+/// password should be used. It may be omitted to keep the stored one.
+#[openapi(tag = "CalDAV")]
+#[put("/caldav", data = "<settings>")]
+pub async fn put(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    settings: JsonBody<CaldavSettings>,
+) -> Result<Json<CaldavSettings>, ApiError> {
+    Ok(Json(settings.into_inner().save(auth.user_id, txn.as_ref()).await?))
+}
+
+/// Stop synchronizing. Events already in the calendar are kept.
+#[openapi(tag = "CalDAV")]
+#[delete("/caldav")]
+pub async fn delete(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+) -> Result<NoContent, ApiError> {
+    caldav::remove(auth.user_id, txn.as_ref()).await?;
+    Ok(NoContent)
+}
+
+/// Synchronize now. Rides changed since the last synchronization are pushed, flagged
+/// events are pulled into the inbox. Fails with 502 if the calendar cannot be reached.
+#[openapi(tag = "CalDAV")]
+#[post("/caldav/sync")]
+pub async fn sync(
+    auth: Auth<ReadWrite>,
+    db: &State<Database>,
+) -> Result<Json<SyncReport>, ApiError> {
+    // Not in a request transaction, the error of a failed sync is recorded as well
+    Ok(Json(caldav::sync(auth.user_id, db.conn.as_ref()).await?))
+}
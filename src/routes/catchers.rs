@@ -0,0 +1,21 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::http::Status;
+use rocket::Request;
+use crate::routes::ApiError;
+
+/// Respond with the error a guard stored using [ApiError::cache_for_catcher], or a
+/// generic [ApiError] for [status] otherwise
+#[catch(default)]
+pub fn default(status: Status, request: &Request) -> ApiError {
+    match request.local_cache(|| None::<ApiError>) {
+        Some(error) => error.clone(),
+        None => ApiError::from_status(status),
+    }
+}
@@ -0,0 +1,29 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, ReadOnly};
+use crate::model::{commute, commute::Commute};
+
+/// Recurring rides of the last 8 weeks, on the same route on at least 4 days at times
+/// within an hour. Commutes without a template for the route propose one to create with
+/// `POST /ride`.
+#[openapi(tag = "Ride")]
+#[get("/commute/suggestions")]
+pub async fn suggestions(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+) -> Result<Json<Vec<Commute>>, ApiError> {
+    Ok(Json(commute::suggestions(auth.user_id, chrono::Utc::now(), db.read_conn.as_ref()).await?))
+}
@@ -0,0 +1,59 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::time::Duration;
+use rocket::{Data, Request};
+use rocket::http::Status;
+use rocket::route::{Handler, Outcome, Route};
+use super::ApiError;
+
+/// Handler cancelling [inner] if it does not finish within [timeout]
+#[derive(Clone)]
+struct Deadline {
+    inner: Box<dyn Handler>,
+    timeout: Duration,
+}
+
+#[rocket::async_trait]
+impl Handler for Deadline {
+    async fn handle<'r>(&self, request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        match tokio::time::timeout(self.timeout, self.inner.handle(request, data)).await {
+            Ok(outcome) => outcome,
+            Err(_) => {
+                warn!("{} {} exceeded the deadline of {} s and was cancelled", request.method(), request.uri().path(), self.timeout.as_secs());
+                let (status, _) = ApiError::from_status(Status::ServiceUnavailable)
+                    .with_description("Request took too long and was cancelled")
+                    .cache_for_catcher(request);
+                Outcome::Error(status)
+            },
+        }
+    }
+}
+
+/// Cancel the handlers of [routes] after [timeout], if given, and respond with 503.
+/// Fairings cannot cancel handlers, so the handlers are wrapped instead. The deadline
+/// covers the request guards, including reading the body, but not streaming the
+/// response, e.g. of server-sent events. Changes of the request transaction are rolled
+/// back.
+pub fn with_deadline(routes: Vec<Route>, timeout: Option<Duration>) -> Vec<Route> {
+    let Some(timeout) = timeout else {
+        return routes;
+    };
+    routes
+        .into_iter()
+        .map(
+            |mut route| {
+                route.handler = Box::new(Deadline {
+                    inner: route.handler,
+                    timeout,
+                });
+                route
+            }
+        )
+        .collect()
+}
@@ -15,7 +15,7 @@ use rocket_okapi::{
 use rocket_okapi::gen::OpenApiGenerator;
 use rocket_okapi::okapi::openapi3::Responses;
 
-#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, schemars::JsonSchema)]
 pub struct ErrorInfo {
     /// HTTP status code
     code: u16,
@@ -25,7 +25,7 @@ pub struct ErrorInfo {
     description: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, schemars::JsonSchema)]
 pub struct ApiError {
     /// Details about the error
     error: ErrorInfo,
@@ -62,6 +62,36 @@ impl ApiError {
         }
     }
 
+    pub fn new_conflict() -> Self {
+        ApiError {
+            error: ErrorInfo {
+                code: Status::Conflict.code,
+                reason: "Conflict".to_string(),
+                description: None,
+            },
+        }
+    }
+
+    pub fn new_payload_too_large() -> Self {
+        ApiError {
+            error: ErrorInfo {
+                code: Status::PayloadTooLarge.code,
+                reason: "Payload Too Large".to_string(),
+                description: None,
+            },
+        }
+    }
+
+    pub fn new_unprocessable_entity() -> Self {
+        ApiError {
+            error: ErrorInfo {
+                code: Status::UnprocessableEntity.code,
+                reason: "Unprocessable Entity".to_string(),
+                description: None,
+            },
+        }
+    }
+
     pub fn new_internal_server_error() -> Self {
         ApiError {
             error: ErrorInfo {
@@ -77,9 +107,47 @@ impl ApiError {
         self
     }
 
+    /// Prefix the description with [context], e.g. the failing item of a batch
+    pub fn with_context<S: ToString>(mut self, context: S) -> Self {
+        let detail = match self.error.description.take() {
+            Some(description) => description,
+            None => self.error.reason.clone(),
+        };
+        self.error.description = Some(format!("{}: {}", context.to_string(), detail));
+        self
+    }
+
+    /// Generic error for [status], used if no more specific error is available
+    pub fn from_status(status: Status) -> Self {
+        ApiError {
+            error: ErrorInfo {
+                code: status.code,
+                reason: status.reason_lossy().to_string(),
+                description: None,
+            },
+        }
+    }
+
+    /// Detailed description, or the reason if there is none
+    pub fn message(&self) -> &str {
+        self.error.description.as_deref().unwrap_or(&self.error.reason)
+    }
+
     pub fn to_status(&self) -> Status {
         Status::from_code(self.error.code).unwrap_or(rocket::http::Status::InternalServerError)
     }
+
+    /// Store the error in the request-local cache of [request], so the default catcher
+    /// responds with it instead of a generic error. Guards failing with [Outcome::Error]
+    /// do not pass their error to the catcher otherwise.
+    ///
+    /// [Outcome::Error]: rocket::outcome::Outcome::Error
+    pub fn cache_for_catcher(self, request: &rocket::Request<'_>) -> (Status, ApiError) {
+        let cached = request.local_cache(|| Some(self.clone()));
+        // The cache is only filled once per request, keep the first error
+        let error = cached.clone().unwrap_or(self);
+        (error.to_status(), error)
+    }
 }
 
 impl Into<(Status, ApiError)> for ApiError {
@@ -90,13 +158,38 @@ impl Into<(Status, ApiError)> for ApiError {
 
 impl From<sea_orm::DbErr> for ApiError {
     fn from(value: sea_orm::DbErr) -> Self {
-        ApiError::new_internal_server_error()
-            .with_description(value.to_string())
+        match value.sql_err() {
+            // Unique indexes guard against concurrent requests creating the same resource
+            Some(sea_orm::SqlErr::UniqueConstraintViolation(_)) => {
+                ApiError::new_conflict()
+                    .with_description("Conflicts with an existing resource")
+            },
+            // The pool reconnects once the database is back, so clients may retry
+            _ if matches!(value, sea_orm::DbErr::ConnectionAcquire(_) | sea_orm::DbErr::Conn(_)) => {
+                ApiError::from_status(rocket::http::Status::ServiceUnavailable)
+                    .with_description("Database is unavailable, retry later")
+            },
+            _ => {
+                ApiError::new_internal_server_error()
+                    .with_description(value.to_string())
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.error.description {
+            Some(description) => write!(f, "{} {}: {}", self.error.code, self.error.reason, description),
+            None => write!(f, "{} {}", self.error.code, self.error.reason),
+        }
     }
 }
 
 impl<'r> rocket::response::Responder<'r, 'static> for ApiError {
-    fn respond_to(self, _: &'r rocket::Request) -> rocket::response::Result<'static> {
+    fn respond_to(self, request: &'r rocket::Request) -> rocket::response::Result<'static> {
+        // Keep the error for fairings, e.g. error reporting
+        request.local_cache(|| Some(self.clone()));
         let body = serde_json::to_string(&self).unwrap();
         rocket::Response::build()
             .sized_body(body.len(), std::io::Cursor::new(body))
@@ -109,12 +202,13 @@ impl<'r> rocket::response::Responder<'r, 'static> for ApiError {
 impl OpenApiResponderInner for ApiError {
     fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
         use rocket_okapi::okapi::{map, openapi3::{RefOr, MediaType}};
-        let mut make_response = |description: &str| {
+        let mut make_response = |error: ApiError, description: &str| {
             rocket_okapi::okapi::openapi3::Response {
                 description: description.to_string(),
                 content: map! {
                     "application/json".to_owned() => MediaType {
                         schema: Some(gen.json_schema::<ApiError>()),
+                        example: serde_json::to_value(error).ok(),
                         ..Default::default()
                     }
                 },
@@ -123,10 +217,34 @@ impl OpenApiResponderInner for ApiError {
         };
         Ok(Responses {
             responses: map! {
-                "400".to_owned() => RefOr::Object(make_response("Bad Request")),
-                "401".to_owned() => RefOr::Object(make_response("Unauthorized")),
-                "404".to_owned() => RefOr::Object(make_response("Not Found")),
-                "500".to_owned() => RefOr::Object(make_response("Internal Server Error")),
+                "400".to_owned() => RefOr::Object(make_response(
+                    ApiError::new_bad_request().with_description("EOF while parsing an object at line 1 column 1"),
+                    "Bad Request, e.g. malformed JSON or invalid query parameters",
+                )),
+                "401".to_owned() => RefOr::Object(make_response(
+                    ApiError::new_unauthorized(),
+                    "Unauthorized, the token is missing, invalid or lacks a required scope",
+                )),
+                "404".to_owned() => RefOr::Object(make_response(
+                    ApiError::new_not_found(),
+                    "Not Found, or owned by another user",
+                )),
+                "409".to_owned() => RefOr::Object(make_response(
+                    ApiError::new_conflict().with_description("Request with this idempotency key is in progress"),
+                    "Conflict, e.g. a tag key which is already used, a tag linked twice to a ride or a request in progress",
+                )),
+                "413".to_owned() => RefOr::Object(make_response(
+                    ApiError::new_payload_too_large(),
+                    "Payload Too Large, the body exceeds json_limit",
+                )),
+                "422".to_owned() => RefOr::Object(make_response(
+                    ApiError::new_unprocessable_entity().with_description("missing field `location_from` at line 1 column 42"),
+                    "Unprocessable Entity, the body does not match the expected structure",
+                )),
+                "500".to_owned() => RefOr::Object(make_response(
+                    ApiError::new_internal_server_error(),
+                    "Internal Server Error",
+                )),
             },
             ..Default::default()
         })
@@ -0,0 +1,60 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::pin::Pin;
+use rocket::{
+    Shutdown,
+    State,
+    futures::stream::Stream,
+    response::stream::{stream, Event as StreamEvent, EventStream},
+    tokio::select,
+    tokio::sync::broadcast::error::RecvError,
+};
+use rocket_okapi::openapi;
+use crate::request_guards::{Auth, Enabled, ReadOnly};
+use crate::request_guards::feature::Events;
+use crate::model::event::EventBus;
+
+/// Stream of server-sent events
+pub type ChangeStream = EventStream<Pin<Box<dyn Stream<Item = StreamEvent> + Send>>>;
+
+/// Stream changes of the user's rides, tags, tag options and ride tags as server-sent
+/// events. The SSE event name is the event type, e.g. `ride.updated`, and the data is
+/// the JSON event as sent to webhooks. If the client falls behind, a `lagged` event
+/// with the number of dropped events is sent and the client should reload its data.
+#[openapi(tag = "Event")]
+#[get("/events")]
+pub fn stream(
+    auth: Auth<ReadOnly>,
+    _feature: Enabled<Events>,
+    events: &State<EventBus>,
+    mut shutdown: Shutdown,
+) -> ChangeStream {
+    let user_id = auth.user_id;
+    let mut receiver = events.subscribe();
+    EventStream::from(Box::pin(stream! {
+        loop {
+            let event = select! {
+                _ = &mut shutdown => break,
+                event = receiver.recv() => event,
+            };
+            match event {
+                Ok(event) if event.user_id == user_id => {
+                    yield StreamEvent::json(&event)
+                        .id(event.id.clone())
+                        .event(event.event_type.clone());
+                },
+                Ok(_) => (),
+                Err(RecvError::Lagged(count)) => {
+                    yield StreamEvent::data(count.to_string()).event("lagged");
+                },
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }) as Pin<Box<dyn Stream<Item = StreamEvent> + Send>>)
+}
@@ -0,0 +1,63 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, ReadOnly};
+use crate::model::fare::{FareDeviation, FareEstimate, Tariff};
+
+/// Relative deviation from the estimate above which rides are listed if not given
+const DEFAULT_THRESHOLD: f64 = 0.25;
+
+/// Make sure that fare regions are configured
+fn check_enabled(tariff: &Tariff) -> Result<(), ApiError> {
+    if !tariff.is_enabled() {
+        Err(ApiError::new_not_found().with_description("Fares are not configured"))?;
+    }
+    Ok(())
+}
+
+/// Expected price of a journey from `from` to `to`, given as ride locations
+#[openapi(tag = "Fare")]
+#[get("/fare/estimate?<from>&<to>")]
+pub async fn estimate(
+    _auth: Auth<ReadOnly>,
+    tariff: &State<Tariff>,
+    from: &str,
+    to: &str,
+) -> Result<Json<FareEstimate>, ApiError> {
+    check_enabled(tariff)?;
+    let estimate = tariff
+        .estimate(from, to)
+        .ok_or_else(|| ApiError::new_not_found().with_description("No fare region covers both locations"))?;
+    Ok(Json(estimate))
+}
+
+/// Rides whose recorded price deviates from the estimate by more than `threshold`,
+/// relative to the estimate (default 0.25)
+#[openapi(tag = "Fare")]
+#[get("/fare/deviations?<threshold>")]
+pub async fn deviations(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    tariff: &State<Tariff>,
+    threshold: Option<f64>,
+) -> Result<Json<Vec<FareDeviation>>, ApiError> {
+    check_enabled(tariff)?;
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+    if !threshold.is_finite() || threshold < 0.0 {
+        Err(ApiError::new_bad_request().with_description("threshold must not be negative"))?;
+    }
+    let deviations = tariff.deviations(auth.user_id, threshold, db.read_conn.as_ref()).await?;
+    Ok(Json(deviations))
+}
@@ -0,0 +1,37 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, ReadOnly};
+use crate::model::geocode::{Geocoder, Place};
+
+/// Search places by name, e.g. to fill in ride locations. Fails with 404 if no
+/// geocoding provider is configured.
+#[openapi(tag = "Geocode")]
+#[get("/geocode?<q>")]
+pub async fn search(
+    _auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    geocoder: &State<Geocoder>,
+    q: &str,
+) -> Result<Json<Vec<Place>>, ApiError> {
+    if !geocoder.is_enabled() {
+        Err(ApiError::new_not_found().with_description("Geocoding is not configured"))?;
+    }
+    if q.trim().is_empty() {
+        Err(ApiError::new_bad_request().with_description("q must not be empty"))?;
+    }
+    let places = geocoder.search(q, db.conn.as_ref()).await?;
+    Ok(Json(places))
+}
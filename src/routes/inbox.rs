@@ -0,0 +1,115 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    response::status::NoContent,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, JsonBody, ReadOnly, ReadWrite, RequestTransaction};
+use crate::request_guards::inbox_secret::InboxSecret;
+use crate::responders::Created;
+use crate::model::{inbox, inbox::{InboundMail, Inbox, InboxAddress, InboxItem}, ride::Ride};
+use crate::model::event::{Action, Event, EventBus, Resource};
+
+/// Make sure that the inbox is configured
+fn check_enabled(inbox: &Inbox) -> Result<(), ApiError> {
+    if !inbox.is_enabled() {
+        Err(ApiError::new_not_found().with_description("Inbox is not configured"))?;
+    }
+    Ok(())
+}
+
+/// Address to forward booking confirmations to, created on first request
+#[openapi(tag = "Inbox")]
+#[get("/inbox/address")]
+pub async fn address(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    inbox: &State<Inbox>,
+) -> Result<Json<InboxAddress>, ApiError> {
+    check_enabled(inbox)?;
+    Ok(Json(inbox.address(auth.user_id, db.conn.as_ref()).await?))
+}
+
+/// Draft rides parsed from received mails or pulled from the calendar, newest first
+#[openapi(tag = "Inbox")]
+#[get("/inbox")]
+pub async fn list(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+) -> Result<Json<Vec<InboxItem>>, ApiError> {
+    Ok(Json(InboxItem::find_all(auth.user_id, db.read_conn.as_ref()).await?))
+}
+
+#[openapi(tag = "Inbox")]
+#[get("/inbox/<item_id>")]
+pub async fn get(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    item_id: u32,
+) -> Result<Json<InboxItem>, ApiError> {
+    // First, make sure that resource belongs to the user
+    inbox::is_owner(item_id, auth.user_id, db.read_conn.as_ref()).await?;
+
+    Ok(Json(InboxItem::find_by_id(item_id, db.read_conn.as_ref()).await?))
+}
+
+/// Create the ride as confirmed by the user, usually the values of the draft with
+/// corrections, and remove the draft. The amount of the draft is linked with the price
+/// tag.
+#[openapi(tag = "Inbox")]
+#[post("/inbox/<item_id>/confirm", data = "<ride>")]
+pub async fn confirm(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    inbox: &State<Inbox>,
+    events: &State<EventBus>,
+    item_id: u32,
+    ride: JsonBody<Ride>,
+) -> Result<Created<Json<Ride>>, ApiError> {
+    // First, make sure that resource belongs to the user
+    inbox::is_owner(item_id, auth.user_id, txn.as_ref()).await?;
+
+    let ride = inbox
+        .confirm(item_id, auth.user_id, ride.into_inner(), &auth.actor(), txn.as_ref())
+        .await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::Ride, Action::Created, ride.id()).with_data(&ride));
+    Ok(Created::new(format!("/ride/{}", ride.id()), Json(ride)))
+}
+
+/// Discard the draft
+#[openapi(tag = "Inbox")]
+#[delete("/inbox/<item_id>")]
+pub async fn delete(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    item_id: u32,
+) -> Result<NoContent, ApiError> {
+    // First, make sure that resource belongs to the user
+    inbox::is_owner(item_id, auth.user_id, txn.as_ref()).await?;
+
+    inbox::remove(item_id, txn.as_ref()).await?;
+    Ok(NoContent)
+}
+
+/// Webhook of the inbound mail service. The mail is stored as draft of the user whose
+/// inbox address is among the recipients, 404 if there is none.
+#[openapi(tag = "Inbox")]
+#[post("/inbox/mail", data = "<mail>")]
+pub async fn receive(
+    _secret: InboxSecret,
+    txn: RequestTransaction<'_>,
+    mail: JsonBody<InboundMail>,
+) -> Result<Created<Json<InboxItem>>, ApiError> {
+    let item = inbox::receive(mail.into_inner(), txn.as_ref()).await?;
+    Ok(Created::new(format!("/inbox/{}", item.id()), Json(item)))
+}
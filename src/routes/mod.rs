@@ -7,10 +7,32 @@
  */
 
 pub mod error;
+pub mod catchers;
+pub mod admin;
+pub mod auth;
+pub mod batch;
+pub mod caldav;
+pub mod commute;
+pub mod deadline;
+pub mod event;
+pub mod fare;
+pub mod geocode;
+pub mod inbox;
+pub mod quick_add;
+pub mod openapi;
+pub mod receipt;
+pub mod report;
 pub mod user;
 pub mod ride;
+pub mod ride_reference;
+pub mod ride_track;
 pub mod ride_tag;
 pub mod tag;
 pub mod tag_option;
+pub mod telegram;
+pub mod webhook;
+pub mod webdav;
+pub mod v1;
+pub mod v2;
 
 pub use error::ApiError;
@@ -0,0 +1,36 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket_okapi::okapi::schemars::schema::SchemaObject;
+use rocket_okapi::okapi::schemars::visit::{self, Visitor};
+use rocket_okapi::settings::OpenApiSettings;
+
+/// Settings for generating the OpenAPI specification
+pub fn settings() -> OpenApiSettings {
+    let mut settings = OpenApiSettings::new();
+    settings.schema_settings = settings.schema_settings.with_visitor(ExampleVisitor);
+    settings
+}
+
+/// Moves the first of the `examples` of a schema, given with `#[schemars(example = ...)]`,
+/// to `example`, which OpenAPI 3.0 and the generated clients understand
+#[derive(Debug, Clone)]
+struct ExampleVisitor;
+
+impl Visitor for ExampleVisitor {
+    fn visit_schema_object(&mut self, schema: &mut SchemaObject) {
+        if let Some(metadata) = schema.metadata.as_mut() {
+            if !metadata.examples.is_empty() {
+                let example = metadata.examples.remove(0);
+                metadata.examples.clear();
+                schema.extensions.insert("example".to_string(), example);
+            }
+        }
+        visit::visit_schema_object(self, schema);
+    }
+}
@@ -0,0 +1,38 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::request_guards::{Auth, JsonBody, ReadWrite, RequestTransaction};
+use crate::responders::Created;
+use crate::model::quick_add::{QuickAdd, QuickAddRequest};
+use crate::model::ride::Ride;
+use crate::model::event::{Action, Event, EventBus, Resource};
+
+/// Create a ride from a line of text like `8:05 Hbf → Airport 3.20€ bus`. The price is
+/// linked with the price tag, words after the destination or `#option` naming options
+/// of enum tags link them. Fails with 422 if the line cannot be parsed.
+#[openapi(tag = "Ride")]
+#[post("/quick_add", data = "<request>")]
+pub async fn post(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    quick_add: &State<QuickAdd>,
+    request: JsonBody<QuickAddRequest>,
+) -> Result<Created<Json<Ride>>, ApiError> {
+    let ride = quick_add
+        .add(auth.user_id, &request.into_inner(), chrono::Utc::now(), &auth.actor(), txn.as_ref())
+        .await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::Ride, Action::Created, ride.id()).with_data(&ride));
+    Ok(Created::new(format!("/ride/{}", ride.id()), Json(ride)))
+}
@@ -0,0 +1,35 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, ReadOnly, ReceiptImage};
+use crate::model::receipt::{ReceiptReader, ReceiptScan};
+
+/// Read amount, date, time and route from the image of a receipt with OCR. The values
+/// are only suggested for the user to confirm, nothing is stored. Fails with 404 if no
+/// OCR engine is configured.
+#[openapi(tag = "Receipt")]
+#[post("/receipt/scan", data = "<image>")]
+pub async fn scan(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    reader: &State<ReceiptReader>,
+    image: ReceiptImage,
+) -> Result<Json<ReceiptScan>, ApiError> {
+    if !reader.is_enabled() {
+        Err(ApiError::new_not_found().with_description("OCR is not configured"))?;
+    }
+    let scan = reader.scan(auth.user_id, image.data, &image.content_type, db.read_conn.as_ref()).await?;
+    Ok(Json(scan))
+}
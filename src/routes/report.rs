@@ -0,0 +1,124 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::NaiveDate;
+use rocket::{State, http::ContentType, serde::json::Json};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, ReadOnly, RequestedLocale};
+use crate::responders::Attachment;
+use crate::model::mobility_budget::MobilityBudget;
+use crate::model::ride_summary::{MonthlySummary, RideSummaries};
+use crate::model::travel_log::TravelLogs;
+use crate::model::vat::{VatReport, VatSummary};
+
+/// First day of [month] given as `YYYY-MM`
+fn parse_month(name: &str, month: &str) -> Result<NaiveDate, ApiError> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map_err(|_| ApiError::new_bad_request().with_description(format!("{} must be a month as YYYY-MM", name)))
+}
+
+/// First and last month of the range `from` to `to` (default `from`)
+fn parse_months(from: &str, to: Option<&str>) -> Result<(NaiveDate, NaiveDate), ApiError> {
+    let first = parse_month("from", from)?;
+    let last = match to {
+        Some(to) => parse_month("to", to)?,
+        None => first,
+    };
+    if last < first {
+        Err(ApiError::new_bad_request().with_description("to must not be before from"))?;
+    }
+    Ok((first, last))
+}
+
+/// Reimbursement lines of the rides departing in the months `from` to `to` (default
+/// `from`), given as `YYYY-MM`, as CSV file in the layout of the employer's
+/// mobility-budget scheme. Fails with 404 if no scheme is configured.
+#[openapi(tag = "Report")]
+#[get("/report/mobility_budget?<from>&<to>")]
+pub async fn mobility_budget(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    budget: &State<MobilityBudget>,
+    from: &str,
+    to: Option<&str>,
+) -> Result<Attachment, ApiError> {
+    if !budget.is_enabled() {
+        Err(ApiError::new_not_found().with_description("Mobility budget is not configured"))?;
+    }
+    let (first, last) = parse_months(from, to)?;
+    let data = budget.to_csv(auth.user_id, first, last, db.read_conn.as_ref()).await?;
+    let file_name = if last == first {
+        format!("mobility-budget-{}.csv", first.format("%Y-%m"))
+    } else {
+        format!("mobility-budget-{}-to-{}.csv", first.format("%Y-%m"), last.format("%Y-%m"))
+    };
+    Ok(Attachment::new(ContentType::CSV, file_name, data))
+}
+
+/// VAT included in the prices of the rides departing in the months `from` to `to`
+/// (default `from`), given as `YYYY-MM` in UTC, summed per VAT rate. Prices of rides
+/// without VAT rate are summed separately.
+#[openapi(tag = "Report")]
+#[get("/report/vat?<from>&<to>")]
+pub async fn vat(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    report: &State<VatReport>,
+    from: &str,
+    to: Option<&str>,
+) -> Result<Json<VatSummary>, ApiError> {
+    let (first, last) = parse_months(from, to)?;
+    Ok(Json(report.summary(auth.user_id, first, last, db.read_conn.as_ref()).await?))
+}
+
+/// Number of rides and their prices and distances per month, for the months `from` to
+/// `to` (default `from`) given as `YYYY-MM` in UTC. Prices are summed, distances unless
+/// their tag is aggregated differently. Months without rides are left out. Read from
+/// summaries kept up to date on changes of rides and tags.
+#[openapi(tag = "Report")]
+#[get("/report/summary?<from>&<to>")]
+pub async fn summary(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    summaries: &State<RideSummaries>,
+    from: &str,
+    to: Option<&str>,
+) -> Result<Json<Vec<MonthlySummary>>, ApiError> {
+    let (first, last) = parse_months(from, to)?;
+    let range = (first.format("%Y-%m").to_string(), last.format("%Y-%m").to_string());
+    // Stale summaries are written when computed again, so not from the read replica
+    Ok(Json(summaries.monthly(auth.user_id, Some(range), db.conn.as_ref()).await?))
+}
+
+/// Travel log of the rides departing in `month` (1 to 12) of `year` in UTC, with
+/// departure, route, purpose and price per ride, to print and sign. `format` is `html`
+/// (default) or `pdf`.
+#[openapi(tag = "Report")]
+#[get("/report/travel_log?<year>&<month>&<format>")]
+pub async fn travel_log(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    logs: &State<TravelLogs>,
+    locale: RequestedLocale,
+    year: i32,
+    month: u32,
+    format: Option<&str>,
+) -> Result<Attachment, ApiError> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| ApiError::new_bad_request().with_description("month must be 1 to 12"))?;
+    let locale = locale.resolve(auth.user_id, db.read_conn.as_ref()).await?;
+    let log = logs.month(auth.user_id, first, locale, db.read_conn.as_ref()).await?;
+    let file_name = format!("travel-log-{}", first.format("%Y-%m"));
+    match format.unwrap_or("html") {
+        "html" => Ok(Attachment::new(ContentType::HTML, format!("{}.html", file_name), log.to_html().into_bytes())),
+        "pdf" => Ok(Attachment::new(ContentType::PDF, format!("{}.pdf", file_name), log.to_pdf())),
+        format => Err(ApiError::new_bad_request().with_description(format!("Unsupported format '{}', use html or pdf", format))),
+    }
+}
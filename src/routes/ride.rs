@@ -14,24 +14,169 @@ use rocket::{
 use rocket_okapi::openapi;
 use super::ApiError;
 use crate::fairings::Database;
-use crate::request_guards::{Auth, ReadOnly, ReadWrite};
-use crate::responders::PaginatedResult;
-use crate::model::{ride, ride::Ride};
+use crate::request_guards::{Auth, FieldSet, IdempotencyKey, IdFilter, IfModifiedSince, Include, IncludeDeleted, JsonBody, LinkProfile, ReadOnly, ReadWrite, RequestedLocale, RequestTransaction};
+use crate::request_guards::include::INCLUDE_TAGS;
+use crate::responders::{Conditional, Count, Created, Idempotent, Linked, Negotiated, OneOf, PaginatedResult, Sparse, Warnings};
+use entity::ride::RideStatus;
+use crate::model::{ride, ride::{Ride, RideFilter}};
+use crate::model::arrival::ArrivalEstimator;
+use crate::model::duplicate::{self, DuplicateGroup, Duplicates, MergeRequest};
+use crate::model::event::{Action, Event, EventBus, Resource};
+use crate::model::frequent_connection::{self, FrequentConnection};
+use crate::model::geocode::Geocoder;
+use crate::model::overlap;
+use crate::model::ride_status::{self, StatusChange, StatusRequest};
+use crate::model::ride_group::{self, GroupBy, RideGroup};
+use crate::model::ride_summary::RideSummaries;
+use crate::model::routing::{RoutePlanner, SuggestRequest, Suggestion};
+use crate::model::tag::Tag;
+use crate::model::{template, template::InstantiateRequest};
 
+/// Statuses of the comma-separated list [status]
+fn parse_statuses(status: Option<&str>) -> Result<Vec<RideStatus>, ApiError> {
+    status
+        .into_iter()
+        .flat_map(|status| status.split(','))
+        .map(|status| status.trim())
+        .filter(|status| !status.is_empty())
+        .map(|status| status.parse().map_err(|error: String| ApiError::new_bad_request().with_description(error)))
+        .collect()
+}
+
+/// List the rides. `status` filters by a comma-separated list of statuses, e.g.
+/// `status=completed,submitted`. With `group_by=day`, `week` or `month`, the rides are
+/// nested under the periods of their departure, with the number of rides and the
+/// numeric tags rolled up by their aggregation per period. With `summary=true`, the
+/// prices and distances of all matching rides, not only of the page, rolled up by the
+/// aggregation of their tags, are sent in `X-Total-Cost` and `X-Total-Distance`.
 #[openapi(tag = "Ride")]
-#[get("/ride?<page>&<size>")]
+#[get("/ride?<page>&<size>&<cursor>&<status>&<group_by>&<summary>")]
 pub async fn list(
     auth: Auth<ReadOnly>,
     db: &State<Database>,
+    summaries: &State<RideSummaries>,
+    links: LinkProfile,
+    fields: FieldSet,
+    include: Include,
+    if_modified_since: IfModifiedSince,
+    ids: IdFilter,
+    deleted: IncludeDeleted,
+    locale: RequestedLocale,
     page: Option<u64>,
     size: Option<u64>,
-) -> Result<PaginatedResult<Json<Vec<Ride>>>, ApiError> {
-    let count = Ride::count_all(auth.user_id, db.conn.as_ref()).await?;
-    if let Some(page) = page {
+    cursor: Option<u32>,
+    status: Option<&str>,
+    group_by: Option<&str>,
+    summary: Option<bool>,
+) -> Result<Conditional<OneOf<PaginatedResult<Negotiated<Sparse<Vec<Linked<Ride>>>>>, Json<Vec<RideGroup<Linked<Ride>>>>>>, ApiError> {
+    let filter = RideFilter {
+        statuses: parse_statuses(status)?,
+    };
+    let last_modified = ride::last_modified(auth.user_id, None, db.read_conn.as_ref()).await?;
+    if if_modified_since.is_fresh(last_modified) {
+        return Ok(Conditional::not_modified(last_modified));
+    }
+    let locale = locale.resolve(auth.user_id, db.read_conn.as_ref()).await?;
+
+    if let Some(group_by) = group_by {
+        let Some(group_by) = GroupBy::parse(group_by) else {
+            Err(
+                ApiError::new_bad_request()
+                    .with_description(format!("Cannot group by '{}', use day, week or month", group_by))
+            )?
+        };
+        if ids.ids().is_some() || page.is_some() || cursor.is_some() {
+            Err(
+                ApiError::new_bad_request()
+                    .with_description("Grouped rides cannot be paginated or fetched by IDs")
+            )?
+        }
+        // The tags are needed for the subtotals, but only returned if requested
+        let with_tags = include.has(INCLUDE_TAGS);
+        let rides = Ride::find_filtered(auth.user_id, &filter, true, deleted.is_set(), db.read_conn.as_ref()).await?;
+        let tags = Tag::find_all(auth.user_id, false, false, db.read_conn.as_ref()).await?;
+        let groups = ride_group::group(rides, &tags, group_by)
+            .into_iter()
+            .map(|group| group.map_rides(|rides| {
+                links.wrap_all(
+                    rides
+                        .into_iter()
+                        .map(|ride| if with_tags { ride } else { ride.without_tags() })
+                        .collect()
+                )
+            }))
+            .collect();
+        return Ok(Conditional::modified(last_modified, OneOf::Second(Json(groups))));
+    }
+
+    if let Some(ids) = ids.ids() {
+        if page.is_some() || cursor.is_some() {
+            Err(
+                ApiError::new_bad_request()
+                    .with_description("Fetching by IDs cannot be paginated")
+            )?
+        }
+        if !filter.statuses.is_empty() {
+            Err(
+                ApiError::new_bad_request()
+                    .with_description("Fetching by IDs cannot be filtered by status")
+            )?
+        }
+        if summary.unwrap_or(false) {
+            Err(
+                ApiError::new_bad_request()
+                    .with_description("Fetching by IDs cannot be summarized")
+            )?
+        }
+        let rides = Ride::find_by_ids(auth.user_id, ids, include.has(INCLUDE_TAGS), deleted.is_set(), db.read_conn.as_ref()).await?;
+        let count = rides.len() as u64;
+        return Ok(Conditional::modified(
+            last_modified,
+            OneOf::First(PaginatedResult::new_complete(Negotiated::new(fields.apply(links.wrap_all(rides)), "ride").with_locale(locale), Some(count))),
+        ));
+    }
+
+    if page.is_some() && cursor.is_some() {
+        Err(
+            ApiError::new_bad_request()
+                .with_description("Either page or cursor can be given")
+        )?
+    }
+    // Sums over all matching rides, in one query independent of the page
+    let summary = match summary {
+        Some(true) => Some(summaries.summary(auth.user_id, &filter, deleted.is_set(), db.conn.as_ref()).await?),
+        _ => None,
+    };
+    let summarize = |result: PaginatedResult<Negotiated<Sparse<Vec<Linked<Ride>>>>>| match summary {
+        Some(summary) => result.with_summary(summary),
+        None => result,
+    };
+    if let Some(cursor) = cursor {
+        let Some(size) = size.filter(|size| *size > 0) else {
+            Err(
+                ApiError::new_bad_request()
+                    .with_description("Page size must be greater than zero.")
+            )?
+        };
+        let count = Ride::count_all(auth.user_id, &filter, deleted.is_set(), db.read_conn.as_ref()).await?;
+        let rides = Ride::find_all_after(auth.user_id, &filter, include.has(INCLUDE_TAGS), deleted.is_set(), db.read_conn.as_ref(), cursor, size).await?;
+        // A full page may be followed by more rides
+        let next_cursor = match rides.last() {
+            Some(ride) if rides.len() as u64 == size => Some(ride.id().to_string()),
+            _ => None,
+        };
+        Ok(Conditional::modified(
+            last_modified,
+            OneOf::First(summarize(PaginatedResult::new_cursor(Negotiated::new(fields.apply(links.wrap_all(rides)), "ride").with_locale(locale), count, size, next_cursor))),
+        ))
+    } else if let Some(page) = page {
         if let Some(size) = size {
             if size > 0 {
-                let rides = Ride::find_all_paginated(auth.user_id, db.conn.as_ref(), page, size).await?;
-                Ok(PaginatedResult::new_paginated(Json(rides), count, page, size))
+                let (rides, count) = Ride::find_all_paginated(auth.user_id, &filter, include.has(INCLUDE_TAGS), deleted.is_set(), db.read_conn.as_ref(), page, size).await?;
+                Ok(Conditional::modified(
+                    last_modified,
+                    OneOf::First(summarize(PaginatedResult::new_paginated(Negotiated::new(fields.apply(links.wrap_all(rides)), "ride").with_locale(locale), count, page, size))),
+                ))
             } else {
                 Err(
                     ApiError::new_bad_request()
@@ -45,22 +190,143 @@ pub async fn list(
             )?
         }
     } else {
-        let rides = Ride::find_all(auth.user_id, db.conn.as_ref()).await?;
-        Ok(PaginatedResult::new_complete(Json(rides), Some(count)))
+        let rides = Ride::find_filtered(auth.user_id, &filter, include.has(INCLUDE_TAGS), deleted.is_set(), db.read_conn.as_ref()).await?;
+        // All rides are fetched, so they need not be counted separately
+        let count = rides.len() as u64;
+        Ok(Conditional::modified(
+            last_modified,
+            OneOf::First(summarize(PaginatedResult::new_complete(Negotiated::new(fields.apply(links.wrap_all(rides)), "ride").with_locale(locale), Some(count)))),
+        ))
     }
 }
 
+/// Number of rides, without fetching them
+#[openapi(tag = "Ride")]
+#[get("/ride/count")]
+pub async fn count(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+) -> Result<Count, ApiError> {
+    Ok(Count::new(Ride::count_all(auth.user_id, &RideFilter::default(), false, db.read_conn.as_ref()).await?))
+}
+
+/// Only the `X-Total-Items` header of the list of rides
 #[openapi(tag = "Ride")]
-#[post("/ride", data = "<ride>")]
+#[head("/ride")]
+pub async fn head(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+) -> Result<Count, ApiError> {
+    Ok(Count::new(Ride::count_all(auth.user_id, &RideFilter::default(), false, db.read_conn.as_ref()).await?))
+}
+
+/// Create a ride. Overlaps with other rides are returned as `warnings`, or fail with 409
+/// with `strict=true`. If enabled, a missing arrival is estimated from the average
+/// duration of the route and `arrival_estimated` is set.
+#[openapi(tag = "Ride")]
+#[post("/ride?<strict>", data = "<ride>")]
 pub async fn post(
     auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    estimator: &State<ArrivalEstimator>,
+    idempotency_key: IdempotencyKey,
+    strict: Option<bool>,
+    ride: JsonBody<Ride>,
+) -> Result<Created<Idempotent<Ride>>, ApiError> {
+    let ride = ride.into_inner();
+    let response = idempotency_key.run(auth.user_id, &ride, txn.as_ref(), || async {
+        let ride = estimator.complete(auth.user_id, ride.clone(), None, txn.as_ref()).await?;
+        let warnings = overlap::check(auth.user_id, &ride, None, strict.unwrap_or(false), txn.as_ref()).await?;
+        let ride = ride::CreateUpdateBuilder::from_json(ride)
+            .insert(auth.user_id, &auth.actor(), txn.as_ref())
+            .await?;
+        txn.publish(events, Event::new(auth.user_id, Resource::Ride, Action::Created, ride.id()).with_data(&ride));
+        Ok(ride.with_warnings(warnings))
+    }).await?;
+    Ok(Created::new(format!("/ride/{}", response.resource_id()?), response))
+}
+
+/// The user's most common routes of the last 180 days, compared case-insensitively and
+/// with at least 2 rides, most frequent first, for one-tap entry of usual rides. Each has
+/// its typical departure times and the most common value of each tag linked with at
+/// least half of its rides. `limit` is 5 by default and at most 50.
+#[openapi(tag = "Ride")]
+#[get("/ride/suggestions?<limit>")]
+pub async fn suggestions(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    limit: Option<usize>,
+) -> Result<Json<Vec<FrequentConnection>>, ApiError> {
+    let limit = limit.unwrap_or(frequent_connection::DEFAULT_LIMIT);
+    if limit == 0 || limit > frequent_connection::MAX_LIMIT {
+        Err(ApiError::new_bad_request().with_description(format!("The limit must be between 1 and {}", frequent_connection::MAX_LIMIT)))?;
+    }
+    let connections = frequent_connection::suggestions(auth.user_id, chrono::Utc::now(), limit, db.read_conn.as_ref()).await?;
+    Ok(Json(connections))
+}
+
+/// Connections from `location_from` to `location_to` departing at `journey_departure`
+/// or later, to prefill the arrival of a new ride. Locations are resolved with
+/// the geocoder unless given as `<latitude>,<longitude>`. Fails with 404 if no router is
+/// configured.
+#[openapi(tag = "Ride")]
+#[post("/ride/suggest", data = "<request>")]
+pub async fn suggest(
+    _auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    planner: &State<RoutePlanner>,
+    geocoder: &State<Geocoder>,
+    request: JsonBody<SuggestRequest>,
+) -> Result<Json<Vec<Suggestion>>, ApiError> {
+    if !planner.is_enabled() {
+        Err(ApiError::new_not_found().with_description("Routing is not configured"))?;
+    }
+    let suggestions = planner.suggest(&request, geocoder, db.conn.as_ref()).await?;
+    Ok(Json(suggestions))
+}
+
+/// Groups of likely duplicate rides: same route, compared case-insensitively, departing
+/// within `window_minutes` (default configured, at most 1440) after the previous ride of
+/// the group and with the same price or none.
+#[openapi(tag = "Ride")]
+#[get("/ride/duplicates?<window_minutes>")]
+pub async fn duplicates(
+    auth: Auth<ReadOnly>,
     db: &State<Database>,
-    ride: Json<Ride>,
+    duplicates: &State<Duplicates>,
+    window_minutes: Option<u32>,
+) -> Result<Json<Vec<DuplicateGroup>>, ApiError> {
+    if window_minutes.is_some_and(|window_minutes| window_minutes > duplicate::MAX_WINDOW_MINUTES) {
+        Err(
+            ApiError::new_bad_request()
+                .with_description(format!("window_minutes must not exceed {}", duplicate::MAX_WINDOW_MINUTES))
+        )?;
+    }
+    let groups = duplicates.find(auth.user_id, window_minutes, db.read_conn.as_ref()).await?;
+    Ok(Json(groups))
+}
+
+/// Merge duplicates into the ride `keep`. Tags of the rides in `remove` which the kept
+/// ride lacks are copied to it, then the removed rides are soft-deleted. Responds with the
+/// kept ride and its tags.
+#[openapi(tag = "Ride")]
+#[post("/ride/duplicates/merge", data = "<request>")]
+pub async fn merge_duplicates(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    request: JsonBody<MergeRequest>,
 ) -> Result<Json<Ride>, ApiError> {
-    let result = ride::CreateUpdateBuilder::from_json(ride.into_inner())
-        .insert(auth.user_id, db.conn.as_ref())
-        .await?;
-    Ok(Json(result))
+    let merged = duplicate::merge(auth.user_id, request.into_inner(), &auth.actor(), txn.as_ref()).await?;
+    for link in &merged.links {
+        txn.publish(events, Event::new(auth.user_id, Resource::RideTag, Action::Created, link.id()).with_data(link));
+    }
+    for ride_id in &merged.removed {
+        txn.publish(events, Event::new(auth.user_id, Resource::Ride, Action::Deleted, *ride_id));
+    }
+    txn.publish(events, Event::new(auth.user_id, Resource::Ride, Action::Updated, merged.ride.id()).with_data(&merged.ride));
+    Ok(Json(merged.ride))
 }
 
 #[openapi(tag = "Ride")]
@@ -68,42 +334,129 @@ pub async fn post(
 pub async fn get(
     auth: Auth<ReadOnly>,
     db: &State<Database>,
+    links: LinkProfile,
+    fields: FieldSet,
+    include: Include,
+    if_modified_since: IfModifiedSince,
+    deleted: IncludeDeleted,
     ride_id: u32,
-) -> Result<Json<Ride>, ApiError> {
+) -> Result<Conditional<Sparse<Linked<Ride>>>, ApiError> {
     // First, make sure that resource belongs to the user
-    ride::is_owner(ride_id, auth.user_id, db.conn.as_ref()).await?;
+    ride::is_owner(ride_id, auth.user_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+
+    let last_modified = ride::last_modified(auth.user_id, Some(ride_id), db.read_conn.as_ref()).await?;
+    if if_modified_since.is_fresh(last_modified) {
+        return Ok(Conditional::not_modified(last_modified));
+    }
 
-    let ride = Ride::find_by_id(ride_id, db.conn.as_ref()).await?;
-    Ok(Json(ride))
+    let ride = Ride::find_by_id(ride_id, include.has(INCLUDE_TAGS), deleted.is_set(), db.read_conn.as_ref()).await?;
+    Ok(Conditional::modified(last_modified, fields.apply(links.wrap(ride))))
 }
 
+/// Update a ride. Responds without content, or with the `warnings` about overlaps with
+/// other rides. With `strict=true`, overlaps fail with 409. If enabled, a missing arrival
+/// is estimated as on creation.
 #[openapi(tag = "Ride")]
-#[put("/ride/<ride_id>", data = "<ride>")]
+#[put("/ride/<ride_id>?<strict>", data = "<ride>")]
 pub async fn put(
     auth: Auth<ReadWrite>,
-    db: &State<Database>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    estimator: &State<ArrivalEstimator>,
     ride_id: u32,
-    ride: Json<Ride>,
-) -> Result<NoContent, ApiError> {
+    strict: Option<bool>,
+    ride: JsonBody<Ride>,
+) -> Result<Warnings, ApiError> {
     // First, make sure that resource belongs to the user
-    ride::is_owner(ride_id, auth.user_id, db.conn.as_ref()).await?;
+    ride::is_owner(ride_id, auth.user_id, false, txn.as_ref()).await?;
 
-    ride::CreateUpdateBuilder::from_json(ride.into_inner())
-        .update(ride_id, db.conn.as_ref())
+    let ride = estimator.complete(auth.user_id, ride.into_inner(), Some(ride_id), txn.as_ref()).await?;
+    let warnings = overlap::check(auth.user_id, &ride, Some(ride_id), strict.unwrap_or(false), txn.as_ref()).await?;
+    ride::CreateUpdateBuilder::from_json(ride)
+        .update(ride_id, &auth.actor(), txn.as_ref())
         .await?;
-    Ok(NoContent)
+    let ride = Ride::find_by_id(ride_id, true, false, txn.as_ref()).await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::Ride, Action::Updated, ride_id).with_data(&ride));
+    Ok(Warnings::new(warnings))
 }
 
+/// Create a ride from the template `ride_id`, departing at `journey_departure` (default
+/// now). The tag links are copied; values of links with `per_ride` set keep the flag
+/// until confirmed by updating the link. Fails with 422 if the ride is not a template.
 #[openapi(tag = "Ride")]
-#[delete("/ride/<ride_id>")]
-pub async fn delete(
+#[post("/ride/<ride_id>/instantiate", data = "<request>")]
+pub async fn instantiate(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    ride_id: u32,
+    request: JsonBody<InstantiateRequest>,
+) -> Result<Created<Json<Ride>>, ApiError> {
+    // First, make sure that resource belongs to the user
+    ride::is_owner(ride_id, auth.user_id, false, txn.as_ref()).await?;
+
+    let ride = template::instantiate(ride_id, auth.user_id, request.into_inner(), chrono::Utc::now(), &auth.actor(), txn.as_ref()).await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::Ride, Action::Created, ride.id()).with_data(&ride));
+    Ok(Created::new(format!("/ride/{}", ride.id()), Json(ride)))
+}
+
+/// Change the status of the ride. Rides can change from `planned` to `completed` or
+/// `cancelled`, from `completed` to `submitted` or `cancelled`, from `cancelled` back to
+/// `planned`, and from `submitted` to `reimbursed` or back to `completed`. Other changes
+/// fail with 409.
+#[openapi(tag = "Ride")]
+#[post("/ride/<ride_id>/status", data = "<request>")]
+pub async fn post_status(
     auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    ride_id: u32,
+    request: JsonBody<StatusRequest>,
+) -> Result<Json<StatusChange>, ApiError> {
+    // First, make sure that resource belongs to the user
+    ride::is_owner(ride_id, auth.user_id, false, txn.as_ref()).await?;
+
+    let change = ride_status::change(ride_id, request.into_inner(), &auth.actor(), txn.as_ref()).await?;
+    let ride = Ride::find_by_id(ride_id, true, false, txn.as_ref()).await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::Ride, Action::Updated, ride_id).with_data(&ride));
+    Ok(Json(change))
+}
+
+/// Changes of the status of the ride, oldest first
+#[openapi(tag = "Ride")]
+#[get("/ride/<ride_id>/status")]
+pub async fn get_status(
+    auth: Auth<ReadOnly>,
     db: &State<Database>,
     ride_id: u32,
-) -> Result<NoContent, ApiError> {
+) -> Result<Json<Vec<StatusChange>>, ApiError> {
     // First, make sure that resource belongs to the user
-    ride::is_owner(ride_id, auth.user_id, db.conn.as_ref()).await?;
+    ride::is_owner(ride_id, auth.user_id, false, db.read_conn.as_ref()).await?;
+
+    Ok(Json(ride_status::history(ride_id, db.read_conn.as_ref()).await?))
+}
+
+/// Soft-delete the ride. With `permanent=true`, the ride and its tag links are deleted
+/// from the database instead, also if already soft-deleted.
+#[openapi(tag = "Ride")]
+#[delete("/ride/<ride_id>?<permanent>")]
+pub async fn delete(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    ride_id: u32,
+    permanent: Option<bool>,
+) -> Result<NoContent, ApiError> {
+    // First, make sure that resource belongs to the user. Soft-deleted ones can still be
+    // deleted permanently.
+    let permanent = permanent.unwrap_or(false);
+    ride::is_owner(ride_id, auth.user_id, permanent, txn.as_ref()).await?;
 
-    ride::remove(ride_id, db.conn.as_ref()).await?;
+    if permanent {
+        ride::purge(ride_id, &auth.actor(), txn.as_ref()).await?;
+    } else {
+        ride::remove(ride_id, &auth.actor(), txn.as_ref()).await?;
+    }
+    txn.publish(events, Event::new(auth.user_id, Resource::Ride, Action::Deleted, ride_id));
     Ok(NoContent)
 }
@@ -0,0 +1,114 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    response::status::NoContent,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, FieldSet, IncludeDeleted, JsonBody, LinkProfile, ReadOnly, ReadWrite, RequestTransaction};
+use crate::responders::{Created, Linked, Sparse};
+use crate::model::{ride, ride_reference, ride_reference::RideReference};
+use crate::model::event::{Action, Event, EventBus, Resource};
+
+/// External references of the ride, e.g. booking numbers, in order of creation
+#[openapi(tag = "Ride")]
+#[get("/ride/<ride_id>/reference")]
+pub async fn list(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    links: LinkProfile,
+    fields: FieldSet,
+    deleted: IncludeDeleted,
+    ride_id: u32,
+) -> Result<Sparse<Vec<Linked<RideReference>>>, ApiError> {
+    // First, make sure that resource belongs to the user
+    ride::is_owner(ride_id, auth.user_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+
+    let references = RideReference::find_all(ride_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+    Ok(fields.apply(links.wrap_all(references)))
+}
+
+#[openapi(tag = "Ride")]
+#[post("/ride/<ride_id>/reference", data = "<reference>")]
+pub async fn post(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    ride_id: u32,
+    reference: JsonBody<RideReference>,
+) -> Result<Created<Json<RideReference>>, ApiError> {
+    // First, make sure that resource belongs to the user
+    ride::is_owner(ride_id, auth.user_id, false, txn.as_ref()).await?;
+
+    let reference = ride_reference::CreateUpdateBuilder::from_json(reference.into_inner())
+        .insert(ride_id, &auth.actor(), txn.as_ref())
+        .await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::RideReference, Action::Created, reference.id()).with_data(&reference));
+    Ok(Created::new(format!("/ride/{}/reference/{}", ride_id, reference.id()), Json(reference)))
+}
+
+#[openapi(tag = "Ride")]
+#[get("/ride/<ride_id>/reference/<reference_id>")]
+pub async fn get(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    links: LinkProfile,
+    fields: FieldSet,
+    deleted: IncludeDeleted,
+    ride_id: u32,
+    reference_id: u32,
+) -> Result<Sparse<Linked<RideReference>>, ApiError> {
+    // First, make sure that resource belongs to the user
+    ride::is_owner(ride_id, auth.user_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+
+    let reference = RideReference::find_by_id(ride_id, reference_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+    Ok(fields.apply(links.wrap(reference)))
+}
+
+#[openapi(tag = "Ride")]
+#[put("/ride/<ride_id>/reference/<reference_id>", data = "<reference>")]
+pub async fn put(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    ride_id: u32,
+    reference_id: u32,
+    reference: JsonBody<RideReference>,
+) -> Result<NoContent, ApiError> {
+    // First, make sure that resource belongs to the user and the reference to the ride
+    ride::is_owner(ride_id, auth.user_id, false, txn.as_ref()).await?;
+    RideReference::find_by_id(ride_id, reference_id, false, txn.as_ref()).await?;
+
+    let reference = ride_reference::CreateUpdateBuilder::from_json(reference.into_inner())
+        .update(reference_id, &auth.actor(), txn.as_ref())
+        .await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::RideReference, Action::Updated, reference_id).with_data(&reference));
+    Ok(NoContent)
+}
+
+#[openapi(tag = "Ride")]
+#[delete("/ride/<ride_id>/reference/<reference_id>")]
+pub async fn delete(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    ride_id: u32,
+    reference_id: u32,
+) -> Result<NoContent, ApiError> {
+    // First, make sure that resource belongs to the user and the reference to the ride
+    ride::is_owner(ride_id, auth.user_id, false, txn.as_ref()).await?;
+    RideReference::find_by_id(ride_id, reference_id, false, txn.as_ref()).await?;
+
+    ride_reference::remove(reference_id, &auth.actor(), txn.as_ref()).await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::RideReference, Action::Deleted, reference_id));
+    Ok(NoContent)
+}
@@ -11,19 +11,54 @@ use rocket::{
     response::status::NoContent,
     serde::json::Json,
 };
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use rocket_okapi::okapi::schemars;
 use rocket_okapi::openapi;
 use super::ApiError;
-use crate::fairings::Database;
-use crate::request_guards::{Auth, ReadOnly, ReadWrite};
+use crate::fairings::{Database, TagCache};
+use crate::request_guards::{Auth, IdempotencyKey, Include, IncludeDeleted, JsonBody, LinkProfile, ReadOnly, ReadWrite, RequestTransaction};
+use crate::request_guards::include::{INCLUDE_OPTIONS, INCLUDE_TAG};
+use crate::responders::{Created, Idempotent, Linked};
 use crate::model::{ride, ride_tag_link, ride_tag_link::RideTagLink, tag};
+use crate::model::event::{Action, Event, EventBus, Resource};
 
 
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
 pub struct RideTagGetReturn {
-    link: RideTagLink,
-    tag: tag::Tag,
+    link: Linked<RideTagLink>,
+    /// Tag descriptor, only if embedded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<Linked<tag::Tag>>,
+}
+
+/// Fetch the tag descriptor of [link] from [tag_cache] if it is to be embedded. The
+/// cache only holds tags which are not deleted, so these are fetched from the database
+/// if [deleted] is set.
+async fn find_tag(
+    link: &RideTagLink,
+    user_id: u32,
+    links: &LinkProfile,
+    include: &Include,
+    deleted: &IncludeDeleted,
+    db: &Database,
+    tag_cache: &TagCache,
+) -> Result<Option<Linked<tag::Tag>>, ApiError> {
+    if include.has(INCLUDE_TAG) {
+        let with_options = include.has(INCLUDE_OPTIONS);
+        let tag = if deleted.is_set() {
+            tag::Tag::find_by_id(link.tag_id(), with_options, true, db.read_conn.as_ref()).await?
+        } else {
+            let tag = tag_cache.get(user_id, link.tag_id(), db.conn.as_ref()).await?;
+            if with_options {
+                tag
+            } else {
+                tag.without_options()
+            }
+        };
+        Ok(Some(links.wrap(tag)))
+    } else {
+        Ok(None)
+    }
 }
 
 #[openapi(tag = "Ride")]
@@ -31,18 +66,22 @@ pub struct RideTagGetReturn {
 pub async fn list(
     auth: Auth<ReadOnly>,
     db: &State<Database>,
+    tag_cache: &State<TagCache>,
+    links: LinkProfile,
+    include: Include,
+    deleted: IncludeDeleted,
     ride_id: u32,
 ) -> Result<Json<Vec<RideTagGetReturn>>, ApiError> {
     // First, make sure that resource belongs to the user
-    ride::is_owner(ride_id, auth.user_id, db.conn.as_ref()).await?;
+    ride::is_owner(ride_id, auth.user_id, deleted.is_set(), db.read_conn.as_ref()).await?;
 
-    let links = RideTagLink::find_all(ride_id, db.conn.as_ref()).await?;
-    let mut result = Vec::with_capacity(links.len());
-    for link in links {
-        let tag = tag::Tag::find_by_id(link.tag_id(), db.conn.as_ref()).await?;
+    let ride_tags = RideTagLink::find_all(ride_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+    let mut result = Vec::with_capacity(ride_tags.len());
+    for link in ride_tags {
+        let tag = find_tag(&link, auth.user_id, &links, &include, &deleted, db, tag_cache).await?;
         result.push(
             RideTagGetReturn {
-                link,
+                link: links.wrap(link),
                 tag,
             }
         );
@@ -55,17 +94,21 @@ pub async fn list(
 pub async fn get_by_tag_id(
     auth: Auth<ReadOnly>,
     db: &State<Database>,
+    tag_cache: &State<TagCache>,
+    links: LinkProfile,
+    include: Include,
+    deleted: IncludeDeleted,
     ride_id: u32,
     tag_id: u32,
 ) -> Result<Json<RideTagGetReturn>, ApiError> {
     // First, make sure that resource belongs to the user
-    ride::is_owner(ride_id, auth.user_id, db.conn.as_ref()).await?;
-    tag::is_owner(tag_id, auth.user_id, db.conn.as_ref()).await?;
+    ride::is_owner(ride_id, auth.user_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+    tag::is_owner(tag_id, auth.user_id, deleted.is_set(), db.read_conn.as_ref()).await?;
 
-    let link = RideTagLink::find_by_tag_id(ride_id, tag_id, db.conn.as_ref()).await?;
-    let tag = tag::Tag::find_by_id(link.tag_id(), db.conn.as_ref()).await?;
+    let link = RideTagLink::find_by_tag_id(ride_id, tag_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+    let tag = find_tag(&link, auth.user_id, &links, &include, &deleted, db, tag_cache).await?;
     let result = RideTagGetReturn {
-        link,
+        link: links.wrap(link),
         tag,
     };
     Ok(Json(result))
@@ -75,25 +118,35 @@ pub async fn get_by_tag_id(
 #[post("/ride/<ride_id>/ride_tags/<tag_id>", data = "<link>")]
 pub async fn post_by_tag_id(
     auth: Auth<ReadWrite>,
-    db: &State<Database>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
     ride_id: u32,
     tag_id: u32,
-    link: Json<RideTagLink>,
-) -> Result<Json<RideTagLink>, ApiError> {
-    // First, make sure that resource belongs to the user
-    ride::is_owner(ride_id, auth.user_id, db.conn.as_ref()).await?;
-    tag::is_owner(tag_id, auth.user_id, db.conn.as_ref()).await?;
-
-    // Prevent double use of tag ID
-    match RideTagLink::find_by_tag_id(ride_id, tag_id, db.conn.as_ref()).await {
-        Ok(_) => Err(ApiError::new_bad_request())?,
-        Err(_) => (),
-    };
-
-    let result = ride_tag_link::CreateUpdateBuilder::from_json(link.into_inner())
-        .insert(ride_id, tag_id, db.conn.as_ref())
-        .await?;
-    Ok(Json(result))
+    idempotency_key: IdempotencyKey,
+    link: JsonBody<RideTagLink>,
+) -> Result<Created<Idempotent<RideTagLink>>, ApiError> {
+    // First, make sure that resource belongs to the user. The tag is only found in the
+    // cache if it belongs to the user.
+    ride::is_owner(ride_id, auth.user_id, false, txn.as_ref()).await?;
+    let tag = tag_cache.get(auth.user_id, tag_id, txn.as_ref()).await?;
+
+    let link = link.into_inner();
+    let response = idempotency_key.run(auth.user_id, &link, txn.as_ref(), || async {
+        // Prevent double use of tag ID
+        if RideTagLink::find_by_tag_id(ride_id, tag_id, false, txn.as_ref()).await.is_ok() {
+            return Err(ApiError::new_bad_request());
+        }
+
+        let builder = ride_tag_link::CreateUpdateBuilder::from_json(link.clone());
+        builder.validate(&tag)?;
+        let link = builder
+            .insert(ride_id, tag_id, &auth.actor(), txn.as_ref())
+            .await?;
+        txn.publish(events, Event::new(auth.user_id, Resource::RideTag, Action::Created, link.id()).with_data(&link));
+        Ok(link)
+    }).await?;
+    Ok(Created::new(format!("/ride_tag/{}", response.resource_id()?), response))
 }
 
 #[openapi(tag = "Ride")]
@@ -101,15 +154,19 @@ pub async fn post_by_tag_id(
 pub async fn get_by_link_id(
     auth: Auth<ReadOnly>,
     db: &State<Database>,
+    tag_cache: &State<TagCache>,
+    links: LinkProfile,
+    include: Include,
+    deleted: IncludeDeleted,
     link_id: u32,
 ) -> Result<Json<RideTagGetReturn>, ApiError> {
     // First, make sure that resource belongs to the user
-    ride_tag_link::is_owner(link_id, auth.user_id, db.conn.as_ref()).await?;
+    ride_tag_link::is_owner(link_id, auth.user_id, deleted.is_set(), db.read_conn.as_ref()).await?;
 
-    let link = RideTagLink::find_by_id(link_id, db.conn.as_ref()).await?;
-    let tag = tag::Tag::find_by_id(link.tag_id(), db.conn.as_ref()).await?;
+    let link = RideTagLink::find_by_id(link_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+    let tag = find_tag(&link, auth.user_id, &links, &include, &deleted, db, tag_cache).await?;
     let result = RideTagGetReturn {
-        link,
+        link: links.wrap(link),
         tag,
     };
     Ok(Json(result))
@@ -119,29 +176,48 @@ pub async fn get_by_link_id(
 #[put("/ride_tag/<link_id>", data = "<link>")]
 pub async fn put(
     auth: Auth<ReadWrite>,
-    db: &State<Database>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
     link_id: u32,
-    link: Json<RideTagLink>,
+    link: JsonBody<RideTagLink>,
 ) -> Result<NoContent, ApiError> {
     // First, make sure that resource belongs to the user
-    ride_tag_link::is_owner(link_id, auth.user_id, db.conn.as_ref()).await?;
-
-    ride_tag_link::CreateUpdateBuilder::from_json(link.into_inner())
-        .update(link_id, db.conn.as_ref())
+    ride_tag_link::is_owner(link_id, auth.user_id, false, txn.as_ref()).await?;
+
+    let tag_id = RideTagLink::find_by_id(link_id, false, txn.as_ref()).await?.tag_id();
+    let tag = tag_cache.get(auth.user_id, tag_id, txn.as_ref()).await?;
+    let builder = ride_tag_link::CreateUpdateBuilder::from_json(link.into_inner());
+    builder.validate(&tag)?;
+    builder
+        .update(link_id, &auth.actor(), txn.as_ref())
         .await?;
+    let link = RideTagLink::find_by_id(link_id, false, txn.as_ref()).await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::RideTag, Action::Updated, link_id).with_data(&link));
     Ok(NoContent)
 }
 
+/// Soft-delete the ride tag. With `permanent=true`, the ride tag is deleted from the
+/// database instead, also if already soft-deleted.
 #[openapi(tag = "Ride")]
-#[delete("/ride_tag/<link_id>")]
+#[delete("/ride_tag/<link_id>?<permanent>")]
 pub async fn delete(
     auth: Auth<ReadWrite>,
-    db: &State<Database>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
     link_id: u32,
+    permanent: Option<bool>,
 ) -> Result<NoContent, ApiError> {
-    // First, make sure that resource belongs to the user
-    ride_tag_link::is_owner(link_id, auth.user_id, db.conn.as_ref()).await?;
-
-    ride_tag_link::remove(link_id, db.conn.as_ref()).await?;
+    // First, make sure that resource belongs to the user. Soft-deleted ones can still be
+    // deleted permanently.
+    let permanent = permanent.unwrap_or(false);
+    ride_tag_link::is_owner(link_id, auth.user_id, permanent, txn.as_ref()).await?;
+
+    if permanent {
+        ride_tag_link::purge(link_id, &auth.actor(), txn.as_ref()).await?;
+    } else {
+        ride_tag_link::remove(link_id, &auth.actor(), txn.as_ref()).await?;
+    }
+    txn.publish(events, Event::new(auth.user_id, Resource::RideTag, Action::Deleted, link_id));
     Ok(NoContent)
 }
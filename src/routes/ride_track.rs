@@ -0,0 +1,96 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    http::ContentType,
+    response::status::NoContent,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, GpxBody, LinkProfile, ReadOnly, ReadWrite, RequestTransaction};
+use crate::responders::{Attachment, Linked};
+use crate::model::{ride, ride_track, ride_track::{RideTrack, RideTracks}};
+use crate::model::event::{Action, Event, EventBus, Resource};
+
+/// Upload the GPX track of the ride, replacing an earlier one. The travelled distance is
+/// filled into the distance tag of the ride, unless the ride has a distance already and
+/// `overwrite_distance` is not set.
+#[openapi(tag = "Ride")]
+#[put("/ride/<ride_id>/track?<overwrite_distance>", data = "<gpx>")]
+pub async fn put(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    tracks: &State<RideTracks>,
+    events: &State<EventBus>,
+    links: LinkProfile,
+    ride_id: u32,
+    overwrite_distance: Option<bool>,
+    gpx: GpxBody,
+) -> Result<Linked<RideTrack>, ApiError> {
+    // First, make sure that resource belongs to the user
+    ride::is_owner(ride_id, auth.user_id, false, txn.as_ref()).await?;
+
+    let track = tracks
+        .attach(ride_id, auth.user_id, gpx.into_inner(), overwrite_distance.unwrap_or(false), &auth.actor(), txn.as_ref())
+        .await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::RideTrack, Action::Updated, track.id()).with_data(&track));
+    Ok(links.wrap(track))
+}
+
+/// Distance and duration of the GPX track of the ride
+#[openapi(tag = "Ride")]
+#[get("/ride/<ride_id>/track")]
+pub async fn get(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    links: LinkProfile,
+    ride_id: u32,
+) -> Result<Linked<RideTrack>, ApiError> {
+    // First, make sure that resource belongs to the user
+    ride::is_owner(ride_id, auth.user_id, false, db.read_conn.as_ref()).await?;
+
+    let track = RideTrack::find_by_ride_id(ride_id, db.read_conn.as_ref()).await?;
+    Ok(links.wrap(track))
+}
+
+/// GPX track of the ride as uploaded. Fails with 409 while the track is quarantined by
+/// the malware scan.
+#[openapi(tag = "Ride")]
+#[get("/ride/<ride_id>/track/gpx")]
+pub async fn get_gpx(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    ride_id: u32,
+) -> Result<Attachment, ApiError> {
+    // First, make sure that resource belongs to the user
+    ride::is_owner(ride_id, auth.user_id, false, db.read_conn.as_ref()).await?;
+
+    let gpx = ride_track::gpx(ride_id, db.read_conn.as_ref()).await?;
+    let content_type = ContentType::new("application", "gpx+xml");
+    Ok(Attachment::new(content_type, format!("ride-{}.gpx", ride_id), gpx.into_bytes()))
+}
+
+/// Remove the GPX track of the ride. The distance filled in from it is kept.
+#[openapi(tag = "Ride")]
+#[delete("/ride/<ride_id>/track")]
+pub async fn delete(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    ride_id: u32,
+) -> Result<NoContent, ApiError> {
+    // First, make sure that resource belongs to the user
+    ride::is_owner(ride_id, auth.user_id, false, txn.as_ref()).await?;
+
+    let track = RideTrack::find_by_ride_id(ride_id, txn.as_ref()).await?;
+    ride_track::remove(ride_id, &auth.actor(), txn.as_ref()).await?;
+    txn.publish(events, Event::new(auth.user_id, Resource::RideTrack, Action::Deleted, track.id()));
+    Ok(NoContent)
+}
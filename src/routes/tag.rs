@@ -13,31 +13,93 @@ use rocket::{
 };
 use rocket_okapi::openapi;
 use super::ApiError;
-use crate::fairings::Database;
-use crate::request_guards::{Auth, ReadOnly, ReadWrite};
-use crate::model::{tag, tag::Tag};
+use crate::fairings::{Database, TagCache};
+use crate::request_guards::{Auth, FieldSet, IdempotencyKey, IdFilter, IfModifiedSince, Include, IncludeDeleted, JsonBody, LinkProfile, ReadOnly, ReadWrite, RequestedLocale, RequestTransaction};
+use crate::request_guards::include::INCLUDE_OPTIONS;
+use crate::responders::{Conditional, Count, Created, Idempotent, Linked, Negotiated, Sparse};
+use crate::model::{tag, tag::{PriceTag, Tag, TagSort}};
+use crate::model::tag_suggestion::{self, ValueSuggestion};
+use crate::model::event::{Action, Event, EventBus, Resource};
 
+/// List the tags. `sort` orders them by `key`, `name`, `created_at` or `order`, prefixed
+/// with `-` for descending order, e.g. `sort=-created_at`. The options of enum tags are
+/// sorted by their `order`.
 #[openapi(tag = "Tag")]
-#[get("/tag")]
+#[get("/tag?<sort>")]
 pub async fn list(
     auth: Auth<ReadOnly>,
     db: &State<Database>,
-) -> Result<Json<Vec<Tag>>, ApiError> {
-    let tags = Tag::find_all(auth.user_id, db.conn.as_ref()).await?;
-    Ok(Json(tags))
+    links: LinkProfile,
+    fields: FieldSet,
+    include: Include,
+    if_modified_since: IfModifiedSince,
+    ids: IdFilter,
+    deleted: IncludeDeleted,
+    locale: RequestedLocale,
+    sort: Option<&str>,
+) -> Result<Conditional<Negotiated<Sparse<Vec<Linked<Tag>>>>>, ApiError> {
+    let sort = match sort {
+        Some(sort) => TagSort::parse(sort).ok_or_else(|| {
+            ApiError::new_bad_request()
+                .with_description(format!("Cannot sort by '{}', use key, name, created_at or order", sort))
+        })?,
+        None => TagSort::default(),
+    };
+    let last_modified = tag::last_modified(auth.user_id, None, db.read_conn.as_ref()).await?;
+    if if_modified_since.is_fresh(last_modified) {
+        return Ok(Conditional::not_modified(last_modified));
+    }
+    let locale = locale.resolve(auth.user_id, db.read_conn.as_ref()).await?;
+
+    let tags = match ids.ids() {
+        Some(ids) => Tag::find_by_ids(auth.user_id, ids, sort, include.has(INCLUDE_OPTIONS), deleted.is_set(), db.read_conn.as_ref()).await?,
+        None => Tag::find_all_sorted(auth.user_id, sort, include.has(INCLUDE_OPTIONS), deleted.is_set(), db.read_conn.as_ref()).await?,
+    };
+    Ok(Conditional::modified(last_modified, Negotiated::new(fields.apply(links.wrap_all(tags)), "tag").with_locale(locale)))
+}
+
+/// Number of tags, without fetching them
+#[openapi(tag = "Tag")]
+#[get("/tag/count")]
+pub async fn count(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+) -> Result<Count, ApiError> {
+    Ok(Count::new(Tag::count_all(auth.user_id, false, db.read_conn.as_ref()).await?))
+}
+
+/// Only the `X-Total-Items` header of the list of tags
+#[openapi(tag = "Tag")]
+#[head("/tag")]
+pub async fn head(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+) -> Result<Count, ApiError> {
+    Ok(Count::new(Tag::count_all(auth.user_id, false, db.read_conn.as_ref()).await?))
 }
 
 #[openapi(tag = "Tag")]
 #[post("/tag", data = "<tag>")]
 pub async fn post(
     auth: Auth<ReadWrite>,
-    db: &State<Database>,
-    tag: Json<Tag>,
-) -> Result<Json<Tag>, ApiError> {
-    let result = tag::CreateUpdateBuilder::from_json(tag.into_inner())
-        .insert(auth.user_id, db.conn.as_ref())
-        .await?;
-    Ok(Json(result))
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
+    price_tag: &State<PriceTag>,
+    idempotency_key: IdempotencyKey,
+    tag: JsonBody<Tag>,
+) -> Result<Created<Idempotent<Tag>>, ApiError> {
+    let tag = tag.into_inner();
+    let response = idempotency_key.run(auth.user_id, &tag, txn.as_ref(), || async {
+        let tag = tag::CreateUpdateBuilder::from_json(tag.clone())
+            .with_price_tag(price_tag)
+            .insert(auth.user_id, &auth.actor(), txn.as_ref())
+            .await?;
+        txn.invalidate_tags(tag_cache, auth.user_id);
+        txn.publish(events, Event::new(auth.user_id, Resource::Tag, Action::Created, tag.id()).with_data(&tag));
+        Ok(tag)
+    }).await?;
+    Ok(Created::new(format!("/tag/{}", response.resource_id()?), response))
 }
 
 #[openapi(tag = "Tag")]
@@ -45,42 +107,124 @@ pub async fn post(
 pub async fn get(
     auth: Auth<ReadOnly>,
     db: &State<Database>,
+    links: LinkProfile,
+    fields: FieldSet,
+    include: Include,
+    if_modified_since: IfModifiedSince,
+    deleted: IncludeDeleted,
     tag_id: u32,
-) -> Result<Json<Tag>, ApiError> {
+) -> Result<Conditional<Sparse<Linked<Tag>>>, ApiError> {
     // First, make sure that tag belongs to the user
-    tag::is_owner(tag_id, auth.user_id, db.conn.as_ref()).await?;
+    tag::is_owner(tag_id, auth.user_id, deleted.is_set(), db.read_conn.as_ref()).await?;
 
-    let tag = Tag::find_by_id(tag_id, db.conn.as_ref()).await?;
-    Ok(Json(tag))
+    let last_modified = tag::last_modified(auth.user_id, Some(tag_id), db.read_conn.as_ref()).await?;
+    if if_modified_since.is_fresh(last_modified) {
+        return Ok(Conditional::not_modified(last_modified));
+    }
+
+    let tag = Tag::find_by_id(tag_id, include.has(INCLUDE_OPTIONS), deleted.is_set(), db.read_conn.as_ref()).await?;
+    Ok(Conditional::modified(last_modified, fields.apply(links.wrap(tag))))
+}
+
+/// Values the tag had most often on the rides of the last `days` days, 365 by default,
+/// for autocompletion. Returns up to `limit` distinct values, 10 by default and at most
+/// 100, with the number of rides and the latest departure. Only string, integer and
+/// money tags have suggestions.
+#[openapi(tag = "Tag")]
+#[get("/tag/<tag_id>/suggestions?<limit>&<days>")]
+pub async fn suggestions(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    tag_id: u32,
+    limit: Option<u64>,
+    days: Option<u32>,
+) -> Result<Json<Vec<ValueSuggestion>>, ApiError> {
+    // First, make sure that tag belongs to the user
+    tag::is_owner(tag_id, auth.user_id, false, db.read_conn.as_ref()).await?;
+
+    let limit = limit.unwrap_or(tag_suggestion::DEFAULT_LIMIT);
+    if limit == 0 || limit > tag_suggestion::MAX_LIMIT {
+        Err(ApiError::new_bad_request().with_description(format!("The limit must be between 1 and {}", tag_suggestion::MAX_LIMIT)))?;
+    }
+    let tag = Tag::find_by_id(tag_id, false, false, db.read_conn.as_ref()).await?;
+    let suggestions = tag_suggestion::suggestions(
+        &tag,
+        limit,
+        days.unwrap_or(tag_suggestion::DEFAULT_DAYS),
+        chrono::Utc::now(),
+        db.read_conn.as_ref(),
+    ).await?;
+    Ok(Json(suggestions))
 }
 
 #[openapi(tag = "Tag")]
 #[put("/tag/<tag_id>", data = "<tag>")]
 pub async fn put(
     auth: Auth<ReadWrite>,
-    db: &State<Database>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
+    price_tag: &State<PriceTag>,
     tag_id: u32,
-    tag: Json<Tag>,
+    tag: JsonBody<Tag>,
 ) -> Result<NoContent, ApiError> {
     // First, make sure that tag belongs to the user
-    tag::is_owner(tag_id, auth.user_id, db.conn.as_ref()).await?;
+    tag::is_owner(tag_id, auth.user_id, false, txn.as_ref()).await?;
 
     tag::CreateUpdateBuilder::from_json(tag.into_inner())
-        .update(tag_id, db.conn.as_ref())
+        .with_price_tag(price_tag)
+        .update(tag_id, &auth.actor(), txn.as_ref())
         .await?;
+    let tag = Tag::find_by_id(tag_id, true, false, txn.as_ref()).await?;
+    txn.invalidate_tags(tag_cache, auth.user_id);
+    txn.publish(events, Event::new(auth.user_id, Resource::Tag, Action::Updated, tag_id).with_data(&tag));
     Ok(NoContent)
 }
 
+/// Restore the soft-deleted tag together with its options and ride tags. Fails with 409
+/// if the tag is not deleted or another tag has its key meanwhile.
+#[openapi(tag = "Tag")]
+#[post("/tag/<tag_id>/restore")]
+pub async fn restore(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
+    tag_id: u32,
+) -> Result<Json<Tag>, ApiError> {
+    // First, make sure that tag belongs to the user, deleted or not
+    tag::is_owner(tag_id, auth.user_id, true, txn.as_ref()).await?;
+
+    tag::restore(tag_id, &auth.actor(), txn.as_ref()).await?;
+    let tag = Tag::find_by_id(tag_id, true, false, txn.as_ref()).await?;
+    txn.invalidate_tags(tag_cache, auth.user_id);
+    txn.publish(events, Event::new(auth.user_id, Resource::Tag, Action::Updated, tag_id).with_data(&tag));
+    Ok(Json(tag))
+}
+
+/// Soft-delete the tag. With `permanent=true`, the tag, its options and its ride tags
+/// are deleted from the database instead, also if already soft-deleted.
 #[openapi(tag = "Tag")]
-#[delete("/tag/<tag_id>")]
+#[delete("/tag/<tag_id>?<permanent>")]
 pub async fn delete(
     auth: Auth<ReadWrite>,
-    db: &State<Database>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
     tag_id: u32,
+    permanent: Option<bool>,
 ) -> Result<NoContent, ApiError> {
-    // First, make sure that tag belongs to the user
-    tag::is_owner(tag_id, auth.user_id, db.conn.as_ref()).await?;
+    // First, make sure that tag belongs to the user. Soft-deleted ones can still be
+    // deleted permanently.
+    let permanent = permanent.unwrap_or(false);
+    tag::is_owner(tag_id, auth.user_id, permanent, txn.as_ref()).await?;
 
-    tag::remove(tag_id, db.conn.as_ref()).await?;
+    if permanent {
+        tag::purge(tag_id, &auth.actor(), txn.as_ref()).await?;
+    } else {
+        tag::remove(tag_id, &auth.actor(), txn.as_ref()).await?;
+    }
+    txn.invalidate_tags(tag_cache, auth.user_id);
+    txn.publish(events, Event::new(auth.user_id, Resource::Tag, Action::Deleted, tag_id));
     Ok(NoContent)
 }
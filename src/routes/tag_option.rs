@@ -13,39 +13,113 @@ use rocket::{
 };
 use rocket_okapi::openapi;
 use super::ApiError;
-use crate::fairings::Database;
-use crate::request_guards::{Auth, ReadOnly, ReadWrite};
+use crate::fairings::{Database, TagCache};
+use crate::request_guards::{Auth, FieldSet, IdempotencyKey, IfModifiedSince, IncludeDeleted, JsonBody, LinkProfile, OptionList, ReadOnly, ReadWrite, RequestTransaction};
+use crate::responders::{Conditional, Created, Idempotent, Linked, PaginatedResult, Sparse};
 use crate::model::{tag, tag_option, tag_option::TagOption};
+use crate::model::event::{Action, Event, EventBus, Resource};
 
+/// List the options of the tag, sorted by their `order`. With `page` and `size`, only a
+/// page of the options is returned.
 #[openapi(tag = "Tag")]
-#[get("/tag/<tag_id>/tag_option")]
+#[get("/tag/<tag_id>/tag_option?<page>&<size>")]
 pub async fn list(
     auth: Auth<ReadOnly>,
     db: &State<Database>,
+    links: LinkProfile,
+    fields: FieldSet,
+    if_modified_since: IfModifiedSince,
+    deleted: IncludeDeleted,
     tag_id: u32,
-) -> Result<Json<Vec<TagOption>>, ApiError> {
+    page: Option<u64>,
+    size: Option<u64>,
+) -> Result<Conditional<PaginatedResult<Sparse<Vec<Linked<TagOption>>>>>, ApiError> {
     // First, make sure that tag belongs to the user
-    tag::is_owner(tag_id, auth.user_id, db.conn.as_ref()).await?;
+    tag::is_owner(tag_id, auth.user_id, deleted.is_set(), db.read_conn.as_ref()).await?;
 
-    let tags = TagOption::find_all(tag_id, db.conn.as_ref()).await?;
-    Ok(Json(tags))
+    let last_modified = tag_option::last_modified(tag_id, None, db.read_conn.as_ref()).await?;
+    if if_modified_since.is_fresh(last_modified) {
+        return Ok(Conditional::not_modified(last_modified));
+    }
+
+    if let Some(page) = page {
+        let Some(size) = size else {
+            Err(
+                ApiError::new_bad_request()
+                    .with_description("Pagination requested and size is not defined")
+            )?
+        };
+        if size == 0 {
+            Err(
+                ApiError::new_bad_request()
+                    .with_description("Page size must be greater than zero.")
+            )?;
+        }
+        let (options, count) = TagOption::find_all_paginated(tag_id, deleted.is_set(), db.read_conn.as_ref(), page, size).await?;
+        Ok(Conditional::modified(
+            last_modified,
+            PaginatedResult::new_paginated(fields.apply(links.wrap_all(options)), count, page, size),
+        ))
+    } else {
+        let options = TagOption::find_all(tag_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+        // All options are fetched, so they need not be counted separately
+        let count = options.len() as u64;
+        Ok(Conditional::modified(
+            last_modified,
+            PaginatedResult::new_complete(fields.apply(links.wrap_all(options)), Some(count)),
+        ))
+    }
 }
 
 #[openapi(tag = "Tag")]
 #[post("/tag/<tag_id>/tag_option", data = "<option>")]
 pub async fn post(
     auth: Auth<ReadWrite>,
-    db: &State<Database>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
     tag_id: u32,
-    option: Json<TagOption>,
-) -> Result<Json<TagOption>, ApiError> {
+    idempotency_key: IdempotencyKey,
+    option: JsonBody<TagOption>,
+) -> Result<Created<Idempotent<TagOption>>, ApiError> {
     // First, make sure that tag belongs to the user
-    tag::is_owner(tag_id, auth.user_id, db.conn.as_ref()).await?;
+    tag::is_owner(tag_id, auth.user_id, false, txn.as_ref()).await?;
 
-    let result = tag_option::CreateUpdateBuilder::from_json(option.into_inner())
-        .insert(tag_id, db.conn.as_ref())
-        .await?;
-    Ok(Json(result))
+    let option = option.into_inner();
+    let response = idempotency_key.run(auth.user_id, &option, txn.as_ref(), || async {
+        let option = tag_option::CreateUpdateBuilder::from_json(option.clone())
+            .insert(tag_id, &auth.actor(), txn.as_ref())
+            .await?;
+        txn.invalidate_tags(tag_cache, auth.user_id);
+        txn.publish(events, Event::new(auth.user_id, Resource::TagOption, Action::Created, option.id()).with_data(&option));
+        Ok(option)
+    }).await?;
+    Ok(Created::new(format!("/tag_option/{}", response.resource_id()?), response))
+}
+
+/// Create options of the tag from a list sent as `text/plain` or `text/csv`, one option
+/// per line, either `value` or `value;name`. The options are appended after the existing
+/// ones in the order of the list. Fails with 422 without creating any option if the list
+/// is empty or a value is repeated or exists already.
+#[openapi(tag = "Tag")]
+#[post("/tag/<tag_id>/tag_option/import", data = "<list>")]
+pub async fn import(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
+    tag_id: u32,
+    list: OptionList,
+) -> Result<Created<Json<Vec<TagOption>>>, ApiError> {
+    // First, make sure that tag belongs to the user
+    tag::is_owner(tag_id, auth.user_id, false, txn.as_ref()).await?;
+
+    let options = tag_option::import(tag_id, list.into_inner(), &auth.actor(), txn.as_ref()).await?;
+    txn.invalidate_tags(tag_cache, auth.user_id);
+    for option in &options {
+        txn.publish(events, Event::new(auth.user_id, Resource::TagOption, Action::Created, option.id()).with_data(option));
+    }
+    Ok(Created::new(format!("/tag/{}/tag_option", tag_id), Json(options)))
 }
 
 #[openapi(tag = "Tag")]
@@ -53,42 +127,89 @@ pub async fn post(
 pub async fn get(
     auth: Auth<ReadOnly>,
     db: &State<Database>,
+    links: LinkProfile,
+    fields: FieldSet,
+    if_modified_since: IfModifiedSince,
+    deleted: IncludeDeleted,
     option_id: u32,
-) -> Result<Json<TagOption>, ApiError> {
+) -> Result<Conditional<Sparse<Linked<TagOption>>>, ApiError> {
     // First, make sure that tag option belongs to the user
-    tag_option::is_owner(option_id, auth.user_id, db.conn.as_ref()).await?;
+    tag_option::is_owner(option_id, auth.user_id, deleted.is_set(), db.read_conn.as_ref()).await?;
 
-    let tag = TagOption::find_by_id(option_id, db.conn.as_ref()).await?;
-    Ok(Json(tag))
+    let tag = TagOption::find_by_id(option_id, deleted.is_set(), db.read_conn.as_ref()).await?;
+    let last_modified = tag_option::last_modified(tag.tag_id(), Some(option_id), db.read_conn.as_ref()).await?;
+    if if_modified_since.is_fresh(last_modified) {
+        return Ok(Conditional::not_modified(last_modified));
+    }
+    Ok(Conditional::modified(last_modified, fields.apply(links.wrap(tag))))
 }
 
 #[openapi(tag = "Tag")]
 #[put("/tag_option/<option_id>", data = "<option>")]
 pub async fn put(
     auth: Auth<ReadWrite>,
-    db: &State<Database>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
     option_id: u32,
-    option: Json<TagOption>,
+    option: JsonBody<TagOption>,
 ) -> Result<NoContent, ApiError> {
     // First, make sure that tag option belongs to the user
-    tag_option::is_owner(option_id, auth.user_id, db.conn.as_ref()).await?;
+    tag_option::is_owner(option_id, auth.user_id, false, txn.as_ref()).await?;
 
     tag_option::CreateUpdateBuilder::from_json(option.into_inner())
-        .update(option_id, db.conn.as_ref())
+        .update(option_id, &auth.actor(), txn.as_ref())
         .await?;
+    let option = TagOption::find_by_id(option_id, false, txn.as_ref()).await?;
+    txn.invalidate_tags(tag_cache, auth.user_id);
+    txn.publish(events, Event::new(auth.user_id, Resource::TagOption, Action::Updated, option_id).with_data(&option));
     Ok(NoContent)
 }
 
+/// Restore the soft-deleted option. Fails with 409 if the option is not deleted, its tag
+/// is deleted or another option of the tag has its value meanwhile.
 #[openapi(tag = "Tag")]
-#[delete("/tag_option/<option_id>")]
+#[post("/tag_option/<option_id>/restore")]
+pub async fn restore(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
+    option_id: u32,
+) -> Result<Json<TagOption>, ApiError> {
+    // First, make sure that tag option belongs to the user, deleted or not
+    tag_option::is_owner(option_id, auth.user_id, true, txn.as_ref()).await?;
+
+    tag_option::restore(option_id, &auth.actor(), txn.as_ref()).await?;
+    let option = TagOption::find_by_id(option_id, false, txn.as_ref()).await?;
+    txn.invalidate_tags(tag_cache, auth.user_id);
+    txn.publish(events, Event::new(auth.user_id, Resource::TagOption, Action::Updated, option_id).with_data(&option));
+    Ok(Json(option))
+}
+
+/// Soft-delete the option. With `permanent=true`, the option and the ride tags selecting
+/// it are deleted from the database instead, also if already soft-deleted.
+#[openapi(tag = "Tag")]
+#[delete("/tag_option/<option_id>?<permanent>")]
 pub async fn delete(
     auth: Auth<ReadWrite>,
-    db: &State<Database>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
     option_id: u32,
+    permanent: Option<bool>,
 ) -> Result<NoContent, ApiError> {
-    // First, make sure that tag option belongs to the user
-    tag_option::is_owner(option_id, auth.user_id, db.conn.as_ref()).await?;
+    // First, make sure that tag option belongs to the user. Soft-deleted ones can still be
+    // deleted permanently.
+    let permanent = permanent.unwrap_or(false);
+    tag_option::is_owner(option_id, auth.user_id, permanent, txn.as_ref()).await?;
 
-    tag_option::remove(option_id, db.conn.as_ref()).await?;
+    if permanent {
+        tag_option::purge(option_id, &auth.actor(), txn.as_ref()).await?;
+    } else {
+        tag_option::remove(option_id, &auth.actor(), txn.as_ref()).await?;
+    }
+    txn.invalidate_tags(tag_cache, auth.user_id);
+    txn.publish(events, Event::new(auth.user_id, Resource::TagOption, Action::Deleted, option_id));
     Ok(NoContent)
 }
@@ -0,0 +1,77 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    response::status::NoContent,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, JsonBody, ReadOnly, ReadWrite, RequestTransaction};
+use crate::request_guards::telegram_secret::TelegramSecret;
+use crate::model::{telegram, telegram::{Reply, Telegram, TelegramLink, Update}};
+use crate::model::quick_add::QuickAdd;
+use crate::model::event::{Action, Event, EventBus, Resource};
+
+/// Make sure that the bot is configured
+fn check_enabled(telegram: &Telegram) -> Result<(), ApiError> {
+    if !telegram.is_enabled() {
+        Err(ApiError::new_not_found().with_description("Telegram bot is not configured"))?;
+    }
+    Ok(())
+}
+
+/// Code to send to the bot as `/start <code>` to add rides from Telegram, created on
+/// first request
+#[openapi(tag = "Telegram")]
+#[get("/telegram/link")]
+pub async fn link(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    telegram: &State<Telegram>,
+) -> Result<Json<TelegramLink>, ApiError> {
+    check_enabled(telegram)?;
+    Ok(Json(telegram::link(auth.user_id, db.conn.as_ref()).await?))
+}
+
+/// Unlink the Telegram account. A new code is created on the next request of the link.
+#[openapi(tag = "Telegram")]
+#[delete("/telegram/link")]
+pub async fn unlink(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    telegram: &State<Telegram>,
+) -> Result<NoContent, ApiError> {
+    check_enabled(telegram)?;
+    telegram::unlink(auth.user_id, txn.as_ref()).await?;
+    Ok(NoContent)
+}
+
+/// Webhook of the Telegram bot. Messages of linked accounts are added like
+/// `POST /quick_add`. The answer is sent to the chat by Telegram, `null` for ignored
+/// updates.
+#[openapi(tag = "Telegram")]
+#[post("/telegram/webhook", data = "<update>")]
+pub async fn webhook(
+    secret: TelegramSecret,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    quick_add: &State<QuickAdd>,
+    update: JsonBody<Update>,
+) -> Result<Json<Option<Reply>>, ApiError> {
+    let handled = telegram::handle(update.into_inner(), quick_add, Some(secret.request_id), txn.as_ref()).await?;
+    let Some(handled) = handled else {
+        return Ok(Json(None));
+    };
+    if let Some((user_id, ride)) = handled.ride {
+        txn.publish(events, Event::new(user_id, Resource::Ride, Action::Created, ride.id()).with_data(&ride));
+    }
+    Ok(Json(Some(handled.reply)))
+}
@@ -6,14 +6,18 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use rocket::{State, serde::json::Json};
+use rocket::{State, http::ContentType, serde::json::Json};
 use rocket_okapi::openapi;
 use sea_orm::prelude::*;
-use sea_orm::{Set, IntoActiveModel};
-use entity::user::{Model as UserModel, Entity as UserEntity, Column as UserColumn, ActiveModel as UserActiveModel};
+use entity::user::{Model as UserModel, Entity as UserEntity, Column as UserColumn};
 use super::ApiError;
-use crate::fairings::Database;
-use crate::request_guards::{Auth, ReadOnly, ReadWrite};
+use crate::fairings::{Database, TagCache};
+use crate::model::archive::{Archive, ConflictMode, ImportReport};
+use crate::model::event::EventBus;
+use crate::model::user::{replace_profile, UserPatch};
+use crate::model::tag::PriceTag;
+use crate::request_guards::{ArchiveBody, Auth, JsonBody, ReadOnly, ReadWrite, RequestedLocale, RequestTransaction};
+use crate::responders::Attachment;
 
 async fn find_user_by_id(id: u32, db: &impl ConnectionTrait) -> Result<Option<UserModel>, ApiError> {
     Ok(
@@ -32,7 +36,7 @@ async fn find_user_by_id(id: u32, db: &impl ConnectionTrait) -> Result<Option<Us
 #[openapi(tag = "User")]
 #[get("/user")]
 pub async fn get(auth: Auth<ReadOnly>, db: &State<Database>) -> Result<Json<UserModel>, ApiError> {
-    match find_user_by_id(auth.user_id, db.conn.as_ref()).await? {
+    match find_user_by_id(auth.user_id, db.read_conn.as_ref()).await? {
         Some(user) => Ok(Json(user)),
         None => Err(
             ApiError::new_internal_server_error()
@@ -40,18 +44,66 @@ pub async fn get(auth: Auth<ReadOnly>, db: &State<Database>) -> Result<Json<User
     }
 }
 
+/// Replace the profile of the user. Fields which are not given are cleared, so prefer
+/// `PATCH /user` to change single fields.
 #[openapi(tag = "User")]
 #[put("/user", data = "<user>")]
-pub async fn put(auth: Auth<ReadWrite>, db: &State<Database>, user: Json<UserModel>) -> Result<Json<UserModel>, ApiError> {
-    let mut model = match find_user_by_id(auth.user_id, db.conn.as_ref()).await? {
-        Some(model) => model.into_active_model(),
-        None => Err(
-            ApiError::new_internal_server_error()
-        )?
+pub async fn put(auth: Auth<ReadWrite>, txn: RequestTransaction<'_>, user: JsonBody<UserModel>) -> Result<Json<UserModel>, ApiError> {
+    Ok(Json(replace_profile(auth.user_id, user.into_inner(), txn.as_ref()).await?))
+}
+
+/// Change single fields of the profile of the user, as JSON merge patch: fields which
+/// are not given are kept and fields set to null are cleared, e.g.
+/// `{"email": "alice@example.org", "avatar_url": null}`
+#[openapi(tag = "User")]
+#[patch("/user", data = "<patch>")]
+pub async fn patch(auth: Auth<ReadWrite>, txn: RequestTransaction<'_>, patch: JsonBody<UserPatch>) -> Result<Json<UserModel>, ApiError> {
+    Ok(Json(patch.into_inner().apply(auth.user_id, txn.as_ref()).await?))
+}
+
+/// ZIP archive of all rides and tags of the user, without soft-deleted ones. It contains
+/// `manifest.json`, `tags.json` with the options, `rides.json` with the ride tags and
+/// `rides.csv` with a column per tag. `rides.csv` is written in the requested locale or
+/// the locale of the user.
+#[openapi(tag = "User")]
+#[get("/user/export.zip")]
+pub async fn export(auth: Auth<ReadOnly>, db: &State<Database>, locale: RequestedLocale) -> Result<Attachment, ApiError> {
+    let locale = locale.resolve(auth.user_id, db.read_conn.as_ref()).await?;
+    let archive = Archive::load(auth.user_id, db.read_conn.as_ref()).await?;
+    let data = archive.to_zip(locale)?;
+    Ok(Attachment::new(ContentType::ZIP, archive.file_name(), data))
+}
+
+/// Import an archive of `GET /user/export.zip`, either the ZIP file or the archive as
+/// JSON. Tags are matched by UUID or tag key, options by UUID or value and rides by
+/// departure, locations and template flag. `conflict` selects how matches are handled:
+/// `skip` (default) keeps them, `overwrite` updates them and replaces the ride tags of
+/// rides, `duplicate` creates rides again. Tags and options are never duplicated, also
+/// not with `duplicate`, as tag keys are unique; matching ones are kept and counted as
+/// skipped. The import is applied completely or not at all.
+#[openapi(tag = "User")]
+#[post("/user/import?<conflict>", data = "<archive>")]
+pub async fn import(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    events: &State<EventBus>,
+    tag_cache: &State<TagCache>,
+    price_tag: &State<PriceTag>,
+    conflict: Option<String>,
+    archive: ArchiveBody,
+) -> Result<Json<ImportReport>, ApiError> {
+    let mode = match conflict {
+        Some(conflict) => conflict.parse::<ConflictMode>()?,
+        None => ConflictMode::Skip,
     };
-    model.name = Set(user.name.clone());
-    match model.update(db.conn.as_ref()).await {
-        Ok(model) => Ok(Json(model)),
-        Err(e) => Err(ApiError::from(e))
+
+    let (report, changes) = archive
+        .import(auth.user_id, mode, &auth.actor(), price_tag, txn.as_ref())
+        .await?;
+    txn.invalidate_tags(tag_cache, auth.user_id);
+    for change in changes {
+        txn.publish(events, change);
     }
+
+    Ok(Json(report))
 }
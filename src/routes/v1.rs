@@ -0,0 +1,153 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Route;
+use rocket::http::Method;
+use rocket_okapi::openapi_get_routes_spec;
+use rocket_okapi::okapi::openapi3::OpenApi;
+
+/// Endpoints of v1 which change in v2, as method and unmounted route URI. They are
+/// deprecated in v1 once a deprecation date is configured.
+pub const DEPRECATED: [(Method, &str); 4] = [
+    // Nested creation of ride tags
+    (Method::Post, "/ride"),
+    (Method::Put, "/ride/<ride_id>"),
+    // Money type for prices
+    (Method::Post, "/ride/<ride_id>/ride_tags/<tag_id>"),
+    (Method::Put, "/ride_tag/<link_id>"),
+];
+
+/// Routes and OpenAPI specification of v1
+pub fn routes() -> (Vec<Route>, OpenApi) {
+    let settings = super::openapi::settings();
+    openapi_get_routes_spec![
+        settings:
+        super::auth::refresh,
+        super::user::get,
+        super::user::put,
+        super::user::patch,
+        super::user::export,
+        super::user::import,
+        super::ride::list,
+        super::ride::count,
+        super::ride::head,
+        super::ride::post,
+        super::ride::suggest,
+        super::ride::suggestions,
+        super::commute::suggestions,
+        super::ride::duplicates,
+        super::ride::merge_duplicates,
+        super::ride::get,
+        super::ride::put,
+        super::ride::instantiate,
+        super::ride::post_status,
+        super::ride::get_status,
+        super::ride::delete,
+        super::ride_reference::list,
+        super::ride_reference::post,
+        super::ride_reference::get,
+        super::ride_reference::put,
+        super::ride_reference::delete,
+        super::ride_track::put,
+        super::ride_track::get,
+        super::ride_track::get_gpx,
+        super::ride_track::delete,
+        super::ride_tag::list,
+        super::ride_tag::get_by_tag_id,
+        super::ride_tag::post_by_tag_id,
+        super::ride_tag::get_by_link_id,
+        super::ride_tag::put,
+        super::ride_tag::delete,
+        super::tag::list,
+        super::tag::count,
+        super::tag::head,
+        super::tag::post,
+        super::tag::get,
+        super::tag::suggestions,
+        super::tag::put,
+        super::tag::restore,
+        super::tag::delete,
+        super::tag_option::list,
+        super::tag_option::post,
+        super::tag_option::import,
+        super::tag_option::get,
+        super::tag_option::put,
+        super::tag_option::restore,
+        super::tag_option::delete,
+        super::batch::post,
+        super::event::stream,
+        super::webhook::list,
+        super::webhook::post,
+        super::webhook::get,
+        super::webhook::put,
+        super::webhook::delete,
+        super::webhook::deliveries,
+        super::geocode::search,
+        super::fare::estimate,
+        super::fare::deviations,
+        super::report::mobility_budget,
+        super::report::vat,
+        super::report::summary,
+        super::report::travel_log,
+        super::receipt::scan,
+        super::inbox::address,
+        super::inbox::list,
+        super::inbox::get,
+        super::inbox::confirm,
+        super::inbox::delete,
+        super::inbox::receive,
+        super::caldav::get,
+        super::caldav::put,
+        super::caldav::delete,
+        super::caldav::sync,
+        super::webdav::get,
+        super::webdav::put,
+        super::webdav::delete,
+        super::webdav::test,
+        super::webdav::upload,
+        super::quick_add::post,
+        super::telegram::link,
+        super::telegram::unlink,
+        super::telegram::webhook,
+        super::admin::retention,
+        super::admin::audit_log,
+        super::admin::verify_audit_log,
+        super::admin::quarantine,
+        super::admin::release_quarantined,
+        super::admin::delete_quarantined,
+        super::admin::features,
+        super::admin::put_feature,
+        super::admin::maintenance,
+        super::admin::put_maintenance,
+    ]
+}
+
+/// Whether the route with [method] and unmounted [uri] is in [DEPRECATED]
+pub fn is_deprecated(method: Method, uri: &str) -> bool {
+    DEPRECATED.iter().any(|(deprecated_method, deprecated_uri)| *deprecated_method == method && *deprecated_uri == uri)
+}
+
+/// Mark the operations in [DEPRECATED] as deprecated in [spec]
+pub fn mark_deprecated(spec: &mut OpenApi) {
+    for (method, uri) in DEPRECATED {
+        // OpenAPI writes parameters as {name} instead of <name>
+        let path = uri.replace('<', "{").replace('>', "}");
+        if let Some(item) = spec.paths.get_mut(&path) {
+            let operation = match method {
+                Method::Get => &mut item.get,
+                Method::Post => &mut item.post,
+                Method::Put => &mut item.put,
+                Method::Delete => &mut item.delete,
+                _ => continue,
+            };
+            if let Some(operation) = operation {
+                operation.deprecated = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::Route;
+use rocket_okapi::okapi::openapi3::OpenApi;
+
+/// Version of the OpenAPI specification of v2
+const VERSION: &str = "2.0.0";
+
+/// Routes and OpenAPI specification of v2. v2 starts with the endpoints of v1. Endpoints
+/// in [DEPRECATED](super::v1::DEPRECATED) are replaced here as their successors are
+/// implemented, all others are shared with v1.
+pub fn routes() -> (Vec<Route>, OpenApi) {
+    let (routes, mut spec) = super::v1::routes();
+    spec.info.version = VERSION.to_string();
+    (routes, spec)
+}
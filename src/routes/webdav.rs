@@ -0,0 +1,93 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    response::status::NoContent,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, JsonBody, ReadOnly, ReadWrite, RequestTransaction};
+use crate::model::credentials::CredentialCipher;
+use crate::model::{webdav, webdav::{Upload, WebdavTarget}};
+
+/// Make sure that credentials can be stored
+fn check_enabled(cipher: &CredentialCipher) -> Result<(), ApiError> {
+    if !cipher.is_enabled() {
+        Err(ApiError::new_not_found().with_description("WebDAV export is not configured"))?;
+    }
+    Ok(())
+}
+
+/// WebDAV folder of the user, without the password
+#[openapi(tag = "WebDAV")]
+#[get("/webdav")]
+pub async fn get(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    cipher: &State<CredentialCipher>,
+) -> Result<Json<WebdavTarget>, ApiError> {
+    check_enabled(cipher)?;
+    Ok(Json(WebdavTarget::find(auth.user_id, db.read_conn.as_ref()).await?))
+}
+
+/// Set the folder archives are uploaded to. The password is stored encrypted. It may be
+/// omitted to keep the stored one.
+#[openapi(tag = "WebDAV")]
+#[put("/webdav", data = "<target>")]
+pub async fn put(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    cipher: &State<CredentialCipher>,
+    target: JsonBody<WebdavTarget>,
+) -> Result<Json<WebdavTarget>, ApiError> {
+    check_enabled(cipher)?;
+    Ok(Json(target.into_inner().save(auth.user_id, cipher, txn.as_ref()).await?))
+}
+
+/// Stop uploading. Files already uploaded are kept.
+#[openapi(tag = "WebDAV")]
+#[delete("/webdav")]
+pub async fn delete(
+    auth: Auth<ReadWrite>,
+    txn: RequestTransaction<'_>,
+    cipher: &State<CredentialCipher>,
+) -> Result<NoContent, ApiError> {
+    check_enabled(cipher)?;
+    webdav::remove(auth.user_id, txn.as_ref()).await?;
+    Ok(NoContent)
+}
+
+/// Check that the folder exists and the credentials are accepted. Fails with 502
+/// otherwise.
+#[openapi(tag = "WebDAV")]
+#[post("/webdav/test")]
+pub async fn test(
+    auth: Auth<ReadOnly>,
+    db: &State<Database>,
+    cipher: &State<CredentialCipher>,
+) -> Result<NoContent, ApiError> {
+    check_enabled(cipher)?;
+    webdav::test(auth.user_id, cipher, db.read_conn.as_ref()).await?;
+    Ok(NoContent)
+}
+
+/// Upload the archive of `GET /user/export.zip` to the folder now
+#[openapi(tag = "WebDAV")]
+#[post("/webdav/upload")]
+pub async fn upload(
+    auth: Auth<ReadWrite>,
+    db: &State<Database>,
+    cipher: &State<CredentialCipher>,
+) -> Result<Json<Upload>, ApiError> {
+    check_enabled(cipher)?;
+    // Not in a request transaction, the error of a failed upload is recorded as well
+    Ok(Json(webdav::upload(auth.user_id, cipher, db.conn.as_ref()).await?))
+}
@@ -0,0 +1,125 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::{
+    State,
+    response::status::NoContent,
+    serde::json::Json,
+};
+use rocket_okapi::openapi;
+use super::ApiError;
+use crate::fairings::Database;
+use crate::request_guards::{Auth, Enabled, JsonBody, ReadOnly, ReadWrite, RequestTransaction};
+use crate::request_guards::feature::Webhooks;
+use crate::responders::PaginatedResult;
+use crate::model::{webhook, webhook::{Delivery, Webhook, WebhookTargets}};
+
+/// Default page size of the delivery log
+const DEFAULT_DELIVERY_PAGE_SIZE: u64 = 50;
+
+#[openapi(tag = "Webhook")]
+#[get("/webhook")]
+pub async fn list(
+    auth: Auth<ReadOnly>,
+    _feature: Enabled<Webhooks>,
+    db: &State<Database>,
+) -> Result<Json<Vec<Webhook>>, ApiError> {
+    let webhooks = Webhook::find_all(auth.user_id, db.read_conn.as_ref()).await?;
+    Ok(Json(webhooks))
+}
+
+#[openapi(tag = "Webhook")]
+#[post("/webhook", data = "<webhook>")]
+pub async fn post(
+    auth: Auth<ReadWrite>,
+    _feature: Enabled<Webhooks>,
+    targets: &State<WebhookTargets>,
+    txn: RequestTransaction<'_>,
+    webhook: JsonBody<Webhook>,
+) -> Result<Json<Webhook>, ApiError> {
+    let result = webhook::CreateUpdateBuilder::from_json(webhook.into_inner())
+        .insert(auth.user_id, targets, txn.as_ref())
+        .await?;
+    Ok(Json(result))
+}
+
+#[openapi(tag = "Webhook")]
+#[get("/webhook/<webhook_id>")]
+pub async fn get(
+    auth: Auth<ReadOnly>,
+    _feature: Enabled<Webhooks>,
+    db: &State<Database>,
+    webhook_id: u32,
+) -> Result<Json<Webhook>, ApiError> {
+    // First, make sure that resource belongs to the user
+    webhook::is_owner(webhook_id, auth.user_id, db.read_conn.as_ref()).await?;
+
+    let webhook = Webhook::find_by_id(webhook_id, db.read_conn.as_ref()).await?;
+    Ok(Json(webhook))
+}
+
+#[openapi(tag = "Webhook")]
+#[put("/webhook/<webhook_id>", data = "<webhook>")]
+pub async fn put(
+    auth: Auth<ReadWrite>,
+    _feature: Enabled<Webhooks>,
+    targets: &State<WebhookTargets>,
+    txn: RequestTransaction<'_>,
+    webhook_id: u32,
+    webhook: JsonBody<Webhook>,
+) -> Result<NoContent, ApiError> {
+    // First, make sure that resource belongs to the user
+    webhook::is_owner(webhook_id, auth.user_id, txn.as_ref()).await?;
+
+    webhook::CreateUpdateBuilder::from_json(webhook.into_inner())
+        .update(webhook_id, targets, txn.as_ref())
+        .await?;
+    Ok(NoContent)
+}
+
+#[openapi(tag = "Webhook")]
+#[delete("/webhook/<webhook_id>")]
+pub async fn delete(
+    auth: Auth<ReadWrite>,
+    _feature: Enabled<Webhooks>,
+    txn: RequestTransaction<'_>,
+    webhook_id: u32,
+) -> Result<NoContent, ApiError> {
+    // First, make sure that resource belongs to the user
+    webhook::is_owner(webhook_id, auth.user_id, txn.as_ref()).await?;
+
+    webhook::remove(webhook_id, txn.as_ref()).await?;
+    Ok(NoContent)
+}
+
+/// Delivery log of a webhook, newest first
+#[openapi(tag = "Webhook")]
+#[get("/webhook/<webhook_id>/deliveries?<page>&<size>")]
+pub async fn deliveries(
+    auth: Auth<ReadOnly>,
+    _feature: Enabled<Webhooks>,
+    db: &State<Database>,
+    webhook_id: u32,
+    page: Option<u64>,
+    size: Option<u64>,
+) -> Result<PaginatedResult<Json<Vec<Delivery>>>, ApiError> {
+    // First, make sure that resource belongs to the user
+    webhook::is_owner(webhook_id, auth.user_id, db.read_conn.as_ref()).await?;
+
+    let page = page.unwrap_or(0);
+    let size = size.unwrap_or(DEFAULT_DELIVERY_PAGE_SIZE);
+    if size == 0 {
+        Err(
+            ApiError::new_bad_request()
+                .with_description("Page size must be greater than zero.")
+        )?;
+    }
+    let count = Delivery::count_all(webhook_id, db.read_conn.as_ref()).await?;
+    let deliveries = Delivery::find_all_paginated(webhook_id, db.read_conn.as_ref(), page, size).await?;
+    Ok(PaginatedResult::new_paginated(Json(deliveries), count, page, size))
+}
@@ -0,0 +1,141 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashMap;
+use rocket::http::{Header, Status};
+use sea_orm::{prelude::*, ActiveModelTrait, IntoActiveModel, QueryOrder, Set};
+use serde_json::json;
+use entity::audit_log;
+use crate::fairings::{AuthCache, Database};
+use crate::model::audit_chain::AuditChain;
+use crate::model::feature::{self, FeatureFlags};
+use crate::model::maintenance::Maintenance;
+use super::ride::create_ride;
+use super::{api, json_body, TestApp};
+
+/// Authorization header of an administrator
+fn admin(app: &TestApp) -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {}", app.token("admin", json!({"ptet:admin": true}))))
+}
+
+#[rocket::async_test]
+async fn test_maintenance_is_stored() {
+    let app = TestApp::new().await;
+    let response = app.client
+        .put(api("/admin/maintenance"))
+        .header(admin(&app))
+        .json(&json!({"enabled": true, "message": "Backup running"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = app.client
+        .post(api("/ride"))
+        .header(app.writer("alice"))
+        .json(&json!({
+            "journey_departure": "2026-10-01T08:00:00Z",
+            "location_from": "Berlin Hbf",
+            "location_to": "Potsdam Hbf",
+            "is_template": false,
+        }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+
+    // A restarted instance reads the mode from the database
+    let db = app.client.rocket().state::<Database>().expect("Database is managed");
+    let status = Maintenance::new(None).reload(db.conn.as_ref()).await.expect("Maintenance mode is loaded");
+    assert!(status.enabled);
+    assert_eq!(status.message.as_deref(), Some("Backup running"));
+
+    let response = app.client
+        .put(api("/admin/maintenance"))
+        .header(admin(&app))
+        .json(&json!({"enabled": false}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json_body(response).await["enabled"], false);
+    let status = Maintenance::new(None).reload(db.conn.as_ref()).await.expect("Maintenance mode is loaded");
+    assert!(!status.enabled);
+}
+
+#[rocket::async_test]
+async fn test_feature_is_stored() {
+    let app = TestApp::new().await;
+    let response = app.client
+        .put(api("/admin/features/events"))
+        .header(admin(&app))
+        .json(&json!({"enabled": false}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = app.client.get(api("/events")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    // A restarted instance reads the switched flag from the database
+    let db = app.client.rocket().state::<Database>().expect("Database is managed");
+    let flags = FeatureFlags::new(&HashMap::new()).expect("Feature flags are valid");
+    flags.reload(db.conn.as_ref()).await.expect("Feature flags are loaded");
+    assert!(!flags.is_enabled(feature::EVENTS));
+    assert!(flags.is_enabled(feature::WEBHOOKS));
+}
+
+#[rocket::async_test]
+async fn test_audit_chain_appends_late_entries() {
+    let app = TestApp::new().await;
+    let db = app.client.rocket().state::<Database>().expect("Database is managed");
+    let auth_cache = app.client.rocket().state::<AuthCache>().expect("Key cache is managed");
+    let chain = AuditChain::new(auth_cache.key_cache.clone(), auth_cache.expect_jwt_audience.clone());
+    for day in 1..=3 {
+        create_ride(&app, "alice", day).await;
+    }
+
+    // The first entry commits only after the later ones were chained, e.g. of a long
+    // running request
+    let entries = audit_log::Entity::find()
+        .order_by_asc(audit_log::Column::Id)
+        .all(db.conn.as_ref())
+        .await
+        .expect("Audit log is read");
+    assert!(entries.len() >= 3);
+    let late = entries[0].clone();
+    audit_log::Entity::delete_by_id(late.id).exec(db.conn.as_ref()).await.expect("Entry is deleted");
+    assert_eq!(chain.extend(db.conn.as_ref()).await.expect("Chain is extended"), entries.len() as u64 - 1);
+    late.clone().into_active_model().reset_all().insert(db.conn.as_ref()).await.expect("Entry is inserted");
+
+    let response = app.client.get(api("/admin/audit_log/verify")).header(admin(&app)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let report = json_body(response).await;
+    assert_eq!(report["valid"], true);
+    assert_eq!(report["pending"], 1);
+
+    // The late entry is appended to the chain instead of breaking it
+    assert_eq!(chain.extend(db.conn.as_ref()).await.expect("Chain is extended"), 1);
+    assert_eq!(chain.extend(db.conn.as_ref()).await.expect("Chain is extended"), 0);
+    let report = chain.verify(db.conn.as_ref()).await.expect("Chain is verified");
+    assert!(report.valid);
+    assert_eq!(report.chained, entries.len() as u64);
+    assert_eq!(report.pending, 0);
+    assert_eq!(report.head, Some(late.id));
+    assert_eq!(report.signed_until, Some(late.id));
+
+    // Changed entries break the chain
+    let mut changed = audit_log::Entity::find_by_id(entries[1].id)
+        .one(db.conn.as_ref())
+        .await
+        .expect("Audit log is read")
+        .expect("Entry exists")
+        .into_active_model();
+    changed.diff = Set("{}".to_string());
+    changed.update(db.conn.as_ref()).await.expect("Entry is changed");
+    let report = chain.verify(db.conn.as_ref()).await.expect("Chain is verified");
+    assert!(!report.valid);
+    assert_eq!(report.broken_at, Some(entries[1].id));
+    assert_eq!(report.error.as_deref(), Some("Entry was changed"));
+}
@@ -0,0 +1,130 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::TimeDelta;
+use rocket::http::{Header, Status};
+use serde_json::json;
+use super::{api, json_body, TestApp};
+
+#[rocket::async_test]
+async fn test_missing_authorization() {
+    let app = TestApp::new().await;
+    let response = app.client.get(api("/tag")).dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let body = json_body(response).await;
+    assert_eq!(body["error"]["description"], "Authorization header is missing");
+}
+
+#[rocket::async_test]
+async fn test_invalid_token() {
+    let app = TestApp::new().await;
+    let response = app.client
+        .get(api("/tag"))
+        .header(Header::new("Authorization", "Bearer not-a-token"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn test_read_only_token_cannot_write() {
+    let app = TestApp::new().await;
+    let response = app.client
+        .post(api("/tag"))
+        .header(app.reader("alice"))
+        .json(&json!({"tag_type": "string", "tag_key": "line"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = app.client.get(api("/tag")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn test_insecure_dev_auth() {
+    let app = TestApp::with_settings(json!({"dev_mode": true, "insecure_dev_auth": "dev"})).await;
+    let response = app.client
+        .post(api("/tag"))
+        .json(&json!({"tag_type": "string", "tag_key": "line"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+
+    // Tokens are ignored, all requests belong to the development user
+    let response = app.client.get(api("/tag")).header(app.reader("alice")).dispatch().await;
+    let body = json_body(response).await;
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+}
+
+#[rocket::async_test]
+async fn test_refresh_token_exchange() {
+    let app = TestApp::new().await;
+    let refresh_token = app.refresh_token("alice", json!({"ptet:write": true}), TimeDelta::days(7));
+    let response = app.client
+        .post(api("/auth/refresh"))
+        .json(&json!({"refresh_token": refresh_token}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert_eq!(body["token_type"], "Bearer");
+    assert_eq!(body["expires_in"], 900);
+    let access_token = body["access_token"].as_str().expect("Access token is returned");
+
+    // The access token inherits the claims of the refresh token
+    let response = app.client
+        .post(api("/tag"))
+        .header(Header::new("Authorization", format!("Bearer {}", access_token)))
+        .json(&json!({"tag_type": "string", "tag_key": "line"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+
+    // Access tokens cannot be exchanged
+    let response = app.client
+        .post(api("/auth/refresh"))
+        .json(&json!({"refresh_token": access_token}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn test_refresh_token_is_no_bearer() {
+    let app = TestApp::new().await;
+    let refresh_token = app.refresh_token("alice", json!({}), TimeDelta::days(7));
+    let response = app.client
+        .get(api("/tag"))
+        .header(Header::new("Authorization", format!("Bearer {}", refresh_token)))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let body = json_body(response).await;
+    assert_eq!(body["error"]["description"], "Refresh token cannot be used for access");
+}
+
+#[rocket::async_test]
+async fn test_refresh_token_max_lifetime() {
+    let app = TestApp::with_settings(json!({"refresh_token_max_lifetime": 86400})).await;
+    let refresh_token = app.refresh_token("alice", json!({}), TimeDelta::days(2));
+    let response = app.client
+        .post(api("/auth/refresh"))
+        .json(&json!({"refresh_token": refresh_token}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let refresh_token = app.refresh_token("alice", json!({}), TimeDelta::hours(12));
+    let response = app.client
+        .post(api("/auth/refresh"))
+        .json(&json!({"refresh_token": refresh_token}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+}
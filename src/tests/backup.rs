@@ -0,0 +1,46 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::http::{ContentType, Status};
+use tempfile::TempDir;
+use crate::commands::backup::{backup, restore};
+use crate::fairings::Database;
+use super::ride::{create_ride, TRACK};
+use super::{api, json_body, TestApp};
+
+/// Database of [app]
+fn database(app: &TestApp) -> &Database {
+    app.client.rocket().state::<Database>().expect("Database is managed")
+}
+
+#[rocket::async_test]
+async fn test_restore_keeps_tracks() {
+    let source = TestApp::new().await;
+    let id = create_ride(&source, "alice", 1).await;
+    let response = source.client
+        .put(api(&format!("/ride/{}/track", id)))
+        .header(source.writer("alice"))
+        .header(ContentType::new("application", "gpx+xml"))
+        .body(TRACK)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let dir = TempDir::new().expect("Temporary directory is created");
+    let archive = dir.path().join("backup.jsonl");
+    backup(Some(&archive), &database(&source).conn).await.expect("Backup is written");
+    let target = TestApp::new().await;
+    restore(Some(&archive), &database(&target).conn).await.expect("Backup is restored");
+
+    let response = target.client.get(api(&format!("/ride/{}/track", id))).header(target.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json_body(response).await["point_count"], 2);
+    let response = target.client.get(api(&format!("/ride/{}/track/gpx", id))).header(target.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().await.as_deref(), Some(TRACK));
+}
@@ -0,0 +1,109 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::LocalResponse;
+use sea_orm::prelude::*;
+use serde_json::{json, Value};
+use entity::idempotency_key;
+use crate::fairings::Database;
+use crate::request_guards::idempotency::IDEMPOTENCY_KEY_HEADER;
+use crate::responders::idempotent::REPLAYED_HEADER;
+use super::{api, json_body, TestApp};
+
+/// Create [tag] as alice with idempotency key [key]
+async fn post_tag<'c>(app: &'c TestApp, key: &str, tag: &Value) -> LocalResponse<'c> {
+    app.client
+        .post(api("/tag"))
+        .header(app.writer("alice"))
+        .header(Header::new(IDEMPOTENCY_KEY_HEADER, key.to_string()))
+        .json(tag)
+        .dispatch()
+        .await
+}
+
+#[rocket::async_test]
+async fn test_retry_is_replayed() {
+    let app = TestApp::new().await;
+    let tag = json!({"tag_type": "string", "tag_key": "line"});
+    let response = post_tag(&app, "key-1", &tag).await;
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.headers().get_one(REPLAYED_HEADER), None);
+    let created = json_body(response).await;
+
+    let response = post_tag(&app, "key-1", &tag).await;
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.headers().get_one(REPLAYED_HEADER), Some("true"));
+    assert_eq!(response.headers().get_one("Location"), Some(api(&format!("/tag/{}", created["id"])).as_str()));
+    assert_eq!(json_body(response).await, created);
+
+    let response = app.client.get(api("/tag")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await.as_array().map(Vec::len), Some(1));
+
+    // Keys are per user
+    let response = app.client
+        .post(api("/tag"))
+        .header(app.writer("bob"))
+        .header(Header::new(IDEMPOTENCY_KEY_HEADER, "key-1"))
+        .json(&tag)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.headers().get_one(REPLAYED_HEADER), None);
+}
+
+#[rocket::async_test]
+async fn test_key_reused_for_other_request() {
+    let app = TestApp::new().await;
+    let response = post_tag(&app, "key-1", &json!({"tag_type": "string", "tag_key": "line"})).await;
+    assert_eq!(response.status(), Status::Created);
+
+    let response = post_tag(&app, "key-1", &json!({"tag_type": "string", "tag_key": "platform"})).await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let response = app.client.get(api("/tag")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await.as_array().map(Vec::len), Some(1));
+}
+
+#[rocket::async_test]
+async fn test_key_in_progress() {
+    let app = TestApp::new().await;
+    let tag = json!({"tag_type": "string", "tag_key": "line"});
+    let response = post_tag(&app, "key-1", &tag).await;
+    assert_eq!(response.status(), Status::Created);
+
+    // A request with the key is still running on another instance
+    let db = app.client.rocket().state::<Database>().expect("Database is managed");
+    idempotency_key::Entity::update_many()
+        .col_expr(idempotency_key::Column::Status, Expr::value(Option::<u32>::None))
+        .col_expr(idempotency_key::Column::Body, Expr::value(Option::<String>::None))
+        .filter(idempotency_key::Column::Key.eq("key-1"))
+        .exec(db.conn.as_ref())
+        .await
+        .expect("Key is reset");
+    let response = post_tag(&app, "key-1", &tag).await;
+    assert_eq!(response.status(), Status::Conflict);
+}
+
+#[rocket::async_test]
+async fn test_key_released_on_error() {
+    let app = TestApp::new().await;
+    let tag = json!({"tag_type": "string", "tag_key": "line"});
+    let response = post_tag(&app, "key-1", &tag).await;
+    assert_eq!(response.status(), Status::Created);
+    let id = json_body(response).await["id"].clone();
+
+    // The failed request is not recorded, so a retry with the same key executes it again
+    let response = post_tag(&app, "key-2", &tag).await;
+    assert_eq!(response.status(), Status::Conflict);
+    let response = app.client.delete(api(&format!("/tag/{}", id))).header(app.writer("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+    let response = post_tag(&app, "key-2", &tag).await;
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.headers().get_one(REPLAYED_HEADER), None);
+    assert_ne!(json_body(response).await["id"], id);
+}
@@ -0,0 +1,130 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! End-to-end tests of the HTTP API. [TestApp] boots the server against an in-memory
+//! SQLite database and mints tokens which the server accepts.
+
+mod admin;
+mod auth;
+mod backup;
+mod idempotency;
+mod report;
+mod ride;
+mod tag;
+mod user;
+mod webhook;
+
+use std::sync::Mutex;
+use chrono::{TimeDelta, Utc};
+use rocket::figment::{Figment, providers::Serialized};
+use rocket::http::Header;
+use rocket::local::asynchronous::{Client, LocalResponse};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use jwt_auth::jwt::TokenProducer;
+use jwt_auth::keys::KeyCache;
+use crate::config::Config;
+
+/// Issuer of the tokens of [TestApp]
+pub const ISSUER: &str = "test-issuer";
+/// Audience of the tokens of [TestApp], the server base URI
+pub const AUDIENCE: &str = "test.example.tld";
+/// Path the API is mounted at
+pub const API: &str = "/api/v1";
+
+/// Server with an empty database and a key cache of its own
+pub struct TestApp {
+    pub client: Client,
+    /// Key cache sharing the keys with the server, for signing tokens
+    keys: Mutex<KeyCache>,
+    /// Directory of the key cache, deleted when the app is dropped
+    _keys_dir: TempDir,
+}
+
+impl TestApp {
+    /// Boot the server with the default settings and migrations applied
+    pub async fn new() -> Self {
+        Self::with_settings(json!({})).await
+    }
+
+    /// Boot the server with [settings] overriding the default ones, given as in the
+    /// config file
+    pub async fn with_settings(settings: Value) -> Self {
+        let keys_dir = TempDir::new().expect("Temporary directory is created");
+        let mut keys = KeyCache::from_path(keys_dir.path()).expect("Key cache is created");
+        keys.create_private_key(None, None).expect("Key is created");
+
+        let config: Config = Figment::new()
+            .merge(Serialized::defaults(json!({
+                "database": "sqlite::memory:",
+                "keys_dir": keys_dir.path(),
+                "server_base_uri": AUDIENCE,
+                "connect_retries": 0,
+            })))
+            .merge(Serialized::globals(settings))
+            .extract()
+            .expect("Test settings are valid");
+        config.validate().expect("Test settings are valid");
+        let client = Client::tracked(crate::build(&config))
+            .await
+            .expect("Server is launched");
+
+        Self {
+            client,
+            keys: Mutex::new(keys),
+            _keys_dir: keys_dir,
+        }
+    }
+
+    /// Token of the user [subject] with the additional [claims]
+    pub fn token(&self, subject: &str, claims: Value) -> String {
+        let mut keys = self.keys.lock().expect("Key cache lock is not poisoned");
+        TokenProducer::new(&mut keys)
+            .with_issuer(ISSUER)
+            .with_audience(AUDIENCE)
+            .with_expiration(Utc::now() + TimeDelta::hours(1))
+            .add_claims_from_json(claims)
+            .and_then(|producer| producer.produce_compact(subject))
+            .expect("Token is signed")
+    }
+
+    /// Refresh token of the user [subject] with the additional [claims], expiring after
+    /// [lifetime]
+    pub fn refresh_token(&self, subject: &str, claims: Value, lifetime: TimeDelta) -> String {
+        let mut keys = self.keys.lock().expect("Key cache lock is not poisoned");
+        TokenProducer::new(&mut keys)
+            .with_issuer(ISSUER)
+            .with_audience(AUDIENCE)
+            .with_expiration(Utc::now() + lifetime)
+            .as_refresh_token()
+            .add_claims_from_json(claims)
+            .and_then(|producer| producer.produce_compact(subject))
+            .expect("Token is signed")
+    }
+
+    /// Authorization header of [subject] with read-only access
+    pub fn reader(&self, subject: &str) -> Header<'static> {
+        Header::new("Authorization", format!("Bearer {}", self.token(subject, json!({}))))
+    }
+
+    /// Authorization header of [subject] with read and write access
+    pub fn writer(&self, subject: &str) -> Header<'static> {
+        Header::new("Authorization", format!("Bearer {}", self.token(subject, json!({"ptet:write": true}))))
+    }
+}
+
+/// Path [path] below the API base, e.g. `/tag`
+pub fn api(path: &str) -> String {
+    format!("{}{}", API, path)
+}
+
+/// Body of [response] as JSON
+pub async fn json_body(response: LocalResponse<'_>) -> Value {
+    let body = response.into_string().await.expect("Response has a body");
+    serde_json::from_str(&body).expect("Response body is JSON")
+}
@@ -0,0 +1,188 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::http::Status;
+use serde_json::{json, Value};
+use super::{api, json_body, TestApp};
+
+/// Create a ride of alice departing at [departure] with price [price] of tag [tag_id]
+/// and return its ID
+async fn create_priced_ride(app: &TestApp, departure: &str, tag_id: &Value, price: f64) -> Value {
+    let response = app.client
+        .post(api("/ride"))
+        .header(app.writer("alice"))
+        .json(&json!({
+            "journey_departure": departure,
+            "location_from": "Berlin Hbf",
+            "location_to": "Potsdam Hbf",
+            "is_template": false,
+        }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    let id = json_body(response).await["id"].clone();
+    let response = app.client
+        .post(api(&format!("/ride/{}/ride_tags/{}", id, tag_id)))
+        .header(app.writer("alice"))
+        .json(&json!({"order": 0, "value": {"type": "Float", "value": price}, "remarks": null}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    id
+}
+
+#[rocket::async_test]
+async fn test_monthly_summary_follows_changes() {
+    let app = TestApp::new().await;
+    let response = app.client
+        .post(api("/tag"))
+        .header(app.writer("alice"))
+        .json(&json!({"tag_type": "float", "tag_key": "price", "unit": "EUR"}))
+        .dispatch()
+        .await;
+    let tag_id = json_body(response).await["id"].clone();
+    create_priced_ride(&app, "2026-09-30T08:00:00Z", &tag_id, 3.8).await;
+    let ride = create_priced_ride(&app, "2026-10-01T08:00:00Z", &tag_id, 2.5).await;
+    create_priced_ride(&app, "2026-10-02T08:00:00Z", &tag_id, 1.2).await;
+
+    let summary = "/report/summary?from=2026-09&to=2026-10";
+    let response = app.client.get(api(summary)).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert_eq!(body[0]["month"], "2026-09");
+    assert_eq!(body[0]["total_cost"], 3.8);
+    assert_eq!(body[1]["ride_count"], 2);
+    assert_eq!(body[1]["total_cost"], 3.7);
+
+    let response = app.client.delete(api(&format!("/ride/{}", ride))).header(app.writer("alice")).dispatch().await;
+    assert!(response.status().code < 300);
+    let response = app.client.get(api(summary)).header(app.reader("alice")).dispatch().await;
+    let body = json_body(response).await;
+    assert_eq!(body[1]["ride_count"], 1);
+    assert_eq!(body[1]["total_cost"], 1.2);
+
+    let response = app.client.get(api("/ride?page=0&size=10&summary=true")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.headers().get_one("X-Total-Cost"), Some("5"));
+
+    let response = app.client.get(api(summary)).header(app.reader("bob")).dispatch().await;
+    assert_eq!(json_body(response).await, json!([]));
+}
+
+#[rocket::async_test]
+async fn test_travel_log() {
+    let app = TestApp::new().await;
+    let response = app.client
+        .post(api("/tag"))
+        .header(app.writer("alice"))
+        .json(&json!({"tag_type": "float", "tag_key": "price", "unit": "EUR"}))
+        .dispatch()
+        .await;
+    let price_tag = json_body(response).await["id"].clone();
+    let response = app.client
+        .post(api("/tag"))
+        .header(app.writer("alice"))
+        .json(&json!({"tag_type": "string", "tag_key": "purpose"}))
+        .dispatch()
+        .await;
+    let purpose_tag = json_body(response).await["id"].clone();
+    let ride = create_priced_ride(&app, "2026-10-01T08:00:00Z", &price_tag, 2.5).await;
+    create_priced_ride(&app, "2026-11-01T08:00:00Z", &price_tag, 9.9).await;
+    let response = app.client
+        .post(api(&format!("/ride/{}/ride_tags/{}", ride, purpose_tag)))
+        .header(app.writer("alice"))
+        .json(&json!({"order": 0, "value": {"type": "String", "value": "Meeting <Q4>"}, "remarks": null}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+
+    let response = app.client
+        .get(api("/report/travel_log?year=2026&month=10&locale=de"))
+        .header(app.reader("alice"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(rocket::http::ContentType::HTML));
+    let html = response.into_string().await.expect("Body is text");
+    assert!(html.contains("Fahrtenbuch Oktober 2026"));
+    assert!(html.contains("Meeting &lt;Q4&gt;"));
+    assert!(html.contains("2,50"));
+    assert!(!html.contains("9,90"));
+
+    let response = app.client
+        .get(api("/report/travel_log?year=2026&month=10&format=pdf"))
+        .header(app.reader("alice"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(rocket::http::ContentType::PDF));
+    let pdf = response.into_bytes().await.expect("Body is present");
+    assert!(pdf.starts_with(b"%PDF-"));
+
+    let response = app.client
+        .get(api("/report/travel_log?year=2026&month=13"))
+        .header(app.reader("alice"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = app.client
+        .get(api("/report/travel_log?year=2026&month=10&format=docx"))
+        .header(app.reader("alice"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn test_tags_are_rolled_up_by_their_aggregation() {
+    let app = TestApp::new().await;
+    let mut tag_ids = Vec::new();
+    for tag in [
+        json!({"tag_type": "float", "tag_key": "price", "unit": "EUR"}),
+        json!({"tag_type": "integer", "tag_key": "delay", "unit": "min", "aggregation": "average"}),
+    ] {
+        let response = app.client.post(api("/tag")).header(app.writer("alice")).json(&tag).dispatch().await;
+        assert_eq!(response.status(), Status::Created);
+        tag_ids.push(json_body(response).await["id"].clone());
+    }
+    for (day, price, delay) in [(1, 2.5, 4), (2, 1.2, 11)] {
+        let ride = create_priced_ride(&app, &format!("2026-10-{:02}T08:00:00Z", day), &tag_ids[0], price).await;
+        let response = app.client
+            .post(api(&format!("/ride/{}/ride_tags/{}", ride, tag_ids[1])))
+            .header(app.writer("alice"))
+            .json(&json!({"order": 0, "value": {"type": "Integer", "value": delay}, "remarks": null}))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Created);
+    }
+
+    let response = app.client.get(api("/ride?group_by=month")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert_eq!(body[0]["totals"], json!({"delay": 7.5, "price": 3.7}));
+
+    // The reports add up prices, so the price tag is only aggregated by sum
+    let response = app.client
+        .put(api(&format!("/tag/{}", tag_ids[0])))
+        .header(app.writer("alice"))
+        .json(&json!({"tag_type": "float", "tag_key": "price", "unit": "EUR", "aggregation": "max"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let response = app.client.get(api("/report/summary?from=2026-10")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await[0]["total_cost"], 3.7);
+    let response = app.client.get(api("/ride?page=0&size=10&summary=true")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.headers().get_one("X-Total-Cost"), Some("3.7"));
+
+    let response = app.client
+        .post(api("/tag"))
+        .header(app.writer("alice"))
+        .json(&json!({"tag_type": "string", "tag_key": "purpose", "aggregation": "sum"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
@@ -0,0 +1,290 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::http::Status;
+use serde_json::json;
+use crate::model::event::EventBus;
+use super::{api, json_body, TestApp};
+
+/// Create a ride of [subject] departing on day [day] of October 2026 and return its ID
+pub(super) async fn create_ride(app: &TestApp, subject: &str, day: u32) -> u64 {
+    let response = app.client
+        .post(api("/ride"))
+        .header(app.writer(subject))
+        .json(&json!({
+            "journey_departure": format!("2026-10-{:02}T08:00:00Z", day),
+            "location_from": "Berlin Hbf",
+            "location_to": "Potsdam Hbf",
+            "is_template": false,
+        }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    json_body(response).await["id"].as_u64().expect("Ride has an ID")
+}
+
+#[rocket::async_test]
+async fn test_create_and_get_ride() {
+    let app = TestApp::new().await;
+    let id = create_ride(&app, "alice", 1).await;
+
+    let response = app.client.get(api(&format!("/ride/{}", id))).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert_eq!(body["location_from"], "Berlin Hbf");
+
+    let response = app.client.get(api(&format!("/ride/{}", id))).header(app.reader("bob")).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn test_paginate_rides() {
+    let app = TestApp::new().await;
+    for day in 1..=5 {
+        create_ride(&app, "alice", day).await;
+    }
+
+    let response = app.client.get(api("/ride?page=0&size=2")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.headers().get_one("X-Total-Items"), Some("5"));
+    assert_eq!(response.headers().get_one("X-Total-Pages"), Some("3"));
+    let body = json_body(response).await;
+    assert_eq!(body.as_array().map(Vec::len), Some(2));
+
+    let response = app.client.get(api("/ride?page=0")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn test_openapi_specification() {
+    let app = TestApp::new().await;
+    let response = app.client.get(api("/openapi.json")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert!(body["paths"]["/ride"].is_object());
+}
+
+#[rocket::async_test]
+async fn test_unknown_and_long_fields_are_rejected() {
+    let app = TestApp::new().await;
+    let response = app.client
+        .post(api("/ride"))
+        .header(app.writer("alice"))
+        .json(&json!({
+            "jurney_departure": "2026-10-01T08:00:00Z",
+            "location_from": "B".repeat(201),
+            "location_to": "Potsdam Hbf",
+            "is_template": false,
+        }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body = json_body(response).await;
+    assert_eq!(
+        body["error"]["description"],
+        "Invalid fields: jurney_departure is unknown; location_from is longer than 200 characters"
+    );
+}
+
+#[rocket::async_test]
+async fn test_fetched_ride_can_be_sent_back() {
+    let app = TestApp::new().await;
+    let id = create_ride(&app, "alice", 1).await;
+    let path = api(&format!("/ride/{}?links=true", id));
+
+    let response = app.client.get(&path).header(app.reader("alice")).dispatch().await;
+    let mut ride = json_body(response).await;
+    ride["remarks"] = json!("Delayed");
+    // Read-only fields like id and _links are ignored
+    let response = app.client.put(api(&format!("/ride/{}", id))).header(app.writer("alice")).json(&ride).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+}
+
+/// GPX document of two track points 0.01° of longitude apart on the equator, ~1112 m
+pub(super) const TRACK: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test"><trk><trkseg>
+<trkpt lat="0.0" lon="0.0"><time>2026-10-01T08:00:00Z</time></trkpt>
+<trkpt lat="0.0" lon="0.01"><time>2026-10-01T08:04:00Z</time></trkpt>
+</trkseg></trk></gpx>"#;
+
+#[rocket::async_test]
+async fn test_track_fills_distance() {
+    let app = TestApp::new().await;
+    let id = create_ride(&app, "alice", 1).await;
+    let response = app.client
+        .post(api("/tag"))
+        .header(app.writer("alice"))
+        .json(&json!({"tag_type": "float", "tag_key": "distance", "unit": "km"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    let tag_id = json_body(response).await["id"].as_u64().expect("Tag has an ID");
+    let gpx = rocket::http::ContentType::new("application", "gpx+xml");
+
+    let response = app.client
+        .put(api(&format!("/ride/{}/track", id)))
+        .header(app.writer("alice"))
+        .header(gpx.clone())
+        .body(TRACK)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert_eq!(body["point_count"], 2);
+    assert_eq!(body["duration"], 240);
+    assert_eq!(body["distance"].as_f64().map(f64::round), Some(1112.0));
+
+    let distance = format!("/ride/{}/ride_tags/{}", id, tag_id);
+    let response = app.client.get(api(&distance)).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await["value"], json!({"type": "Float", "value": 1.11}));
+
+    // A manual distance is kept on the next upload unless overwritten
+    let link = app.client.get(api(&distance)).header(app.reader("alice")).dispatch().await;
+    let mut link = json_body(link).await;
+    link["value"] = json!({"type": "Float", "value": 2.5});
+    let response = app.client
+        .put(api(&format!("/ride_tag/{}", link["id"])))
+        .header(app.writer("alice"))
+        .json(&link)
+        .dispatch()
+        .await;
+    assert!(response.status().code < 300);
+    app.client.put(api(&format!("/ride/{}/track", id))).header(app.writer("alice")).header(gpx.clone()).body(TRACK).dispatch().await;
+    let response = app.client.get(api(&distance)).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await["value"]["value"], 2.5);
+
+    app.client.put(api(&format!("/ride/{}/track?overwrite_distance=true", id))).header(app.writer("alice")).header(gpx).body(TRACK).dispatch().await;
+    let response = app.client.get(api(&distance)).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await["value"]["value"], 1.11);
+
+    let response = app.client.get(api(&format!("/ride/{}/track/gpx", id))).header(app.reader("bob")).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+/// Address of a clamd stand-in answering each scan with [reply]
+async fn fake_clamd(reply: &'static str) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Port is free");
+    let address = format!("tcp://{}", listener.local_addr().expect("Listener has an address"));
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut command = [0; 10];
+            stream.read_exact(&mut command).await.expect("Command is sent");
+            loop {
+                let length = stream.read_u32().await.expect("Chunk length is sent");
+                if length == 0 {
+                    break;
+                }
+                let mut chunk = vec![0; length as usize];
+                stream.read_exact(&mut chunk).await.expect("Chunk is sent");
+            }
+            stream.write_all(format!("{}\0", reply).as_bytes()).await.expect("Reply is sent");
+        }
+    });
+    address
+}
+
+#[rocket::async_test]
+async fn test_infected_track_is_quarantined() {
+    let clamd = fake_clamd("stream: Eicar-Test-Signature FOUND").await;
+    let app = TestApp::with_settings(json!({"malware_scanner": "clamd", "malware_scanner_url": clamd})).await;
+    let admin = rocket::http::Header::new("Authorization", format!("Bearer {}", app.token("admin", json!({"ptet:admin": true}))));
+    let id = create_ride(&app, "alice", 1).await;
+
+    let response = app.client
+        .put(api(&format!("/ride/{}/track", id)))
+        .header(app.writer("alice"))
+        .header(rocket::http::ContentType::new("application", "gpx+xml"))
+        .body(TRACK)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json_body(response).await["scan_status"], "quarantined");
+    let response = app.client.get(api(&format!("/ride/{}/track/gpx", id))).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    let response = app.client.get(api("/admin/quarantine")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = app.client.get(api("/admin/quarantine?page=0&size=10")).header(admin.clone()).dispatch().await;
+    assert_eq!(response.headers().get_one("X-Total-Items"), Some("1"));
+    let quarantined = json_body(response).await;
+    assert_eq!(quarantined.as_array().map(Vec::len), Some(1));
+    assert_eq!(quarantined[0]["ride_id"], id);
+    assert_eq!(quarantined[0]["finding"], "Eicar-Test-Signature");
+
+    let release = format!("/admin/quarantine/{}/release", quarantined[0]["id"]);
+    let response = app.client.post(api(&release)).header(admin.clone()).dispatch().await;
+    assert_eq!(json_body(response).await["scan_status"], "released");
+    let response = app.client.get(api(&format!("/ride/{}/track/gpx", id))).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = app.client.post(api(&release)).header(admin).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn test_frequent_connections() {
+    let app = TestApp::new().await;
+    let yesterday = chrono::Utc::now() - chrono::TimeDelta::days(1);
+    for (days, from, to) in [(1, "Berlin Hbf", "Potsdam Hbf"), (2, "berlin hbf", "Potsdam Hbf"), (3, "Berlin Hbf", "Potsdam Hbf"), (4, "Potsdam Hbf", "Berlin Hbf")] {
+        let response = app.client
+            .post(api("/ride"))
+            .header(app.writer("alice"))
+            .json(&json!({
+                "journey_departure": yesterday - chrono::TimeDelta::days(days),
+                "location_from": from,
+                "location_to": to,
+                "is_template": false,
+            }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Created);
+    }
+
+    let response = app.client.get(api("/ride/suggestions")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+    assert_eq!(body[0]["location_from"], "Berlin Hbf");
+    assert_eq!(body[0]["count"], 3);
+    assert_eq!(body[0]["departures"][0]["count"], 3);
+}
+
+#[rocket::async_test]
+async fn test_failed_request_is_rolled_back() {
+    let app = TestApp::new().await;
+    let mut events = app.client.rocket().state::<EventBus>().expect("Event bus is managed").subscribe();
+    let response = app.client
+        .post(api("/batch"))
+        .header(app.writer("alice"))
+        .json(&json!({
+            "operations": [
+                {
+                    "op": "create_ride",
+                    "ride": {
+                        "journey_departure": "2026-10-01T08:00:00Z",
+                        "location_from": "Berlin Hbf",
+                        "location_to": "Potsdam Hbf",
+                        "is_template": false,
+                    },
+                },
+                {"op": "delete_ride", "ride_id": 9999},
+            ],
+        }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    // The ride created by the first operation is neither stored nor announced
+    let response = app.client.get(api("/ride?page=0&size=10")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.headers().get_one("X-Total-Items"), Some("0"));
+    assert!(events.try_recv().is_err());
+
+    create_ride(&app, "alice", 2).await;
+    assert_eq!(events.try_recv().map(|event| event.event_type).ok().as_deref(), Some("ride.created"));
+}
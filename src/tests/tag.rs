@@ -0,0 +1,173 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::http::{ContentType, Status};
+use serde_json::{json, Value};
+use super::{api, json_body, TestApp};
+
+/// Create a tag of [subject] and return it
+async fn create_tag(app: &TestApp, subject: &str, tag: Value) -> Value {
+    let response = app.client
+        .post(api("/tag"))
+        .header(app.writer(subject))
+        .json(&tag)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    json_body(response).await
+}
+
+#[rocket::async_test]
+async fn test_tag_lifecycle() {
+    let app = TestApp::new().await;
+    let tag = create_tag(&app, "alice", json!({"tag_type": "float", "tag_key": "price", "unit": "EUR"})).await;
+    let path = api(&format!("/tag/{}", tag["id"]));
+
+    let response = app.client.get(&path).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert_eq!(body["tag_key"], "price");
+    assert_eq!(body["unit"], "EUR");
+
+    let response = app.client
+        .put(&path)
+        .header(app.writer("alice"))
+        .json(&json!({"tag_type": "float", "tag_key": "price", "tag_name": "Price", "unit": "EUR"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NoContent);
+    let response = app.client.get(&path).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await["tag_display_name"], "Price");
+
+    let response = app.client.delete(&path).header(app.writer("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+    let response = app.client.get(&path).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn test_tags_of_other_users_are_hidden() {
+    let app = TestApp::new().await;
+    let tag = create_tag(&app, "alice", json!({"tag_type": "string", "tag_key": "line"})).await;
+
+    let response = app.client
+        .get(api(&format!("/tag/{}", tag["id"])))
+        .header(app.reader("bob"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = app.client.get(api("/tag")).header(app.reader("bob")).dispatch().await;
+    assert_eq!(json_body(response).await, json!([]));
+}
+
+#[rocket::async_test]
+async fn test_import_tag_options() {
+    let app = TestApp::new().await;
+    let tag = create_tag(&app, "alice", json!({"tag_type": "enum", "tag_key": "station"})).await;
+    let path = api(&format!("/tag/{}/tag_option", tag["id"]));
+
+    let response = app.client
+        .post(format!("{}/import", path))
+        .header(app.writer("alice"))
+        .header(ContentType::Plain)
+        .body("Berlin Hbf\nAlexanderplatz;Alex\n")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+
+    // Repeated values are rejected without creating any option
+    let response = app.client
+        .post(format!("{}/import", path))
+        .header(app.writer("alice"))
+        .header(ContentType::Plain)
+        .body("Zoo\nBerlin Hbf\n")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = app.client.get(&path).header(app.reader("alice")).dispatch().await;
+    let options = json_body(response).await;
+    let options: Vec<(&str, &str, u64)> = options
+        .as_array()
+        .expect("Options are a list")
+        .iter()
+        .map(|option| (option["value"].as_str().unwrap(), option["display_name"].as_str().unwrap(), option["order"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(options, vec![("Berlin Hbf", "Berlin Hbf", 0), ("Alexanderplatz", "Alex", 1)]);
+}
+
+#[rocket::async_test]
+async fn test_value_suggestions() {
+    let app = TestApp::new().await;
+    let tag = create_tag(&app, "alice", json!({"tag_type": "string", "tag_key": "line"})).await;
+    let departure = chrono::Utc::now() - chrono::TimeDelta::days(1);
+    for line in ["S1", "RE7", "S1"] {
+        let response = app.client
+            .post(api("/ride"))
+            .header(app.writer("alice"))
+            .json(&json!({
+                "journey_departure": departure,
+                "location_from": "Berlin Hbf",
+                "location_to": "Potsdam Hbf",
+                "is_template": false,
+            }))
+            .dispatch()
+            .await;
+        let ride = json_body(response).await;
+        let response = app.client
+            .post(api(&format!("/ride/{}/ride_tags/{}", ride["id"], tag["id"])))
+            .header(app.writer("alice"))
+            .json(&json!({"order": 0, "value": {"type": "String", "value": line}}))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Created);
+    }
+
+    let response = app.client
+        .get(api(&format!("/tag/{}/suggestions", tag["id"])))
+        .header(app.reader("alice"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert_eq!(body[0]["value"]["value"], "S1");
+    assert_eq!(body[0]["count"], 2);
+    assert_eq!(body[1]["value"]["value"], "RE7");
+
+    let response = app.client
+        .get(api(&format!("/tag/{}/suggestions?limit=0", tag["id"])))
+        .header(app.reader("alice"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn test_restore_tag() {
+    let app = TestApp::new().await;
+    let tag = create_tag(&app, "alice", json!({"tag_type": "string", "tag_key": "line"})).await;
+    let path = api(&format!("/tag/{}", tag["id"]));
+    let restore = api(&format!("/tag/{}/restore", tag["id"]));
+
+    let response = app.client.post(&restore).header(app.writer("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    app.client.delete(&path).header(app.writer("alice")).dispatch().await;
+    let response = app.client.post(&restore).header(app.writer("bob")).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = app.client.post(&restore).header(app.writer("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = app.client.get(&path).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // The key is taken by a new tag while the old one is deleted
+    app.client.delete(&path).header(app.writer("alice")).dispatch().await;
+    create_tag(&app, "alice", json!({"tag_type": "string", "tag_key": "line"})).await;
+    let response = app.client.post(&restore).header(app.writer("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+}
@@ -0,0 +1,187 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::http::{ContentType, Status};
+use serde_json::{json, Value};
+use super::ride::create_ride;
+use super::{api, json_body, TestApp};
+
+#[rocket::async_test]
+async fn test_patch_profile() {
+    let app = TestApp::new().await;
+    let response = app.client
+        .patch(api("/user"))
+        .header(app.writer("alice"))
+        .json(&json!({"name": "Alice", "email": "alice@example.org", "theme": "dark", "currency": "EUR"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // Fields not given are kept, null clears a field
+    let response = app.client
+        .patch(api("/user"))
+        .header(app.writer("alice"))
+        .json(&json!({"email": null, "distance_unit": "mi"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert_eq!(body["name"], "Alice");
+    assert_eq!(body["email"], json!(null));
+    assert_eq!(body["theme"], "dark");
+    assert_eq!(body["distance_unit"], "mi");
+
+    for patch in [json!({"email": "alice"}), json!({"currency": "euro"}), json!({"avatar_url": "file:///etc/passwd"}), json!({"nickname": "Al"})] {
+        let response = app.client.patch(api("/user")).header(app.writer("alice")).json(&patch).dispatch().await;
+        assert_eq!(response.status(), Status::UnprocessableEntity, "{}", patch);
+    }
+    let response = app.client.get(api("/user")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await["currency"], "EUR");
+
+    // A fetched profile can be sent back, and PUT clears fields not given
+    let response = app.client.get(api("/user")).header(app.reader("alice")).dispatch().await;
+    let mut profile = json_body(response).await;
+    profile["name"] = json!("Alice A.");
+    let response = app.client.put(api("/user")).header(app.writer("alice")).json(&profile).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = app.client.put(api("/user")).header(app.writer("alice")).json(&json!({"name": "Alice"})).dispatch().await;
+    assert_eq!(json_body(response).await["theme"], json!(null));
+}
+
+/// Create a tag `line` and a ride of alice tagged with line `S7`, and return the IDs of
+/// the tag, the ride and the ride tag
+async fn create_tagged_ride(app: &TestApp) -> (Value, u64, Value) {
+    let response = app.client
+        .post(api("/tag"))
+        .header(app.writer("alice"))
+        .json(&json!({"tag_type": "string", "tag_key": "line", "tag_name": "Line"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    let tag_id = json_body(response).await["id"].clone();
+    let ride_id = create_ride(app, "alice", 1).await;
+    let response = app.client
+        .post(api(&format!("/ride/{}/ride_tags/{}", ride_id, tag_id)))
+        .header(app.writer("alice"))
+        .json(&json!({"order": 0, "value": {"type": "String", "value": "S7"}, "remarks": null}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    (tag_id, ride_id, json_body(response).await["id"].clone())
+}
+
+/// Archive of alice as ZIP file
+async fn export(app: &TestApp) -> Vec<u8> {
+    let response = app.client.get(api("/user/export.zip")).header(app.reader("alice")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    response.into_bytes().await.expect("Archive is returned")
+}
+
+/// Import [archive] for alice with [conflict] mode and return the report
+async fn import(app: &TestApp, archive: &[u8], conflict: &str) -> Value {
+    let response = app.client
+        .post(api(&format!("/user/import?conflict={}", conflict)))
+        .header(app.writer("alice"))
+        .header(ContentType::ZIP)
+        .body(archive)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    json_body(response).await
+}
+
+/// Number of tags and rides of alice
+async fn count(app: &TestApp) -> (usize, String) {
+    let response = app.client.get(api("/tag")).header(app.reader("alice")).dispatch().await;
+    let tags = json_body(response).await.as_array().map(Vec::len).unwrap_or_default();
+    let response = app.client.get(api("/ride?page=0&size=10")).header(app.reader("alice")).dispatch().await;
+    let rides = response.headers().get_one("X-Total-Items").unwrap_or_default().to_string();
+    (tags, rides)
+}
+
+#[rocket::async_test]
+async fn test_import_into_empty_account() {
+    let source = TestApp::new().await;
+    create_tagged_ride(&source).await;
+    let archive = export(&source).await;
+
+    let target = TestApp::new().await;
+    let report = import(&target, &archive, "skip").await;
+    assert_eq!(report["tags"]["created"], 1);
+    assert_eq!(report["rides"]["created"], 1);
+    assert_eq!(report["ride_tags"]["created"], 1);
+    assert_eq!(count(&target).await, (1, "1".to_string()));
+}
+
+#[rocket::async_test]
+async fn test_import_skip() {
+    let app = TestApp::new().await;
+    let (tag_id, _, _) = create_tagged_ride(&app).await;
+    let archive = export(&app).await;
+    app.client
+        .put(api(&format!("/tag/{}", tag_id)))
+        .header(app.writer("alice"))
+        .json(&json!({"tag_type": "string", "tag_key": "line", "tag_name": "Changed"}))
+        .dispatch()
+        .await;
+
+    let report = import(&app, &archive, "skip").await;
+    assert_eq!(report["tags"], json!({"created": 0, "updated": 0, "skipped": 1, "deleted": 0}));
+    assert_eq!(report["rides"], json!({"created": 0, "updated": 0, "skipped": 1, "deleted": 0}));
+    assert_eq!(report["ride_tags"]["skipped"], 1);
+    assert_eq!(count(&app).await, (1, "1".to_string()));
+    let response = app.client.get(api(&format!("/tag/{}", tag_id))).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await["tag_display_name"], "Changed");
+}
+
+#[rocket::async_test]
+async fn test_import_overwrite() {
+    let app = TestApp::new().await;
+    let (tag_id, ride_id, link_id) = create_tagged_ride(&app).await;
+    let archive = export(&app).await;
+    app.client
+        .put(api(&format!("/tag/{}", tag_id)))
+        .header(app.writer("alice"))
+        .json(&json!({"tag_type": "string", "tag_key": "line", "tag_name": "Changed"}))
+        .dispatch()
+        .await;
+    let response = app.client
+        .put(api(&format!("/ride_tag/{}", link_id)))
+        .header(app.writer("alice"))
+        .json(&json!({"order": 0, "value": {"type": "String", "value": "S8"}, "remarks": null}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NoContent);
+
+    let report = import(&app, &archive, "overwrite").await;
+    assert_eq!(report["tags"]["updated"], 1);
+    assert_eq!(report["rides"]["updated"], 1);
+    assert_eq!(report["ride_tags"]["deleted"], 1);
+    assert_eq!(report["ride_tags"]["created"], 1);
+    assert_eq!(count(&app).await, (1, "1".to_string()));
+    let response = app.client.get(api(&format!("/tag/{}", tag_id))).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await["tag_display_name"], "Line");
+    let link = format!("/ride/{}/ride_tags/{}", ride_id, tag_id);
+    let response = app.client.get(api(&link)).header(app.reader("alice")).dispatch().await;
+    assert_eq!(json_body(response).await["link"]["value"], json!({"type": "String", "value": "S7"}));
+}
+
+#[rocket::async_test]
+async fn test_import_duplicate() {
+    let app = TestApp::new().await;
+    create_tagged_ride(&app).await;
+    let archive = export(&app).await;
+
+    // Rides are created again, but tags are never duplicated as their keys are unique
+    let report = import(&app, &archive, "duplicate").await;
+    assert_eq!(report["tags"]["skipped"], 1);
+    assert_eq!(report["tags"]["created"], 0);
+    assert_eq!(report["rides"]["created"], 1);
+    assert_eq!(report["ride_tags"]["created"], 1);
+    assert_eq!(count(&app).await, (1, "2".to_string()));
+}
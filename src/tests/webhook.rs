@@ -0,0 +1,37 @@
+/*
+ * SPDX-License-Identifier: MPL-2.0
+ *   Copyright (c) 2025 Philipp Le <philipp@philipple.de>.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rocket::http::Status;
+use serde_json::json;
+use super::{api, TestApp};
+
+#[rocket::async_test]
+async fn test_private_target_is_rejected() {
+    let app = TestApp::new().await;
+    for url in ["http://127.0.0.1:8080/hook", "http://[::1]/hook", "http://192.168.0.10/hook", "http://169.254.169.254/latest"] {
+        let response = app.client
+            .post(api("/webhook"))
+            .header(app.writer("alice"))
+            .json(&json!({"url": url, "events": ["*"]}))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity, "{}", url);
+    }
+}
+
+#[rocket::async_test]
+async fn test_allowed_host_is_accepted() {
+    let app = TestApp::with_settings(json!({"webhook_allowed_hosts": ["127.0.0.1"]})).await;
+    let response = app.client
+        .post(api("/webhook"))
+        .header(app.writer("alice"))
+        .json(&json!({"url": "http://127.0.0.1:8080/hook", "events": ["*"]}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+}
@@ -54,7 +54,7 @@ def routes_ride_post(data: Ride, api_config_override: Optional[APIConfig] = None
     with httpx.Client(base_url=base_path, verify=api_config.verify) as client:
         response = client.request("post", httpx.URL(path), headers=headers, params=query_params, json=data.dict())
 
-    if response.status_code != 200:
+    if response.status_code != 201:
         raise HTTPException(response.status_code, f" failed with status code: {response.status_code}")
 
     return Ride(**response.json()) if response.json() is not None else Ride()
@@ -216,7 +216,7 @@ def routes_ride_tag_post_by_tag_id(
     with httpx.Client(base_url=base_path, verify=api_config.verify) as client:
         response = client.request("post", httpx.URL(path), headers=headers, params=query_params, json=data.dict())
 
-    if response.status_code != 200:
+    if response.status_code != 201:
         raise HTTPException(response.status_code, f" failed with status code: {response.status_code}")
 
     return RideTagLink(**response.json()) if response.json() is not None else RideTagLink()
@@ -52,7 +52,7 @@ def routes_tag_post(data: Tag, api_config_override: Optional[APIConfig] = None)
     with httpx.Client(base_url=base_path, verify=api_config.verify) as client:
         response = client.request("post", httpx.URL(path), headers=headers, params=query_params, json=data.dict())
 
-    if response.status_code != 200:
+    if response.status_code != 201:
         raise HTTPException(response.status_code, f" failed with status code: {response.status_code}")
 
     return Tag(**response.json()) if response.json() is not None else Tag()
@@ -182,7 +182,7 @@ def routes_tag_option_post(tag_id: int, data: TagOption, api_config_override: Op
     with httpx.Client(base_url=base_path, verify=api_config.verify) as client:
         response = client.request("post", httpx.URL(path), headers=headers, params=query_params, json=data.dict())
 
-    if response.status_code != 200:
+    if response.status_code != 201:
         raise HTTPException(response.status_code, f" failed with status code: {response.status_code}")
 
     return TagOption(**response.json()) if response.json() is not None else TagOption()
@@ -52,7 +52,7 @@ async def routes_tag_post(data: Tag, api_config_override: Optional[APIConfig] =
     async with httpx.AsyncClient(base_url=base_path, verify=api_config.verify) as client:
         response = await client.request("post", httpx.URL(path), headers=headers, params=query_params, json=data.dict())
 
-    if response.status_code != 200:
+    if response.status_code != 201:
         raise HTTPException(response.status_code, f" failed with status code: {response.status_code}")
 
     return Tag(**response.json()) if response.json() is not None else Tag()
@@ -184,7 +184,7 @@ async def routes_tag_option_post(
     async with httpx.AsyncClient(base_url=base_path, verify=api_config.verify) as client:
         response = await client.request("post", httpx.URL(path), headers=headers, params=query_params, json=data.dict())
 
-    if response.status_code != 200:
+    if response.status_code != 201:
         raise HTTPException(response.status_code, f" failed with status code: {response.status_code}")
 
     return TagOption(**response.json()) if response.json() is not None else TagOption()